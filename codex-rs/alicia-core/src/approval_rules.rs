@@ -0,0 +1,165 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ActionKind;
+use crate::classify_command;
+
+/// Commands the workspace approves ahead of time, saved as `[[approval_rules]]` in the
+/// project policy file. They stand in for the reviewer when the policy requires approval;
+/// they never lift a `deny`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ApprovalRuleSet {
+    pub rules: Vec<ApprovalRule>,
+}
+
+/// Approves the commands whose words, joined by single spaces, match `pattern`: `*` stands
+/// for any run of characters and `?` for one, so `cargo test*` also covers
+/// `cargo test -p core`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApprovalRule {
+    pub pattern: String,
+    /// Only commands `classify_command` finds neither writing files nor using the network.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+    /// Exact words of the approved command. When set, the rule approves only that command
+    /// and `pattern` is just its label: joined words can't tell `rm "a b"` from `rm a b`, and
+    /// a `*` in an argument would turn into a wildcard.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command: Vec<String>,
+}
+
+impl ApprovalRule {
+    /// Approves exactly `command`, what "always allow" on an approval adds.
+    pub fn for_command(command: &[String]) -> Self {
+        Self {
+            pattern: command.join(" "),
+            read_only: false,
+            command: command.to_vec(),
+        }
+    }
+
+    pub fn matches(&self, command: &[String]) -> bool {
+        if command.is_empty() {
+            return false;
+        }
+        let matched = if self.command.is_empty() {
+            glob_matches(&self.pattern, &command.join(" "))
+        } else {
+            self.command == command
+        };
+        matched && (!self.read_only || classify_command(command) == [ActionKind::ExecuteCommand])
+    }
+}
+
+impl ApprovalRuleSet {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The first rule approving `command`.
+    pub fn matching_rule(&self, command: &[String]) -> Option<&ApprovalRule> {
+        self.rules.iter().find(|rule| rule.matches(command))
+    }
+
+    /// Appends `rule` unless an identical one is already there. Returns whether it was added.
+    pub fn add(&mut self, rule: ApprovalRule) -> bool {
+        if self.rules.contains(&rule) {
+            return false;
+        }
+        self.rules.push(rule);
+        true
+    }
+}
+
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it is currently expanded up to.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, star_t)) => {
+                    p = star + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|ch| *ch == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::ApprovalRule;
+    use super::ApprovalRuleSet;
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn rules_match_globs_and_read_only_commands() {
+        let mut rules = ApprovalRuleSet::default();
+        assert!(rules.add(ApprovalRule::for_command(&words("cargo test"))));
+        assert!(!rules.add(ApprovalRule::for_command(&words("cargo test"))));
+        assert!(rules.add(ApprovalRule {
+            pattern: "git *".to_string(),
+            read_only: true,
+            command: Vec::new(),
+        }));
+
+        assert_eq!(
+            rules
+                .matching_rule(&words("cargo test"))
+                .map(|rule| rule.pattern.as_str()),
+            Some("cargo test")
+        );
+        assert_eq!(rules.matching_rule(&words("cargo test -p core")), None);
+        assert_eq!(
+            rules
+                .matching_rule(&words("git log --oneline"))
+                .map(|rule| rule.pattern.as_str()),
+            Some("git *")
+        );
+        assert_eq!(rules.matching_rule(&words("git push origin main")), None);
+        assert_eq!(rules.matching_rule(&words("gitk")), None);
+
+        let any_args = ApprovalRule {
+            pattern: "cargo test*".to_string(),
+            read_only: false,
+            command: Vec::new(),
+        };
+        assert!(any_args.matches(&words("cargo test -p core")));
+        assert!(!any_args.matches(&words("cargo build")));
+        assert!(!any_args.matches(&[]));
+    }
+
+    #[test]
+    fn always_allow_rules_match_the_exact_command_words() {
+        let spaced = vec!["rm".to_string(), "a b".to_string()];
+        let rule = ApprovalRule::for_command(&spaced);
+        assert!(rule.matches(&spaced));
+        assert!(!rule.matches(&words("rm a b")));
+
+        let starred = ApprovalRule::for_command(&words("rm *.log"));
+        assert!(starred.matches(&words("rm *.log")));
+        assert!(!starred.matches(&words("rm important.log")));
+        assert!(!starred.matches(&words("rm *.log -rf")));
+    }
+}
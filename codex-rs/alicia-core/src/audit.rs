@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -11,6 +12,8 @@ use tokio::io::AsyncWriteExt;
 use tokio::sync::Mutex;
 
 use crate::ActionKind;
+use crate::ApproverIdentity;
+use crate::AuditEncryption;
 use crate::PermissionProfile;
 use crate::PolicyDecision;
 
@@ -39,10 +42,21 @@ pub struct AuditRecord {
     pub action_kind: ActionKind,
     pub target: String,
     pub profile: PermissionProfile,
+    /// Profile from the project policy file the decision came from, on top of `profile`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_name: Option<String>,
     pub policy_decision: PolicyDecision,
     pub approval_decision: ApprovalDecision,
     pub result_status: ResultStatus,
     pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_sequence: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approver: Option<ApproverIdentity>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
 }
 
 impl AuditRecord {
@@ -63,18 +77,49 @@ impl AuditRecord {
             action_kind,
             target: target.into(),
             profile,
+            profile_name: None,
             policy_decision,
             approval_decision,
             result_status,
             duration_ms,
+            action_id: None,
+            event_sequence: None,
+            approver: None,
+            metadata: BTreeMap::new(),
         }
     }
+
+    pub fn with_profile_name(mut self, profile_name: impl Into<String>) -> Self {
+        self.profile_name = Some(profile_name.into());
+        self
+    }
+
+    pub fn with_action_id(mut self, action_id: impl Into<String>) -> Self {
+        self.action_id = Some(action_id.into());
+        self
+    }
+
+    pub fn with_event_sequence(mut self, event_sequence: u64) -> Self {
+        self.event_sequence = Some(event_sequence);
+        self
+    }
+
+    pub fn with_approver(mut self, approver: ApproverIdentity) -> Self {
+        self.approver = Some(approver);
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: BTreeMap<String, String>) -> Self {
+        self.metadata = metadata;
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct AuditLogger {
     path: PathBuf,
     writer: Arc<Mutex<tokio::fs::File>>,
+    encryption: Option<AuditEncryption>,
 }
 
 impl AuditLogger {
@@ -93,9 +138,19 @@ impl AuditLogger {
         Ok(Self {
             path,
             writer: Arc::new(Mutex::new(file)),
+            encryption: None,
         })
     }
 
+    pub fn with_encryption(mut self, encryption: AuditEncryption) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
+
     pub fn path(&self) -> &Path {
         &self.path
     }
@@ -105,6 +160,11 @@ impl AuditLogger {
             std::io::Error::other(format!("failed to serialize audit record: {err}"))
         })?;
         serialized = redact_secrets(serialized);
+        if let Some(encryption) = &self.encryption {
+            serialized = encryption
+                .encrypt_line(&serialized)
+                .map_err(std::io::Error::other)?;
+        }
         serialized.push('\n');
 
         let mut writer = self.writer.lock().await;
@@ -124,8 +184,11 @@ fn unix_timestamp_now() -> i64 {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use pretty_assertions::assert_eq;
     use serde_json::Value;
+    use serde_json::json;
     use tempfile::TempDir;
 
     use super::ApprovalDecision;
@@ -133,6 +196,7 @@ mod tests {
     use super::AuditRecord;
     use super::ResultStatus;
     use crate::ActionKind;
+    use crate::ApproverIdentity;
     use crate::PermissionProfile;
     use crate::PolicyDecision;
 
@@ -213,6 +277,73 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn append_persists_action_link_when_present() -> anyhow::Result<()> {
+        let temp = TempDir::new()?;
+        let log_path = temp.path().join("audit.jsonl");
+        let logger = AuditLogger::open(&log_path).await?;
+
+        logger.append(&build_record("src/main.rs")).await?;
+        logger
+            .append(
+                &build_record("src/lib.rs")
+                    .with_action_id("act-7")
+                    .with_event_sequence(12)
+                    .with_approver(ApproverIdentity::configured("ops-oncall")),
+            )
+            .await?;
+
+        let text = tokio::fs::read_to_string(&log_path).await?;
+        let values: Vec<Value> = text
+            .lines()
+            .map(serde_json::from_str::<Value>)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0].get("action_id"), None);
+        assert_eq!(values[0].get("event_sequence"), None);
+        assert_eq!(values[0].get("approver"), None);
+        assert_eq!(
+            values[1].get("action_id").and_then(Value::as_str),
+            Some("act-7")
+        );
+        assert_eq!(
+            values[1].get("event_sequence").and_then(Value::as_u64),
+            Some(12)
+        );
+
+        let parsed: AuditRecord = serde_json::from_value(values[1].clone())?;
+        assert_eq!(parsed.action_id.as_deref(), Some("act-7"));
+        assert_eq!(parsed.event_sequence, Some(12));
+        assert_eq!(
+            parsed.approver,
+            Some(ApproverIdentity::configured("ops-oncall"))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn append_serializes_custom_metadata_fields() -> anyhow::Result<()> {
+        let temp = TempDir::new()?;
+        let log_path = temp.path().join("audit.jsonl");
+        let logger = AuditLogger::open(&log_path).await?;
+
+        let metadata = BTreeMap::from([
+            ("cost_center".to_string(), "eng-42".to_string()),
+            ("environment".to_string(), "staging".to_string()),
+        ]);
+        logger
+            .append(&build_record("src/main.rs").with_metadata(metadata))
+            .await?;
+
+        let text = tokio::fs::read_to_string(&log_path).await?;
+        let value: Value = serde_json::from_str(text.trim_end())?;
+        assert_eq!(
+            value.get("metadata"),
+            Some(&json!({"cost_center": "eng-42", "environment": "staging"}))
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn append_redacts_secret_patterns_before_persisting() -> anyhow::Result<()> {
         let temp = TempDir::new()?;
@@ -0,0 +1,318 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use age::secrecy::ExposeSecret;
+use age::x25519::Identity;
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use codex_keyring_store::KeyringStore;
+use thiserror::Error;
+
+use crate::AuditRecord;
+
+pub const AUDIT_KEY_ENV_VAR: &str = "ALICIA_AUDIT_KEY";
+pub const AUDIT_KEYRING_SERVICE: &str = "codex-alicia-audit";
+pub const ENCRYPTED_AUDIT_LINE_PREFIX: &str = "alicia-age-v1:";
+
+#[derive(Debug, Error)]
+pub enum AuditEncryptionError {
+    #[error("audit encryption key variable `{0}` is not set")]
+    MissingEnvKey(String),
+    #[error("audit encryption key is invalid: {0}")]
+    InvalidKey(String),
+    #[error("failed to access audit encryption key in OS keychain: {0}")]
+    Keyring(String),
+    #[error("no audit encryption key is stored in the OS keychain for `{0}`")]
+    MissingKeyringKey(String),
+    #[error("failed to encrypt audit record: {0}")]
+    EncryptFailed(String),
+    #[error("failed to read audit log `{path}`: {source}")]
+    ReadFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("audit log line {line} is not encrypted")]
+    UnencryptedLine { line: usize },
+    #[error("audit log line {line} failed to decrypt: {reason}")]
+    DecryptFailed { line: usize, reason: String },
+    #[error("audit log line {line} is not a valid audit record: {reason}")]
+    InvalidRecord { line: usize, reason: String },
+}
+
+/// Per-line age encryption for the append-only audit log.
+///
+/// Each JSONL record is encrypted on its own so the log stays appendable; the file
+/// never holds plaintext once encryption is enabled.
+#[derive(Clone)]
+pub struct AuditEncryption {
+    identity: Arc<Identity>,
+}
+
+impl fmt::Debug for AuditEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditEncryption")
+            .field("recipient", &self.identity.to_public().to_string())
+            .finish()
+    }
+}
+
+impl AuditEncryption {
+    pub fn generate() -> Self {
+        Self {
+            identity: Arc::new(Identity::generate()),
+        }
+    }
+
+    pub fn from_secret_key(secret_key: &str) -> Result<Self, AuditEncryptionError> {
+        let identity = Identity::from_str(secret_key.trim())
+            .map_err(|err| AuditEncryptionError::InvalidKey(err.to_string()))?;
+        Ok(Self {
+            identity: Arc::new(identity),
+        })
+    }
+
+    pub fn from_env(variable: &str) -> Result<Self, AuditEncryptionError> {
+        let secret_key = std::env::var(variable)
+            .map_err(|_| AuditEncryptionError::MissingEnvKey(variable.to_string()))?;
+        Self::from_secret_key(&secret_key)
+    }
+
+    /// Loads the audit key stored for `account`, generating and persisting a new one on
+    /// first use.
+    pub fn from_keyring(
+        keyring_store: &dyn KeyringStore,
+        account: &str,
+    ) -> Result<Self, AuditEncryptionError> {
+        if let Some(stored) = Self::stored_in_keyring(keyring_store, account)? {
+            return Ok(stored);
+        }
+
+        let generated = Self::generate();
+        keyring_store
+            .save(
+                AUDIT_KEYRING_SERVICE,
+                account,
+                generated.identity.to_string().expose_secret(),
+            )
+            .map_err(|err| AuditEncryptionError::Keyring(err.message()))?;
+        Ok(generated)
+    }
+
+    /// Loads the audit key stored for `account` without ever creating one, for reading a
+    /// log back: a fresh key could not decrypt it anyway.
+    pub fn load_from_keyring(
+        keyring_store: &dyn KeyringStore,
+        account: &str,
+    ) -> Result<Self, AuditEncryptionError> {
+        Self::stored_in_keyring(keyring_store, account)?
+            .ok_or_else(|| AuditEncryptionError::MissingKeyringKey(account.to_string()))
+    }
+
+    fn stored_in_keyring(
+        keyring_store: &dyn KeyringStore,
+        account: &str,
+    ) -> Result<Option<Self>, AuditEncryptionError> {
+        let loaded = keyring_store
+            .load(AUDIT_KEYRING_SERVICE, account)
+            .map_err(|err| AuditEncryptionError::Keyring(err.message()))?;
+        loaded
+            .map(|secret_key| Self::from_secret_key(&secret_key))
+            .transpose()
+    }
+
+    pub fn encrypt_line(&self, plaintext: &str) -> Result<String, AuditEncryptionError> {
+        let ciphertext = age::encrypt(&self.identity.to_public(), plaintext.as_bytes())
+            .map_err(|err| AuditEncryptionError::EncryptFailed(err.to_string()))?;
+        Ok(format!(
+            "{ENCRYPTED_AUDIT_LINE_PREFIX}{}",
+            BASE64_STANDARD.encode(ciphertext)
+        ))
+    }
+
+    fn decrypt_line(&self, line_number: usize, line: &str) -> Result<String, AuditEncryptionError> {
+        let Some(encoded) = line.strip_prefix(ENCRYPTED_AUDIT_LINE_PREFIX) else {
+            return Err(AuditEncryptionError::UnencryptedLine { line: line_number });
+        };
+        let ciphertext =
+            BASE64_STANDARD
+                .decode(encoded)
+                .map_err(|err| AuditEncryptionError::DecryptFailed {
+                    line: line_number,
+                    reason: err.to_string(),
+                })?;
+        let plaintext = age::decrypt(self.identity.as_ref(), &ciphertext).map_err(|err| {
+            AuditEncryptionError::DecryptFailed {
+                line: line_number,
+                reason: err.to_string(),
+            }
+        })?;
+        String::from_utf8(plaintext).map_err(|err| AuditEncryptionError::DecryptFailed {
+            line: line_number,
+            reason: err.to_string(),
+        })
+    }
+
+    /// Decrypts every line of an encrypted audit log and checks that each one is a
+    /// well-formed audit record. Fails on the first line that does not.
+    ///
+    /// This is not an authenticity check: anyone holding the public recipient can encrypt
+    /// a line for this key, and removed or reordered lines go unnoticed.
+    pub fn decrypt_and_verify(
+        &self,
+        path: &Path,
+    ) -> Result<Vec<AuditRecord>, AuditEncryptionError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|source| AuditEncryptionError::ReadFailed {
+                path: path.to_string_lossy().to_string(),
+                source,
+            })?;
+
+        let mut records = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line_number = index + 1;
+            let plaintext = self.decrypt_line(line_number, line)?;
+            let record = serde_json::from_str::<AuditRecord>(&plaintext).map_err(|err| {
+                AuditEncryptionError::InvalidRecord {
+                    line: line_number,
+                    reason: err.to_string(),
+                }
+            })?;
+            records.push(record);
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use age::secrecy::ExposeSecret;
+    use codex_keyring_store::tests::MockKeyringStore;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::AuditEncryption;
+    use super::AuditEncryptionError;
+    use super::ENCRYPTED_AUDIT_LINE_PREFIX;
+    use crate::ActionKind;
+    use crate::ApprovalDecision;
+    use crate::AuditLogger;
+    use crate::AuditRecord;
+    use crate::PermissionProfile;
+    use crate::PolicyDecision;
+    use crate::ResultStatus;
+
+    fn build_record(target: &str) -> AuditRecord {
+        AuditRecord::new(
+            "sess-encrypted",
+            ActionKind::ExecuteCommand,
+            target,
+            PermissionProfile::ReadWriteWithApproval,
+            PolicyDecision::RequireApproval,
+            ApprovalDecision::Approved,
+            ResultStatus::Succeeded,
+            3,
+        )
+    }
+
+    #[tokio::test]
+    async fn encrypted_logger_never_writes_plaintext_and_round_trips() -> anyhow::Result<()> {
+        let temp = TempDir::new()?;
+        let log_path = temp.path().join("audit.jsonl");
+        let encryption = AuditEncryption::generate();
+        let logger = AuditLogger::open(&log_path)
+            .await?
+            .with_encryption(encryption.clone());
+
+        let first = build_record("rm -rf build");
+        let second = build_record("cargo test");
+        logger.append(&first).await?;
+        logger.append(&second).await?;
+
+        let text = tokio::fs::read_to_string(&log_path).await?;
+        assert_eq!(text.lines().count(), 2);
+        assert!(!text.contains("rm -rf build"));
+        assert!(
+            text.lines()
+                .all(|line| line.starts_with(ENCRYPTED_AUDIT_LINE_PREFIX))
+        );
+
+        let records = encryption.decrypt_and_verify(&log_path)?;
+        assert_eq!(records, vec![first, second]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn decrypt_and_verify_rejects_foreign_key_and_plaintext_lines() -> anyhow::Result<()> {
+        let temp = TempDir::new()?;
+        let log_path = temp.path().join("audit.jsonl");
+        let logger = AuditLogger::open(&log_path)
+            .await?
+            .with_encryption(AuditEncryption::generate());
+        logger.append(&build_record("cargo build")).await?;
+
+        let other_key = AuditEncryption::generate();
+        assert!(matches!(
+            other_key.decrypt_and_verify(&log_path),
+            Err(AuditEncryptionError::DecryptFailed { line: 1, .. })
+        ));
+
+        let plain_path = temp.path().join("plain.jsonl");
+        AuditLogger::open(&plain_path)
+            .await?
+            .append(&build_record("cargo build"))
+            .await?;
+        assert!(matches!(
+            other_key.decrypt_and_verify(&plain_path),
+            Err(AuditEncryptionError::UnencryptedLine { line: 1 })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn keyring_key_is_created_once_and_reused() -> anyhow::Result<()> {
+        let keyring = MockKeyringStore::default();
+
+        let first = AuditEncryption::from_keyring(&keyring, "workspace-a")?;
+        let stored = keyring
+            .saved_value("workspace-a")
+            .ok_or_else(|| anyhow::anyhow!("expected key to be persisted"))?;
+        let second = AuditEncryption::from_keyring(&keyring, "workspace-a")?;
+
+        assert_eq!(first.identity.to_string().expose_secret(), stored.as_str());
+        assert_eq!(second.identity.to_string().expose_secret(), stored.as_str());
+        Ok(())
+    }
+
+    #[test]
+    fn loading_from_the_keyring_never_creates_a_key() -> anyhow::Result<()> {
+        let keyring = MockKeyringStore::default();
+
+        assert!(matches!(
+            AuditEncryption::load_from_keyring(&keyring, "workspace-a"),
+            Err(AuditEncryptionError::MissingKeyringKey(account)) if account == "workspace-a"
+        ));
+        assert_eq!(keyring.saved_value("workspace-a"), None);
+
+        let created = AuditEncryption::from_keyring(&keyring, "workspace-a")?;
+        let loaded = AuditEncryption::load_from_keyring(&keyring, "workspace-a")?;
+        assert_eq!(
+            loaded.identity.to_string().expose_secret(),
+            created.identity.to_string().expose_secret()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_secret_key_is_rejected() {
+        assert!(matches!(
+            AuditEncryption::from_secret_key("not-a-key"),
+            Err(AuditEncryptionError::InvalidKey(_))
+        ));
+    }
+}
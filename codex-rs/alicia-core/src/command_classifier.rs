@@ -0,0 +1,308 @@
+use std::path::Path;
+
+use crate::ActionKind;
+
+const SHELL_PROGRAMS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "fish"];
+const WRAPPER_PROGRAMS: &[&str] = &["sudo", "env", "time", "nice", "nohup", "command", "exec"];
+const NETWORK_PROGRAMS: &[&str] = &[
+    "curl", "wget", "ssh", "scp", "sftp", "ftp", "rsync", "nc", "ncat", "netcat", "telnet", "ping",
+    "dig", "nslookup", "http", "https", "gh", "aws", "gcloud", "az", "npx",
+];
+const WRITE_PROGRAMS: &[&str] = &[
+    "rm", "mv", "cp", "touch", "mkdir", "rmdir", "tee", "dd", "chmod", "chown", "ln", "truncate",
+];
+const GIT_NETWORK_SUBCOMMANDS: &[&str] =
+    &["clone", "fetch", "pull", "push", "ls-remote", "submodule"];
+
+/// Infers what a command does beyond running a process: network tools, package managers
+/// (network and file writes), file-writing programs and output redirects. Shell wrappers
+/// such as `sh -c "..."` are looked into. The result always starts with
+/// `ActionKind::ExecuteCommand`, followed by the other kinds in declaration order.
+pub fn classify_command(command: &[String]) -> Vec<ActionKind> {
+    let mut writes_files = false;
+    let mut accesses_network = false;
+    for segment in command_segments(command) {
+        let (segment_writes, segment_network) = classify_segment(&segment);
+        writes_files |= segment_writes;
+        accesses_network |= segment_network;
+    }
+
+    let mut action_kinds = vec![ActionKind::ExecuteCommand];
+    if writes_files {
+        action_kinds.push(ActionKind::WriteFile);
+    }
+    if accesses_network {
+        action_kinds.push(ActionKind::NetworkAccess);
+    }
+    action_kinds
+}
+
+/// Splits `command` into simple commands, expanding `sh -c` scripts and separating on
+/// `;`, `&&`, `||`, `|` and `&`.
+fn command_segments(command: &[String]) -> Vec<Vec<String>> {
+    let script = shell_script(command);
+    let tokens = match script {
+        Some(script) => tokenize_script(script),
+        None => command.to_vec(),
+    };
+
+    let mut segments = Vec::new();
+    let mut current = Vec::new();
+    for token in tokens {
+        if matches!(token.as_str(), ";" | "&&" | "||" | "|" | "&") {
+            if !current.is_empty() {
+                segments.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(token);
+        }
+    }
+    if !current.is_empty() {
+        segments.push(current);
+    }
+    segments
+}
+
+fn shell_script(command: &[String]) -> Option<&str> {
+    let (program, args) = command.split_first()?;
+    let program = program_name(program);
+    let script_flag = if SHELL_PROGRAMS.contains(&program.as_str()) {
+        args.iter()
+            .position(|arg| arg.starts_with('-') && !arg.starts_with("--") && arg.contains('c'))
+    } else if program == "cmd" {
+        args.iter().position(|arg| arg.eq_ignore_ascii_case("/c"))
+    } else if program == "pwsh" || program == "powershell" {
+        args.iter()
+            .position(|arg| arg.eq_ignore_ascii_case("-command"))
+    } else {
+        None
+    }?;
+    args.get(script_flag + 1).map(String::as_str)
+}
+
+/// Whitespace tokenizer that honours quotes and splits control operators and redirects into
+/// their own tokens.
+fn tokenize_script(script: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut chars = script.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if let Some(open) = quote {
+            if ch == open {
+                quote = None;
+            } else {
+                current.push(ch);
+            }
+            continue;
+        }
+        match ch {
+            '\'' | '"' => quote = Some(ch),
+            ';' | '|' | '&' | '>' => {
+                // `2>` and `2>&1` keep their file descriptor with the operator.
+                let fd_prefix =
+                    ch == '>' && !current.is_empty() && current.chars().all(|c| c.is_ascii_digit());
+                if !current.is_empty() && !fd_prefix {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                let mut operator = std::mem::take(&mut current);
+                operator.push(ch);
+                if chars.peek() == Some(&ch) {
+                    operator.extend(chars.next());
+                } else if ch == '>' && chars.peek() == Some(&'&') {
+                    operator.extend(chars.next());
+                    while let Some(fd) = chars.next_if(|next| next.is_ascii_digit() || *next == '-')
+                    {
+                        operator.push(fd);
+                    }
+                }
+                tokens.push(operator);
+            }
+            ch if ch.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(ch),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// Returns `(writes_files, accesses_network)` for one simple command.
+fn classify_segment(segment: &[String]) -> (bool, bool) {
+    let mut writes_files = has_file_redirect(segment);
+    let words: Vec<&String> = segment
+        .iter()
+        .skip_while(|word| {
+            WRAPPER_PROGRAMS.contains(&program_name(word).as_str())
+                || word.starts_with('-')
+                || is_env_assignment(word)
+        })
+        .collect();
+    let Some((program, args)) = words.split_first() else {
+        return (writes_files, false);
+    };
+    let program = program_name(program);
+    let subcommand = args
+        .iter()
+        .find(|arg| !arg.starts_with('-'))
+        .map(|arg| arg.as_str());
+
+    let mut accesses_network = NETWORK_PROGRAMS.contains(&program.as_str());
+    writes_files |= WRITE_PROGRAMS.contains(&program.as_str());
+    match program.as_str() {
+        "git" => {
+            accesses_network |=
+                git_subcommand(args).is_some_and(|sub| GIT_NETWORK_SUBCOMMANDS.contains(&sub));
+        }
+        "sed" | "perl" => {
+            writes_files |= args
+                .iter()
+                .any(|arg| arg.starts_with("-i") || arg.starts_with("--in-place"));
+        }
+        "docker" | "podman" => {
+            accesses_network |= matches!(
+                subcommand,
+                Some("pull" | "push" | "login" | "build" | "run")
+            );
+        }
+        _ => {}
+    }
+    if installs_packages(&program, subcommand) {
+        accesses_network = true;
+        writes_files = true;
+    }
+    (writes_files, accesses_network)
+}
+
+/// First git argument that is not a global option; `-C` and `-c` take a value.
+fn git_subcommand<'a>(args: &[&'a String]) -> Option<&'a str> {
+    let mut args = args.iter().copied().map(String::as_str);
+    while let Some(arg) = args.next() {
+        match arg {
+            "-C" | "-c" => {
+                args.next();
+            }
+            arg if arg.starts_with('-') => {}
+            arg => return Some(arg),
+        }
+    }
+    None
+}
+
+fn installs_packages(program: &str, subcommand: Option<&str>) -> bool {
+    match program {
+        "npm" | "pnpm" | "bun" => matches!(
+            subcommand,
+            Some("install" | "i" | "add" | "ci" | "update" | "upgrade" | "publish")
+        ),
+        // Bare `yarn` installs the lockfile.
+        "yarn" => matches!(
+            subcommand,
+            None | Some("install" | "add" | "upgrade" | "publish")
+        ),
+        "pip" | "pip3" | "uv" | "poetry" | "pipenv" => matches!(
+            subcommand,
+            Some("install" | "add" | "sync" | "download" | "lock" | "update")
+        ),
+        "cargo" => matches!(
+            subcommand,
+            Some("install" | "add" | "fetch" | "update" | "publish")
+        ),
+        "go" => matches!(subcommand, Some("get" | "install" | "mod")),
+        "gem" | "brew" | "apt" | "apt-get" | "dnf" | "yum" | "apk" | "choco" | "winget" => {
+            matches!(subcommand, Some("install" | "update" | "upgrade" | "add"))
+        }
+        _ => false,
+    }
+}
+
+/// Redirects into anything other than `/dev/null` or another descriptor write a file.
+fn has_file_redirect(segment: &[String]) -> bool {
+    segment.iter().enumerate().any(|(index, token)| {
+        let operator = token.trim_start_matches(|ch: char| ch.is_ascii_digit());
+        if operator != ">" && operator != ">>" {
+            return false;
+        }
+        segment
+            .get(index + 1)
+            .is_some_and(|target| target != "/dev/null" && !target.starts_with('&'))
+    })
+}
+
+fn is_env_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+    })
+}
+
+fn program_name(program: &str) -> String {
+    Path::new(program)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::classify_command;
+    use crate::ActionKind;
+
+    fn classify(command: &[&str]) -> Vec<ActionKind> {
+        let command: Vec<String> = command.iter().map(|part| (*part).to_string()).collect();
+        classify_command(&command)
+    }
+
+    #[test]
+    fn infers_network_and_file_writes_from_command_vectors() {
+        assert_eq!(
+            classify(&["cargo", "test"]),
+            vec![ActionKind::ExecuteCommand]
+        );
+        assert_eq!(
+            classify(&["/usr/bin/curl", "-fsSL", "https://example.com"]),
+            vec![ActionKind::ExecuteCommand, ActionKind::NetworkAccess]
+        );
+        assert_eq!(
+            classify(&["npm", "install", "left-pad"]),
+            vec![
+                ActionKind::ExecuteCommand,
+                ActionKind::WriteFile,
+                ActionKind::NetworkAccess
+            ]
+        );
+        assert_eq!(
+            classify(&["git", "-C", "repo", "push"]),
+            vec![ActionKind::ExecuteCommand, ActionKind::NetworkAccess]
+        );
+        assert_eq!(
+            classify(&["sed", "-i", "s/a/b/", "src/main.rs"]),
+            vec![ActionKind::ExecuteCommand, ActionKind::WriteFile]
+        );
+    }
+
+    #[test]
+    fn looks_into_shell_scripts_and_redirects() {
+        assert_eq!(
+            classify(&["bash", "-lc", "cargo build 2>&1 >/dev/null && echo ok"]),
+            vec![ActionKind::ExecuteCommand]
+        );
+        assert_eq!(
+            classify(&["sh", "-c", "echo 'a > b' >> notes.txt"]),
+            vec![ActionKind::ExecuteCommand, ActionKind::WriteFile]
+        );
+        assert_eq!(
+            classify(&["sh", "-c", "FOO=1 sudo wget -q x|tar xz"]),
+            vec![ActionKind::ExecuteCommand, ActionKind::NetworkAccess]
+        );
+    }
+}
@@ -0,0 +1,409 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+
+use crate::session::SessionManager;
+use crate::session::SessionMode;
+use crate::session::SessionStartRequest;
+
+/// Exit code reported when a fake session is stopped before its script finishes.
+pub const FAKE_TERMINATED_EXIT_CODE: i32 = -1;
+
+const FAKE_OUTPUT_CAPACITY: usize = 256;
+const FAKE_INPUT_CAPACITY: usize = 128;
+const FAKE_DRAIN_ATTEMPTS: usize = 200;
+
+/// What a fake session prints and how it ends. Without an `exit_code` the session keeps
+/// running until [`FakeSessionManager::finish`] or a stop.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FakeSessionScript {
+    pub output: Vec<String>,
+    pub exit_code: Option<i32>,
+    /// Writes input straight back as output, like a terminal echo.
+    pub echo_input: bool,
+}
+
+impl FakeSessionScript {
+    pub fn exiting(exit_code: i32) -> Self {
+        Self {
+            exit_code: Some(exit_code),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_output(mut self, chunk: impl Into<String>) -> Self {
+        self.output.push(chunk.into());
+        self
+    }
+
+    pub fn with_echo_input(mut self) -> Self {
+        self.echo_input = true;
+        self
+    }
+}
+
+/// In-memory double for [`SessionManager`]: sessions play a [`FakeSessionScript`] instead of
+/// spawning processes, and input is captured instead of written to a PTY. Hand
+/// [`FakeSessionManager::session_manager`] to anything that takes a real manager; events are
+/// the same `CommandStarted`/`CommandOutputChunk`/`CommandFinished` stream.
+#[derive(Debug, Clone, Default)]
+pub struct FakeSessionManager {
+    backend: Arc<FakeBackend>,
+}
+
+impl FakeSessionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Script used by sessions without one of their own. Defaults to exiting with 0.
+    pub fn set_default_script(&self, script: FakeSessionScript) {
+        if let Ok(mut default_script) = self.backend.default_script.lock() {
+            *default_script = Some(script);
+        }
+    }
+
+    /// Script for the next session started as `session_id`.
+    pub fn script(&self, session_id: impl Into<String>, script: FakeSessionScript) {
+        if let Ok(mut scripts) = self.backend.scripts.lock() {
+            scripts.insert(session_id.into(), script);
+        }
+    }
+
+    /// A manager backed by this fake; clones share the same sessions.
+    pub fn session_manager(&self) -> SessionManager {
+        SessionManager::with_fake_backend(Arc::clone(&self.backend))
+    }
+
+    /// Requests started so far, in order.
+    pub fn started_requests(&self) -> Vec<SessionStartRequest> {
+        self.backend
+            .started
+            .lock()
+            .map(|started| started.clone())
+            .unwrap_or_default()
+    }
+
+    /// Bytes written to `session_id`'s input so far.
+    pub fn input(&self, session_id: &str) -> Vec<u8> {
+        self.backend
+            .processes
+            .lock()
+            .ok()
+            .and_then(|processes| processes.get(session_id).map(FakeProcess::input))
+            .unwrap_or_default()
+    }
+
+    /// Emits `chunk` as output of a running session. Returns false when it is not running.
+    pub fn emit_output(&self, session_id: &str, chunk: &str) -> bool {
+        self.with_process(session_id, |process| {
+            process.output_tx.send(chunk.as_bytes().to_vec()).is_ok()
+        })
+    }
+
+    /// Window size of `session_id` after its last resize; `None` before any resize.
+    pub fn pty_size(&self, session_id: &str) -> Option<(u16, u16)> {
+        self.backend
+            .processes
+            .lock()
+            .ok()
+            .and_then(|processes| processes.get(session_id).and_then(FakeProcess::pty_size))
+    }
+
+    /// Ends a running session with `exit_code`. Returns false when it already ended.
+    pub fn finish(&self, session_id: &str, exit_code: i32) -> bool {
+        self.with_process(session_id, |process| process.exit.finish(exit_code))
+    }
+
+    fn with_process(&self, session_id: &str, f: impl FnOnce(&FakeProcess) -> bool) -> bool {
+        self.backend
+            .processes
+            .lock()
+            .ok()
+            .and_then(|processes| processes.get(session_id).map(f))
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct FakeBackend {
+    default_script: StdMutex<Option<FakeSessionScript>>,
+    scripts: StdMutex<HashMap<String, FakeSessionScript>>,
+    processes: StdMutex<HashMap<String, Arc<FakeProcess>>>,
+    started: StdMutex<Vec<SessionStartRequest>>,
+}
+
+impl FakeBackend {
+    pub(crate) fn spawn(
+        &self,
+        request: &SessionStartRequest,
+    ) -> (
+        Arc<FakeProcess>,
+        broadcast::Receiver<Vec<u8>>,
+        oneshot::Receiver<i32>,
+    ) {
+        let script = self
+            .scripts
+            .lock()
+            .ok()
+            .and_then(|mut scripts| scripts.remove(&request.session_id))
+            .or_else(|| {
+                self.default_script
+                    .lock()
+                    .ok()
+                    .and_then(|script| script.clone())
+            })
+            .unwrap_or_else(|| FakeSessionScript::exiting(0));
+        let (writer_tx, mut writer_rx) = mpsc::channel::<Vec<u8>>(FAKE_INPUT_CAPACITY);
+        let (output_tx, output_rx) = broadcast::channel(FAKE_OUTPUT_CAPACITY);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let process = Arc::new(FakeProcess {
+            writer_tx,
+            output_tx: output_tx.clone(),
+            input: Arc::new(StdMutex::new(Vec::new())),
+            exit: Arc::new(FakeExit {
+                code: StdMutex::new(None),
+                exit_tx: StdMutex::new(Some(exit_tx)),
+            }),
+            launched: Arc::new(Notify::new()),
+            pty: request.mode != SessionMode::Pipe,
+            pty_size: StdMutex::new(None),
+        });
+
+        let input = Arc::clone(&process.input);
+        let echo_tx = script.echo_input.then(|| output_tx.clone());
+        tokio::spawn(async move {
+            while let Some(bytes) = writer_rx.recv().await {
+                if let Ok(mut input) = input.lock() {
+                    input.extend_from_slice(&bytes);
+                }
+                if let Some(echo_tx) = &echo_tx {
+                    let _ = echo_tx.send(bytes);
+                }
+            }
+        });
+
+        let launched = Arc::clone(&process.launched);
+        let exit = Arc::clone(&process.exit);
+        tokio::spawn(async move {
+            launched.notified().await;
+            for chunk in script.output {
+                let _ = output_tx.send(chunk.into_bytes());
+            }
+            let Some(exit_code) = script.exit_code else {
+                return;
+            };
+            // Let the forwarder drain the output so `CommandFinished` comes last. Receivers
+            // that never read would stall this, hence the bound.
+            for _ in 0..FAKE_DRAIN_ATTEMPTS {
+                if output_tx.is_empty() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            exit.finish(exit_code);
+        });
+
+        if let Ok(mut processes) = self.processes.lock() {
+            processes.insert(request.session_id.clone(), Arc::clone(&process));
+        }
+        if let Ok(mut started) = self.started.lock() {
+            started.push(request.clone());
+        }
+        (process, output_rx, exit_rx)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct FakeProcess {
+    writer_tx: mpsc::Sender<Vec<u8>>,
+    output_tx: broadcast::Sender<Vec<u8>>,
+    input: Arc<StdMutex<Vec<u8>>>,
+    exit: Arc<FakeExit>,
+    launched: Arc<Notify>,
+    pty: bool,
+    pty_size: StdMutex<Option<(u16, u16)>>,
+}
+
+impl FakeProcess {
+    pub(crate) fn writer_sender(&self) -> mpsc::Sender<Vec<u8>> {
+        self.writer_tx.clone()
+    }
+
+    pub(crate) fn output_receiver(&self) -> broadcast::Receiver<Vec<u8>> {
+        self.output_tx.subscribe()
+    }
+
+    pub(crate) fn has_exited(&self) -> bool {
+        self.exit_code().is_some()
+    }
+
+    pub(crate) fn exit_code(&self) -> Option<i32> {
+        self.exit.code.lock().ok().and_then(|code| *code)
+    }
+
+    pub(crate) fn terminate(&self) {
+        self.exit.finish(FAKE_TERMINATED_EXIT_CODE);
+    }
+
+    /// Starts playing the script; called once `CommandStarted` has been sent.
+    pub(crate) fn launch(&self) {
+        self.launched.notify_one();
+    }
+
+    pub(crate) fn resize(&self, rows: u16, cols: u16) -> anyhow::Result<()> {
+        if !self.pty {
+            anyhow::bail!("process was not spawned with a PTY");
+        }
+        if let Ok(mut pty_size) = self.pty_size.lock() {
+            *pty_size = Some((rows, cols));
+        }
+        Ok(())
+    }
+
+    fn pty_size(&self) -> Option<(u16, u16)> {
+        self.pty_size.lock().ok().and_then(|size| *size)
+    }
+
+    fn input(&self) -> Vec<u8> {
+        self.input
+            .lock()
+            .map(|input| input.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+struct FakeExit {
+    code: StdMutex<Option<i32>>,
+    exit_tx: StdMutex<Option<oneshot::Sender<i32>>>,
+}
+
+impl FakeExit {
+    fn finish(&self, exit_code: i32) -> bool {
+        let Ok(mut code) = self.code.lock() else {
+            return false;
+        };
+        if code.is_some() {
+            return false;
+        }
+        *code = Some(exit_code);
+        if let Some(exit_tx) = self.exit_tx.lock().ok().and_then(|mut tx| tx.take()) {
+            let _ = exit_tx.send(exit_code);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use pretty_assertions::assert_eq;
+
+    use super::FakeSessionManager;
+    use super::FakeSessionScript;
+    use crate::IpcEvent;
+    use crate::SessionManagerError;
+    use crate::SessionMode;
+    use crate::SessionStartRequest;
+
+    #[tokio::test]
+    async fn fake_sessions_play_scripts_and_capture_input() -> anyhow::Result<()> {
+        let fake = FakeSessionManager::new();
+        fake.script(
+            "sess-fake",
+            FakeSessionScript::default()
+                .with_output("compilando\n")
+                .with_echo_input(),
+        );
+        let manager = fake.session_manager();
+        let mut events = manager.event_receiver();
+
+        manager
+            .start(SessionStartRequest::new(
+                "sess-fake",
+                "cargo",
+                vec!["build".to_string()],
+                PathBuf::from("/ws"),
+                Default::default(),
+            ))
+            .await?;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let reattached = manager.reattach("sess-fake").await?;
+        reattached.writer_tx.send(b"y\n".to_vec()).await?;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(fake.finish("sess-fake", 3));
+
+        let mut names = Vec::new();
+        loop {
+            let message = tokio::time::timeout(Duration::from_secs(2), events.recv()).await??;
+            match message.event {
+                IpcEvent::CommandStarted(event) => {
+                    names.push(format!("started {}", event.command.join(" ")));
+                }
+                IpcEvent::CommandOutputChunk(event) => names.push(event.chunk),
+                IpcEvent::CommandFinished(event) => {
+                    names.push(format!("finished {}", event.exit_code));
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        assert_eq!(
+            names,
+            vec![
+                "started cargo build".to_string(),
+                "compilando\n".to_string(),
+                "y\n".to_string(),
+                "finished 3".to_string(),
+            ]
+        );
+        assert_eq!(fake.input("sess-fake"), b"y\n".to_vec());
+        assert_eq!(fake.started_requests().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resizes_reach_pty_sessions_only() -> anyhow::Result<()> {
+        let fake = FakeSessionManager::new();
+        fake.set_default_script(FakeSessionScript::default());
+        let manager = fake.session_manager();
+        let request = |session_id: &str| {
+            SessionStartRequest::new(
+                session_id,
+                "vim",
+                Vec::new(),
+                PathBuf::from("/ws"),
+                Default::default(),
+            )
+        };
+        manager
+            .start(request("sess-pty").with_mode(SessionMode::Pty))
+            .await?;
+        manager
+            .start(request("sess-pipe").with_mode(SessionMode::Pipe))
+            .await?;
+
+        assert_eq!(fake.pty_size("sess-pty"), None);
+        manager.resize("sess-pty", 40, 120).await?;
+        assert_eq!(fake.pty_size("sess-pty"), Some((40, 120)));
+        assert!(matches!(
+            manager.resize("sess-pipe", 40, 120).await,
+            Err(SessionManagerError::ResizeFailed { .. })
+        ));
+        assert!(matches!(
+            manager.resize("sess-missing", 40, 120).await,
+            Err(SessionManagerError::SessionNotFound(_))
+        ));
+        Ok(())
+    }
+}
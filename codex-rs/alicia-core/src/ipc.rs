@@ -1,10 +1,29 @@
+pub mod client;
+pub mod server;
+
 use serde::Deserialize;
 use serde::Serialize;
+use thiserror::Error;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
 
+use crate::network_isolation::NetworkIsolation;
 use crate::policy::ActionKind;
 
 pub const IPC_PROTOCOL_VERSION: u16 = 1;
 
+#[derive(Debug, Error)]
+pub enum IpcTransportError {
+    #[error("IPC endpoint `{0}` is already served by another process")]
+    AddressInUse(String),
+    #[error("IPC endpoint `{0}` exists and is not a socket")]
+    NotASocket(String),
+    #[error("IPC transport failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid IPC message: {0}")]
+    InvalidMessage(#[from] serde_json::Error),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IpcMessage {
@@ -33,6 +52,60 @@ pub enum IpcEvent {
     CommandFinished(CommandFinished),
     PatchPreviewReady(PatchPreviewReady),
     PatchApplied(PatchApplied),
+    NetworkAccessBlocked(NetworkAccessBlocked),
+    AgentMessageDelta(AgentMessageDelta),
+    AgentMessageComplete(AgentMessageComplete),
+    ToolCallStarted(ToolCallStarted),
+    ToolCallFinished(ToolCallFinished),
+}
+
+impl IpcEvent {
+    /// Whether an agent may send this event over IPC. Approval resolutions, applied patches
+    /// and network blocks are the UI's own verdicts, never taken from a client.
+    pub fn is_agent_event(&self) -> bool {
+        !matches!(
+            self,
+            Self::ApprovalResolved(_) | Self::PatchApplied(_) | Self::NetworkAccessBlocked(_)
+        )
+    }
+
+    /// Action whose approval or patch the event is about.
+    pub fn action_id(&self) -> Option<&str> {
+        match self {
+            Self::ActionProposed(event) => Some(&event.action_id),
+            Self::ApprovalRequested(event) => Some(&event.action_id),
+            Self::ApprovalResolved(event) => Some(&event.action_id),
+            Self::PatchPreviewReady(event) => Some(&event.action_id),
+            Self::PatchApplied(event) => Some(&event.action_id),
+            Self::CommandStarted(_)
+            | Self::CommandOutputChunk(_)
+            | Self::CommandFinished(_)
+            | Self::NetworkAccessBlocked(_)
+            | Self::AgentMessageDelta(_)
+            | Self::AgentMessageComplete(_)
+            | Self::ToolCallStarted(_)
+            | Self::ToolCallFinished(_) => None,
+        }
+    }
+
+    /// Terminal session the event is about.
+    pub fn command_id(&self) -> Option<&str> {
+        match self {
+            Self::CommandStarted(event) => Some(&event.command_id),
+            Self::CommandOutputChunk(event) => Some(&event.command_id),
+            Self::CommandFinished(event) => Some(&event.command_id),
+            Self::NetworkAccessBlocked(event) => Some(&event.command_id),
+            Self::ActionProposed(_)
+            | Self::ApprovalRequested(_)
+            | Self::ApprovalResolved(_)
+            | Self::PatchPreviewReady(_)
+            | Self::PatchApplied(_)
+            | Self::AgentMessageDelta(_)
+            | Self::AgentMessageComplete(_)
+            | Self::ToolCallStarted(_)
+            | Self::ToolCallFinished(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -56,6 +129,86 @@ pub struct ApprovalRequested {
 pub struct ApprovalResolved {
     pub action_id: String,
     pub resolution: ApprovalResolution,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approver: Option<ApproverIdentity>,
+    /// Why a denial was made, so the agent can change course instead of retrying.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<DenialReason>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DenialCategory {
+    TooRisky,
+    WrongApproach,
+    NeedsTests,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DenialReason {
+    pub category: DenialCategory,
+    /// Free text the reviewer added to the category.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl DenialReason {
+    pub fn new(category: DenialCategory) -> Self {
+        Self {
+            category,
+            detail: None,
+        }
+    }
+
+    /// Blank details are dropped.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        let detail = detail.into();
+        self.detail = (!detail.trim().is_empty()).then_some(detail);
+        self
+    }
+}
+
+/// Who resolved an approval. Expirations carry no approver.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApproverIdentity {
+    pub source: ApproverSource,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApproverSource {
+    OsUser,
+    Configured,
+    RemotePrincipal,
+}
+
+impl ApproverIdentity {
+    pub fn new(source: ApproverSource, name: impl Into<String>) -> Self {
+        Self {
+            source,
+            name: name.into(),
+        }
+    }
+
+    pub fn configured(name: impl Into<String>) -> Self {
+        Self::new(ApproverSource::Configured, name)
+    }
+
+    pub fn remote_principal(name: impl Into<String>) -> Self {
+        Self::new(ApproverSource::RemotePrincipal, name)
+    }
+
+    pub fn os_user() -> Option<Self> {
+        ["USER", "USERNAME"]
+            .iter()
+            .find_map(|variable| std::env::var(variable).ok())
+            .filter(|name| !name.trim().is_empty())
+            .map(|name| Self::new(ApproverSource::OsUser, name))
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
@@ -111,17 +264,92 @@ pub struct PatchApplied {
     pub files: Vec<String>,
 }
 
+/// A session launched offline tried to reach the network; `detail` is the output line that
+/// gave it away.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkAccessBlocked {
+    pub command_id: String,
+    pub isolation: NetworkIsolation,
+    pub detail: String,
+}
+
+/// Next piece of an agent message being streamed; the first delta of a `message_id` opens
+/// the message.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentMessageDelta {
+    pub message_id: String,
+    pub delta: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentMessageComplete {
+    pub message_id: String,
+}
+
+/// A tool call of the agent message `message_id` was dispatched. `session_id` and `action_id`
+/// link it to the terminal session or approval it created, if any.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCallStarted {
+    pub call_id: String,
+    pub message_id: String,
+    pub name: String,
+    pub arguments: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolCallStatus {
+    Succeeded,
+    Failed,
+    Blocked,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCallFinished {
+    pub call_id: String,
+    pub status: ToolCallStatus,
+    pub duration_ms: u64,
+}
+
+/// Writes `message` as one line of JSON, the framing both ends of the socket use.
+async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &IpcMessage,
+) -> Result<(), IpcTransportError> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
     use serde_json::json;
 
     use super::ActionProposed;
+    use super::AgentMessageDelta;
     use super::ApprovalRequested;
+    use super::ApprovalResolution;
+    use super::ApprovalResolved;
+    use super::ApproverIdentity;
     use super::CommandOutputChunk;
     use super::CommandOutputStream;
+    use super::DenialCategory;
+    use super::DenialReason;
     use super::IpcEvent;
     use super::IpcMessage;
+    use super::ToolCallStarted;
     use crate::policy::ActionKind;
 
     #[test]
@@ -147,6 +375,59 @@ mod tests {
         assert_eq!(serialized, expected);
     }
 
+    #[test]
+    fn serializes_agent_message_delta() {
+        let message = IpcMessage::new(IpcEvent::AgentMessageDelta(AgentMessageDelta {
+            message_id: "msg-1".to_string(),
+            delta: "Vou ".to_string(),
+        }));
+
+        let serialized = serde_json::to_value(message);
+        let Ok(serialized) = serialized else {
+            panic!("failed to serialize agent message delta");
+        };
+
+        assert_eq!(
+            serialized,
+            json!({
+                "protocolVersion": 1,
+                "type": "agent_message_delta",
+                "messageId": "msg-1",
+                "delta": "Vou "
+            })
+        );
+    }
+
+    #[test]
+    fn serializes_tool_call_started_without_missing_links() {
+        let message = IpcMessage::new(IpcEvent::ToolCallStarted(ToolCallStarted {
+            call_id: "call-1".to_string(),
+            message_id: "msg-1".to_string(),
+            name: "run_command".to_string(),
+            arguments: "cargo test".to_string(),
+            session_id: Some("agent-0".to_string()),
+            action_id: None,
+        }));
+
+        let serialized = serde_json::to_value(message);
+        let Ok(serialized) = serialized else {
+            panic!("failed to serialize tool call started");
+        };
+
+        assert_eq!(
+            serialized,
+            json!({
+                "protocolVersion": 1,
+                "type": "tool_call_started",
+                "callId": "call-1",
+                "messageId": "msg-1",
+                "name": "run_command",
+                "arguments": "cargo test",
+                "sessionId": "agent-0"
+            })
+        );
+    }
+
     #[test]
     fn deserializes_action_proposed_message() {
         let raw = json!({
@@ -171,6 +452,89 @@ mod tests {
         assert_eq!(parsed, expected);
     }
 
+    #[test]
+    fn approval_resolved_round_trips_optional_approver() {
+        let message = IpcMessage::new(IpcEvent::ApprovalResolved(ApprovalResolved {
+            action_id: "act-rm".to_string(),
+            resolution: ApprovalResolution::Approved,
+            approver: Some(ApproverIdentity::remote_principal("ops@example.com")),
+            reason: None,
+        }));
+
+        let serialized = serde_json::to_value(&message);
+        let Ok(serialized) = serialized else {
+            panic!("failed to serialize approval resolved message");
+        };
+        assert_eq!(
+            serialized,
+            json!({
+                "protocolVersion": 1,
+                "type": "approval_resolved",
+                "actionId": "act-rm",
+                "resolution": "approved",
+                "approver": {
+                    "source": "remote_principal",
+                    "name": "ops@example.com"
+                }
+            })
+        );
+
+        let legacy: Result<IpcMessage, serde_json::Error> = serde_json::from_value(json!({
+            "protocolVersion": 1,
+            "type": "approval_resolved",
+            "actionId": "act-rm",
+            "resolution": "expired"
+        }));
+        let Ok(legacy) = legacy else {
+            panic!("failed to deserialize approval resolved without approver");
+        };
+        assert_eq!(
+            legacy,
+            IpcMessage::new(IpcEvent::ApprovalResolved(ApprovalResolved {
+                action_id: "act-rm".to_string(),
+                resolution: ApprovalResolution::Expired,
+                approver: None,
+                reason: None,
+            }))
+        );
+    }
+
+    #[test]
+    fn approval_resolved_carries_the_denial_reason() {
+        let message = IpcMessage::new(IpcEvent::ApprovalResolved(ApprovalResolved {
+            action_id: "act-rm".to_string(),
+            resolution: ApprovalResolution::Denied,
+            approver: None,
+            reason: Some(
+                DenialReason::new(DenialCategory::NeedsTests).with_detail("cubra o parser"),
+            ),
+        }));
+
+        let serialized = serde_json::to_value(&message);
+        let Ok(serialized) = serialized else {
+            panic!("failed to serialize approval resolved message");
+        };
+        assert_eq!(
+            serialized,
+            json!({
+                "protocolVersion": 1,
+                "type": "approval_resolved",
+                "actionId": "act-rm",
+                "resolution": "denied",
+                "reason": {
+                    "category": "needs_tests",
+                    "detail": "cubra o parser"
+                }
+            })
+        );
+        let parsed: Result<IpcMessage, serde_json::Error> = serde_json::from_value(serialized);
+        assert!(parsed.is_ok_and(|parsed| parsed == message));
+        assert_eq!(
+            DenialReason::new(DenialCategory::Other).with_detail("  "),
+            DenialReason::new(DenialCategory::Other)
+        );
+    }
+
     #[test]
     fn rejects_invalid_payloads() {
         let missing_required_field = json!({
@@ -0,0 +1,56 @@
+//! Agent side of the IPC transport served by [`IpcServer`](super::server::IpcServer).
+
+use std::path::Path;
+
+use tokio::io::AsyncBufReadExt;
+use tokio::io::BufReader;
+use tokio::io::Lines;
+use tokio::io::ReadHalf;
+use tokio::io::WriteHalf;
+
+use super::IpcMessage;
+use super::IpcTransportError;
+use super::write_message;
+
+#[cfg(unix)]
+type IpcStream = tokio::net::UnixStream;
+#[cfg(windows)]
+type IpcStream = tokio::net::windows::named_pipe::NamedPipeClient;
+
+#[derive(Debug)]
+pub struct IpcClient {
+    lines: Lines<BufReader<ReadHalf<IpcStream>>>,
+    writer: WriteHalf<IpcStream>,
+}
+
+impl IpcClient {
+    /// Connects to the UI listening on the socket, or named pipe, at `path`.
+    pub async fn connect(path: impl AsRef<Path>) -> Result<Self, IpcTransportError> {
+        #[cfg(unix)]
+        let stream = tokio::net::UnixStream::connect(path.as_ref()).await?;
+        #[cfg(windows)]
+        let stream = tokio::net::windows::named_pipe::ClientOptions::new()
+            .open(path.as_ref().as_os_str())?;
+        let (reader, writer) = tokio::io::split(stream);
+        Ok(Self {
+            lines: BufReader::new(reader).lines(),
+            writer,
+        })
+    }
+
+    /// Sends an event for the UI to apply, like `ActionProposed` or `CommandStarted`.
+    pub async fn send(&mut self, message: &IpcMessage) -> Result<(), IpcTransportError> {
+        write_message(&mut self.writer, message).await
+    }
+
+    /// Next message from the UI, such as an `ApprovalResolved`; `None` once the UI closed the
+    /// connection.
+    pub async fn recv(&mut self) -> Result<Option<IpcMessage>, IpcTransportError> {
+        while let Some(line) = self.lines.next_line().await? {
+            if !line.trim().is_empty() {
+                return Ok(Some(serde_json::from_str(&line)?));
+            }
+        }
+        Ok(None)
+    }
+}
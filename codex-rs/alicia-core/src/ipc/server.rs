@@ -0,0 +1,282 @@
+//! UI side of the IPC transport: `IpcMessage`s as newline-delimited JSON over a Unix domain
+//! socket, or a named pipe such as `\\.\pipe\alicia` on Windows, so an agent running in
+//! another process can drive the UI and hear how its approvals were resolved.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWrite;
+use tokio::io::BufReader;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+use super::IpcMessage;
+use super::IpcTransportError;
+use super::write_message;
+
+/// Longest line a client may send; longer ones are skipped without being buffered whole.
+pub const MAX_IPC_LINE_BYTES: usize = 1024 * 1024;
+
+pub struct IpcServer {
+    path: PathBuf,
+    #[cfg(unix)]
+    listener: tokio::net::UnixListener,
+    #[cfg(windows)]
+    next_pipe: tokio::net::windows::named_pipe::NamedPipeServer,
+}
+
+impl std::fmt::Debug for IpcServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IpcServer")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl IpcServer {
+    /// Listens on `path`. A socket file left behind by a run that is gone is replaced; one
+    /// another process still answers on is `AddressInUse`, and anything else already at
+    /// `path` is `NotASocket` and left alone.
+    #[cfg(unix)]
+    pub fn bind(path: impl Into<PathBuf>) -> Result<Self, IpcTransportError> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let path = path.into();
+        if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+            if !metadata.file_type().is_socket() {
+                return Err(IpcTransportError::NotASocket(
+                    path.to_string_lossy().to_string(),
+                ));
+            }
+            if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+                return Err(IpcTransportError::AddressInUse(
+                    path.to_string_lossy().to_string(),
+                ));
+            }
+            std::fs::remove_file(&path)?;
+        }
+        let listener = tokio::net::UnixListener::bind(&path)?;
+        Ok(Self { path, listener })
+    }
+
+    /// Creates the first instance of the pipe named `path`; another process owning it is
+    /// `AddressInUse`.
+    #[cfg(windows)]
+    pub fn bind(path: impl Into<PathBuf>) -> Result<Self, IpcTransportError> {
+        let path = path.into();
+        let next_pipe = tokio::net::windows::named_pipe::ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&path)
+            .map_err(|error| match error.kind() {
+                std::io::ErrorKind::PermissionDenied => {
+                    IpcTransportError::AddressInUse(path.to_string_lossy().to_string())
+                }
+                _ => error.into(),
+            })?;
+        Ok(Self { path, next_pipe })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Accepts clients until the endpoint fails. Every message a client writes goes to
+    /// `incoming`; lines that are not an `IpcMessage` or are longer than
+    /// [`MAX_IPC_LINE_BYTES`] are skipped. Every message sent on
+    /// `outgoing` after a client connected is written to it.
+    #[cfg(unix)]
+    pub async fn serve(
+        self,
+        incoming: mpsc::Sender<IpcMessage>,
+        outgoing: broadcast::Sender<IpcMessage>,
+    ) -> Result<(), IpcTransportError> {
+        loop {
+            let (stream, _) = self.listener.accept().await?;
+            tokio::spawn(serve_connection(
+                stream,
+                incoming.clone(),
+                outgoing.subscribe(),
+            ));
+        }
+    }
+
+    #[cfg(windows)]
+    pub async fn serve(
+        mut self,
+        incoming: mpsc::Sender<IpcMessage>,
+        outgoing: broadcast::Sender<IpcMessage>,
+    ) -> Result<(), IpcTransportError> {
+        loop {
+            self.next_pipe.connect().await?;
+            let connected = std::mem::replace(
+                &mut self.next_pipe,
+                tokio::net::windows::named_pipe::ServerOptions::new().create(&self.path)?,
+            );
+            tokio::spawn(serve_connection(
+                connected,
+                incoming.clone(),
+                outgoing.subscribe(),
+            ));
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+async fn serve_connection<S>(
+    stream: S,
+    incoming: mpsc::Sender<IpcMessage>,
+    mut outgoing: broadcast::Receiver<IpcMessage>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+    // Bytes of the line being read; `read_until` keeps partial reads here when the other
+    // branch wins the select, so reading resumes where it stopped.
+    let mut line = Vec::new();
+    // Set while skipping the rest of a line past `MAX_IPC_LINE_BYTES`.
+    let mut oversized = false;
+    loop {
+        let limit = (MAX_IPC_LINE_BYTES + 1).saturating_sub(line.len()) as u64;
+        tokio::select! {
+            read = (&mut reader).take(limit).read_until(b'\n', &mut line) => {
+                let Ok(read) = read else {
+                    break;
+                };
+                let complete = line.ends_with(b"\n");
+                if read == 0 || complete {
+                    let parsed = (!oversized)
+                        .then(|| serde_json::from_slice::<IpcMessage>(&line).ok())
+                        .flatten();
+                    line.clear();
+                    oversized = false;
+                    if let Some(message) = parsed
+                        && incoming.send(message).await.is_err()
+                    {
+                        break;
+                    }
+                    if read == 0 {
+                        break;
+                    }
+                } else if line.len() > MAX_IPC_LINE_BYTES {
+                    line.clear();
+                    oversized = true;
+                }
+            }
+            message = outgoing.recv() => match message {
+                Ok(message) => {
+                    if write_message(&mut writer, &message).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tokio::io::AsyncWriteExt;
+    use tokio::sync::broadcast;
+    use tokio::sync::mpsc;
+
+    use super::IpcServer;
+    use super::MAX_IPC_LINE_BYTES;
+    use crate::ApprovalResolution;
+    use crate::IpcEvent;
+    use crate::IpcMessage;
+    use crate::IpcTransportError;
+    use crate::ipc::ActionProposed;
+    use crate::ipc::ApprovalResolved;
+    use crate::ipc::client::IpcClient;
+    use crate::policy::ActionKind;
+
+    #[tokio::test]
+    async fn clients_send_events_and_receive_resolutions() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("alicia.sock");
+        let server = IpcServer::bind(&path)?;
+        assert!(matches!(
+            IpcServer::bind(&path),
+            Err(IpcTransportError::AddressInUse(_))
+        ));
+        let (incoming_tx, mut incoming_rx) = mpsc::channel(8);
+        let (outgoing_tx, _) = broadcast::channel(8);
+        let serving = tokio::spawn(server.serve(incoming_tx, outgoing_tx.clone()));
+
+        let mut client = IpcClient::connect(&path).await?;
+        let proposed = IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+            action_id: "act-1".to_string(),
+            action_kind: ActionKind::ExecuteCommand,
+            target: "cargo test".to_string(),
+        }));
+        client.send(&proposed).await?;
+        assert_eq!(incoming_rx.recv().await, Some(proposed));
+
+        let resolved = IpcMessage::new(IpcEvent::ApprovalResolved(ApprovalResolved {
+            action_id: "act-1".to_string(),
+            resolution: ApprovalResolution::Approved,
+            reason: None,
+            approver: None,
+        }));
+        outgoing_tx.send(resolved.clone())?;
+        assert_eq!(client.recv().await?, Some(resolved));
+
+        serving.abort();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn oversized_lines_are_skipped() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("alicia.sock");
+        let server = IpcServer::bind(&path)?;
+        let (incoming_tx, mut incoming_rx) = mpsc::channel(8);
+        let (outgoing_tx, _) = broadcast::channel(8);
+        let serving = tokio::spawn(server.serve(incoming_tx, outgoing_tx));
+
+        let proposed = IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+            action_id: "act-1".to_string(),
+            action_kind: ActionKind::ExecuteCommand,
+            target: "cargo test".to_string(),
+        }));
+        let mut stream = tokio::net::UnixStream::connect(&path).await?;
+        let mut payload = vec![b'x'; MAX_IPC_LINE_BYTES + 16];
+        payload.push(b'\n');
+        payload.extend(serde_json::to_vec(&proposed)?);
+        payload.push(b'\n');
+        stream.write_all(&payload).await?;
+
+        assert_eq!(incoming_rx.recv().await, Some(proposed));
+        serving.abort();
+        Ok(())
+    }
+
+    #[test]
+    fn bind_leaves_files_that_are_not_sockets() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "importante")?;
+
+        assert!(matches!(
+            IpcServer::bind(&path),
+            Err(IpcTransportError::NotASocket(_))
+        ));
+        assert_eq!(std::fs::read_to_string(&path)?, "importante");
+        Ok(())
+    }
+}
@@ -1,19 +1,53 @@
+pub mod approval_rules;
 pub mod audit;
+pub mod audit_encryption;
+pub mod command_classifier;
+#[cfg(feature = "test-util")]
+pub mod fake_session;
 pub mod ipc;
+pub mod named_profile;
+pub mod network_isolation;
 pub mod policy;
 pub mod policy_bridge;
 pub mod project_policy;
 pub mod session;
+pub mod session_env;
+pub mod shell_command;
+#[cfg(feature = "proptest")]
+pub mod strategies;
 
+pub use approval_rules::ApprovalRule;
+pub use approval_rules::ApprovalRuleSet;
 pub use audit::ApprovalDecision;
 pub use audit::AuditLogger;
 pub use audit::AuditRecord;
 pub use audit::ResultStatus;
+pub use audit_encryption::AUDIT_KEY_ENV_VAR;
+pub use audit_encryption::AUDIT_KEYRING_SERVICE;
+pub use audit_encryption::AuditEncryption;
+pub use audit_encryption::AuditEncryptionError;
+pub use audit_encryption::ENCRYPTED_AUDIT_LINE_PREFIX;
+pub use command_classifier::classify_command;
 pub use ipc::ApprovalResolution;
+pub use ipc::ApproverIdentity;
+pub use ipc::ApproverSource;
 pub use ipc::CommandOutputStream;
+pub use ipc::DenialCategory;
+pub use ipc::DenialReason;
 pub use ipc::IPC_PROTOCOL_VERSION;
 pub use ipc::IpcEvent;
 pub use ipc::IpcMessage;
+pub use ipc::IpcTransportError;
+pub use named_profile::CommandRule;
+pub use named_profile::NamedProfile;
+pub use named_profile::PathRule;
+pub use named_profile::ProfileDefinition;
+pub use network_isolation::BLACKHOLE_PROXY_URL;
+pub use network_isolation::NetworkIsolation;
+pub use network_isolation::available_network_isolation;
+pub use network_isolation::detect_network_violation;
+pub use network_isolation::isolate_network;
+pub use network_isolation::network_namespace_available;
 pub use policy::ActionKind;
 pub use policy::POLICY_CONTRACT_VERSION;
 pub use policy::PermissionProfile;
@@ -22,8 +56,11 @@ pub use policy_bridge::EffectiveRuntimePolicy;
 pub use policy_bridge::PolicyBridgeError;
 pub use policy_bridge::WorkspaceGuardResult;
 pub use policy_bridge::ensure_target_in_workspace;
+pub use policy_bridge::ensure_target_in_workspace_roots;
 pub use policy_bridge::map_profile_to_runtime_policy;
 pub use policy_bridge::network_decision_for_profile;
+pub use project_policy::ApprovalValidity;
+pub use project_policy::AuditFieldSource;
 pub use project_policy::PROJECT_POLICY_RELATIVE_PATH;
 pub use project_policy::PROJECT_POLICY_SCHEMA_VERSION;
 pub use project_policy::ProjectPolicyConfig;
@@ -33,9 +70,17 @@ pub use project_policy::project_policy_file_path;
 pub use project_policy::resolve_effective_network_decision;
 pub use project_policy::resolve_effective_profile;
 pub use project_policy::resolve_effective_runtime_policy;
+pub use project_policy::save_project_policy;
+pub use session::DEFAULT_SESSION_EVENTS_CAPACITY;
 pub use session::ReattachedSession;
 pub use session::SessionAuditContext;
 pub use session::SessionManager;
 pub use session::SessionManagerError;
 pub use session::SessionMode;
 pub use session::SessionStartRequest;
+pub use session_env::EnvEdits;
+pub use session_env::is_secret_env_var;
+pub use session_env::is_valid_env_var_name;
+pub use shell_command::ShellCommand;
+pub use shell_command::ShellCommandError;
+pub use shell_command::ShellKind;
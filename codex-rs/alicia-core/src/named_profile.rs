@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ActionKind;
+use crate::PermissionProfile;
+use crate::PolicyDecision;
+
+/// A profile declared under `[profiles.<name>]` in the project policy file. Actions it does
+/// not decide fall back to `base`, which defaults to the file's `permission_profile`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileDefinition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<PermissionProfile>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub decisions: BTreeMap<ActionKind, PolicyDecision>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<CommandRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<PathRule>,
+}
+
+/// Decides every command whose leading words are `prefix`, e.g. `git push`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandRule {
+    pub prefix: String,
+    pub decision: PolicyDecision,
+}
+
+/// Decides the file actions on `path` and everything under it. Both the rule and the action's
+/// path are taken relative to the workspace root before comparing, so `./src`, `src` and
+/// `<root>/src` are the same path.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathRule {
+    pub path: PathBuf,
+    pub decision: PolicyDecision,
+}
+
+/// The profile decisions are taken from: one of the built-in `PermissionProfile`s, or a
+/// profile from the project policy file composed on top of one. Within `commands` and
+/// `paths` the first matching rule wins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedProfile {
+    pub name: String,
+    pub base: PermissionProfile,
+    pub decisions: BTreeMap<ActionKind, PolicyDecision>,
+    pub commands: Vec<CommandRule>,
+    pub paths: Vec<PathRule>,
+}
+
+impl NamedProfile {
+    pub fn builtin(profile: PermissionProfile) -> Self {
+        Self {
+            name: profile.name().to_string(),
+            base: profile,
+            decisions: BTreeMap::new(),
+            commands: Vec::new(),
+            paths: Vec::new(),
+        }
+    }
+
+    pub fn from_definition(
+        name: impl Into<String>,
+        definition: &ProfileDefinition,
+        default_base: PermissionProfile,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            base: definition.base.unwrap_or(default_base),
+            decisions: definition.decisions.clone(),
+            commands: definition.commands.clone(),
+            paths: definition.paths.clone(),
+        }
+    }
+
+    /// Name to record next to `base` for profiles from the policy file; `None` when the
+    /// profile is just its built-in base.
+    pub fn custom_name(&self) -> Option<&str> {
+        let builtin = self.name == self.base.name()
+            && self.decisions.is_empty()
+            && self.commands.is_empty()
+            && self.paths.is_empty();
+        (!builtin).then_some(self.name.as_str())
+    }
+
+    pub fn decision_for(&self, action: ActionKind) -> PolicyDecision {
+        self.decisions
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| self.base.decision_for(action))
+    }
+
+    /// Decision of the first command rule matching `command`, which then covers every action
+    /// the command performs.
+    pub fn command_rule_decision(&self, command: &[String]) -> Option<PolicyDecision> {
+        self.commands
+            .iter()
+            .find(|rule| {
+                let prefix: Vec<&str> = rule.prefix.split_whitespace().collect();
+                !prefix.is_empty()
+                    && command.len() >= prefix.len()
+                    && prefix
+                        .iter()
+                        .zip(command)
+                        .all(|(word, token)| word == token)
+            })
+            .map(|rule| rule.decision)
+    }
+
+    pub fn decision_for_path(
+        &self,
+        workspace_root: &Path,
+        path: &Path,
+        action: ActionKind,
+    ) -> PolicyDecision {
+        let file_action = matches!(
+            action,
+            ActionKind::ReadFile | ActionKind::WriteFile | ActionKind::ApplyPatch
+        );
+        let path = workspace_relative(workspace_root, path);
+        self.paths
+            .iter()
+            .find(|rule| {
+                file_action && path.starts_with(workspace_relative(workspace_root, &rule.path))
+            })
+            .map_or_else(|| self.decision_for(action), |rule| rule.decision)
+    }
+}
+
+/// `path` relative to `workspace_root` with `.` and `..` resolved lexically. A `..` that climbs
+/// above the root is kept, so such paths match no rule.
+fn workspace_relative(workspace_root: &Path, path: &Path) -> PathBuf {
+    let path = path.strip_prefix(workspace_root).unwrap_or(path);
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use super::CommandRule;
+    use super::NamedProfile;
+    use super::PathRule;
+    use super::ProfileDefinition;
+    use crate::ActionKind;
+    use crate::PermissionProfile;
+    use crate::PolicyDecision;
+
+    #[test]
+    fn custom_profiles_override_their_base_per_action_command_and_path() {
+        let definition = ProfileDefinition {
+            base: None,
+            decisions: BTreeMap::from([(ActionKind::ExecuteCommand, PolicyDecision::Allow)]),
+            commands: vec![CommandRule {
+                prefix: "git push".to_string(),
+                decision: PolicyDecision::Deny,
+            }],
+            paths: vec![PathRule {
+                path: PathBuf::from("src/generated"),
+                decision: PolicyDecision::Deny,
+            }],
+        };
+        let profile = NamedProfile::from_definition(
+            "ci",
+            &definition,
+            PermissionProfile::ReadWriteWithApproval,
+        );
+
+        assert_eq!(profile.custom_name(), Some("ci"));
+        assert_eq!(
+            profile.decision_for(ActionKind::ExecuteCommand),
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            profile.decision_for(ActionKind::NetworkAccess),
+            PolicyDecision::RequireApproval
+        );
+        let command = |line: &str| line.split(' ').map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            profile.command_rule_decision(&command("git push origin main")),
+            Some(PolicyDecision::Deny)
+        );
+        assert_eq!(profile.command_rule_decision(&command("git pull")), None);
+        let root = Path::new("/work/app");
+        for path in [
+            "src/generated/api.rs",
+            "./src/generated/api.rs",
+            "/work/app/src/generated/api.rs",
+            "src/other/../generated/api.rs",
+        ] {
+            assert_eq!(
+                profile.decision_for_path(root, Path::new(path), ActionKind::ApplyPatch),
+                PolicyDecision::Deny,
+                "{path}"
+            );
+        }
+        for path in ["src/generated_old.rs", "../src/generated/api.rs"] {
+            assert_eq!(
+                profile.decision_for_path(root, Path::new(path), ActionKind::ApplyPatch),
+                PolicyDecision::RequireApproval,
+                "{path}"
+            );
+        }
+        assert_eq!(
+            NamedProfile::builtin(PermissionProfile::FullAccess).custom_name(),
+            None
+        );
+    }
+}
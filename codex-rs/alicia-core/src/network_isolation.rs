@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Discard port on loopback: connections through the proxy fail immediately.
+pub const BLACKHOLE_PROXY_URL: &str = "http://127.0.0.1:9";
+/// How tools print the blackhole proxy when they fail to reach it; only a violation when
+/// the port is not followed by another digit (`127.0.0.1:9000` is some local server).
+const BLACKHOLE_PROXY_MENTIONS: [&str; 2] = ["127.0.0.1:9", "127.0.0.1 port 9"];
+
+const PROXY_ENV_VARS: [&str; 6] = [
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+];
+const OFFLINE_ENV_VARS: [(&str, &str); 4] = [
+    ("CARGO_NET_OFFLINE", "true"),
+    ("npm_config_offline", "true"),
+    ("PIP_NO_INDEX", "1"),
+    ("GOPROXY", "off"),
+];
+/// Lowercased output fragments printed by common tools when a connection is refused or a
+/// name does not resolve, which is what isolated sessions see when they try the network.
+/// Longest unterminated line kept between output chunks; past it the partial line is
+/// checked as is and dropped.
+const MAX_PENDING_LINE_BYTES: usize = 8 * 1024;
+const NETWORK_VIOLATION_MARKERS: [&str; 7] = [
+    "could not resolve host",
+    "temporary failure in name resolution",
+    "network is unreachable",
+    "name or service not known",
+    "getaddrinfo enotfound",
+    "getaddrinfo failed",
+    "nodename nor servname provided",
+];
+
+/// How a session launched while the network decision is `Deny` is kept offline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkIsolation {
+    /// Fresh network namespace with only loopback (`unshare --user --net`).
+    Namespace,
+    /// Proxy variables point at a closed loopback port and package managers run offline.
+    /// Programs that ignore proxies can still reach the network.
+    ProxyBlackhole,
+}
+
+impl NetworkIsolation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Namespace => "namespace",
+            Self::ProxyBlackhole => "proxy_blackhole",
+        }
+    }
+
+    /// Whether every program is kept offline, not just the ones that honor proxies.
+    pub fn is_enforced(self) -> bool {
+        matches!(self, Self::Namespace)
+    }
+}
+
+/// The isolation [`isolate_network`] applies on this machine.
+pub fn available_network_isolation() -> NetworkIsolation {
+    if network_namespace_available() {
+        NetworkIsolation::Namespace
+    } else {
+        NetworkIsolation::ProxyBlackhole
+    }
+}
+
+/// Whether unprivileged network namespaces work here. Probed once by running
+/// `unshare --user --map-root-user --net true`; always false outside Linux.
+pub fn network_namespace_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        cfg!(target_os = "linux")
+            && Command::new("unshare")
+                .args(["--user", "--map-root-user", "--net", "true"])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+    })
+}
+
+/// Rewrites a launch so the process runs offline. Proxy and offline variables are always set
+/// in `env`; when namespaces are available the program is also wrapped in `unshare`.
+/// Returns the program and arguments to spawn.
+pub fn isolate_network(
+    program: &str,
+    args: &[String],
+    env: &mut HashMap<String, String>,
+) -> (String, Vec<String>, NetworkIsolation) {
+    for variable in PROXY_ENV_VARS {
+        env.insert(variable.to_string(), BLACKHOLE_PROXY_URL.to_string());
+    }
+    env.insert("NO_PROXY".to_string(), String::new());
+    env.insert("no_proxy".to_string(), String::new());
+    for (variable, value) in OFFLINE_ENV_VARS {
+        env.insert(variable.to_string(), value.to_string());
+    }
+
+    if !network_namespace_available() {
+        return (
+            program.to_string(),
+            args.to_vec(),
+            NetworkIsolation::ProxyBlackhole,
+        );
+    }
+    let mut wrapped_args = vec![
+        "--user".to_string(),
+        "--map-root-user".to_string(),
+        "--net".to_string(),
+        "--".to_string(),
+        program.to_string(),
+    ];
+    wrapped_args.extend(args.iter().cloned());
+    (
+        "unshare".to_string(),
+        wrapped_args,
+        NetworkIsolation::Namespace,
+    )
+}
+
+/// First line of `output` that reports a refused network attempt.
+pub fn detect_network_violation(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find(|line| {
+            let line = line.to_ascii_lowercase();
+            NETWORK_VIOLATION_MARKERS
+                .iter()
+                .any(|marker| line.contains(marker))
+                || mentions_blackhole_proxy(&line)
+        })
+        .map(|line| line.trim().to_string())
+}
+
+/// Matches session output against the violation markers one whole line at a time, so a
+/// marker split across output chunks is still found and a port cut off mid-chunk
+/// (`127.0.0.1:9` then `000`) is not mistaken for the blackhole proxy.
+#[derive(Debug, Default)]
+pub(crate) struct NetworkViolationScanner {
+    pending: String,
+}
+
+impl NetworkViolationScanner {
+    /// First violation among the lines `chunk` completes; the unterminated tail waits for
+    /// the next chunk.
+    pub(crate) fn feed(&mut self, chunk: &str) -> Option<String> {
+        self.pending.push_str(chunk);
+        let mut violation = None;
+        if let Some(end) = self.pending.rfind('\n') {
+            let tail = self.pending.split_off(end + 1);
+            violation = detect_network_violation(&self.pending);
+            self.pending = tail;
+        }
+        if self.pending.len() > MAX_PENDING_LINE_BYTES {
+            violation = violation.or_else(|| detect_network_violation(&self.pending));
+            self.pending.clear();
+        }
+        violation
+    }
+
+    /// Checks the last line once the output ends without a newline.
+    pub(crate) fn finish(&mut self) -> Option<String> {
+        let violation = detect_network_violation(&self.pending);
+        self.pending.clear();
+        violation
+    }
+}
+
+fn mentions_blackhole_proxy(line: &str) -> bool {
+    BLACKHOLE_PROXY_MENTIONS.iter().any(|mention| {
+        line.match_indices(mention).any(|(start, _)| {
+            !line[start + mention.len()..].starts_with(|next: char| next.is_ascii_digit())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use super::BLACKHOLE_PROXY_URL;
+    use super::NetworkIsolation;
+    use super::NetworkViolationScanner;
+    use super::detect_network_violation;
+    use super::isolate_network;
+
+    #[test]
+    fn isolation_blackholes_proxies_and_reports_refused_connections() {
+        let mut env = HashMap::new();
+        let (program, args, isolation) =
+            isolate_network("curl", &["https://example.com".to_string()], &mut env);
+
+        assert_eq!(
+            env.get("HTTPS_PROXY").map(String::as_str),
+            Some(BLACKHOLE_PROXY_URL)
+        );
+        assert_eq!(
+            env.get("CARGO_NET_OFFLINE").map(String::as_str),
+            Some("true")
+        );
+        match isolation {
+            NetworkIsolation::Namespace => {
+                assert_eq!(program, "unshare");
+                assert_eq!(args[3..], ["--", "curl", "https://example.com"]);
+            }
+            NetworkIsolation::ProxyBlackhole => {
+                assert_eq!(program, "curl");
+                assert_eq!(args, vec!["https://example.com".to_string()]);
+            }
+        }
+
+        assert_eq!(
+            detect_network_violation("  % Total\ncurl: (6) Could not resolve host: example.com\n"),
+            Some("curl: (6) Could not resolve host: example.com".to_string())
+        );
+        assert_eq!(
+            detect_network_violation(
+                "curl: (7) Failed to connect to 127.0.0.1 port 9 after 0 ms: Couldn't connect"
+            ),
+            Some(
+                "curl: (7) Failed to connect to 127.0.0.1 port 9 after 0 ms: Couldn't connect"
+                    .to_string()
+            )
+        );
+        assert_eq!(detect_network_violation("test result: ok"), None);
+        assert_eq!(
+            detect_network_violation("Listening on http://127.0.0.1:9000"),
+            None
+        );
+        assert_eq!(
+            detect_network_violation("warning: unused import `std::net::getaddrinfo`"),
+            None
+        );
+    }
+
+    #[test]
+    fn scanner_matches_markers_on_whole_lines_across_chunks() {
+        let mut scanner = NetworkViolationScanner::default();
+        assert_eq!(scanner.feed("curl: (6) Could not res"), None);
+        assert_eq!(
+            scanner.feed("olve host: example.com\nnext"),
+            Some("curl: (6) Could not resolve host: example.com".to_string())
+        );
+
+        let mut scanner = NetworkViolationScanner::default();
+        assert_eq!(scanner.feed("Listening on http://127.0.0.1:9"), None);
+        assert_eq!(scanner.feed("000\n"), None);
+
+        let mut scanner = NetworkViolationScanner::default();
+        assert_eq!(scanner.feed("ping: example.com: Name or service"), None);
+        assert_eq!(
+            scanner.feed(" not known"),
+            None,
+            "an unterminated line waits for more output"
+        );
+        assert_eq!(
+            scanner.finish(),
+            Some("ping: example.com: Name or service not known".to_string())
+        );
+    }
+}
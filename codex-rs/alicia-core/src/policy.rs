@@ -3,7 +3,7 @@ use serde::Serialize;
 
 pub const POLICY_CONTRACT_VERSION: &str = "v1";
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ActionKind {
     ReadFile,
@@ -30,6 +30,25 @@ pub enum PermissionProfile {
 }
 
 impl PermissionProfile {
+    pub const ALL: [Self; 3] = [
+        Self::ReadOnly,
+        Self::ReadWriteWithApproval,
+        Self::FullAccess,
+    ];
+
+    /// Name used for the profile in the policy file and in audit records.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ReadOnly => "read_only",
+            Self::ReadWriteWithApproval => "read_write_with_approval",
+            Self::FullAccess => "full_access",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|profile| profile.name() == name)
+    }
+
     pub fn decision_for(self, action: ActionKind) -> PolicyDecision {
         match self {
             Self::ReadOnly => match action {
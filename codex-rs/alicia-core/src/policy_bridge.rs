@@ -40,6 +40,8 @@ pub enum PolicyBridgeError {
         "target `{target}` is outside workspace `{workspace}` and was denied by workspace guard"
     )]
     TargetOutsideWorkspace { workspace: String, target: String },
+    #[error("no workspace root configured")]
+    NoWorkspaceRoots,
 }
 
 pub fn map_profile_to_runtime_policy(profile: PermissionProfile) -> EffectiveRuntimePolicy {
@@ -98,6 +100,56 @@ pub fn ensure_target_in_workspace(
     })
 }
 
+/// Multi-root variant of [`ensure_target_in_workspace`]. Relative targets resolve against the
+/// first root; the result names the deepest root containing the target, so nested roots
+/// govern their own subtree.
+pub fn ensure_target_in_workspace_roots(
+    workspace_roots: &[PathBuf],
+    target: &Path,
+) -> Result<WorkspaceGuardResult, PolicyBridgeError> {
+    let mut canonical_roots = Vec::with_capacity(workspace_roots.len());
+    for workspace_root in workspace_roots {
+        let canonical_root = std::fs::canonicalize(workspace_root).map_err(|source| {
+            PolicyBridgeError::WorkspaceCanonicalizationFailed {
+                workspace: workspace_root.to_string_lossy().to_string(),
+                source,
+            }
+        })?;
+        canonical_roots.push(canonical_root);
+    }
+    let Some(primary_root) = canonical_roots.first() else {
+        return Err(PolicyBridgeError::NoWorkspaceRoots);
+    };
+    let candidate = resolve_candidate_path(primary_root, target);
+    let canonical_target = canonicalize_with_missing_suffix(&candidate).map_err(|source| {
+        PolicyBridgeError::TargetCanonicalizationFailed {
+            target: candidate.to_string_lossy().to_string(),
+            source,
+        }
+    })?;
+
+    let Some(canonical_workspace) = canonical_roots
+        .iter()
+        .filter(|root| canonical_target.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .cloned()
+    else {
+        return Err(PolicyBridgeError::TargetOutsideWorkspace {
+            workspace: canonical_roots
+                .iter()
+                .map(|root| root.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            target: canonical_target.to_string_lossy().to_string(),
+        });
+    };
+
+    Ok(WorkspaceGuardResult {
+        canonical_workspace,
+        canonical_target,
+    })
+}
+
 fn resolve_candidate_path(workspace_root: &Path, target: &Path) -> PathBuf {
     let normalized = normalize_path(target);
     if normalized.is_absolute() {
@@ -167,6 +219,7 @@ mod tests {
 
     use super::PolicyBridgeError;
     use super::ensure_target_in_workspace;
+    use super::ensure_target_in_workspace_roots;
     use super::map_profile_to_runtime_policy;
     use super::network_decision_for_profile;
     use crate::PermissionProfile;
@@ -255,6 +308,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn multi_root_guard_picks_the_deepest_root_containing_the_target() -> anyhow::Result<()> {
+        let root = TempDir::new()?;
+        let service_a = root.path().join("service-a");
+        let service_b = root.path().join("service-b");
+        let vendored = service_a.join("vendor/lib");
+        std::fs::create_dir_all(&service_b)?;
+        std::fs::create_dir_all(&vendored)?;
+        let roots = vec![service_a.clone(), service_b.clone(), vendored.clone()];
+
+        let relative = ensure_target_in_workspace_roots(&roots, &PathBuf::from("src/main.rs"))?;
+        assert_eq!(relative.canonical_workspace, service_a.canonicalize()?);
+
+        let in_b = ensure_target_in_workspace_roots(&roots, &service_b.join("Cargo.toml"))?;
+        assert_eq!(in_b.canonical_workspace, service_b.canonicalize()?);
+
+        let nested = ensure_target_in_workspace_roots(&roots, &vendored.join("lib.rs"))?;
+        assert_eq!(nested.canonical_workspace, vendored.canonicalize()?);
+
+        assert!(matches!(
+            ensure_target_in_workspace_roots(&roots, &root.path().join("other")),
+            Err(PolicyBridgeError::TargetOutsideWorkspace { .. })
+        ));
+        assert!(matches!(
+            ensure_target_in_workspace_roots(&[], &service_a),
+            Err(PolicyBridgeError::NoWorkspaceRoots)
+        ));
+        Ok(())
+    }
+
     #[test]
     fn workspace_guard_blocks_symlink_escape() -> anyhow::Result<()> {
         let root = TempDir::new()?;
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -5,11 +6,14 @@ use serde::Deserialize;
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::ActionKind;
+use crate::ApprovalRuleSet;
 use crate::EffectiveRuntimePolicy;
+use crate::NamedProfile;
 use crate::PermissionProfile;
 use crate::PolicyDecision;
+use crate::ProfileDefinition;
 use crate::map_profile_to_runtime_policy;
-use crate::network_decision_for_profile;
 
 pub const PROJECT_POLICY_RELATIVE_PATH: &str = ".codex/alicia-policy.toml";
 pub const PROJECT_POLICY_SCHEMA_VERSION: u32 = 1;
@@ -20,6 +24,156 @@ pub struct ProjectPolicyConfig {
     #[serde(default = "project_policy_schema_version")]
     pub schema_version: u32,
     pub permission_profile: PermissionProfile,
+    /// Profile to use instead of `permission_profile`: a key of `profiles` or a built-in name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileDefinition>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub audit_fields: BTreeMap<String, AuditFieldSource>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub approval_validity: BTreeMap<ActionKind, ApprovalValidity>,
+    #[serde(default, skip_serializing_if = "ApprovalRuleSet::is_empty")]
+    pub approval_rules: ApprovalRuleSet,
+}
+
+/// How long a granted approval keeps covering new executions of the same action. The default
+/// keeps it valid for the rest of the session and reusable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApprovalValidity {
+    /// Seconds after the approval was granted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_s: Option<u64>,
+    /// Every execution needs its own approval.
+    #[serde(default)]
+    pub single_use: bool,
+}
+
+impl ApprovalValidity {
+    /// Whether an approval granted at `granted_at_unix_s` and already used `uses` times still
+    /// covers an execution at `now_unix_s`.
+    pub fn covers(&self, granted_at_unix_s: i64, uses: u32, now_unix_s: i64) -> bool {
+        if self.single_use && uses > 0 {
+            return false;
+        }
+        self.ttl_s.is_none_or(|ttl_s| {
+            let ttl_s = i64::try_from(ttl_s).unwrap_or(i64::MAX);
+            now_unix_s < granted_at_unix_s.saturating_add(ttl_s)
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditFieldSource {
+    Value(String),
+    Env(String),
+}
+
+impl ProjectPolicyConfig {
+    pub fn new(permission_profile: PermissionProfile) -> Self {
+        Self {
+            schema_version: PROJECT_POLICY_SCHEMA_VERSION,
+            permission_profile,
+            profile: None,
+            profiles: BTreeMap::new(),
+            audit_fields: BTreeMap::new(),
+            approval_validity: BTreeMap::new(),
+            approval_rules: ApprovalRuleSet::default(),
+        }
+    }
+
+    /// What `load_project_policy` and `save_project_policy` check beyond the TOML schema;
+    /// `path` only names the file in the errors.
+    pub fn validate(&self, path: &Path) -> Result<(), ProjectPolicyConfigError> {
+        let path = path.to_string_lossy().to_string();
+        if self.schema_version != PROJECT_POLICY_SCHEMA_VERSION {
+            return Err(ProjectPolicyConfigError::UnsupportedSchemaVersion {
+                path,
+                expected: PROJECT_POLICY_SCHEMA_VERSION,
+                found: self.schema_version,
+            });
+        }
+        if let Some(name) = self.profile.as_ref()
+            && self.resolve_profile().is_none()
+        {
+            return Err(ProjectPolicyConfigError::UnknownProfile {
+                path,
+                name: name.clone(),
+            });
+        }
+        for (name, definition) in &self.profiles {
+            let reason = if name.trim().is_empty() {
+                Some("profile name is empty")
+            } else if definition
+                .commands
+                .iter()
+                .any(|rule| rule.prefix.trim().is_empty())
+            {
+                Some("command rule has an empty prefix")
+            } else if definition
+                .paths
+                .iter()
+                .any(|rule| rule.path.as_os_str().is_empty())
+            {
+                Some("path rule has an empty path")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(ProjectPolicyConfigError::InvalidProfile {
+                    path,
+                    name: name.clone(),
+                    reason: reason.to_string(),
+                });
+            }
+        }
+        if let Some(rule) = self
+            .approval_rules
+            .rules
+            .iter()
+            .find(|rule| rule.pattern.trim().is_empty())
+        {
+            return Err(ProjectPolicyConfigError::InvalidApprovalRule {
+                path,
+                pattern: rule.pattern.clone(),
+                reason: "pattern is empty".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The profile selected by `profile`, or the built-in `permission_profile`. `None` when
+    /// `profile` names neither a defined nor a built-in profile.
+    pub fn resolve_profile(&self) -> Option<NamedProfile> {
+        let Some(name) = self.profile.as_deref() else {
+            return Some(NamedProfile::builtin(self.permission_profile));
+        };
+        match self.profiles.get(name) {
+            Some(definition) => Some(NamedProfile::from_definition(
+                name,
+                definition,
+                self.permission_profile,
+            )),
+            None => PermissionProfile::from_name(name).map(NamedProfile::builtin),
+        }
+    }
+
+    /// Resolves the configured audit fields; env-backed fields whose variable is unset are
+    /// omitted rather than recorded as empty strings.
+    pub fn resolve_audit_metadata(&self) -> BTreeMap<String, String> {
+        self.audit_fields
+            .iter()
+            .filter_map(|(name, source)| {
+                let value = match source {
+                    AuditFieldSource::Value(value) => value.clone(),
+                    AuditFieldSource::Env(variable) => std::env::var(variable).ok()?,
+                };
+                Some((name.clone(), value))
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Error)]
@@ -44,6 +198,32 @@ pub enum ProjectPolicyConfigError {
         expected: u32,
         found: u32,
     },
+    #[error("unknown permission profile `{name}` in project policy file `{path}`")]
+    UnknownProfile { path: String, name: String },
+    #[error("invalid permission profile `{name}` in project policy file `{path}`: {reason}")]
+    InvalidProfile {
+        path: String,
+        name: String,
+        reason: String,
+    },
+    #[error("invalid approval rule `{pattern}` in project policy file `{path}`: {reason}")]
+    InvalidApprovalRule {
+        path: String,
+        pattern: String,
+        reason: String,
+    },
+    #[error("failed to serialize project policy file `{path}`: {source}")]
+    SerializeFailed {
+        path: String,
+        #[source]
+        source: toml::ser::Error,
+    },
+    #[error("failed to write project policy file `{path}`: {source}")]
+    WriteFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
 }
 
 pub fn project_policy_file_path(workspace_root: &Path) -> PathBuf {
@@ -71,23 +251,42 @@ pub fn load_project_policy(
             source,
         })?;
 
-    if config.schema_version != PROJECT_POLICY_SCHEMA_VERSION {
-        return Err(ProjectPolicyConfigError::UnsupportedSchemaVersion {
+    config.validate(&config_path)?;
+    Ok(Some(config))
+}
+
+/// Validates `config` and writes it to the workspace's policy file, creating `.codex` when
+/// needed. Returns the path written.
+pub fn save_project_policy(
+    workspace_root: &Path,
+    config: &ProjectPolicyConfig,
+) -> Result<PathBuf, ProjectPolicyConfigError> {
+    let config_path = project_policy_file_path(workspace_root);
+    config.validate(&config_path)?;
+    let contents =
+        toml::to_string(config).map_err(|source| ProjectPolicyConfigError::SerializeFailed {
             path: config_path.to_string_lossy().to_string(),
-            expected: PROJECT_POLICY_SCHEMA_VERSION,
-            found: config.schema_version,
-        });
+            source,
+        })?;
+    let write_failed = |source| ProjectPolicyConfigError::WriteFailed {
+        path: config_path.to_string_lossy().to_string(),
+        source,
+    };
+    if let Some(parent) = config_path.parent() {
+        std::fs::create_dir_all(parent).map_err(write_failed)?;
     }
-
-    Ok(Some(config))
+    std::fs::write(&config_path, contents).map_err(write_failed)?;
+    Ok(config_path)
 }
 
 pub fn resolve_effective_profile(
     workspace_root: &Path,
     fallback_profile: PermissionProfile,
-) -> Result<PermissionProfile, ProjectPolicyConfigError> {
+) -> Result<NamedProfile, ProjectPolicyConfigError> {
     let override_config = load_project_policy(workspace_root)?;
-    Ok(override_config.map_or(fallback_profile, |config| config.permission_profile))
+    Ok(override_config
+        .and_then(|config| config.resolve_profile())
+        .unwrap_or_else(|| NamedProfile::builtin(fallback_profile)))
 }
 
 pub fn resolve_effective_runtime_policy(
@@ -95,7 +294,7 @@ pub fn resolve_effective_runtime_policy(
     fallback_profile: PermissionProfile,
 ) -> Result<EffectiveRuntimePolicy, ProjectPolicyConfigError> {
     let effective_profile = resolve_effective_profile(workspace_root, fallback_profile)?;
-    Ok(map_profile_to_runtime_policy(effective_profile))
+    Ok(map_profile_to_runtime_policy(effective_profile.base))
 }
 
 pub fn resolve_effective_network_decision(
@@ -103,7 +302,7 @@ pub fn resolve_effective_network_decision(
     fallback_profile: PermissionProfile,
 ) -> Result<PolicyDecision, ProjectPolicyConfigError> {
     let effective_profile = resolve_effective_profile(workspace_root, fallback_profile)?;
-    Ok(network_decision_for_profile(effective_profile))
+    Ok(effective_profile.decision_for(ActionKind::NetworkAccess))
 }
 
 fn project_policy_schema_version() -> u32 {
@@ -112,9 +311,13 @@ fn project_policy_schema_version() -> u32 {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use pretty_assertions::assert_eq;
     use tempfile::TempDir;
 
+    use super::ApprovalValidity;
+    use super::AuditFieldSource;
     use super::PROJECT_POLICY_RELATIVE_PATH;
     use super::PROJECT_POLICY_SCHEMA_VERSION;
     use super::ProjectPolicyConfig;
@@ -123,8 +326,15 @@ mod tests {
     use super::resolve_effective_network_decision;
     use super::resolve_effective_profile;
     use super::resolve_effective_runtime_policy;
+    use super::save_project_policy;
+    use crate::ActionKind;
+    use crate::ApprovalRule;
+    use crate::ApprovalRuleSet;
+    use crate::CommandRule;
+    use crate::NamedProfile;
     use crate::PermissionProfile;
     use crate::PolicyDecision;
+    use crate::ProfileDefinition;
     use crate::map_profile_to_runtime_policy;
 
     fn write_project_policy_file(workspace: &TempDir, contents: &str) -> anyhow::Result<()> {
@@ -161,12 +371,93 @@ permission_profile = "read_write_with_approval"
         let expected = Some(ProjectPolicyConfig {
             schema_version: PROJECT_POLICY_SCHEMA_VERSION,
             permission_profile: PermissionProfile::ReadWriteWithApproval,
+            profile: None,
+            profiles: BTreeMap::new(),
+            audit_fields: BTreeMap::new(),
+            approval_validity: BTreeMap::new(),
+            approval_rules: ApprovalRuleSet::default(),
         });
         assert_eq!(loaded, expected);
 
         Ok(())
     }
 
+    #[test]
+    fn load_project_policy_reads_approval_validity_per_action_kind() -> anyhow::Result<()> {
+        let workspace = TempDir::new()?;
+        write_project_policy_file(
+            &workspace,
+            r#"
+permission_profile = "read_write_with_approval"
+
+[approval_validity.execute_command]
+ttl_s = 600
+single_use = true
+
+[approval_validity.network_access]
+ttl_s = 60
+"#,
+        )?;
+
+        let Some(loaded) = load_project_policy(workspace.path())? else {
+            anyhow::bail!("expected project policy to load");
+        };
+        let execute = loaded.approval_validity[&ActionKind::ExecuteCommand];
+        let network = loaded.approval_validity[&ActionKind::NetworkAccess];
+        assert_eq!(
+            network,
+            ApprovalValidity {
+                ttl_s: Some(60),
+                single_use: false,
+            }
+        );
+        assert!(execute.covers(1_000, 0, 1_599));
+        assert!(!execute.covers(1_000, 0, 1_600));
+        assert!(!execute.covers(1_000, 1, 1_001));
+        assert!(network.covers(1_000, 3, 1_059));
+        assert!(ApprovalValidity::default().covers(0, 10, i64::MAX));
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_project_policy_reads_audit_fields() -> anyhow::Result<()> {
+        let workspace = TempDir::new()?;
+        write_project_policy_file(
+            &workspace,
+            r#"
+permission_profile = "read_only"
+
+[audit_fields]
+cost_center = { value = "eng-42" }
+environment = { env = "ALICIA_TEST_AUDIT_ENV_THAT_IS_NOT_SET" }
+"#,
+        )?;
+
+        let Some(loaded) = load_project_policy(workspace.path())? else {
+            anyhow::bail!("expected project policy to load");
+        };
+        assert_eq!(
+            loaded.audit_fields,
+            BTreeMap::from([
+                (
+                    "cost_center".to_string(),
+                    AuditFieldSource::Value("eng-42".to_string())
+                ),
+                (
+                    "environment".to_string(),
+                    AuditFieldSource::Env("ALICIA_TEST_AUDIT_ENV_THAT_IS_NOT_SET".to_string())
+                ),
+            ])
+        );
+        assert_eq!(
+            loaded.resolve_audit_metadata(),
+            BTreeMap::from([("cost_center".to_string(), "eng-42".to_string())])
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn resolve_effective_profile_applies_project_override() -> anyhow::Result<()> {
         let workspace = TempDir::new()?;
@@ -180,7 +471,10 @@ permission_profile = "full_access"
 
         let resolved_profile =
             resolve_effective_profile(workspace.path(), PermissionProfile::ReadOnly)?;
-        assert_eq!(resolved_profile, PermissionProfile::FullAccess);
+        assert_eq!(
+            resolved_profile,
+            NamedProfile::builtin(PermissionProfile::FullAccess)
+        );
 
         let runtime_policy =
             resolve_effective_runtime_policy(workspace.path(), PermissionProfile::ReadOnly)?;
@@ -202,11 +496,135 @@ permission_profile = "full_access"
 
         let resolved_profile =
             resolve_effective_profile(workspace.path(), PermissionProfile::ReadWriteWithApproval)?;
-        assert_eq!(resolved_profile, PermissionProfile::ReadWriteWithApproval);
+        assert_eq!(
+            resolved_profile,
+            NamedProfile::builtin(PermissionProfile::ReadWriteWithApproval)
+        );
 
         Ok(())
     }
 
+    #[test]
+    fn resolve_effective_profile_selects_named_profiles() -> anyhow::Result<()> {
+        let workspace = TempDir::new()?;
+        write_project_policy_file(
+            &workspace,
+            r#"
+permission_profile = "read_write_with_approval"
+profile = "ci"
+
+[profiles.ci]
+decisions = { execute_command = "allow", network_access = "deny" }
+commands = [{ prefix = "git push", decision = "deny" }]
+paths = [{ path = "vendor", decision = "deny" }]
+"#,
+        )?;
+
+        let resolved_profile =
+            resolve_effective_profile(workspace.path(), PermissionProfile::FullAccess)?;
+        assert_eq!(resolved_profile.name, "ci");
+        assert_eq!(
+            resolved_profile.base,
+            PermissionProfile::ReadWriteWithApproval
+        );
+        assert_eq!(
+            resolved_profile.decision_for(ActionKind::ExecuteCommand),
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            resolve_effective_network_decision(workspace.path(), PermissionProfile::FullAccess)?,
+            PolicyDecision::Deny
+        );
+
+        write_project_policy_file(
+            &workspace,
+            r#"
+permission_profile = "full_access"
+profile = "missing"
+"#,
+        )?;
+        assert!(matches!(
+            load_project_policy(workspace.path()),
+            Err(ProjectPolicyConfigError::UnknownProfile { ref name, .. }) if name == "missing"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn save_project_policy_round_trips_and_rejects_invalid_profiles() -> anyhow::Result<()> {
+        let workspace = TempDir::new()?;
+        let mut config = ProjectPolicyConfig::new(PermissionProfile::ReadWriteWithApproval);
+        config.profile = Some("ci".to_string());
+        config.profiles.insert(
+            "ci".to_string(),
+            ProfileDefinition {
+                decisions: BTreeMap::from([(ActionKind::ExecuteCommand, PolicyDecision::Allow)]),
+                commands: vec![CommandRule {
+                    prefix: "git push".to_string(),
+                    decision: PolicyDecision::Deny,
+                }],
+                ..ProfileDefinition::default()
+            },
+        );
+
+        let written = save_project_policy(workspace.path(), &config)?;
+        assert_eq!(written, workspace.path().join(PROJECT_POLICY_RELATIVE_PATH));
+        assert_eq!(load_project_policy(workspace.path())?, Some(config.clone()));
+
+        config.profile = Some("missing".to_string());
+        assert!(matches!(
+            save_project_policy(workspace.path(), &config),
+            Err(ProjectPolicyConfigError::UnknownProfile { .. })
+        ));
+        config.profile = None;
+        config.profiles.insert(
+            "empty".to_string(),
+            ProfileDefinition {
+                commands: vec![CommandRule {
+                    prefix: " ".to_string(),
+                    decision: PolicyDecision::Deny,
+                }],
+                ..ProfileDefinition::default()
+            },
+        );
+        assert!(matches!(
+            save_project_policy(workspace.path(), &config),
+            Err(ProjectPolicyConfigError::InvalidProfile { ref name, .. }) if name == "empty"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn approval_rules_round_trip_and_reject_empty_patterns() -> anyhow::Result<()> {
+        let workspace = TempDir::new()?;
+        let mut config = ProjectPolicyConfig::new(PermissionProfile::ReadWriteWithApproval);
+        config.approval_rules.add(ApprovalRule {
+            pattern: "cargo test*".to_string(),
+            read_only: false,
+            command: Vec::new(),
+        });
+        config.approval_rules.add(ApprovalRule {
+            pattern: "ls *".to_string(),
+            read_only: true,
+            command: Vec::new(),
+        });
+        save_project_policy(workspace.path(), &config)?;
+        assert_eq!(load_project_policy(workspace.path())?, Some(config.clone()));
+
+        config.approval_rules.add(ApprovalRule {
+            pattern: " ".to_string(),
+            read_only: false,
+            command: Vec::new(),
+        });
+        assert!(matches!(
+            save_project_policy(workspace.path(), &config),
+            Err(ProjectPolicyConfigError::InvalidApprovalRule { .. })
+        ));
+        Ok(())
+    }
+
     #[test]
     fn load_project_policy_rejects_unknown_fields() -> anyhow::Result<()> {
         let workspace = TempDir::new()?;
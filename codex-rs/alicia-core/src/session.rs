@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -17,18 +18,37 @@ use tokio::sync::oneshot;
 
 use crate::ActionKind;
 use crate::ApprovalDecision;
+use crate::ApproverIdentity;
 use crate::AuditRecord;
+use crate::EnvEdits;
 use crate::PermissionProfile;
 use crate::PolicyDecision;
 use crate::ResultStatus;
+use crate::ShellCommand;
+use crate::ShellCommandError;
+#[cfg(feature = "test-util")]
+use crate::fake_session::FakeBackend;
+#[cfg(feature = "test-util")]
+use crate::fake_session::FakeProcess;
 use crate::ipc::CommandFinished;
 use crate::ipc::CommandOutputChunk;
 use crate::ipc::CommandOutputStream;
 use crate::ipc::CommandStarted;
 use crate::ipc::IpcEvent;
 use crate::ipc::IpcMessage;
-
-const SESSION_EVENTS_CAPACITY: usize = 1024;
+use crate::ipc::NetworkAccessBlocked;
+use crate::network_isolation::NetworkIsolation;
+use crate::network_isolation::NetworkViolationScanner;
+use crate::network_isolation::isolate_network;
+
+/// Events a subscriber may fall behind before it lags and loses the oldest ones.
+pub const DEFAULT_SESSION_EVENTS_CAPACITY: usize = 1024;
+const TRANSIENT_SPAWN_ERROR_MARKERS: [&str; 4] = [
+    "resource temporarily unavailable",
+    "eagain",
+    "out of pty devices",
+    "no more ptys",
+];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SessionMode {
@@ -47,6 +67,8 @@ pub struct SessionStartRequest {
     pub arg0: Option<String>,
     pub mode: SessionMode,
     pub audit_context: SessionAuditContext,
+    /// Launch offline with `isolate_network`, for profiles whose network decision is `Deny`.
+    pub network_disabled: bool,
 }
 
 impl SessionStartRequest {
@@ -67,6 +89,7 @@ impl SessionStartRequest {
             arg0: None,
             mode: SessionMode::Auto,
             audit_context: SessionAuditContext::for_execute_command(String::new()),
+            network_disabled: false,
         }
     }
 
@@ -84,6 +107,31 @@ impl SessionStartRequest {
         self.audit_context = audit_context;
         self
     }
+
+    pub fn with_network_disabled(mut self, network_disabled: bool) -> Self {
+        self.network_disabled = network_disabled;
+        self
+    }
+
+    /// Applies environment changes reviewed before launch; their names are audited.
+    pub fn with_env_edits(mut self, edits: &EnvEdits) -> Self {
+        edits.apply(&mut self.env);
+        self.audit_context.metadata.extend(edits.audit_metadata());
+        self
+    }
+
+    /// Runs `command` through its shell, audited as the command line the shell receives.
+    pub fn from_shell(
+        session_id: impl Into<String>,
+        command: &ShellCommand,
+        cwd: PathBuf,
+        env: HashMap<String, String>,
+    ) -> Result<Self, ShellCommandError> {
+        let (program, args) = command.build()?;
+        let command_line = command.command_line()?;
+        Ok(Self::new(session_id, program, args, cwd, env)
+            .with_audit_context(SessionAuditContext::for_execute_command(command_line)))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -91,8 +139,13 @@ pub struct SessionAuditContext {
     pub action_kind: ActionKind,
     pub target: String,
     pub profile: PermissionProfile,
+    pub profile_name: Option<String>,
     pub policy_decision: PolicyDecision,
     pub approval_decision: ApprovalDecision,
+    pub action_id: Option<String>,
+    pub event_sequence: Option<u64>,
+    pub approver: Option<ApproverIdentity>,
+    pub metadata: BTreeMap<String, String>,
 }
 
 impl SessionAuditContext {
@@ -101,8 +154,13 @@ impl SessionAuditContext {
             action_kind: ActionKind::ExecuteCommand,
             target: target.into(),
             profile: PermissionProfile::FullAccess,
+            profile_name: None,
             policy_decision: PolicyDecision::Allow,
             approval_decision: ApprovalDecision::NotRequired,
+            action_id: None,
+            event_sequence: None,
+            approver: None,
+            metadata: BTreeMap::new(),
         }
     }
 }
@@ -128,20 +186,121 @@ pub enum SessionManagerError {
         #[source]
         source: AnyhowError,
     },
+    #[error("failed to resize session `{session_id}`: {source}")]
+    ResizeFailed {
+        session_id: String,
+        #[source]
+        source: AnyhowError,
+    },
+}
+
+impl SessionManagerError {
+    /// Whether the failure is likely to succeed on retry (EAGAIN, PTY exhaustion, EINTR).
+    pub fn is_transient(&self) -> bool {
+        let Self::SpawnFailed { source, .. } = self else {
+            return false;
+        };
+        source.chain().any(|cause| {
+            if let Some(io_error) = cause.downcast_ref::<std::io::Error>()
+                && matches!(
+                    io_error.kind(),
+                    std::io::ErrorKind::WouldBlock
+                        | std::io::ErrorKind::Interrupted
+                        | std::io::ErrorKind::ResourceBusy
+                )
+            {
+                return true;
+            }
+            let message = cause.to_string().to_ascii_lowercase();
+            TRANSIENT_SPAWN_ERROR_MARKERS
+                .iter()
+                .any(|marker| message.contains(marker))
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
 struct SessionRecord {
-    handle: Arc<ProcessHandle>,
+    handle: Arc<SessionHandle>,
     audit_context: SessionAuditContext,
     cancellation_requested: bool,
 }
 
+/// A running session: a real process, or a scripted one from `FakeSessionManager`.
+#[derive(Debug)]
+enum SessionHandle {
+    Process(ProcessHandle),
+    #[cfg(feature = "test-util")]
+    Fake(Arc<FakeProcess>),
+}
+
+impl SessionHandle {
+    fn writer_sender(&self) -> mpsc::Sender<Vec<u8>> {
+        match self {
+            Self::Process(handle) => handle.writer_sender(),
+            #[cfg(feature = "test-util")]
+            Self::Fake(process) => process.writer_sender(),
+        }
+    }
+
+    fn output_receiver(&self) -> broadcast::Receiver<Vec<u8>> {
+        match self {
+            Self::Process(handle) => handle.output_receiver(),
+            #[cfg(feature = "test-util")]
+            Self::Fake(process) => process.output_receiver(),
+        }
+    }
+
+    fn has_exited(&self) -> bool {
+        match self {
+            Self::Process(handle) => handle.has_exited(),
+            #[cfg(feature = "test-util")]
+            Self::Fake(process) => process.has_exited(),
+        }
+    }
+
+    fn exit_code(&self) -> Option<i32> {
+        match self {
+            Self::Process(handle) => handle.exit_code(),
+            #[cfg(feature = "test-util")]
+            Self::Fake(process) => process.exit_code(),
+        }
+    }
+
+    fn terminate(&self) {
+        match self {
+            Self::Process(handle) => handle.terminate(),
+            #[cfg(feature = "test-util")]
+            Self::Fake(process) => process.terminate(),
+        }
+    }
+
+    fn resize(&self, rows: u16, cols: u16) -> anyhow::Result<()> {
+        match self {
+            Self::Process(handle) => handle.resize(rows, cols),
+            #[cfg(feature = "test-util")]
+            Self::Fake(process) => process.resize(rows, cols),
+        }
+    }
+
+    /// Called once `CommandStarted` is out; fake sessions only start their script then.
+    fn launched(&self) {
+        match self {
+            Self::Process(_) => {}
+            #[cfg(feature = "test-util")]
+            Self::Fake(process) => process.launch(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SessionManager {
     sessions: Arc<Mutex<HashMap<String, SessionRecord>>>,
     events_tx: broadcast::Sender<IpcMessage>,
+    event_capacity: usize,
     audit_logger: Option<crate::AuditLogger>,
+    #[cfg(feature = "test-util")]
+    fake_backend: Option<Arc<FakeBackend>>,
 }
 
 impl Default for SessionManager {
@@ -152,20 +311,44 @@ impl Default for SessionManager {
 
 impl SessionManager {
     pub fn new() -> Self {
-        let (events_tx, _) = broadcast::channel(SESSION_EVENTS_CAPACITY);
+        let (events_tx, _) = broadcast::channel(DEFAULT_SESSION_EVENTS_CAPACITY);
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
             events_tx,
+            event_capacity: DEFAULT_SESSION_EVENTS_CAPACITY,
             audit_logger: None,
+            #[cfg(feature = "test-util")]
+            fake_backend: None,
         }
     }
 
+    #[cfg(feature = "test-util")]
+    pub(crate) fn with_fake_backend(fake_backend: Arc<FakeBackend>) -> Self {
+        let mut manager = Self::new();
+        manager.fake_backend = Some(fake_backend);
+        manager
+    }
+
     pub fn with_audit_logger(audit_logger: crate::AuditLogger) -> Self {
         let mut manager = Self::new();
         manager.audit_logger = Some(audit_logger);
         manager
     }
 
+    /// Resizes the event channel. Receivers taken before this call stay on the old channel,
+    /// so set it before anyone subscribes.
+    pub fn with_event_capacity(mut self, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (events_tx, _) = broadcast::channel(capacity);
+        self.events_tx = events_tx;
+        self.event_capacity = capacity;
+        self
+    }
+
+    pub fn event_capacity(&self) -> usize {
+        self.event_capacity
+    }
+
     pub fn event_receiver(&self) -> broadcast::Receiver<IpcMessage> {
         self.events_tx.subscribe()
     }
@@ -188,14 +371,18 @@ impl SessionManager {
         }
 
         let started_at = Instant::now();
-        let SpawnedProcess {
-            session,
-            output_rx,
-            exit_rx,
-        } = self.spawn_process(&request).await?;
+        let mut launch = request.clone();
+        let isolation = request.network_disabled.then(|| {
+            let (program, args, isolation) =
+                isolate_network(&request.program, &request.args, &mut launch.env);
+            launch.program = program;
+            launch.args = args;
+            isolation
+        });
+        let (handle, output_rx, exit_rx) = self.spawn_session(&launch).await?;
         let command = build_command(&request.program, &request.args);
         let command_text = command.join(" ");
-        let handle = Arc::new(session);
+        let handle = Arc::new(handle);
         let mut audit_context = request.audit_context.clone();
         if audit_context.target.is_empty() {
             audit_context.target = command_text;
@@ -219,7 +406,7 @@ impl SessionManager {
             );
         }
 
-        self.spawn_output_forwarder(request.session_id.clone(), output_rx);
+        self.spawn_output_forwarder(request.session_id.clone(), output_rx, isolation);
         self.spawn_exit_watcher(
             request.session_id.clone(),
             exit_rx,
@@ -234,6 +421,7 @@ impl SessionManager {
                 command,
                 cwd: request.cwd.to_string_lossy().to_string(),
             })));
+        handle.launched();
 
         Ok(())
     }
@@ -292,11 +480,59 @@ impl SessionManager {
         })
     }
 
+    /// Resizes a PTY session's window, e.g. when the terminal panel changes size. Pipe
+    /// sessions have no window and fail with `ResizeFailed`.
+    pub async fn resize(
+        &self,
+        session_id: &str,
+        rows: u16,
+        cols: u16,
+    ) -> Result<(), SessionManagerError> {
+        let handle = {
+            let sessions = self.sessions.lock().await;
+            let Some(record) = sessions.get(session_id) else {
+                return Err(SessionManagerError::SessionNotFound(session_id.to_string()));
+            };
+            Arc::clone(&record.handle)
+        };
+
+        handle
+            .resize(rows, cols)
+            .map_err(|source| SessionManagerError::ResizeFailed {
+                session_id: session_id.to_string(),
+                source,
+            })
+    }
+
     pub async fn is_active(&self, session_id: &str) -> bool {
         let sessions = self.sessions.lock().await;
         sessions.contains_key(session_id)
     }
 
+    async fn spawn_session(
+        &self,
+        request: &SessionStartRequest,
+    ) -> Result<
+        (
+            SessionHandle,
+            broadcast::Receiver<Vec<u8>>,
+            oneshot::Receiver<i32>,
+        ),
+        SessionManagerError,
+    > {
+        #[cfg(feature = "test-util")]
+        if let Some(fake_backend) = &self.fake_backend {
+            let (process, output_rx, exit_rx) = fake_backend.spawn(request);
+            return Ok((SessionHandle::Fake(process), output_rx, exit_rx));
+        }
+        let SpawnedProcess {
+            session,
+            output_rx,
+            exit_rx,
+        } = self.spawn_process(request).await?;
+        Ok((SessionHandle::Process(session), output_rx, exit_rx))
+    }
+
     async fn spawn_process(
         &self,
         request: &SessionStartRequest,
@@ -354,13 +590,27 @@ impl SessionManager {
         })
     }
 
+    /// Forwards output as events. Offline sessions also report their first refused network
+    /// attempt as `NetworkAccessBlocked`.
     fn spawn_output_forwarder(
         &self,
         session_id: String,
         mut output_rx: broadcast::Receiver<Vec<u8>>,
+        isolation: Option<NetworkIsolation>,
     ) {
         let events_tx = self.events_tx.clone();
         tokio::spawn(async move {
+            let mut violation_reported = false;
+            let mut scanner = NetworkViolationScanner::default();
+            let report_violation = |detail: String, isolation: NetworkIsolation| {
+                let _ = events_tx.send(IpcMessage::new(IpcEvent::NetworkAccessBlocked(
+                    NetworkAccessBlocked {
+                        command_id: session_id.clone(),
+                        isolation,
+                        detail,
+                    },
+                )));
+            };
             loop {
                 match output_rx.recv().await {
                     Ok(bytes) => {
@@ -368,6 +618,13 @@ impl SessionManager {
                         if chunk.is_empty() {
                             continue;
                         }
+                        if let Some(isolation) = isolation
+                            && !violation_reported
+                            && let Some(detail) = scanner.feed(&chunk)
+                        {
+                            violation_reported = true;
+                            report_violation(detail, isolation);
+                        }
                         let _ = events_tx.send(IpcMessage::new(IpcEvent::CommandOutputChunk(
                             CommandOutputChunk {
                                 command_id: session_id.clone(),
@@ -381,6 +638,12 @@ impl SessionManager {
                     Err(broadcast::error::RecvError::Closed) => break,
                 }
             }
+            if let Some(isolation) = isolation
+                && !violation_reported
+                && let Some(detail) = scanner.finish()
+            {
+                report_violation(detail, isolation);
+            }
         });
     }
 
@@ -419,16 +682,22 @@ impl SessionManager {
                 } else {
                     ResultStatus::Failed
                 };
-                let audit_record = AuditRecord::new(
+                let audit_context = removed_session.audit_context;
+                let mut audit_record = AuditRecord::new(
                     session_id,
-                    removed_session.audit_context.action_kind,
-                    removed_session.audit_context.target,
-                    removed_session.audit_context.profile,
-                    removed_session.audit_context.policy_decision,
-                    removed_session.audit_context.approval_decision,
+                    audit_context.action_kind,
+                    audit_context.target,
+                    audit_context.profile,
+                    audit_context.policy_decision,
+                    audit_context.approval_decision,
                     result_status,
                     duration_ms,
                 );
+                audit_record.profile_name = audit_context.profile_name;
+                audit_record.action_id = audit_context.action_id;
+                audit_record.event_sequence = audit_context.event_sequence;
+                audit_record.approver = audit_context.approver;
+                audit_record.metadata = audit_context.metadata;
                 let _ = audit_logger.append(&audit_record).await;
             }
         });
@@ -444,6 +713,7 @@ fn build_command(program: &str, args: &[String]) -> Vec<String> {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::collections::HashMap;
     use std::path::PathBuf;
     use std::time::Duration;
@@ -460,22 +730,17 @@ mod tests {
     use super::SessionStartRequest;
     use crate::ActionKind;
     use crate::ApprovalDecision;
+    use crate::ApproverIdentity;
     use crate::AuditLogger;
     use crate::IpcEvent;
     use crate::IpcMessage;
     use crate::PermissionProfile;
     use crate::PolicyDecision;
+    use crate::ShellCommand;
+    use crate::ShellCommandError;
 
-    fn shell_command(script: &str) -> (String, Vec<String>) {
-        if cfg!(windows) {
-            let cmd = std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string());
-            (cmd, vec!["/C".to_string(), script.to_string()])
-        } else {
-            (
-                "/bin/sh".to_string(),
-                vec!["-c".to_string(), script.to_string()],
-            )
-        }
+    fn shell_command(script: &str) -> Result<(String, Vec<String>), ShellCommandError> {
+        ShellCommand::new(script).build()
     }
 
     fn delayed_echo_script(marker: &str) -> String {
@@ -595,7 +860,7 @@ mod tests {
         let manager = SessionManager::new();
         let mut events_rx = manager.event_receiver();
         let marker = "alicia_bridge_pipe_ok";
-        let (program, args) = shell_command(&delayed_echo_script(marker));
+        let (program, args) = shell_command(&delayed_echo_script(marker))?;
         let request =
             SessionStartRequest::new("sess-pipe", program, args, PathBuf::from("."), env_map())
                 .with_mode(SessionMode::Pipe);
@@ -651,7 +916,7 @@ mod tests {
     async fn reattach_returns_live_receivers_for_running_session() -> Result<()> {
         let manager = SessionManager::new();
         let marker = "reattach_bridge_ok";
-        let (program, args) = shell_command(&delayed_echo_script(marker));
+        let (program, args) = shell_command(&delayed_echo_script(marker))?;
         let request = SessionStartRequest::new(
             "sess-reattach",
             program,
@@ -681,7 +946,7 @@ mod tests {
     async fn stop_terminates_and_removes_active_session() -> Result<()> {
         let manager = SessionManager::new();
         let mut events_rx = manager.event_receiver();
-        let (program, args) = shell_command(&long_running_script());
+        let (program, args) = shell_command(&long_running_script())?;
         let request =
             SessionStartRequest::new("sess-stop", program, args, PathBuf::from("."), env_map())
                 .with_mode(SessionMode::Pipe);
@@ -715,7 +980,7 @@ mod tests {
     async fn duplicate_session_ids_are_rejected() -> Result<()> {
         let manager = SessionManager::new();
         let mut events_rx = manager.event_receiver();
-        let (program, args) = shell_command(&long_running_script());
+        let (program, args) = shell_command(&long_running_script())?;
         let request = SessionStartRequest::new(
             "sess-dup",
             program.clone(),
@@ -748,7 +1013,7 @@ mod tests {
         let manager = SessionManager::new();
         let mut events_rx = manager.event_receiver();
         let first_marker = "cancel_first_start";
-        let (program, args) = shell_command(&long_running_script_with_marker_start(first_marker));
+        let (program, args) = shell_command(&long_running_script_with_marker_start(first_marker))?;
         manager
             .start(
                 SessionStartRequest::new(
@@ -777,7 +1042,7 @@ mod tests {
         );
 
         let second_marker = "cancel_reuse_second_ok";
-        let (program, args) = shell_command(&delayed_echo_script(second_marker));
+        let (program, args) = shell_command(&delayed_echo_script(second_marker))?;
         manager
             .start(
                 SessionStartRequest::new(
@@ -846,11 +1111,16 @@ mod tests {
             action_kind: ActionKind::ExecuteCommand,
             target: "long_running_task".to_string(),
             profile: PermissionProfile::ReadWriteWithApproval,
+            profile_name: None,
             policy_decision: PolicyDecision::RequireApproval,
             approval_decision: ApprovalDecision::Approved,
+            action_id: Some("act-audit-cancel".to_string()),
+            event_sequence: Some(3),
+            approver: Some(ApproverIdentity::configured("release-manager")),
+            metadata: BTreeMap::from([("environment".to_string(), "ci".to_string())]),
         };
 
-        let (program, args) = shell_command(&long_running_script());
+        let (program, args) = shell_command(&long_running_script())?;
         manager
             .start(
                 SessionStartRequest::new(
@@ -901,10 +1171,51 @@ mod tests {
             entry.get("approval_decision").and_then(Value::as_str),
             Some("approved")
         );
+        assert_eq!(
+            entry.get("action_id").and_then(Value::as_str),
+            Some("act-audit-cancel")
+        );
+        assert_eq!(entry.get("event_sequence").and_then(Value::as_u64), Some(3));
+        assert_eq!(
+            entry
+                .get("metadata")
+                .and_then(|metadata| metadata.get("environment"))
+                .and_then(Value::as_str),
+            Some("ci")
+        );
+        assert_eq!(
+            entry
+                .get("approver")
+                .and_then(|approver| approver.get("name"))
+                .and_then(Value::as_str),
+            Some("release-manager")
+        );
 
         Ok(())
     }
 
+    #[test]
+    fn spawn_failures_classify_transient_causes() {
+        let would_block = SessionManagerError::SpawnFailed {
+            session_id: "sess-eagain".to_string(),
+            source: anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::WouldBlock))
+                .context("spawn pty"),
+        };
+        let pty_exhausted = SessionManagerError::SpawnFailed {
+            session_id: "sess-pty".to_string(),
+            source: anyhow::anyhow!("openpty failed: out of pty devices"),
+        };
+        let missing_binary = SessionManagerError::SpawnFailed {
+            session_id: "sess-missing".to_string(),
+            source: anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::NotFound)),
+        };
+
+        assert!(would_block.is_transient());
+        assert!(pty_exhausted.is_transient());
+        assert!(!missing_binary.is_transient());
+        assert!(!SessionManagerError::PtyUnavailable.is_transient());
+    }
+
     #[test]
     fn command_builder_keeps_program_as_first_token() {
         let command = super::build_command(
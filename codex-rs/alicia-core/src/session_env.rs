@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+/// Name fragments of variables that usually hold credentials, e.g. `GITHUB_TOKEN`.
+const SECRET_NAME_MARKERS: [&str; 6] =
+    ["KEY", "SECRET", "TOKEN", "PASSWORD", "PASSWD", "CREDENTIAL"];
+
+/// Whether `name` looks like it holds a credential; such values are masked when shown.
+pub fn is_secret_env_var(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    SECRET_NAME_MARKERS
+        .iter()
+        .any(|marker| name.contains(marker))
+}
+
+/// Whether `name` can be set in a process environment.
+pub fn is_valid_env_var_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['=', '\0']) && !name.chars().any(char::is_whitespace)
+}
+
+/// Changes reviewed for a session's environment before it launches. Audit records only
+/// carry the names, never the values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvEdits {
+    pub set: BTreeMap<String, String>,
+    pub removed: BTreeSet<String>,
+}
+
+impl EnvEdits {
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.removed.is_empty()
+    }
+
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        self.removed.remove(&name);
+        self.set.insert(name, value.into());
+    }
+
+    pub fn remove(&mut self, name: impl Into<String>) {
+        let name = name.into();
+        self.set.remove(&name);
+        self.removed.insert(name);
+    }
+
+    pub fn apply(&self, env: &mut HashMap<String, String>) {
+        for name in &self.removed {
+            env.remove(name);
+        }
+        for (name, value) in &self.set {
+            env.insert(name.clone(), value.clone());
+        }
+    }
+
+    /// `env_set`/`env_removed` with the comma separated names, for `SessionAuditContext`.
+    pub fn audit_metadata(&self) -> BTreeMap<String, String> {
+        let mut metadata = BTreeMap::new();
+        if !self.set.is_empty() {
+            let names: Vec<&str> = self.set.keys().map(String::as_str).collect();
+            metadata.insert("env_set".to_string(), names.join(","));
+        }
+        if !self.removed.is_empty() {
+            let names: Vec<&str> = self.removed.iter().map(String::as_str).collect();
+            metadata.insert("env_removed".to_string(), names.join(","));
+        }
+        metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use super::EnvEdits;
+    use super::is_secret_env_var;
+    use super::is_valid_env_var_name;
+
+    #[test]
+    fn edits_apply_and_are_audited_by_name() {
+        let mut env = HashMap::from([
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            ("AWS_SECRET_ACCESS_KEY".to_string(), "abc".to_string()),
+        ]);
+        let mut edits = EnvEdits::default();
+        edits.set("RUST_LOG", "debug");
+        edits.remove("AWS_SECRET_ACCESS_KEY");
+        edits.apply(&mut env);
+
+        assert_eq!(
+            env,
+            HashMap::from([
+                ("PATH".to_string(), "/usr/bin".to_string()),
+                ("RUST_LOG".to_string(), "debug".to_string()),
+            ])
+        );
+        let metadata = edits.audit_metadata();
+        assert_eq!(
+            metadata.get("env_set").map(String::as_str),
+            Some("RUST_LOG")
+        );
+        assert_eq!(
+            metadata.get("env_removed").map(String::as_str),
+            Some("AWS_SECRET_ACCESS_KEY")
+        );
+
+        assert!(is_secret_env_var("github_token"));
+        assert!(!is_secret_env_var("HOME"));
+        assert!(!is_valid_env_var_name("A=B"));
+        assert!(is_valid_env_var_name("RUST_LOG"));
+    }
+}
@@ -0,0 +1,274 @@
+use thiserror::Error;
+
+const POSIX_SHELL: &str = "/bin/sh";
+const CMD_SHELL: &str = "cmd.exe";
+/// Characters a POSIX shell reads literally outside quotes.
+const POSIX_SAFE_PUNCTUATION: &[char] = &['_', '@', '%', '+', '=', ':', ',', '.', '/', '-'];
+/// Characters `cmd.exe` gives a meaning to outside quotes.
+const CMD_SPECIAL_CHARACTERS: &[char] = &[
+    ' ', '\t', '"', '&', '|', '<', '>', '^', '%', '!', '(', ')', ',', ';', '=',
+];
+/// Expands to nothing, so `%` never starts an environment variable inside `cmd /C`.
+const CMD_PERCENT_ESCAPE: &str = "%%cd:~,%";
+
+/// Which shell a [`ShellCommand`] is written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellKind {
+    /// `/bin/sh -c`.
+    Posix,
+    /// `cmd.exe /C`, or `%COMSPEC%` when set.
+    Cmd,
+}
+
+impl ShellKind {
+    /// The shell of the platform Alicia was built for.
+    pub fn host() -> Self {
+        if cfg!(windows) {
+            Self::Cmd
+        } else {
+            Self::Posix
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Posix => "sh",
+            Self::Cmd => "cmd",
+        }
+    }
+
+    fn default_program(self) -> String {
+        match self {
+            Self::Posix => POSIX_SHELL.to_string(),
+            Self::Cmd => std::env::var("COMSPEC").unwrap_or_else(|_| CMD_SHELL.to_string()),
+        }
+    }
+
+    fn script_flag(self) -> &'static str {
+        match self {
+            Self::Posix => "-c",
+            Self::Cmd => "/C",
+        }
+    }
+
+    /// Quotes `argument` so the shell passes it as one literal argument.
+    pub fn quote(self, argument: &str) -> Result<String, ShellCommandError> {
+        match self {
+            Self::Posix => quote_posix(argument),
+            Self::Cmd => quote_cmd(argument),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ShellCommandError {
+    #[error("shell command is empty")]
+    EmptyCommand,
+    #[error("argument {argument:?} cannot be passed safely through {shell}")]
+    UnsafeArgument {
+        argument: String,
+        shell: &'static str,
+    },
+}
+
+/// A command line run through the platform shell, instead of hand-rolling `/bin/sh -c`
+/// versus `cmd.exe /C`. `script` is passed as written, so it may use pipes and redirects;
+/// arguments added with [`ShellCommand::arg`] are quoted for the target shell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellCommand {
+    kind: ShellKind,
+    shell: Option<String>,
+    script: String,
+    args: Vec<String>,
+}
+
+impl ShellCommand {
+    pub fn new(script: impl Into<String>) -> Self {
+        Self {
+            kind: ShellKind::host(),
+            shell: None,
+            script: script.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Runs `program` with `args`, every word quoted.
+    pub fn program(program: &str, args: &[String]) -> Self {
+        Self::new(String::new())
+            .arg(program)
+            .args(args.iter().cloned())
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args(mut self, args: impl IntoIterator<Item = String>) -> Self {
+        self.args.extend(args);
+        self
+    }
+
+    pub fn with_kind(mut self, kind: ShellKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Shell binary to run instead of `/bin/sh` or `%COMSPEC%`, e.g. `/bin/bash`.
+    pub fn with_shell(mut self, shell: impl Into<String>) -> Self {
+        self.shell = Some(shell.into());
+        self
+    }
+
+    pub fn kind(&self) -> ShellKind {
+        self.kind
+    }
+
+    /// The script followed by the quoted arguments, as the shell receives it.
+    pub fn command_line(&self) -> Result<String, ShellCommandError> {
+        let mut words = Vec::with_capacity(self.args.len() + 1);
+        if !self.script.trim().is_empty() {
+            words.push(self.script.trim().to_string());
+        }
+        for arg in &self.args {
+            words.push(self.kind.quote(arg)?);
+        }
+        if words.is_empty() {
+            return Err(ShellCommandError::EmptyCommand);
+        }
+        Ok(words.join(" "))
+    }
+
+    /// Program and arguments for `SessionStartRequest` or `std::process::Command`.
+    pub fn build(&self) -> Result<(String, Vec<String>), ShellCommandError> {
+        let command_line = self.command_line()?;
+        let program = self
+            .shell
+            .clone()
+            .unwrap_or_else(|| self.kind.default_program());
+        Ok((
+            program,
+            vec![self.kind.script_flag().to_string(), command_line],
+        ))
+    }
+}
+
+fn quote_posix(argument: &str) -> Result<String, ShellCommandError> {
+    if argument.contains('\0') {
+        return Err(unsafe_argument(argument, ShellKind::Posix));
+    }
+    if !argument.is_empty()
+        && argument
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || POSIX_SAFE_PUNCTUATION.contains(&ch))
+    {
+        return Ok(argument.to_string());
+    }
+    Ok(format!("'{}'", argument.replace('\'', r"'\''")))
+}
+
+/// Double quotes as `CommandLineToArgvW` reads them, with `%` neutralized. Line breaks end a
+/// `cmd` command line wherever they are, so they cannot be quoted.
+fn quote_cmd(argument: &str) -> Result<String, ShellCommandError> {
+    if argument.contains(['\0', '\n', '\r']) {
+        return Err(unsafe_argument(argument, ShellKind::Cmd));
+    }
+    if !argument.is_empty() && !argument.contains(CMD_SPECIAL_CHARACTERS) {
+        return Ok(argument.to_string());
+    }
+    let mut quoted = String::with_capacity(argument.len() + 2);
+    quoted.push('"');
+    let mut backslashes = 0;
+    for ch in argument.chars() {
+        match ch {
+            '\\' => {
+                backslashes += 1;
+                quoted.push(ch);
+            }
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push_str("\"\"");
+                backslashes = 0;
+            }
+            '%' => {
+                quoted.push_str(CMD_PERCENT_ESCAPE);
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push(ch);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes));
+    quoted.push('"');
+    Ok(quoted)
+}
+
+fn unsafe_argument(argument: &str, kind: ShellKind) -> ShellCommandError {
+    ShellCommandError::UnsafeArgument {
+        argument: argument.to_string(),
+        shell: kind.name(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::ShellCommand;
+    use super::ShellCommandError;
+    use super::ShellKind;
+
+    #[test]
+    fn quotes_arguments_for_each_shell() -> Result<(), ShellCommandError> {
+        let args = vec![
+            "a b".to_string(),
+            "it's".to_string(),
+            "100%".to_string(),
+            r#"C:\dir\"#.to_string(),
+            String::new(),
+        ];
+        let posix = ShellCommand::program("echo", &args).with_kind(ShellKind::Posix);
+        assert_eq!(
+            posix.command_line()?,
+            r#"echo 'a b' 'it'\''s' 100% 'C:\dir\' ''"#
+        );
+        let cmd = ShellCommand::program("echo", &args).with_kind(ShellKind::Cmd);
+        assert_eq!(
+            cmd.command_line()?,
+            r#"echo "a b" it's "100%%cd:~,%" C:\dir\ """#
+        );
+        assert_eq!(ShellKind::Cmd.quote(r#"say "hi" \"#)?, r#""say ""hi"" \\""#);
+
+        let (program, args) = ShellCommand::new("ls | wc -l")
+            .with_kind(ShellKind::Posix)
+            .with_shell("/bin/bash")
+            .build()?;
+        assert_eq!(program, "/bin/bash");
+        assert_eq!(args, vec!["-c".to_string(), "ls | wc -l".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_what_cannot_be_quoted() {
+        assert_eq!(
+            ShellCommand::new("echo")
+                .arg("linha\noutra")
+                .with_kind(ShellKind::Cmd)
+                .build(),
+            Err(ShellCommandError::UnsafeArgument {
+                argument: "linha\noutra".to_string(),
+                shell: "cmd",
+            })
+        );
+        assert_eq!(
+            ShellCommand::new("  ").build(),
+            Err(ShellCommandError::EmptyCommand)
+        );
+        assert_eq!(
+            ShellKind::Posix.quote("linha\noutra"),
+            Ok("'linha\noutra'".to_string())
+        );
+    }
+}
@@ -0,0 +1,410 @@
+//! proptest strategies for IPC events, unified diffs and approval sequences. Ids are drawn
+//! from small pools so generated events keep referring to the same actions and sessions,
+//! which is where stores get interesting: duplicates, resolutions before requests, output
+//! after a session finished.
+
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prelude::*;
+use proptest::sample::select;
+
+use crate::ActionKind;
+use crate::ApprovalResolution;
+use crate::ApproverIdentity;
+use crate::ApproverSource;
+use crate::CommandOutputStream;
+use crate::DenialCategory;
+use crate::DenialReason;
+use crate::IpcEvent;
+use crate::IpcMessage;
+use crate::NetworkIsolation;
+use crate::ipc::ActionProposed;
+use crate::ipc::AgentMessageComplete;
+use crate::ipc::AgentMessageDelta;
+use crate::ipc::ApprovalRequested;
+use crate::ipc::ApprovalResolved;
+use crate::ipc::CommandFinished;
+use crate::ipc::CommandOutputChunk;
+use crate::ipc::CommandStarted;
+use crate::ipc::NetworkAccessBlocked;
+use crate::ipc::PatchApplied;
+use crate::ipc::PatchPreviewReady;
+use crate::ipc::ToolCallFinished;
+use crate::ipc::ToolCallStarted;
+use crate::ipc::ToolCallStatus;
+
+/// Approval expirations are generated within an hour after this instant (2025-01-01T00:00:00Z).
+pub const STRATEGY_START_UNIX_S: i64 = 1_735_689_600;
+pub const MAX_STRATEGY_APPROVAL_TTL_S: i64 = 3_600;
+
+pub const STRATEGY_ACTION_IDS: [&str; 6] = ["act-0", "act-1", "act-2", "act-3", "act-4", "act-5"];
+pub const STRATEGY_COMMAND_IDS: [&str; 3] = ["sess-0", "sess-1", "sess-2"];
+pub const STRATEGY_MESSAGE_IDS: [&str; 2] = ["msg-0", "msg-1"];
+pub const STRATEGY_TOOL_CALL_IDS: [&str; 2] = ["call-0", "call-1"];
+
+const ACTION_KINDS: [ActionKind; 5] = [
+    ActionKind::ReadFile,
+    ActionKind::WriteFile,
+    ActionKind::ExecuteCommand,
+    ActionKind::ApplyPatch,
+    ActionKind::NetworkAccess,
+];
+const FILE_PATHS: [&str; 4] = ["src/main.rs", "src/lib.rs", "README.md", "docs/guia.md"];
+const APPROVER_NAMES: [&str; 3] = ["ana", "bruno", "ci-bot"];
+const WORDS: [&str; 10] = [
+    "cargo",
+    "test",
+    "ok",
+    "erro",
+    "compilando",
+    "ação",
+    "\u{1b}[31mfalhou\u{1b}[0m",
+    "\t",
+    "-",
+    "+",
+];
+/// Unchanged lines between consecutive generated hunks.
+const DIFF_HUNK_GAP: usize = 3;
+
+pub fn action_id() -> impl Strategy<Value = String> {
+    select(STRATEGY_ACTION_IDS.to_vec()).prop_map(str::to_string)
+}
+
+pub fn command_id() -> impl Strategy<Value = String> {
+    select(STRATEGY_COMMAND_IDS.to_vec()).prop_map(str::to_string)
+}
+
+pub fn message_id() -> impl Strategy<Value = String> {
+    select(STRATEGY_MESSAGE_IDS.to_vec()).prop_map(str::to_string)
+}
+
+pub fn tool_call_id() -> impl Strategy<Value = String> {
+    select(STRATEGY_TOOL_CALL_IDS.to_vec()).prop_map(str::to_string)
+}
+
+pub fn action_kind() -> impl Strategy<Value = ActionKind> {
+    select(ACTION_KINDS.to_vec())
+}
+
+pub fn file_path() -> impl Strategy<Value = String> {
+    select(FILE_PATHS.to_vec()).prop_map(str::to_string)
+}
+
+fn text() -> impl Strategy<Value = String> {
+    vec(select(WORDS.to_vec()), 0..6).prop_map(|words| words.join(" "))
+}
+
+fn expires_at_unix_s() -> impl Strategy<Value = i64> {
+    (0..=MAX_STRATEGY_APPROVAL_TTL_S).prop_map(|ttl_s| STRATEGY_START_UNIX_S + ttl_s)
+}
+
+pub fn approval_resolution() -> impl Strategy<Value = ApprovalResolution> {
+    prop_oneof![
+        Just(ApprovalResolution::Approved),
+        Just(ApprovalResolution::Denied),
+        Just(ApprovalResolution::Expired),
+    ]
+}
+
+pub fn approver_identity() -> impl Strategy<Value = ApproverIdentity> {
+    (
+        prop_oneof![
+            Just(ApproverSource::OsUser),
+            Just(ApproverSource::Configured),
+            Just(ApproverSource::RemotePrincipal),
+        ],
+        select(APPROVER_NAMES.to_vec()),
+    )
+        .prop_map(|(source, name)| ApproverIdentity::new(source, name))
+}
+
+pub fn denial_reason() -> impl Strategy<Value = DenialReason> {
+    (
+        prop_oneof![
+            Just(DenialCategory::TooRisky),
+            Just(DenialCategory::WrongApproach),
+            Just(DenialCategory::NeedsTests),
+            Just(DenialCategory::Other),
+        ],
+        option::of("[a-z ]{1,24}"),
+    )
+        .prop_map(|(category, detail)| {
+            let reason = DenialReason::new(category);
+            match detail {
+                Some(detail) => reason.with_detail(detail),
+                None => reason,
+            }
+        })
+}
+
+pub fn action_proposed() -> impl Strategy<Value = IpcEvent> {
+    (action_id(), action_kind(), file_path()).prop_map(|(action_id, action_kind, target)| {
+        IpcEvent::ActionProposed(ActionProposed {
+            action_id,
+            action_kind,
+            target,
+        })
+    })
+}
+
+pub fn approval_requested() -> impl Strategy<Value = IpcEvent> {
+    (action_id(), text(), expires_at_unix_s()).prop_map(
+        |(action_id, summary, expires_at_unix_s)| {
+            IpcEvent::ApprovalRequested(ApprovalRequested {
+                action_id,
+                summary,
+                expires_at_unix_s,
+            })
+        },
+    )
+}
+
+pub fn approval_resolved() -> impl Strategy<Value = IpcEvent> {
+    (
+        action_id(),
+        approval_resolution(),
+        option::of(approver_identity()),
+        option::of(denial_reason()),
+    )
+        .prop_map(|(action_id, resolution, approver, reason)| {
+            IpcEvent::ApprovalResolved(ApprovalResolved {
+                action_id,
+                resolution,
+                approver,
+                reason: reason.filter(|_| resolution == ApprovalResolution::Denied),
+            })
+        })
+}
+
+/// Any IPC event, with ids from the shared pools.
+pub fn ipc_event() -> impl Strategy<Value = IpcEvent> {
+    prop_oneof![
+        action_proposed(),
+        approval_requested(),
+        approval_resolved(),
+        (
+            command_id(),
+            vec(select(WORDS[..2].to_vec()), 1..3),
+            file_path()
+        )
+            .prop_map(|(command_id, command, cwd)| {
+                IpcEvent::CommandStarted(CommandStarted {
+                    command_id,
+                    command: command.into_iter().map(str::to_string).collect(),
+                    cwd,
+                })
+            }),
+        (
+            command_id(),
+            prop_oneof![
+                Just(CommandOutputStream::Stdout),
+                Just(CommandOutputStream::Stderr)
+            ],
+            text(),
+        )
+            .prop_map(|(command_id, stream, chunk)| {
+                IpcEvent::CommandOutputChunk(CommandOutputChunk {
+                    command_id,
+                    stream,
+                    chunk: format!("{chunk}\n"),
+                })
+            }),
+        (command_id(), -1..=2, 0..60_000_u64).prop_map(|(command_id, exit_code, duration_ms)| {
+            IpcEvent::CommandFinished(CommandFinished {
+                command_id,
+                exit_code,
+                duration_ms,
+            })
+        }),
+        (action_id(), vec(file_path(), 1..3)).prop_map(|(action_id, files)| {
+            IpcEvent::PatchPreviewReady(PatchPreviewReady { action_id, files })
+        }),
+        (action_id(), vec(file_path(), 1..3)).prop_map(
+            |(action_id, files)| IpcEvent::PatchApplied(PatchApplied { action_id, files })
+        ),
+        (
+            command_id(),
+            prop_oneof![
+                Just(NetworkIsolation::Namespace),
+                Just(NetworkIsolation::ProxyBlackhole)
+            ],
+            text(),
+        )
+            .prop_map(|(command_id, isolation, detail)| {
+                IpcEvent::NetworkAccessBlocked(NetworkAccessBlocked {
+                    command_id,
+                    isolation,
+                    detail,
+                })
+            }),
+        (message_id(), text()).prop_map(|(message_id, delta)| {
+            IpcEvent::AgentMessageDelta(AgentMessageDelta { message_id, delta })
+        }),
+        message_id().prop_map(|message_id| {
+            IpcEvent::AgentMessageComplete(AgentMessageComplete { message_id })
+        }),
+        (
+            tool_call_id(),
+            message_id(),
+            text(),
+            option::of(command_id()),
+            option::of(action_id()),
+        )
+            .prop_map(|(call_id, message_id, arguments, session_id, action_id)| {
+                IpcEvent::ToolCallStarted(ToolCallStarted {
+                    call_id,
+                    message_id,
+                    name: "run_command".to_string(),
+                    arguments,
+                    session_id,
+                    action_id,
+                })
+            }),
+        (
+            tool_call_id(),
+            prop_oneof![
+                Just(ToolCallStatus::Succeeded),
+                Just(ToolCallStatus::Failed),
+                Just(ToolCallStatus::Blocked)
+            ],
+            0..60_000_u64,
+        )
+            .prop_map(|(call_id, status, duration_ms)| {
+                IpcEvent::ToolCallFinished(ToolCallFinished {
+                    call_id,
+                    status,
+                    duration_ms,
+                })
+            }),
+    ]
+}
+
+pub fn ipc_message() -> impl Strategy<Value = IpcMessage> {
+    ipc_event().prop_map(IpcMessage::new)
+}
+
+/// Proposals with their approval requests, interleaved with resolutions for any action in
+/// the pool: resolutions may arrive before, after or instead of the request, and twice.
+pub fn approval_sequence() -> impl Strategy<Value = Vec<IpcEvent>> {
+    let request = (action_id(), action_kind(), text(), expires_at_unix_s()).prop_map(
+        |(action_id, action_kind, summary, expires_at_unix_s)| {
+            vec![
+                IpcEvent::ActionProposed(ActionProposed {
+                    action_id: action_id.clone(),
+                    action_kind,
+                    target: summary.clone(),
+                }),
+                IpcEvent::ApprovalRequested(ApprovalRequested {
+                    action_id,
+                    summary,
+                    expires_at_unix_s,
+                }),
+            ]
+        },
+    );
+    vec(
+        prop_oneof![request, approval_resolved().prop_map(|event| vec![event])],
+        1..24,
+    )
+    .prop_map(|groups| groups.into_iter().flatten().collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffLine {
+    Context,
+    Removed,
+    Added,
+}
+
+fn diff_hunk_lines() -> impl Strategy<Value = Vec<(DiffLine, String)>> {
+    vec(
+        (
+            prop_oneof![
+                Just(DiffLine::Context),
+                Just(DiffLine::Removed),
+                Just(DiffLine::Added)
+            ],
+            text(),
+        ),
+        1..8,
+    )
+}
+
+/// Body of a unified diff for one file: one to four well-formed hunks whose headers match
+/// their line counts, without the `---`/`+++` file header.
+pub fn unified_diff() -> impl Strategy<Value = String> {
+    vec(diff_hunk_lines(), 1..5).prop_map(|hunks| {
+        let mut diff = String::new();
+        let mut old_start = 1;
+        let mut new_start = 1;
+        for lines in hunks {
+            let count = |kind| lines.iter().filter(|(line, _)| *line == kind).count();
+            let context = count(DiffLine::Context);
+            let old_count = context + count(DiffLine::Removed);
+            let new_count = context + count(DiffLine::Added);
+            diff.push_str(&format!(
+                "@@ -{old_start},{old_count} +{new_start},{new_count} @@\n"
+            ));
+            for (line, content) in &lines {
+                let prefix = match line {
+                    DiffLine::Context => ' ',
+                    DiffLine::Removed => '-',
+                    DiffLine::Added => '+',
+                };
+                diff.push(prefix);
+                diff.push_str(content);
+                diff.push('\n');
+            }
+            old_start += old_count + DIFF_HUNK_GAP;
+            new_start += new_count + DIFF_HUNK_GAP;
+        }
+        diff
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::ipc_message;
+    use super::unified_diff;
+    use crate::IpcMessage;
+
+    proptest! {
+        #[test]
+        fn generated_messages_round_trip_through_json(message in ipc_message()) {
+            let json = serde_json::to_string(&message)?;
+            let decoded: IpcMessage = serde_json::from_str(&json)?;
+            prop_assert_eq!(decoded, message);
+        }
+
+        #[test]
+        fn generated_diff_headers_match_their_bodies(diff in unified_diff()) {
+            let mut expected = None;
+            let mut seen = (0, 0);
+            for line in diff.lines() {
+                if let Some(header) = line.strip_prefix("@@ -") {
+                    prop_assert_eq!(expected.take().unwrap_or(seen), seen);
+                    let counts: Vec<usize> = header
+                        .trim_end_matches(" @@")
+                        .split(" +")
+                        .filter_map(|range| range.split_once(',')?.1.parse().ok())
+                        .collect();
+                    prop_assert_eq!(counts.len(), 2);
+                    expected = Some((counts[0], counts[1]));
+                    seen = (0, 0);
+                    continue;
+                }
+                match line.chars().next() {
+                    Some('-') => seen.0 += 1,
+                    Some('+') => seen.1 += 1,
+                    _ => {
+                        seen.0 += 1;
+                        seen.1 += 1;
+                    }
+                }
+            }
+            prop_assert_eq!(expected.unwrap_or(seen), seen);
+        }
+    }
+}
@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(diff) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(hunks) = codex_alicia_ui::parse_unified_diff_hunks(diff) else {
+        return;
+    };
+    // Accepted hunks must hold exactly the lines their headers declare.
+    for hunk in hunks {
+        let context = hunk
+            .lines
+            .iter()
+            .filter(|line| !line.starts_with(['+', '-', '\\']))
+            .count();
+        assert_eq!(hunk.old_count, context + hunk.removed_lines);
+        assert_eq!(hunk.new_count, context + hunk.added_lines);
+    }
+});
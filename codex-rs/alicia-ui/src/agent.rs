@@ -0,0 +1,482 @@
+//! Prompt → model → tool-call loop. The model only ever sees the conversation; the runtime
+//! turns its tool calls into the same proposals, approvals and sessions a human would go
+//! through, and feeds the results back until the model answers without calling tools.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use codex_alicia_core::ipc::ToolCallStatus;
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+use crate::ChatMessage;
+use crate::TokenUsage;
+
+/// Model calls allowed per prompt, so a model that keeps calling tools cannot loop forever.
+pub const MAX_AGENT_STEPS_PER_PROMPT: usize = 8;
+/// Trailing output lines of a command returned to the model.
+pub const MAX_AGENT_TOOL_OUTPUT_LINES: usize = 40;
+/// Bytes of a file returned to the model by `ReadFile`.
+pub const MAX_AGENT_READ_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgentRole {
+    User,
+    Assistant,
+    Tool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgentTool {
+    /// `command[0]` is the program; `cwd` defaults to the workspace root.
+    RunCommand {
+        command: Vec<String>,
+        cwd: Option<String>,
+    },
+    /// Path relative to the workspace root.
+    ReadFile { path: String },
+    ProposePatch {
+        file_path: String,
+        unified_diff: String,
+    },
+}
+
+impl AgentTool {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::RunCommand { .. } => "run_command",
+            Self::ReadFile { .. } => "read_file",
+            Self::ProposePatch { .. } => "propose_patch",
+        }
+    }
+
+    /// One line describing the arguments, for the chat.
+    pub fn arguments_summary(&self) -> String {
+        match self {
+            Self::RunCommand {
+                command,
+                cwd: Some(cwd),
+            } => format!("{} (em {cwd})", command.join(" ")),
+            Self::RunCommand { command, cwd: None } => command.join(" "),
+            Self::ReadFile { path } => path.clone(),
+            Self::ProposePatch { file_path, .. } => file_path.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentToolCall {
+    pub id: String,
+    pub tool: AgentTool,
+}
+
+/// One entry of the conversation sent to the model. Tool results carry the id of the call
+/// they answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentMessage {
+    pub role: AgentRole,
+    pub content: String,
+    pub tool_calls: Vec<AgentToolCall>,
+    pub tool_call_id: Option<String>,
+}
+
+impl AgentMessage {
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: AgentRole::User,
+            content: content.into(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+
+    pub fn tool_result(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            role: AgentRole::Tool,
+            content: content.into(),
+            tool_calls: Vec::new(),
+            tool_call_id: Some(tool_call_id.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentRequest {
+    pub messages: Vec<AgentMessage>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AgentReply {
+    pub text: String,
+    pub tool_calls: Vec<AgentToolCall>,
+    /// `None` when the provider does not report usage.
+    pub usage: Option<TokenUsage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AgentError {
+    #[error("model `{model}` request failed: {reason}")]
+    ModelFailed { model: String, reason: String },
+}
+
+#[async_trait]
+pub trait AgentModel: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn complete(&self, request: &AgentRequest) -> Result<AgentReply, AgentError>;
+
+    /// Sends the reply text through `deltas` as it is generated; the returned reply holds the
+    /// whole text. Models that cannot stream send it as a single delta.
+    async fn stream(
+        &self,
+        request: &AgentRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<AgentReply, AgentError> {
+        let reply = self.complete(request).await?;
+        if !reply.text.is_empty() {
+            let _ = deltas.send(reply.text.clone());
+        }
+        Ok(reply)
+    }
+}
+
+/// Conversation with one model plus the tool calls still waiting on a session.
+pub struct AgentLoop {
+    model: Arc<dyn AgentModel>,
+    history: Vec<AgentMessage>,
+    /// `(tool call id, session id)` of commands that have not finished yet.
+    waiting_sessions: Vec<(String, String)>,
+    needs_reply: bool,
+    steps_left: usize,
+    next_id: u64,
+    next_message_id: u64,
+    /// Spending the loop may reach before pausing; starts at the store's budget limit.
+    budget_ceiling_usd: Option<f64>,
+    /// Approval the loop is paused behind after going over budget.
+    budget_approval_id: Option<String>,
+}
+
+impl AgentLoop {
+    pub fn new(model: Arc<dyn AgentModel>) -> Self {
+        Self {
+            model,
+            history: Vec::new(),
+            waiting_sessions: Vec::new(),
+            needs_reply: false,
+            steps_left: MAX_AGENT_STEPS_PER_PROMPT,
+            next_id: 0,
+            next_message_id: 0,
+            budget_ceiling_usd: None,
+            budget_approval_id: None,
+        }
+    }
+
+    pub fn model_name(&self) -> &str {
+        self.model.name()
+    }
+
+    /// Switches the model answering from the next reply on; the conversation is kept.
+    pub fn set_model(&mut self, model: Arc<dyn AgentModel>) {
+        self.model = model;
+    }
+
+    pub fn history(&self) -> &[AgentMessage] {
+        &self.history
+    }
+
+    /// Whether the loop has nothing left to do until the next prompt.
+    pub fn is_idle(&self) -> bool {
+        !self.needs_reply && self.waiting_sessions.is_empty()
+    }
+
+    pub(crate) fn push_prompt(&mut self, prompt: &ChatMessage) {
+        let mut content = prompt.content.clone();
+        if !prompt.attachments.is_empty() {
+            let paths: Vec<&str> = prompt
+                .attachments
+                .iter()
+                .map(|attachment| attachment.path.as_str())
+                .collect();
+            content.push_str(&format!("\n\nAnexos: {}", paths.join(", ")));
+        }
+        self.history.push(AgentMessage::user(content));
+        self.needs_reply = true;
+        self.steps_left = MAX_AGENT_STEPS_PER_PROMPT;
+    }
+
+    /// Adds the contents of an attached file ahead of the prompt that references it.
+    pub(crate) fn push_context_file(&mut self, path: &str, content: &str) {
+        self.history.push(AgentMessage::user(format!(
+            "Conteudo de `{path}`:\n```\n{content}\n```"
+        )));
+    }
+
+    pub(crate) fn push_tool_result(&mut self, tool_call_id: &str, content: impl Into<String>) {
+        self.history
+            .push(AgentMessage::tool_result(tool_call_id, content));
+        self.needs_reply = true;
+    }
+
+    pub(crate) fn wait_for_session(&mut self, tool_call_id: String, session_id: String) {
+        self.waiting_sessions.push((tool_call_id, session_id));
+    }
+
+    pub(crate) fn waiting_sessions(&self) -> &[(String, String)] {
+        &self.waiting_sessions
+    }
+
+    pub(crate) fn stop_waiting(&mut self, tool_call_id: &str) {
+        self.waiting_sessions.retain(|(id, _)| id != tool_call_id);
+    }
+
+    /// Id for a session or action created on the model's behalf.
+    pub(crate) fn next_id(&mut self) -> String {
+        let id = format!("agent-{}", self.next_id);
+        self.next_id = self.next_id.saturating_add(1);
+        id
+    }
+
+    /// Whether every tool result is in and the model owes an answer.
+    pub(crate) fn ready_for_model(&self) -> bool {
+        self.needs_reply && self.waiting_sessions.is_empty()
+    }
+
+    pub(crate) fn budget_ceiling_usd(&mut self, limit_usd: f64) -> f64 {
+        *self.budget_ceiling_usd.get_or_insert(limit_usd)
+    }
+
+    pub(crate) fn raise_budget_ceiling(&mut self, ceiling_usd: f64) {
+        self.budget_ceiling_usd = Some(ceiling_usd);
+    }
+
+    pub(crate) fn budget_approval_id(&self) -> Option<&str> {
+        self.budget_approval_id.as_deref()
+    }
+
+    pub(crate) fn set_budget_approval_id(&mut self, action_id: Option<String>) {
+        self.budget_approval_id = action_id;
+    }
+
+    /// Drops what is left of the current prompt: pending tool results are not awaited and
+    /// the model is not asked again.
+    pub(crate) fn abandon_prompt(&mut self) {
+        self.needs_reply = false;
+        self.waiting_sessions.clear();
+    }
+
+    /// Id of the streamed message carrying the next reply.
+    pub(crate) fn next_message_id(&mut self) -> String {
+        let id = format!("agent-msg-{}", self.next_message_id);
+        self.next_message_id = self.next_message_id.saturating_add(1);
+        id
+    }
+
+    /// Streams the conversation's next reply, handing each text delta to `on_delta` as it
+    /// arrives, and records the reply. `Ok(None)` means the step budget of the current prompt
+    /// ran out.
+    pub(crate) async fn request_reply(
+        &mut self,
+        mut on_delta: impl FnMut(&str),
+    ) -> Result<Option<AgentReply>, AgentError> {
+        self.needs_reply = false;
+        if self.steps_left == 0 {
+            return Ok(None);
+        }
+        self.steps_left -= 1;
+        let model = Arc::clone(&self.model);
+        let request = AgentRequest {
+            messages: self.history.clone(),
+        };
+        let (deltas_tx, mut deltas_rx) = mpsc::unbounded_channel();
+        let reply = {
+            let mut reply = std::pin::pin!(model.stream(&request, deltas_tx));
+            loop {
+                tokio::select! {
+                    result = &mut reply => break result?,
+                    Some(delta) = deltas_rx.recv() => on_delta(&delta),
+                }
+            }
+        };
+        while let Ok(delta) = deltas_rx.try_recv() {
+            on_delta(&delta);
+        }
+        self.history.push(AgentMessage {
+            role: AgentRole::Assistant,
+            content: reply.text.clone(),
+            tool_calls: reply.tool_calls.clone(),
+            tool_call_id: None,
+        });
+        Ok(Some(reply))
+    }
+}
+
+impl std::fmt::Debug for AgentLoop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentLoop")
+            .field("model", &self.model.name())
+            .field("history", &self.history)
+            .field("waiting_sessions", &self.waiting_sessions)
+            .field("needs_reply", &self.needs_reply)
+            .field("steps_left", &self.steps_left)
+            .field("budget_approval_id", &self.budget_approval_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Output tail of a finished command, as returned to the model.
+pub(crate) fn command_tool_result(exit_code: i32, lines: &[String]) -> String {
+    let tail = &lines[lines.len().saturating_sub(MAX_AGENT_TOOL_OUTPUT_LINES)..];
+    format!("exit_code={exit_code}\n{}", tail.join("\n"))
+}
+
+/// Status of a tool result, read from the prefixes the runtime gives failed and blocked calls.
+pub(crate) fn tool_result_status(result: &str) -> ToolCallStatus {
+    if result.starts_with("bloqueado:") {
+        ToolCallStatus::Blocked
+    } else if result.starts_with("erro:")
+        || (result.starts_with("exit_code=") && !result.starts_with("exit_code=0\n"))
+    {
+        ToolCallStatus::Failed
+    } else {
+        ToolCallStatus::Succeeded
+    }
+}
+
+/// Model that replays canned replies and records every request, for tests and demos.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug)]
+pub struct ScriptedAgentModel {
+    name: String,
+    replies: std::sync::Mutex<std::collections::VecDeque<AgentReply>>,
+    requests: std::sync::Mutex<Vec<AgentRequest>>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl ScriptedAgentModel {
+    pub fn new(replies: impl IntoIterator<Item = AgentReply>) -> Self {
+        Self {
+            name: "scripted".to_string(),
+            replies: std::sync::Mutex::new(replies.into_iter().collect()),
+            requests: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn requests(&self) -> Vec<AgentRequest> {
+        self.requests
+            .lock()
+            .map(|requests| requests.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+#[async_trait]
+impl AgentModel for ScriptedAgentModel {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn complete(&self, request: &AgentRequest) -> Result<AgentReply, AgentError> {
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.push(request.clone());
+        }
+        self.replies
+            .lock()
+            .ok()
+            .and_then(|mut replies| replies.pop_front())
+            .ok_or_else(|| AgentError::ModelFailed {
+                model: self.name().to_string(),
+                reason: "no scripted reply left".to_string(),
+            })
+    }
+
+    /// Streams the canned text word by word.
+    async fn stream(
+        &self,
+        request: &AgentRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<AgentReply, AgentError> {
+        let reply = self.complete(request).await?;
+        for word in reply.text.split_inclusive(' ') {
+            let _ = deltas.send(word.to_string());
+        }
+        Ok(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use codex_alicia_core::ipc::ToolCallStatus;
+    use pretty_assertions::assert_eq;
+
+    use super::AgentLoop;
+    use super::AgentReply;
+    use super::AgentRole;
+    use super::MAX_AGENT_STEPS_PER_PROMPT;
+    use super::ScriptedAgentModel;
+    use super::command_tool_result;
+    use super::tool_result_status;
+    use crate::ChatMessage;
+    use crate::ChatRole;
+    use crate::ContextAttachment;
+
+    #[tokio::test]
+    async fn loop_stops_asking_the_model_once_the_step_budget_is_spent() {
+        let replies = (0..=MAX_AGENT_STEPS_PER_PROMPT).map(|step| AgentReply {
+            text: format!("passo {step}"),
+            tool_calls: Vec::new(),
+            usage: None,
+        });
+        let model = Arc::new(ScriptedAgentModel::new(replies));
+        let mut agent = AgentLoop::new(model.clone());
+        let mut prompt = ChatMessage::new(0, ChatRole::User, "corrija o teste");
+        prompt.attachments.push(ContextAttachment {
+            path: "src/lib.rs".to_string(),
+            size_bytes: 10,
+        });
+        agent.push_prompt(&prompt);
+
+        let mut replies = 0;
+        while agent.ready_for_model() {
+            if agent.request_reply(|_| {}).await.ok().flatten().is_none() {
+                break;
+            }
+            replies += 1;
+            agent.push_tool_result("call", "ok");
+        }
+
+        assert_eq!(replies, MAX_AGENT_STEPS_PER_PROMPT);
+        assert!(agent.is_idle());
+        let requests = model.requests();
+        assert_eq!(requests.len(), MAX_AGENT_STEPS_PER_PROMPT);
+        assert_eq!(
+            requests[0].messages[0].content,
+            "corrija o teste\n\nAnexos: src/lib.rs"
+        );
+        assert_eq!(agent.history()[1].role, AgentRole::Assistant);
+    }
+
+    #[test]
+    fn command_results_keep_the_output_tail() {
+        let lines: Vec<String> = (0..100).map(|line| format!("linha {line}")).collect();
+        let result = command_tool_result(1, &lines);
+        assert!(result.starts_with("exit_code=1\nlinha 60\n"));
+        assert!(result.ends_with("linha 99"));
+        assert_eq!(tool_result_status(&result), ToolCallStatus::Failed);
+        assert_eq!(
+            tool_result_status(&command_tool_result(0, &lines)),
+            ToolCallStatus::Succeeded
+        );
+        assert_eq!(
+            tool_result_status("bloqueado: fora do workspace"),
+            ToolCallStatus::Blocked
+        );
+    }
+}
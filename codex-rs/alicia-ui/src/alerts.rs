@@ -0,0 +1,174 @@
+use std::process::Command;
+
+const BEL: char = '\u{7}';
+const ESC: char = '\u{1b}';
+
+/// Events that can ring or flash the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertEvent {
+    /// A session printed a BEL outside an escape sequence.
+    Bell,
+    ApprovalRequested,
+    /// A command finished with a non-zero exit code.
+    CommandFailed,
+}
+
+impl AlertEvent {
+    pub const ALL: [Self; 3] = [Self::Bell, Self::ApprovalRequested, Self::CommandFailed];
+}
+
+/// How an alert shows up: a sound, a flash of the window, both or neither.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AlertChannels {
+    pub audible: bool,
+    pub visual: bool,
+}
+
+impl AlertChannels {
+    pub fn is_off(self) -> bool {
+        !self.audible && !self.visual
+    }
+}
+
+/// Alert channels per event. Only the bell flashes by default; sounds are opt-in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlertSettings {
+    pub bell: AlertChannels,
+    pub approval_requested: AlertChannels,
+    pub command_failed: AlertChannels,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        Self {
+            bell: AlertChannels {
+                audible: false,
+                visual: true,
+            },
+            approval_requested: AlertChannels::default(),
+            command_failed: AlertChannels::default(),
+        }
+    }
+}
+
+impl AlertSettings {
+    pub fn channels(&self, event: AlertEvent) -> AlertChannels {
+        match event {
+            AlertEvent::Bell => self.bell,
+            AlertEvent::ApprovalRequested => self.approval_requested,
+            AlertEvent::CommandFailed => self.command_failed,
+        }
+    }
+
+    pub fn channels_mut(&mut self, event: AlertEvent) -> &mut AlertChannels {
+        match event {
+            AlertEvent::Bell => &mut self.bell,
+            AlertEvent::ApprovalRequested => &mut self.approval_requested,
+            AlertEvent::CommandFailed => &mut self.command_failed,
+        }
+    }
+}
+
+/// An alert raised by the store, for the view to play and flash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub event: AlertEvent,
+    /// Session or approval the alert is about.
+    pub source: String,
+    pub channels: AlertChannels,
+}
+
+/// Finds BEL characters that ring the bell, leaving alone the ones ending an OSC sequence
+/// (`ESC ]` ... `BEL`), even when the sequence is split across chunks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct BellDetector {
+    after_escape: bool,
+    in_osc: bool,
+}
+
+impl BellDetector {
+    /// `text` without the bells it rang, and how many there were.
+    pub(crate) fn feed(&mut self, text: &str) -> (String, usize) {
+        let mut output = String::with_capacity(text.len());
+        let mut bells = 0;
+        for ch in text.chars() {
+            let after_escape = std::mem::take(&mut self.after_escape);
+            match ch {
+                BEL if self.in_osc => self.in_osc = false,
+                BEL => {
+                    bells += 1;
+                    continue;
+                }
+                ESC => self.after_escape = true,
+                ']' if after_escape => self.in_osc = true,
+                '\\' if after_escape => self.in_osc = false,
+                _ => {}
+            }
+            output.push(ch);
+        }
+        (output, bells)
+    }
+}
+
+/// Plays the system alert sound through a helper: `afplay` on macOS, PowerShell's
+/// `SystemSounds` on Windows and `canberra-gtk-play` elsewhere. Returns once the helper is
+/// spawned; it is reaped on a background thread.
+pub fn play_alert_sound() -> std::io::Result<()> {
+    let mut child = alert_sound_command().spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn alert_sound_command() -> Command {
+    let mut command = Command::new("afplay");
+    command.arg("/System/Library/Sounds/Ping.aiff");
+    command
+}
+
+#[cfg(windows)]
+fn alert_sound_command() -> Command {
+    let mut command = Command::new("powershell");
+    command.args([
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        "[System.Media.SystemSounds]::Exclamation.Play()",
+    ]);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn alert_sound_command() -> Command {
+    let mut command = Command::new("canberra-gtk-play");
+    command.args(["--id", "bell", "--description", "AlicIA"]);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::BellDetector;
+
+    #[test]
+    fn counts_bells_outside_osc_sequences() {
+        let mut detector = BellDetector::default();
+        assert_eq!(
+            detector.feed("pronto\u{7}\u{1b}]0;titulo\u{7}ok"),
+            ("pronto\u{1b}]0;titulo\u{7}ok".to_string(), 1)
+        );
+        assert_eq!(
+            detector.feed("\u{1b}]2;meio"),
+            ("\u{1b}]2;meio".to_string(), 0)
+        );
+        assert_eq!(
+            detector.feed(" do titulo\u{7}\u{7}\u{7}"),
+            (" do titulo\u{7}".to_string(), 2)
+        );
+        assert_eq!(
+            detector.feed("\u{1b}]8;;x\u{1b}\\\u{7}"),
+            ("\u{1b}]8;;x\u{1b}\\".to_string(), 1)
+        );
+    }
+}
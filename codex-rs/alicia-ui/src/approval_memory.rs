@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+pub const APPROVAL_MEMORY_FILE_NAME: &str = "alicia-approval-memory.json";
+
+/// Approvals remembered across runs, keyed by workspace root.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ApprovalMemory {
+    pub workspaces: BTreeMap<String, RememberedApprovals>,
+}
+
+/// What a workspace remembers. Nothing is consulted until `enabled` is turned on.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RememberedApprovals {
+    pub enabled: bool,
+    pub commands: Vec<RememberedCommand>,
+    pub targets: Vec<RememberedTarget>,
+}
+
+/// Approves exactly the command `prefix`, or with `any_arguments` every command whose
+/// leading words are `prefix`, like a `CommandRule` of the project policy.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RememberedCommand {
+    pub prefix: Vec<String>,
+    pub remembered_at_unix_s: i64,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub any_arguments: bool,
+}
+
+impl RememberedCommand {
+    pub fn matches(&self, command: &[String]) -> bool {
+        if self.any_arguments {
+            command.len() >= self.prefix.len()
+                && command.iter().zip(&self.prefix).all(|(a, b)| a == b)
+        } else {
+            command == self.prefix.as_slice()
+        }
+    }
+}
+
+/// Approves actions on `path`, relative to the workspace root, and anything under it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RememberedTarget {
+    pub path: String,
+    pub remembered_at_unix_s: i64,
+}
+
+#[derive(Debug, Error)]
+pub enum ApprovalMemoryError {
+    #[error("failed to read approval memory `{path}`: {source}")]
+    ReadFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("approval memory `{path}` is invalid: {source}")]
+    InvalidFile {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("failed to write approval memory `{path}`: {source}")]
+    WriteFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// `$CODEX_HOME/alicia-approval-memory.json`, falling back to `~/.codex`.
+pub fn default_approval_memory_path() -> Option<PathBuf> {
+    let codex_home = std::env::var_os("CODEX_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".codex")))?;
+    Some(codex_home.join(APPROVAL_MEMORY_FILE_NAME))
+}
+
+impl ApprovalMemory {
+    /// Missing files remember nothing.
+    pub fn load(path: &Path) -> Result<Self, ApprovalMemoryError> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(source) if source.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(source) => {
+                return Err(ApprovalMemoryError::ReadFailed {
+                    path: path.to_string_lossy().to_string(),
+                    source,
+                });
+            }
+        };
+        serde_json::from_str(&text).map_err(|source| ApprovalMemoryError::InvalidFile {
+            path: path.to_string_lossy().to_string(),
+            source,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ApprovalMemoryError> {
+        let write_failed = |source| ApprovalMemoryError::WriteFailed {
+            path: path.to_string_lossy().to_string(),
+            source,
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(write_failed)?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(std::io::Error::other);
+        std::fs::write(path, text.map_err(write_failed)?).map_err(write_failed)
+    }
+
+    pub fn for_workspace(&self, workspace_root: &Path) -> RememberedApprovals {
+        self.workspaces
+            .get(workspace_root.to_string_lossy().as_ref())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Replaces what `workspace_root` remembers; an empty, disabled memory drops the entry.
+    pub fn set_workspace(&mut self, workspace_root: &Path, remembered: RememberedApprovals) {
+        let key = workspace_root.to_string_lossy().to_string();
+        if remembered == RememberedApprovals::default() {
+            self.workspaces.remove(&key);
+        } else {
+            self.workspaces.insert(key, remembered);
+        }
+    }
+}
+
+impl RememberedApprovals {
+    /// Remembers exactly `command`. Returns false when it is empty or already remembered.
+    pub fn remember_command(&mut self, command: Vec<String>, now_unix_s: i64) -> bool {
+        if command.is_empty() || self.commands.iter().any(|known| known.prefix == command) {
+            return false;
+        }
+        self.commands.push(RememberedCommand {
+            prefix: command,
+            remembered_at_unix_s: now_unix_s,
+            any_arguments: false,
+        });
+        true
+    }
+
+    /// Remembers `path` relative to `workspace_root`, with `.` and `..` resolved. Returns
+    /// false when it is already remembered or would cover the whole workspace or anything
+    /// outside it, like `.`, `src/..` or the root itself.
+    pub fn remember_target(&mut self, workspace_root: &Path, path: &str, now_unix_s: i64) -> bool {
+        let target = workspace_relative(workspace_root, Path::new(path.trim()));
+        if too_broad(&target) {
+            return false;
+        }
+        let path = target.to_string_lossy().to_string();
+        if self.targets.iter().any(|known| known.path == path) {
+            return false;
+        }
+        self.targets.push(RememberedTarget {
+            path,
+            remembered_at_unix_s: now_unix_s,
+        });
+        true
+    }
+
+    pub fn revoke_command(&mut self, prefix: &[String]) -> bool {
+        let before = self.commands.len();
+        self.commands.retain(|command| command.prefix != prefix);
+        self.commands.len() != before
+    }
+
+    pub fn revoke_target(&mut self, path: &str) -> bool {
+        let before = self.targets.len();
+        self.targets.retain(|target| target.path != path);
+        self.targets.len() != before
+    }
+
+    /// The remembered command approving `command`, the longest when several match.
+    pub fn command_match(&self, command: &[String]) -> Option<&RememberedCommand> {
+        if !self.enabled {
+            return None;
+        }
+        self.commands
+            .iter()
+            .filter(|remembered| remembered.matches(command))
+            .max_by_key(|remembered| remembered.prefix.len())
+    }
+
+    /// Whether `path` is a remembered target or under one, both taken relative to
+    /// `workspace_root` after resolving `.` and `..`, so `src/generated/../../secrets` is not
+    /// under `src/generated`. Targets covering the whole workspace never match.
+    pub fn covers_target(&self, workspace_root: &Path, path: &str) -> bool {
+        let path = workspace_relative(workspace_root, Path::new(path));
+        self.enabled
+            && self.targets.iter().any(|target| {
+                let target = workspace_relative(workspace_root, Path::new(&target.path));
+                !too_broad(&target) && path.starts_with(target)
+            })
+    }
+}
+
+/// `path` relative to `workspace_root` when it is under it, after `normalize_path`.
+fn workspace_relative(workspace_root: &Path, path: &Path) -> PathBuf {
+    let path = normalize_path(path);
+    match path.strip_prefix(normalize_path(workspace_root)) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => path,
+    }
+}
+
+/// Whether a target from `workspace_relative` is the workspace root or outside it.
+fn too_broad(target: &Path) -> bool {
+    target.as_os_str().is_empty()
+        || target.has_root()
+        || matches!(
+            target.components().next(),
+            Some(Component::ParentDir | Component::Prefix(_))
+        )
+}
+
+/// Resolves `.` and `..` lexically; a `..` with nothing left to climb out of is kept.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::ApprovalMemory;
+    use super::RememberedApprovals;
+    use super::RememberedCommand;
+    use super::RememberedTarget;
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn remembers_commands_and_targets_per_workspace_once_enabled()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let config = TempDir::new()?;
+        let path = config.path().join("memory.json");
+        assert_eq!(ApprovalMemory::load(&path)?, ApprovalMemory::default());
+
+        let mut remembered = RememberedApprovals::default();
+        assert!(remembered.remember_command(words("cargo test"), 10));
+        assert!(!remembered.remember_command(words("cargo test"), 20));
+        assert!(!remembered.remember_command(Vec::new(), 20));
+        assert!(remembered.remember_target(Path::new("/repo"), "src/generated", 30));
+        assert_eq!(remembered.command_match(&words("cargo test -p core")), None);
+
+        remembered.enabled = true;
+        assert_eq!(
+            remembered
+                .command_match(&words("cargo test"))
+                .map(|command| command.prefix.clone()),
+            Some(words("cargo test"))
+        );
+        assert_eq!(remembered.command_match(&words("cargo test -p core")), None);
+        assert_eq!(remembered.command_match(&words("cargo build")), None);
+        remembered.commands.push(RememberedCommand {
+            prefix: words("cargo fmt"),
+            remembered_at_unix_s: 40,
+            any_arguments: true,
+        });
+        assert!(
+            remembered
+                .command_match(&words("cargo fmt --check"))
+                .is_some()
+        );
+        remembered.commands.pop();
+        let root = Path::new("/repo");
+        assert!(remembered.covers_target(root, "src/generated/api.rs"));
+        assert!(remembered.covers_target(root, "./src/generated/./api.rs"));
+        assert!(remembered.covers_target(root, "/repo/src/generated/api.rs"));
+        assert!(!remembered.covers_target(root, "src/generated_old.rs"));
+        assert!(!remembered.covers_target(root, "src/generated/../../secrets.env"));
+
+        let mut memory = ApprovalMemory::default();
+        memory.set_workspace(Path::new("/repo"), remembered.clone());
+        memory.save(&path)?;
+        let restored = ApprovalMemory::load(&path)?;
+        assert_eq!(restored.for_workspace(Path::new("/repo")), remembered);
+        assert_eq!(
+            restored.for_workspace(Path::new("/other")),
+            RememberedApprovals::default()
+        );
+
+        assert!(remembered.revoke_command(&words("cargo test")));
+        assert!(!remembered.revoke_command(&words("cargo test")));
+        assert!(remembered.revoke_target("src/generated"));
+        remembered.enabled = false;
+        memory.set_workspace(Path::new("/repo"), remembered);
+        assert!(memory.workspaces.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn targets_covering_the_whole_workspace_are_never_remembered() {
+        let root = Path::new("/repo");
+        let mut remembered = RememberedApprovals {
+            enabled: true,
+            ..Default::default()
+        };
+        for target in [
+            ".", "./", "src/..", "", "/repo", "/repo/.", "..", "../other", "/",
+        ] {
+            assert!(!remembered.remember_target(root, target, 10), "{target}");
+        }
+        assert!(remembered.targets.is_empty());
+
+        assert!(remembered.remember_target(root, "/repo/./src/generated/", 10));
+        assert_eq!(remembered.targets[0].path, "src/generated");
+        assert!(!remembered.remember_target(root, "src/generated", 20));
+
+        // A memory file written before targets were checked still covers nothing broad.
+        remembered.targets.push(RememberedTarget {
+            path: "./".to_string(),
+            remembered_at_unix_s: 30,
+        });
+        assert!(!remembered.covers_target(root, "src/main.rs"));
+        assert!(remembered.covers_target(root, "src/generated/api.rs"));
+    }
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use codex_alicia_core::ActionKind;
+use codex_alicia_core::ApprovalResolution;
+
+/// How long approvals of one action kind waited between `ApprovalRequested` and
+/// `ApprovalResolved`. `action_kind` is `None` for approvals requested without an
+/// `ActionProposed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalLatencyStats {
+    pub action_kind: Option<ActionKind>,
+    pub approved: u64,
+    pub denied: u64,
+    pub expired: u64,
+    /// Sorted, so percentiles are a lookup.
+    latencies_ms: Vec<u64>,
+}
+
+impl ApprovalLatencyStats {
+    pub(crate) fn new(action_kind: Option<ActionKind>) -> Self {
+        Self {
+            action_kind,
+            approved: 0,
+            denied: 0,
+            expired: 0,
+            latencies_ms: Vec::new(),
+        }
+    }
+
+    pub(crate) fn record(&mut self, resolution: ApprovalResolution, latency: Duration) {
+        let counter = match resolution {
+            ApprovalResolution::Approved => &mut self.approved,
+            ApprovalResolution::Denied => &mut self.denied,
+            ApprovalResolution::Expired => &mut self.expired,
+        };
+        *counter = counter.saturating_add(1);
+        let latency_ms = u64::try_from(latency.as_millis()).unwrap_or(u64::MAX);
+        let index = self
+            .latencies_ms
+            .partition_point(|sample| *sample <= latency_ms);
+        self.latencies_ms.insert(index, latency_ms);
+    }
+
+    pub fn resolved(&self) -> u64 {
+        self.latencies_ms.len() as u64
+    }
+
+    /// Nearest-rank percentile, `percentile` from 0.0 to 100.0; `None` before any resolution.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let last = self.latencies_ms.len().checked_sub(1)?;
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * self.latencies_ms.len() as f64).ceil();
+        let index = (rank as usize).saturating_sub(1).min(last);
+        Some(Duration::from_millis(self.latencies_ms[index]))
+    }
+
+    /// Time agents spent waiting on these approvals, summed.
+    pub fn total_wait(&self) -> Duration {
+        Duration::from_millis(self.latencies_ms.iter().sum())
+    }
+}
+
+/// Snapshot returned by `UiEventStore::approval_metrics`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApprovalMetrics {
+    pub by_kind: Vec<ApprovalLatencyStats>,
+    pub pending: usize,
+    /// How long the oldest approval still pending has waited.
+    pub oldest_pending_wait: Option<Duration>,
+    /// Resolutions per hour since the first approval was requested.
+    pub resolved_per_hour: Option<f64>,
+}
+
+impl ApprovalMetrics {
+    pub fn resolved(&self) -> u64 {
+        self.by_kind
+            .iter()
+            .map(ApprovalLatencyStats::resolved)
+            .sum()
+    }
+
+    pub fn total_wait(&self) -> Duration {
+        self.by_kind
+            .iter()
+            .map(ApprovalLatencyStats::total_wait)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use codex_alicia_core::ActionKind;
+    use codex_alicia_core::ApprovalResolution;
+    use pretty_assertions::assert_eq;
+
+    use super::ApprovalLatencyStats;
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let mut stats = ApprovalLatencyStats::new(Some(ActionKind::ExecuteCommand));
+        assert_eq!(stats.percentile(50.0), None);
+        for (seconds, resolution) in [
+            (40, ApprovalResolution::Approved),
+            (10, ApprovalResolution::Approved),
+            (30, ApprovalResolution::Denied),
+            (20, ApprovalResolution::Approved),
+            (300, ApprovalResolution::Expired),
+        ] {
+            stats.record(resolution, Duration::from_secs(seconds));
+        }
+
+        assert_eq!((stats.approved, stats.denied, stats.expired), (3, 1, 1));
+        assert_eq!(stats.resolved(), 5);
+        assert_eq!(stats.percentile(0.0), Some(Duration::from_secs(10)));
+        assert_eq!(stats.percentile(50.0), Some(Duration::from_secs(30)));
+        assert_eq!(stats.percentile(90.0), Some(Duration::from_secs(300)));
+        assert_eq!(stats.total_wait(), Duration::from_secs(400));
+    }
+}
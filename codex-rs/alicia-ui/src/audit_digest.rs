@@ -0,0 +1,288 @@
+use std::ops::Range;
+
+use codex_alicia_core::ActionKind;
+use codex_alicia_core::ApprovalDecision;
+use codex_alicia_core::AuditRecord;
+use codex_alicia_core::ResultStatus;
+
+use crate::action_kind_name;
+
+const DIGEST_ACTION_KINDS: [ActionKind; 5] = [
+    ActionKind::ReadFile,
+    ActionKind::WriteFile,
+    ActionKind::ExecuteCommand,
+    ActionKind::ApplyPatch,
+    ActionKind::NetworkAccess,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditDigest {
+    pub range_start_unix_s: i64,
+    pub range_end_unix_s: i64,
+    pub total_records: usize,
+    pub commands_executed: usize,
+    pub commands_failed: usize,
+    pub approvals_granted: usize,
+    pub approvals_denied: usize,
+    pub approvals_expired: usize,
+    pub blocked_actions: usize,
+    pub action_kinds: Vec<AuditDigestActionKindRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditDigestActionKindRow {
+    pub action_kind: ActionKind,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub blocked: usize,
+}
+
+impl AuditDigestActionKindRow {
+    pub fn failure_rate(&self) -> Option<f64> {
+        failure_rate(self.failed, self.succeeded + self.failed)
+    }
+}
+
+impl AuditDigest {
+    pub fn from_records<'a>(
+        records: impl IntoIterator<Item = &'a AuditRecord>,
+        range: Range<i64>,
+    ) -> Self {
+        let mut digest = Self {
+            range_start_unix_s: range.start,
+            range_end_unix_s: range.end,
+            total_records: 0,
+            commands_executed: 0,
+            commands_failed: 0,
+            approvals_granted: 0,
+            approvals_denied: 0,
+            approvals_expired: 0,
+            blocked_actions: 0,
+            action_kinds: DIGEST_ACTION_KINDS
+                .iter()
+                .map(|action_kind| AuditDigestActionKindRow {
+                    action_kind: *action_kind,
+                    total: 0,
+                    succeeded: 0,
+                    failed: 0,
+                    blocked: 0,
+                })
+                .collect(),
+        };
+
+        for record in records
+            .into_iter()
+            .filter(|record| range.contains(&record.timestamp))
+        {
+            digest.total_records += 1;
+
+            match record.approval_decision {
+                ApprovalDecision::NotRequired => {}
+                ApprovalDecision::Approved => digest.approvals_granted += 1,
+                ApprovalDecision::Denied => digest.approvals_denied += 1,
+                ApprovalDecision::Expired => digest.approvals_expired += 1,
+            }
+
+            if record.action_kind == ActionKind::ExecuteCommand {
+                match record.result_status {
+                    ResultStatus::Succeeded => digest.commands_executed += 1,
+                    ResultStatus::Failed => {
+                        digest.commands_executed += 1;
+                        digest.commands_failed += 1;
+                    }
+                    ResultStatus::Blocked => {}
+                }
+            }
+
+            if record.result_status == ResultStatus::Blocked {
+                digest.blocked_actions += 1;
+            }
+
+            if let Some(row) = digest
+                .action_kinds
+                .iter_mut()
+                .find(|row| row.action_kind == record.action_kind)
+            {
+                row.total += 1;
+                match record.result_status {
+                    ResultStatus::Succeeded => row.succeeded += 1,
+                    ResultStatus::Failed => row.failed += 1,
+                    ResultStatus::Blocked => row.blocked += 1,
+                }
+            }
+        }
+
+        digest
+    }
+
+    pub fn command_failure_rate(&self) -> Option<f64> {
+        failure_rate(self.commands_failed, self.commands_executed)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        markdown.push_str("# Resumo de auditoria AlicIA\n\n");
+        markdown.push_str(&format!(
+            "Periodo (unix): {} ate {}\n\n",
+            self.range_start_unix_s, self.range_end_unix_s
+        ));
+        markdown.push_str("| Metrica | Valor |\n|---|---|\n");
+        for (label, value) in [
+            ("Registros", self.total_records.to_string()),
+            ("Comandos executados", self.commands_executed.to_string()),
+            ("Comandos com falha", self.commands_failed.to_string()),
+            (
+                "Taxa de falha de comandos",
+                format_rate(self.command_failure_rate()),
+            ),
+            ("Aprovacoes concedidas", self.approvals_granted.to_string()),
+            ("Aprovacoes negadas", self.approvals_denied.to_string()),
+            ("Aprovacoes expiradas", self.approvals_expired.to_string()),
+            ("Acoes bloqueadas", self.blocked_actions.to_string()),
+        ] {
+            markdown.push_str(&format!("| {label} | {value} |\n"));
+        }
+
+        markdown.push_str("\n## Por tipo de acao\n\n");
+        markdown.push_str("| Tipo | Total | Sucesso | Falha | Bloqueado | Taxa de falha |\n");
+        markdown.push_str("|---|---|---|---|---|---|\n");
+        for row in self.action_kinds.iter().filter(|row| row.total > 0) {
+            markdown.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                action_kind_name(row.action_kind),
+                row.total,
+                row.succeeded,
+                row.failed,
+                row.blocked,
+                format_rate(row.failure_rate())
+            ));
+        }
+
+        markdown
+    }
+}
+
+fn failure_rate(failed: usize, total: usize) -> Option<f64> {
+    if total == 0 {
+        return None;
+    }
+    Some(failed as f64 / total as f64)
+}
+
+fn format_rate(rate: Option<f64>) -> String {
+    rate.map_or_else(
+        || String::from("n/a"),
+        |rate| format!("{:.1}%", rate * 100.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::ActionKind;
+    use codex_alicia_core::ApprovalDecision;
+    use codex_alicia_core::AuditRecord;
+    use codex_alicia_core::PermissionProfile;
+    use codex_alicia_core::PolicyDecision;
+    use codex_alicia_core::ResultStatus;
+    use pretty_assertions::assert_eq;
+
+    use super::AuditDigest;
+
+    fn record_at(
+        timestamp: i64,
+        action_kind: ActionKind,
+        approval_decision: ApprovalDecision,
+        result_status: ResultStatus,
+    ) -> AuditRecord {
+        let mut record = AuditRecord::new(
+            "sess-digest",
+            action_kind,
+            "target",
+            PermissionProfile::ReadWriteWithApproval,
+            PolicyDecision::RequireApproval,
+            approval_decision,
+            result_status,
+            5,
+        );
+        record.timestamp = timestamp;
+        record
+    }
+
+    fn sample_records() -> Vec<AuditRecord> {
+        vec![
+            record_at(
+                90,
+                ActionKind::ExecuteCommand,
+                ApprovalDecision::Approved,
+                ResultStatus::Failed,
+            ),
+            record_at(
+                100,
+                ActionKind::ExecuteCommand,
+                ApprovalDecision::Approved,
+                ResultStatus::Succeeded,
+            ),
+            record_at(
+                110,
+                ActionKind::ExecuteCommand,
+                ApprovalDecision::Approved,
+                ResultStatus::Failed,
+            ),
+            record_at(
+                120,
+                ActionKind::ExecuteCommand,
+                ApprovalDecision::Denied,
+                ResultStatus::Blocked,
+            ),
+            record_at(
+                130,
+                ActionKind::ApplyPatch,
+                ApprovalDecision::Expired,
+                ResultStatus::Blocked,
+            ),
+            record_at(
+                200,
+                ActionKind::ExecuteCommand,
+                ApprovalDecision::Approved,
+                ResultStatus::Succeeded,
+            ),
+        ]
+    }
+
+    #[test]
+    fn digest_summarizes_records_inside_range() {
+        let records = sample_records();
+        let digest = AuditDigest::from_records(&records, 100..200);
+
+        assert_eq!(digest.total_records, 4);
+        assert_eq!(digest.commands_executed, 2);
+        assert_eq!(digest.commands_failed, 1);
+        assert_eq!(digest.command_failure_rate(), Some(0.5));
+        assert_eq!(digest.approvals_granted, 2);
+        assert_eq!(digest.approvals_denied, 1);
+        assert_eq!(digest.approvals_expired, 1);
+        assert_eq!(digest.blocked_actions, 2);
+    }
+
+    #[test]
+    fn digest_markdown_lists_metrics_and_active_action_kinds() {
+        let records = sample_records();
+        let markdown = AuditDigest::from_records(&records, 100..200).to_markdown();
+
+        assert!(markdown.starts_with("# Resumo de auditoria AlicIA\n"));
+        assert!(markdown.contains("| Taxa de falha de comandos | 50.0% |"));
+        assert!(markdown.contains("| Acoes bloqueadas | 2 |"));
+        assert!(markdown.contains("| execute_command | 3 | 1 | 1 | 1 | 50.0% |"));
+        assert!(markdown.contains("| apply_patch | 1 | 0 | 0 | 1 | n/a |"));
+        assert!(!markdown.contains("| read_file |"));
+    }
+
+    #[test]
+    fn empty_range_reports_unknown_failure_rate() {
+        let digest = AuditDigest::from_records(&sample_records(), 500..600);
+
+        assert_eq!(digest.total_records, 0);
+        assert_eq!(digest.command_failure_rate(), None);
+    }
+}
@@ -0,0 +1,331 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_alicia_core::ActionKind;
+use codex_alicia_core::AuditRecord;
+use codex_alicia_core::ResultStatus;
+use thiserror::Error;
+
+use crate::action_kind_name;
+use crate::approval_decision_name;
+use crate::permission_profile_name;
+use crate::policy_decision_name;
+use crate::result_status_name;
+
+/// Where the "Exportar auditoria" button writes, under the workspace root.
+pub const AUDIT_EXPORTS_DIR: &str = ".codex/audit-exports";
+
+const CSV_HEADER: &str = "timestamp,session_id,action_kind,target,profile,profile_name,policy_decision,approval_decision,result_status,duration_ms,action_id,event_sequence,approver,metadata";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AuditExportFormat {
+    /// One JSON array with every record.
+    #[default]
+    Json,
+    Csv,
+    /// One record per line, like the audit log itself.
+    Jsonl,
+}
+
+impl AuditExportFormat {
+    pub const ALL: [Self; 3] = [Self::Json, Self::Csv, Self::Jsonl];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AuditExportError {
+    #[error("failed to serialize audit records: {0}")]
+    SerializeFailed(#[from] serde_json::Error),
+    #[error("failed to write audit export `{path}`: {source}")]
+    WriteFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Which audit records an export keeps; an empty list or `None` keeps all.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AuditExportFilter {
+    pub session_id: Option<String>,
+    pub action_kinds: Vec<ActionKind>,
+    pub result_statuses: Vec<ResultStatus>,
+    /// Inclusive bounds on `AuditRecord::timestamp`, in unix seconds.
+    pub since_unix_s: Option<i64>,
+    pub until_unix_s: Option<i64>,
+}
+
+impl AuditExportFilter {
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    pub fn with_action_kinds(mut self, action_kinds: Vec<ActionKind>) -> Self {
+        self.action_kinds = action_kinds;
+        self
+    }
+
+    pub fn with_result_statuses(mut self, result_statuses: Vec<ResultStatus>) -> Self {
+        self.result_statuses = result_statuses;
+        self
+    }
+
+    pub fn with_time_range(mut self, since_unix_s: Option<i64>, until_unix_s: Option<i64>) -> Self {
+        self.since_unix_s = since_unix_s;
+        self.until_unix_s = until_unix_s;
+        self
+    }
+
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.session_id
+            .as_deref()
+            .is_none_or(|session_id| record.session_id == session_id)
+            && (self.action_kinds.is_empty() || self.action_kinds.contains(&record.action_kind))
+            && (self.result_statuses.is_empty()
+                || self.result_statuses.contains(&record.result_status))
+            && self
+                .since_unix_s
+                .is_none_or(|since| record.timestamp >= since)
+            && self
+                .until_unix_s
+                .is_none_or(|until| record.timestamp <= until)
+    }
+}
+
+/// Writes the audit records a filter keeps in one of the export formats.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AuditExporter {
+    pub format: AuditExportFormat,
+    pub filter: AuditExportFilter,
+}
+
+impl AuditExporter {
+    pub fn new(format: AuditExportFormat) -> Self {
+        Self {
+            format,
+            filter: AuditExportFilter::default(),
+        }
+    }
+
+    pub fn with_filter(mut self, filter: AuditExportFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn filtered<'a>(&self, records: &'a [AuditRecord]) -> Vec<&'a AuditRecord> {
+        records
+            .iter()
+            .filter(|record| self.filter.matches(record))
+            .collect()
+    }
+
+    pub fn render(&self, records: &[AuditRecord]) -> Result<String, AuditExportError> {
+        let records = self.filtered(records);
+        match self.format {
+            AuditExportFormat::Json => {
+                let mut text = serde_json::to_string_pretty(&records)?;
+                text.push('\n');
+                Ok(text)
+            }
+            AuditExportFormat::Jsonl => {
+                let mut text = String::new();
+                for record in records {
+                    text.push_str(&serde_json::to_string(record)?);
+                    text.push('\n');
+                }
+                Ok(text)
+            }
+            AuditExportFormat::Csv => {
+                let mut text = format!("{CSV_HEADER}\n");
+                for record in records {
+                    text.push_str(&csv_row(record));
+                    text.push('\n');
+                }
+                Ok(text)
+            }
+        }
+    }
+
+    /// Writes the kept records to `path`, creating its directory. Returns how many were
+    /// written.
+    pub fn write_to(
+        &self,
+        path: &Path,
+        records: &[AuditRecord],
+    ) -> Result<usize, AuditExportError> {
+        let count = self.filtered(records).len();
+        let text = self.render(records)?;
+        path.parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(path, text))
+            .map_err(|source| AuditExportError::WriteFailed {
+                path: path.to_string_lossy().to_string(),
+                source,
+            })?;
+        Ok(count)
+    }
+}
+
+/// `<workspace>/.codex/audit-exports/auditoria-<unix_s>.<extension>`.
+pub fn audit_export_path(
+    workspace_root: &Path,
+    format: AuditExportFormat,
+    generated_at_unix_s: i64,
+) -> PathBuf {
+    workspace_root.join(AUDIT_EXPORTS_DIR).join(format!(
+        "auditoria-{generated_at_unix_s}.{}",
+        format.extension()
+    ))
+}
+
+fn csv_row(record: &AuditRecord) -> String {
+    let metadata: Vec<String> = record
+        .metadata
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect();
+    let fields = [
+        record.timestamp.to_string(),
+        record.session_id.clone(),
+        action_kind_name(record.action_kind).to_string(),
+        record.target.clone(),
+        permission_profile_name(record.profile).to_string(),
+        record.profile_name.clone().unwrap_or_default(),
+        policy_decision_name(record.policy_decision).to_string(),
+        approval_decision_name(record.approval_decision).to_string(),
+        result_status_name(record.result_status).to_string(),
+        record.duration_ms.to_string(),
+        record.action_id.clone().unwrap_or_default(),
+        record
+            .event_sequence
+            .map(|sequence| sequence.to_string())
+            .unwrap_or_default(),
+        record
+            .approver
+            .as_ref()
+            .map(|approver| approver.name.clone())
+            .unwrap_or_default(),
+        metadata.join(";"),
+    ];
+    fields
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::ActionKind;
+    use codex_alicia_core::ApprovalDecision;
+    use codex_alicia_core::AuditRecord;
+    use codex_alicia_core::PermissionProfile;
+    use codex_alicia_core::PolicyDecision;
+    use codex_alicia_core::ResultStatus;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::AuditExportFilter;
+    use super::AuditExportFormat;
+    use super::AuditExporter;
+
+    fn record(
+        session_id: &str,
+        action_kind: ActionKind,
+        target: &str,
+        result_status: ResultStatus,
+        timestamp: i64,
+    ) -> AuditRecord {
+        let mut record = AuditRecord::new(
+            session_id,
+            action_kind,
+            target,
+            PermissionProfile::ReadWriteWithApproval,
+            PolicyDecision::RequireApproval,
+            ApprovalDecision::Approved,
+            result_status,
+            12,
+        );
+        record.timestamp = timestamp;
+        record
+    }
+
+    #[test]
+    fn exports_the_filtered_records_in_each_format() -> Result<(), Box<dyn std::error::Error>> {
+        let records = vec![
+            record(
+                "sess-1",
+                ActionKind::ExecuteCommand,
+                "echo \"oi\", mundo",
+                ResultStatus::Succeeded,
+                100,
+            ),
+            record(
+                "sess-1",
+                ActionKind::WriteFile,
+                "src/lib.rs",
+                ResultStatus::Blocked,
+                200,
+            ),
+            record(
+                "sess-2",
+                ActionKind::ExecuteCommand,
+                "ls",
+                ResultStatus::Succeeded,
+                300,
+            ),
+        ];
+        let filter = AuditExportFilter::default()
+            .with_session_id("sess-1")
+            .with_result_statuses(vec![ResultStatus::Succeeded, ResultStatus::Blocked])
+            .with_time_range(Some(100), Some(250));
+
+        let csv = AuditExporter::new(AuditExportFormat::Csv)
+            .with_filter(
+                filter
+                    .clone()
+                    .with_action_kinds(vec![ActionKind::ExecuteCommand]),
+            )
+            .render(&records)?;
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("timestamp,session_id,action_kind,target,"));
+        assert!(lines[1].starts_with("100,sess-1,execute_command,\"echo \"\"oi\"\", mundo\","));
+
+        let jsonl = AuditExporter::new(AuditExportFormat::Jsonl)
+            .with_filter(filter.clone())
+            .render(&records)?;
+        let parsed: Vec<AuditRecord> = jsonl
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(parsed, records[..2].to_vec());
+
+        let dir = TempDir::new()?;
+        let path = dir.path().join("exports").join("auditoria.json");
+        let written = AuditExporter::new(AuditExportFormat::Json)
+            .with_filter(filter)
+            .write_to(&path, &records)?;
+        assert_eq!(written, 2);
+        let parsed: Vec<AuditRecord> = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        assert_eq!(parsed, records[..2].to_vec());
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use codex_alicia_core::ipc::ToolCallStatus;
+
+use crate::markdown::MarkdownBlock;
+use crate::markdown::parse_markdown;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatRole {
+    User,
+    Agent,
+    System,
+}
+
+/// Workspace file referenced as context by a prompt. `path` is relative to the workspace root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextAttachment {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+/// Tool call made by the agent in a message, shown as a row under it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatToolCall {
+    pub call_id: String,
+    pub name: String,
+    pub arguments: String,
+    pub session_id: Option<String>,
+    pub action_id: Option<String>,
+    /// `None` while the call is running.
+    pub status: Option<ToolCallStatus>,
+    pub duration_ms: Option<u64>,
+}
+
+/// Chat message with its markdown parsed once, so rendering does not re-parse every frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatMessage {
+    pub id: u64,
+    pub role: ChatRole,
+    pub content: String,
+    pub attachments: Vec<ContextAttachment>,
+    pub tool_calls: Vec<ChatToolCall>,
+    /// Still receiving `AgentMessageDelta`s.
+    pub streaming: bool,
+    blocks: Vec<MarkdownBlock>,
+}
+
+impl ChatMessage {
+    pub fn new(id: u64, role: ChatRole, content: impl Into<String>) -> Self {
+        let content = content.into();
+        let blocks = parse_markdown(&content);
+        Self {
+            id,
+            role,
+            content,
+            attachments: Vec::new(),
+            tool_calls: Vec::new(),
+            streaming: false,
+            blocks,
+        }
+    }
+
+    pub(crate) fn append(&mut self, delta: &str) {
+        self.content.push_str(delta);
+        self.blocks = parse_markdown(&self.content);
+    }
+
+    pub fn blocks(&self) -> &[MarkdownBlock] {
+        &self.blocks
+    }
+}
+
+/// The conversation shown in the chat panel: messages in order, with agent replies streamed
+/// into them as deltas arrive.
+#[derive(Debug, Default)]
+pub struct ChatStore {
+    messages: Vec<ChatMessage>,
+    next_id: u64,
+    /// Chat message id of each streamed `message_id`.
+    streams: HashMap<String, u64>,
+}
+
+impl ChatStore {
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    pub fn message_mut(&mut self, id: u64) -> Option<&mut ChatMessage> {
+        self.messages.iter_mut().find(|message| message.id == id)
+    }
+
+    /// Appends a finished message. Returns its id.
+    pub fn push(&mut self, role: ChatRole, content: impl Into<String>) -> u64 {
+        let id = self.next_id;
+        self.next_id = self.next_id.saturating_add(1);
+        self.messages.push(ChatMessage::new(id, role, content));
+        id
+    }
+
+    /// The first delta of `message_id` opens a streaming agent message; deltas after the
+    /// stream completed are dropped. Returns the chat message id the delta went to.
+    pub fn append_delta(&mut self, message_id: &str, delta: &str) -> Option<u64> {
+        if let Some(id) = self.streams.get(message_id).copied() {
+            let message = self
+                .messages
+                .iter_mut()
+                .find(|message| message.id == id && message.streaming)?;
+            message.append(delta);
+            return Some(id);
+        }
+        let id = self.push(ChatRole::Agent, delta);
+        if let Some(message) = self.messages.last_mut() {
+            message.streaming = true;
+        }
+        self.streams.insert(message_id.to_string(), id);
+        Some(id)
+    }
+
+    pub fn complete_stream(&mut self, message_id: &str) {
+        if let Some(id) = self.streams.get(message_id).copied()
+            && let Some(message) = self.message_mut(id)
+        {
+            message.streaming = false;
+        }
+    }
+
+    /// Hangs `tool_call` under the message streamed for `message_id`. A message that called
+    /// tools without any text joins the agent message right before it, or gets an empty one.
+    pub fn start_tool_call(&mut self, message_id: &str, tool_call: ChatToolCall) {
+        let id = match self.streams.get(message_id) {
+            Some(id) => *id,
+            None => {
+                let id = match self.messages.last() {
+                    Some(last) if last.role == ChatRole::Agent => last.id,
+                    _ => self.push(ChatRole::Agent, ""),
+                };
+                self.streams.insert(message_id.to_string(), id);
+                id
+            }
+        };
+        if let Some(message) = self.message_mut(id) {
+            message.tool_calls.push(tool_call);
+        }
+    }
+
+    /// Marks the latest running call `call_id` as finished.
+    pub fn finish_tool_call(&mut self, call_id: &str, status: ToolCallStatus, duration_ms: u64) {
+        if let Some(tool_call) = self
+            .messages
+            .iter_mut()
+            .rev()
+            .flat_map(|message| message.tool_calls.iter_mut())
+            .find(|tool_call| tool_call.call_id == call_id && tool_call.status.is_none())
+        {
+            tool_call.status = Some(status);
+            tool_call.duration_ms = Some(duration_ms);
+        }
+    }
+
+    /// Whether an agent message is still being streamed.
+    pub fn is_streaming(&self) -> bool {
+        self.messages.iter().any(|message| message.streaming)
+    }
+
+    /// Drops every message and stream; ids keep counting up.
+    pub fn clear(&mut self) {
+        self.messages.clear();
+        self.streams.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::ipc::ToolCallStatus;
+    use pretty_assertions::assert_eq;
+
+    use super::ChatRole;
+    use super::ChatStore;
+    use super::ChatToolCall;
+
+    fn tool_call(call_id: &str) -> ChatToolCall {
+        ChatToolCall {
+            call_id: call_id.to_string(),
+            name: "shell".to_string(),
+            arguments: "ls".to_string(),
+            session_id: None,
+            action_id: None,
+            status: None,
+            duration_ms: None,
+        }
+    }
+
+    #[test]
+    fn streams_deltas_and_tool_calls_into_agent_messages() {
+        let mut chat = ChatStore::default();
+        let prompt = chat.push(ChatRole::User, "liste os arquivos");
+        let reply = chat.append_delta("msg-1", "Vou ");
+        assert_eq!(chat.append_delta("msg-1", "listar."), reply);
+        assert!(chat.is_streaming());
+        chat.start_tool_call("msg-1", tool_call("call-1"));
+        chat.complete_stream("msg-1");
+        assert_eq!(chat.append_delta("msg-1", " atrasado"), None);
+        chat.finish_tool_call("call-1", ToolCallStatus::Succeeded, 7);
+
+        let messages = chat.messages();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].id, prompt);
+        assert_eq!(messages[1].content, "Vou listar.");
+        assert!(!chat.is_streaming());
+        assert_eq!(messages[1].tool_calls[0].duration_ms, Some(7));
+
+        // A tool call without text joins the agent reply before it.
+        chat.start_tool_call("msg-2", tool_call("call-2"));
+        assert_eq!(chat.messages().len(), 2);
+        assert_eq!(chat.messages()[1].tool_calls.len(), 2);
+
+        chat.clear();
+        assert!(chat.messages().is_empty());
+        assert_eq!(chat.push(ChatRole::System, "limpo"), 2);
+    }
+}
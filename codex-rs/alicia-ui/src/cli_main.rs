@@ -0,0 +1,166 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Parser;
+use clap::Subcommand;
+use clap::ValueEnum;
+use codex_alicia_core::AuditLogger;
+use codex_alicia_core::PermissionProfile;
+use codex_alicia_core::SessionAuditContext;
+use codex_alicia_core::SessionManager;
+use codex_alicia_core::SessionMode;
+use codex_alicia_core::SessionStartRequest;
+use codex_alicia_ui::APPROVAL_EXPIRY_INTERVAL;
+use codex_alicia_ui::AliciaUiRuntime;
+use codex_alicia_ui::HeadlessApprovalMode;
+use codex_alicia_ui::read_audit_log;
+use codex_alicia_ui::run_headless_session;
+use tokio::io::BufReader;
+
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliApprovalMode {
+    Ask,
+    All,
+    None,
+}
+
+impl From<CliApprovalMode> for HeadlessApprovalMode {
+    fn from(value: CliApprovalMode) -> Self {
+        match value {
+            CliApprovalMode::Ask => HeadlessApprovalMode::Ask,
+            CliApprovalMode::All => HeadlessApprovalMode::ApproveAll,
+            CliApprovalMode::None => HeadlessApprovalMode::DenyAll,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "codex-alicia-cli",
+    about = "Executa sessoes AlicIA no terminal, sem a interface grafica."
+)]
+struct AliciaCli {
+    #[command(subcommand)]
+    command: AliciaCliCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum AliciaCliCommand {
+    /// Executa um comando, pedindo as aprovacoes no terminal.
+    Run(RunArgs),
+    /// Imprime os registros de um JSONL de auditoria, um por linha.
+    Audit(AuditArgs),
+}
+
+#[derive(Debug, clap::Args)]
+struct RunArgs {
+    /// Identificador da sessao.
+    #[arg(long, default_value = "alicia-cli")]
+    session_id: String,
+
+    /// Diretorio de trabalho da sessao; tambem a raiz do workspace.
+    #[arg(long)]
+    cwd: Option<PathBuf>,
+
+    /// Como responder as aprovacoes (ask/all/none).
+    #[arg(long, value_enum, default_value_t = CliApprovalMode::Ask)]
+    approve: CliApprovalMode,
+
+    /// Caminho opcional do JSONL de auditoria.
+    #[arg(long)]
+    audit_path: Option<PathBuf>,
+
+    /// Ao terminar, imprime os registros de auditoria da sessao.
+    #[arg(long)]
+    dump_audit: bool,
+
+    /// Comando a executar, preferencialmente apos `--`.
+    #[arg(required = true, trailing_var_arg = true)]
+    command: Vec<String>,
+}
+
+#[derive(Debug, clap::Args)]
+struct AuditArgs {
+    /// JSONL de auditoria sem criptografia.
+    path: PathBuf,
+
+    /// Mostra so os registros desta acao.
+    #[arg(long)]
+    action_id: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    match AliciaCli::parse().command {
+        AliciaCliCommand::Run(args) => run(args).await,
+        AliciaCliCommand::Audit(args) => {
+            for record in read_audit_log(&args.path, args.action_id.as_deref())? {
+                println!("{}", serde_json::to_string(&record)?);
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn run(args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = match args.cwd {
+        Some(path) => path,
+        None => std::env::current_dir()?,
+    };
+    let Some((program, rest)) = args.command.split_first() else {
+        return Err(std::io::Error::other("comando nao informado").into());
+    };
+
+    let session_manager = match &args.audit_path {
+        Some(path) => SessionManager::with_audit_logger(AuditLogger::open(path).await?),
+        None => SessionManager::new(),
+    };
+    let mut runtime = AliciaUiRuntime::new(session_manager, 2_000).with_workspace_root(cwd.clone());
+    let _approval_expiry = runtime.spawn_approval_expiry_task(APPROVAL_EXPIRY_INTERVAL);
+    runtime
+        .store_mut()
+        .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
+    let request = SessionStartRequest::new(
+        args.session_id,
+        program.clone(),
+        rest.to_vec(),
+        cwd,
+        std::env::vars().collect(),
+    )
+    .with_mode(SessionMode::Pipe)
+    .with_audit_context(SessionAuditContext::for_execute_command(
+        args.command.join(" "),
+    ));
+
+    let mut answers = BufReader::new(tokio::io::stdin());
+    let mut out = std::io::stdout();
+    let result = run_headless_session(
+        &mut runtime,
+        request,
+        args.approve.into(),
+        &mut answers,
+        &mut out,
+    )
+    .await;
+
+    let shutdown = runtime.shutdown(SHUTDOWN_TIMEOUT).await;
+    for session_id in &shutdown.unfinished_sessions {
+        eprintln!("Sessao {session_id} continuou rodando apos o encerramento.");
+    }
+    if args.dump_audit {
+        for record in runtime.store().audit_records() {
+            println!("{}", serde_json::to_string(record)?);
+        }
+    }
+
+    match result {
+        Ok(0) => Ok(()),
+        Ok(exit_code) => std::process::exit(exit_code.max(1)),
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use thiserror::Error;
+
+use crate::ApprovalPrompt;
+use crate::PatchHunkDecision;
+use crate::PatchPreviewState;
+use crate::UiEventStore;
+use crate::action_kind_name;
+use crate::approval_status_name;
+
+/// What a copy button of the approval queue or a diff preview puts on the clipboard, by
+/// action id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardExport {
+    Patch(String),
+    FileList(String),
+    ApprovalSummary(String),
+}
+
+impl ClipboardExport {
+    /// The text to copy; `None` when the store has nothing for the action, e.g. a patch of
+    /// a preview without hunks.
+    pub fn text(&self, store: &UiEventStore) -> Option<String> {
+        match self {
+            Self::Patch(action_id) => patch_text(store.diff_preview(action_id)?),
+            Self::FileList(action_id) => {
+                let files = match store.diff_preview(action_id) {
+                    Some(preview) => preview.files.clone(),
+                    None => store.approval(action_id)?.impact_files.clone(),
+                };
+                (!files.is_empty()).then(|| files.join("\n"))
+            }
+            Self::ApprovalSummary(action_id) => {
+                Some(approval_summary_text(&store.approval_prompt(action_id)?))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ClipboardError {
+    #[error("clipboard is unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// The hunks not rejected yet, as a unified diff `git apply` accepts.
+pub fn patch_text(preview: &PatchPreviewState) -> Option<String> {
+    let mut patch = String::new();
+    for file_preview in &preview.file_previews {
+        let hunks: Vec<_> = file_preview
+            .hunks
+            .iter()
+            .filter(|hunk| hunk.decision != PatchHunkDecision::Rejected)
+            .collect();
+        if hunks.is_empty() {
+            continue;
+        }
+        patch.push_str(&format!(
+            "--- a/{path}\n+++ b/{path}\n",
+            path = file_preview.file_path
+        ));
+        for hunk in hunks {
+            patch.push_str(&hunk.header);
+            patch.push('\n');
+            for line in &hunk.lines {
+                patch.push_str(line);
+                patch.push('\n');
+            }
+        }
+    }
+    (!patch.is_empty()).then_some(patch)
+}
+
+pub fn approval_summary_text(prompt: &ApprovalPrompt) -> String {
+    let mut lines = vec![
+        format!(
+            "Aprovacao {} ({})",
+            prompt.action_id,
+            approval_status_name(prompt.status)
+        ),
+        format!("O que: {}", prompt.what),
+    ];
+    if let Some(action_kind) = prompt.action_kind {
+        lines.push(format!("Tipo: {}", action_kind_name(action_kind)));
+    }
+    if let Some(target) = prompt.where_target.as_deref() {
+        lines.push(format!("Onde: {target}"));
+    }
+    if let Some(command) = prompt.command.as_deref() {
+        lines.push(format!("Comando: {command}"));
+    }
+    if !prompt.command_action_kinds.is_empty() {
+        let kinds: Vec<&str> = prompt
+            .command_action_kinds
+            .iter()
+            .map(|action_kind| action_kind_name(*action_kind))
+            .collect();
+        lines.push(format!("Tambem: {}", kinds.join(", ")));
+    }
+    lines.push(format!(
+        "Impacto: {}",
+        prompt.impact.as_deref().unwrap_or("sem diff informado")
+    ));
+    lines.push(format!("Expira em: unix={}", prompt.expires_at_unix_s));
+    lines.join("\n")
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn copy_to_clipboard(text: &str) -> Result<(), ClipboardError> {
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|error| ClipboardError::Unavailable(error.to_string()))?;
+    clipboard
+        .set_text(text.to_string())
+        .map_err(|error| ClipboardError::Unavailable(error.to_string()))
+}
+
+#[cfg(target_os = "android")]
+pub fn copy_to_clipboard(_text: &str) -> Result<(), ClipboardError> {
+    Err(ClipboardError::Unavailable(
+        "not supported on Android".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::IpcEvent;
+    use codex_alicia_core::IpcMessage;
+    use codex_alicia_core::ipc::ApprovalRequested;
+    use codex_alicia_core::ipc::PatchPreviewReady;
+    use pretty_assertions::assert_eq;
+
+    use super::ClipboardExport;
+    use crate::PatchHunkDecision;
+    use crate::UiEventStore;
+
+    #[test]
+    fn exports_the_pending_patch_files_and_approval_summary()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let mut store = UiEventStore::default();
+        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+            PatchPreviewReady {
+                action_id: "act-1".to_string(),
+                files: vec!["src/main.rs".to_string(), "README.md".to_string()],
+            },
+        )));
+        store.attach_patch_file_diff(
+            "act-1",
+            "src/main.rs",
+            "@@ -1,1 +1,1 @@\n-old\n+new\n@@ -9,1 +9,1 @@\n-a\n+b\n",
+        )?;
+        store.set_patch_hunk_decision(
+            "act-1",
+            "src/main.rs",
+            "hunk-2",
+            PatchHunkDecision::Rejected,
+        )?;
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-1".to_string(),
+                summary: "Aplicar patch".to_string(),
+                expires_at_unix_s: 60,
+            },
+        )));
+
+        assert_eq!(
+            ClipboardExport::Patch("act-1".to_string()).text(&store),
+            Some("--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,1 +1,1 @@\n-old\n+new\n".to_string())
+        );
+        assert_eq!(
+            ClipboardExport::FileList("act-1".to_string()).text(&store),
+            Some("src/main.rs\nREADME.md".to_string())
+        );
+        assert_eq!(
+            ClipboardExport::ApprovalSummary("act-1".to_string()).text(&store),
+            Some(
+                "Aprovacao act-1 (pending)\nO que: Aplicar patch\nImpacto: 2 arquivo(s): src/main.rs, README.md\nExpira em: unix=60"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            ClipboardExport::Patch("act-9".to_string()).text(&store),
+            None
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,387 @@
+use egui::Color32;
+use egui::FontId;
+use egui::text::LayoutJob;
+use egui::text::TextFormat;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub code: String,
+}
+
+impl CodeBlock {
+    /// Fence language tag, or a best-effort guess from the code when the fence has none.
+    pub fn effective_language(&self) -> Option<&str> {
+        self.language
+            .as_deref()
+            .or_else(|| detect_language(&self.code))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeTokenKind {
+    Plain,
+    Keyword,
+    String,
+    Comment,
+    Number,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeToken {
+    pub kind: CodeTokenKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CodeLanguage {
+    Rust,
+    Python,
+    JavaScript,
+    Shell,
+    Json,
+    Toml,
+    Other,
+}
+
+impl CodeLanguage {
+    fn from_tag(tag: Option<&str>) -> Self {
+        match tag.map(str::to_ascii_lowercase).as_deref() {
+            Some("rust" | "rs") => Self::Rust,
+            Some("python" | "py") => Self::Python,
+            Some("javascript" | "js" | "typescript" | "ts" | "tsx" | "jsx") => Self::JavaScript,
+            Some("bash" | "sh" | "shell" | "zsh" | "console" | "powershell" | "ps1") => Self::Shell,
+            Some("json") => Self::Json,
+            Some("toml") => Self::Toml,
+            _ => Self::Other,
+        }
+    }
+
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Self::Rust => &[
+                "as", "async", "await", "break", "const", "continue", "crate", "else", "enum",
+                "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+                "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+                "trait", "true", "type", "unsafe", "use", "where", "while",
+            ],
+            Self::Python => &[
+                "and", "as", "async", "await", "break", "class", "continue", "def", "elif", "else",
+                "except", "False", "finally", "for", "from", "if", "import", "in", "is", "lambda",
+                "None", "not", "or", "pass", "raise", "return", "True", "try", "while", "with",
+                "yield",
+            ],
+            Self::JavaScript => &[
+                "async",
+                "await",
+                "break",
+                "case",
+                "class",
+                "const",
+                "continue",
+                "default",
+                "else",
+                "export",
+                "false",
+                "for",
+                "function",
+                "if",
+                "import",
+                "in",
+                "interface",
+                "let",
+                "new",
+                "null",
+                "of",
+                "return",
+                "switch",
+                "this",
+                "throw",
+                "true",
+                "try",
+                "type",
+                "undefined",
+                "var",
+                "while",
+            ],
+            Self::Shell => &[
+                "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function",
+                "if", "in", "local", "then", "while",
+            ],
+            Self::Json | Self::Toml => &["true", "false", "null"],
+            Self::Other => &[],
+        }
+    }
+
+    fn line_comment(self) -> Option<&'static str> {
+        match self {
+            Self::Rust | Self::JavaScript => Some("//"),
+            Self::Python | Self::Shell | Self::Toml => Some("#"),
+            Self::Json | Self::Other => None,
+        }
+    }
+}
+
+/// Guesses the language of an untagged code block from a few strong markers.
+pub fn detect_language(code: &str) -> Option<&'static str> {
+    let trimmed = code.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    if let Some(shebang) = trimmed
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("#!"))
+    {
+        return if shebang.contains("python") {
+            Some("python")
+        } else if shebang.contains("node") {
+            Some("javascript")
+        } else {
+            Some("sh")
+        };
+    }
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        return Some("json");
+    }
+
+    let candidates: [(&'static str, &[&str]); 5] = [
+        (
+            "rust",
+            &[
+                "fn ", "let mut ", "impl ", "pub fn", "use std", "::", "-> ", "println!",
+            ],
+        ),
+        (
+            "python",
+            &[
+                "def ", "import ", "from ", "elif ", "self.", "print(", "None",
+            ],
+        ),
+        (
+            "javascript",
+            &[
+                "function ",
+                "const ",
+                "=> ",
+                "console.log",
+                "require(",
+                "export ",
+            ],
+        ),
+        (
+            "sh",
+            &[
+                "$ ", "cargo ", "git ", "npm ", "cd ", "echo ", "sudo ", " && ", "export ",
+            ],
+        ),
+        (
+            "toml",
+            &["[package]", "[dependencies]", "[workspace]", " = \""],
+        ),
+    ];
+    candidates
+        .iter()
+        .map(|(language, markers)| {
+            let score = markers
+                .iter()
+                .filter(|marker| trimmed.contains(**marker))
+                .count();
+            (*language, score)
+        })
+        .filter(|(_, score)| *score > 0)
+        .max_by_key(|(_, score)| *score)
+        .map(|(language, _)| language)
+}
+
+/// Splits `code` into coarse highlight tokens (keywords, strings, comments, numbers).
+pub fn highlight_code(language: Option<&str>, code: &str) -> Vec<CodeToken> {
+    let language = CodeLanguage::from_tag(language);
+    let keywords = language.keywords();
+    let line_comment = language.line_comment();
+    let mut tokens: Vec<CodeToken> = Vec::new();
+    let mut push = |kind: CodeTokenKind, text: &str| {
+        if let Some(last) = tokens.last_mut()
+            && last.kind == kind
+        {
+            last.text.push_str(text);
+            return;
+        }
+        tokens.push(CodeToken {
+            kind,
+            text: text.to_string(),
+        });
+    };
+
+    let mut rest = code;
+    while let Some(ch) = rest.chars().next() {
+        if let Some(marker) = line_comment
+            && rest.starts_with(marker)
+        {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            push(CodeTokenKind::Comment, &rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if ch == '"' || ch == '\'' {
+            let mut end = rest.len();
+            let mut escaped = false;
+            for (offset, next) in rest[1..].char_indices() {
+                if next == '\n' {
+                    end = 1 + offset;
+                    break;
+                }
+                if escaped {
+                    escaped = false;
+                } else if next == '\\' {
+                    escaped = true;
+                } else if next == ch {
+                    end = 1 + offset + 1;
+                    break;
+                }
+            }
+            push(CodeTokenKind::String, &rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if ch.is_alphanumeric() || ch == '_' {
+            let is_number = ch.is_ascii_digit();
+            let end = rest
+                .find(|next: char| {
+                    !(next.is_alphanumeric() || next == '_' || (is_number && next == '.'))
+                })
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            let kind = if is_number {
+                CodeTokenKind::Number
+            } else if keywords.contains(&word) {
+                CodeTokenKind::Keyword
+            } else {
+                CodeTokenKind::Plain
+            };
+            push(kind, word);
+            rest = &rest[end..];
+            continue;
+        }
+        push(CodeTokenKind::Plain, &rest[..ch.len_utf8()]);
+        rest = &rest[ch.len_utf8()..];
+    }
+
+    tokens
+}
+
+/// Renders `block` with a copy button; returns `true` when the code was copied this frame.
+pub fn render_code_block(ui: &mut egui::Ui, block: &CodeBlock) -> bool {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let language = block.effective_language();
+    let mut copied = false;
+    egui::Frame::group(ui.style())
+        .fill(ui.visuals().extreme_bg_color)
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                match (block.language.as_deref(), language) {
+                    (Some(tag), _) => {
+                        ui.small(tag);
+                    }
+                    (None, Some(detected)) => {
+                        ui.small(format!("{detected} (detectado)"));
+                    }
+                    (None, None) => {}
+                }
+                if ui.small_button("Copiar").clicked() {
+                    ui.ctx().copy_text(block.code.clone());
+                    copied = true;
+                }
+            });
+            let mut job = LayoutJob::default();
+            for token in highlight_code(language, &block.code) {
+                job.append(
+                    &token.text,
+                    0.0,
+                    token_format(token.kind, font_id.clone(), ui.visuals().text_color()),
+                );
+            }
+            ui.label(job);
+        });
+    copied
+}
+
+fn token_format(kind: CodeTokenKind, font_id: FontId, plain: Color32) -> TextFormat {
+    let color = match kind {
+        CodeTokenKind::Plain => plain,
+        CodeTokenKind::Keyword => Color32::from_rgb(198, 120, 221),
+        CodeTokenKind::String => Color32::from_rgb(152, 195, 121),
+        CodeTokenKind::Comment => Color32::from_rgb(127, 132, 142),
+        CodeTokenKind::Number => Color32::from_rgb(209, 154, 102),
+    };
+    TextFormat::simple(font_id, color)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::CodeBlock;
+    use super::CodeToken;
+    use super::CodeTokenKind;
+    use super::detect_language;
+    use super::highlight_code;
+
+    fn token(kind: CodeTokenKind, text: &str) -> CodeToken {
+        CodeToken {
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn highlights_rust_keywords_strings_and_comments() {
+        let tokens = highlight_code(Some("rust"), "let x = \"hi\"; // note");
+
+        assert_eq!(
+            tokens,
+            vec![
+                token(CodeTokenKind::Keyword, "let"),
+                token(CodeTokenKind::Plain, " x = "),
+                token(CodeTokenKind::String, "\"hi\""),
+                token(CodeTokenKind::Plain, "; "),
+                token(CodeTokenKind::Comment, "// note"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_language_keeps_plain_text_and_numbers() {
+        let tokens = highlight_code(None, "retry 3 # times");
+
+        assert_eq!(
+            tokens,
+            vec![
+                token(CodeTokenKind::Plain, "retry "),
+                token(CodeTokenKind::Number, "3"),
+                token(CodeTokenKind::Plain, " # times"),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_language_of_untagged_blocks() {
+        assert_eq!(
+            detect_language("fn main() {\n    println!(\"hi\");\n}"),
+            Some("rust")
+        );
+        assert_eq!(detect_language("def run():\n    print(1)"), Some("python"));
+        assert_eq!(detect_language("{\"ok\": true}"), Some("json"));
+        assert_eq!(detect_language("#!/bin/bash\nls"), Some("sh"));
+        assert_eq!(detect_language("$ cargo test && git status"), Some("sh"));
+        assert_eq!(detect_language("hello world"), None);
+
+        let tagged = CodeBlock {
+            language: Some("toml".to_string()),
+            code: "fn main() {}".to_string(),
+        };
+        assert_eq!(tagged.effective_language(), Some("toml"));
+    }
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+/// Runs of one command line, aggregated from `CommandFinished` events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandStats {
+    pub command: String,
+    pub runs: u64,
+    pub failures: u64,
+    pub total_duration_ms: u64,
+    pub first_duration_ms: u64,
+    pub last_duration_ms: u64,
+}
+
+impl CommandStats {
+    pub(crate) fn new(command: String) -> Self {
+        Self {
+            command,
+            runs: 0,
+            failures: 0,
+            total_duration_ms: 0,
+            first_duration_ms: 0,
+            last_duration_ms: 0,
+        }
+    }
+
+    pub(crate) fn record(&mut self, exit_code: i32, duration_ms: u64) {
+        if self.runs == 0 {
+            self.first_duration_ms = duration_ms;
+        }
+        self.runs = self.runs.saturating_add(1);
+        if exit_code != 0 {
+            self.failures = self.failures.saturating_add(1);
+        }
+        self.total_duration_ms = self.total_duration_ms.saturating_add(duration_ms);
+        self.last_duration_ms = duration_ms;
+    }
+
+    pub fn average_duration(&self) -> Duration {
+        Duration::from_millis(self.total_duration_ms.checked_div(self.runs).unwrap_or(0))
+    }
+
+    /// Share of runs that exited non-zero, from 0.0 to 1.0.
+    pub fn failure_rate(&self) -> f64 {
+        if self.runs == 0 {
+            return 0.0;
+        }
+        self.failures as f64 / self.runs as f64
+    }
+
+    /// How many times slower the last run was than the first; `None` until there are two
+    /// runs or when the first one took no measurable time.
+    pub fn slowdown(&self) -> Option<f64> {
+        if self.runs < 2 || self.first_duration_ms == 0 {
+            return None;
+        }
+        Some(self.last_duration_ms as f64 / self.first_duration_ms as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use pretty_assertions::assert_eq;
+
+    use super::CommandStats;
+
+    #[test]
+    fn aggregates_durations_and_failures() {
+        let mut stats = CommandStats::new("cargo test".to_string());
+        assert_eq!(stats.slowdown(), None);
+        stats.record(0, 1_000);
+        stats.record(101, 2_000);
+        stats.record(0, 3_000);
+
+        assert_eq!(stats.runs, 3);
+        assert_eq!(stats.average_duration(), Duration::from_millis(2_000));
+        assert_eq!(stats.failure_rate(), 1.0 / 3.0);
+        assert_eq!(stats.slowdown(), Some(3.0));
+    }
+}
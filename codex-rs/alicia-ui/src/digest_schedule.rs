@@ -0,0 +1,355 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::str::FromStr;
+
+use serde_json::json;
+
+use crate::AuditDigest;
+
+/// Mondays at 09:00 UTC.
+pub const DEFAULT_DIGEST_SCHEDULE: &str = "0 9 * * 1";
+
+const SECONDS_PER_DAY: i64 = 86_400;
+/// How far `DigestSchedule::next_after` looks before deciding a schedule never fires, e.g.
+/// `0 0 30 2 *`.
+const MAX_SCHEDULE_LOOKAHEAD_DAYS: i64 = 4 * 366;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum DigestScheduleError {
+    #[error("schedule `{0}` needs five fields: minute hour day-of-month month day-of-week")]
+    FieldCount(String),
+    #[error("invalid {field} `{value}` in schedule")]
+    InvalidField { field: &'static str, value: String },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DigestDeliveryError {
+    #[error("failed to write digest to {path}: {source}")]
+    WriteFailed {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("webhook request failed: {0}")]
+    RequestFailed(#[from] reqwest::Error),
+    #[error("webhook answered {status}: {body}")]
+    WebhookRejected { status: u16, body: String },
+    #[error("failed to run sendmail: {0}")]
+    SendmailUnavailable(#[source] std::io::Error),
+    #[error("sendmail exited with {0}")]
+    SendmailFailed(std::process::ExitStatus),
+}
+
+/// When to generate the audit digest, as a cron expression evaluated in UTC: `minute hour
+/// day-of-month month day-of-week`, each a `*`, a number, a range `a-b` or a list of them,
+/// optionally stepped with `/n`. Sunday is 0 or 7. As in cron, when both day fields are
+/// restricted a day matching either one fires. `@hourly`, `@daily` and `@weekly` are
+/// accepted too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DigestSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    days_of_month_restricted: bool,
+    days_of_week_restricted: bool,
+}
+
+impl FromStr for DigestSchedule {
+    type Err = DigestScheduleError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let expanded = match spec.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields.as_slice() else {
+            return Err(DigestScheduleError::FieldCount(spec.to_string()));
+        };
+        let mut days_of_week = parse_field(day_of_week, "day-of-week", 0, 7)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, "minute", 0, 59)?,
+            hours: parse_field(hour, "hour", 0, 23)?,
+            days_of_month: parse_field(day_of_month, "day-of-month", 1, 31)?,
+            months: parse_field(month, "month", 1, 12)?,
+            days_of_week,
+            days_of_month_restricted: !day_of_month.starts_with('*'),
+            days_of_week_restricted: !day_of_week.starts_with('*'),
+        })
+    }
+}
+
+impl DigestSchedule {
+    /// First minute strictly after `after_unix_s` the schedule fires at; `None` when it never
+    /// does.
+    pub fn next_after(&self, after_unix_s: i64) -> Option<i64> {
+        let start = after_unix_s
+            .div_euclid(60)
+            .saturating_add(1)
+            .saturating_mul(60);
+        let limit = start.saturating_add(MAX_SCHEDULE_LOOKAHEAD_DAYS * SECONDS_PER_DAY);
+        let mut candidate = start;
+        while candidate < limit {
+            let days = candidate.div_euclid(SECONDS_PER_DAY);
+            let seconds_of_day = candidate.rem_euclid(SECONDS_PER_DAY);
+            if !self.matches_day(days) {
+                candidate = (days + 1) * SECONDS_PER_DAY;
+                continue;
+            }
+            let hour = seconds_of_day / 3_600;
+            if self.hours & (1 << hour) == 0 {
+                candidate = days * SECONDS_PER_DAY + (hour + 1) * 3_600;
+                continue;
+            }
+            let minute = seconds_of_day % 3_600 / 60;
+            if self.minutes & (1 << minute) == 0 {
+                candidate += 60;
+                continue;
+            }
+            return Some(candidate);
+        }
+        None
+    }
+
+    fn matches_day(&self, days_since_epoch: i64) -> bool {
+        let (_, month, day) = civil_from_days(days_since_epoch);
+        if self.months & (1 << month) == 0 {
+            return false;
+        }
+        // 1970-01-01 was a Thursday.
+        let weekday = (days_since_epoch + 4).rem_euclid(7);
+        let day_of_month = self.days_of_month & (1 << day) != 0;
+        let day_of_week = self.days_of_week & (1 << weekday) != 0;
+        match (self.days_of_month_restricted, self.days_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+fn parse_field(
+    text: &str,
+    field: &'static str,
+    min: u32,
+    max: u32,
+) -> Result<u64, DigestScheduleError> {
+    let invalid = || DigestScheduleError::InvalidField {
+        field,
+        value: text.to_string(),
+    };
+    let number = |value: &str| {
+        value
+            .parse::<u32>()
+            .ok()
+            .filter(|value| (min..=max).contains(value))
+            .ok_or_else(invalid)
+    };
+    let mut mask = 0_u64;
+    for item in text.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(invalid)?,
+            ),
+            None => (item, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (number(start)?, number(end)?),
+                None if item.contains('/') => (number(range)?, max),
+                None => {
+                    let value = number(range)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// Year, month (1-12) and day (1-31) of a day counted from 1970-01-01, in the proleptic
+/// Gregorian calendar.
+fn civil_from_days(days_since_epoch: i64) -> (i64, u32, u32) {
+    let days = days_since_epoch + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let month = month as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Where a scheduled digest goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DigestDelivery {
+    /// Writes `audit-digest-<start>-<end>.md` into `dir`.
+    Directory(PathBuf),
+    /// Posts `{"text": <markdown>, "digest": {...}}` as JSON, which Slack and Teams style
+    /// incoming webhooks accept.
+    Webhook(String),
+    /// Mails the markdown to `to` through the local `sendmail`.
+    Email(String),
+}
+
+impl DigestDelivery {
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Self::Directory(_) => "directory",
+            Self::Webhook(_) => "webhook",
+            Self::Email(_) => "email",
+        }
+    }
+}
+
+pub async fn deliver_audit_digest(
+    digest: &AuditDigest,
+    delivery: &DigestDelivery,
+) -> Result<(), DigestDeliveryError> {
+    let markdown = digest.to_markdown();
+    match delivery {
+        DigestDelivery::Directory(dir) => {
+            let path = dir.join(format!(
+                "audit-digest-{}-{}.md",
+                digest.range_start_unix_s, digest.range_end_unix_s
+            ));
+            std::fs::create_dir_all(dir)
+                .and_then(|()| std::fs::write(&path, markdown))
+                .map_err(|source| DigestDeliveryError::WriteFailed { path, source })
+        }
+        DigestDelivery::Webhook(url) => {
+            let response = reqwest::Client::new()
+                .post(url)
+                .json(&json!({
+                    "text": markdown,
+                    "digest": {
+                        "rangeStartUnixS": digest.range_start_unix_s,
+                        "rangeEndUnixS": digest.range_end_unix_s,
+                        "totalRecords": digest.total_records,
+                        "commandsExecuted": digest.commands_executed,
+                        "commandsFailed": digest.commands_failed,
+                        "approvalsGranted": digest.approvals_granted,
+                        "approvalsDenied": digest.approvals_denied,
+                        "approvalsExpired": digest.approvals_expired,
+                        "blockedActions": digest.blocked_actions,
+                    },
+                }))
+                .send()
+                .await?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(DigestDeliveryError::WebhookRejected {
+                    status: status.as_u16(),
+                    body: response.text().await.unwrap_or_default(),
+                });
+            }
+            Ok(())
+        }
+        DigestDelivery::Email(to) => {
+            let message = format!(
+                "To: {to}\nSubject: Resumo de auditoria AlicIA\nContent-Type: text/plain; charset=utf-8\n\n{markdown}"
+            );
+            send_mail(message)
+        }
+    }
+}
+
+fn send_mail(message: String) -> Result<(), DigestDeliveryError> {
+    let mut child = Command::new("sendmail")
+        .arg("-t")
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(DigestDeliveryError::SendmailUnavailable)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(message.as_bytes())
+            .map_err(DigestDeliveryError::SendmailUnavailable)?;
+    }
+    let status = child
+        .wait()
+        .map_err(DigestDeliveryError::SendmailUnavailable)?;
+    if !status.success() {
+        return Err(DigestDeliveryError::SendmailFailed(status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::DigestSchedule;
+    use super::DigestScheduleError;
+    use super::civil_from_days;
+
+    // 2024-01-01T00:00:00Z, a Monday.
+    const MONDAY: i64 = 1_704_067_200;
+
+    #[test]
+    fn next_after_follows_the_cron_fields() -> Result<(), DigestScheduleError> {
+        let weekly: DigestSchedule = "0 9 * * 1".parse()?;
+        assert_eq!(weekly.next_after(MONDAY), Some(MONDAY + 9 * 3_600));
+        assert_eq!(
+            weekly.next_after(MONDAY + 9 * 3_600),
+            Some(MONDAY + 7 * 86_400 + 9 * 3_600)
+        );
+
+        let every_quarter_hour: DigestSchedule = "*/15 * * * *".parse()?;
+        assert_eq!(
+            every_quarter_hour.next_after(MONDAY + 61),
+            Some(MONDAY + 900)
+        );
+
+        // Day 15 or any Sunday, whichever comes first.
+        let either_day: DigestSchedule = "0 0 15 * 7".parse()?;
+        assert_eq!(either_day.next_after(MONDAY), Some(MONDAY + 6 * 86_400));
+
+        let never: DigestSchedule = "0 0 30 2 *".parse()?;
+        assert_eq!(never.next_after(MONDAY), None);
+        assert_eq!(
+            "@weekly".parse::<DigestSchedule>(),
+            "0 0 * * 0".parse::<DigestSchedule>()
+        );
+
+        assert_eq!(
+            "0 9 * *".parse::<DigestSchedule>(),
+            Err(DigestScheduleError::FieldCount("0 9 * *".to_string()))
+        );
+        assert_eq!(
+            "0 24 * * *".parse::<DigestSchedule>(),
+            Err(DigestScheduleError::InvalidField {
+                field: "hour",
+                value: "24".to_string(),
+            })
+        );
+        assert_eq!(civil_from_days(MONDAY / 86_400), (2024, 1, 1));
+        Ok(())
+    }
+}
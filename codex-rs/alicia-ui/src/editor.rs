@@ -0,0 +1,266 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use codex_alicia_core::PolicyBridgeError;
+use codex_alicia_core::ensure_target_in_workspace;
+use thiserror::Error;
+
+/// Command used to open files. `{file}`, `{line}` and `{column}` are replaced per argument;
+/// the command is split on whitespace and never goes through a shell. Terminal editors such
+/// as vim only work when the UI itself was started from a terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorConfig {
+    pub command: String,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self::vscode()
+    }
+}
+
+impl EditorConfig {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+
+    pub fn vscode() -> Self {
+        Self::new("code --goto {file}:{line}:{column}")
+    }
+
+    pub fn jetbrains() -> Self {
+        Self::new("idea --line {line} --column {column} {file}")
+    }
+
+    pub fn vim() -> Self {
+        Self::new("vim +call\\ cursor({line},{column}) {file}")
+    }
+
+    /// Uses `$VISUAL` or `$EDITOR` when they name a known editor, VS Code otherwise.
+    pub fn from_env() -> Self {
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_default();
+        let program = Path::new(editor.split_whitespace().next().unwrap_or_default())
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match program.as_str() {
+            "vim" | "nvim" | "vi" => Self::vim(),
+            "idea" | "idea64" | "clion" | "rustrover" | "pycharm" | "webstorm" | "goland" => {
+                Self::new(format!(
+                    "{program} --line {{line}} --column {{column}} {{file}}"
+                ))
+            }
+            _ => Self::vscode(),
+        }
+    }
+
+    /// Program and arguments for `location`, with placeholders filled in. Missing line and
+    /// column default to 1.
+    pub fn command_for(&self, file: &Path, location: &EditorLocation) -> Vec<String> {
+        let file = file.to_string_lossy();
+        let line = location.line.unwrap_or(1).to_string();
+        let column = location.column.unwrap_or(1).to_string();
+        split_command(&self.command)
+            .into_iter()
+            .map(|argument| {
+                argument
+                    .replace("{file}", &file)
+                    .replace("{line}", &line)
+                    .replace("{column}", &column)
+            })
+            .collect()
+    }
+}
+
+/// Workspace-relative file position to open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorLocation {
+    pub path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl EditorLocation {
+    pub fn file(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            line: None,
+            column: None,
+        }
+    }
+
+    pub fn at_line(path: impl Into<String>, line: usize) -> Self {
+        Self {
+            path: path.into(),
+            line: Some(line),
+            column: None,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{line}:{column}", self.path),
+            (Some(line), None) => format!("{}:{line}", self.path),
+            _ => self.path.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum EditorError {
+    #[error("editor command is empty")]
+    EmptyCommand,
+    #[error("`{path}` is outside the workspace: {source}")]
+    OutsideWorkspace {
+        path: String,
+        #[source]
+        source: PolicyBridgeError,
+    },
+    #[error("`{0}` does not exist")]
+    FileNotFound(String),
+    #[error("failed to launch `{program}`: {source}")]
+    LaunchFailed {
+        program: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Opens `location` with `config` after checking it stays inside `workspace_root`. Returns
+/// the canonical file that was opened.
+pub fn open_in_editor(
+    workspace_root: &Path,
+    config: &EditorConfig,
+    location: &EditorLocation,
+) -> Result<PathBuf, EditorError> {
+    let guard = ensure_target_in_workspace(workspace_root, Path::new(&location.path)).map_err(
+        |source| EditorError::OutsideWorkspace {
+            path: location.path.clone(),
+            source,
+        },
+    )?;
+    if !guard.canonical_target.is_file() {
+        return Err(EditorError::FileNotFound(location.path.clone()));
+    }
+    let command = config.command_for(&guard.canonical_target, location);
+    let Some((program, args)) = command.split_first() else {
+        return Err(EditorError::EmptyCommand);
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(&guard.canonical_workspace)
+        .spawn()
+        .map_err(|source| EditorError::LaunchFailed {
+            program: program.clone(),
+            source,
+        })?;
+    std::thread::spawn(move || child.wait());
+    Ok(guard.canonical_target)
+}
+
+/// Splits on whitespace; `\ ` keeps a literal space inside an argument.
+fn split_command(command: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut chars = command.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' && chars.peek() == Some(&' ') {
+            current.push(' ');
+            chars.next();
+        } else if ch.is_whitespace() {
+            if !current.is_empty() {
+                arguments.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(ch);
+        }
+    }
+    if !current.is_empty() {
+        arguments.push(current);
+    }
+    arguments
+}
+
+/// `path:line` or `path:line:column` references in tool output, such as compiler
+/// diagnostics (`--> src/main.rs:10:5`) or test failures. Paths need an extension or a
+/// separator so ratios like `12:30` are not picked up.
+pub fn find_path_links(text: &str) -> Vec<EditorLocation> {
+    let mut links: Vec<EditorLocation> = Vec::new();
+    for token in text.split(|ch: char| {
+        ch.is_whitespace()
+            || matches!(
+                ch,
+                '(' | ')' | '[' | ']' | '"' | '\'' | '`' | ',' | '<' | '>'
+            )
+    }) {
+        let Some(link) = parse_path_link(token) else {
+            continue;
+        };
+        if !links.contains(&link) {
+            links.push(link);
+        }
+    }
+    links
+}
+
+fn parse_path_link(token: &str) -> Option<EditorLocation> {
+    let token = token.trim_end_matches(['.', ':', ';']);
+    let mut parts = token.splitn(3, ':');
+    let path = parts.next()?;
+    let line = parts.next()?.parse::<usize>().ok()?;
+    let column = match parts.next() {
+        Some(column) => Some(column.parse::<usize>().ok()?),
+        None => None,
+    };
+    let looks_like_path = !path.is_empty()
+        && !path.contains("//")
+        && (path.contains('/') || path.contains('\\') || Path::new(path).extension().is_some());
+    (looks_like_path && line > 0).then(|| EditorLocation {
+        path: path.to_string(),
+        line: Some(line),
+        column,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use pretty_assertions::assert_eq;
+
+    use super::EditorConfig;
+    use super::EditorLocation;
+    use super::find_path_links;
+
+    #[test]
+    fn detects_path_links_and_fills_editor_placeholders() {
+        let output = "error[E0308]: mismatched types\n  --> src/main.rs:10:5\nat (tests/app.test.ts:42) took 12:30, see https://x.dev:443/a\n  --> src/main.rs:10:5";
+
+        let links = find_path_links(output);
+
+        assert_eq!(
+            links,
+            vec![
+                EditorLocation {
+                    path: "src/main.rs".to_string(),
+                    line: Some(10),
+                    column: Some(5),
+                },
+                EditorLocation::at_line("tests/app.test.ts", 42),
+            ]
+        );
+        assert_eq!(
+            EditorConfig::vscode().command_for(Path::new("/ws/src/main.rs"), &links[0]),
+            vec!["code", "--goto", "/ws/src/main.rs:10:5"]
+        );
+        assert_eq!(
+            EditorConfig::vim().command_for(Path::new("/ws/a b.rs"), &links[1]),
+            vec!["vim", "+call cursor(42,1)", "/ws/a b.rs"]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+use codex_alicia_core::EnvEdits;
+use codex_alicia_core::SessionStartRequest;
+use codex_alicia_core::is_secret_env_var;
+use codex_alicia_core::is_valid_env_var_name;
+
+const MASKED_VALUE: &str = "••••••";
+
+/// Review of a staged session's environment: variables are added, overridden or removed
+/// as [`EnvEdits`] over the request's map, and values of secret-looking names stay masked
+/// until revealed.
+#[derive(Debug, Clone)]
+pub struct SessionEnvEditor {
+    original: BTreeMap<String, String>,
+    edits: EnvEdits,
+    revealed: BTreeSet<String>,
+    filter: String,
+    new_name: String,
+    new_value: String,
+    message: Option<String>,
+}
+
+impl SessionEnvEditor {
+    pub fn new(request: &SessionStartRequest) -> Self {
+        Self {
+            original: request
+                .env
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            edits: EnvEdits::default(),
+            revealed: BTreeSet::new(),
+            filter: String::new(),
+            new_name: String::new(),
+            new_value: String::new(),
+            message: None,
+        }
+    }
+
+    pub fn edits(&self) -> &EnvEdits {
+        &self.edits
+    }
+
+    /// The environment the session would launch with.
+    pub fn env(&self) -> BTreeMap<String, String> {
+        let mut env = self.original.clone();
+        for name in &self.edits.removed {
+            env.remove(name);
+        }
+        env.extend(
+            self.edits
+                .set
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        env
+    }
+
+    /// Adds or overrides `name`; setting it back to its original value drops the edit.
+    /// Returns false when the name cannot be an environment variable.
+    pub fn set(&mut self, name: &str, value: &str) -> bool {
+        let name = name.trim();
+        if !is_valid_env_var_name(name) {
+            return false;
+        }
+        if self.original.get(name).map(String::as_str) == Some(value) {
+            self.reset(name);
+        } else {
+            self.edits.set(name, value);
+        }
+        true
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        if self.original.contains_key(name) {
+            self.edits.remove(name);
+        } else {
+            self.edits.set.remove(name);
+        }
+    }
+
+    /// Drops any edit of `name`, back to the request's value.
+    pub fn reset(&mut self, name: &str) {
+        self.edits.set.remove(name);
+        self.edits.removed.remove(name);
+    }
+
+    pub fn display_value<'a>(&self, name: &str, value: &'a str) -> &'a str {
+        if is_secret_env_var(name) && !self.revealed.contains(name) {
+            MASKED_VALUE
+        } else {
+            value
+        }
+    }
+
+    pub fn toggle_reveal(&mut self, name: &str) {
+        if !self.revealed.remove(name) {
+            self.revealed.insert(name.to_string());
+        }
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Filtro:");
+            ui.text_edit_singleline(&mut self.filter);
+        });
+        let filter = self.filter.to_ascii_uppercase();
+        let mut changed = None;
+        let mut removed = None;
+        let mut reset = None;
+        let mut toggled = None;
+        egui::Grid::new("alicia_session_env")
+            .striped(true)
+            .show(ui, |ui| {
+                for (name, value) in self.env() {
+                    if !name.to_ascii_uppercase().contains(&filter) {
+                        continue;
+                    }
+                    let edited = self.edits.set.contains_key(&name);
+                    if edited {
+                        ui.colored_label(ui.visuals().warn_fg_color, &name);
+                    } else {
+                        ui.monospace(&name);
+                    }
+                    if is_secret_env_var(&name) && !self.revealed.contains(&name) {
+                        ui.monospace(MASKED_VALUE);
+                        if ui.small_button("Mostrar").clicked() {
+                            toggled = Some(name.clone());
+                        }
+                    } else {
+                        let mut draft = value.clone();
+                        if ui.text_edit_singleline(&mut draft).changed() {
+                            changed = Some((name.clone(), draft));
+                        }
+                        if is_secret_env_var(&name) && ui.small_button("Ocultar").clicked() {
+                            toggled = Some(name.clone());
+                        }
+                    }
+                    if ui.small_button("Remover").clicked() {
+                        removed = Some(name.clone());
+                    }
+                    if edited && ui.small_button("Restaurar").clicked() {
+                        reset = Some(name.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+        for name in &self.edits.removed {
+            ui.horizontal(|ui| {
+                ui.colored_label(ui.visuals().error_fg_color, format!("{name} (removida)"));
+                if ui.small_button("Restaurar").clicked() {
+                    reset = Some(name.clone());
+                }
+            });
+        }
+        if let Some((name, value)) = changed {
+            self.set(&name, &value);
+        }
+        if let Some(name) = removed {
+            self.remove(&name);
+        }
+        if let Some(name) = reset {
+            self.reset(&name);
+        }
+        if let Some(name) = toggled {
+            self.toggle_reveal(&name);
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_name).hint_text("NOME"));
+            ui.add(egui::TextEdit::singleline(&mut self.new_value).hint_text("valor"));
+            if ui.button("Adicionar").clicked() {
+                let name = self.new_name.clone();
+                let value = std::mem::take(&mut self.new_value);
+                if self.set(&name, &value) {
+                    self.new_name.clear();
+                    self.message = None;
+                } else {
+                    self.message = Some(format!("Nome de variável inválido: {name:?}"));
+                }
+            }
+        });
+        if let Some(message) = self.message.as_deref() {
+            ui.colored_label(ui.visuals().error_fg_color, message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use codex_alicia_core::SessionStartRequest;
+    use pretty_assertions::assert_eq;
+
+    use super::MASKED_VALUE;
+    use super::SessionEnvEditor;
+
+    #[test]
+    fn edits_are_tracked_against_the_request_and_secrets_are_masked() {
+        let request = SessionStartRequest::new(
+            "session-1",
+            "bash",
+            Vec::new(),
+            PathBuf::from("/ws"),
+            HashMap::from([
+                ("HOME".to_string(), "/home/alicia".to_string()),
+                ("OPENAI_API_KEY".to_string(), "sk-123".to_string()),
+            ]),
+        );
+        let mut editor = SessionEnvEditor::new(&request);
+
+        assert!(editor.set("RUST_LOG", "debug"));
+        assert!(editor.set("HOME", "/tmp"));
+        assert!(!editor.set("A=B", "x"));
+        editor.remove("OPENAI_API_KEY");
+        assert_eq!(
+            editor.env().into_iter().collect::<Vec<_>>(),
+            vec![
+                ("HOME".to_string(), "/tmp".to_string()),
+                ("RUST_LOG".to_string(), "debug".to_string()),
+            ]
+        );
+
+        assert!(editor.set("HOME", "/home/alicia"));
+        editor.reset("OPENAI_API_KEY");
+        assert_eq!(
+            editor.edits().set.keys().collect::<Vec<_>>(),
+            vec!["RUST_LOG"]
+        );
+        assert!(editor.edits().removed.is_empty());
+
+        assert_eq!(
+            editor.display_value("OPENAI_API_KEY", "sk-123"),
+            MASKED_VALUE
+        );
+        editor.toggle_reveal("OPENAI_API_KEY");
+        assert_eq!(editor.display_value("OPENAI_API_KEY", "sk-123"), "sk-123");
+        assert_eq!(editor.display_value("HOME", "/home/alicia"), "/home/alicia");
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+
+pub const DEFAULT_ERROR_CENTER_CAPACITY: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorAction {
+    Retry,
+    OpenSettings,
+}
+
+/// Stable code, beginner message and follow-up actions for an error surfaced to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorReport {
+    pub code: &'static str,
+    pub message: String,
+    pub suggested_actions: Vec<ErrorAction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorEntry {
+    pub id: u64,
+    pub timestamp_unix_s: i64,
+    pub code: &'static str,
+    pub message: String,
+    pub suggested_actions: Vec<ErrorAction>,
+    pub read: bool,
+}
+
+/// Bounded history of recent errors; the oldest entries are dropped once `capacity` is hit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorCenter {
+    entries: VecDeque<ErrorEntry>,
+    capacity: usize,
+    next_id: u64,
+}
+
+impl Default for ErrorCenter {
+    fn default() -> Self {
+        Self::new(DEFAULT_ERROR_CENTER_CAPACITY)
+    }
+}
+
+impl ErrorCenter {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            next_id: 0,
+        }
+    }
+
+    pub fn record(&mut self, report: ErrorReport, now_unix_s: i64) -> u64 {
+        let id = self.next_id;
+        self.next_id = self.next_id.saturating_add(1);
+        self.entries.push_back(ErrorEntry {
+            id,
+            timestamp_unix_s: now_unix_s,
+            code: report.code,
+            message: report.message,
+            suggested_actions: report.suggested_actions,
+            read: false,
+        });
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+        id
+    }
+
+    /// Entries newest first.
+    pub fn entries(&self) -> impl Iterator<Item = &ErrorEntry> {
+        self.entries.iter().rev()
+    }
+
+    pub fn entry(&self, id: u64) -> Option<&ErrorEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn unread_count(&self) -> usize {
+        self.entries.iter().filter(|entry| !entry.read).count()
+    }
+
+    pub fn mark_all_read(&mut self) {
+        for entry in &mut self.entries {
+            entry.read = true;
+        }
+    }
+
+    pub fn dismiss(&mut self, id: u64) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.id != id);
+        self.entries.len() != before
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::ErrorAction;
+    use super::ErrorCenter;
+    use super::ErrorEntry;
+    use super::ErrorReport;
+
+    fn report(code: &'static str) -> ErrorReport {
+        ErrorReport {
+            code,
+            message: format!("{code} happened"),
+            suggested_actions: vec![ErrorAction::Retry],
+        }
+    }
+
+    #[test]
+    fn keeps_newest_entries_within_capacity() {
+        let mut center = ErrorCenter::new(2);
+        center.record(report("first"), 10);
+        center.record(report("second"), 11);
+        let third = center.record(report("third"), 12);
+
+        let codes: Vec<&str> = center.entries().map(|entry| entry.code).collect();
+        assert_eq!(codes, vec!["third", "second"]);
+        assert_eq!(
+            center.entry(third),
+            Some(&ErrorEntry {
+                id: 2,
+                timestamp_unix_s: 12,
+                code: "third",
+                message: "third happened".to_string(),
+                suggested_actions: vec![ErrorAction::Retry],
+                read: false,
+            })
+        );
+    }
+
+    #[test]
+    fn tracks_unread_and_dismissed_entries() {
+        let mut center = ErrorCenter::default();
+        let first = center.record(report("first"), 10);
+        center.record(report("second"), 11);
+        assert_eq!(center.unread_count(), 2);
+
+        center.mark_all_read();
+        assert_eq!(center.unread_count(), 0);
+
+        assert!(center.dismiss(first));
+        assert!(!center.dismiss(first));
+        assert_eq!(center.len(), 1);
+    }
+}
@@ -0,0 +1,173 @@
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_alicia_core::IpcMessage;
+use thiserror::Error;
+
+pub const EVENT_JOURNAL_FILE_NAME: &str = "alicia-events.jsonl";
+
+/// `<workspace>/.codex/alicia-events.jsonl`, next to the project policy.
+pub fn event_journal_path(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".codex").join(EVENT_JOURNAL_FILE_NAME)
+}
+
+#[derive(Debug, Error)]
+pub enum EventJournalError {
+    #[error("failed to open event journal `{path}`: {source}")]
+    OpenFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to read event journal `{path}`: {source}")]
+    ReadFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("event journal `{path}` has an invalid event on line {line}: {source}")]
+    InvalidLine {
+        path: String,
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("failed to write event journal `{path}`: {source}")]
+    WriteFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Append-only JSONL file with every `IpcMessage` the store applied, one per line, flushed
+/// as each is written so a crash loses at most the line being written.
+#[derive(Debug)]
+pub struct EventJournal {
+    path: PathBuf,
+    file: std::fs::File,
+}
+
+impl EventJournal {
+    /// Opens `path` for appending, creating it and its directory when missing.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, EventJournalError> {
+        let path = path.into();
+        let open_failed = |source| EventJournalError::OpenFailed {
+            path: path.to_string_lossy().to_string(),
+            source,
+        };
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent).map_err(open_failed)?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(open_failed)?;
+        Ok(Self { path, file })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&mut self, message: &IpcMessage) -> Result<(), EventJournalError> {
+        let mut line =
+            serde_json::to_vec(message).map_err(|error| EventJournalError::WriteFailed {
+                path: self.path.to_string_lossy().to_string(),
+                source: std::io::Error::other(error),
+            })?;
+        line.push(b'\n');
+        self.file
+            .write_all(&line)
+            .and_then(|()| self.file.flush())
+            .map_err(|source| EventJournalError::WriteFailed {
+                path: self.path.to_string_lossy().to_string(),
+                source,
+            })
+    }
+}
+
+/// The events journaled at `path`, in order; a missing file has none. A last line cut short
+/// by a crash is dropped, any other invalid line fails the read.
+pub fn read_event_journal(path: &Path) -> Result<Vec<IpcMessage>, EventJournalError> {
+    let read_failed = |source| EventJournalError::ReadFailed {
+        path: path.to_string_lossy().to_string(),
+        source,
+    };
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(source) if source.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => return Err(read_failed(source)),
+    };
+    let lines = std::io::BufReader::new(file)
+        .lines()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_failed)?;
+    let mut events = Vec::with_capacity(lines.len());
+    for (index, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(message) => events.push(message),
+            Err(source) if index + 1 == lines.len() && source.is_eof() => break,
+            Err(source) => {
+                return Err(EventJournalError::InvalidLine {
+                    path: path.to_string_lossy().to_string(),
+                    line: index + 1,
+                    source,
+                });
+            }
+        }
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::IpcEvent;
+    use codex_alicia_core::IpcMessage;
+    use codex_alicia_core::ipc::PatchPreviewReady;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::EventJournal;
+    use super::EventJournalError;
+    use super::read_event_journal;
+
+    #[test]
+    fn appends_across_opens_and_tolerates_a_torn_last_line()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join(".codex").join("events.jsonl");
+        assert!(read_event_journal(&path)?.is_empty());
+
+        let preview = |action_id: &str| {
+            IpcMessage::new(IpcEvent::PatchPreviewReady(PatchPreviewReady {
+                action_id: action_id.to_string(),
+                files: vec!["src/lib.rs".to_string()],
+            }))
+        };
+        EventJournal::open(&path)?.append(&preview("act-1"))?;
+        EventJournal::open(&path)?.append(&preview("act-2"))?;
+        let mut torn = std::fs::read_to_string(&path)?;
+        torn.push_str("{\"event\":");
+        std::fs::write(&path, &torn)?;
+        assert_eq!(
+            read_event_journal(&path)?,
+            vec![preview("act-1"), preview("act-2")]
+        );
+
+        std::fs::write(&path, format!("nao e json\n{torn}"))?;
+        assert!(matches!(
+            read_event_journal(&path),
+            Err(EventJournalError::InvalidLine { line: 1, .. })
+        ));
+        Ok(())
+    }
+}
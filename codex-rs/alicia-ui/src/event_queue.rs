@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+/// Events read from the session manager that may wait for the event task to apply them; see
+/// `AliciaUiRuntime::spawn_event_task`.
+pub const EVENT_QUEUE_CAPACITY: usize = 4_096;
+/// Events applied under one store lock, so frontends get the lock back between batches.
+pub(crate) const EVENT_APPLY_BATCH: usize = 256;
+/// How often a runtime whose events are applied by the event task checks the store while
+/// waiting for a session to finish.
+pub(crate) const EVENT_TASK_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// Names under which each subscriber of the session events reports lag.
+pub(crate) const RUNTIME_EVENTS_SUBSCRIBER: &str = "runtime";
+pub(crate) const EVENT_TASK_SUBSCRIBER: &str = "event_task";
+
+/// Backpressure of the event task's queue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventQueueMetrics {
+    pub capacity: usize,
+    /// Events still queued when the last batch was applied.
+    pub depth: usize,
+    pub max_depth: usize,
+    /// Times the reader found the queue full and waited for the applier to catch up.
+    pub full_waits: u64,
+    pub applied: u64,
+}
@@ -0,0 +1,137 @@
+/// Lines from the end of the output looked at when classifying a failure.
+pub const FAILURE_TAIL_LINES: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    MissingBinary,
+    PermissionDenied,
+    CompileError,
+    TestFailure,
+}
+
+impl FailureKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MissingBinary => "missing_binary",
+            Self::PermissionDenied => "permission_denied",
+            Self::CompileError => "compile_error",
+            Self::TestFailure => "test_failure",
+        }
+    }
+}
+
+/// Suggested next step for a session that finished with a non-zero exit code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailureSuggestion {
+    pub kind: FailureKind,
+    pub title: String,
+    pub suggestion: String,
+}
+
+/// Classifies a failed command from its exit code and the last lines of its output. Known
+/// failures, in order: missing binary, permission denied, compile error, test failure.
+pub fn classify_failure(
+    command: &[String],
+    exit_code: i32,
+    output_tail: &[String],
+) -> Option<FailureSuggestion> {
+    if exit_code == 0 {
+        return None;
+    }
+    let program = command.first().map_or("", String::as_str);
+    let tail = output_tail.join("\n");
+    let tail_lower = tail.to_lowercase();
+
+    if exit_code == 127
+        || tail_lower.contains("command not found")
+        || tail_lower.contains("is not recognized as an internal or external command")
+    {
+        return Some(FailureSuggestion {
+            kind: FailureKind::MissingBinary,
+            title: format!("`{program}` nao foi encontrado"),
+            suggestion: format!("Instale `{program}` ou confira se ele esta no PATH."),
+        });
+    }
+    if exit_code == 126 || tail_lower.contains("permission denied") {
+        return Some(FailureSuggestion {
+            kind: FailureKind::PermissionDenied,
+            title: "Permissao negada".to_string(),
+            suggestion: "Confira as permissoes do arquivo (por exemplo `chmod +x`) ou se o perfil permite a acao.".to_string(),
+        });
+    }
+    let is_cargo = program == "cargo";
+    if tail.contains("error[E")
+        || tail.contains("error: could not compile")
+        || tail.contains("SyntaxError")
+        || tail.contains(": error:")
+    {
+        let suggestion = if is_cargo && command.get(1).is_some_and(|sub| sub != "build") {
+            "Rode `cargo build` primeiro para ver os erros de compilacao completos.".to_string()
+        } else {
+            "Corrija o primeiro erro de compilacao mostrado na saida e rode de novo.".to_string()
+        };
+        return Some(FailureSuggestion {
+            kind: FailureKind::CompileError,
+            title: "Erro de compilacao".to_string(),
+            suggestion,
+        });
+    }
+    if tail.contains("test result: FAILED")
+        || tail.contains("Tests failed")
+        || tail_lower.contains("npm err! test failed")
+        || tail.contains("FAILED (failures=")
+        || tail.lines().any(|line| line.starts_with("FAILED "))
+    {
+        let suggestion = if is_cargo {
+            "Rode so o teste que falhou (`cargo test nome_do_teste`) para ver a saida completa."
+        } else {
+            "Rode so o teste que falhou para ver a saida completa."
+        };
+        return Some(FailureSuggestion {
+            kind: FailureKind::TestFailure,
+            title: "Testes falharam".to_string(),
+            suggestion: suggestion.to_string(),
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::FailureKind;
+    use super::classify_failure;
+
+    fn kind(command: &str, exit_code: i32, output: &str) -> Option<FailureKind> {
+        let command: Vec<String> = command.split(' ').map(str::to_string).collect();
+        let tail: Vec<String> = output.lines().map(str::to_string).collect();
+        classify_failure(&command, exit_code, &tail).map(|suggestion| suggestion.kind)
+    }
+
+    #[test]
+    fn classifies_common_failures() {
+        assert_eq!(
+            kind("rg foo", 127, "sh: rg: command not found"),
+            Some(FailureKind::MissingBinary)
+        );
+        assert_eq!(
+            kind("./deploy.sh", 126, "sh: ./deploy.sh: Permission denied"),
+            Some(FailureKind::PermissionDenied)
+        );
+        assert_eq!(
+            kind(
+                "cargo test",
+                101,
+                "error[E0425]: cannot find value `x`\nerror: could not compile `app`"
+            ),
+            Some(FailureKind::CompileError)
+        );
+        assert_eq!(
+            kind("cargo test", 101, "test result: FAILED. 3 passed; 1 failed"),
+            Some(FailureKind::TestFailure)
+        );
+        assert_eq!(kind("false", 1, ""), None);
+        assert_eq!(kind("rg foo", 0, "command not found"), None);
+    }
+}
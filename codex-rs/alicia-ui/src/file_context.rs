@@ -0,0 +1,229 @@
+use std::path::Path;
+
+use codex_alicia_core::PolicyBridgeError;
+use codex_alicia_core::ensure_target_in_workspace;
+use thiserror::Error;
+
+use crate::PatchHunkDecision;
+use crate::PatchHunkPreview;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileContextLineKind {
+    Unchanged,
+    HunkContext,
+    Removed,
+    Added,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileContextLine {
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub kind: FileContextLineKind,
+    pub text: String,
+    pub hunk_id: Option<String>,
+}
+
+/// Whole workspace file with the hunks of a pending patch spliced in place, so hunks can be
+/// reviewed together with their surrounding code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FullFileContext {
+    pub file_path: String,
+    pub lines: Vec<FileContextLine>,
+    pub hunk_starts: Vec<FullFileHunkStart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FullFileHunkStart {
+    pub line_index: usize,
+    pub hunk_id: String,
+    pub header: String,
+    pub decision: PatchHunkDecision,
+}
+
+#[derive(Debug, Error)]
+pub enum FileContextError {
+    #[error("file `{file_path}` is outside the workspace: {source}")]
+    OutsideWorkspace {
+        file_path: String,
+        #[source]
+        source: PolicyBridgeError,
+    },
+    #[error("failed to read file `{file_path}`: {source}")]
+    ReadFailed {
+        file_path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl FullFileContext {
+    pub fn load(
+        workspace_root: &Path,
+        file_path: &str,
+        hunks: &[PatchHunkPreview],
+    ) -> Result<Self, FileContextError> {
+        let guard = ensure_target_in_workspace(workspace_root, &workspace_root.join(file_path))
+            .map_err(|source| FileContextError::OutsideWorkspace {
+                file_path: file_path.to_string(),
+                source,
+            })?;
+        let original_text = std::fs::read_to_string(&guard.canonical_target).map_err(|source| {
+            FileContextError::ReadFailed {
+                file_path: file_path.to_string(),
+                source,
+            }
+        })?;
+        Ok(Self::build(file_path, &original_text, hunks))
+    }
+
+    /// Splices `hunks` into the pre-patch `original_text`. Hunks are placed by their old
+    /// start line; lines outside any hunk are kept as unchanged context.
+    pub fn build(file_path: &str, original_text: &str, hunks: &[PatchHunkPreview]) -> Self {
+        let original_lines: Vec<&str> = original_text.lines().collect();
+        let mut ordered_hunks: Vec<&PatchHunkPreview> = hunks.iter().collect();
+        ordered_hunks.sort_by_key(|hunk| hunk.old_start);
+
+        let mut context = Self {
+            file_path: file_path.to_string(),
+            lines: Vec::new(),
+            hunk_starts: Vec::new(),
+        };
+        let mut old_cursor = 1_usize;
+        let mut new_cursor = 1_usize;
+
+        for hunk in ordered_hunks {
+            // A pure insertion (`-N,0`) goes after old line N rather than before it.
+            let first_hunk_old_line = if hunk.old_count == 0 {
+                hunk.old_start.saturating_add(1)
+            } else {
+                hunk.old_start
+            };
+            while old_cursor < first_hunk_old_line && old_cursor <= original_lines.len() {
+                context.push_unchanged(&original_lines, &mut old_cursor, &mut new_cursor);
+            }
+
+            context.hunk_starts.push(FullFileHunkStart {
+                line_index: context.lines.len(),
+                hunk_id: hunk.hunk_id.clone(),
+                header: hunk.header.clone(),
+                decision: hunk.decision,
+            });
+            for line in &hunk.lines {
+                let (kind, text) = if let Some(text) = line.strip_prefix('+') {
+                    (FileContextLineKind::Added, text)
+                } else if let Some(text) = line.strip_prefix('-') {
+                    (FileContextLineKind::Removed, text)
+                } else if let Some(text) = line.strip_prefix(' ') {
+                    (FileContextLineKind::HunkContext, text)
+                } else {
+                    continue;
+                };
+                let (old_line, new_line) = match kind {
+                    FileContextLineKind::Added => (None, Some(new_cursor)),
+                    FileContextLineKind::Removed => (Some(old_cursor), None),
+                    FileContextLineKind::HunkContext | FileContextLineKind::Unchanged => {
+                        (Some(old_cursor), Some(new_cursor))
+                    }
+                };
+                if old_line.is_some() {
+                    old_cursor += 1;
+                }
+                if new_line.is_some() {
+                    new_cursor += 1;
+                }
+                context.lines.push(FileContextLine {
+                    old_line,
+                    new_line,
+                    kind,
+                    text: text.to_string(),
+                    hunk_id: Some(hunk.hunk_id.clone()),
+                });
+            }
+        }
+
+        while old_cursor <= original_lines.len() {
+            context.push_unchanged(&original_lines, &mut old_cursor, &mut new_cursor);
+        }
+
+        context
+    }
+
+    fn push_unchanged(
+        &mut self,
+        original_lines: &[&str],
+        old_cursor: &mut usize,
+        new_cursor: &mut usize,
+    ) {
+        let text = original_lines
+            .get(old_cursor.saturating_sub(1))
+            .copied()
+            .unwrap_or_default();
+        self.lines.push(FileContextLine {
+            old_line: Some(*old_cursor),
+            new_line: Some(*new_cursor),
+            kind: FileContextLineKind::Unchanged,
+            text: text.to_string(),
+            hunk_id: None,
+        });
+        *old_cursor += 1;
+        *new_cursor += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::FileContextLineKind;
+    use super::FullFileContext;
+    use crate::DiffParseError;
+    use crate::parse_unified_diff_hunks;
+
+    #[test]
+    fn splices_hunks_into_full_file_in_place() -> Result<(), DiffParseError> {
+        let original = "fn main() {\n    println!(\"old\");\n}\n\nfn helper() {}\n";
+        let diff = "@@ -1,3 +1,3 @@\n fn main() {\n-    println!(\"old\");\n+    println!(\"new\");\n }\n@@ -5,0 +6,1 @@\n+fn added() {}\n";
+        let hunks = parse_unified_diff_hunks(diff)?;
+
+        let context = FullFileContext::build("src/main.rs", original, &hunks);
+
+        let rendered: Vec<(FileContextLineKind, &str, Option<&str>)> = context
+            .lines
+            .iter()
+            .map(|line| (line.kind, line.text.as_str(), line.hunk_id.as_deref()))
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![
+                (
+                    FileContextLineKind::HunkContext,
+                    "fn main() {",
+                    Some("hunk-1")
+                ),
+                (
+                    FileContextLineKind::Removed,
+                    "    println!(\"old\");",
+                    Some("hunk-1")
+                ),
+                (
+                    FileContextLineKind::Added,
+                    "    println!(\"new\");",
+                    Some("hunk-1")
+                ),
+                (FileContextLineKind::HunkContext, "}", Some("hunk-1")),
+                (FileContextLineKind::Unchanged, "", None),
+                (FileContextLineKind::Unchanged, "fn helper() {}", None),
+                (FileContextLineKind::Added, "fn added() {}", Some("hunk-2")),
+            ]
+        );
+        let starts: Vec<(usize, &str)> = context
+            .hunk_starts
+            .iter()
+            .map(|start| (start.line_index, start.hunk_id.as_str()))
+            .collect();
+        assert_eq!(starts, vec![(0, "hunk-1"), (6, "hunk-2")]);
+        assert_eq!(context.lines[6].new_line, Some(6));
+        Ok(())
+    }
+}
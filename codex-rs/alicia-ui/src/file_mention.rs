@@ -0,0 +1,147 @@
+use std::path::Path;
+
+use crate::symbol_context::SKIPPED_DIRS;
+
+/// Files listed for `@` completion; larger workspaces are cut off.
+pub const MAX_MENTION_CANDIDATES: usize = 10_000;
+
+/// Paths referenced as `@path` in `input`, in order and without repeats. Trailing punctuation
+/// is not part of the path, so `veja @src/lib.rs.` mentions `src/lib.rs`.
+pub fn parse_file_mentions(input: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    for word in input.split_whitespace() {
+        let Some(path) = word.strip_prefix('@') else {
+            continue;
+        };
+        let path = path.trim_end_matches([',', '.', ';', ':', '!', '?', ')']);
+        if !path.is_empty() && !mentions.iter().any(|existing| existing == path) {
+            mentions.push(path.to_string());
+        }
+    }
+    mentions
+}
+
+/// The mention being typed at the end of `input`, without the `@`.
+pub fn active_file_mention(input: &str) -> Option<&str> {
+    if input.ends_with(char::is_whitespace) {
+        return None;
+    }
+    input.split_whitespace().next_back()?.strip_prefix('@')
+}
+
+/// Workspace files relative to `workspace_root` with `/` separators, skipping hidden and
+/// build directories.
+pub fn workspace_files(workspace_root: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    collect_workspace_files(workspace_root, workspace_root, &mut files);
+    files.sort();
+    files
+}
+
+fn collect_workspace_files(root: &Path, dir: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if files.len() >= MAX_MENTION_CANDIDATES {
+            return;
+        }
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if file_name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            if !SKIPPED_DIRS.contains(&file_name.as_str()) {
+                collect_workspace_files(root, &path, files);
+            }
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let components: Vec<String> = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect();
+            files.push(components.join("/"));
+        }
+    }
+}
+
+/// Up to `limit` of `files` containing the characters of `query` in order, best first: a
+/// match inside the file name beats one spread over directories, and shorter paths win ties.
+pub fn fuzzy_file_matches(files: &[String], query: &str, limit: usize) -> Vec<String> {
+    let query = query.to_lowercase();
+    let mut scored: Vec<(usize, &String)> = files
+        .iter()
+        .filter_map(|file| fuzzy_score(file, &query).map(|score| (score, file)))
+        .collect();
+    scored.sort_by(|(left_score, left), (right_score, right)| {
+        left_score
+            .cmp(right_score)
+            .then(left.len().cmp(&right.len()))
+            .then(left.cmp(right))
+    });
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, file)| file.clone())
+        .collect()
+}
+
+/// Lower is better; `None` when `query` is not a subsequence of `file`.
+fn fuzzy_score(file: &str, query: &str) -> Option<usize> {
+    let file = file.to_lowercase();
+    let file_name_start = file.rfind('/').map_or(0, |index| index + 1);
+    let mut chars = file.char_indices();
+    let mut previous: Option<usize> = None;
+    let mut first: Option<usize> = None;
+    let mut gaps = 0;
+    for wanted in query.chars() {
+        let (index, _) = chars.find(|(_, ch)| *ch == wanted)?;
+        if let Some(previous) = previous {
+            gaps += index - previous - 1;
+        }
+        first.get_or_insert(index);
+        previous = Some(index);
+    }
+    let outside_file_name = first.map_or(0, |first| usize::from(first < file_name_start));
+    Some(outside_file_name * 1_000 + gaps)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::active_file_mention;
+    use super::fuzzy_file_matches;
+    use super::parse_file_mentions;
+    use super::workspace_files;
+
+    #[test]
+    fn parses_mentions_without_trailing_punctuation() {
+        assert_eq!(
+            parse_file_mentions("compare @src/lib.rs, @Cargo.toml e @src/lib.rs. mail@host"),
+            vec!["src/lib.rs".to_string(), "Cargo.toml".to_string()]
+        );
+        assert_eq!(active_file_mention("veja @src/li"), Some("src/li"));
+        assert_eq!(active_file_mention("veja @src/lib.rs "), None);
+    }
+
+    #[test]
+    fn ranks_file_name_matches_first() -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::tempdir()?;
+        std::fs::create_dir_all(workspace.path().join("src/bin"))?;
+        std::fs::create_dir_all(workspace.path().join("target"))?;
+        for file in ["src/lib.rs", "src/bin/main.rs", "target/lib.rs", ".env"] {
+            std::fs::write(workspace.path().join(file), "")?;
+        }
+        let files = workspace_files(workspace.path());
+        assert_eq!(files, vec!["src/bin/main.rs", "src/lib.rs"]);
+
+        assert_eq!(fuzzy_file_matches(&files, "lib", 5), vec!["src/lib.rs"]);
+        assert_eq!(
+            fuzzy_file_matches(&files, "main", 5),
+            vec!["src/bin/main.rs"]
+        );
+        assert_eq!(fuzzy_file_matches(&files, "srs", 1), vec!["src/lib.rs"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use egui::FontData;
+use egui::FontDefinitions;
+use egui::FontFamily;
+use egui::FontId;
+use egui::TextStyle;
+use thiserror::Error;
+
+pub const DEFAULT_MONOSPACE_FONT_SIZE: f32 = 13.0;
+const CUSTOM_MONOSPACE_FONT: &str = "alicia-monospace";
+const FALLBACK_MONOSPACE_FONT: &str = "alicia-monospace-fallback";
+/// System fonts with wide box-drawing and symbol coverage. The first one found is added after
+/// egui's bundled Hack font, so glyphs missing from the main font still render.
+const FALLBACK_FONT_FILES: &[&str] = &[
+    "DejaVuSansMono.ttf",
+    "NotoSansMono-Regular.ttf",
+    "Menlo.ttc",
+    "CascadiaMono.ttf",
+    "consola.ttf",
+];
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc"];
+const MAX_FONT_DIR_DEPTH: usize = 4;
+
+/// Monospace font used by the terminal, timeline and diff views.
+///
+/// An empty `family` keeps egui's bundled Hack. egui does not shape text, so ligatures never
+/// render; with `ligatures` off the font's `NL` (no ligatures) variant is preferred when
+/// installed, which keeps glyph widths identical to other terminals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontConfig {
+    pub family: String,
+    pub size: f32,
+    pub ligatures: bool,
+}
+
+impl Default for FontConfig {
+    fn default() -> Self {
+        Self {
+            family: String::new(),
+            size: DEFAULT_MONOSPACE_FONT_SIZE,
+            ligatures: true,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum FontConfigError {
+    #[error("font family `{0}` was not found")]
+    FamilyNotFound(String),
+    #[error("failed to read font `{path}`: {source}")]
+    ReadFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Applies `config` to `ctx`. Size and the fallback font are applied even when the configured
+/// family cannot be loaded; that error is still returned so it can be surfaced.
+pub fn apply_font_config(ctx: &egui::Context, config: &FontConfig) -> Result<(), FontConfigError> {
+    let font_files = installed_font_files(&font_search_dirs());
+    let mut definitions = FontDefinitions::default();
+    if let Some(path) = FALLBACK_FONT_FILES.iter().find_map(|file_name| {
+        font_files.iter().find(|path| {
+            path.file_name()
+                .is_some_and(|name| name.eq_ignore_ascii_case(file_name))
+        })
+    }) && let Ok(bytes) = std::fs::read(path)
+    {
+        add_monospace_font(&mut definitions, FALLBACK_MONOSPACE_FONT, bytes, false);
+    }
+
+    let result = if config.family.trim().is_empty() {
+        Ok(())
+    } else {
+        resolve_font_file(&config.family, config.ligatures, &font_files)
+            .ok_or_else(|| FontConfigError::FamilyNotFound(config.family.clone()))
+            .and_then(|path| {
+                std::fs::read(&path).map_err(|source| FontConfigError::ReadFailed {
+                    path: path.to_string_lossy().to_string(),
+                    source,
+                })
+            })
+            .map(|bytes| add_monospace_font(&mut definitions, CUSTOM_MONOSPACE_FONT, bytes, true))
+    };
+
+    ctx.set_fonts(definitions);
+    ctx.style_mut(|style| {
+        style
+            .text_styles
+            .insert(TextStyle::Monospace, FontId::monospace(config.size));
+    });
+    result
+}
+
+fn add_monospace_font(
+    definitions: &mut FontDefinitions,
+    name: &str,
+    bytes: Vec<u8>,
+    primary: bool,
+) {
+    definitions
+        .font_data
+        .insert(name.to_string(), Arc::new(FontData::from_owned(bytes)));
+    let family = definitions
+        .families
+        .entry(FontFamily::Monospace)
+        .or_default();
+    if primary {
+        family.insert(0, name.to_string());
+    } else {
+        family.push(name.to_string());
+    }
+}
+
+pub fn font_search_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![
+        PathBuf::from("/usr/share/fonts"),
+        PathBuf::from("/usr/local/share/fonts"),
+        PathBuf::from("/Library/Fonts"),
+        PathBuf::from("/System/Library/Fonts"),
+    ];
+    if let Some(home) = std::env::var_os("HOME") {
+        let home = PathBuf::from(home);
+        dirs.push(home.join(".local/share/fonts"));
+        dirs.push(home.join(".fonts"));
+        dirs.push(home.join("Library/Fonts"));
+    }
+    if let Some(windir) = std::env::var_os("WINDIR") {
+        dirs.push(PathBuf::from(windir).join("Fonts"));
+    }
+    dirs
+}
+
+pub fn installed_font_files(search_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in search_dirs {
+        collect_font_files(dir, MAX_FONT_DIR_DEPTH, &mut files);
+    }
+    files
+}
+
+/// Picks the file for `family`, which may be a path or a family name such as
+/// `JetBrains Mono`. Regular weights win over other styles.
+pub fn resolve_font_file(family: &str, ligatures: bool, font_files: &[PathBuf]) -> Option<PathBuf> {
+    let family = family.trim();
+    if Path::new(family).is_file() {
+        return Some(PathBuf::from(family));
+    }
+    let wanted = normalize_font_name(family);
+    if wanted.is_empty() {
+        return None;
+    }
+    let no_ligatures_prefix = format!("{wanted}nl");
+
+    font_files
+        .iter()
+        .filter_map(|path| {
+            let stem = normalize_font_name(&path.file_stem()?.to_string_lossy());
+            if !stem.starts_with(&wanted) {
+                return None;
+            }
+            let is_no_ligatures = stem.starts_with(&no_ligatures_prefix);
+            let variant_mismatch = is_no_ligatures == ligatures;
+            let not_regular = !(stem.ends_with("regular") || stem == wanted);
+            Some(((variant_mismatch, not_regular, stem.len()), path))
+        })
+        .min_by_key(|(key, _)| *key)
+        .map(|(_, path)| path.clone())
+}
+
+fn collect_font_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 0 {
+                collect_font_files(&path, depth - 1, files);
+            }
+        } else if path.extension().is_some_and(|extension| {
+            FONT_EXTENSIONS
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        }) {
+            files.push(path);
+        }
+    }
+}
+
+fn normalize_font_name(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|ch| ch.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::installed_font_files;
+    use super::resolve_font_file;
+
+    #[test]
+    fn resolves_regular_variant_and_honors_ligature_preference() -> std::io::Result<()> {
+        let temp = TempDir::new()?;
+        let family_dir = temp.path().join("jetbrains");
+        std::fs::create_dir_all(&family_dir)?;
+        for file_name in [
+            "JetBrainsMono-Bold.ttf",
+            "JetBrainsMono-Regular.ttf",
+            "JetBrainsMonoNL-Regular.ttf",
+            "README.txt",
+        ] {
+            std::fs::write(family_dir.join(file_name), b"")?;
+        }
+        let font_files = installed_font_files(&[temp.path().to_path_buf()]);
+
+        assert_eq!(
+            resolve_font_file("JetBrains Mono", true, &font_files),
+            Some(family_dir.join("JetBrainsMono-Regular.ttf"))
+        );
+        assert_eq!(
+            resolve_font_file("JetBrains Mono", false, &font_files),
+            Some(family_dir.join("JetBrainsMonoNL-Regular.ttf"))
+        );
+        assert_eq!(resolve_font_file("Fira Code", true, &font_files), None);
+        Ok(())
+    }
+}
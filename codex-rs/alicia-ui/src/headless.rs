@@ -0,0 +1,312 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use codex_alicia_core::AuditRecord;
+use codex_alicia_core::ENCRYPTED_AUDIT_LINE_PREFIX;
+use codex_alicia_core::SessionStartRequest;
+use thiserror::Error;
+use tokio::io::AsyncBufRead;
+use tokio::io::AsyncBufReadExt;
+
+use crate::AliciaUiRuntime;
+use crate::AliciaUiRuntimeError;
+use crate::CommandLifecycle;
+use crate::UiEventStoreError;
+use crate::UiNotice;
+use crate::approval_summary_text;
+use crate::unix_now_s;
+
+const HEADLESS_POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// How `run_headless_session` answers the approvals the session raises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadlessApprovalMode {
+    /// Prints each approval and reads the answer, one per line.
+    Ask,
+    ApproveAll,
+    DenyAll,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalAnswer {
+    Approve,
+    Deny,
+    /// Approves and saves an approval rule in the project policy, like "Sempre permitir".
+    AlwaysAllow,
+}
+
+impl ApprovalAnswer {
+    /// Reads `s`/`sim`, `n`/`nao` or `m`/`sempre`, plus the English `y`/`yes`, `no` and
+    /// `a`/`always`, in any case.
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "s" | "sim" | "y" | "yes" => Some(Self::Approve),
+            "n" | "nao" | "não" | "no" => Some(Self::Deny),
+            "m" | "sempre" | "a" | "always" => Some(Self::AlwaysAllow),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum HeadlessError {
+    #[error(transparent)]
+    Runtime(#[from] AliciaUiRuntimeError),
+    #[error(transparent)]
+    Store(#[from] UiEventStoreError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(
+        "audit log `{path}` is encrypted; decrypt it with `codex-alicia-ui-app --decrypt-audit`"
+    )]
+    EncryptedAudit { path: String },
+    #[error("audit log `{path}` has an invalid record on line {line}: {source}")]
+    InvalidAuditLine {
+        path: String,
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// Runs `request` to completion without the egui view: streams the terminal lines to `out`,
+/// answers approvals per `mode` (reading `answers` when asking; end of input denies) and
+/// returns the exit code, or 1 when the session never started.
+pub async fn run_headless_session<R, W>(
+    runtime: &mut AliciaUiRuntime,
+    request: SessionStartRequest,
+    mode: HeadlessApprovalMode,
+    answers: &mut R,
+    out: &mut W,
+) -> Result<i32, HeadlessError>
+where
+    R: AsyncBufRead + Unpin,
+    W: Write,
+{
+    let session_id = request.session_id.clone();
+    match runtime.start_session(request).await {
+        Ok(()) => writeln!(out, "Sessao iniciada: {session_id}")?,
+        Err(error) => {
+            let Some(action_id) = runtime.parked_approval_id(&session_id) else {
+                return Err(error.into());
+            };
+            writeln!(out, "{}", error.beginner_message())?;
+            writeln!(
+                out,
+                "Aguardando aprovacao {action_id} para iniciar a sessao."
+            )?;
+        }
+    }
+
+    let mut answered = HashSet::new();
+    let mut printed_lines = 0_usize;
+    loop {
+        runtime.pump_events();
+        let pending: Vec<String> = runtime
+            .store()
+            .pending_approvals()
+            .iter()
+            .map(|approval| approval.action_id.clone())
+            .filter(|action_id| !answered.contains(action_id))
+            .collect();
+        for action_id in pending {
+            let answer = match mode {
+                HeadlessApprovalMode::Ask => {
+                    ask_approval(runtime, &action_id, answers, out).await?
+                }
+                HeadlessApprovalMode::ApproveAll => ApprovalAnswer::Approve,
+                HeadlessApprovalMode::DenyAll => ApprovalAnswer::Deny,
+            };
+            let mut store = runtime.store_mut();
+            match answer {
+                ApprovalAnswer::Approve => store.approve(&action_id)?,
+                ApprovalAnswer::Deny => store.deny(&action_id)?,
+                ApprovalAnswer::AlwaysAllow => store.always_allow(&action_id)?,
+            };
+            answered.insert(action_id);
+        }
+        runtime.save_requested_approval_rules();
+        for started in runtime.resume_parked_sessions().await? {
+            writeln!(
+                out,
+                "{}",
+                UiNotice::SessionStartedAfterApproval.message(&started)
+            )?;
+        }
+
+        let session_missing = runtime.store().terminal_session(&session_id).is_none();
+        if runtime.parked_approval_id(&session_id).is_none()
+            && session_missing
+            && !runtime.session_manager().is_active(&session_id).await
+        {
+            writeln!(out, "Aprovacao negada ou expirada; sessao nao iniciada.")?;
+            return Ok(1);
+        }
+        if let Some(session) = runtime.store().terminal_session(&session_id) {
+            let lines = session.visible_lines();
+            if printed_lines < lines.len() {
+                for line in &lines[printed_lines..] {
+                    writeln!(out, "{line}")?;
+                }
+                printed_lines = lines.len();
+            }
+            if let CommandLifecycle::Finished {
+                exit_code,
+                duration_ms,
+            } = session.lifecycle
+            {
+                writeln!(
+                    out,
+                    "Sessao finalizada (exit_code={exit_code}, duration_ms={duration_ms})."
+                )?;
+                return Ok(exit_code);
+            }
+        }
+        out.flush()?;
+        tokio::time::sleep(HEADLESS_POLL_INTERVAL).await;
+    }
+}
+
+async fn ask_approval<R, W>(
+    runtime: &AliciaUiRuntime,
+    action_id: &str,
+    answers: &mut R,
+    out: &mut W,
+) -> Result<ApprovalAnswer, HeadlessError>
+where
+    R: AsyncBufRead + Unpin,
+    W: Write,
+{
+    let prompt = runtime.store().approval_prompt(action_id);
+    if let Some(prompt) = prompt {
+        writeln!(out, "{}", approval_summary_text(&prompt))?;
+        writeln!(out, "Tempo restante: {}", prompt.countdown(unix_now_s()))?;
+    }
+    loop {
+        write!(out, "Aprovar {action_id}? [s]im/[n]ao/se[m]pre: ")?;
+        out.flush()?;
+        let mut line = String::new();
+        if answers.read_line(&mut line).await? == 0 {
+            writeln!(out)?;
+            writeln!(out, "Sem resposta; negando {action_id}.")?;
+            return Ok(ApprovalAnswer::Deny);
+        }
+        if let Some(answer) = ApprovalAnswer::parse(&line) {
+            return Ok(answer);
+        }
+        writeln!(out, "Resposta invalida: {}", line.trim())?;
+    }
+}
+
+/// The records of a plaintext audit log, only those of `action_id` when given.
+pub fn read_audit_log(
+    path: &Path,
+    action_id: Option<&str>,
+) -> Result<Vec<AuditRecord>, HeadlessError> {
+    let text = std::fs::read_to_string(path)?;
+    let mut records = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line.starts_with(ENCRYPTED_AUDIT_LINE_PREFIX) {
+            return Err(HeadlessError::EncryptedAudit {
+                path: path.to_string_lossy().to_string(),
+            });
+        }
+        let record: AuditRecord =
+            serde_json::from_str(line).map_err(|source| HeadlessError::InvalidAuditLine {
+                path: path.to_string_lossy().to_string(),
+                line: index + 1,
+                source,
+            })?;
+        if action_id.is_none_or(|action_id| record.action_id.as_deref() == Some(action_id)) {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use codex_alicia_core::PermissionProfile;
+    use codex_alicia_core::SessionMode;
+    use codex_alicia_core::SessionStartRequest;
+    use codex_alicia_core::fake_session::FakeSessionManager;
+    use codex_alicia_core::fake_session::FakeSessionScript;
+    use pretty_assertions::assert_eq;
+
+    use super::ApprovalAnswer;
+    use super::HeadlessApprovalMode;
+    use super::run_headless_session;
+    use crate::AliciaUiRuntime;
+
+    #[test]
+    fn parses_portuguese_and_english_answers() {
+        assert_eq!(
+            ApprovalAnswer::parse(" Sim\n"),
+            Some(ApprovalAnswer::Approve)
+        );
+        assert_eq!(ApprovalAnswer::parse("n"), Some(ApprovalAnswer::Deny));
+        assert_eq!(
+            ApprovalAnswer::parse("always"),
+            Some(ApprovalAnswer::AlwaysAllow)
+        );
+        assert_eq!(ApprovalAnswer::parse("talvez"), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn asks_for_the_start_approval_and_streams_the_output()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let fake = FakeSessionManager::new();
+        fake.set_default_script(FakeSessionScript::exiting(0).with_output("pronto\n"));
+        let request = |session_id: &str| {
+            SessionStartRequest::new(
+                session_id,
+                "cargo",
+                vec!["test".to_string()],
+                std::env::temp_dir(),
+                HashMap::new(),
+            )
+            .with_mode(SessionMode::Pipe)
+        };
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 128);
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
+
+        let mut answers: &[u8] = b"talvez\ns\n";
+        let mut out = Vec::new();
+        let exit_code = run_headless_session(
+            &mut runtime,
+            request("sess-1"),
+            HeadlessApprovalMode::Ask,
+            &mut answers,
+            &mut out,
+        )
+        .await?;
+        let out = String::from_utf8(out)?;
+        assert_eq!(exit_code, 0);
+        assert!(out.contains("Aprovacao sess-1-start"));
+        assert!(out.contains("Resposta invalida: talvez"));
+        assert!(out.contains("pronto"));
+
+        let mut out = Vec::new();
+        let exit_code = run_headless_session(
+            &mut runtime,
+            request("sess-2"),
+            HeadlessApprovalMode::DenyAll,
+            &mut tokio::io::empty(),
+            &mut out,
+        )
+        .await?;
+        assert_eq!(exit_code, 1);
+        assert!(String::from_utf8(out)?.contains("sessao nao iniciada"));
+        assert_eq!(fake.started_requests().len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use serde::Serialize;
+
+/// How often `AliciaUiRuntime::pump_events` refreshes the health the store publishes; the
+/// check reads the project policy and opens the audit log.
+pub const HEALTH_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// State of the project policy file at the last check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum PolicyFileStatus {
+    /// No `.codex/alicia-policy.toml`; the built-in profiles apply.
+    Missing,
+    Valid,
+    Invalid {
+        error: String,
+    },
+}
+
+/// Snapshot of `AliciaUiRuntime::health`, behind the status bar indicator, the `getHealth`
+/// JSON-RPC method and `GET /health`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeHealth {
+    /// False once the session events stopped reaching the store.
+    pub event_loop_alive: bool,
+    /// Events lost by subscribers that fell behind, summed.
+    pub lagged_events: u64,
+    pub active_sessions: usize,
+    pub pending_approvals: usize,
+    /// `None` without an audit logger.
+    pub audit_writable: Option<bool>,
+    pub policy: PolicyFileStatus,
+    pub checked_at_unix_s: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthLevel {
+    Healthy,
+    /// Working, with something to look at: lost events, a failing audit log or an
+    /// unreadable policy.
+    Degraded,
+    /// No events reach the store, so the UI shows stale state.
+    Down,
+}
+
+impl RuntimeHealth {
+    pub fn level(&self) -> HealthLevel {
+        if !self.event_loop_alive {
+            HealthLevel::Down
+        } else if self.problems().is_empty() {
+            HealthLevel::Healthy
+        } else {
+            HealthLevel::Degraded
+        }
+    }
+
+    /// One line per check that failed.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.event_loop_alive {
+            problems.push("loop de eventos parado".to_string());
+        }
+        if self.lagged_events > 0 {
+            problems.push(format!(
+                "{} evento(s) perdidos por atraso",
+                self.lagged_events
+            ));
+        }
+        if self.audit_writable == Some(false) {
+            problems.push("log de auditoria sem escrita".to_string());
+        }
+        if let PolicyFileStatus::Invalid { error } = &self.policy {
+            problems.push(format!("politica do projeto invalida: {error}"));
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::HealthLevel;
+    use super::PolicyFileStatus;
+    use super::RuntimeHealth;
+
+    #[test]
+    fn level_follows_the_failed_checks() {
+        let mut health = RuntimeHealth {
+            event_loop_alive: true,
+            lagged_events: 0,
+            active_sessions: 2,
+            pending_approvals: 1,
+            audit_writable: None,
+            policy: PolicyFileStatus::Missing,
+            checked_at_unix_s: 0,
+        };
+        assert_eq!(health.level(), HealthLevel::Healthy);
+
+        health.policy = PolicyFileStatus::Invalid {
+            error: "expected `=`".to_string(),
+        };
+        health.audit_writable = Some(false);
+        assert_eq!(health.level(), HealthLevel::Degraded);
+        assert_eq!(
+            health.problems(),
+            vec![
+                "log de auditoria sem escrita".to_string(),
+                "politica do projeto invalida: expected `=`".to_string(),
+            ]
+        );
+
+        health.event_loop_alive = false;
+        assert_eq!(health.level(), HealthLevel::Down);
+    }
+}
@@ -0,0 +1,481 @@
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UiLanguage {
+    #[default]
+    Portuguese,
+    English,
+}
+
+impl UiLanguage {
+    pub const ALL: [UiLanguage; 2] = [UiLanguage::Portuguese, UiLanguage::English];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Portuguese => "pt-BR",
+            Self::English => "en",
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::Portuguese => "Português",
+            Self::English => "English",
+        }
+    }
+
+    /// Accepts language tags such as `pt`, `pt-BR`, `pt_BR.UTF-8`, `en` or `en-US`.
+    pub fn from_code(code: &str) -> Option<Self> {
+        let primary = code
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match primary.as_str() {
+            "pt" => Some(Self::Portuguese),
+            "en" => Some(Self::English),
+            _ => None,
+        }
+    }
+}
+
+static CURRENT_LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+/// Language used by `beginner_message` methods. Defaults to Portuguese.
+pub fn ui_language() -> UiLanguage {
+    match CURRENT_LANGUAGE.load(Ordering::Relaxed) {
+        1 => UiLanguage::English,
+        _ => UiLanguage::Portuguese,
+    }
+}
+
+pub fn set_ui_language(language: UiLanguage) {
+    let value = match language {
+        UiLanguage::Portuguese => 0,
+        UiLanguage::English => 1,
+    };
+    CURRENT_LANGUAGE.store(value, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BeginnerMessage {
+    SessionNotFound,
+    SessionInputNotBound,
+    SessionInputSendFailed,
+    ApprovalNotPending,
+    PatchPreviewNotFound,
+    PatchFileNotFound,
+    PatchHunkNotFound,
+    AttachmentOutsideWorkspace,
+    AttachmentNotAFile,
+    MalformedPatchDiff,
+    UnknownModel,
+    ApprovalCommandNotEditable,
+    SessionAlreadyExists,
+    RuntimeSessionNotFound,
+    PtyUnavailable,
+    SpawnFailed,
+    ResizeFailed,
+    SpawnRetriesExhausted,
+    ResolveProfileFailed,
+    WorkspaceGuardBlocked,
+    CommandBlocked,
+    PatchBlocked,
+    PatchApplyFailed,
+    SessionStopTimeout,
+    OperationCancelled,
+    AuditWriteFailed,
+    AgentFailed,
+    AuditExportFailed,
+    AuditDigestDeliveryFailed,
+    RecentDirsLoadFailed,
+    RecentDirsSaveFailed,
+    ApprovalMemoryLoadFailed,
+    ApprovalMemorySaveFailed,
+    EventJournalWriteFailed,
+    EventJournalRestoreFailed,
+    ApprovalRuleSaveFailed,
+}
+
+/// Status lines the command-line frontends print outside the error flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiNotice {
+    SessionStartedAfterApproval,
+}
+
+impl UiNotice {
+    /// The notice in the current `ui_language`, followed by `detail`.
+    pub fn message(self, detail: &str) -> String {
+        self.message_in(ui_language(), detail)
+    }
+
+    pub fn message_in(self, language: UiLanguage, detail: &str) -> String {
+        let text = match (language, self) {
+            (UiLanguage::Portuguese, Self::SessionStartedAfterApproval) => {
+                "Sessao iniciada apos aprovacao"
+            }
+            (UiLanguage::English, Self::SessionStartedAfterApproval) => {
+                "Session started after approval"
+            }
+        };
+        format!("{text}: {detail}")
+    }
+}
+
+/// Renders a `problem + next step` message. `{reason}` in the catalog text is replaced by
+/// `reason`.
+pub(crate) fn beginner_error_message(
+    language: UiLanguage,
+    message: BeginnerMessage,
+    reason: &str,
+) -> String {
+    let (problem, next_step) = match language {
+        UiLanguage::Portuguese => portuguese_catalog(message),
+        UiLanguage::English => english_catalog(message),
+    };
+    let problem = problem.replace("{reason}", reason);
+    match language {
+        UiLanguage::Portuguese => format!("{problem} Proximo passo: {next_step}"),
+        UiLanguage::English => format!("{problem} Next step: {next_step}"),
+    }
+}
+
+fn portuguese_catalog(message: BeginnerMessage) -> (&'static str, &'static str) {
+    match message {
+        BeginnerMessage::SessionNotFound => (
+            "Nao encontrei a sessao selecionada.",
+            "Escolha outra sessao ativa ou inicie uma nova sessao.",
+        ),
+        BeginnerMessage::SessionInputNotBound => (
+            "A sessao ainda nao esta pronta para receber texto.",
+            "Aguarde a sessao iniciar e tente novamente.",
+        ),
+        BeginnerMessage::SessionInputSendFailed => (
+            "Nao consegui enviar seu texto para o terminal.",
+            "Confira se a sessao ainda esta ativa e tente de novo.",
+        ),
+        BeginnerMessage::ApprovalNotPending => (
+            "Essa aprovacao ja foi resolvida.",
+            "Atualize a tela e siga para a proxima aprovacao pendente.",
+        ),
+        BeginnerMessage::PatchPreviewNotFound => (
+            "Nao encontrei a previa dessa mudanca.",
+            "Gere a previa novamente antes de aprovar ou rejeitar.",
+        ),
+        BeginnerMessage::PatchFileNotFound => (
+            "Nao encontrei o arquivo da mudanca selecionada.",
+            "Atualize a previa e tente abrir o arquivo novamente.",
+        ),
+        BeginnerMessage::PatchHunkNotFound => (
+            "Nao encontrei o bloco da mudanca selecionada.",
+            "Atualize a previa do diff e escolha o bloco novamente.",
+        ),
+        BeginnerMessage::AttachmentOutsideWorkspace => (
+            "Esse arquivo esta fora do workspace e nao pode ser anexado.",
+            "Arraste um arquivo de dentro do workspace atual.",
+        ),
+        BeginnerMessage::AttachmentNotAFile => (
+            "So consigo anexar arquivos, nao pastas ou caminhos inexistentes.",
+            "Arraste um arquivo existente do workspace.",
+        ),
+        BeginnerMessage::MalformedPatchDiff => (
+            "O diff recebido esta malformado: {reason}",
+            "Peca ao agente para gerar o diff novamente antes de revisar.",
+        ),
+        BeginnerMessage::UnknownModel => (
+            "Esse modelo nao esta configurado: {reason}",
+            "Escolha um dos modelos listados na barra lateral.",
+        ),
+        BeginnerMessage::ApprovalCommandNotEditable => (
+            "Nao consegui alterar o comando dessa aprovacao: {reason}",
+            "Revise o comando editado ou aprove o comando original.",
+        ),
+        BeginnerMessage::SessionAlreadyExists => (
+            "Ja existe uma sessao com esse identificador.",
+            "Use outro identificador de sessao e tente iniciar novamente.",
+        ),
+        BeginnerMessage::RuntimeSessionNotFound => (
+            "Nao encontrei a sessao que voce tentou usar.",
+            "Confirme o identificador da sessao ou inicie uma nova sessao.",
+        ),
+        BeginnerMessage::PtyUnavailable => (
+            "Este ambiente nao suporta terminal PTY.",
+            "Inicie a sessao no modo pipe.",
+        ),
+        BeginnerMessage::SpawnFailed => (
+            "Nao consegui iniciar a sessao.",
+            "Confirme o comando e o diretorio de trabalho antes de tentar de novo.",
+        ),
+        BeginnerMessage::ResizeFailed => (
+            "Nao consegui redimensionar o terminal da sessao.",
+            "Sessoes em modo pipe nao tem janela; inicie a sessao em modo PTY para redimensionar.",
+        ),
+        BeginnerMessage::SpawnRetriesExhausted => (
+            "Nao consegui iniciar a sessao mesmo apos novas tentativas.",
+            "Feche terminais ou processos sobrando e tente iniciar de novo em instantes.",
+        ),
+        BeginnerMessage::ResolveProfileFailed => (
+            "Nao consegui carregar a politica efetiva do projeto.",
+            "Revise o arquivo .codex/alicia-policy.toml e tente novamente.",
+        ),
+        BeginnerMessage::WorkspaceGuardBlocked => (
+            "A sessao foi bloqueada por tentar usar caminho fora do workspace.",
+            "Use um diretorio dentro do workspace atual.",
+        ),
+        BeginnerMessage::CommandBlocked => (
+            "A execucao foi bloqueada pela policy: {reason}",
+            "Aprove explicitamente a acao ou ajuste o perfil de permissao.",
+        ),
+        BeginnerMessage::PatchBlocked => (
+            "A aplicacao do patch foi bloqueada pela policy: {reason}",
+            "Aprove explicitamente o patch ou ajuste o perfil de permissao.",
+        ),
+        BeginnerMessage::PatchApplyFailed => (
+            "Nao consegui aplicar os blocos aprovados: {reason}",
+            "Decida todos os blocos; se o arquivo mudou, peca um diff novo.",
+        ),
+        BeginnerMessage::SessionStopTimeout => (
+            "A sessao demorou demais para encerrar.",
+            "Tente cancelar novamente ou finalize o processo manualmente no sistema.",
+        ),
+        BeginnerMessage::OperationCancelled => (
+            "A operacao na sessao foi interrompida antes de terminar.",
+            "Repita a acao quando quiser continuar.",
+        ),
+        BeginnerMessage::AuditWriteFailed => (
+            "A tarefa foi encerrada, mas nao consegui salvar o log de auditoria.",
+            "Verifique permissoes de escrita do arquivo de auditoria e tente novamente.",
+        ),
+        BeginnerMessage::AgentFailed => (
+            "O modelo nao respondeu ao prompt: {reason}",
+            "Confira a conexao e a configuracao do modelo e envie o prompt novamente.",
+        ),
+        BeginnerMessage::AuditExportFailed => (
+            "Nao consegui exportar a auditoria: {reason}",
+            "Confira se a pasta de destino existe e permite gravacao.",
+        ),
+        BeginnerMessage::AuditDigestDeliveryFailed => (
+            "Nao consegui entregar o resumo de auditoria: {reason}",
+            "Revise o destino do resumo na configuracao do agendamento.",
+        ),
+        BeginnerMessage::RecentDirsLoadFailed => (
+            "Nao consegui ler os diretorios recentes: {reason}",
+            "Digite o diretorio da sessao; a lista sera refeita com as proximas sessoes.",
+        ),
+        BeginnerMessage::RecentDirsSaveFailed => (
+            "Nao consegui salvar os diretorios recentes: {reason}",
+            "Confira se o arquivo de configuracao permite gravacao.",
+        ),
+        BeginnerMessage::ApprovalMemoryLoadFailed => (
+            "Nao consegui ler as aprovacoes lembradas: {reason}",
+            "As acoes voltam a pedir aprovacao; lembre-as de novo ou corrija o arquivo.",
+        ),
+        BeginnerMessage::ApprovalMemorySaveFailed => (
+            "Nao consegui salvar as aprovacoes lembradas: {reason}",
+            "Confira se o arquivo de configuracao permite gravacao.",
+        ),
+        BeginnerMessage::EventJournalWriteFailed => (
+            "Nao consegui gravar o historico de eventos, que deixou de ser salvo: {reason}",
+            "Libere espaco ou permissao de escrita em .codex e reinicie para voltar a salvar.",
+        ),
+        BeginnerMessage::EventJournalRestoreFailed => (
+            "Nao consegui restaurar o historico de eventos: {reason}",
+            "As novas sessoes seguem normalmente; revise o arquivo de historico se precisar dele.",
+        ),
+        BeginnerMessage::ApprovalRuleSaveFailed => (
+            "Nao consegui salvar a regra de aprovacao: {reason}",
+            "A aprovacao vale agora; revise o arquivo .codex/alicia-policy.toml para lembrar a regra.",
+        ),
+    }
+}
+
+fn english_catalog(message: BeginnerMessage) -> (&'static str, &'static str) {
+    match message {
+        BeginnerMessage::SessionNotFound => (
+            "I could not find the selected session.",
+            "Pick another active session or start a new one.",
+        ),
+        BeginnerMessage::SessionInputNotBound => (
+            "The session is not ready to receive text yet.",
+            "Wait for the session to start and try again.",
+        ),
+        BeginnerMessage::SessionInputSendFailed => (
+            "I could not send your text to the terminal.",
+            "Check that the session is still running and try again.",
+        ),
+        BeginnerMessage::ApprovalNotPending => (
+            "This approval has already been resolved.",
+            "Refresh the screen and move on to the next pending approval.",
+        ),
+        BeginnerMessage::PatchPreviewNotFound => (
+            "I could not find the preview for this change.",
+            "Generate the preview again before approving or rejecting.",
+        ),
+        BeginnerMessage::PatchFileNotFound => (
+            "I could not find the file for the selected change.",
+            "Refresh the preview and open the file again.",
+        ),
+        BeginnerMessage::PatchHunkNotFound => (
+            "I could not find the block of the selected change.",
+            "Refresh the diff preview and pick the block again.",
+        ),
+        BeginnerMessage::AttachmentOutsideWorkspace => (
+            "This file is outside the workspace and cannot be attached.",
+            "Drag a file from inside the current workspace.",
+        ),
+        BeginnerMessage::AttachmentNotAFile => (
+            "I can only attach files, not folders or missing paths.",
+            "Drag an existing file from the workspace.",
+        ),
+        BeginnerMessage::MalformedPatchDiff => (
+            "The received diff is malformed: {reason}",
+            "Ask the agent to generate the diff again before reviewing.",
+        ),
+        BeginnerMessage::UnknownModel => (
+            "This model is not configured: {reason}",
+            "Pick one of the models listed in the sidebar.",
+        ),
+        BeginnerMessage::ApprovalCommandNotEditable => (
+            "I could not change the command of this approval: {reason}",
+            "Review the edited command or approve the original one.",
+        ),
+        BeginnerMessage::SessionAlreadyExists => (
+            "A session with this identifier already exists.",
+            "Use a different session identifier and start again.",
+        ),
+        BeginnerMessage::RuntimeSessionNotFound => (
+            "I could not find the session you tried to use.",
+            "Check the session identifier or start a new session.",
+        ),
+        BeginnerMessage::PtyUnavailable => (
+            "This environment does not support PTY terminals.",
+            "Start the session in pipe mode.",
+        ),
+        BeginnerMessage::SpawnFailed => (
+            "I could not start the session.",
+            "Check the command and the working directory before trying again.",
+        ),
+        BeginnerMessage::ResizeFailed => (
+            "I could not resize the session terminal.",
+            "Pipe sessions have no window; start the session in PTY mode to resize it.",
+        ),
+        BeginnerMessage::SpawnRetriesExhausted => (
+            "I could not start the session even after retrying.",
+            "Close leftover terminals or processes and try again in a moment.",
+        ),
+        BeginnerMessage::ResolveProfileFailed => (
+            "I could not load the effective project policy.",
+            "Review the .codex/alicia-policy.toml file and try again.",
+        ),
+        BeginnerMessage::WorkspaceGuardBlocked => (
+            "The session was blocked for using a path outside the workspace.",
+            "Use a directory inside the current workspace.",
+        ),
+        BeginnerMessage::CommandBlocked => (
+            "Execution was blocked by the policy: {reason}",
+            "Explicitly approve the action or adjust the permission profile.",
+        ),
+        BeginnerMessage::PatchBlocked => (
+            "Applying the patch was blocked by the policy: {reason}",
+            "Explicitly approve the patch or adjust the permission profile.",
+        ),
+        BeginnerMessage::PatchApplyFailed => (
+            "I could not apply the approved hunks: {reason}",
+            "Decide every hunk; if the file changed, ask for a new diff.",
+        ),
+        BeginnerMessage::SessionStopTimeout => (
+            "The session took too long to stop.",
+            "Try cancelling again or end the process manually in the system.",
+        ),
+        BeginnerMessage::OperationCancelled => (
+            "The session operation was interrupted before it finished.",
+            "Repeat the action whenever you want to continue.",
+        ),
+        BeginnerMessage::AuditWriteFailed => (
+            "The task finished, but I could not save the audit log.",
+            "Check write permissions for the audit file and try again.",
+        ),
+        BeginnerMessage::AgentFailed => (
+            "The model did not answer the prompt: {reason}",
+            "Check the connection and the model configuration and send the prompt again.",
+        ),
+        BeginnerMessage::AuditExportFailed => (
+            "I could not export the audit log: {reason}",
+            "Check that the destination folder exists and is writable.",
+        ),
+        BeginnerMessage::AuditDigestDeliveryFailed => (
+            "I could not deliver the audit digest: {reason}",
+            "Review the digest destination in the schedule configuration.",
+        ),
+        BeginnerMessage::RecentDirsLoadFailed => (
+            "I could not read the recent directories: {reason}",
+            "Type the session directory; the list is rebuilt as new sessions start.",
+        ),
+        BeginnerMessage::RecentDirsSaveFailed => (
+            "I could not save the recent directories: {reason}",
+            "Check that the configuration file is writable.",
+        ),
+        BeginnerMessage::ApprovalMemoryLoadFailed => (
+            "I could not read the remembered approvals: {reason}",
+            "Actions will ask for approval again; remember them again or fix the file.",
+        ),
+        BeginnerMessage::ApprovalMemorySaveFailed => (
+            "I could not save the remembered approvals: {reason}",
+            "Check that the configuration file is writable.",
+        ),
+        BeginnerMessage::EventJournalWriteFailed => (
+            "I could not write the event history, which is no longer being saved: {reason}",
+            "Free up space or write access in .codex and restart to resume saving.",
+        ),
+        BeginnerMessage::EventJournalRestoreFailed => (
+            "I could not restore the event history: {reason}",
+            "New sessions work as usual; review the history file if you need it.",
+        ),
+        BeginnerMessage::ApprovalRuleSaveFailed => (
+            "I could not save the approval rule: {reason}",
+            "The approval still applies now; review the .codex/alicia-policy.toml file to keep the rule.",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::BeginnerMessage;
+    use super::UiLanguage;
+    use super::UiNotice;
+    use super::beginner_error_message;
+
+    #[test]
+    fn parses_common_language_tags() {
+        assert_eq!(
+            UiLanguage::from_code("pt_BR.UTF-8"),
+            Some(UiLanguage::Portuguese)
+        );
+        assert_eq!(UiLanguage::from_code("en-US"), Some(UiLanguage::English));
+        assert_eq!(UiLanguage::from_code("EN"), Some(UiLanguage::English));
+        assert_eq!(UiLanguage::from_code("fr"), None);
+    }
+
+    #[test]
+    fn renders_catalog_entry_per_language() {
+        assert_eq!(
+            beginner_error_message(
+                UiLanguage::English,
+                BeginnerMessage::CommandBlocked,
+                "profile denies execution"
+            ),
+            "Execution was blocked by the policy: profile denies execution Next step: Explicitly approve the action or adjust the permission profile."
+        );
+        assert_eq!(
+            beginner_error_message(UiLanguage::Portuguese, BeginnerMessage::PtyUnavailable, ""),
+            "Este ambiente nao suporta terminal PTY. Proximo passo: Inicie a sessao no modo pipe."
+        );
+        assert_eq!(
+            UiNotice::SessionStartedAfterApproval.message_in(UiLanguage::English, "sess-1"),
+            "Session started after approval: sess-1"
+        );
+    }
+}
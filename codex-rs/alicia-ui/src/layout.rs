@@ -0,0 +1,133 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+pub const LAYOUT_FILE_NAME: &str = "alicia-ui-layout.json";
+
+/// Panel sizes, visibility and terminal preferences of `AliciaEguiView`, persisted between
+/// runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LayoutState {
+    pub audit_panel_width: f32,
+    pub approval_panel_width: f32,
+    pub timeline_height: f32,
+    pub audit_panel_visible: bool,
+    pub approval_panel_visible: bool,
+    /// Copy terminal text as soon as the mouse selection ends.
+    pub terminal_copy_on_select: bool,
+}
+
+impl Default for LayoutState {
+    fn default() -> Self {
+        Self {
+            audit_panel_width: 300.0,
+            approval_panel_width: 340.0,
+            timeline_height: 200.0,
+            audit_panel_visible: true,
+            approval_panel_visible: true,
+            terminal_copy_on_select: false,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LayoutStoreError {
+    #[error("failed to read layout `{path}`: {source}")]
+    ReadFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("layout `{path}` is invalid: {source}")]
+    InvalidLayout {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("failed to write layout `{path}`: {source}")]
+    WriteFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// `$CODEX_HOME/alicia-ui-layout.json`, falling back to `~/.codex`.
+pub fn default_layout_path() -> Option<PathBuf> {
+    let codex_home = std::env::var_os("CODEX_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".codex")))?;
+    Some(codex_home.join(LAYOUT_FILE_NAME))
+}
+
+impl LayoutState {
+    /// Missing files yield the default layout.
+    pub fn load(path: &Path) -> Result<Self, LayoutStoreError> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(source) if source.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(source) => {
+                return Err(LayoutStoreError::ReadFailed {
+                    path: path.to_string_lossy().to_string(),
+                    source,
+                });
+            }
+        };
+        serde_json::from_str(&text).map_err(|source| LayoutStoreError::InvalidLayout {
+            path: path.to_string_lossy().to_string(),
+            source,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), LayoutStoreError> {
+        let write_failed = |source| LayoutStoreError::WriteFailed {
+            path: path.to_string_lossy().to_string(),
+            source,
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(write_failed)?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(std::io::Error::other);
+        std::fs::write(path, text.map_err(write_failed)?).map_err(write_failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::LayoutState;
+
+    #[test]
+    fn saves_and_restores_layout_and_defaults_missing_fields()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let temp = TempDir::new()?;
+        let path = temp.path().join("nested").join("layout.json");
+        assert_eq!(LayoutState::load(&path)?, LayoutState::default());
+
+        let layout = LayoutState {
+            audit_panel_width: 250.0,
+            approval_panel_visible: false,
+            ..LayoutState::default()
+        };
+        layout.save(&path)?;
+        assert_eq!(LayoutState::load(&path)?, layout);
+
+        std::fs::write(&path, r#"{"timelineHeight": 120.0}"#)?;
+        assert_eq!(
+            LayoutState::load(&path)?,
+            LayoutState {
+                timeline_height: 120.0,
+                ..LayoutState::default()
+            }
+        );
+        Ok(())
+    }
+}
@@ -1,39 +1,407 @@
+mod agent;
+mod alerts;
+mod approval_memory;
+mod approval_metrics;
+mod audit_digest;
+mod audit_export;
+mod chat;
+mod clipboard;
+mod code_block;
+mod command_stats;
+mod digest_schedule;
+mod editor;
+mod env_editor;
+mod error_center;
+mod event_journal;
+mod event_queue;
+mod failure_hints;
+mod file_context;
+mod file_mention;
+mod fonts;
+mod headless;
+mod health;
+mod i18n;
+mod layout;
+mod markdown;
+mod memory_budget;
+mod model_bridge;
+mod model_registry;
+mod notifications;
+mod output_search;
+mod patch_apply;
+mod policy_editor;
+mod pull_request;
+mod recent_dirs;
+mod rest;
+mod rpc;
+mod session_colors;
+mod session_compare;
+mod session_report;
+mod shared_store;
+mod shell_integration;
+mod shutdown;
+pub mod simulation;
+mod slash_command;
+mod store_config;
+mod symbol_context;
+mod terminal_emulator;
+mod terminal_graphics;
+mod terminal_selection;
+mod terminal_wrap;
+mod token_usage;
+mod unified_diff;
+#[cfg(any(test, feature = "test-util"))]
+pub mod view_harness;
+
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::collections::VecDeque;
+use std::ops::Range;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::RwLockReadGuard;
+use std::sync::RwLockWriteGuard;
 use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 use codex_alicia_core::ActionKind;
 use codex_alicia_core::ApprovalDecision;
 use codex_alicia_core::ApprovalResolution;
+use codex_alicia_core::ApprovalRule;
+use codex_alicia_core::ApprovalValidity;
+use codex_alicia_core::ApproverIdentity;
+use codex_alicia_core::ApproverSource;
 use codex_alicia_core::AuditLogger;
 use codex_alicia_core::AuditRecord;
 use codex_alicia_core::CommandOutputStream;
+use codex_alicia_core::DEFAULT_SESSION_EVENTS_CAPACITY;
+use codex_alicia_core::DenialCategory;
+use codex_alicia_core::DenialReason;
+use codex_alicia_core::EnvEdits;
 use codex_alicia_core::IpcEvent;
 use codex_alicia_core::IpcMessage;
+use codex_alicia_core::IpcTransportError;
+use codex_alicia_core::NamedProfile;
+use codex_alicia_core::NetworkIsolation;
 use codex_alicia_core::PermissionProfile;
 use codex_alicia_core::PolicyDecision;
+use codex_alicia_core::ProjectPolicyConfig;
 use codex_alicia_core::ResultStatus;
 use codex_alicia_core::SessionAuditContext;
 use codex_alicia_core::SessionManager;
 use codex_alicia_core::SessionManagerError;
+use codex_alicia_core::SessionMode;
 use codex_alicia_core::SessionStartRequest;
+use codex_alicia_core::available_network_isolation;
+use codex_alicia_core::classify_command;
 use codex_alicia_core::ensure_target_in_workspace;
+use codex_alicia_core::ensure_target_in_workspace_roots;
 use codex_alicia_core::ipc::ActionProposed;
+use codex_alicia_core::ipc::AgentMessageComplete;
+use codex_alicia_core::ipc::AgentMessageDelta;
 use codex_alicia_core::ipc::ApprovalRequested;
 use codex_alicia_core::ipc::ApprovalResolved;
 use codex_alicia_core::ipc::CommandFinished;
 use codex_alicia_core::ipc::CommandOutputChunk;
 use codex_alicia_core::ipc::CommandStarted;
+use codex_alicia_core::ipc::NetworkAccessBlocked;
 use codex_alicia_core::ipc::PatchApplied;
 use codex_alicia_core::ipc::PatchPreviewReady;
-use codex_alicia_core::network_decision_for_profile;
-use codex_alicia_core::resolve_effective_profile;
+use codex_alicia_core::ipc::ToolCallFinished;
+use codex_alicia_core::ipc::ToolCallStarted;
+use codex_alicia_core::ipc::ToolCallStatus;
+use codex_alicia_core::ipc::server::IpcServer;
+use codex_alicia_core::load_project_policy;
+use codex_alicia_core::save_project_policy;
+use serde::Deserialize;
+use serde::Serialize;
 use thiserror::Error;
 use tokio::sync::mpsc;
-
-const DEFAULT_SCROLLBACK_LINES: usize = 2_000;
-const OUTPUT_PREVIEW_MAX_CHARS: usize = 80;
+use tokio_util::sync::CancellationToken;
+use tracing::instrument;
+
+pub use agent::AgentError;
+pub use agent::AgentLoop;
+pub use agent::AgentMessage;
+pub use agent::AgentModel;
+pub use agent::AgentReply;
+pub use agent::AgentRequest;
+pub use agent::AgentRole;
+pub use agent::AgentTool;
+pub use agent::AgentToolCall;
+pub use agent::MAX_AGENT_READ_BYTES;
+pub use agent::MAX_AGENT_STEPS_PER_PROMPT;
+pub use agent::MAX_AGENT_TOOL_OUTPUT_LINES;
+#[cfg(any(test, feature = "test-util"))]
+pub use agent::ScriptedAgentModel;
+pub use alerts::Alert;
+pub use alerts::AlertChannels;
+pub use alerts::AlertEvent;
+pub use alerts::AlertSettings;
+pub use alerts::play_alert_sound;
+pub use approval_memory::APPROVAL_MEMORY_FILE_NAME;
+pub use approval_memory::ApprovalMemory;
+pub use approval_memory::ApprovalMemoryError;
+pub use approval_memory::RememberedApprovals;
+pub use approval_memory::RememberedCommand;
+pub use approval_memory::RememberedTarget;
+pub use approval_memory::default_approval_memory_path;
+pub use approval_metrics::ApprovalLatencyStats;
+pub use approval_metrics::ApprovalMetrics;
+pub use audit_digest::AuditDigest;
+pub use audit_digest::AuditDigestActionKindRow;
+pub use audit_export::AUDIT_EXPORTS_DIR;
+pub use audit_export::AuditExportError;
+pub use audit_export::AuditExportFilter;
+pub use audit_export::AuditExportFormat;
+pub use audit_export::AuditExporter;
+pub use audit_export::audit_export_path;
+pub use chat::ChatMessage;
+pub use chat::ChatRole;
+pub use chat::ChatStore;
+pub use chat::ChatToolCall;
+pub use chat::ContextAttachment;
+pub use clipboard::ClipboardError;
+pub use clipboard::ClipboardExport;
+pub use clipboard::approval_summary_text;
+pub use clipboard::copy_to_clipboard;
+pub use clipboard::patch_text;
+pub use code_block::CodeBlock;
+pub use code_block::CodeToken;
+pub use code_block::CodeTokenKind;
+pub use code_block::detect_language;
+pub use code_block::highlight_code;
+pub use code_block::render_code_block;
+pub use command_stats::CommandStats;
+pub use digest_schedule::DEFAULT_DIGEST_SCHEDULE;
+pub use digest_schedule::DigestDelivery;
+pub use digest_schedule::DigestDeliveryError;
+pub use digest_schedule::DigestSchedule;
+pub use digest_schedule::DigestScheduleError;
+pub use digest_schedule::deliver_audit_digest;
+pub use editor::EditorConfig;
+pub use editor::EditorError;
+pub use editor::EditorLocation;
+pub use editor::find_path_links;
+pub use editor::open_in_editor;
+pub use env_editor::SessionEnvEditor;
+pub use error_center::ErrorAction;
+pub use error_center::ErrorCenter;
+pub use error_center::ErrorEntry;
+pub use error_center::ErrorReport;
+pub use event_journal::EVENT_JOURNAL_FILE_NAME;
+pub use event_journal::EventJournal;
+pub use event_journal::EventJournalError;
+pub use event_journal::event_journal_path;
+pub use event_journal::read_event_journal;
+pub use event_queue::EVENT_QUEUE_CAPACITY;
+pub use event_queue::EventQueueMetrics;
+pub use failure_hints::FAILURE_TAIL_LINES;
+pub use failure_hints::FailureKind;
+pub use failure_hints::FailureSuggestion;
+pub use failure_hints::classify_failure;
+pub use file_context::FileContextError;
+pub use file_context::FileContextLine;
+pub use file_context::FileContextLineKind;
+pub use file_context::FullFileContext;
+pub use file_context::FullFileHunkStart;
+pub use file_mention::MAX_MENTION_CANDIDATES;
+pub use file_mention::active_file_mention;
+pub use file_mention::fuzzy_file_matches;
+pub use file_mention::parse_file_mentions;
+pub use file_mention::workspace_files;
+pub use fonts::DEFAULT_MONOSPACE_FONT_SIZE;
+pub use fonts::FontConfig;
+pub use fonts::FontConfigError;
+pub use fonts::apply_font_config;
+pub use fonts::font_search_dirs;
+pub use fonts::installed_font_files;
+pub use fonts::resolve_font_file;
+pub use headless::ApprovalAnswer;
+pub use headless::HeadlessApprovalMode;
+pub use headless::HeadlessError;
+pub use headless::read_audit_log;
+pub use headless::run_headless_session;
+pub use health::HEALTH_REFRESH_INTERVAL;
+pub use health::HealthLevel;
+pub use health::PolicyFileStatus;
+pub use health::RuntimeHealth;
+pub use i18n::UiLanguage;
+pub use i18n::UiNotice;
+pub use i18n::set_ui_language;
+pub use i18n::ui_language;
+pub use layout::LAYOUT_FILE_NAME;
+pub use layout::LayoutState;
+pub use layout::LayoutStoreError;
+pub use layout::default_layout_path;
+pub use markdown::InlineSpan;
+pub use markdown::MarkdownBlock;
+pub use markdown::parse_markdown;
+pub use markdown::render_markdown;
+pub use memory_budget::MemoryEvictions;
+pub use memory_budget::MemoryUsage;
+pub use memory_budget::format_byte_size;
+pub use model_bridge::BridgedAgentModel;
+pub use model_bridge::ChatDelta;
+pub use model_bridge::ChatDeltaStream;
+pub use model_bridge::ModelBridge;
+pub use model_registry::ModelDescriptor;
+pub use model_registry::ModelRegistry;
+pub use notifications::DEFAULT_INPUT_PROMPT_IDLE;
+pub use notifications::DEFAULT_LONG_COMMAND_THRESHOLD;
+pub use notifications::DesktopNotification;
+pub use notifications::NotificationKind;
+pub use notifications::NotificationSettings;
+pub use notifications::send_desktop_notification;
+pub use output_search::MAX_OUTPUT_SEARCH_HITS;
+pub use output_search::OutputSearchHit;
+pub use patch_apply::PATCH_BACKUPS_DIR;
+pub use patch_apply::PatchApplyError;
+pub use patch_apply::apply_approved_hunks_to;
+pub use patch_apply::filtered_unified_diff;
+pub use patch_apply::patch_backup_path;
+pub use policy_editor::PolicyEditor;
+pub use policy_editor::PolicyEditorError;
+pub use pull_request::PULL_REQUEST_ACTION_SUFFIX;
+pub use pull_request::ProviderHosts;
+pub use pull_request::PullRequestDraft;
+pub use pull_request::PullRequestError;
+pub use pull_request::PullRequestProvider;
+pub use pull_request::draft_pull_request;
+pub use pull_request::git_remote_url;
+pub use pull_request::open_pull_request;
+pub use pull_request::push_branch;
+pub use recent_dirs::MAX_RECENT_DIRS;
+pub use recent_dirs::RECENT_DIRS_FILE_NAME;
+pub use recent_dirs::RecentDirs;
+pub use recent_dirs::RecentDirsError;
+pub use recent_dirs::default_recent_dirs_path;
+pub use rest::rest_router;
+pub use rest::serve_rest_api;
+pub use rpc::RemoteAccess;
+pub use rpc::RpcCall;
+pub use rpc::handle_rpc_request;
+pub use rpc::listen_json_rpc;
+pub use rpc::serve_json_rpc;
+pub use session_colors::SESSION_PALETTE;
+pub use session_compare::ComparisonField;
+pub use session_compare::ComparisonRow;
+pub use session_compare::SessionComparison;
+pub use session_compare::compare_sessions;
+pub use session_report::session_report_html;
+pub use session_report::task_report_html;
+pub use shared_store::SharedUiEventStore;
+pub use shell_integration::ShellCommandRun;
+pub use shutdown::ShutdownReport;
+pub use slash_command::SlashCommandHandler;
+pub use slash_command::SlashCommandInvocation;
+pub use slash_command::SlashCommandRegistry;
+pub use slash_command::SlashCommandSpec;
+pub use slash_command::parse_slash_command;
+pub use store_config::ScrollbackLimits;
+pub use store_config::StoreConfig;
+pub use symbol_context::CallSite;
+pub use symbol_context::HunkSymbolContext;
+pub use symbol_context::MAX_LISTED_CALL_SITES;
+pub use symbol_context::ModifiedSymbol;
+pub use symbol_context::SymbolDefinition;
+pub use symbol_context::SymbolKind;
+pub use symbol_context::find_call_sites;
+pub use symbol_context::find_definitions;
+pub use symbol_context::load_hunk_symbol_contexts;
+pub use symbol_context::modified_symbols;
+pub use terminal_emulator::PTY_COLUMNS;
+pub use terminal_emulator::PTY_ROWS;
+pub use terminal_emulator::TerminalEmulator;
+pub use terminal_emulator::TerminalSpan;
+pub use terminal_graphics::GraphicsProtocol;
+pub use terminal_graphics::InlineImage;
+pub use terminal_selection::TerminalCell;
+pub use terminal_selection::TerminalSelection;
+pub use terminal_wrap::SOFT_WRAP_INDICATOR;
+pub use terminal_wrap::TerminalWrapMode;
+pub use terminal_wrap::display_width;
+pub use terminal_wrap::soft_wrap_lines;
+pub use token_usage::MAX_TRACKED_TURNS;
+pub use token_usage::ModelPricing;
+pub use token_usage::TokenUsage;
+pub use token_usage::TokenUsageLedger;
+pub use token_usage::TurnTokenUsage;
+pub use token_usage::format_cost_usd;
+pub use token_usage::format_token_count;
+pub use unified_diff::DiffLine;
+pub use unified_diff::DiffLineKind;
+pub use unified_diff::DiffParseError;
+pub use unified_diff::SideBySideRow;
+pub use unified_diff::parse_unified_diff_hunks;
+
+use crate::agent::command_tool_result;
+use crate::agent::tool_result_status;
+use crate::alerts::BellDetector;
+use crate::event_queue::EVENT_APPLY_BATCH;
+use crate::event_queue::EVENT_TASK_POLL_INTERVAL;
+use crate::event_queue::EVENT_TASK_SUBSCRIBER;
+use crate::event_queue::RUNTIME_EVENTS_SUBSCRIBER;
+use crate::i18n::BeginnerMessage;
+use crate::i18n::beginner_error_message;
+use crate::output_search::search_lines;
+use crate::session_colors::pick_session_color;
+use crate::shell_integration::CommandMarkerTracker;
+use crate::shell_integration::looks_like_input_prompt;
+use crate::shell_integration::shell_integration_env;
+use crate::shell_integration::strip_osc7_cwd;
+use crate::shutdown::INTERRUPT_INPUT;
+use crate::shutdown::write_event_snapshot;
+use crate::terminal_graphics::GraphicsFilter;
+
+const MAX_INLINE_IMAGES_PER_SESSION: usize = 32;
+const APPROVAL_RATE_WINDOW: Duration = Duration::from_secs(60);
+const VISUAL_ALERT_DURATION: Duration = Duration::from_millis(600);
+const MAX_SHELL_COMMANDS_PER_SESSION: usize = 500;
+/// Erase-display sequences (`ESC[2J` screen, `ESC[3J` scrollback), as sent by `clear`.
+const CLEAR_SCREEN_SEQUENCES: [&str; 2] = ["\x1b[2J", "\x1b[3J"];
+const CURSOR_HOME_SEQUENCE: &str = "\x1b[H";
+const CLEAR_EMULATOR_SCREEN: &str = "\x1b[H\x1b[2J\x1b[3J";
+/// Only the most recent `path:line` links of the active terminal get an open button.
+const MAX_TERMINAL_PATH_LINKS: usize = 8;
+/// Budget offered when the memory limit is turned on in the settings panel.
+const DEFAULT_MEMORY_BUDGET_MB: usize = 256;
+/// Per-terminal cap offered when the scrollback byte limit is turned on in the settings panel.
+const DEFAULT_SCROLLBACK_MB: usize = 16;
+/// Replaces the scrollback of a finished session evicted by the memory budget.
+const EVICTED_SCROLLBACK_MARKER: &str = "[saida descartada para liberar memoria]";
+const PULL_REQUEST_APPROVAL_TTL_S: i64 = 600;
+const PARKED_SESSION_ACTION_SUFFIX: &str = "-start";
+const PARKED_SESSION_APPROVAL_TTL_S: i64 = 600;
+/// Session id of audit records produced by the chat rather than a terminal session.
+const CHAT_AUDIT_SESSION_ID: &str = "chat";
+/// Target of the audit records of profile changes.
+const PROFILE_CHANGE_TARGET: &str = "permission_profile";
+/// With Ctrl+Alt, engages the panic switch from anywhere in the view.
+const PANIC_SWITCH_KEY: egui::Key = egui::Key::P;
+/// What the user types to confirm an escalation to `FullAccess` in the profile selector.
+pub const FULL_ACCESS_CONFIRMATION: &str = "full_access";
+/// Where the chat's "Relatório da tarefa" button writes, under the workspace root.
+const TASK_REPORTS_DIR: &str = ".codex/reports";
+/// Length first offered for a temporary `FullAccess` grant.
+const DEFAULT_TEMPORARY_FULL_ACCESS_MINUTES: u32 = 15;
+const MAX_MENTION_SUGGESTIONS: usize = 8;
+/// How often the task from `AliciaUiRuntime::spawn_approval_expiry_task` looks for expired
+/// approvals.
+pub const APPROVAL_EXPIRY_INTERVAL: Duration = Duration::from_secs(1);
+const APPROVAL_EVENTS_CAPACITY: usize = 256;
+/// Events from IPC clients waiting for the store, across all connections.
+const IPC_INCOMING_CAPACITY: usize = 256;
+/// Below this, the approval queue shows the countdown in the warning color.
+const APPROVAL_COUNTDOWN_WARNING_S: i64 = 60;
+/// How long `AliciaUiRuntime::stop_session` waits for a cancelled session to finish.
+pub const DEFAULT_SESSION_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Asks the frontend to draw again, e.g. `egui::Context::request_repaint`.
+pub type RepaintCallback = std::sync::Arc<dyn Fn() + Send + Sync>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CommandLifecycle {
@@ -48,7 +416,28 @@ pub struct TerminalSessionState {
     pub cwd: String,
     pub lifecycle: CommandLifecycle,
     lines: VecDeque<String>,
+    /// Bytes of `lines`, kept up to date for the byte cap.
+    lines_bytes: usize,
     partial_line: String,
+    /// Column (in chars) where the next char lands in `partial_line`; `\r` rewinds it to 0.
+    cursor_column: usize,
+    graphics: GraphicsFilter,
+    inline_images: VecDeque<InlineImage>,
+    wrap_mode: TerminalWrapMode,
+    /// Screen of PTY sessions; the line model above keeps serving search and links.
+    emulator: Option<TerminalEmulator>,
+    /// Set once `cwd` was reported outside every workspace root.
+    outside_workspace: bool,
+    /// How the session was kept offline, when the profile denied it the network.
+    network_isolation: Option<NetworkIsolation>,
+    failure_suggestion: Option<FailureSuggestion>,
+    last_output_at: Option<Instant>,
+    /// Set while the output stops on what looks like a prompt; cleared by the next output.
+    waiting_for_input: bool,
+    command_markers: CommandMarkerTracker,
+    /// Commands the shell delimited with OSC 133 markers, oldest first.
+    shell_commands: VecDeque<ShellCommandRun>,
+    bell: BellDetector,
 }
 
 impl TerminalSessionState {
@@ -59,7 +448,21 @@ impl TerminalSessionState {
             cwd: event.cwd.clone(),
             lifecycle: CommandLifecycle::Running,
             lines: VecDeque::new(),
+            lines_bytes: 0,
             partial_line: String::new(),
+            cursor_column: 0,
+            graphics: GraphicsFilter::default(),
+            inline_images: VecDeque::new(),
+            wrap_mode: TerminalWrapMode::default(),
+            emulator: None,
+            outside_workspace: false,
+            network_isolation: None,
+            failure_suggestion: None,
+            last_output_at: None,
+            waiting_for_input: false,
+            command_markers: CommandMarkerTracker::default(),
+            shell_commands: VecDeque::new(),
+            bell: BellDetector::default(),
         }
     }
 
@@ -70,33 +473,129 @@ impl TerminalSessionState {
             cwd: String::new(),
             lifecycle: CommandLifecycle::Running,
             lines: VecDeque::new(),
+            lines_bytes: 0,
             partial_line: String::new(),
+            cursor_column: 0,
+            graphics: GraphicsFilter::default(),
+            inline_images: VecDeque::new(),
+            wrap_mode: TerminalWrapMode::default(),
+            emulator: None,
+            outside_workspace: false,
+            network_isolation: None,
+            failure_suggestion: None,
+            last_output_at: None,
+            waiting_for_input: false,
+            command_markers: CommandMarkerTracker::default(),
+            shell_commands: VecDeque::new(),
+            bell: BellDetector::default(),
         }
     }
 
-    fn reset_for_started(&mut self, event: &CommandStarted) {
+    fn reset_for_started(&mut self, event: &CommandStarted, limits: ScrollbackLimits) {
         self.command = event.command.clone();
         self.cwd = event.cwd.clone();
         self.lifecycle = CommandLifecycle::Running;
         self.lines.clear();
+        self.lines_bytes = 0;
         self.partial_line.clear();
+        self.cursor_column = 0;
+        self.graphics = GraphicsFilter::default();
+        self.inline_images.clear();
+        self.failure_suggestion = None;
+        self.command_markers = CommandMarkerTracker::default();
+        self.shell_commands.clear();
+        self.bell = BellDetector::default();
+        if let Some(emulator) = &mut self.emulator {
+            let (rows, columns) = emulator.size();
+            *emulator = TerminalEmulator::new(limits.max_lines);
+            emulator.set_size(rows, columns);
+        }
     }
 
-    fn append_output_chunk(&mut self, chunk: &str, max_scrollback_lines: usize) {
-        for ch in chunk.chars() {
-            if ch == '\n' {
-                if self.partial_line.ends_with('\r') {
-                    self.partial_line.pop();
+    /// Returns the new working directory when the chunk reported a `cd` through OSC 7, the
+    /// shell commands it finished through OSC 133 and how many times it rang the bell.
+    fn append_output_chunk(
+        &mut self,
+        chunk: &str,
+        limits: ScrollbackLimits,
+    ) -> (Option<String>, Vec<ShellCommandRun>, usize) {
+        let now = Instant::now();
+        self.last_output_at = Some(now);
+        self.waiting_for_input = false;
+        let filtered = self.graphics.filter(chunk);
+        self.inline_images.extend(filtered.images);
+        while self.inline_images.len() > MAX_INLINE_IMAGES_PER_SESSION {
+            self.inline_images.pop_front();
+        }
+
+        if let Some(emulator) = &mut self.emulator {
+            emulator.process(&filtered.text);
+        }
+
+        let (output, bells) = self.bell.feed(&filtered.text);
+        let (output, reported_cwd) = strip_osc7_cwd(&output);
+        let (output, finished_commands) = self.command_markers.feed(&output, now);
+        self.shell_commands
+            .extend(finished_commands.iter().cloned());
+        while self.shell_commands.len() > MAX_SHELL_COMMANDS_PER_SESSION {
+            self.shell_commands.pop_front();
+        }
+        let mut text = output.as_str();
+        if let Some(end) = CLEAR_SCREEN_SEQUENCES
+            .iter()
+            .filter_map(|sequence| text.rfind(sequence).map(|start| start + sequence.len()))
+            .max()
+        {
+            self.clear();
+            text = text[end..]
+                .strip_prefix(CURSOR_HOME_SEQUENCE)
+                .unwrap_or(&text[end..]);
+        }
+
+        for ch in text.chars() {
+            match ch {
+                '\n' => {
+                    self.lines_bytes += self.partial_line.len();
+                    self.lines.push_back(std::mem::take(&mut self.partial_line));
+                    self.cursor_column = 0;
+                    self.trim_scrollback_to(limits);
                 }
-                self.lines.push_back(std::mem::take(&mut self.partial_line));
-                while self.lines.len() > max_scrollback_lines {
-                    self.lines.pop_front();
+                '\r' => self.cursor_column = 0,
+                _ => {
+                    // Progress bars redraw with `\r`: overwrite in place like a terminal does.
+                    match self.partial_line.char_indices().nth(self.cursor_column) {
+                        Some((byte_index, existing)) => self.partial_line.replace_range(
+                            byte_index..byte_index + existing.len_utf8(),
+                            ch.encode_utf8(&mut [0; 4]),
+                        ),
+                        None => {
+                            if limits.max_bytes.is_none_or(|max_bytes| {
+                                self.partial_line.len() + ch.len_utf8() <= max_bytes
+                            }) {
+                                self.partial_line.push(ch);
+                            }
+                        }
+                    }
+                    self.cursor_column += 1;
                 }
-                continue;
             }
+        }
+        self.trim_scrollback_to(limits);
 
-            self.partial_line.push(ch);
+        let cwd = reported_cwd.filter(|cwd| *cwd != self.cwd);
+        if let Some(cwd) = &cwd {
+            self.cwd.clone_from(cwd);
         }
+        (cwd, finished_commands, bells)
+    }
+
+    /// Empties the visible buffer; the session keeps running.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.lines_bytes = 0;
+        self.partial_line.clear();
+        self.cursor_column = 0;
+        self.inline_images.clear();
     }
 
     pub fn visible_lines(&self) -> Vec<String> {
@@ -111,14 +610,78 @@ impl TerminalSessionState {
         self.visible_lines().join("\n")
     }
 
-    fn trim_scrollback_to(&mut self, max_scrollback_lines: usize) {
-        while self.lines.len() > max_scrollback_lines {
-            self.lines.pop_front();
+    pub fn wrap_mode(&self) -> TerminalWrapMode {
+        self.wrap_mode
+    }
+
+    pub fn emulator(&self) -> Option<&TerminalEmulator> {
+        self.emulator.as_ref()
+    }
+
+    pub fn shell_commands(&self) -> &VecDeque<ShellCommandRun> {
+        &self.shell_commands
+    }
+
+    pub fn outside_workspace(&self) -> bool {
+        self.outside_workspace
+    }
+
+    pub fn network_isolation(&self) -> Option<NetworkIsolation> {
+        self.network_isolation
+    }
+
+    pub fn waiting_for_input(&self) -> bool {
+        self.waiting_for_input
+    }
+
+    /// Next step suggested after the session failed, when the failure was recognized.
+    pub fn failure_suggestion(&self) -> Option<&FailureSuggestion> {
+        self.failure_suggestion.as_ref()
+    }
+
+    /// Images stripped out of the output, oldest first; each left a placeholder line.
+    pub fn inline_images(&self) -> impl Iterator<Item = &InlineImage> {
+        self.inline_images.iter()
+    }
+
+    /// Drops the oldest lines until both limits hold; the line being written counts toward
+    /// the byte cap.
+    fn trim_scrollback_to(&mut self, limits: ScrollbackLimits) {
+        while self.lines.len() > limits.max_lines
+            || limits
+                .max_bytes
+                .is_some_and(|max_bytes| self.scrollback_bytes() > max_bytes)
+        {
+            let Some(line) = self.lines.pop_front() else {
+                break;
+            };
+            self.lines_bytes -= line.len();
+        }
+    }
+
+    fn scrollback_bytes(&self) -> usize {
+        self.lines_bytes + self.partial_line.len()
+    }
+
+    /// Drops the scrollback, leaving a marker line. Returns how many lines were dropped.
+    fn evict_scrollback(&mut self) -> usize {
+        if self.lines.len() == 1
+            && self.lines.front().map(String::as_str) == Some(EVICTED_SCROLLBACK_MARKER)
+        {
+            return 0;
         }
+        let evicted = self.lines.len() + usize::from(!self.partial_line.is_empty());
+        self.lines.clear();
+        self.partial_line.clear();
+        self.cursor_column = 0;
+        self.lines.push_back(EVICTED_SCROLLBACK_MARKER.to_string());
+        self.lines_bytes = EVICTED_SCROLLBACK_MARKER.len();
+        evicted
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ApprovalStatus {
     Pending,
     Approved,
@@ -126,7 +689,8 @@ pub enum ApprovalStatus {
     Expired,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ApprovalItem {
     pub action_id: String,
     pub summary: String,
@@ -135,7 +699,27 @@ pub struct ApprovalItem {
     pub action_kind: Option<ActionKind>,
     pub target: Option<String>,
     pub command: Option<Vec<String>>,
+    /// Command proposed before the reviewer edited it into `command`.
+    pub original_command: Option<Vec<String>>,
     pub impact_files: Vec<String>,
+    pub resolved_sequence: Option<u64>,
+    pub resolved_at_unix_s: Option<i64>,
+    /// Executions started under this approval, checked against `ApprovalValidity::single_use`.
+    pub uses: u32,
+    pub approver: Option<ApproverIdentity>,
+    pub denial_reason: Option<DenialReason>,
+    /// Times this request arrived while pending, its duplicates included.
+    pub occurrences: u32,
+    /// Requests for the same action collapsed into this one; they share its resolution.
+    pub duplicate_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandApprovalLink {
+    pub action_id: String,
+    pub decision: ApprovalDecision,
+    pub event_sequence: Option<u64>,
+    pub approver: Option<ApproverIdentity>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -146,11 +730,26 @@ pub struct ApprovalPrompt {
     pub where_target: Option<String>,
     pub action_kind: Option<ActionKind>,
     pub command: Option<String>,
+    /// Kinds inferred from the command besides running it, e.g. `NetworkAccess` for `curl`.
+    pub command_action_kinds: Vec<ActionKind>,
     pub impact: Option<String>,
     pub expires_at_unix_s: i64,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl ApprovalPrompt {
+    /// Seconds left before the expiry task resolves the approval as expired, zero once due.
+    pub fn seconds_remaining(&self, now_unix_s: i64) -> i64 {
+        self.expires_at_unix_s.saturating_sub(now_unix_s).max(0)
+    }
+
+    /// `seconds_remaining` as `mm:ss`, or `h:mm:ss` past an hour.
+    pub fn countdown(&self, now_unix_s: i64) -> String {
+        format_countdown(self.seconds_remaining(now_unix_s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PatchPreviewState {
     pub action_id: String,
     pub files: Vec<String>,
@@ -158,13 +757,15 @@ pub struct PatchPreviewState {
     pub applied: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PatchFilePreview {
     pub file_path: String,
     pub hunks: Vec<PatchHunkPreview>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PatchHunkPreview {
     pub hunk_id: String,
     pub header: String,
@@ -174,22 +775,80 @@ pub struct PatchHunkPreview {
     pub new_count: usize,
     pub added_lines: usize,
     pub removed_lines: usize,
+    pub lines: Vec<String>,
     pub decision: PatchHunkDecision,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PatchHunkDecision {
     Pending,
     Approved,
     Rejected,
 }
 
+/// What a timeline entry is about, for filtering the timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineKind {
+    Command,
+    Approval,
+    Patch,
+    Audit,
+    Agent,
+    System,
+}
+
+impl TimelineKind {
+    pub const ALL: [Self; 6] = [
+        Self::Command,
+        Self::Approval,
+        Self::Patch,
+        Self::Audit,
+        Self::Agent,
+        Self::System,
+    ];
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TimelineEntry {
     pub sequence: u64,
+    pub kind: TimelineKind,
+    /// The session the entry is about, when it is about one.
+    pub session_id: Option<String>,
     pub summary: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AuditHealth {
+    #[default]
+    NoWrites,
+    Healthy,
+    Failing {
+        consecutive_failures: u64,
+        last_error: String,
+    },
+}
+
+/// Live counters shown in the status bar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusMetrics {
+    pub pending_approvals: usize,
+    pub running_sessions: usize,
+    /// Time the runtime took to drain and apply the last batch of events.
+    pub last_event_latency: Option<Duration>,
+    pub dropped_events: u64,
+    /// Size of the session manager's event channel.
+    pub event_channel_capacity: usize,
+    /// Events lost by each subscriber that fell more than `event_channel_capacity` behind.
+    pub lagged_subscribers: BTreeMap<String, u64>,
+    /// `None` unless the event task applies the events; see
+    /// `AliciaUiRuntime::spawn_event_task`.
+    pub event_queue: Option<EventQueueMetrics>,
+    pub audit_health: AuditHealth,
+    pub unread_errors: usize,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Error)]
 pub enum UiEventStoreError {
     #[error("session `{0}` not found")]
@@ -213,41 +872,84 @@ pub enum UiEventStoreError {
         file_path: String,
         hunk_id: String,
     },
+    #[error("attachment `{path}` is outside the workspace: {reason}")]
+    AttachmentOutsideWorkspace { path: String, reason: String },
+    #[error("attachment `{0}` is not a file")]
+    AttachmentNotAFile(String),
+    #[error("diff for action `{action_id}` file `{file_path}` is malformed: {source}")]
+    MalformedPatchDiff {
+        action_id: String,
+        file_path: String,
+        source: DiffParseError,
+    },
+    #[error("model `{0}` is not registered")]
+    UnknownModel(String),
+    #[error("command of approval `{action_id}` cannot be modified: {reason}")]
+    ApprovalCommandNotEditable { action_id: String, reason: String },
 }
 
 impl UiEventStoreError {
     pub fn beginner_message(&self) -> String {
-        match self {
-            Self::SessionNotFound(_) => beginner_error_message(
-                "Nao encontrei a sessao selecionada.",
-                "Escolha outra sessao ativa ou inicie uma nova sessao.",
-            ),
-            Self::SessionInputNotBound(_) => beginner_error_message(
-                "A sessao ainda nao esta pronta para receber texto.",
-                "Aguarde a sessao iniciar e tente novamente.",
-            ),
-            Self::SessionInputSendFailed { .. } => beginner_error_message(
-                "Nao consegui enviar seu texto para o terminal.",
-                "Confira se a sessao ainda esta ativa e tente de novo.",
-            ),
-            Self::ApprovalNotPending(_) => beginner_error_message(
-                "Essa aprovacao ja foi resolvida.",
-                "Atualize a tela e siga para a proxima aprovacao pendente.",
-            ),
-            Self::PatchPreviewNotFound(_) => beginner_error_message(
-                "Nao encontrei a previa dessa mudanca.",
-                "Gere a previa novamente antes de aprovar ou rejeitar.",
-            ),
-            Self::PatchFileNotFound { .. } => beginner_error_message(
-                "Nao encontrei o arquivo da mudanca selecionada.",
-                "Atualize a previa e tente abrir o arquivo novamente.",
-            ),
-            Self::PatchHunkNotFound { .. } => beginner_error_message(
-                "Nao encontrei o bloco da mudanca selecionada.",
-                "Atualize a previa do diff e escolha o bloco novamente.",
-            ),
+        self.beginner_message_in(ui_language())
+    }
+
+    pub fn error_report(&self) -> ErrorReport {
+        let (code, suggested_actions) = match self {
+            Self::SessionNotFound(_) => ("session_not_found", Vec::new()),
+            Self::SessionInputNotBound(_) => ("session_input_not_bound", vec![ErrorAction::Retry]),
+            Self::SessionInputSendFailed { .. } => {
+                ("session_input_send_failed", vec![ErrorAction::Retry])
+            }
+            Self::ApprovalNotPending(_) => ("approval_not_pending", Vec::new()),
+            Self::PatchPreviewNotFound(_) => ("patch_preview_not_found", Vec::new()),
+            Self::PatchFileNotFound { .. } => ("patch_file_not_found", Vec::new()),
+            Self::PatchHunkNotFound { .. } => ("patch_hunk_not_found", Vec::new()),
+            Self::AttachmentOutsideWorkspace { .. } => ("attachment_outside_workspace", Vec::new()),
+            Self::AttachmentNotAFile(_) => ("attachment_not_a_file", Vec::new()),
+            Self::MalformedPatchDiff { .. } => ("patch_diff_malformed", Vec::new()),
+            Self::UnknownModel(_) => ("unknown_model", Vec::new()),
+            Self::ApprovalCommandNotEditable { .. } => {
+                ("approval_command_not_editable", Vec::new())
+            }
+        };
+        ErrorReport {
+            code,
+            message: self.beginner_message(),
+            suggested_actions,
         }
     }
+
+    pub fn beginner_message_in(&self, language: UiLanguage) -> String {
+        let message = match self {
+            Self::SessionNotFound(_) => BeginnerMessage::SessionNotFound,
+            Self::SessionInputNotBound(_) => BeginnerMessage::SessionInputNotBound,
+            Self::SessionInputSendFailed { .. } => BeginnerMessage::SessionInputSendFailed,
+            Self::ApprovalNotPending(_) => BeginnerMessage::ApprovalNotPending,
+            Self::PatchPreviewNotFound(_) => BeginnerMessage::PatchPreviewNotFound,
+            Self::PatchFileNotFound { .. } => BeginnerMessage::PatchFileNotFound,
+            Self::PatchHunkNotFound { .. } => BeginnerMessage::PatchHunkNotFound,
+            Self::AttachmentOutsideWorkspace { .. } => BeginnerMessage::AttachmentOutsideWorkspace,
+            Self::AttachmentNotAFile(_) => BeginnerMessage::AttachmentNotAFile,
+            Self::MalformedPatchDiff { source, .. } => {
+                return beginner_error_message(
+                    language,
+                    BeginnerMessage::MalformedPatchDiff,
+                    &source.to_string(),
+                );
+            }
+            Self::UnknownModel(model) => {
+                return beginner_error_message(language, BeginnerMessage::UnknownModel, model);
+            }
+            Self::ApprovalCommandNotEditable { reason, .. } => {
+                return beginner_error_message(
+                    language,
+                    BeginnerMessage::ApprovalCommandNotEditable,
+                    reason,
+                );
+            }
+        };
+        beginner_error_message(language, message, "")
+    }
 }
 
 #[derive(Debug, Error)]
@@ -269,63 +971,129 @@ pub enum AliciaUiRuntimeError {
     },
     #[error("command execution blocked for session `{session_id}`: {reason}")]
     CommandBlocked { session_id: String, reason: String },
+    #[error("patch apply blocked for action `{action_id}`: {reason}")]
+    PatchBlocked { action_id: String, reason: String },
+    #[error("{0}")]
+    PatchApplyFailed(#[from] PatchApplyError),
+    #[error(
+        "failed to spawn session `{session_id}` after {attempts} attempts: {}",
+        .failures.join("; ")
+    )]
+    SpawnRetriesExhausted {
+        session_id: String,
+        attempts: u32,
+        failures: Vec<String>,
+    },
     #[error("timed out waiting for session `{session_id}` to finish after cancellation")]
     SessionStopTimeout { session_id: String },
+    #[error("operation on session `{session_id}` was cancelled")]
+    OperationCancelled { session_id: String },
     #[error("failed to persist audit record for session `{session_id}`: {source}")]
     AuditWriteFailed {
         session_id: String,
         #[source]
         source: std::io::Error,
     },
+    #[error("{0}")]
+    AgentFailed(#[from] AgentError),
+    #[error("{0}")]
+    AuditExportFailed(#[from] AuditExportError),
 }
 
 impl AliciaUiRuntimeError {
     pub fn beginner_message(&self) -> String {
-        match self {
+        self.beginner_message_in(ui_language())
+    }
+
+    pub fn error_report(&self) -> ErrorReport {
+        let (code, suggested_actions) = match self {
             Self::SessionManager(error) => match error {
-                SessionManagerError::SessionAlreadyExists(_) => beginner_error_message(
-                    "Ja existe uma sessao com esse identificador.",
-                    "Use outro identificador de sessao e tente iniciar novamente.",
-                ),
-                SessionManagerError::SessionNotFound(_) => beginner_error_message(
-                    "Nao encontrei a sessao que voce tentou usar.",
-                    "Confirme o identificador da sessao ou inicie uma nova sessao.",
-                ),
-                SessionManagerError::PtyUnavailable => beginner_error_message(
-                    "Este ambiente nao suporta terminal PTY.",
-                    "Inicie a sessao no modo pipe.",
-                ),
-                SessionManagerError::SpawnFailed { .. } => beginner_error_message(
-                    "Nao consegui iniciar a sessao.",
-                    "Confirme o comando e o diretorio de trabalho antes de tentar de novo.",
-                ),
+                SessionManagerError::SessionAlreadyExists(_) => {
+                    ("session_already_exists", Vec::new())
+                }
+                SessionManagerError::SessionNotFound(_) => ("session_not_found", Vec::new()),
+                SessionManagerError::PtyUnavailable => {
+                    ("pty_unavailable", vec![ErrorAction::OpenSettings])
+                }
+                SessionManagerError::SpawnFailed { .. } => {
+                    ("spawn_failed", vec![ErrorAction::Retry])
+                }
+                SessionManagerError::ResizeFailed { .. } => ("session_resize_failed", Vec::new()),
             },
-            Self::ResolveProfileFailed { .. } => beginner_error_message(
-                "Nao consegui carregar a politica efetiva do projeto.",
-                "Revise o arquivo .codex/alicia-policy.toml e tente novamente.",
-            ),
-            Self::WorkspaceGuardBlocked { .. } => beginner_error_message(
-                "A sessao foi bloqueada por tentar usar caminho fora do workspace.",
-                "Use um diretorio dentro do workspace atual.",
-            ),
-            Self::CommandBlocked { reason, .. } => beginner_error_message(
-                &format!("A execucao foi bloqueada pela policy: {reason}"),
-                "Aprove explicitamente a acao ou ajuste o perfil de permissao.",
-            ),
-            Self::SessionStopTimeout { .. } => beginner_error_message(
-                "A sessao demorou demais para encerrar.",
-                "Tente cancelar novamente ou finalize o processo manualmente no sistema.",
-            ),
-            Self::AuditWriteFailed { .. } => beginner_error_message(
-                "A tarefa foi encerrada, mas nao consegui salvar o log de auditoria.",
-                "Verifique permissoes de escrita do arquivo de auditoria e tente novamente.",
-            ),
+            Self::ResolveProfileFailed { .. } => {
+                ("resolve_profile_failed", vec![ErrorAction::OpenSettings])
+            }
+            Self::WorkspaceGuardBlocked { .. } => {
+                ("workspace_guard_blocked", vec![ErrorAction::OpenSettings])
+            }
+            Self::CommandBlocked { .. } => ("command_blocked", vec![ErrorAction::OpenSettings]),
+            Self::PatchBlocked { .. } => ("patch_blocked", vec![ErrorAction::OpenSettings]),
+            Self::PatchApplyFailed(_) => ("patch_apply_failed", Vec::new()),
+            Self::SpawnRetriesExhausted { .. } => {
+                ("spawn_retries_exhausted", vec![ErrorAction::Retry])
+            }
+            Self::SessionStopTimeout { .. } => ("session_stop_timeout", vec![ErrorAction::Retry]),
+            Self::OperationCancelled { .. } => ("operation_cancelled", vec![ErrorAction::Retry]),
+            Self::AuditWriteFailed { .. } => {
+                ("audit_write_failed", vec![ErrorAction::OpenSettings])
+            }
+            Self::AgentFailed(_) => ("agent_failed", vec![ErrorAction::Retry]),
+            Self::AuditExportFailed(_) => ("audit_export_failed", Vec::new()),
+        };
+        ErrorReport {
+            code,
+            message: self.beginner_message(),
+            suggested_actions,
         }
     }
-}
 
-fn beginner_error_message(problem: &str, next_step: &str) -> String {
-    format!("{problem} Proximo passo: {next_step}")
+    pub fn beginner_message_in(&self, language: UiLanguage) -> String {
+        let message = match self {
+            Self::SessionManager(error) => match error {
+                SessionManagerError::SessionAlreadyExists(_) => {
+                    BeginnerMessage::SessionAlreadyExists
+                }
+                SessionManagerError::SessionNotFound(_) => BeginnerMessage::RuntimeSessionNotFound,
+                SessionManagerError::PtyUnavailable => BeginnerMessage::PtyUnavailable,
+                SessionManagerError::SpawnFailed { .. } => BeginnerMessage::SpawnFailed,
+                SessionManagerError::ResizeFailed { .. } => BeginnerMessage::ResizeFailed,
+            },
+            Self::ResolveProfileFailed { .. } => BeginnerMessage::ResolveProfileFailed,
+            Self::WorkspaceGuardBlocked { .. } => BeginnerMessage::WorkspaceGuardBlocked,
+            Self::CommandBlocked { reason, .. } => {
+                return beginner_error_message(language, BeginnerMessage::CommandBlocked, reason);
+            }
+            Self::PatchBlocked { reason, .. } => {
+                return beginner_error_message(language, BeginnerMessage::PatchBlocked, reason);
+            }
+            Self::PatchApplyFailed(error) => {
+                return beginner_error_message(
+                    language,
+                    BeginnerMessage::PatchApplyFailed,
+                    &error.to_string(),
+                );
+            }
+            Self::SpawnRetriesExhausted { .. } => BeginnerMessage::SpawnRetriesExhausted,
+            Self::SessionStopTimeout { .. } => BeginnerMessage::SessionStopTimeout,
+            Self::OperationCancelled { .. } => BeginnerMessage::OperationCancelled,
+            Self::AuditWriteFailed { .. } => BeginnerMessage::AuditWriteFailed,
+            Self::AgentFailed(error) => {
+                return beginner_error_message(
+                    language,
+                    BeginnerMessage::AgentFailed,
+                    &error.to_string(),
+                );
+            }
+            Self::AuditExportFailed(error) => {
+                return beginner_error_message(
+                    language,
+                    BeginnerMessage::AuditExportFailed,
+                    &error.to_string(),
+                );
+            }
+        };
+        beginner_error_message(language, message, "")
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -334,49 +1102,207 @@ struct ActionContext {
     target: String,
 }
 
+/// A time-boxed escalation to `FullAccess` and the profile it falls back to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TemporaryFullAccess {
+    previous: NamedProfile,
+    previous_pinned: bool,
+    expires_at_unix_s: i64,
+}
+
 #[derive(Debug)]
 pub struct UiEventStore {
     events: Vec<IpcMessage>,
+    /// Where pushed events are also written, so a later run can `replay_journal` them.
+    event_journal: Option<EventJournal>,
     timeline: Vec<TimelineEntry>,
     next_sequence: u64,
-    permission_profile: PermissionProfile,
+    profile: NamedProfile,
+    /// Set once the profile was picked on the user's behalf, through the selector or the
+    /// runtime; project policies no longer replace it when sessions start.
+    profile_pinned: bool,
     sessions: HashMap<String, TerminalSessionState>,
     session_order: Vec<String>,
+    /// Accent of each session, kept for the life of the store.
+    session_colors: HashMap<String, egui::Color32>,
     active_session_id: Option<String>,
     session_input_writers: HashMap<String, mpsc::Sender<Vec<u8>>>,
     approvals: HashMap<String, ApprovalItem>,
     pending_approval_ids: VecDeque<String>,
+    resolved_approval_ids: Vec<String>,
+    /// `ApprovalResolved` messages of resolutions made here, whether in the view, over
+    /// JSON-RPC/REST or by expiry, until the runtime broadcasts them to IPC clients.
+    unpublished_resolutions: Vec<IpcMessage>,
+    /// Sessions an IPC client announced itself, the only ones clients may report on.
+    ipc_session_ids: HashSet<String>,
     action_contexts: HashMap<String, ActionContext>,
     approval_commands: HashMap<String, Vec<String>>,
     patch_previews: HashMap<String, PatchPreviewState>,
     audit_records: Vec<AuditRecord>,
-    max_scrollback_lines: usize,
+    unpersisted_audit_records: Vec<AuditRecord>,
+    approver_identity: Option<ApproverIdentity>,
+    error_center: ErrorCenter,
+    chat: ChatStore,
+    draft_attachments: Vec<ContextAttachment>,
+    /// Submitted prompts the runtime has not handed to the agent yet.
+    agent_prompt_ids: Vec<u64>,
+    token_usage: TokenUsageLedger,
+    model_pricing: BTreeMap<String, ModelPricing>,
+    /// Cost of the turns of priced models.
+    total_cost_usd: f64,
+    budget_limit_usd: Option<f64>,
+    available_models: Vec<ModelDescriptor>,
+    selected_model: Option<String>,
+    slash_commands: SlashCommandRegistry,
+    /// Commands of the sessions asked for with `/session new`; empty means the default shell.
+    requested_sessions: Vec<Vec<String>>,
+    /// Patches whose approved hunks the view asked to write to the workspace.
+    requested_hunk_applies: Vec<String>,
+    requested_audit_exports: Vec<AuditExporter>,
+    /// When set, requested sessions wait in `staged_sessions` for their environment to be
+    /// reviewed instead of starting right away.
+    review_session_env: bool,
+    staged_sessions: Vec<SessionStartRequest>,
+    /// Staged sessions the user launched, for [`AliciaUiRuntime::start_staged_sessions`].
+    launched_sessions: Vec<SessionStartRequest>,
+    /// Working directory picked for new sessions; `None` is the workspace root.
+    session_cwd: Option<PathBuf>,
+    /// Recent working directories of the workspace, published by the runtime.
+    recent_dirs: Vec<PathBuf>,
+    /// Root path rules of the profile are relative to, published by the runtime; empty when
+    /// no runtime drives the store.
+    workspace_root: PathBuf,
+    /// Last `AliciaUiRuntime::health` check; `None` when no runtime drives the store.
+    runtime_health: Option<RuntimeHealth>,
+    /// Approvals the workspace remembers across runs, published by the runtime.
+    remembered_approvals: RememberedApprovals,
+    /// Set when `remembered_approvals` changed since the runtime last saved them.
+    remembered_approvals_changed: bool,
+    /// Rules from "always allow" with the approval they came from, for
+    /// [`AliciaUiRuntime::save_requested_approval_rules`].
+    requested_approval_rules: Vec<(String, ApprovalRule)>,
+    /// Profile picked in the selector, for [`AliciaUiRuntime::apply_requested_profile_change`].
+    requested_profile: Option<PermissionProfile>,
+    /// Set by the panic switch; `pump_agent` leaves prompts queued until it is cleared, and
+    /// the runtime refuses to start sessions or apply patches meanwhile.
+    agent_paused: bool,
+    temporary_full_access: Option<TemporaryFullAccess>,
+    cwd_changes: Vec<(String, String)>,
+    /// PTY window sizes asked for by the terminal panel, for
+    /// [`AliciaUiRuntime::resize_requested_sessions`].
+    requested_resizes: Vec<(String, u16, u16)>,
+    command_stats: BTreeMap<String, CommandStats>,
+    /// When each pending approval was requested, to measure how long it waits.
+    approval_requested_at: HashMap<String, Instant>,
+    first_approval_requested_at: Option<Instant>,
+    /// When the distinct approval requests of the last minute were queued.
+    approval_request_times: VecDeque<Instant>,
+    approval_latency: BTreeMap<Option<ActionKind>, ApprovalLatencyStats>,
+    last_event_latency: Option<Duration>,
+    dropped_events: u64,
+    event_channel_capacity: usize,
+    lagged_subscribers: BTreeMap<String, u64>,
+    event_queue: Option<EventQueueMetrics>,
+    events_bytes: usize,
+    memory_evictions: MemoryEvictions,
+    audit_health: AuditHealth,
+    notification_settings: NotificationSettings,
+    pending_notifications: Vec<DesktopNotification>,
+    /// Long command thresholds set for single sessions, over the global one.
+    session_long_command_thresholds: HashMap<String, Duration>,
+    /// Scrollback caps that replace the configured ones for some sessions.
+    session_scrollback_limits: HashMap<String, ScrollbackLimits>,
+    attention_requested: bool,
+    alert_settings: AlertSettings,
+    pending_alerts: Vec<Alert>,
+    config: StoreConfig,
+    approval_validity: BTreeMap<ActionKind, ApprovalValidity>,
 }
 
 impl Default for UiEventStore {
     fn default() -> Self {
-        Self::new(DEFAULT_SCROLLBACK_LINES)
+        Self::new(StoreConfig::default())
     }
 }
 
 impl UiEventStore {
-    pub fn new(max_scrollback_lines: usize) -> Self {
+    pub fn new(config: StoreConfig) -> Self {
         Self {
             events: Vec::new(),
+            event_journal: None,
             timeline: Vec::new(),
             next_sequence: 0,
-            permission_profile: PermissionProfile::ReadWriteWithApproval,
+            profile: NamedProfile::builtin(PermissionProfile::ReadWriteWithApproval),
+            profile_pinned: false,
             sessions: HashMap::new(),
             session_order: Vec::new(),
+            session_colors: HashMap::new(),
             active_session_id: None,
             session_input_writers: HashMap::new(),
             approvals: HashMap::new(),
             pending_approval_ids: VecDeque::new(),
+            resolved_approval_ids: Vec::new(),
+            unpublished_resolutions: Vec::new(),
+            ipc_session_ids: HashSet::new(),
             action_contexts: HashMap::new(),
             approval_commands: HashMap::new(),
             patch_previews: HashMap::new(),
             audit_records: Vec::new(),
-            max_scrollback_lines: max_scrollback_lines.max(1),
+            unpersisted_audit_records: Vec::new(),
+            approver_identity: ApproverIdentity::os_user(),
+            error_center: ErrorCenter::default(),
+            chat: ChatStore::default(),
+            draft_attachments: Vec::new(),
+            agent_prompt_ids: Vec::new(),
+            token_usage: TokenUsageLedger::default(),
+            model_pricing: BTreeMap::new(),
+            total_cost_usd: 0.0,
+            budget_limit_usd: None,
+            available_models: Vec::new(),
+            selected_model: None,
+            slash_commands: SlashCommandRegistry::default(),
+            requested_sessions: Vec::new(),
+            requested_hunk_applies: Vec::new(),
+            requested_audit_exports: Vec::new(),
+            review_session_env: false,
+            staged_sessions: Vec::new(),
+            launched_sessions: Vec::new(),
+            session_cwd: None,
+            recent_dirs: Vec::new(),
+            runtime_health: None,
+            remembered_approvals: RememberedApprovals::default(),
+            remembered_approvals_changed: false,
+            workspace_root: PathBuf::new(),
+            requested_approval_rules: Vec::new(),
+            requested_profile: None,
+            agent_paused: false,
+            temporary_full_access: None,
+            cwd_changes: Vec::new(),
+            requested_resizes: Vec::new(),
+            command_stats: BTreeMap::new(),
+            approval_requested_at: HashMap::new(),
+            first_approval_requested_at: None,
+            approval_request_times: VecDeque::new(),
+            approval_latency: BTreeMap::new(),
+            last_event_latency: None,
+            dropped_events: 0,
+            event_channel_capacity: DEFAULT_SESSION_EVENTS_CAPACITY,
+            lagged_subscribers: BTreeMap::new(),
+            event_queue: None,
+            events_bytes: 0,
+            memory_evictions: MemoryEvictions::default(),
+            audit_health: AuditHealth::default(),
+            notification_settings: NotificationSettings::default(),
+            pending_notifications: Vec::new(),
+            session_long_command_thresholds: HashMap::new(),
+            session_scrollback_limits: HashMap::new(),
+            attention_requested: false,
+            alert_settings: AlertSettings::default(),
+            pending_alerts: Vec::new(),
+            config: config
+                .with_max_scrollback_lines(config.max_scrollback_lines)
+                .with_max_scrollback_bytes(config.max_scrollback_bytes),
+            approval_validity: BTreeMap::new(),
         }
     }
 
@@ -394,11 +1320,15 @@ impl UiEventStore {
                 format!("approval_requested {} {}", event.action_id, event.summary)
             }
             IpcEvent::ApprovalResolved(event) => {
-                format!(
+                let mut summary = format!(
                     "approval_resolved {} {}",
                     event.action_id,
                     approval_resolution_name(event.resolution)
-                )
+                );
+                if let Some(reason) = &event.reason {
+                    summary.push_str(&format!(" {}", denial_reason_text(reason)));
+                }
+                summary
             }
             IpcEvent::CommandStarted(event) => {
                 let command = if event.command.is_empty() {
@@ -409,11 +1339,7 @@ impl UiEventStore {
                 format!("command_started {} {}", event.command_id, command)
             }
             IpcEvent::CommandOutputChunk(event) => {
-                let mut preview: String =
-                    event.chunk.chars().take(OUTPUT_PREVIEW_MAX_CHARS).collect();
-                if event.chunk.chars().count() > OUTPUT_PREVIEW_MAX_CHARS {
-                    preview.push_str("...");
-                }
+                let preview = self.config.preview(&event.chunk);
                 format!(
                     "command_output_chunk {} {} {}",
                     event.command_id,
@@ -441,31 +1367,129 @@ impl UiEventStore {
                     event.files.len()
                 )
             }
+            IpcEvent::NetworkAccessBlocked(event) => {
+                format!(
+                    "network_access_blocked {} {}",
+                    event.command_id, event.detail
+                )
+            }
+            IpcEvent::AgentMessageDelta(event) => {
+                format!(
+                    "agent_message_delta {} chars={}",
+                    event.message_id,
+                    event.delta.chars().count()
+                )
+            }
+            IpcEvent::AgentMessageComplete(event) => {
+                format!("agent_message_complete {}", event.message_id)
+            }
+            IpcEvent::ToolCallStarted(event) => {
+                format!(
+                    "tool_call_started {} {} {}",
+                    event.call_id, event.name, event.arguments
+                )
+            }
+            IpcEvent::ToolCallFinished(event) => {
+                format!(
+                    "tool_call_finished {} status={} duration_ms={}",
+                    event.call_id,
+                    tool_call_status_name(event.status),
+                    event.duration_ms
+                )
+            }
         };
 
-        self.timeline.push(TimelineEntry {
-            sequence: self.next_sequence,
-            summary,
-        });
-        self.next_sequence = self.next_sequence.saturating_add(1);
+        let (kind, session_id) = timeline_kind_and_session(&message.event);
+        let sequence = self.push_timeline(kind, session_id, summary);
 
-        self.apply_event(&message.event);
+        self.apply_event(&message.event, sequence);
+        if let Some(event_journal) = self.event_journal.as_mut()
+            && let Err(error) = event_journal.append(&message)
+        {
+            self.event_journal = None;
+            self.record_error(
+                ErrorReport {
+                    code: "event_journal_write_failed",
+                    message: beginner_error_message(
+                        ui_language(),
+                        BeginnerMessage::EventJournalWriteFailed,
+                        &error.to_string(),
+                    ),
+                    suggested_actions: Vec::new(),
+                },
+                unix_now_s(),
+            );
+        }
+        self.events_bytes = self
+            .events_bytes
+            .saturating_add(estimated_message_bytes(&message));
         self.events.push(message);
+        self.trim_timeline();
+    }
+
+    fn trim_timeline(&mut self) {
+        let excess = self
+            .timeline
+            .len()
+            .saturating_sub(self.config.max_timeline_entries);
+        if excess > 0 {
+            self.timeline.drain(..excess);
+        }
     }
 
-    fn apply_event(&mut self, event: &IpcEvent) {
+    fn apply_event(&mut self, event: &IpcEvent, sequence: u64) {
         match event {
             IpcEvent::ActionProposed(event) => self.apply_action_proposed(event),
             IpcEvent::ApprovalRequested(event) => self.apply_approval_requested(event),
-            IpcEvent::ApprovalResolved(event) => self.apply_approval_resolved(event),
+            IpcEvent::ApprovalResolved(event) => self.apply_approval_resolved(event, sequence),
             IpcEvent::CommandStarted(event) => self.apply_command_started(event),
             IpcEvent::CommandOutputChunk(event) => self.apply_command_output_chunk(event),
             IpcEvent::CommandFinished(event) => self.apply_command_finished(event),
             IpcEvent::PatchPreviewReady(event) => self.apply_patch_preview_ready(event),
-            IpcEvent::PatchApplied(event) => self.apply_patch_applied(event),
+            IpcEvent::PatchApplied(event) => self.apply_patch_applied(event, sequence),
+            IpcEvent::NetworkAccessBlocked(event) => {
+                self.apply_network_access_blocked(event, sequence);
+            }
+            IpcEvent::AgentMessageDelta(event) => self.apply_agent_message_delta(event),
+            IpcEvent::AgentMessageComplete(event) => self.apply_agent_message_complete(event),
+            IpcEvent::ToolCallStarted(event) => self.apply_tool_call_started(event),
+            IpcEvent::ToolCallFinished(event) => self.apply_tool_call_finished(event),
         }
     }
 
+    fn apply_agent_message_delta(&mut self, event: &AgentMessageDelta) {
+        self.chat.append_delta(&event.message_id, &event.delta);
+    }
+
+    fn apply_agent_message_complete(&mut self, event: &AgentMessageComplete) {
+        self.chat.complete_stream(&event.message_id);
+    }
+
+    fn apply_tool_call_started(&mut self, event: &ToolCallStarted) {
+        self.chat.start_tool_call(
+            &event.message_id,
+            ChatToolCall {
+                call_id: event.call_id.clone(),
+                name: event.name.clone(),
+                arguments: event.arguments.clone(),
+                session_id: event.session_id.clone(),
+                action_id: event.action_id.clone(),
+                status: None,
+                duration_ms: None,
+            },
+        );
+    }
+
+    fn apply_tool_call_finished(&mut self, event: &ToolCallFinished) {
+        self.chat
+            .finish_tool_call(&event.call_id, event.status, event.duration_ms);
+    }
+
+    /// Whether an agent message is still being streamed, for the typing indicator.
+    pub fn is_agent_typing(&self) -> bool {
+        self.chat.is_streaming()
+    }
+
     fn apply_action_proposed(&mut self, event: &ActionProposed) {
         self.action_contexts.insert(
             event.action_id.clone(),
@@ -488,6 +1512,10 @@ impl UiEventStore {
             .patch_previews
             .get(&event.action_id)
             .map_or_else(Vec::new, |preview| preview.files.clone());
+        let already_pending = self
+            .pending_approval_ids
+            .iter()
+            .any(|id| id == &event.action_id);
 
         let entry = self
             .approvals
@@ -500,12 +1528,29 @@ impl UiEventStore {
                 action_kind: action_context.as_ref().map(|ctx| ctx.action_kind),
                 target: action_context.as_ref().map(|ctx| ctx.target.clone()),
                 command: approval_command.clone(),
+                original_command: None,
                 impact_files: impact_files.clone(),
+                resolved_sequence: None,
+                resolved_at_unix_s: None,
+                uses: 0,
+                approver: None,
+                denial_reason: None,
+                occurrences: 0,
+                duplicate_ids: Vec::new(),
             });
 
+        if already_pending {
+            entry.occurrences = entry.occurrences.saturating_add(1);
+        } else {
+            entry.occurrences = 1;
+            entry.duplicate_ids.clear();
+        }
         entry.summary = event.summary.clone();
         entry.expires_at_unix_s = event.expires_at_unix_s;
         entry.status = ApprovalStatus::Pending;
+        entry.resolved_at_unix_s = None;
+        entry.uses = 0;
+        entry.denial_reason = None;
 
         if let Some(action_context) = action_context {
             entry.action_kind = Some(action_context.action_kind);
@@ -520,30 +1565,167 @@ impl UiEventStore {
             entry.impact_files = impact_files;
         }
 
+        let now = Instant::now();
+        if !already_pending {
+            if let Some(primary_id) = self.pending_duplicate_of(&event.action_id) {
+                self.collapse_duplicate_approval(&event.action_id, &primary_id);
+                return;
+            }
+            if self.approval_rate_limited(now) {
+                self.expire_rate_limited_approval(&event.action_id);
+                return;
+            }
+            self.approval_request_times.push_back(now);
+        }
+        self.approval_requested_at
+            .insert(event.action_id.clone(), now);
+        self.first_approval_requested_at.get_or_insert(now);
+
         if !self
             .pending_approval_ids
             .iter()
             .any(|id| id == &event.action_id)
         {
             self.pending_approval_ids.push_back(event.action_id.clone());
+            self.queue_notification(
+                NotificationKind::ApprovalRequested,
+                "Aprovacao pendente",
+                event.summary.clone(),
+            );
+            self.queue_alert(AlertEvent::ApprovalRequested, &event.action_id);
         }
     }
 
-    fn apply_approval_resolved(&mut self, event: &ApprovalResolved) {
-        if let Some(approval) = self.approvals.get_mut(&event.action_id) {
-            approval.status = match event.resolution {
-                ApprovalResolution::Approved => ApprovalStatus::Approved,
-                ApprovalResolution::Denied => ApprovalStatus::Denied,
-                ApprovalResolution::Expired => ApprovalStatus::Expired,
+    /// The pending request asking for the same action as `action_id`: same kind, target and
+    /// command. Requests without a target or command are never considered duplicates.
+    fn pending_duplicate_of(&self, action_id: &str) -> Option<String> {
+        let context = self.action_contexts.get(action_id);
+        let target = context.map(|context| context.target.as_str());
+        let command = self.approval_commands.get(action_id).map(Vec::as_slice);
+        if target.is_none() && command.is_none() {
+            return None;
+        }
+        let action_kind = context.map(|context| context.action_kind);
+        self.pending_approval_ids
+            .iter()
+            .filter(|id| id.as_str() != action_id)
+            .find(|id| {
+                self.approvals.get(id.as_str()).is_some_and(|approval| {
+                    approval.action_kind == action_kind
+                        && approval.target.as_deref() == target
+                        && approval.command.as_deref() == command
+                })
+            })
+            .cloned()
+    }
+
+    fn collapse_duplicate_approval(&mut self, action_id: &str, primary_id: &str) {
+        let Some(primary) = self.approvals.get_mut(primary_id) else {
+            return;
+        };
+        primary.occurrences = primary.occurrences.saturating_add(1);
+        if !primary.duplicate_ids.iter().any(|id| id == action_id) {
+            primary.duplicate_ids.push(action_id.to_string());
+        }
+        let occurrences = primary.occurrences;
+        self.push_timeline(
+            TimelineKind::Approval,
+            None,
+            format!("approval_duplicate {action_id} of={primary_id} occurrences={occurrences}"),
+        );
+    }
+
+    /// Whether `max_approval_requests_per_minute` distinct requests were already queued in
+    /// the minute before `now`.
+    fn approval_rate_limited(&mut self, now: Instant) -> bool {
+        while self
+            .approval_request_times
+            .front()
+            .is_some_and(|queued_at| {
+                now.saturating_duration_since(*queued_at) >= APPROVAL_RATE_WINDOW
+            })
+        {
+            self.approval_request_times.pop_front();
+        }
+        self.approval_request_times.len() >= self.config.max_approval_requests_per_minute
+    }
+
+    fn expire_rate_limited_approval(&mut self, action_id: &str) {
+        if let Some(approval) = self.approvals.get_mut(action_id) {
+            approval.status = ApprovalStatus::Expired;
+            approval.resolved_at_unix_s = Some(unix_now_s());
+        }
+        self.push_timeline(
+            TimelineKind::Approval,
+            None,
+            format!(
+                "approval_rate_limited {action_id} limit={}/min",
+                self.config.max_approval_requests_per_minute
+            ),
+        );
+    }
+
+    fn apply_approval_resolved(&mut self, event: &ApprovalResolved, sequence: u64) {
+        let duplicate_ids = self
+            .approvals
+            .get(&event.action_id)
+            .map_or_else(Vec::new, |approval| approval.duplicate_ids.clone());
+        for duplicate_id in duplicate_ids {
+            let Some(primary) = self.approvals.get(&event.action_id).cloned() else {
+                break;
+            };
+            let Some(duplicate) = self
+                .approvals
+                .get_mut(&duplicate_id)
+                .filter(|duplicate| duplicate.status == ApprovalStatus::Pending)
+            else {
+                continue;
             };
+            duplicate.command.clone_from(&primary.command);
+            duplicate
+                .original_command
+                .clone_from(&primary.original_command);
+            duplicate.status = approval_status_for(event.resolution);
+            duplicate.resolved_sequence = Some(sequence);
+            duplicate.resolved_at_unix_s = Some(unix_now_s());
+            duplicate.approver = event.approver.clone();
+            duplicate.denial_reason = event.reason.clone();
+            self.resolved_approval_ids.retain(|id| id != &duplicate_id);
+            self.resolved_approval_ids.push(duplicate_id);
+        }
+
+        if let Some(approval) = self.approvals.get_mut(&event.action_id) {
+            approval.status = approval_status_for(event.resolution);
+            approval.resolved_sequence = Some(sequence);
+            approval.resolved_at_unix_s = Some(unix_now_s());
+            approval.approver = event.approver.clone();
+            approval.denial_reason = event.reason.clone();
+            self.resolved_approval_ids
+                .retain(|id| id != &event.action_id);
+            self.resolved_approval_ids.push(event.action_id.clone());
+            if let Some(requested_at) = self.approval_requested_at.remove(&event.action_id) {
+                self.approval_latency
+                    .entry(approval.action_kind)
+                    .or_insert_with(|| ApprovalLatencyStats::new(approval.action_kind))
+                    .record(event.resolution, requested_at.elapsed());
+            }
         }
 
         self.remove_pending_approval(&event.action_id);
     }
 
+    fn add_session_to_order(&mut self, session_id: &str) {
+        self.session_order.push(session_id.to_string());
+        if !self.session_colors.contains_key(session_id) {
+            let color = pick_session_color(session_id, self.session_colors.values());
+            self.session_colors.insert(session_id.to_string(), color);
+        }
+    }
+
     fn apply_command_started(&mut self, event: &CommandStarted) {
+        let limits = self.scrollback_limits(&event.command_id);
         if let Some(session) = self.sessions.get_mut(&event.command_id) {
-            session.reset_for_started(event);
+            session.reset_for_started(event, limits);
         } else {
             self.sessions.insert(
                 event.command_id.clone(),
@@ -552,7 +1734,7 @@ impl UiEventStore {
         }
 
         if !self.session_order.iter().any(|id| id == &event.command_id) {
-            self.session_order.push(event.command_id.clone());
+            self.add_session_to_order(&event.command_id);
         }
 
         if self.active_session_id.is_none() {
@@ -566,34 +1748,249 @@ impl UiEventStore {
                 event.command_id.clone(),
                 TerminalSessionState::pending_session(event.command_id.clone()),
             );
-            self.session_order.push(event.command_id.clone());
+            self.add_session_to_order(&event.command_id);
             if self.active_session_id.is_none() {
                 self.active_session_id = Some(event.command_id.clone());
             }
         }
 
-        if let Some(session) = self.sessions.get_mut(&event.command_id) {
-            session.append_output_chunk(&event.chunk, self.max_scrollback_lines);
+        let limits = self.scrollback_limits(&event.command_id);
+        let Some((cwd, finished_commands, bells)) = self
+            .sessions
+            .get_mut(&event.command_id)
+            .map(|session| session.append_output_chunk(&event.chunk, limits))
+        else {
+            return;
+        };
+        for run in finished_commands {
+            let exit_code = run
+                .exit_code
+                .map_or_else(|| "?".to_string(), |code| code.to_string());
+            self.push_timeline(
+                TimelineKind::Command,
+                Some(event.command_id.as_str()),
+                format!(
+                    "shell_command_finished {} exit_code={exit_code} duration_ms={} {}",
+                    event.command_id, run.duration_ms, run.command
+                ),
+            );
+            if let Some(exit_code) = run.exit_code
+                && !run.command.is_empty()
+            {
+                self.command_stats
+                    .entry(run.command.clone())
+                    .or_insert_with(|| CommandStats::new(run.command.clone()))
+                    .record(exit_code, run.duration_ms);
+            }
         }
-    }
-
-    fn apply_command_finished(&mut self, event: &CommandFinished) {
-        if !self.sessions.contains_key(&event.command_id) {
-            self.sessions.insert(
-                event.command_id.clone(),
-                TerminalSessionState::pending_session(event.command_id.clone()),
+        if bells > 0 {
+            self.queue_alert(AlertEvent::Bell, &event.command_id);
+        }
+        if let Some(cwd) = cwd {
+            self.push_timeline(
+                TimelineKind::Command,
+                Some(event.command_id.as_str()),
+                format!("session_cwd_changed {} {cwd}", event.command_id),
             );
-            self.session_order.push(event.command_id.clone());
+            self.cwd_changes.push((event.command_id.clone(), cwd));
         }
+    }
 
-        if let Some(session) = self.sessions.get_mut(&event.command_id) {
-            session.lifecycle = CommandLifecycle::Finished {
-                exit_code: event.exit_code,
-                duration_ms: event.duration_ms,
+    /// Flags running sessions whose output stopped on a prompt at least
+    /// `input_prompt_idle` before `now`, notifying once per prompt. Returns the sessions
+    /// flagged by this call.
+    pub fn detect_input_prompts(&mut self, now: Instant) -> Vec<String> {
+        let idle = self.notification_settings.input_prompt_idle;
+        let mut flagged = Vec::new();
+        for session_id in &self.session_order {
+            let Some(session) = self.sessions.get_mut(session_id) else {
+                continue;
             };
+            if session.waiting_for_input
+                || session.lifecycle != CommandLifecycle::Running
+                || !session
+                    .last_output_at
+                    .is_some_and(|last| now.saturating_duration_since(last) >= idle)
+            {
+                continue;
+            }
+            let Some(prompt) = session.visible_lines().pop() else {
+                continue;
+            };
+            if looks_like_input_prompt(&prompt) {
+                session.waiting_for_input = true;
+                flagged.push((session_id.clone(), prompt.trim_end().to_string()));
+            }
         }
-    }
-
+        for (session_id, prompt) in &flagged {
+            self.push_timeline(
+                TimelineKind::Command,
+                Some(session_id.as_str()),
+                format!("session_waiting_input {session_id} {prompt}"),
+            );
+            self.queue_notification(
+                NotificationKind::InputRequested,
+                "Sessao aguardando entrada",
+                format!("{session_id}: {prompt}"),
+            );
+        }
+        flagged
+            .into_iter()
+            .map(|(session_id, _)| session_id)
+            .collect()
+    }
+
+    /// Per command line, most run first.
+    /// Latency percentiles per action kind and throughput of the approvals seen so far.
+    pub fn approval_metrics(&self) -> ApprovalMetrics {
+        let pending_waits = self
+            .pending_approval_ids
+            .iter()
+            .filter_map(|action_id| self.approval_requested_at.get(action_id))
+            .map(Instant::elapsed);
+        let resolved: u64 = self
+            .approval_latency
+            .values()
+            .map(ApprovalLatencyStats::resolved)
+            .sum();
+        let resolved_per_hour = self
+            .first_approval_requested_at
+            .map(|first| first.elapsed().as_secs_f64() / 3_600.0)
+            .filter(|hours| *hours > 0.0)
+            .map(|hours| resolved as f64 / hours);
+        ApprovalMetrics {
+            by_kind: self.approval_latency.values().cloned().collect(),
+            pending: self.pending_approval_ids.len(),
+            oldest_pending_wait: pending_waits.max(),
+            resolved_per_hour,
+        }
+    }
+
+    pub fn command_stats(&self) -> Vec<&CommandStats> {
+        let mut stats: Vec<&CommandStats> = self.command_stats.values().collect();
+        stats.sort_by(|left, right| right.runs.cmp(&left.runs));
+        stats
+    }
+
+    /// Sessions whose working directory changed since the last call, with the new directory.
+    pub fn take_cwd_changes(&mut self) -> Vec<(String, String)> {
+        std::mem::take(&mut self.cwd_changes)
+    }
+
+    /// Records whether `session_id`'s directory is inside a workspace root. Leaving the
+    /// workspace is audited as a denied command and raised in the error center, once per
+    /// excursion.
+    pub fn record_session_workspace_check(&mut self, session_id: &str, inside_workspace: bool) {
+        let Some(session) = self.sessions.get_mut(session_id) else {
+            return;
+        };
+        let newly_outside = !inside_workspace && !session.outside_workspace;
+        session.outside_workspace = !inside_workspace;
+        if !newly_outside {
+            return;
+        }
+        let cwd = session.cwd.clone();
+        let record = AuditRecord::new(
+            session_id,
+            ActionKind::ExecuteCommand,
+            format!("cd {cwd}"),
+            self.profile.base,
+            PolicyDecision::Deny,
+            ApprovalDecision::NotRequired,
+            ResultStatus::Failed,
+            0,
+        )
+        .with_metadata(BTreeMap::from([(
+            "reason".to_string(),
+            "cwd_outside_workspace".to_string(),
+        )]));
+        self.add_unpersisted_audit_record(record);
+        self.record_error(
+            ErrorReport {
+                code: "session_cwd_outside_workspace",
+                message: format!(
+                    "A sessao {session_id} saiu do workspace e esta em {cwd}. Comandos dela podem alterar arquivos fora do projeto."
+                ),
+                suggested_actions: Vec::new(),
+            },
+            unix_now_s(),
+        );
+    }
+
+    fn apply_command_finished(&mut self, event: &CommandFinished) {
+        if !self.sessions.contains_key(&event.command_id) {
+            self.sessions.insert(
+                event.command_id.clone(),
+                TerminalSessionState::pending_session(event.command_id.clone()),
+            );
+            self.add_session_to_order(&event.command_id);
+        }
+
+        if let Some(session) = self.sessions.get_mut(&event.command_id) {
+            session.lifecycle = CommandLifecycle::Finished {
+                exit_code: event.exit_code,
+                duration_ms: event.duration_ms,
+            };
+            session.waiting_for_input = false;
+            if !session.command.is_empty() {
+                let command = session.command.join(" ");
+                self.command_stats
+                    .entry(command.clone())
+                    .or_insert_with(|| CommandStats::new(command))
+                    .record(event.exit_code, event.duration_ms);
+            }
+            let lines = session.visible_lines();
+            let tail = &lines[lines.len().saturating_sub(FAILURE_TAIL_LINES)..];
+            session.failure_suggestion = classify_failure(&session.command, event.exit_code, tail);
+            let suggestion_summary = session.failure_suggestion.as_ref().map(|suggestion| {
+                format!(
+                    "failure_suggestion {} {} {}",
+                    event.command_id,
+                    suggestion.kind.name(),
+                    suggestion.suggestion
+                )
+            });
+            if let Some(summary) = suggestion_summary {
+                self.push_timeline(
+                    TimelineKind::Command,
+                    Some(event.command_id.as_str()),
+                    summary,
+                );
+            }
+        }
+
+        if event.exit_code != 0 {
+            self.queue_alert(AlertEvent::CommandFailed, &event.command_id);
+        }
+
+        if Duration::from_millis(event.duration_ms)
+            >= self.long_command_threshold(&event.command_id)
+        {
+            let command = self
+                .sessions
+                .get(&event.command_id)
+                .filter(|session| !session.command.is_empty())
+                .map_or_else(
+                    || event.command_id.clone(),
+                    |session| session.command.join(" "),
+                );
+            self.queue_notification(
+                NotificationKind::LongCommandFinished,
+                "Comando finalizado",
+                format!(
+                    "{command} (exit_code={}, {}s)",
+                    event.exit_code,
+                    event.duration_ms / 1_000
+                ),
+            );
+            if self.notification_settings.long_command_finished
+                && self.notification_settings.request_attention
+            {
+                self.attention_requested = true;
+            }
+        }
+    }
+
     fn apply_patch_preview_ready(&mut self, event: &PatchPreviewReady) {
         self.patch_previews.insert(
             event.action_id.clone(),
@@ -617,7 +2014,12 @@ impl UiEventStore {
         }
     }
 
-    fn apply_patch_applied(&mut self, event: &PatchApplied) {
+    #[instrument(
+        name = "alicia.apply_patch",
+        skip_all,
+        fields(action_id = %event.action_id, files = event.files.len())
+    )]
+    fn apply_patch_applied(&mut self, event: &PatchApplied, sequence: u64) {
         if let Some(preview) = self.patch_previews.get_mut(&event.action_id) {
             preview.applied = true;
             if preview.files.is_empty() {
@@ -647,6 +2049,167 @@ impl UiEventStore {
         {
             approval.impact_files = event.files.clone();
         }
+
+        // What the approval actually ended as; a patch applied with no resolved approval
+        // is recorded as not having needed one.
+        let approval_decision = match self
+            .approvals
+            .get(&event.action_id)
+            .map(|approval| approval.status)
+        {
+            Some(ApprovalStatus::Approved) => ApprovalDecision::Approved,
+            Some(ApprovalStatus::Denied) => ApprovalDecision::Denied,
+            Some(ApprovalStatus::Expired) => ApprovalDecision::Expired,
+            Some(ApprovalStatus::Pending) | None => ApprovalDecision::NotRequired,
+        };
+        for file_path in &event.files {
+            let mut record = self.patch_audit_record(
+                &event.action_id,
+                file_path,
+                approval_decision,
+                ResultStatus::Succeeded,
+                sequence,
+            );
+            record.approver = self
+                .approvals
+                .get(&event.action_id)
+                .and_then(|approval| approval.approver.clone());
+            let hunks = self
+                .patch_previews
+                .get(&event.action_id)
+                .and_then(|preview| {
+                    preview
+                        .file_previews
+                        .iter()
+                        .find(|file| file.file_path == *file_path)
+                })
+                .map_or(&[][..], |file| file.hunks.as_slice());
+            if !hunks.is_empty() {
+                // Only approved hunks are written; pending ones are left out like rejected ones.
+                let applied: Vec<_> = hunks
+                    .iter()
+                    .filter(|hunk| hunk.decision == PatchHunkDecision::Approved)
+                    .collect();
+                let rejected: Vec<_> = hunks
+                    .iter()
+                    .filter(|hunk| hunk.decision == PatchHunkDecision::Rejected)
+                    .collect();
+                record.metadata.insert(
+                    "hunks_applied".to_string(),
+                    applied
+                        .iter()
+                        .map(|hunk| hunk.hunk_id.as_str())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                );
+                record.metadata.insert(
+                    "hunks_rejected".to_string(),
+                    rejected
+                        .iter()
+                        .map(|hunk| hunk.hunk_id.as_str())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                );
+                record.metadata.insert(
+                    "partial_apply".to_string(),
+                    (applied.len() < hunks.len()).to_string(),
+                );
+            }
+            self.add_unpersisted_audit_record(record);
+        }
+    }
+
+    fn patch_audit_record(
+        &self,
+        action_id: &str,
+        file_path: &str,
+        approval_decision: ApprovalDecision,
+        result_status: ResultStatus,
+        event_sequence: u64,
+    ) -> AuditRecord {
+        let session_id = self
+            .active_session_id
+            .clone()
+            .unwrap_or_else(|| action_id.to_string());
+        AuditRecord::new(
+            session_id,
+            ActionKind::ApplyPatch,
+            file_path,
+            self.profile.base,
+            self.profile.decision_for_path(
+                &self.workspace_root,
+                Path::new(file_path),
+                ActionKind::ApplyPatch,
+            ),
+            approval_decision,
+            result_status,
+            0,
+        )
+        .with_action_id(action_id)
+        .with_event_sequence(event_sequence)
+    }
+
+    /// Audits the network action that opened (or failed to open) a pull request for a patch.
+    fn record_pull_request_audit(
+        &mut self,
+        network_action_id: &str,
+        target: &str,
+        result_status: ResultStatus,
+    ) {
+        let policy_decision = self.profile.decision_for(ActionKind::NetworkAccess);
+        let approval = self.approvals.get(network_action_id);
+        let approval_decision = match (policy_decision, approval.map(|approval| approval.status)) {
+            (PolicyDecision::RequireApproval, Some(ApprovalStatus::Approved)) => {
+                ApprovalDecision::Approved
+            }
+            (PolicyDecision::RequireApproval, Some(ApprovalStatus::Expired)) => {
+                ApprovalDecision::Expired
+            }
+            (PolicyDecision::RequireApproval, _) => ApprovalDecision::Denied,
+            (PolicyDecision::Allow | PolicyDecision::Deny, _) => ApprovalDecision::NotRequired,
+        };
+        let session_id = self
+            .active_session_id
+            .clone()
+            .unwrap_or_else(|| network_action_id.to_string());
+        let mut record = AuditRecord::new(
+            session_id,
+            ActionKind::NetworkAccess,
+            target,
+            self.profile.base,
+            policy_decision,
+            approval_decision,
+            result_status,
+            0,
+        )
+        .with_action_id(network_action_id);
+        record.approver = approval.and_then(|approval| approval.approver.clone());
+        self.add_unpersisted_audit_record(record);
+    }
+
+    /// The session was launched offline, so the attempt already failed; it is audited as a
+    /// blocked `NetworkAccess` against the session.
+    fn apply_network_access_blocked(&mut self, event: &NetworkAccessBlocked, sequence: u64) {
+        let record = AuditRecord::new(
+            event.command_id.clone(),
+            ActionKind::NetworkAccess,
+            event.detail.clone(),
+            self.profile.base,
+            PolicyDecision::Deny,
+            ApprovalDecision::NotRequired,
+            ResultStatus::Blocked,
+            0,
+        )
+        .with_event_sequence(sequence);
+        self.add_unpersisted_audit_record(record);
+    }
+
+    fn add_unpersisted_audit_record(&mut self, mut record: AuditRecord) {
+        if record.profile_name.is_none() {
+            record.profile_name = self.profile.custom_name().map(str::to_string);
+        }
+        self.unpersisted_audit_records.push(record.clone());
+        self.add_audit_record(record);
     }
 
     fn remove_pending_approval(&mut self, action_id: &str) {
@@ -654,20 +2217,372 @@ impl UiEventStore {
             .retain(|pending_id| pending_id != action_id);
     }
 
+    /// A store rebuilt from the events journaled at `path`, still journaling there; see
+    /// [`Self::replay_journal`].
+    pub fn replay_from(path: &Path) -> Result<Self, EventJournalError> {
+        let mut store = Self::default();
+        store.replay_journal(path)?;
+        Ok(store)
+    }
+
+    /// Pushes the events journaled at `path`, rebuilding the sessions with their output, the
+    /// approvals and the patch previews of a previous run, then journals new events there.
+    /// Sessions the journal left running are marked lost with exit code -1, since their
+    /// processes ended with that run. Alerts, notifications and audit records raised while
+    /// replaying are dropped; the previous run already delivered them.
+    ///
+    /// Replayed approvals are history only. The journal is a plain file anyone with workspace
+    /// access can edit, and it carries no resolution times to check TTLs or single use
+    /// against, so pending and approved ones come back expired and no command or patch can
+    /// run under them.
+    pub fn replay_journal(&mut self, path: &Path) -> Result<usize, EventJournalError> {
+        let events = read_event_journal(path)?;
+        let replayed = events.len();
+        self.event_journal = None;
+        for message in events {
+            self.push(message);
+        }
+        let mut lost: Vec<String> = self
+            .sessions
+            .values_mut()
+            .filter(|session| matches!(session.lifecycle, CommandLifecycle::Running))
+            .map(|session| {
+                session.lifecycle = CommandLifecycle::Finished {
+                    exit_code: -1,
+                    duration_ms: 0,
+                };
+                session.session_id.clone()
+            })
+            .collect();
+        lost.sort();
+        for session_id in lost {
+            self.push_timeline(
+                TimelineKind::Command,
+                Some(session_id.as_str()),
+                format!("session_lost_on_restart {session_id}"),
+            );
+        }
+        for approval in self.approvals.values_mut() {
+            if matches!(
+                approval.status,
+                ApprovalStatus::Pending | ApprovalStatus::Approved
+            ) {
+                approval.status = ApprovalStatus::Expired;
+            }
+            approval.resolved_at_unix_s = None;
+        }
+        self.resolved_approval_ids.clear();
+        self.pending_alerts.clear();
+        self.pending_notifications.clear();
+        self.attention_requested = false;
+        self.cwd_changes.clear();
+        self.unpersisted_audit_records.clear();
+        self.event_journal = Some(EventJournal::open(path)?);
+        Ok(replayed)
+    }
+
+    /// Journals every event pushed from now on to `event_journal`.
+    pub fn set_event_journal(&mut self, event_journal: EventJournal) {
+        self.event_journal = Some(event_journal);
+    }
+
+    pub fn event_journal_path(&self) -> Option<&Path> {
+        self.event_journal.as_ref().map(EventJournal::path)
+    }
+
     pub fn events(&self) -> &[IpcMessage] {
         &self.events
     }
 
+    pub fn memory_budget_bytes(&self) -> Option<usize> {
+        self.config.memory_budget_bytes
+    }
+
+    pub fn set_memory_budget_bytes(&mut self, budget_bytes: Option<usize>) {
+        self.config.memory_budget_bytes = budget_bytes;
+        self.enforce_memory_budget();
+    }
+
+    pub fn memory_usage_estimate(&self) -> MemoryUsage {
+        MemoryUsage {
+            events_bytes: self.events_bytes,
+            scrollback_bytes: self
+                .sessions
+                .values()
+                .map(TerminalSessionState::scrollback_bytes)
+                .sum(),
+            previews_bytes: self
+                .patch_previews
+                .values()
+                .flat_map(|preview| &preview.file_previews)
+                .flat_map(|file| &file.hunks)
+                .flat_map(|hunk| &hunk.lines)
+                .map(String::len)
+                .sum(),
+        }
+    }
+
+    pub fn memory_evictions(&self) -> MemoryEvictions {
+        self.memory_evictions
+    }
+
+    /// Brings the store back under its memory budget. The scrollback of finished sessions
+    /// goes first, oldest session first, then output chunk events, whose text already lives
+    /// in the scrollback. Running sessions, approvals and previews are never evicted, so the
+    /// store may stay over budget. Returns the bytes released.
+    pub fn enforce_memory_budget(&mut self) -> usize {
+        let Some(budget_bytes) = self.config.memory_budget_bytes else {
+            return 0;
+        };
+        let mut excess = self
+            .memory_usage_estimate()
+            .total()
+            .saturating_sub(budget_bytes);
+        if excess == 0 {
+            return 0;
+        }
+
+        let mut released = 0;
+        let mut evicted_lines = 0;
+        for session_id in &self.session_order {
+            if excess == 0 {
+                break;
+            }
+            let Some(session) = self.sessions.get_mut(session_id) else {
+                continue;
+            };
+            if !matches!(session.lifecycle, CommandLifecycle::Finished { .. }) {
+                continue;
+            }
+            let before = session.scrollback_bytes();
+            evicted_lines += session.evict_scrollback();
+            let freed = before.saturating_sub(session.scrollback_bytes());
+            released += freed;
+            excess = excess.saturating_sub(freed);
+        }
+
+        let mut evicted_events = 0;
+        if excess > 0 {
+            let mut kept = Vec::with_capacity(self.events.len());
+            for message in std::mem::take(&mut self.events) {
+                if excess > 0 && matches!(message.event, IpcEvent::CommandOutputChunk(_)) {
+                    let size = estimated_message_bytes(&message);
+                    self.events_bytes = self.events_bytes.saturating_sub(size);
+                    released += size;
+                    excess = excess.saturating_sub(size);
+                    evicted_events += 1;
+                } else {
+                    kept.push(message);
+                }
+            }
+            self.events = kept;
+        }
+
+        if evicted_lines == 0 && evicted_events == 0 {
+            return 0;
+        }
+        self.memory_evictions.passes = self.memory_evictions.passes.saturating_add(1);
+        self.memory_evictions.scrollback_lines = self
+            .memory_evictions
+            .scrollback_lines
+            .saturating_add(evicted_lines as u64);
+        self.memory_evictions.events = self.memory_evictions.events.saturating_add(evicted_events);
+        self.memory_evictions.bytes = self.memory_evictions.bytes.saturating_add(released as u64);
+        self.push_timeline(
+            TimelineKind::System,
+            None,
+            format!(
+                "memory_evicted scrollback_lines={evicted_lines} events={evicted_events} bytes={released}"
+            ),
+        );
+        released
+    }
+
     pub fn timeline(&self) -> &[TimelineEntry] {
         &self.timeline
     }
 
+    /// The timeline entries of any of `kinds` (all when empty), of `session_id` when given and
+    /// whose summary contains `text_query`, ignoring case.
+    pub fn timeline_filtered(
+        &self,
+        kinds: &[TimelineKind],
+        session_id: Option<&str>,
+        text_query: &str,
+    ) -> Vec<&TimelineEntry> {
+        let query = text_query.trim().to_lowercase();
+        self.timeline
+            .iter()
+            .filter(|entry| kinds.is_empty() || kinds.contains(&entry.kind))
+            .filter(|entry| {
+                session_id
+                    .is_none_or(|session_id| self.timeline_entry_session(entry) == Some(session_id))
+            })
+            .filter(|entry| query.is_empty() || entry.summary.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    fn push_timeline(
+        &mut self,
+        kind: TimelineKind,
+        session_id: Option<&str>,
+        summary: String,
+    ) -> u64 {
+        let sequence = self.next_sequence;
+        self.timeline.push(TimelineEntry {
+            sequence,
+            kind,
+            session_id: session_id.map(str::to_string),
+            summary,
+        });
+        self.next_sequence = self.next_sequence.saturating_add(1);
+        sequence
+    }
+
     pub fn has_running_sessions(&self) -> bool {
         self.sessions
             .values()
             .any(|session| matches!(session.lifecycle, CommandLifecycle::Running))
     }
 
+    pub fn status_metrics(&self) -> StatusMetrics {
+        StatusMetrics {
+            pending_approvals: self.pending_approval_count(),
+            running_sessions: self
+                .sessions
+                .values()
+                .filter(|session| matches!(session.lifecycle, CommandLifecycle::Running))
+                .count(),
+            last_event_latency: self.last_event_latency,
+            dropped_events: self.dropped_events,
+            event_channel_capacity: self.event_channel_capacity,
+            lagged_subscribers: self.lagged_subscribers.clone(),
+            event_queue: self.event_queue,
+            audit_health: self.audit_health.clone(),
+            unread_errors: self.error_center.unread_count(),
+        }
+    }
+
+    /// Records the tokens one model call used.
+    pub fn record_token_usage(&mut self, model: &str, usage: TokenUsage) {
+        self.token_usage.record(model, usage, unix_now_s());
+        if let Some(pricing) = self.model_pricing.get(model) {
+            self.total_cost_usd += pricing.cost_usd(usage);
+        }
+        self.push_timeline(
+            TimelineKind::Agent,
+            None,
+            format!(
+                "token_usage {model} prompt={} completion={}",
+                usage.prompt_tokens, usage.completion_tokens
+            ),
+        );
+    }
+
+    pub fn token_usage(&self) -> &TokenUsageLedger {
+        &self.token_usage
+    }
+
+    /// Prices turns of `model` recorded from now on.
+    pub fn set_model_pricing(&mut self, model: impl Into<String>, pricing: ModelPricing) {
+        self.model_pricing.insert(model.into(), pricing);
+    }
+
+    pub fn model_pricing(&self, model: &str) -> Option<ModelPricing> {
+        self.model_pricing.get(model).copied()
+    }
+
+    pub fn total_cost_usd(&self) -> f64 {
+        self.total_cost_usd
+    }
+
+    /// Spending after which the agent loop pauses until an approval lets it continue.
+    pub fn set_budget_limit_usd(&mut self, budget_limit_usd: Option<f64>) {
+        self.budget_limit_usd = budget_limit_usd.filter(|limit| *limit > 0.0);
+    }
+
+    pub fn budget_limit_usd(&self) -> Option<f64> {
+        self.budget_limit_usd
+    }
+
+    pub fn set_available_models(&mut self, models: Vec<ModelDescriptor>) {
+        self.available_models = models;
+    }
+
+    pub fn available_models(&self) -> &[ModelDescriptor] {
+        &self.available_models
+    }
+
+    /// Model the agent should answer with; the runtime switches before the next reply.
+    pub fn selected_model(&self) -> Option<&str> {
+        self.selected_model.as_deref()
+    }
+
+    pub fn select_model(&mut self, id: &str) -> Result<(), UiEventStoreError> {
+        if !self.available_models.iter().any(|model| model.id == id) {
+            return Err(UiEventStoreError::UnknownModel(id.to_string()));
+        }
+        if self.selected_model.as_deref() == Some(id) {
+            return Ok(());
+        }
+        self.selected_model = Some(id.to_string());
+        self.push_timeline(TimelineKind::Agent, None, format!("model_selected {id}"));
+        Ok(())
+    }
+
+    pub fn record_event_latency(&mut self, latency: Duration) {
+        self.last_event_latency = Some(latency);
+    }
+
+    pub fn set_event_channel_capacity(&mut self, capacity: usize) {
+        self.event_channel_capacity = capacity;
+    }
+
+    /// `subscriber` fell behind the event channel and lost `skipped` events.
+    pub fn record_subscriber_lag(&mut self, subscriber: &str, skipped: u64) {
+        let lagged = self
+            .lagged_subscribers
+            .entry(subscriber.to_string())
+            .or_default();
+        *lagged = lagged.saturating_add(skipped);
+        self.record_dropped_events(skipped);
+    }
+
+    pub fn record_event_queue(&mut self, metrics: EventQueueMetrics) {
+        self.event_queue = Some(metrics);
+    }
+
+    pub fn record_dropped_events(&mut self, count: u64) {
+        self.dropped_events = self.dropped_events.saturating_add(count);
+        self.push_timeline(
+            TimelineKind::System,
+            None,
+            format!("events_dropped count={count} total={}", self.dropped_events),
+        );
+    }
+
+    pub fn record_audit_write(&mut self, result: Result<(), String>) {
+        self.audit_health = match (result, &self.audit_health) {
+            (Ok(()), _) => AuditHealth::Healthy,
+            (
+                Err(last_error),
+                AuditHealth::Failing {
+                    consecutive_failures,
+                    ..
+                },
+            ) => AuditHealth::Failing {
+                consecutive_failures: consecutive_failures.saturating_add(1),
+                last_error,
+            },
+            (Err(last_error), AuditHealth::NoWrites | AuditHealth::Healthy) => {
+                AuditHealth::Failing {
+                    consecutive_failures: 1,
+                    last_error,
+                }
+            }
+        };
+    }
+
     pub fn pending_approval_count(&self) -> usize {
         self.pending_approval_ids.len()
     }
@@ -686,6 +2601,11 @@ impl UiEventStore {
     pub fn approval_prompt(&self, action_id: &str) -> Option<ApprovalPrompt> {
         let approval = self.approvals.get(action_id)?;
         let command = approval.command.as_ref().map(|command| command.join(" "));
+        let command_action_kinds = approval
+            .command
+            .as_deref()
+            .map(inferred_command_action_kinds)
+            .unwrap_or_default();
         let impact = if approval.impact_files.is_empty() {
             None
         } else {
@@ -703,6 +2623,7 @@ impl UiEventStore {
             where_target: approval.target.clone(),
             action_kind: approval.action_kind,
             command,
+            command_action_kinds,
             impact,
             expires_at_unix_s: approval.expires_at_unix_s,
         })
@@ -718,54 +2639,147 @@ impl UiEventStore {
         }
     }
 
+    /// Decision of this run for `command`, falling back to the approval memory of the
+    /// workspace when it is enabled and remembers the command.
     pub fn resolved_approval_decision_for_command(
         &self,
         command: &[String],
     ) -> Option<ApprovalDecision> {
-        for message in self.events.iter().rev() {
-            let IpcEvent::ApprovalResolved(event) = &message.event else {
-                continue;
-            };
-            let Some(approval) = self.approvals.get(&event.action_id) else {
-                continue;
-            };
-            if approval
-                .command
-                .as_ref()
-                .is_none_or(|stored| stored != command)
-            {
-                continue;
-            }
-            return Some(match event.resolution {
-                ApprovalResolution::Approved => ApprovalDecision::Approved,
-                ApprovalResolution::Denied => ApprovalDecision::Denied,
-                ApprovalResolution::Expired => ApprovalDecision::Expired,
-            });
-        }
+        self.resolved_approval_for_command(command)
+            .map(|link| link.decision)
+            .or_else(|| {
+                self.remembered_approvals
+                    .command_match(command)
+                    .map(|_| ApprovalDecision::Approved)
+            })
+    }
 
-        for approval in self.approvals.values() {
-            if approval
-                .command
-                .as_ref()
-                .is_none_or(|stored| stored != command)
-            {
-                continue;
-            }
-            match approval.status {
-                ApprovalStatus::Pending => {}
-                ApprovalStatus::Approved => return Some(ApprovalDecision::Approved),
-                ApprovalStatus::Denied => return Some(ApprovalDecision::Denied),
-                ApprovalStatus::Expired => return Some(ApprovalDecision::Expired),
+    pub fn resolved_approval_for_command(&self, command: &[String]) -> Option<CommandApprovalLink> {
+        self.resolved_approval_for_command_at(command, unix_now_s())
+    }
+
+    /// Latest decision for `command` that still applies at `now_unix_s`. An approval stops
+    /// matching once the `ApprovalValidity` of its action kind runs out, so the command needs
+    /// approval again; denials keep matching.
+    pub fn resolved_approval_for_command_at(
+        &self,
+        command: &[String],
+        now_unix_s: i64,
+    ) -> Option<CommandApprovalLink> {
+        let (approval, link) = self.latest_approval_for_command(command)?;
+        if link.decision == ApprovalDecision::Approved {
+            let validity =
+                self.approval_validity(approval.action_kind.unwrap_or(ActionKind::ExecuteCommand));
+            let granted_at_unix_s = approval.resolved_at_unix_s.unwrap_or(now_unix_s);
+            if !validity.covers(granted_at_unix_s, approval.uses, now_unix_s) {
+                return None;
             }
         }
+        Some(link)
+    }
 
-        None
+    /// Latest decision for `command` regardless of validity, for linking audit records to
+    /// the approval a session ran under.
+    pub fn latest_resolved_approval_for_command(
+        &self,
+        command: &[String],
+    ) -> Option<CommandApprovalLink> {
+        self.latest_approval_for_command(command)
+            .map(|(_, link)| link)
     }
 
-    pub fn resolve_pending_approval(
-        &mut self,
-        action_id: &str,
+    fn latest_approval_for_command(
+        &self,
+        command: &[String],
+    ) -> Option<(&ApprovalItem, CommandApprovalLink)> {
+        self.resolved_approval_ids
+            .iter()
+            .rev()
+            .filter_map(|action_id| self.approvals.get(action_id))
+            .filter(|approval| approval.command.as_deref() == Some(command))
+            .find_map(|approval| {
+                let decision = match approval.status {
+                    ApprovalStatus::Pending => return None,
+                    ApprovalStatus::Approved => ApprovalDecision::Approved,
+                    ApprovalStatus::Denied => ApprovalDecision::Denied,
+                    ApprovalStatus::Expired => ApprovalDecision::Expired,
+                };
+                let link = CommandApprovalLink {
+                    action_id: approval.action_id.clone(),
+                    decision,
+                    event_sequence: approval.resolved_sequence,
+                    approver: approval.approver.clone(),
+                };
+                Some((approval, link))
+            })
+    }
+
+    pub fn approval_validity(&self, action_kind: ActionKind) -> ApprovalValidity {
+        self.approval_validity
+            .get(&action_kind)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Replaces the per-kind validity; kinds left out keep approvals valid and reusable.
+    pub fn set_approval_validity(
+        &mut self,
+        approval_validity: BTreeMap<ActionKind, ApprovalValidity>,
+    ) {
+        self.approval_validity = approval_validity;
+    }
+
+    /// Counts an execution started under `action_id`'s approval.
+    pub fn record_approval_use(&mut self, action_id: &str) {
+        if let Some(approval) = self.approvals.get_mut(action_id) {
+            approval.uses = approval.uses.saturating_add(1);
+        }
+    }
+
+    pub fn approval_history(&self) -> Vec<&ApprovalItem> {
+        self.resolved_approval_ids
+            .iter()
+            .filter_map(|action_id| self.approvals.get(action_id))
+            .collect()
+    }
+
+    pub fn approver_identity(&self) -> Option<&ApproverIdentity> {
+        self.approver_identity.as_ref()
+    }
+
+    /// Sets who is recorded as approving decisions taken through this store. Defaults to the
+    /// OS user.
+    pub fn set_approver_identity(&mut self, approver_identity: Option<ApproverIdentity>) {
+        self.approver_identity = approver_identity;
+    }
+
+    pub fn resolve_pending_approval(
+        &mut self,
+        action_id: &str,
+        resolution: ApprovalResolution,
+    ) -> Result<IpcMessage, UiEventStoreError> {
+        let approver = self.approver_identity.clone();
+        self.resolve_pending_approval_as(action_id, resolution, approver)
+    }
+
+    /// Resolves a pending approval on behalf of an explicit approver, e.g. a remote principal
+    /// relayed by a webhook or WebSocket bridge.
+    #[instrument(name = "alicia.resolve_approval", skip(self, approver), err)]
+    pub fn resolve_pending_approval_as(
+        &mut self,
+        action_id: &str,
+        resolution: ApprovalResolution,
+        approver: Option<ApproverIdentity>,
+    ) -> Result<IpcMessage, UiEventStoreError> {
+        self.resolve_pending_approval_with(action_id, resolution, approver, None)
+    }
+
+    fn resolve_pending_approval_with(
+        &mut self,
+        action_id: &str,
         resolution: ApprovalResolution,
+        approver: Option<ApproverIdentity>,
+        reason: Option<DenialReason>,
     ) -> Result<IpcMessage, UiEventStoreError> {
         let Some(approval) = self.approvals.get(action_id) else {
             return Err(UiEventStoreError::ApprovalNotPending(action_id.to_string()));
@@ -778,19 +2792,86 @@ impl UiEventStore {
         let message = IpcMessage::new(IpcEvent::ApprovalResolved(ApprovalResolved {
             action_id: action_id.to_string(),
             resolution,
+            approver,
+            reason,
         }));
         self.push(message.clone());
+        self.unpublished_resolutions.push(message.clone());
         Ok(message)
     }
 
+    /// Takes the resolutions made since the last call, for the runtime to broadcast.
+    pub fn take_unpublished_resolutions(&mut self) -> Vec<IpcMessage> {
+        std::mem::take(&mut self.unpublished_resolutions)
+    }
+
     pub fn approve(&mut self, action_id: &str) -> Result<IpcMessage, UiEventStoreError> {
         self.resolve_pending_approval(action_id, ApprovalResolution::Approved)
     }
 
+    /// Approves `action_id` to run `command` instead of the proposed one. The edited command
+    /// replaces the proposed one, so it is what runs under the approval; the original stays
+    /// in `ApprovalItem::original_command` for the audit record.
+    pub fn approve_with_command(
+        &mut self,
+        action_id: &str,
+        mut command: Vec<String>,
+    ) -> Result<IpcMessage, UiEventStoreError> {
+        let Some(approval) = self
+            .approvals
+            .get_mut(action_id)
+            .filter(|approval| approval.status == ApprovalStatus::Pending)
+        else {
+            return Err(UiEventStoreError::ApprovalNotPending(action_id.to_string()));
+        };
+        let not_editable = |reason: &str| UiEventStoreError::ApprovalCommandNotEditable {
+            action_id: action_id.to_string(),
+            reason: reason.to_string(),
+        };
+        let Some(proposed) = approval.command.clone() else {
+            return Err(not_editable("no command was proposed"));
+        };
+        command.retain(|token| !token.is_empty());
+        if command.is_empty() {
+            return Err(not_editable("the edited command is empty"));
+        }
+        if command != proposed {
+            approval.original_command.get_or_insert(proposed.clone());
+            approval.command = Some(command.clone());
+            self.approval_commands
+                .insert(action_id.to_string(), command.clone());
+            self.push_timeline(
+                TimelineKind::Approval,
+                None,
+                format!(
+                    "approval_command_modified {action_id} from={} to={}",
+                    proposed.join(" "),
+                    command.join(" ")
+                ),
+            );
+        }
+        self.approve(action_id)
+    }
+
     pub fn deny(&mut self, action_id: &str) -> Result<IpcMessage, UiEventStoreError> {
         self.resolve_pending_approval(action_id, ApprovalResolution::Denied)
     }
 
+    /// Denies `action_id` telling IPC consumers why, so the agent can change course.
+    pub fn deny_with_reason(
+        &mut self,
+        action_id: &str,
+        reason: DenialReason,
+    ) -> Result<IpcMessage, UiEventStoreError> {
+        let approver = self.approver_identity.clone();
+        self.resolve_pending_approval_with(
+            action_id,
+            ApprovalResolution::Denied,
+            approver,
+            Some(reason),
+        )
+    }
+
     pub fn expire_pending_approvals(&mut self, now_unix_s: i64) -> Vec<IpcMessage> {
         let to_expire: Vec<String> = self
             .pending_approval_ids
@@ -807,7 +2888,7 @@ impl UiEventStore {
         let mut messages = Vec::with_capacity(to_expire.len());
         for action_id in to_expire {
             if let Ok(message) =
-                self.resolve_pending_approval(&action_id, ApprovalResolution::Expired)
+                self.resolve_pending_approval_as(&action_id, ApprovalResolution::Expired, None)
             {
                 messages.push(message);
             }
@@ -817,7 +2898,7 @@ impl UiEventStore {
     }
 
     pub fn add_audit_record(&mut self, record: AuditRecord) {
-        let summary = format!(
+        let mut summary = format!(
             "audit session={} action={} target={} policy={} approval={} result={}",
             record.session_id,
             action_kind_name(record.action_kind),
@@ -826,1701 +2907,11771 @@ impl UiEventStore {
             approval_decision_name(record.approval_decision),
             result_status_name(record.result_status)
         );
+        if let Some(action_id) = record.action_id.as_deref() {
+            summary.push_str(&format!(" action_id={action_id}"));
+        }
+        if let Some(event_sequence) = record.event_sequence {
+            summary.push_str(&format!(" event=#{event_sequence}"));
+        }
+        if let Some(approver) = &record.approver {
+            summary.push_str(&format!(" approver={}", approver.name));
+        }
 
-        self.timeline.push(TimelineEntry {
-            sequence: self.next_sequence,
-            summary,
-        });
-        self.next_sequence = self.next_sequence.saturating_add(1);
+        self.push_timeline(TimelineKind::Audit, Some(&record.session_id), summary);
+        // Denials were decided by the user, so only policy and expiry blocks are notified.
+        if record.result_status == ResultStatus::Blocked
+            && record.approval_decision != ApprovalDecision::Denied
+        {
+            self.queue_notification(
+                NotificationKind::ActionBlocked,
+                "Acao bloqueada",
+                format!("{} {}", action_kind_name(record.action_kind), record.target),
+            );
+        }
         self.audit_records.push(record);
     }
 
-    pub fn audit_records(&self) -> &[AuditRecord] {
-        &self.audit_records
+    pub fn notification_settings(&self) -> &NotificationSettings {
+        &self.notification_settings
     }
 
-    pub fn permission_profile(&self) -> PermissionProfile {
-        self.permission_profile
+    pub fn set_notification_settings(&mut self, settings: NotificationSettings) {
+        self.notification_settings = settings;
     }
 
-    pub fn set_permission_profile(&mut self, profile: PermissionProfile) {
-        self.permission_profile = profile;
+    /// Overrides the long command threshold for `session_id`; `None` goes back to the global
+    /// one.
+    pub fn set_session_long_command_threshold(
+        &mut self,
+        session_id: &str,
+        threshold: Option<Duration>,
+    ) {
+        match threshold {
+            Some(threshold) => {
+                self.session_long_command_thresholds
+                    .insert(session_id.to_string(), threshold);
+            }
+            None => {
+                self.session_long_command_thresholds.remove(session_id);
+            }
+        }
     }
 
-    pub fn terminal_session_ids(&self) -> &[String] {
-        &self.session_order
+    pub fn session_long_command_threshold(&self, session_id: &str) -> Option<Duration> {
+        self.session_long_command_thresholds
+            .get(session_id)
+            .copied()
     }
 
-    pub fn active_session_id(&self) -> Option<&str> {
-        self.active_session_id.as_deref()
+    fn long_command_threshold(&self, session_id: &str) -> Duration {
+        self.session_long_command_threshold(session_id)
+            .unwrap_or(self.notification_settings.long_command_threshold)
     }
 
-    pub fn set_active_session(&mut self, session_id: &str) -> Result<(), UiEventStoreError> {
-        if !self.sessions.contains_key(session_id) {
-            return Err(UiEventStoreError::SessionNotFound(session_id.to_string()));
-        }
+    /// Whether a long command finished since the last call while `request_attention` is
+    /// set, for the host to flag its window.
+    pub fn take_attention_request(&mut self) -> bool {
+        std::mem::take(&mut self.attention_requested)
+    }
 
-        self.active_session_id = Some(session_id.to_string());
-        Ok(())
+    /// Notifications raised since the last call, for the host to show with
+    /// `send_desktop_notification`.
+    pub fn take_notifications(&mut self) -> Vec<DesktopNotification> {
+        std::mem::take(&mut self.pending_notifications)
     }
 
-    pub fn terminal_session(&self, session_id: &str) -> Option<&TerminalSessionState> {
-        self.sessions.get(session_id)
+    pub fn alert_settings(&self) -> &AlertSettings {
+        &self.alert_settings
     }
 
-    pub fn active_terminal_text(&self) -> Option<String> {
-        let active_session_id = self.active_session_id.as_ref()?;
-        let session = self.sessions.get(active_session_id)?;
-        Some(session.visible_text())
+    pub fn set_alert_settings(&mut self, settings: AlertSettings) {
+        self.alert_settings = settings;
     }
 
-    pub fn max_scrollback_lines(&self) -> usize {
-        self.max_scrollback_lines
+    /// Bells, approvals and failed commands since the last call whose alert is turned on,
+    /// for the view to play or flash.
+    pub fn take_alerts(&mut self) -> Vec<Alert> {
+        std::mem::take(&mut self.pending_alerts)
     }
 
-    pub fn set_max_scrollback_lines(&mut self, max_scrollback_lines: usize) {
-        self.max_scrollback_lines = max_scrollback_lines.max(1);
-        for session in self.sessions.values_mut() {
-            session.trim_scrollback_to(self.max_scrollback_lines);
+    fn queue_alert(&mut self, event: AlertEvent, source: &str) {
+        let channels = self.alert_settings.channels(event);
+        if !channels.is_off() {
+            self.pending_alerts.push(Alert {
+                event,
+                source: source.to_string(),
+                channels,
+            });
         }
     }
 
-    pub fn bind_session_input(
-        &mut self,
-        session_id: impl Into<String>,
-        writer: mpsc::Sender<Vec<u8>>,
-    ) {
-        self.session_input_writers.insert(session_id.into(), writer);
+    fn queue_notification(&mut self, kind: NotificationKind, title: &str, body: impl Into<String>) {
+        if self.notification_settings.is_enabled(kind) {
+            self.pending_notifications.push(DesktopNotification {
+                kind,
+                title: title.to_string(),
+                body: body.into(),
+            });
+        }
     }
 
-    pub fn unbind_session_input(&mut self, session_id: &str) {
-        self.session_input_writers.remove(session_id);
+    pub fn record_error(&mut self, report: ErrorReport, now_unix_s: i64) -> u64 {
+        self.push_timeline(TimelineKind::System, None, format!("error {}", report.code));
+        self.error_center.record(report, now_unix_s)
     }
 
-    pub fn send_input_to_session(
-        &self,
-        session_id: &str,
-        input: impl AsRef<[u8]>,
-    ) -> Result<(), UiEventStoreError> {
-        let Some(writer) = self.session_input_writers.get(session_id) else {
-            return Err(UiEventStoreError::SessionInputNotBound(
-                session_id.to_string(),
-            ));
-        };
-
-        writer.try_send(input.as_ref().to_vec()).map_err(|error| {
-            UiEventStoreError::SessionInputSendFailed {
-                session_id: session_id.to_string(),
-                reason: error.to_string(),
-            }
-        })
+    pub fn error_center(&self) -> &ErrorCenter {
+        &self.error_center
     }
 
-    pub fn send_input_to_active_session(
-        &self,
-        input: impl AsRef<[u8]>,
-    ) -> Result<(), UiEventStoreError> {
-        let Some(active_session_id) = self.active_session_id.as_deref() else {
-            return Err(UiEventStoreError::SessionNotFound(
-                "<active_session>".to_string(),
-            ));
-        };
-
-        self.send_input_to_session(active_session_id, input)
+    pub fn error_center_mut(&mut self) -> &mut ErrorCenter {
+        &mut self.error_center
     }
 
-    pub fn diff_preview(&self, action_id: &str) -> Option<&PatchPreviewState> {
-        self.patch_previews.get(action_id)
+    pub fn push_chat_message(&mut self, role: ChatRole, content: impl Into<String>) -> u64 {
+        let id = self.chat.push(role, content);
+        self.push_timeline(
+            TimelineKind::Agent,
+            None,
+            format!("chat_message {id} {}", chat_role_name(role)),
+        );
+        id
     }
 
-    pub fn unapplied_diff_previews(&self) -> Vec<&PatchPreviewState> {
-        self.patch_previews
-            .values()
-            .filter(|preview| !preview.applied)
-            .collect()
+    pub fn chat_messages(&self) -> &[ChatMessage] {
+        self.chat.messages()
     }
 
-    pub fn attach_patch_file_diff(
-        &mut self,
-        action_id: &str,
-        file_path: impl Into<String>,
-        unified_diff: &str,
-    ) -> Result<usize, UiEventStoreError> {
-        let file_path = file_path.into();
-        let hunks = parse_unified_diff_hunks(unified_diff);
-        let preview = self
-            .patch_previews
-            .get_mut(action_id)
-            .ok_or_else(|| UiEventStoreError::PatchPreviewNotFound(action_id.to_string()))?;
+    pub fn chat(&self) -> &ChatStore {
+        &self.chat
+    }
 
-        if !preview.files.iter().any(|file| file == &file_path) {
-            preview.files.push(file_path.clone());
+    /// Sends the prompt as a user message carrying the pending draft attachments.
+    /// `/model <id>` switches the agent's model instead of reaching the agent.
+    /// Posts `content` as a user message. A registered slash command runs instead of reaching
+    /// the agent and its output is posted as a system reply.
+    pub fn submit_chat_prompt(&mut self, content: impl Into<String>) -> u64 {
+        let content = content.into();
+        if let Some(invocation) = parse_slash_command(&content) {
+            let id = self.push_chat_message(ChatRole::User, content.clone());
+            let reply = match self.slash_commands.handler(&invocation.name) {
+                Some(handler) => handler.run(self, &invocation.args),
+                None => {
+                    let names: Vec<String> = self
+                        .slash_commands
+                        .specs()
+                        .into_iter()
+                        .map(|spec| format!("/{}", spec.name))
+                        .collect();
+                    format!(
+                        "Comando desconhecido: /{}. Comandos: {}.",
+                        invocation.name,
+                        names.join(", ")
+                    )
+                }
+            };
+            self.push_chat_message(ChatRole::System, reply);
+            return id;
         }
-
-        if let Some(file_preview) = preview
-            .file_previews
-            .iter_mut()
-            .find(|file| file.file_path == file_path)
-        {
-            file_preview.hunks = hunks.clone();
-        } else {
-            preview.file_previews.push(PatchFilePreview {
-                file_path: file_path.clone(),
-                hunks: hunks.clone(),
-            });
+        let id = self.push_chat_message(ChatRole::User, content);
+        if let Some(message) = self.chat.message_mut(id) {
+            message.attachments = std::mem::take(&mut self.draft_attachments);
         }
+        self.agent_prompt_ids.push(id);
+        id
+    }
 
-        if let Some(approval) = self.approvals.get_mut(action_id)
-            && !approval.impact_files.iter().any(|file| file == &file_path)
-        {
-            approval.impact_files.push(file_path.clone());
-        }
+    /// Prompts submitted since the last call, oldest first.
+    pub fn take_agent_prompts(&mut self) -> Vec<ChatMessage> {
+        let ids = std::mem::take(&mut self.agent_prompt_ids);
+        self.chat
+            .messages()
+            .iter()
+            .filter(|message| ids.contains(&message.id))
+            .cloned()
+            .collect()
+    }
 
-        self.timeline.push(TimelineEntry {
-            sequence: self.next_sequence,
-            summary: format!(
-                "patch_hunks_loaded {} file={} hunks={}",
-                action_id,
-                file_path,
-                hunks.len()
-            ),
-        });
-        self.next_sequence = self.next_sequence.saturating_add(1);
+    /// Drops every chat message, including prompts the agent has not received yet.
+    pub fn clear_chat(&mut self) {
+        self.chat.clear();
+        self.agent_prompt_ids.clear();
+        self.push_timeline(TimelineKind::Agent, None, "chat_cleared".to_string());
+    }
 
-        Ok(hunks.len())
+    pub fn register_slash_command(&mut self, handler: std::sync::Arc<dyn SlashCommandHandler>) {
+        self.slash_commands.register(handler);
     }
 
-    pub fn set_patch_hunk_decision(
-        &mut self,
-        action_id: &str,
-        file_path: &str,
-        hunk_id: &str,
-        decision: PatchHunkDecision,
-    ) -> Result<(), UiEventStoreError> {
-        let preview = self
-            .patch_previews
-            .get_mut(action_id)
-            .ok_or_else(|| UiEventStoreError::PatchPreviewNotFound(action_id.to_string()))?;
+    pub fn slash_commands(&self) -> &SlashCommandRegistry {
+        &self.slash_commands
+    }
 
-        let file_preview = preview
-            .file_previews
-            .iter_mut()
-            .find(|file| file.file_path == file_path)
-            .ok_or_else(|| UiEventStoreError::PatchFileNotFound {
-                action_id: action_id.to_string(),
-                file_path: file_path.to_string(),
-            })?;
+    /// Queues a session for [`AliciaUiRuntime::start_requested_sessions`].
+    pub fn request_session(&mut self, command: Vec<String>) {
+        self.requested_sessions.push(command);
+    }
 
-        let hunk = file_preview
-            .hunks
-            .iter_mut()
-            .find(|hunk| hunk.hunk_id == hunk_id)
-            .ok_or_else(|| UiEventStoreError::PatchHunkNotFound {
-                action_id: action_id.to_string(),
-                file_path: file_path.to_string(),
-                hunk_id: hunk_id.to_string(),
-            })?;
+    pub fn take_requested_sessions(&mut self) -> Vec<Vec<String>> {
+        std::mem::take(&mut self.requested_sessions)
+    }
 
-        hunk.decision = decision;
-        self.timeline.push(TimelineEntry {
-            sequence: self.next_sequence,
-            summary: format!(
-                "patch_hunk_decision {} file={} hunk={} decision={}",
-                action_id,
-                file_path,
-                hunk_id,
-                patch_hunk_decision_name(decision)
-            ),
-        });
-        self.next_sequence = self.next_sequence.saturating_add(1);
+    /// Queues `action_id`'s patch for [`AliciaUiRuntime::apply_requested_hunks`].
+    pub fn request_hunk_apply(&mut self, action_id: &str) {
+        if !self.requested_hunk_applies.iter().any(|id| id == action_id) {
+            self.requested_hunk_applies.push(action_id.to_string());
+        }
+    }
 
-        Ok(())
+    pub fn take_requested_hunk_applies(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.requested_hunk_applies)
     }
 
-    pub fn approve_patch_hunk(
-        &mut self,
-        action_id: &str,
-        file_path: &str,
-        hunk_id: &str,
-    ) -> Result<(), UiEventStoreError> {
-        self.set_patch_hunk_decision(action_id, file_path, hunk_id, PatchHunkDecision::Approved)
+    /// Queues an export of the audit records for [`AliciaUiRuntime::export_requested_audits`].
+    pub fn request_audit_export(&mut self, exporter: AuditExporter) {
+        self.requested_audit_exports.push(exporter);
     }
 
-    pub fn reject_patch_hunk(
-        &mut self,
-        action_id: &str,
-        file_path: &str,
-        hunk_id: &str,
-    ) -> Result<(), UiEventStoreError> {
-        self.set_patch_hunk_decision(action_id, file_path, hunk_id, PatchHunkDecision::Rejected)
+    pub fn take_requested_audit_exports(&mut self) -> Vec<AuditExporter> {
+        std::mem::take(&mut self.requested_audit_exports)
     }
 
-    pub fn unresolved_patch_hunk_count(&self, action_id: &str) -> Option<usize> {
-        let preview = self.patch_previews.get(action_id)?;
-        Some(
-            preview
-                .file_previews
-                .iter()
-                .flat_map(|file| file.hunks.iter())
-                .filter(|hunk| hunk.decision == PatchHunkDecision::Pending)
-                .count(),
-        )
+    pub fn set_review_session_env(&mut self, review_session_env: bool) {
+        self.review_session_env = review_session_env;
     }
-}
 
-#[derive(Debug)]
-pub struct AliciaUiRuntime {
-    session_manager: SessionManager,
-    events_rx: tokio::sync::broadcast::Receiver<IpcMessage>,
-    store: UiEventStore,
-    audit_logger: Option<AuditLogger>,
-    workspace_root: PathBuf,
-}
+    pub fn review_session_env(&self) -> bool {
+        self.review_session_env
+    }
 
-impl AliciaUiRuntime {
-    pub fn new(session_manager: SessionManager, max_scrollback_lines: usize) -> Self {
-        let events_rx = session_manager.event_receiver();
-        let workspace_root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-        Self {
-            session_manager,
-            events_rx,
-            store: UiEventStore::new(max_scrollback_lines),
-            audit_logger: None,
-            workspace_root,
-        }
+    /// Holds `request` until its environment is reviewed in the view.
+    pub fn stage_session(&mut self, request: SessionStartRequest) {
+        self.staged_sessions.push(request);
     }
 
-    pub fn with_workspace_root(mut self, workspace_root: PathBuf) -> Self {
-        self.workspace_root = workspace_root;
-        self
+    pub fn staged_sessions(&self) -> &[SessionStartRequest] {
+        &self.staged_sessions
     }
 
-    pub fn with_audit_logger(mut self, audit_logger: AuditLogger) -> Self {
-        self.audit_logger = Some(audit_logger);
-        self
+    /// Applies the reviewed `edits` to the staged session and queues it to start. Returns
+    /// false when no session with that id is staged.
+    pub fn launch_staged_session(&mut self, session_id: &str, edits: &EnvEdits) -> bool {
+        let Some(index) = self
+            .staged_sessions
+            .iter()
+            .position(|request| request.session_id == session_id)
+        else {
+            return false;
+        };
+        let request = self.staged_sessions.remove(index).with_env_edits(edits);
+        self.launched_sessions.push(request);
+        true
     }
 
-    pub fn store(&self) -> &UiEventStore {
-        &self.store
+    pub fn discard_staged_session(&mut self, session_id: &str) -> bool {
+        let before = self.staged_sessions.len();
+        self.staged_sessions
+            .retain(|request| request.session_id != session_id);
+        self.staged_sessions.len() != before
     }
 
-    pub fn store_mut(&mut self) -> &mut UiEventStore {
-        &mut self.store
+    pub fn take_launched_sessions(&mut self) -> Vec<SessionStartRequest> {
+        std::mem::take(&mut self.launched_sessions)
     }
 
-    pub fn session_manager(&self) -> &SessionManager {
-        &self.session_manager
+    pub fn set_session_cwd(&mut self, session_cwd: Option<PathBuf>) {
+        self.session_cwd = session_cwd;
     }
 
-    pub async fn start_session(
-        &mut self,
-        request: SessionStartRequest,
-    ) -> Result<(), AliciaUiRuntimeError> {
-        let mut request = request;
-        let session_id = request.session_id.clone();
-        let command = command_tokens(&request.program, &request.args);
-        let command_target = command_target(
-            &request.program,
-            &request.args,
-            request.audit_context.target.as_str(),
-        );
-        let guard =
-            ensure_target_in_workspace(&self.workspace_root, &request.cwd).map_err(|source| {
-                AliciaUiRuntimeError::WorkspaceGuardBlocked {
-                    session_id: session_id.clone(),
-                    cwd: request.cwd.to_string_lossy().to_string(),
-                    source,
-                }
-            })?;
-        request.cwd = guard.canonical_target;
+    pub fn session_cwd(&self) -> Option<&Path> {
+        self.session_cwd.as_deref()
+    }
 
-        let fallback_profile = self.store.permission_profile();
-        let effective_profile = resolve_effective_profile(&self.workspace_root, fallback_profile)
-            .map_err(|source| AliciaUiRuntimeError::ResolveProfileFailed {
-            workspace: self.workspace_root.to_string_lossy().to_string(),
-            source,
-        })?;
-        self.store.set_permission_profile(effective_profile);
+    pub fn set_recent_dirs(&mut self, recent_dirs: Vec<PathBuf>) {
+        self.recent_dirs = recent_dirs;
+    }
 
-        let exec_decision = effective_profile.decision_for(ActionKind::ExecuteCommand);
-        let network_decision = network_decision_for_profile(effective_profile);
-        let policy_decision = combine_policy_decisions(exec_decision, network_decision);
-        let store_approval_decision = self.store.resolved_approval_decision_for_command(&command);
-        let requested_approval_decision = selected_approval_decision(
-            request.audit_context.approval_decision,
-            store_approval_decision,
-        );
-        let approval_decision =
-            effective_approval_decision(policy_decision, requested_approval_decision);
+    pub fn recent_dirs(&self) -> &[PathBuf] {
+        &self.recent_dirs
+    }
 
-        if let Some(reason) = blocked_reason(policy_decision, approval_decision) {
-            self.record_blocked_audit(
-                &session_id,
-                command_target.as_str(),
-                effective_profile,
-                policy_decision,
-                approval_decision,
-            )
-            .await?;
-            return Err(AliciaUiRuntimeError::CommandBlocked { session_id, reason });
+    pub fn set_runtime_health(&mut self, runtime_health: RuntimeHealth) {
+        self.runtime_health = Some(runtime_health);
+    }
+
+    pub fn runtime_health(&self) -> Option<&RuntimeHealth> {
+        self.runtime_health.as_ref()
+    }
+
+    pub fn set_workspace_root(&mut self, workspace_root: PathBuf) {
+        self.workspace_root = workspace_root;
+    }
+
+    pub fn workspace_root(&self) -> &Path {
+        &self.workspace_root
+    }
+
+    pub fn set_remembered_approvals(&mut self, remembered_approvals: RememberedApprovals) {
+        self.remembered_approvals = remembered_approvals;
+        self.remembered_approvals_changed = false;
+    }
+
+    pub fn remembered_approvals(&self) -> &RememberedApprovals {
+        &self.remembered_approvals
+    }
+
+    /// Opts the workspace in or out of the approval memory. Turning it off keeps what was
+    /// remembered but stops consulting it.
+    pub fn set_approval_memory_enabled(&mut self, enabled: bool) {
+        if self.remembered_approvals.enabled != enabled {
+            self.remembered_approvals.enabled = enabled;
+            self.remembered_approvals_changed = true;
         }
+    }
 
-        request.audit_context = SessionAuditContext {
-            action_kind: ActionKind::ExecuteCommand,
-            target: command_target,
-            profile: effective_profile,
-            policy_decision,
-            approval_decision,
+    /// Approves `action_id` and, with the approval memory on, remembers its command, or its
+    /// target when it has none, so later runs approve them without asking.
+    pub fn approve_and_remember(
+        &mut self,
+        action_id: &str,
+    ) -> Result<IpcMessage, UiEventStoreError> {
+        let message = self.approve(action_id)?;
+        if !self.remembered_approvals.enabled {
+            return Ok(message);
+        }
+        let Some(approval) = self.approvals.get(action_id) else {
+            return Ok(message);
+        };
+        let now_unix_s = unix_now_s();
+        let remembered = match (approval.command.clone(), approval.target.as_deref()) {
+            (Some(command), _) => {
+                let summary = format!("command={}", command.join(" "));
+                self.remembered_approvals
+                    .remember_command(command, now_unix_s)
+                    .then_some(summary)
+            }
+            (None, Some(target)) => self
+                .remembered_approvals
+                .remember_target(&self.workspace_root, target, now_unix_s)
+                .then(|| format!("target={target}")),
+            (None, None) => None,
         };
+        if let Some(remembered) = remembered {
+            self.remembered_approvals_changed = true;
+            self.push_approval_memory_entry(format!(
+                "approval_remembered {action_id} {remembered}"
+            ));
+        }
+        Ok(message)
+    }
 
-        self.session_manager.start(request).await?;
-        self.bind_session_input(&session_id).await?;
-        self.pump_events();
-        Ok(())
+    /// Approves `action_id` and asks the runtime to add a rule approving its command to the
+    /// project policy, so it is no longer prompted for in this workspace.
+    pub fn always_allow(&mut self, action_id: &str) -> Result<IpcMessage, UiEventStoreError> {
+        let message = self.approve(action_id)?;
+        let rule = self
+            .approvals
+            .get(action_id)
+            .and_then(|approval| approval.command.as_deref())
+            .map(ApprovalRule::for_command);
+        if let Some(rule) = rule {
+            self.push_approval_memory_entry(format!(
+                "approval_rule_requested {action_id} pattern={}",
+                rule.pattern
+            ));
+            self.requested_approval_rules
+                .push((action_id.to_string(), rule));
+        }
+        Ok(message)
     }
 
-    pub async fn stop_session(&mut self, session_id: &str) -> Result<(), AliciaUiRuntimeError> {
-        self.session_manager.cancel(session_id).await?;
-        self.store.unbind_session_input(session_id);
-        let finished_event = self
-            .wait_for_session_finished_event(session_id, Duration::from_secs(10))
-            .await
-            .ok_or_else(|| AliciaUiRuntimeError::SessionStopTimeout {
-                session_id: session_id.to_string(),
-            })?;
-        self.record_cancellation_audit(session_id, &finished_event)
-            .await?;
-        self.pump_events();
-        Ok(())
+    /// Rules from "always allow", each with the action id of the approval it came from.
+    pub fn take_requested_approval_rules(&mut self) -> Vec<(String, ApprovalRule)> {
+        std::mem::take(&mut self.requested_approval_rules)
     }
 
-    pub async fn bind_session_input(
-        &mut self,
-        session_id: &str,
-    ) -> Result<(), AliciaUiRuntimeError> {
-        let reattached = self.session_manager.reattach(session_id).await?;
-        self.store
-            .bind_session_input(session_id.to_string(), reattached.writer_tx);
-        Ok(())
+    pub fn revoke_remembered_command(&mut self, prefix: &[String]) -> bool {
+        if !self.remembered_approvals.revoke_command(prefix) {
+            return false;
+        }
+        self.remembered_approvals_changed = true;
+        self.push_approval_memory_entry(format!(
+            "approval_memory_revoked command={}",
+            prefix.join(" ")
+        ));
+        true
     }
 
-    pub fn send_input_to_active_session(
-        &self,
-        input: impl AsRef<[u8]>,
-    ) -> Result<(), UiEventStoreError> {
-        self.store.send_input_to_active_session(input)
+    pub fn revoke_remembered_target(&mut self, path: &str) -> bool {
+        if !self.remembered_approvals.revoke_target(path) {
+            return false;
+        }
+        self.remembered_approvals_changed = true;
+        self.push_approval_memory_entry(format!("approval_memory_revoked target={path}"));
+        true
     }
 
-    pub fn send_line_to_active_session(&self, line: &str) -> Result<(), UiEventStoreError> {
-        let mut payload = line.as_bytes().to_vec();
-        payload.push(b'\n');
-        self.store.send_input_to_active_session(payload)
+    fn push_approval_memory_entry(&mut self, summary: String) {
+        self.push_timeline(TimelineKind::Approval, None, summary);
     }
 
-    pub fn pump_events(&mut self) -> usize {
-        let mut processed = 0;
+    /// The remembered approvals when they changed since the last call, for the runtime to
+    /// save.
+    pub fn take_remembered_approvals_change(&mut self) -> Option<RememberedApprovals> {
+        std::mem::take(&mut self.remembered_approvals_changed)
+            .then(|| self.remembered_approvals.clone())
+    }
 
-        loop {
-            match self.events_rx.try_recv() {
-                Ok(message) => {
-                    self.store.push(message);
-                    processed += 1;
-                }
-                Err(tokio::sync::broadcast::error::TryRecvError::Lagged(_)) => continue,
-                Err(tokio::sync::broadcast::error::TryRecvError::Empty) => break,
-                Err(tokio::sync::broadcast::error::TryRecvError::Closed) => break,
+    /// Adds `path` as a context attachment for the next prompt. The file must resolve inside
+    /// `workspace_root`; attaching the same file twice is a no-op.
+    pub fn attach_context_file(
+        &mut self,
+        workspace_root: &Path,
+        path: &Path,
+    ) -> Result<ContextAttachment, UiEventStoreError> {
+        let display_path = path.to_string_lossy().to_string();
+        let guard = ensure_target_in_workspace(workspace_root, path).map_err(|err| {
+            UiEventStoreError::AttachmentOutsideWorkspace {
+                path: display_path.clone(),
+                reason: err.to_string(),
             }
+        })?;
+        let metadata = std::fs::metadata(&guard.canonical_target)
+            .ok()
+            .filter(std::fs::Metadata::is_file)
+            .ok_or_else(|| UiEventStoreError::AttachmentNotAFile(display_path))?;
+        let relative_path = guard
+            .canonical_target
+            .strip_prefix(&guard.canonical_workspace)
+            .unwrap_or(&guard.canonical_target)
+            .to_string_lossy()
+            .to_string();
+        let attachment = ContextAttachment {
+            path: relative_path,
+            size_bytes: metadata.len(),
+        };
+
+        if !self
+            .draft_attachments
+            .iter()
+            .any(|existing| existing.path == attachment.path)
+        {
+            self.push_timeline(
+                TimelineKind::Agent,
+                None,
+                format!(
+                    "context_attached {} bytes={}",
+                    attachment.path, attachment.size_bytes
+                ),
+            );
+            self.draft_attachments.push(attachment.clone());
         }
+        Ok(attachment)
+    }
 
-        processed
+    pub fn remove_context_attachment(&mut self, path: &str) -> bool {
+        let before = self.draft_attachments.len();
+        self.draft_attachments
+            .retain(|attachment| attachment.path != path);
+        let removed = self.draft_attachments.len() != before;
+        if removed {
+            self.push_timeline(
+                TimelineKind::Agent,
+                None,
+                format!("context_detached {path}"),
+            );
+        }
+        removed
     }
 
-    async fn record_blocked_audit(
+    pub fn draft_attachments(&self) -> &[ContextAttachment] {
+        &self.draft_attachments
+    }
+
+    /// Attaches every `@path` mentioned in `input`, resolved against `workspace_root`.
+    /// Returns the mentions that could not be attached.
+    pub fn attach_file_mentions(
         &mut self,
-        session_id: &str,
-        target: &str,
-        profile: PermissionProfile,
-        policy_decision: PolicyDecision,
-        approval_decision: ApprovalDecision,
-    ) -> Result<(), AliciaUiRuntimeError> {
+        workspace_root: &Path,
+        input: &str,
+    ) -> Vec<UiEventStoreError> {
+        parse_file_mentions(input)
+            .into_iter()
+            .filter_map(|mention| {
+                self.attach_context_file(workspace_root, &workspace_root.join(mention))
+                    .err()
+            })
+            .collect()
+    }
+
+    /// Audits the read of an attachment handed to the agent as a `ReadFile` action of the chat.
+    pub fn record_context_read(&mut self, path: &str, result_status: ResultStatus) {
+        let policy_decision = self.profile.decision_for_path(
+            &self.workspace_root,
+            Path::new(path),
+            ActionKind::ReadFile,
+        );
         let record = AuditRecord::new(
-            session_id,
-            ActionKind::ExecuteCommand,
-            target,
-            profile,
+            CHAT_AUDIT_SESSION_ID,
+            ActionKind::ReadFile,
+            path,
+            self.profile.base,
             policy_decision,
-            approval_decision,
-            ResultStatus::Blocked,
+            ApprovalDecision::NotRequired,
+            result_status,
             0,
         );
-        if let Some(audit_logger) = self.audit_logger.clone() {
-            audit_logger.append(&record).await.map_err(|source| {
-                AliciaUiRuntimeError::AuditWriteFailed {
-                    session_id: session_id.to_string(),
-                    source,
-                }
-            })?;
-        }
-        self.store.add_audit_record(record);
-        Ok(())
+        self.add_unpersisted_audit_record(record);
     }
 
-    async fn wait_for_session_finished_event(
-        &mut self,
-        session_id: &str,
-        timeout: Duration,
-    ) -> Option<CommandFinished> {
-        let deadline = tokio::time::Instant::now() + timeout;
+    /// Applies `message` from an IPC client when the client may send it: an agent event
+    /// that neither touches an action whose approval is already resolved nor reports on a
+    /// session the client did not announce. Anything else is noted in the timeline and
+    /// dropped. Returns whether it was applied.
+    pub fn push_from_ipc(&mut self, message: IpcMessage) -> bool {
+        let resolved_action = message.event.action_id().is_some_and(|action_id| {
+            self.approvals
+                .get(action_id)
+                .is_some_and(|approval| approval.status != ApprovalStatus::Pending)
+        });
+        let foreign_session = match &message.event {
+            IpcEvent::CommandStarted(event) => self.sessions.contains_key(&event.command_id),
+            event => event
+                .command_id()
+                .is_some_and(|command_id| !self.ipc_session_ids.contains(command_id)),
+        };
+        if !message.event.is_agent_event() || resolved_action || foreign_session {
+            self.record_rejected_ipc_message(&message);
+            return false;
+        }
+        if let IpcEvent::CommandStarted(event) = &message.event {
+            self.ipc_session_ids.insert(event.command_id.clone());
+        }
+        self.push(message);
+        true
+    }
 
-        loop {
-            let now = tokio::time::Instant::now();
-            if now >= deadline {
-                return None;
+    /// Notes an event an IPC client was not allowed to send; it is not applied.
+    pub fn record_rejected_ipc_message(&mut self, message: &IpcMessage) {
+        let summary = match &message.event {
+            IpcEvent::ApprovalResolved(event) => {
+                format!("ipc_rejected approval_resolved {}", event.action_id)
             }
-
-            let remaining = deadline.saturating_duration_since(now);
-            match tokio::time::timeout(remaining, self.events_rx.recv()).await {
-                Ok(Ok(message)) => {
-                    let mut finished = None;
-                    if let IpcEvent::CommandFinished(event) = &message.event
-                        && event.command_id == session_id
-                    {
-                        finished = Some(event.clone());
-                    }
-                    self.store.push(message);
-                    if finished.is_some() {
-                        return finished;
-                    }
-                }
-                Ok(Err(tokio::sync::broadcast::error::RecvError::Lagged(_))) => continue,
-                Ok(Err(tokio::sync::broadcast::error::RecvError::Closed)) => return None,
-                Err(_) => return None,
+            IpcEvent::PatchApplied(event) => {
+                format!("ipc_rejected patch_applied {}", event.action_id)
             }
-        }
+            IpcEvent::NetworkAccessBlocked(event) => {
+                format!("ipc_rejected network_access_blocked {}", event.command_id)
+            }
+            event => match event.action_id().or_else(|| event.command_id()) {
+                Some(id) => format!("ipc_rejected {id}"),
+                None => "ipc_rejected".to_string(),
+            },
+        };
+        self.push_timeline(TimelineKind::System, None, summary);
     }
 
-    async fn record_cancellation_audit(
+    pub fn record_code_block_copy(&mut self, message_id: u64, block: &CodeBlock) {
+        let preview = self.config.preview(&block.code);
+        self.push_timeline(
+            TimelineKind::Agent,
+            None,
+            format!(
+                "code_block_copied message={message_id} lang={} chars={} {}",
+                block.effective_language().unwrap_or("text"),
+                block.code.chars().count(),
+                preview.replace('\n', "\\n")
+            ),
+        );
+    }
+
+    pub fn record_spawn_attempt_failure(
         &mut self,
         session_id: &str,
-        finished_event: &CommandFinished,
-    ) -> Result<(), AliciaUiRuntimeError> {
-        let Some(audit_logger) = self.audit_logger.clone() else {
-            return Ok(());
+        attempt: u32,
+        max_attempts: u32,
+        error: &str,
+        retry_in: Option<Duration>,
+    ) {
+        let outcome = match retry_in {
+            Some(retry_in) => format!("retry_in_ms={}", retry_in.as_millis()),
+            None => String::from("giving_up"),
         };
+        self.push_timeline(
+            TimelineKind::Command,
+            Some(session_id),
+            format!(
+                "session_spawn_attempt_failed {session_id} attempt={attempt}/{max_attempts} {outcome} error={error}"
+            ),
+        );
+    }
 
-        let target = self
-            .store
-            .terminal_session(session_id)
-            .and_then(|session| {
-                if session.command.is_empty() {
-                    None
-                } else {
-                    Some(session.command.join(" "))
-                }
-            })
-            .unwrap_or_else(|| session_id.to_string());
-        let profile = self.store.permission_profile();
-        let policy_decision = profile.decision_for(ActionKind::ExecuteCommand);
-        let approval_decision = match policy_decision {
-            PolicyDecision::RequireApproval => ApprovalDecision::Approved,
-            PolicyDecision::Allow | PolicyDecision::Deny => ApprovalDecision::NotRequired,
-        };
-        let result_status = if finished_event.exit_code == 0 {
-            ResultStatus::Succeeded
-        } else {
-            ResultStatus::Failed
-        };
-        let record = AuditRecord::new(
-            session_id,
-            ActionKind::ExecuteCommand,
-            target,
-            profile,
-            policy_decision,
-            approval_decision,
-            result_status,
-            finished_event.duration_ms,
-        );
-        audit_logger.append(&record).await.map_err(|source| {
-            AliciaUiRuntimeError::AuditWriteFailed {
-                session_id: session_id.to_string(),
-                source,
-            }
-        })?;
-        self.store.add_audit_record(record);
-        Ok(())
+    pub fn audit_records(&self) -> &[AuditRecord] {
+        &self.audit_records
     }
-}
 
-#[derive(Debug, Default)]
-pub struct AliciaEguiView {
-    terminal_input_buffer: String,
-    status_message: Option<String>,
-}
+    /// Drains audit records generated by the store itself (patch applies and rejected hunks)
+    /// that still need to be written to the audit log.
+    pub fn take_unpersisted_audit_records(&mut self) -> Vec<AuditRecord> {
+        std::mem::take(&mut self.unpersisted_audit_records)
+    }
 
-impl AliciaEguiView {
-    pub fn render(&mut self, ctx: &egui::Context, store: &mut UiEventStore) -> Vec<IpcMessage> {
-        let pending_approvals: Vec<ApprovalItem> =
-            store.pending_approvals().into_iter().cloned().collect();
-        let unapplied_previews: Vec<PatchPreviewState> = store
-            .unapplied_diff_previews()
-            .into_iter()
-            .cloned()
-            .collect();
-        let timeline: Vec<TimelineEntry> = store.timeline().to_vec();
-        let session_ids = store.terminal_session_ids().to_vec();
-        let mut requested_resolutions: Vec<(String, ApprovalResolution)> = Vec::new();
-        let mut requested_hunk_decisions: Vec<(String, String, String, PatchHunkDecision)> =
-            Vec::new();
-        let mut emitted_messages = Vec::new();
+    pub fn audit_digest(&self, range: Range<i64>) -> AuditDigest {
+        AuditDigest::from_records(&self.audit_records, range)
+    }
 
-        egui::TopBottomPanel::top("alicia_status_bar").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                ui.label(format!(
-                    "Perfil ativo: {}",
-                    permission_profile_name(store.permission_profile())
-                ));
-                ui.separator();
-                ui.label(format!(
-                    "Aprovações pendentes: {}",
-                    store.pending_approval_count()
-                ));
-                if let Some(status_message) = self.status_message.as_deref() {
-                    ui.separator();
-                    ui.label(status_message);
-                }
-            });
-        });
+    pub fn audit_records_for_action(&self, action_id: &str) -> Vec<&AuditRecord> {
+        self.audit_records
+            .iter()
+            .filter(|record| record.action_id.as_deref() == Some(action_id))
+            .collect()
+    }
 
-        egui::SidePanel::right("alicia_approval_queue")
-            .resizable(true)
-            .default_width(340.0)
-            .show(ctx, |ui| {
-                ui.heading("Fila de Aprovações");
-                ui.separator();
+    pub fn permission_profile(&self) -> PermissionProfile {
+        self.profile.base
+    }
 
-                if pending_approvals.is_empty() {
-                    ui.label("Sem aprovações pendentes.");
-                } else {
-                    egui::ScrollArea::vertical().show(ui, |ui| {
-                        for approval in &pending_approvals {
-                            ui.group(|ui| {
-                                ui.label(format!("Ação: {}", approval.action_id));
-                                ui.label(format!("O que: {}", approval.summary));
+    pub fn set_permission_profile(&mut self, profile: PermissionProfile) {
+        self.profile = NamedProfile::builtin(profile);
+    }
 
-                                if let Some(action_kind) = approval.action_kind {
-                                    ui.label(format!("Tipo: {}", action_kind_name(action_kind)));
-                                }
+    /// The active profile, which may be a named profile from the project policy file.
+    pub fn profile(&self) -> &NamedProfile {
+        &self.profile
+    }
 
-                                if let Some(target) = approval.target.as_deref() {
-                                    ui.label(format!("Onde: {target}"));
-                                }
+    pub fn set_profile(&mut self, profile: NamedProfile) {
+        self.profile = profile;
+    }
 
-                                if let Some(command) = approval.command.as_ref() {
-                                    ui.label(format!("Comando: {}", command.join(" ")));
-                                }
+    /// Whether the profile was picked on the user's behalf; see
+    /// [`UiEventStore::change_permission_profile`].
+    pub fn profile_pinned(&self) -> bool {
+        self.profile_pinned
+    }
 
-                                if approval.impact_files.is_empty() {
-                                    ui.label("Impacto: sem diff informado");
-                                } else {
-                                    ui.label(format!(
-                                        "Impacto: {} arquivo(s)",
-                                        approval.impact_files.len()
-                                    ));
-                                    for file in &approval.impact_files {
-                                        ui.label(format!("- {file}"));
-                                    }
-                                }
+    /// Switches to the built-in `profile` on the user's behalf, recording the change in the
+    /// timeline and as a `WriteFile` audit record of `permission_profile`. Escalations to
+    /// `FullAccess` are audited as approved by the local approver. The profile is pinned: it
+    /// keeps precedence over the project policy of sessions started afterwards.
+    pub fn change_permission_profile(&mut self, profile: PermissionProfile) {
+        self.temporary_full_access = None;
+        self.profile_pinned = true;
+        if self.profile == NamedProfile::builtin(profile) {
+            return;
+        }
+        let previous = std::mem::replace(&mut self.profile, NamedProfile::builtin(profile));
+        self.record_profile_change(&previous.name, BTreeMap::new());
+    }
 
-                                ui.label(format!(
-                                    "Expira em unix={} (status: {})",
-                                    approval.expires_at_unix_s,
-                                    approval_status_name(approval.status)
-                                ));
+    /// Escalates to `FullAccess` until `duration` from `now_unix_s`, after which
+    /// [`UiEventStore::expire_temporary_full_access`] restores the current profile. Granting
+    /// again while a grant runs only moves its deadline. The grant is pinned like a selector
+    /// change, so project policies don't replace it while it runs.
+    pub fn grant_temporary_full_access(&mut self, duration: Duration, now_unix_s: i64) {
+        let expires_at_unix_s =
+            now_unix_s.saturating_add(i64::try_from(duration.as_secs()).unwrap_or(i64::MAX));
+        let current = std::mem::replace(
+            &mut self.profile,
+            NamedProfile::builtin(PermissionProfile::FullAccess),
+        );
+        let (previous, previous_pinned) = self.temporary_full_access.take().map_or_else(
+            || (current.clone(), self.profile_pinned),
+            |grant| (grant.previous, grant.previous_pinned),
+        );
+        self.profile_pinned = true;
+        let metadata = BTreeMap::from([
+            ("reason".to_string(), "temporary_full_access".to_string()),
+            (
+                "expires_at_unix_s".to_string(),
+                expires_at_unix_s.to_string(),
+            ),
+            ("restores_profile".to_string(), previous.name.clone()),
+        ]);
+        self.temporary_full_access = Some(TemporaryFullAccess {
+            previous,
+            previous_pinned,
+            expires_at_unix_s,
+        });
+        self.record_profile_change(&current.name, metadata);
+    }
 
-                                ui.horizontal(|ui| {
-                                    if ui.button("Aprovar").clicked() {
-                                        requested_resolutions.push((
-                                            approval.action_id.clone(),
-                                            ApprovalResolution::Approved,
-                                        ));
-                                    }
-                                    if ui.button("Rejeitar").clicked() {
-                                        requested_resolutions.push((
-                                            approval.action_id.clone(),
-                                            ApprovalResolution::Denied,
-                                        ));
-                                    }
-                                });
-                            });
-                            ui.separator();
-                        }
-                    });
-                }
-                ui.heading("Diff Preview");
-                ui.separator();
-                if unapplied_previews.is_empty() {
-                    ui.label("Nenhum diff pendente de aplicação.");
-                } else {
-                    egui::ScrollArea::vertical().show(ui, |ui| {
-                        for preview in &unapplied_previews {
-                            ui.group(|ui| {
-                                ui.label(format!("Ação: {}", preview.action_id));
-                                ui.label(format!("Arquivos: {}", preview.files.len()));
-                                if preview.file_previews.is_empty() {
-                                    for file in &preview.files {
-                                        ui.label(format!("- {file}"));
-                                    }
-                                } else {
-                                    for file_preview in &preview.file_previews {
-                                        ui.separator();
-                                        ui.label(format!("Arquivo: {}", file_preview.file_path));
+    /// When the temporary grant is due at `now_unix_s`, goes back to the profile it replaced.
+    /// Returns whether it did.
+    pub fn expire_temporary_full_access(&mut self, now_unix_s: i64) -> bool {
+        let Some(grant) = self
+            .temporary_full_access
+            .take_if(|grant| grant.expires_at_unix_s <= now_unix_s)
+        else {
+            return false;
+        };
+        let previous = std::mem::replace(&mut self.profile, grant.previous);
+        self.profile_pinned = grant.previous_pinned;
+        self.record_profile_change(
+            &previous.name,
+            BTreeMap::from([(
+                "reason".to_string(),
+                "temporary_full_access_expired".to_string(),
+            )]),
+        );
+        let restored = self.profile.name.clone();
+        self.push_chat_message(
+            ChatRole::System,
+            format!("Acesso total temporario expirou; perfil {restored} restaurado."),
+        );
+        true
+    }
 
-                                        if file_preview.hunks.is_empty() {
-                                            ui.label(
-                                                "Sem blocos (hunks) detalhados para este arquivo.",
-                                            );
-                                            continue;
-                                        }
+    /// Deadline of the running temporary `FullAccess` grant, for the countdown.
+    pub fn temporary_full_access_expires_at(&self) -> Option<i64> {
+        self.temporary_full_access
+            .as_ref()
+            .map(|grant| grant.expires_at_unix_s)
+    }
 
-                                        for hunk in &file_preview.hunks {
-                                            ui.group(|ui| {
-                                                ui.label(format!("Bloco: {}", hunk.hunk_id));
-                                                ui.label(hunk.header.as_str());
-                                                ui.label(format!(
-                                                    "Impacto: +{} / -{}",
-                                                    hunk.added_lines, hunk.removed_lines
-                                                ));
-                                                ui.label(format!(
-                                                    "Decisão: {}",
-                                                    patch_hunk_decision_name(hunk.decision)
-                                                ));
+    /// Records the switch from `previous` to the current profile in the timeline and as a
+    /// `WriteFile` audit record of `permission_profile`.
+    fn record_profile_change(&mut self, previous: &str, mut metadata: BTreeMap<String, String>) {
+        let profile = self.profile.base;
+        let summary = format!("profile_changed from={previous} to={}", self.profile.name);
+        let sequence = self.push_timeline(TimelineKind::System, None, summary);
+
+        let escalation = profile == PermissionProfile::FullAccess;
+        let session_id = self
+            .active_session_id
+            .clone()
+            .unwrap_or_else(|| CHAT_AUDIT_SESSION_ID.to_string());
+        metadata.insert("previous_profile".to_string(), previous.to_string());
+        let mut record = AuditRecord::new(
+            session_id,
+            ActionKind::WriteFile,
+            PROFILE_CHANGE_TARGET,
+            profile,
+            PolicyDecision::Allow,
+            if escalation {
+                ApprovalDecision::Approved
+            } else {
+                ApprovalDecision::NotRequired
+            },
+            ResultStatus::Succeeded,
+            0,
+        )
+        .with_event_sequence(sequence)
+        .with_metadata(metadata);
+        if escalation {
+            record.approver = self.approver_identity.clone();
+        }
+        self.add_unpersisted_audit_record(record);
+    }
 
-                                                ui.horizontal(|ui| {
-                                                    if ui.button("Aprovar bloco").clicked() {
-                                                        requested_hunk_decisions.push((
-                                                            preview.action_id.clone(),
-                                                            file_preview.file_path.clone(),
-                                                            hunk.hunk_id.clone(),
-                                                            PatchHunkDecision::Approved,
-                                                        ));
-                                                    }
-                                                    if ui.button("Rejeitar bloco").clicked() {
-                                                        requested_hunk_decisions.push((
-                                                            preview.action_id.clone(),
-                                                            file_preview.file_path.clone(),
-                                                            hunk.hunk_id.clone(),
-                                                            PatchHunkDecision::Rejected,
-                                                        ));
-                                                    }
-                                                });
-                                            });
-                                        }
-                                    }
-                                }
-                            });
-                            ui.separator();
-                        }
-                    });
-                }
-            });
+    /// Records a save of the project policy from the policy editor in the timeline and as a
+    /// `WriteFile` audit record of the policy file, approved by the current approver when
+    /// the saved policy grants full access.
+    pub fn record_policy_saved(&mut self, path: &Path, grants_full_access: bool) {
+        let target = path.display().to_string();
+        let summary = format!("policy_saved path={target} full_access={grants_full_access}");
+        let sequence = self.push_timeline(TimelineKind::System, None, summary);
+
+        let session_id = self
+            .active_session_id
+            .clone()
+            .unwrap_or_else(|| CHAT_AUDIT_SESSION_ID.to_string());
+        let mut record = AuditRecord::new(
+            session_id,
+            ActionKind::WriteFile,
+            target,
+            self.profile.base,
+            PolicyDecision::Allow,
+            if grants_full_access {
+                ApprovalDecision::Approved
+            } else {
+                ApprovalDecision::NotRequired
+            },
+            ResultStatus::Succeeded,
+            0,
+        )
+        .with_event_sequence(sequence)
+        .with_metadata(BTreeMap::from([
+            ("reason".to_string(), "policy_editor".to_string()),
+            (
+                "grants_full_access".to_string(),
+                grants_full_access.to_string(),
+            ),
+        ]));
+        if grants_full_access {
+            record.approver = self.approver_identity.clone();
+        }
+        self.add_unpersisted_audit_record(record);
+    }
 
-        egui::TopBottomPanel::bottom("alicia_timeline")
-            .resizable(true)
-            .default_height(200.0)
-            .show(ctx, |ui| {
-                ui.heading("Timeline");
-                ui.separator();
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    for entry in &timeline {
-                        ui.label(format!("#{} {}", entry.sequence, entry.summary));
-                    }
-                });
-            });
+    /// Queues a profile change for [`AliciaUiRuntime::apply_requested_profile_change`]; a
+    /// later request replaces an unapplied one.
+    pub fn request_profile_change(&mut self, profile: PermissionProfile) {
+        self.requested_profile = Some(profile);
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Terminal");
+    pub fn take_requested_profile_change(&mut self) -> Option<PermissionProfile> {
+        self.requested_profile.take()
+    }
 
-            if session_ids.is_empty() {
-                ui.label("Nenhuma sessão ativa.");
-            } else {
-                let previous_active = store.active_session_id().map(str::to_string);
-                let mut selected_session = previous_active
-                    .clone()
-                    .or_else(|| session_ids.first().cloned())
-                    .unwrap_or_default();
+    /// The panic switch: drops to a pinned `ReadOnly`, denies every pending approval that is
+    /// not a plain read and pauses the agent, auditing it all in one record. Until
+    /// [`UiEventStore::resume_agent`], the runtime starts no session and applies no patch.
+    /// Returns the denials, for the runtime to broadcast.
+    pub fn engage_panic_switch(&mut self) -> Vec<IpcMessage> {
+        self.temporary_full_access = None;
+        self.profile_pinned = true;
+        let previous = std::mem::replace(
+            &mut self.profile,
+            NamedProfile::builtin(PermissionProfile::ReadOnly),
+        );
+        let to_deny: Vec<String> = self
+            .pending_approval_ids
+            .iter()
+            .filter(|action_id| {
+                self.approvals
+                    .get(*action_id)
+                    .is_none_or(|approval| approval.action_kind != Some(ActionKind::ReadFile))
+            })
+            .cloned()
+            .collect();
+        let denied: Vec<IpcMessage> = to_deny
+            .iter()
+            .filter_map(|action_id| self.deny(action_id).ok())
+            .collect();
+        self.agent_paused = true;
 
-                egui::ComboBox::from_label("Sessão")
-                    .selected_text(selected_session.clone())
-                    .show_ui(ui, |ui| {
-                        for session_id in &session_ids {
-                            ui.selectable_value(
-                                &mut selected_session,
-                                session_id.clone(),
-                                session_id.as_str(),
-                            );
-                        }
-                    });
+        let summary = format!(
+            "panic_switch_engaged from={} denied_approvals={}",
+            previous.name,
+            denied.len()
+        );
+        let sequence = self.push_timeline(TimelineKind::System, None, summary);
+        let session_id = self
+            .active_session_id
+            .clone()
+            .unwrap_or_else(|| CHAT_AUDIT_SESSION_ID.to_string());
+        let mut record = AuditRecord::new(
+            session_id,
+            ActionKind::WriteFile,
+            PROFILE_CHANGE_TARGET,
+            PermissionProfile::ReadOnly,
+            PolicyDecision::Allow,
+            ApprovalDecision::NotRequired,
+            ResultStatus::Succeeded,
+            0,
+        )
+        .with_event_sequence(sequence)
+        .with_metadata(BTreeMap::from([
+            ("reason".to_string(), "panic_switch".to_string()),
+            ("previous_profile".to_string(), previous.name),
+            ("denied_approvals".to_string(), to_deny.join(",")),
+        ]));
+        record.approver = self.approver_identity.clone();
+        self.add_unpersisted_audit_record(record);
+        self.push_chat_message(
+            ChatRole::System,
+            format!(
+                "Botao de panico acionado: perfil read_only, {} aprovacoes negadas e agente pausado.",
+                denied.len()
+            ),
+        );
+        denied
+    }
 
-                if previous_active.as_deref() != Some(selected_session.as_str())
-                    && let Err(error) = store.set_active_session(&selected_session)
-                {
-                    self.status_message = Some(error.beginner_message());
-                }
+    pub fn is_agent_paused(&self) -> bool {
+        self.agent_paused
+    }
 
-                let mut terminal_text = store.active_terminal_text().unwrap_or_default();
-                ui.add(
-                    egui::TextEdit::multiline(&mut terminal_text)
-                        .font(egui::TextStyle::Monospace)
-                        .desired_rows(20)
-                        .interactive(false),
-                );
+    /// Lets `pump_agent` hand queued prompts to the agent again after the panic switch.
+    pub fn resume_agent(&mut self) {
+        self.agent_paused = false;
+    }
 
-                ui.horizontal(|ui| {
-                    let response = ui.text_edit_singleline(&mut self.terminal_input_buffer);
-                    let mut should_send = ui.button("Enviar").clicked();
-                    if response.lost_focus()
-                        && ui.input(|input| input.key_pressed(egui::Key::Enter))
-                    {
-                        should_send = true;
-                    }
-
-                    if should_send && !self.terminal_input_buffer.is_empty() {
-                        let mut payload = self.terminal_input_buffer.clone().into_bytes();
-                        payload.push(b'\n');
+    pub fn terminal_session_ids(&self) -> &[String] {
+        &self.session_order
+    }
 
-                        match store.send_input_to_active_session(payload) {
-                            Ok(()) => {
-                                self.terminal_input_buffer.clear();
-                                self.status_message =
-                                    Some(String::from("Input enviado para a sessão."));
-                            }
-                            Err(error) => {
-                                self.status_message = Some(error.beginner_message());
-                            }
-                        }
-                    }
-                });
-            }
-        });
+    /// Accent used for the session in the session list, the timeline and the comparison
+    /// window.
+    pub fn session_color(&self, session_id: &str) -> Option<egui::Color32> {
+        self.session_colors.get(session_id).copied()
+    }
 
-        for (action_id, resolution) in requested_resolutions {
-            match store.resolve_pending_approval(&action_id, resolution) {
-                Ok(message) => {
-                    emitted_messages.push(message);
-                    self.status_message = Some(format!(
-                        "Aprovação {} marcada como {}.",
-                        action_id,
-                        approval_resolution_name(resolution)
-                    ));
-                }
-                Err(error) => {
-                    self.status_message = Some(error.beginner_message());
-                }
-            }
-        }
+    /// The session a timeline entry is about: the one it was recorded for, or else the one its
+    /// summary names right after the event kind, like `command_started <session> ...`.
+    pub fn timeline_entry_session(&self, entry: &TimelineEntry) -> Option<&str> {
+        let session_id = entry
+            .session_id
+            .as_deref()
+            .or_else(|| entry.summary.split(' ').nth(1))?;
+        self.session_colors
+            .get_key_value(session_id)
+            .map(|(session_id, _)| session_id.as_str())
+    }
 
-        for (action_id, file_path, hunk_id, decision) in requested_hunk_decisions {
-            match store.set_patch_hunk_decision(&action_id, &file_path, &hunk_id, decision) {
-                Ok(()) => {
-                    self.status_message = Some(format!(
-                        "Bloco {} ({}) atualizado para {}.",
-                        hunk_id,
-                        file_path,
-                        patch_hunk_decision_name(decision)
-                    ));
-                }
-                Err(error) => {
-                    self.status_message = Some(error.beginner_message());
-                }
-            }
-        }
+    pub fn active_session_id(&self) -> Option<&str> {
+        self.active_session_id.as_deref()
+    }
 
-        if store.has_running_sessions() {
-            ctx.request_repaint_after(Duration::from_millis(33));
+    pub fn set_active_session(&mut self, session_id: &str) -> Result<(), UiEventStoreError> {
+        if !self.sessions.contains_key(session_id) {
+            return Err(UiEventStoreError::SessionNotFound(session_id.to_string()));
         }
 
-        emitted_messages
+        self.active_session_id = Some(session_id.to_string());
+        Ok(())
     }
-}
 
-fn command_target(program: &str, args: &[String], audit_target: &str) -> String {
-    if audit_target.is_empty() {
-        command_tokens(program, args).join(" ")
-    } else {
-        audit_target.to_string()
+    pub fn terminal_session(&self, session_id: &str) -> Option<&TerminalSessionState> {
+        self.sessions.get(session_id)
     }
-}
-
-fn command_tokens(program: &str, args: &[String]) -> Vec<String> {
-    let mut command = Vec::with_capacity(args.len() + 1);
-    command.push(program.to_string());
-    command.extend(args.iter().cloned());
-    command
-}
 
-fn selected_approval_decision(
-    requested_decision: ApprovalDecision,
-    store_decision: Option<ApprovalDecision>,
-) -> ApprovalDecision {
-    if let Some(store_decision) = store_decision {
-        store_decision
-    } else {
-        requested_decision
+    /// Lines of every session's output containing `query`, ignoring case, in session order
+    /// and capped at [`MAX_OUTPUT_SEARCH_HITS`].
+    pub fn search_all_output(&self, query: &str) -> Vec<OutputSearchHit> {
+        let mut hits = Vec::new();
+        if query.is_empty() {
+            return hits;
+        }
+        for session_id in &self.session_order {
+            let Some(session) = self.sessions.get(session_id) else {
+                continue;
+            };
+            if !search_lines(session_id, &session.visible_lines(), query, &mut hits) {
+                break;
+            }
+        }
+        hits
     }
-}
 
-fn combine_policy_decisions(
-    exec_decision: PolicyDecision,
-    network_decision: PolicyDecision,
-) -> PolicyDecision {
-    match (exec_decision, network_decision) {
-        (PolicyDecision::Deny, _) | (_, PolicyDecision::Deny) => PolicyDecision::Deny,
-        (PolicyDecision::RequireApproval, _) | (_, PolicyDecision::RequireApproval) => {
-            PolicyDecision::RequireApproval
-        }
-        (PolicyDecision::Allow, PolicyDecision::Allow) => PolicyDecision::Allow,
+    pub fn active_terminal_text(&self) -> Option<String> {
+        let active_session_id = self.active_session_id.as_ref()?;
+        let session = self.sessions.get(active_session_id)?;
+        Some(session.visible_text())
     }
-}
 
-fn effective_approval_decision(
-    policy_decision: PolicyDecision,
-    requested_approval_decision: ApprovalDecision,
-) -> ApprovalDecision {
-    match policy_decision {
-        PolicyDecision::Allow | PolicyDecision::Deny => ApprovalDecision::NotRequired,
-        PolicyDecision::RequireApproval => requested_approval_decision,
+    pub fn config(&self) -> StoreConfig {
+        self.config
     }
-}
 
-fn blocked_reason(
-    policy_decision: PolicyDecision,
-    approval_decision: ApprovalDecision,
-) -> Option<String> {
-    match policy_decision {
-        PolicyDecision::Allow => None,
-        PolicyDecision::Deny => Some(String::from("policy decision is deny")),
-        PolicyDecision::RequireApproval => match approval_decision {
-            ApprovalDecision::Approved => None,
-            ApprovalDecision::NotRequired => Some(String::from(
-                "approval required but no explicit decision was provided",
-            )),
-            ApprovalDecision::Denied => {
-                Some(String::from("approval required and was explicitly denied"))
-            }
-            ApprovalDecision::Expired => {
-                Some(String::from("approval required but the decision expired"))
-            }
-        },
+    /// Applies every limit of `config` to what the store already holds.
+    pub fn set_config(&mut self, config: StoreConfig) {
+        self.set_max_scrollback_lines(config.max_scrollback_lines);
+        self.set_max_scrollback_bytes(config.max_scrollback_bytes);
+        self.set_output_preview_max_columns(config.output_preview_max_columns);
+        self.set_max_timeline_entries(config.max_timeline_entries);
+        self.set_memory_budget_bytes(config.memory_budget_bytes);
+        self.config = self
+            .config
+            .with_max_approval_requests_per_minute(config.max_approval_requests_per_minute);
     }
-}
 
-fn action_kind_name(action_kind: ActionKind) -> &'static str {
-    match action_kind {
-        ActionKind::ReadFile => "read_file",
-        ActionKind::WriteFile => "write_file",
-        ActionKind::ExecuteCommand => "execute_command",
-        ActionKind::ApplyPatch => "apply_patch",
-        ActionKind::NetworkAccess => "network_access",
+    pub fn clear_terminal(&mut self, session_id: &str) -> Result<(), UiEventStoreError> {
+        let Some(session) = self.sessions.get_mut(session_id) else {
+            return Err(UiEventStoreError::SessionNotFound(session_id.to_string()));
+        };
+        session.clear();
+        if let Some(emulator) = &mut session.emulator {
+            emulator.process(CLEAR_EMULATOR_SCREEN);
+        }
+        self.push_timeline(
+            TimelineKind::Command,
+            Some(session_id),
+            format!("terminal_cleared {session_id}"),
+        );
+        Ok(())
     }
-}
 
-fn approval_resolution_name(resolution: ApprovalResolution) -> &'static str {
-    match resolution {
-        ApprovalResolution::Approved => "approved",
-        ApprovalResolution::Denied => "denied",
-        ApprovalResolution::Expired => "expired",
+    /// Backs `session_id` with a VT100 screen from its next output on. Sessions not started
+    /// yet get it when their `CommandStarted` arrives.
+    pub fn enable_terminal_emulation(&mut self, session_id: &str) {
+        let max_scrollback_lines = self.scrollback_limits(session_id).max_lines;
+        self.sessions
+            .entry(session_id.to_string())
+            .or_insert_with(|| TerminalSessionState::pending_session(session_id.to_string()))
+            .emulator
+            .get_or_insert_with(|| TerminalEmulator::new(max_scrollback_lines));
     }
-}
 
-fn approval_status_name(status: ApprovalStatus) -> &'static str {
-    match status {
-        ApprovalStatus::Pending => "pending",
-        ApprovalStatus::Approved => "approved",
-        ApprovalStatus::Denied => "denied",
-        ApprovalStatus::Expired => "expired",
+    /// Records how `session_id` was launched offline, for the terminal header and the
+    /// timeline.
+    pub fn set_network_isolation(&mut self, session_id: &str, isolation: NetworkIsolation) {
+        self.sessions
+            .entry(session_id.to_string())
+            .or_insert_with(|| TerminalSessionState::pending_session(session_id.to_string()))
+            .network_isolation = Some(isolation);
+        let summary = format!(
+            "network_isolation session={session_id} level={}",
+            isolation.name()
+        );
+        self.push_timeline(TimelineKind::Command, Some(session_id), summary);
     }
-}
 
-fn command_output_stream_name(stream: CommandOutputStream) -> &'static str {
-    match stream {
-        CommandOutputStream::Stdout => "stdout",
-        CommandOutputStream::Stderr => "stderr",
+    /// Queues a PTY resize when `session_id`'s screen is not `rows` x `columns` yet. Only the
+    /// latest size per session is kept.
+    pub fn request_terminal_resize(&mut self, session_id: &str, rows: u16, columns: u16) {
+        let Some(emulator) = self
+            .sessions
+            .get(session_id)
+            .and_then(TerminalSessionState::emulator)
+        else {
+            return;
+        };
+        self.requested_resizes
+            .retain(|(requested, _, _)| requested != session_id);
+        if emulator.size() != (rows, columns) {
+            self.requested_resizes
+                .push((session_id.to_string(), rows, columns));
+        }
     }
-}
 
-fn permission_profile_name(profile: PermissionProfile) -> &'static str {
-    match profile {
-        PermissionProfile::ReadOnly => "read_only",
-        PermissionProfile::ReadWriteWithApproval => "read_write_with_approval",
-        PermissionProfile::FullAccess => "full_access",
+    pub fn take_requested_resizes(&mut self) -> Vec<(String, u16, u16)> {
+        std::mem::take(&mut self.requested_resizes)
     }
-}
 
-fn policy_decision_name(policy_decision: PolicyDecision) -> &'static str {
-    match policy_decision {
-        PolicyDecision::Allow => "allow",
-        PolicyDecision::RequireApproval => "require_approval",
-        PolicyDecision::Deny => "deny",
+    /// Resizes `session_id`'s screen to match its PTY. Returns false for sessions without
+    /// terminal emulation.
+    pub fn resize_terminal(
+        &mut self,
+        session_id: &str,
+        rows: u16,
+        columns: u16,
+    ) -> Result<bool, UiEventStoreError> {
+        let Some(session) = self.sessions.get_mut(session_id) else {
+            return Err(UiEventStoreError::SessionNotFound(session_id.to_string()));
+        };
+        let Some(emulator) = &mut session.emulator else {
+            return Ok(false);
+        };
+        emulator.set_size(rows, columns);
+        Ok(true)
     }
-}
 
-fn approval_decision_name(approval_decision: ApprovalDecision) -> &'static str {
-    match approval_decision {
-        ApprovalDecision::NotRequired => "not_required",
-        ApprovalDecision::Approved => "approved",
-        ApprovalDecision::Denied => "denied",
-        ApprovalDecision::Expired => "expired",
+    pub fn set_terminal_wrap_mode(
+        &mut self,
+        session_id: &str,
+        wrap_mode: TerminalWrapMode,
+    ) -> Result<(), UiEventStoreError> {
+        let Some(session) = self.sessions.get_mut(session_id) else {
+            return Err(UiEventStoreError::SessionNotFound(session_id.to_string()));
+        };
+        session.wrap_mode = wrap_mode;
+        Ok(())
     }
-}
 
-fn result_status_name(result_status: ResultStatus) -> &'static str {
-    match result_status {
-        ResultStatus::Succeeded => "succeeded",
-        ResultStatus::Failed => "failed",
-        ResultStatus::Blocked => "blocked",
+    pub fn max_scrollback_lines(&self) -> usize {
+        self.config.max_scrollback_lines
     }
-}
 
-fn patch_hunk_decision_name(decision: PatchHunkDecision) -> &'static str {
-    match decision {
-        PatchHunkDecision::Pending => "pending",
-        PatchHunkDecision::Approved => "approved",
-        PatchHunkDecision::Rejected => "rejected",
+    pub fn set_max_scrollback_lines(&mut self, max_scrollback_lines: usize) {
+        self.config.max_scrollback_lines = max_scrollback_lines.max(1);
+        self.trim_scrollback();
     }
-}
 
-fn parse_hunk_range(raw: &str, prefix: char) -> Option<(usize, usize)> {
-    let raw = raw.strip_prefix(prefix)?;
-    let mut parts = raw.split(',');
-    let start = parts.next()?.parse::<usize>().ok()?;
-    let count = parts
-        .next()
-        .map_or(Some(1_usize), |value| value.parse::<usize>().ok())?;
-    Some((start, count))
-}
+    pub fn max_scrollback_bytes(&self) -> Option<usize> {
+        self.config.max_scrollback_bytes
+    }
 
-fn parse_unified_diff_hunks(unified_diff: &str) -> Vec<PatchHunkPreview> {
-    let mut hunks = Vec::new();
-    let mut current_hunk: Option<PatchHunkPreview> = None;
-    let mut hunk_index = 0_usize;
+    /// Caps the text kept per session on top of the line cap; `None` caps lines only.
+    pub fn set_max_scrollback_bytes(&mut self, max_scrollback_bytes: Option<usize>) {
+        self.config = self.config.with_max_scrollback_bytes(max_scrollback_bytes);
+        self.trim_scrollback();
+    }
 
-    for line in unified_diff.lines() {
-        if line.starts_with("@@") {
-            if let Some(previous) = current_hunk.take() {
-                hunks.push(previous);
+    /// Overrides both scrollback caps for `session_id`, trimming what it already holds;
+    /// `None` goes back to the configured ones.
+    pub fn set_session_scrollback_limits(
+        &mut self,
+        session_id: &str,
+        limits: Option<ScrollbackLimits>,
+    ) {
+        match limits {
+            Some(limits) => {
+                self.session_scrollback_limits
+                    .insert(session_id.to_string(), limits);
             }
-
-            let mut parts = line.split_whitespace();
-            if parts.next() != Some("@@") {
-                continue;
+            None => {
+                self.session_scrollback_limits.remove(session_id);
             }
+        }
+        self.trim_scrollback();
+    }
 
-            let Some(old_range) = parts.next() else {
-                continue;
-            };
-            let Some(new_range) = parts.next() else {
-                continue;
-            };
-
-            let Some((old_start, old_count)) = parse_hunk_range(old_range, '-') else {
-                continue;
-            };
-            let Some((new_start, new_count)) = parse_hunk_range(new_range, '+') else {
-                continue;
-            };
+    pub fn session_scrollback_limits(&self, session_id: &str) -> Option<ScrollbackLimits> {
+        self.session_scrollback_limits.get(session_id).copied()
+    }
 
-            hunk_index = hunk_index.saturating_add(1);
-            current_hunk = Some(PatchHunkPreview {
-                hunk_id: format!("hunk-{hunk_index}"),
-                header: line.to_string(),
-                old_start,
-                old_count,
-                new_start,
-                new_count,
-                added_lines: 0,
-                removed_lines: 0,
-                decision: PatchHunkDecision::Pending,
-            });
-            continue;
-        }
+    fn scrollback_limits(&self, session_id: &str) -> ScrollbackLimits {
+        self.session_scrollback_limits(session_id)
+            .unwrap_or_else(|| self.config.scrollback_limits())
+    }
 
-        if let Some(current_hunk) = current_hunk.as_mut() {
-            if line.starts_with('+') && !line.starts_with("+++") {
-                current_hunk.added_lines = current_hunk.added_lines.saturating_add(1);
-                continue;
-            }
-            if line.starts_with('-') && !line.starts_with("---") {
-                current_hunk.removed_lines = current_hunk.removed_lines.saturating_add(1);
-            }
+    fn trim_scrollback(&mut self) {
+        for (session_id, session) in &mut self.sessions {
+            let limits = self
+                .session_scrollback_limits
+                .get(session_id)
+                .copied()
+                .unwrap_or_else(|| self.config.scrollback_limits());
+            session.trim_scrollback_to(limits);
         }
     }
 
-    if let Some(previous) = current_hunk.take() {
-        hunks.push(previous);
+    /// Applies to timeline entries recorded from now on.
+    pub fn set_output_preview_max_columns(&mut self, output_preview_max_columns: usize) {
+        self.config.output_preview_max_columns = output_preview_max_columns;
     }
 
-    hunks
-}
+    pub fn set_max_timeline_entries(&mut self, max_timeline_entries: usize) {
+        self.config.max_timeline_entries = max_timeline_entries.max(1);
+        self.trim_timeline();
+    }
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
-    use std::path::PathBuf;
-    use std::time::Duration;
+    pub fn bind_session_input(
+        &mut self,
+        session_id: impl Into<String>,
+        writer: mpsc::Sender<Vec<u8>>,
+    ) {
+        self.session_input_writers.insert(session_id.into(), writer);
+    }
 
-    use codex_alicia_core::ActionKind;
-    use codex_alicia_core::ApprovalDecision;
-    use codex_alicia_core::IpcEvent;
-    use codex_alicia_core::IpcMessage;
-    use codex_alicia_core::PermissionProfile;
-    use codex_alicia_core::PolicyDecision;
-    use codex_alicia_core::ResultStatus;
-    use codex_alicia_core::SessionManager;
-    use codex_alicia_core::SessionManagerError;
-    use codex_alicia_core::SessionMode;
-    use codex_alicia_core::SessionStartRequest;
-    use codex_alicia_core::ipc::ActionProposed;
-    use codex_alicia_core::ipc::ApprovalRequested;
-    use codex_alicia_core::ipc::CommandOutputChunk;
-    use codex_alicia_core::ipc::CommandStarted;
-    use codex_alicia_core::ipc::PatchApplied;
-    use codex_alicia_core::ipc::PatchPreviewReady;
-    use pretty_assertions::assert_eq;
-    use tokio::sync::mpsc::error::TryRecvError;
+    pub fn unbind_session_input(&mut self, session_id: &str) {
+        self.session_input_writers.remove(session_id);
+    }
 
-    use super::AliciaUiRuntime;
-    use super::AliciaUiRuntimeError;
-    use super::ApprovalPrompt;
-    use super::ApprovalStatus;
-    use super::CommandLifecycle;
-    use super::PatchHunkDecision;
-    use super::UiEventStore;
-    use super::UiEventStoreError;
+    pub fn send_input_to_session(
+        &self,
+        session_id: &str,
+        input: impl AsRef<[u8]>,
+    ) -> Result<(), UiEventStoreError> {
+        let Some(writer) = self.session_input_writers.get(session_id) else {
+            return Err(UiEventStoreError::SessionInputNotBound(
+                session_id.to_string(),
+            ));
+        };
 
-    fn start_event(session_id: &str) -> IpcMessage {
-        IpcMessage::new(IpcEvent::CommandStarted(CommandStarted {
-            command_id: session_id.to_string(),
-            command: vec!["sh".to_string(), "-c".to_string(), "echo hi".to_string()],
-            cwd: ".".to_string(),
-        }))
+        writer.try_send(input.as_ref().to_vec()).map_err(|error| {
+            UiEventStoreError::SessionInputSendFailed {
+                session_id: session_id.to_string(),
+                reason: error.to_string(),
+            }
+        })
     }
 
-    fn shell_echo_input_command() -> (String, Vec<String>) {
-        if cfg!(windows) {
-            let cmd = std::env::var("COMSPEC").unwrap_or_else(|_| String::from("cmd.exe"));
-            let script = String::from("set /p ALICIA_INPUT=& echo !ALICIA_INPUT!");
-            (cmd, vec![String::from("/V:ON"), String::from("/C"), script])
+    pub fn send_input_to_active_session(
+        &self,
+        input: impl AsRef<[u8]>,
+    ) -> Result<(), UiEventStoreError> {
+        let Some(active_session_id) = self.active_session_id.as_deref() else {
+            return Err(UiEventStoreError::SessionNotFound(
+                "<active_session>".to_string(),
+            ));
+        };
+
+        self.send_input_to_session(active_session_id, input)
+    }
+
+    pub fn diff_preview(&self, action_id: &str) -> Option<&PatchPreviewState> {
+        self.patch_previews.get(action_id)
+    }
+
+    /// Every diff preview, applied or not, ordered by action id.
+    pub fn diff_previews(&self) -> Vec<&PatchPreviewState> {
+        let mut previews: Vec<&PatchPreviewState> = self.patch_previews.values().collect();
+        previews.sort_by(|left, right| left.action_id.cmp(&right.action_id));
+        previews
+    }
+
+    pub fn unapplied_diff_previews(&self) -> Vec<&PatchPreviewState> {
+        self.patch_previews
+            .values()
+            .filter(|preview| !preview.applied)
+            .collect()
+    }
+
+    pub fn attach_patch_file_diff(
+        &mut self,
+        action_id: &str,
+        file_path: impl Into<String>,
+        unified_diff: &str,
+    ) -> Result<usize, UiEventStoreError> {
+        let file_path = file_path.into();
+        let hunks = parse_unified_diff_hunks(unified_diff).map_err(|source| {
+            UiEventStoreError::MalformedPatchDiff {
+                action_id: action_id.to_string(),
+                file_path: file_path.clone(),
+                source,
+            }
+        })?;
+        let preview = self
+            .patch_previews
+            .get_mut(action_id)
+            .ok_or_else(|| UiEventStoreError::PatchPreviewNotFound(action_id.to_string()))?;
+
+        if !preview.files.iter().any(|file| file == &file_path) {
+            preview.files.push(file_path.clone());
+        }
+
+        if let Some(file_preview) = preview
+            .file_previews
+            .iter_mut()
+            .find(|file| file.file_path == file_path)
+        {
+            file_preview.hunks = hunks.clone();
         } else {
-            (
-                String::from("/bin/sh"),
-                vec![
-                    String::from("-c"),
-                    String::from("read ALICIA_INPUT; echo $ALICIA_INPUT"),
-                ],
-            )
+            preview.file_previews.push(PatchFilePreview {
+                file_path: file_path.clone(),
+                hunks: hunks.clone(),
+            });
         }
+
+        if let Some(approval) = self.approvals.get_mut(action_id)
+            && !approval.impact_files.iter().any(|file| file == &file_path)
+        {
+            approval.impact_files.push(file_path.clone());
+        }
+
+        self.push_timeline(
+            TimelineKind::Patch,
+            None,
+            format!(
+                "patch_hunks_loaded {} file={} hunks={}",
+                action_id,
+                file_path,
+                hunks.len()
+            ),
+        );
+
+        Ok(hunks.len())
     }
 
-    fn shell_echo_command(marker: &str) -> (String, Vec<String>) {
-        if cfg!(windows) {
-            let cmd = std::env::var("COMSPEC").unwrap_or_else(|_| String::from("cmd.exe"));
-            let script = format!("echo {marker}");
-            (cmd, vec![String::from("/C"), script])
-        } else {
-            (
-                String::from("/bin/sh"),
-                vec![String::from("-c"), format!("echo {marker}")],
-            )
+    pub fn set_patch_hunk_decision(
+        &mut self,
+        action_id: &str,
+        file_path: &str,
+        hunk_id: &str,
+        decision: PatchHunkDecision,
+    ) -> Result<(), UiEventStoreError> {
+        let approver = self.approver_identity.clone();
+        self.set_patch_hunk_decision_as(action_id, file_path, hunk_id, decision, approver)
+    }
+
+    /// Like [`UiEventStore::set_patch_hunk_decision`], on behalf of an explicit approver such
+    /// as a remote principal.
+    pub fn set_patch_hunk_decision_as(
+        &mut self,
+        action_id: &str,
+        file_path: &str,
+        hunk_id: &str,
+        decision: PatchHunkDecision,
+        approver: Option<ApproverIdentity>,
+    ) -> Result<(), UiEventStoreError> {
+        let preview = self
+            .patch_previews
+            .get_mut(action_id)
+            .ok_or_else(|| UiEventStoreError::PatchPreviewNotFound(action_id.to_string()))?;
+
+        let file_preview = preview
+            .file_previews
+            .iter_mut()
+            .find(|file| file.file_path == file_path)
+            .ok_or_else(|| UiEventStoreError::PatchFileNotFound {
+                action_id: action_id.to_string(),
+                file_path: file_path.to_string(),
+            })?;
+
+        let hunk = file_preview
+            .hunks
+            .iter_mut()
+            .find(|hunk| hunk.hunk_id == hunk_id)
+            .ok_or_else(|| UiEventStoreError::PatchHunkNotFound {
+                action_id: action_id.to_string(),
+                file_path: file_path.to_string(),
+                hunk_id: hunk_id.to_string(),
+            })?;
+
+        let previous_decision = std::mem::replace(&mut hunk.decision, decision);
+        let summary = format!(
+            "patch_hunk_decision {} file={} hunk={} decision={}",
+            action_id,
+            file_path,
+            hunk_id,
+            patch_hunk_decision_name(decision)
+        );
+        let decision_sequence = self.push_timeline(TimelineKind::Patch, None, summary);
+
+        if decision == PatchHunkDecision::Rejected && previous_decision != decision {
+            let mut record = self
+                .patch_audit_record(
+                    action_id,
+                    file_path,
+                    ApprovalDecision::Denied,
+                    ResultStatus::Blocked,
+                    decision_sequence,
+                )
+                .with_metadata(BTreeMap::from([(
+                    "hunk_id".to_string(),
+                    hunk_id.to_string(),
+                )]));
+            record.approver = approver;
+            self.add_unpersisted_audit_record(record);
         }
+
+        Ok(())
     }
 
-    fn inherited_env() -> HashMap<String, String> {
-        std::env::vars().collect()
+    pub fn approve_patch_hunk(
+        &mut self,
+        action_id: &str,
+        file_path: &str,
+        hunk_id: &str,
+    ) -> Result<(), UiEventStoreError> {
+        self.set_patch_hunk_decision(action_id, file_path, hunk_id, PatchHunkDecision::Approved)
     }
 
-    fn sample_unified_diff() -> &'static str {
-        "@@ -1,2 +1,3 @@\n-line_1\n+line_1_new\n line_2\n+line_3\n@@ -10,1 +11,2 @@\n-old_tail\n+new_tail_a\n+new_tail_b\n"
+    pub fn reject_patch_hunk(
+        &mut self,
+        action_id: &str,
+        file_path: &str,
+        hunk_id: &str,
+    ) -> Result<(), UiEventStoreError> {
+        self.set_patch_hunk_decision(action_id, file_path, hunk_id, PatchHunkDecision::Rejected)
     }
 
-    #[test]
-    fn stores_events_and_counts_pending_approvals() {
-        let mut store = UiEventStore::default();
+    /// Approves every hunk of `action_id`. Returns how many hunks changed decision.
+    pub fn approve_all_hunks(&mut self, action_id: &str) -> Result<usize, UiEventStoreError> {
+        self.set_bulk_hunk_decision(action_id, None, PatchHunkDecision::Approved)
+    }
 
-        store.push(start_event("cmd-1"));
-        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
-            ApprovalRequested {
-                action_id: "act-1".to_string(),
-                summary: "requires approval".to_string(),
-                expires_at_unix_s: 1_735_689_600,
-            },
-        )));
+    pub fn approve_all_hunks_in_file(
+        &mut self,
+        action_id: &str,
+        file_path: &str,
+    ) -> Result<usize, UiEventStoreError> {
+        self.set_bulk_hunk_decision(action_id, Some(file_path), PatchHunkDecision::Approved)
+    }
 
-        assert_eq!(store.events().len(), 2);
-        assert_eq!(store.pending_approval_count(), 1);
+    pub fn reject_all_hunks(&mut self, action_id: &str) -> Result<usize, UiEventStoreError> {
+        self.set_bulk_hunk_decision(action_id, None, PatchHunkDecision::Rejected)
     }
 
-    #[test]
-    fn terminal_scrollback_keeps_recent_lines() {
-        let mut store = UiEventStore::new(3);
-        store.push(start_event("cmd-scroll"));
-        store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
-            CommandOutputChunk {
-                command_id: "cmd-scroll".to_string(),
-                stream: codex_alicia_core::CommandOutputStream::Stdout,
-                chunk: "a\nb\nc\nd\n".to_string(),
-            },
-        )));
+    pub fn reject_all_hunks_in_file(
+        &mut self,
+        action_id: &str,
+        file_path: &str,
+    ) -> Result<usize, UiEventStoreError> {
+        self.set_bulk_hunk_decision(action_id, Some(file_path), PatchHunkDecision::Rejected)
+    }
 
-        let terminal = store.active_terminal_text();
-        let Some(terminal) = terminal else {
-            panic!("expected active terminal text");
-        };
+    /// Sets `decision` on every hunk of `action_id`, or only of `file_path`, with a single
+    /// timeline entry. Rejections are audited once per file, listing the hunks.
+    pub fn set_bulk_hunk_decision(
+        &mut self,
+        action_id: &str,
+        file_path: Option<&str>,
+        decision: PatchHunkDecision,
+    ) -> Result<usize, UiEventStoreError> {
+        let preview = self
+            .patch_previews
+            .get_mut(action_id)
+            .ok_or_else(|| UiEventStoreError::PatchPreviewNotFound(action_id.to_string()))?;
+        if let Some(file_path) = file_path
+            && !preview
+                .file_previews
+                .iter()
+                .any(|file| file.file_path == file_path)
+        {
+            return Err(UiEventStoreError::PatchFileNotFound {
+                action_id: action_id.to_string(),
+                file_path: file_path.to_string(),
+            });
+        }
+
+        let mut changed_hunks: Vec<(String, Vec<String>)> = Vec::new();
+        for file_preview in preview
+            .file_previews
+            .iter_mut()
+            .filter(|file| file_path.is_none_or(|file_path| file.file_path == file_path))
+        {
+            let mut hunk_ids = Vec::new();
+            for hunk in file_preview
+                .hunks
+                .iter_mut()
+                .filter(|hunk| hunk.decision != decision)
+            {
+                hunk.decision = decision;
+                hunk_ids.push(hunk.hunk_id.clone());
+            }
+            if !hunk_ids.is_empty() {
+                changed_hunks.push((file_preview.file_path.clone(), hunk_ids));
+            }
+        }
+
+        let changed = changed_hunks
+            .iter()
+            .map(|(_, hunk_ids)| hunk_ids.len())
+            .sum();
+        let summary = format!(
+            "patch_hunk_bulk_decision {} file={} hunks={} decision={}",
+            action_id,
+            file_path.unwrap_or("*"),
+            changed,
+            patch_hunk_decision_name(decision)
+        );
+        let decision_sequence = self.push_timeline(TimelineKind::Patch, None, summary);
+
+        if decision == PatchHunkDecision::Rejected {
+            for (file_path, hunk_ids) in changed_hunks {
+                let mut record = self
+                    .patch_audit_record(
+                        action_id,
+                        &file_path,
+                        ApprovalDecision::Denied,
+                        ResultStatus::Blocked,
+                        decision_sequence,
+                    )
+                    .with_metadata(BTreeMap::from([(
+                        "hunk_ids".to_string(),
+                        hunk_ids.join(","),
+                    )]));
+                record.approver = self.approver_identity.clone();
+                self.add_unpersisted_audit_record(record);
+            }
+        }
+
+        Ok(changed)
+    }
+
+    pub fn unresolved_patch_hunk_count(&self, action_id: &str) -> Option<usize> {
+        let preview = self.patch_previews.get(action_id)?;
+        Some(
+            preview
+                .file_previews
+                .iter()
+                .flat_map(|file| file.hunks.iter())
+                .filter(|hunk| hunk.decision == PatchHunkDecision::Pending)
+                .count(),
+        )
+    }
+}
+
+/// Retry schedule for transient spawn failures (EAGAIN, PTY exhaustion). The backoff doubles
+/// after each attempt, capped at `max_backoff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpawnRetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for SpawnRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl SpawnRetryPolicy {
+    pub fn no_retry() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    pub fn backoff_for(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(1_u32 << exponent)
+            .min(self.max_backoff)
+    }
+}
+
+/// Extra root a runtime may launch sessions in, governed by its own
+/// `.codex/alicia-policy.toml`. `fallback_profile` applies when the root has no policy file;
+/// `None` falls back to the profile currently in the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceRoot {
+    pub path: PathBuf,
+    pub fallback_profile: Option<PermissionProfile>,
+}
+
+impl WorkspaceRoot {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            fallback_profile: None,
+        }
+    }
+
+    pub fn with_fallback_profile(mut self, fallback_profile: PermissionProfile) -> Self {
+        self.fallback_profile = Some(fallback_profile);
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct AliciaUiRuntime {
+    session_manager: SessionManager,
+    /// `None` once the event task owns the subscription.
+    events_rx: Option<tokio::sync::broadcast::Receiver<IpcMessage>>,
+    store: SharedUiEventStore,
+    audit_logger: Option<AuditLogger>,
+    audit_metadata: BTreeMap<String, String>,
+    spawn_retry_policy: SpawnRetryPolicy,
+    session_stop_timeout: Duration,
+    workspace_root: PathBuf,
+    additional_workspace_roots: Vec<WorkspaceRoot>,
+    /// Profile each session was started under, resolved from the root containing its cwd.
+    session_profiles: HashMap<String, NamedProfile>,
+    editor_config: EditorConfig,
+    /// Start requests blocked only for lack of an approval, keyed by the approval action id.
+    parked_sessions: BTreeMap<String, SessionStartRequest>,
+    /// Workspace root of each parked start, kept while its approval is pending so "always
+    /// allow" rules land in that root's policy.
+    parked_roots: HashMap<String, PathBuf>,
+    agent: Option<AgentLoop>,
+    model_registry: ModelRegistry,
+    next_requested_session: u64,
+    /// When each agent tool call still waiting on its session was dispatched.
+    agent_tool_call_starts: HashMap<String, Instant>,
+    repaint: Option<RepaintCallback>,
+    /// Set when the session events stop reaching the store, by `pump_events` or the event
+    /// task.
+    event_loop_closed: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    health_checked_at: Option<Instant>,
+    /// Held while the approval expiry task should run; dropping it stops the task.
+    expiry_task_guard: Option<tokio::sync::mpsc::Receiver<()>>,
+    approval_events_tx: tokio::sync::broadcast::Sender<IpcMessage>,
+    /// Where `shutdown` saves the store's events.
+    snapshot_path: Option<PathBuf>,
+    recent_dirs_path: Option<PathBuf>,
+    recent_dirs: RecentDirs,
+    approval_memory_path: Option<PathBuf>,
+    approval_memory: ApprovalMemory,
+}
+
+impl AliciaUiRuntime {
+    pub fn new(session_manager: SessionManager, max_scrollback_lines: usize) -> Self {
+        Self::with_store_config(
+            session_manager,
+            StoreConfig::default().with_max_scrollback_lines(max_scrollback_lines),
+        )
+    }
+
+    pub fn with_store_config(session_manager: SessionManager, config: StoreConfig) -> Self {
+        let events_rx = Some(session_manager.event_receiver());
+        let workspace_root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let (approval_events_tx, _) = tokio::sync::broadcast::channel(APPROVAL_EVENTS_CAPACITY);
+        let mut store = UiEventStore::new(config);
+        store.set_event_channel_capacity(session_manager.event_capacity());
+        store.set_workspace_root(workspace_root.clone());
+        Self {
+            session_manager,
+            events_rx,
+            store: SharedUiEventStore::new(store),
+            audit_logger: None,
+            audit_metadata: BTreeMap::new(),
+            spawn_retry_policy: SpawnRetryPolicy::default(),
+            session_stop_timeout: DEFAULT_SESSION_STOP_TIMEOUT,
+            workspace_root,
+            additional_workspace_roots: Vec::new(),
+            session_profiles: HashMap::new(),
+            editor_config: EditorConfig::from_env(),
+            parked_sessions: BTreeMap::new(),
+            parked_roots: HashMap::new(),
+            agent: None,
+            model_registry: ModelRegistry::new(),
+            next_requested_session: 0,
+            agent_tool_call_starts: HashMap::new(),
+            repaint: None,
+            event_loop_closed: std::sync::Arc::default(),
+            health_checked_at: None,
+            expiry_task_guard: None,
+            approval_events_tx,
+            snapshot_path: None,
+            recent_dirs_path: None,
+            recent_dirs: RecentDirs::default(),
+            approval_memory_path: None,
+            approval_memory: ApprovalMemory::default(),
+        }
+    }
+
+    /// Called whenever state changed outside of `pump_events`, so the frontend shows it
+    /// without waiting for input.
+    pub fn with_repaint_callback(mut self, repaint: RepaintCallback) -> Self {
+        self.repaint = Some(repaint);
+        self
+    }
+
+    /// Starts a task that every `interval` expires the approvals and the temporary
+    /// `FullAccess` grant past their deadline in the shared store, broadcasts the
+    /// resolutions and asks the frontend to repaint, so expiry doesn't wait for
+    /// `pump_events`. The task ends when the runtime is dropped or the handle aborted;
+    /// calling this again replaces it.
+    pub fn spawn_approval_expiry_task(
+        &mut self,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let (guard_tx, guard_rx) = tokio::sync::mpsc::channel::<()>(1);
+        self.expiry_task_guard = Some(guard_rx);
+        let store = self.store.clone();
+        let approval_events_tx = self.approval_events_tx.clone();
+        let repaint = self.repaint.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                ticker.tick().await;
+                if guard_tx.is_closed() {
+                    break;
+                }
+                let now_unix_s = unix_now_s();
+                let (messages, restored) = {
+                    let mut store = store.write();
+                    store.expire_pending_approvals(now_unix_s);
+                    let restored = store.expire_temporary_full_access(now_unix_s);
+                    (store.take_unpublished_resolutions(), restored)
+                };
+                if messages.is_empty() && !restored {
+                    continue;
+                }
+                for message in messages {
+                    // Nobody listening is fine; the store already holds the resolution.
+                    let _ = approval_events_tx.send(message);
+                }
+                if let Some(repaint) = &repaint {
+                    repaint();
+                }
+            }
+        })
+    }
+
+    /// Starts a task that generates the audit digest whenever `schedule` fires and hands it
+    /// to each of `deliveries`. Each digest covers the records since the previous one; the
+    /// first covers everything the store holds. Failed deliveries land in the error center,
+    /// and webhooks are skipped while the profile denies network access. The task runs until
+    /// the handle is aborted or the schedule never fires again.
+    pub fn spawn_audit_digest_task(
+        &self,
+        schedule: DigestSchedule,
+        deliveries: Vec<DigestDelivery>,
+    ) -> tokio::task::JoinHandle<()> {
+        let store = self.store.clone();
+        tokio::spawn(async move {
+            let mut range_start = i64::MIN;
+            loop {
+                let now = unix_now_s();
+                let Some(fire_at) = schedule.next_after(now) else {
+                    break;
+                };
+                tokio::time::sleep(Duration::from_secs(
+                    u64::try_from(fire_at.saturating_sub(now)).unwrap_or(0),
+                ))
+                .await;
+                let digest = store.read().audit_digest(range_start..fire_at);
+                range_start = fire_at;
+                for delivery in &deliveries {
+                    let network_denied = matches!(delivery, DigestDelivery::Webhook(_))
+                        && store
+                            .read()
+                            .profile()
+                            .decision_for(ActionKind::NetworkAccess)
+                            == PolicyDecision::Deny;
+                    let result = if network_denied {
+                        Err(String::from("network access denied by the profile"))
+                    } else {
+                        deliver_audit_digest(&digest, delivery)
+                            .await
+                            .map_err(|error| error.to_string())
+                    };
+                    if let Err(error) = result {
+                        store.write().record_error(
+                            ErrorReport {
+                                code: "audit_digest_delivery_failed",
+                                message: beginner_error_message(
+                                    ui_language(),
+                                    BeginnerMessage::AuditDigestDeliveryFailed,
+                                    &format!("{}: {error}", delivery.kind_name()),
+                                ),
+                                suggested_actions: Vec::new(),
+                            },
+                            unix_now_s(),
+                        );
+                    }
+                }
+            }
+        })
+    }
+
+    /// Applies session events on a task of their own, so a burst of output does not stall the
+    /// caller inside `pump_events`. A reader moves events into a queue of `capacity`; when
+    /// it is full the reader waits and the broadcast channel absorbs the burst, dropping
+    /// events only once that lags too. The applier takes batches of the queue and asks the
+    /// frontend to repaint after each one. From then on `pump_events` no longer applies
+    /// events. Call it once; the tasks end when the session manager closes or the handle is
+    /// aborted.
+    pub fn spawn_event_task(&mut self, capacity: usize) -> tokio::task::JoinHandle<()> {
+        let capacity = capacity.max(1);
+        let mut events_rx = self
+            .events_rx
+            .take()
+            .unwrap_or_else(|| self.session_manager.event_receiver());
+        let (queue_tx, mut queue_rx) = tokio::sync::mpsc::channel(capacity);
+        let full_waits = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+
+        let reader_store = self.store.clone();
+        let reader_full_waits = std::sync::Arc::clone(&full_waits);
+        tokio::spawn(async move {
+            loop {
+                let message = match events_rx.recv().await {
+                    Ok(message) => message,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        reader_store
+                            .write()
+                            .record_subscriber_lag(EVENT_TASK_SUBSCRIBER, skipped);
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                let message = match queue_tx.try_send(message) {
+                    Ok(()) => continue,
+                    Err(tokio::sync::mpsc::error::TrySendError::Full(message)) => message,
+                    Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => break,
+                };
+                reader_full_waits.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                if queue_tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let store = self.store.clone();
+        let repaint = self.repaint.clone();
+        let event_loop_closed = std::sync::Arc::clone(&self.event_loop_closed);
+        tokio::spawn(async move {
+            let mut metrics = EventQueueMetrics {
+                capacity,
+                ..EventQueueMetrics::default()
+            };
+            let mut batch = Vec::with_capacity(EVENT_APPLY_BATCH);
+            while queue_rx.recv_many(&mut batch, EVENT_APPLY_BATCH).await > 0 {
+                let started = Instant::now();
+                metrics.depth = queue_rx.len();
+                metrics.max_depth = metrics.max_depth.max(metrics.depth + batch.len());
+                metrics.full_waits = full_waits.load(std::sync::atomic::Ordering::Relaxed);
+                metrics.applied = metrics.applied.saturating_add(batch.len() as u64);
+                {
+                    let mut store = store.write();
+                    for message in batch.drain(..) {
+                        store.push(message);
+                    }
+                    store.record_event_latency(started.elapsed());
+                    store.record_event_queue(metrics);
+                    store.enforce_memory_budget();
+                }
+                if let Some(repaint) = &repaint {
+                    repaint();
+                }
+            }
+            event_loop_closed.store(true, std::sync::atomic::Ordering::Relaxed);
+        })
+    }
+
+    /// Restores the sessions, approvals and patch previews journaled at `event_journal_path`
+    /// by a previous run and keeps journaling every event there.
+    pub fn with_event_journal_path(self, event_journal_path: PathBuf) -> Self {
+        let replayed = self.store.write().replay_journal(&event_journal_path);
+        if let Err(error) = replayed {
+            self.store.write().push_chat_message(
+                ChatRole::System,
+                beginner_error_message(
+                    ui_language(),
+                    BeginnerMessage::EventJournalRestoreFailed,
+                    &error.to_string(),
+                ),
+            );
+        }
+        self
+    }
+
+    /// Saves the store's events as JSONL at `snapshot_path` on `shutdown`.
+    pub fn with_snapshot_path(mut self, snapshot_path: PathBuf) -> Self {
+        self.snapshot_path = Some(snapshot_path);
+        self
+    }
+
+    /// Winds the runtime down within `timeout`: running sessions get a Ctrl-C and half the
+    /// time to exit before they are killed, pending approvals are expired (parked sessions
+    /// are dropped with them), the audit records still in memory are written and the events
+    /// are saved at the snapshot path. A failing step is reported and the rest still run.
+    pub async fn shutdown(&mut self, timeout: Duration) -> ShutdownReport {
+        let mut report = ShutdownReport::default();
+        let deadline = tokio::time::Instant::now() + timeout;
+        self.pump_events();
+
+        let running = self.running_session_ids();
+        for session_id in &running {
+            // Sessions without an input writer go straight to the kill below.
+            let _ = self
+                .store
+                .read()
+                .send_input_to_session(session_id, INTERRUPT_INPUT);
+        }
+        let still_running = self
+            .wait_for_sessions_to_finish(&running, tokio::time::Instant::now() + timeout / 2)
+            .await;
+        report.interrupted_sessions = running
+            .iter()
+            .filter(|session_id| !still_running.contains(session_id))
+            .cloned()
+            .collect();
+        for session_id in &still_running {
+            match self.session_manager.cancel(session_id).await {
+                Ok(()) => report.killed_sessions.push(session_id.clone()),
+                Err(error) => report.errors.push(error.to_string()),
+            }
+        }
+        report.unfinished_sessions = self
+            .wait_for_sessions_to_finish(&still_running, deadline)
+            .await;
+        report
+            .killed_sessions
+            .retain(|session_id| !report.unfinished_sessions.contains(session_id));
+
+        let pending: Vec<String> = self
+            .store
+            .read()
+            .pending_approvals()
+            .into_iter()
+            .map(|approval| approval.action_id.clone())
+            .collect();
+        for action_id in pending {
+            let expired = self.store.write().resolve_pending_approval_as(
+                &action_id,
+                ApprovalResolution::Expired,
+                None,
+            );
+            if expired.is_ok() {
+                report.expired_approvals += 1;
+            }
+        }
+        self.publish_resolutions();
+        self.parked_sessions.clear();
+
+        match self.flush_store_audit_records().await {
+            Ok(flushed) => report.flushed_audit_records = flushed,
+            Err(error) => report.errors.push(error.to_string()),
+        }
+
+        if let Some(path) = self.snapshot_path.clone() {
+            let written = write_event_snapshot(&path, self.store.read().events());
+            match written {
+                Ok(()) => report.snapshot_path = Some(path),
+                Err(error) => report.errors.push(format!(
+                    "failed to write snapshot `{}`: {error}",
+                    path.display()
+                )),
+            }
+        }
+        report
+    }
+
+    fn running_session_ids(&self) -> Vec<String> {
+        let store = self.store.read();
+        store
+            .terminal_session_ids()
+            .iter()
+            .filter(|session_id| {
+                store
+                    .terminal_session(session_id)
+                    .is_some_and(|session| matches!(session.lifecycle, CommandLifecycle::Running))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Sessions of `session_ids` still running at `deadline`.
+    async fn wait_for_sessions_to_finish(
+        &mut self,
+        session_ids: &[String],
+        deadline: tokio::time::Instant,
+    ) -> Vec<String> {
+        loop {
+            self.pump_events();
+            let running = self.running_session_ids();
+            let waiting: Vec<String> = session_ids
+                .iter()
+                .filter(|session_id| running.contains(session_id))
+                .cloned()
+                .collect();
+            if waiting.is_empty() || tokio::time::Instant::now() >= deadline {
+                return waiting;
+            }
+            tokio::time::sleep(EVENT_TASK_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Every `ApprovalResolved` of the store, whether the view, JSON-RPC/REST, an expiry or
+    /// the panic switch resolved it.
+    pub fn approval_event_receiver(&self) -> tokio::sync::broadcast::Receiver<IpcMessage> {
+        self.approval_events_tx.subscribe()
+    }
+
+    /// Broadcasts `message` to the approval event receivers and IPC clients. Resolutions
+    /// made through the store are broadcast by [`AliciaUiRuntime::pump_events`] already.
+    pub fn publish_ipc_message(&self, message: IpcMessage) {
+        let _ = self.approval_events_tx.send(message);
+    }
+
+    /// Broadcasts the resolutions the store made since the last call; returns how many.
+    fn publish_resolutions(&self) -> usize {
+        let messages = self.store.write().take_unpublished_resolutions();
+        let published = messages.len();
+        for message in messages {
+            // Nobody listening is fine; the store already holds the resolution.
+            let _ = self.approval_events_tx.send(message);
+        }
+        published
+    }
+
+    /// Serves `server` so an agent in another process drives the UI: the agent events it
+    /// sends go through [`UiEventStore::push_from_ipc`] as they arrive, and it receives the
+    /// approval resolutions the runtime broadcasts. Verdicts a client sends itself, like an
+    /// `ApprovalResolved`, and events about resolved actions or the runtime's own sessions
+    /// are dropped and noted in the timeline. The task ends when the endpoint fails or the handle
+    /// is aborted.
+    pub fn spawn_ipc_server(
+        &self,
+        server: IpcServer,
+    ) -> tokio::task::JoinHandle<Result<(), IpcTransportError>> {
+        let (incoming_tx, mut incoming_rx) = tokio::sync::mpsc::channel(IPC_INCOMING_CAPACITY);
+        let store = self.store.clone();
+        let repaint = self.repaint.clone();
+        let serving = server.serve(incoming_tx, self.approval_events_tx.clone());
+        tokio::spawn(async move {
+            tokio::pin!(serving);
+            loop {
+                tokio::select! {
+                    result = &mut serving => return result,
+                    Some(message) = incoming_rx.recv() => {
+                        store.write().push_from_ipc(message);
+                        if let Some(repaint) = &repaint {
+                            repaint();
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Expires approvals past their deadline and broadcasts the resolutions; returns how
+    /// many expired.
+    pub fn expire_approvals(&mut self, now_unix_s: i64) -> usize {
+        let expired = self
+            .store
+            .write()
+            .expire_pending_approvals(now_unix_s)
+            .len();
+        self.publish_resolutions();
+        expired
+    }
+
+    /// Answers chat prompts with `model`; see [`AliciaUiRuntime::pump_agent`].
+    pub fn with_agent_model(mut self, model: std::sync::Arc<dyn AgentModel>) -> Self {
+        self.store.write().selected_model = Some(model.name().to_string());
+        self.agent = Some(AgentLoop::new(model));
+        self
+    }
+
+    /// Answers chat prompts with an embedder's streaming backend: text deltas go to the chat
+    /// as they arrive and tool calls go through the same proposals as
+    /// [`AliciaUiRuntime::with_agent_model`].
+    pub fn with_model_bridge(self, bridge: std::sync::Arc<dyn ModelBridge>) -> Self {
+        self.with_agent_model(std::sync::Arc::new(BridgedAgentModel::new(bridge)))
+    }
+
+    /// Lets the user switch between `registry`'s models; the first one answers until then.
+    pub fn with_model_registry(mut self, registry: ModelRegistry) -> Self {
+        let descriptors = registry.descriptors();
+        for descriptor in &descriptors {
+            if let Some(pricing) = registry.pricing(&descriptor.id) {
+                self.store
+                    .write()
+                    .set_model_pricing(descriptor.id.clone(), pricing);
+            }
+        }
+        self.store.write().set_available_models(descriptors);
+        if let Some(model) = registry.default_model() {
+            self = self.with_agent_model(model);
+        }
+        self.model_registry = registry;
+        self
+    }
+
+    pub fn model_registry(&self) -> &ModelRegistry {
+        &self.model_registry
+    }
+
+    /// Applies the store's model selection to the agent.
+    fn sync_selected_model(&mut self) {
+        let Some(agent) = self.agent.as_mut() else {
+            return;
+        };
+        let Some(selected) = self.store.read().selected_model().map(str::to_string) else {
+            return;
+        };
+        if agent.model_name() == selected {
+            return;
+        }
+        if let Some(model) = self.model_registry.model(&selected) {
+            agent.set_model(model);
+        }
+    }
+
+    pub fn agent(&self) -> Option<&AgentLoop> {
+        self.agent.as_ref()
+    }
+
+    pub fn with_spawn_retry_policy(mut self, spawn_retry_policy: SpawnRetryPolicy) -> Self {
+        self.spawn_retry_policy = spawn_retry_policy;
+        self
+    }
+
+    pub fn with_session_stop_timeout(mut self, session_stop_timeout: Duration) -> Self {
+        self.session_stop_timeout = session_stop_timeout;
+        self
+    }
+
+    pub fn with_workspace_root(mut self, workspace_root: PathBuf) -> Self {
+        self.store
+            .write()
+            .set_workspace_root(workspace_root.clone());
+        self.workspace_root = workspace_root;
+        self.publish_recent_dirs();
+        self.publish_approval_memory();
+        self
+    }
+
+    /// Restores the recent working directories saved at `recent_dirs_path` and keeps
+    /// recording there the cwd of every session started.
+    pub fn with_recent_dirs_path(mut self, recent_dirs_path: PathBuf) -> Self {
+        match RecentDirs::load(&recent_dirs_path) {
+            Ok(recent_dirs) => self.recent_dirs = recent_dirs,
+            Err(error) => {
+                self.store.write().push_chat_message(
+                    ChatRole::System,
+                    beginner_error_message(
+                        ui_language(),
+                        BeginnerMessage::RecentDirsLoadFailed,
+                        &error.to_string(),
+                    ),
+                );
+            }
+        }
+        self.recent_dirs_path = Some(recent_dirs_path);
+        self.publish_recent_dirs();
+        self
+    }
+
+    fn publish_recent_dirs(&mut self) {
+        let recent_dirs = self.recent_dirs.for_workspace(&self.workspace_root);
+        self.store.write().set_recent_dirs(recent_dirs);
+    }
+
+    /// Records `cwd` among the main workspace's recent directories and saves them.
+    fn remember_recent_dir(&mut self, cwd: &Path) {
+        let Ok(guard) = ensure_target_in_workspace(&self.workspace_root, cwd) else {
+            return;
+        };
+        if !self
+            .recent_dirs
+            .record(&self.workspace_root, &guard.canonical_target)
+        {
+            return;
+        }
+        self.publish_recent_dirs();
+        let Some(recent_dirs_path) = self.recent_dirs_path.as_deref() else {
+            return;
+        };
+        if let Err(error) = self.recent_dirs.save(recent_dirs_path) {
+            self.store.write().push_chat_message(
+                ChatRole::System,
+                beginner_error_message(
+                    ui_language(),
+                    BeginnerMessage::RecentDirsSaveFailed,
+                    &error.to_string(),
+                ),
+            );
+        }
+    }
+
+    /// Restores the approvals remembered at `approval_memory_path` and saves there the ones
+    /// remembered or revoked from now on.
+    pub fn with_approval_memory_path(mut self, approval_memory_path: PathBuf) -> Self {
+        match ApprovalMemory::load(&approval_memory_path) {
+            Ok(approval_memory) => self.approval_memory = approval_memory,
+            Err(error) => {
+                self.store.write().push_chat_message(
+                    ChatRole::System,
+                    beginner_error_message(
+                        ui_language(),
+                        BeginnerMessage::ApprovalMemoryLoadFailed,
+                        &error.to_string(),
+                    ),
+                );
+            }
+        }
+        self.approval_memory_path = Some(approval_memory_path);
+        self.publish_approval_memory();
+        self
+    }
+
+    fn publish_approval_memory(&mut self) {
+        let remembered = self.approval_memory.for_workspace(&self.workspace_root);
+        self.store.write().set_remembered_approvals(remembered);
+    }
+
+    /// Saves the main workspace's remembered approvals when the store changed them.
+    fn save_approval_memory(&mut self) {
+        let Some(remembered) = self.store.write().take_remembered_approvals_change() else {
+            return;
+        };
+        self.approval_memory
+            .set_workspace(&self.workspace_root, remembered);
+        let Some(approval_memory_path) = self.approval_memory_path.as_deref() else {
+            return;
+        };
+        if let Err(error) = self.approval_memory.save(approval_memory_path) {
+            self.store.write().push_chat_message(
+                ChatRole::System,
+                beginner_error_message(
+                    ui_language(),
+                    BeginnerMessage::ApprovalMemorySaveFailed,
+                    &error.to_string(),
+                ),
+            );
+        }
+    }
+
+    /// Appends the rules from "always allow" to the project policy of the root the approved
+    /// session would run under (the main workspace for approvals of no parked start). A
+    /// missing policy file is created with `ReadWriteWithApproval`, never with the live
+    /// profile, which may be a temporary escalation.
+    pub fn save_requested_approval_rules(&mut self) {
+        let requested = self.store.write().take_requested_approval_rules();
+        let mut rules_by_root: BTreeMap<PathBuf, Vec<ApprovalRule>> = BTreeMap::new();
+        for (action_id, rule) in requested {
+            let root = self
+                .parked_roots
+                .get(&action_id)
+                .cloned()
+                .unwrap_or_else(|| self.workspace_root.clone());
+            rules_by_root.entry(root).or_default().push(rule);
+        }
+        {
+            let store = self.store.read();
+            self.parked_roots.retain(|action_id, _| {
+                store
+                    .approval(action_id)
+                    .is_some_and(|approval| approval.status == ApprovalStatus::Pending)
+            });
+        }
+        for (root, rules) in rules_by_root {
+            let saved = load_project_policy(&root).and_then(|config| {
+                let mut config = config.unwrap_or_else(|| {
+                    ProjectPolicyConfig::new(PermissionProfile::ReadWriteWithApproval)
+                });
+                let mut added = false;
+                for rule in rules {
+                    added |= config.approval_rules.add(rule);
+                }
+                if added {
+                    save_project_policy(&root, &config)?;
+                }
+                Ok(())
+            });
+            if let Err(error) = saved {
+                self.store.write().push_chat_message(
+                    ChatRole::System,
+                    beginner_error_message(
+                        ui_language(),
+                        BeginnerMessage::ApprovalRuleSaveFailed,
+                        &error.to_string(),
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Allows sessions under another root, e.g. a sibling service checked out next to the
+    /// main workspace. Nested roots govern their own subtree.
+    pub fn with_additional_workspace_root(mut self, workspace_root: WorkspaceRoot) -> Self {
+        self.additional_workspace_roots.push(workspace_root);
+        self
+    }
+
+    /// Main workspace first, then the additional roots.
+    pub fn workspace_roots(&self) -> Vec<PathBuf> {
+        std::iter::once(self.workspace_root.clone())
+            .chain(
+                self.additional_workspace_roots
+                    .iter()
+                    .map(|root| root.path.clone()),
+            )
+            .collect()
+    }
+
+    /// Profile a session was started under, falling back to the store's current profile.
+    pub fn session_profile(&self, session_id: &str) -> PermissionProfile {
+        self.session_named_profile(session_id).base
+    }
+
+    pub fn session_named_profile(&self, session_id: &str) -> NamedProfile {
+        self.session_profiles
+            .get(session_id)
+            .cloned()
+            .unwrap_or_else(|| self.store.read().profile().clone())
+    }
+
+    fn root_fallback_profile(&self, canonical_root: &Path) -> Option<PermissionProfile> {
+        self.additional_workspace_roots
+            .iter()
+            .find(|root| std::fs::canonicalize(&root.path).is_ok_and(|path| path == canonical_root))
+            .and_then(|root| root.fallback_profile)
+    }
+
+    /// Profile governing `canonical_root` and its policy file: read-only under the panic
+    /// switch, then a pinned selector profile, the root's policy file, the root's fallback
+    /// profile and finally the store's profile. The store's profile is never changed here, so
+    /// each root keeps its own rules.
+    fn resolve_root_profile(
+        &self,
+        canonical_root: &Path,
+    ) -> Result<(NamedProfile, Option<ProjectPolicyConfig>), AliciaUiRuntimeError> {
+        let project_policy = load_project_policy(canonical_root).map_err(|source| {
+            AliciaUiRuntimeError::ResolveProfileFailed {
+                workspace: canonical_root.to_string_lossy().to_string(),
+                source,
+            }
+        })?;
+        let store = self.store.read();
+        let named_profile = if store.is_agent_paused() {
+            NamedProfile::builtin(PermissionProfile::ReadOnly)
+        } else if store.profile_pinned() {
+            store.profile().clone()
+        } else {
+            project_policy
+                .as_ref()
+                .and_then(ProjectPolicyConfig::resolve_profile)
+                .unwrap_or_else(|| {
+                    self.root_fallback_profile(canonical_root)
+                        .map_or_else(|| store.profile().clone(), NamedProfile::builtin)
+                })
+        };
+        Ok((named_profile, project_policy))
+    }
+
+    /// Profile of the main workspace root; an unreadable policy file falls back to read-only.
+    fn workspace_profile(&self) -> NamedProfile {
+        self.resolve_root_profile(&self.workspace_root)
+            .map(|(named_profile, _)| named_profile)
+            .unwrap_or_else(|_| NamedProfile::builtin(PermissionProfile::ReadOnly))
+    }
+
+    pub fn with_audit_logger(mut self, audit_logger: AuditLogger) -> Self {
+        self.audit_logger = Some(audit_logger);
+        self
+    }
+
+    pub fn with_editor_config(mut self, editor_config: EditorConfig) -> Self {
+        self.editor_config = editor_config;
+        self
+    }
+
+    /// Opens a workspace file in the configured editor; paths escaping the workspace are
+    /// refused.
+    pub fn open_in_editor(&self, location: &EditorLocation) -> Result<PathBuf, EditorError> {
+        open_in_editor(&self.workspace_root, &self.editor_config, location)
+    }
+
+    /// Proposes the `NetworkAccess` action that gates opening `draft` and, when the profile
+    /// requires it, asks for its approval. Returns the network action id.
+    pub fn request_pull_request(
+        &mut self,
+        draft: &PullRequestDraft,
+        provider: &PullRequestProvider,
+    ) -> String {
+        let action_id = draft.network_action_id();
+        self.store
+            .write()
+            .push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+                action_id: action_id.clone(),
+                action_kind: ActionKind::NetworkAccess,
+                target: provider.api_base().to_string(),
+            })));
+        if self
+            .workspace_profile()
+            .decision_for(ActionKind::NetworkAccess)
+            == PolicyDecision::RequireApproval
+        {
+            self.store
+                .write()
+                .push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                    ApprovalRequested {
+                        action_id: action_id.clone(),
+                        summary: format!("Abrir PR {}", draft.title),
+                        expires_at_unix_s: unix_now_s().saturating_add(PULL_REQUEST_APPROVAL_TTL_S),
+                    },
+                )));
+        }
+        action_id
+    }
+
+    /// Pushes `HEAD` to the draft branch and opens the PR once the network action from
+    /// `request_pull_request` is allowed by the profile or approved. The outcome is audited.
+    pub async fn create_pull_request(
+        &mut self,
+        draft: &PullRequestDraft,
+        provider: &PullRequestProvider,
+        token: &str,
+    ) -> Result<String, PullRequestError> {
+        let action_id = draft.network_action_id();
+        let network_decision = self
+            .workspace_profile()
+            .decision_for(ActionKind::NetworkAccess);
+        match network_decision {
+            PolicyDecision::Allow => {}
+            PolicyDecision::Deny => {
+                self.store.write().record_pull_request_audit(
+                    &action_id,
+                    provider.api_base(),
+                    ResultStatus::Blocked,
+                );
+                return Err(PullRequestError::NetworkDenied(action_id));
+            }
+            PolicyDecision::RequireApproval => {
+                let approved = self
+                    .store
+                    .read()
+                    .approval(&action_id)
+                    .is_some_and(|approval| approval.status == ApprovalStatus::Approved);
+                if !approved {
+                    return Err(PullRequestError::NotApproved(action_id));
+                }
+            }
+        }
+
+        let opened = match push_branch(&self.workspace_root, "origin", &draft.branch) {
+            Ok(()) => open_pull_request(provider, token, draft).await,
+            Err(error) => Err(error),
+        };
+        match &opened {
+            Ok(url) => self.store.write().record_pull_request_audit(
+                &action_id,
+                url,
+                ResultStatus::Succeeded,
+            ),
+            Err(_) => self.store.write().record_pull_request_audit(
+                &action_id,
+                provider.api_base(),
+                ResultStatus::Failed,
+            ),
+        }
+        opened
+    }
+
+    pub fn store(&self) -> RwLockReadGuard<'_, UiEventStore> {
+        self.store.read()
+    }
+
+    pub fn store_mut(&mut self) -> RwLockWriteGuard<'_, UiEventStore> {
+        self.store.write()
+    }
+
+    /// Handle for other frontends (a TUI, the JSON-RPC server) to observe and drive the store
+    /// this runtime feeds.
+    pub fn shared_store(&self) -> SharedUiEventStore {
+        self.store.clone()
+    }
+
+    pub fn session_manager(&self) -> &SessionManager {
+        &self.session_manager
+    }
+
+    pub async fn start_session(
+        &mut self,
+        request: SessionStartRequest,
+    ) -> Result<(), AliciaUiRuntimeError> {
+        self.start_session_with_cancellation(request, &CancellationToken::new())
+            .await
+    }
+
+    /// Like `start_session`, but gives up with `OperationCancelled` once `cancellation` is
+    /// cancelled, e.g. because the user closed the tab while spawn retries were backing off.
+    /// A spawn attempt already under way still completes.
+    #[instrument(
+        name = "alicia.start_session",
+        skip_all,
+        fields(session_id = %request.session_id, program = %request.program),
+        err
+    )]
+    pub async fn start_session_with_cancellation(
+        &mut self,
+        request: SessionStartRequest,
+        cancellation: &CancellationToken,
+    ) -> Result<(), AliciaUiRuntimeError> {
+        let mut request = request;
+        let session_id = request.session_id.clone();
+        let command = command_tokens(&request.program, &request.args);
+        let command_target = command_target(
+            &request.program,
+            &request.args,
+            request.audit_context.target.as_str(),
+        );
+        let guard = ensure_target_in_workspace_roots(&self.workspace_roots(), &request.cwd)
+            .map_err(|source| AliciaUiRuntimeError::WorkspaceGuardBlocked {
+                session_id: session_id.clone(),
+                cwd: request.cwd.to_string_lossy().to_string(),
+                source,
+            })?;
+        request.cwd = guard.canonical_target;
+        let session_root = guard.canonical_workspace;
+        // A temporary grant past its deadline must not decide this start.
+        self.revert_expired_full_access().await?;
+
+        let (named_profile, project_policy) = self.resolve_root_profile(&session_root)?;
+        let locked_down = self.store.read().is_agent_paused();
+        let effective_profile = named_profile.base;
+        self.audit_metadata = project_policy
+            .as_ref()
+            .map_or_else(BTreeMap::new, ProjectPolicyConfig::resolve_audit_metadata);
+        if let Some(config) = project_policy.as_ref() {
+            self.store
+                .write()
+                .set_approval_validity(config.approval_validity.clone());
+        }
+        self.session_profiles
+            .insert(session_id.clone(), named_profile.clone());
+
+        let network_decision = named_profile.decision_for(ActionKind::NetworkAccess);
+        let network_isolation =
+            (network_decision == PolicyDecision::Deny).then(available_network_isolation);
+        let policy_decision = if locked_down {
+            PolicyDecision::Deny
+        } else {
+            named_profile
+                .command_rule_decision(&command)
+                .unwrap_or_else(|| {
+                    classify_command(&command)
+                        .into_iter()
+                        .map(|action_kind| named_profile.decision_for(action_kind))
+                        .fold(PolicyDecision::Allow, combine_policy_decisions)
+                })
+        };
+        // Blackholed proxies leave programs that ignore them online, so without a network
+        // namespace the launch fails closed: it needs an approval even where the profile
+        // would allow it.
+        let policy_decision = match network_isolation {
+            Some(isolation) if !isolation.is_enforced() => {
+                combine_policy_decisions(policy_decision, PolicyDecision::RequireApproval)
+            }
+            Some(_) | None => policy_decision,
+        };
+        let store_approval = self.store.read().resolved_approval_for_command(&command);
+        let approval_in_use = store_approval
+            .as_ref()
+            .filter(|link| link.decision == ApprovalDecision::Approved)
+            .map(|link| link.action_id.clone());
+        let approval_rule = store_approval
+            .is_none()
+            .then(|| {
+                project_policy
+                    .as_ref()
+                    .and_then(|config| config.approval_rules.matching_rule(&command))
+                    .map(|rule| rule.pattern.clone())
+            })
+            .flatten();
+        let remembered_prefix = (store_approval.is_none() && approval_rule.is_none())
+            .then(|| {
+                self.store
+                    .read()
+                    .remembered_approvals()
+                    .command_match(&command)
+                    .map(|remembered| remembered.prefix.join(" "))
+            })
+            .flatten();
+        let requested_approval_decision = selected_approval_decision(
+            request.audit_context.approval_decision,
+            store_approval
+                .as_ref()
+                .map(|link| link.decision)
+                .or(approval_rule
+                    .as_ref()
+                    .or(remembered_prefix.as_ref())
+                    .map(|_| ApprovalDecision::Approved)),
+        );
+        let approval_decision =
+            effective_approval_decision(policy_decision, requested_approval_decision);
+        let mut metadata = self.audit_metadata.clone();
+        if locked_down {
+            metadata.insert("reason".to_string(), "panic_switch".to_string());
+        }
+        if let Some(isolation) = network_isolation {
+            metadata.insert(
+                "network_isolation".to_string(),
+                isolation.name().to_string(),
+            );
+        }
+        if let Some(approval_rule) = approval_rule {
+            metadata.insert("approval_rule".to_string(), approval_rule);
+        }
+        if let Some(remembered_prefix) = remembered_prefix {
+            metadata.insert("remembered_approval".to_string(), remembered_prefix);
+        }
+        if let Some(original_command) = store_approval.as_ref().and_then(|link| {
+            self.store
+                .read()
+                .approval(&link.action_id)
+                .and_then(|approval| approval.original_command.clone())
+        }) {
+            metadata.insert("original_command".to_string(), original_command.join(" "));
+            metadata.insert("modified_command".to_string(), command.join(" "));
+        }
+        let (action_id, event_sequence, approver) = match store_approval {
+            Some(link) => (Some(link.action_id), link.event_sequence, link.approver),
+            None => (
+                request.audit_context.action_id.take(),
+                request.audit_context.event_sequence,
+                request.audit_context.approver.take(),
+            ),
+        };
+
+        request.audit_context = SessionAuditContext {
+            action_kind: ActionKind::ExecuteCommand,
+            target: command_target,
+            profile: effective_profile,
+            profile_name: named_profile.custom_name().map(str::to_string),
+            policy_decision,
+            approval_decision,
+            action_id,
+            event_sequence,
+            approver,
+            metadata,
+        };
+        request.network_disabled = network_decision == PolicyDecision::Deny;
+
+        if let Some(reason) = blocked_reason(policy_decision, approval_decision) {
+            self.record_blocked_audit(&session_id, &request.audit_context)
+                .await?;
+            if policy_decision == PolicyDecision::RequireApproval
+                && approval_decision == ApprovalDecision::NotRequired
+            {
+                self.park_for_approval(request, command, session_root);
+            }
+            return Err(AliciaUiRuntimeError::CommandBlocked { session_id, reason });
+        }
+
+        let max_attempts = self.spawn_retry_policy.max_attempts.max(1);
+        let mut failures = Vec::new();
+        let mut attempt = 1;
+        loop {
+            if cancellation.is_cancelled() {
+                return Err(AliciaUiRuntimeError::OperationCancelled { session_id });
+            }
+            let error = match self.session_manager.start(request.clone()).await {
+                Ok(()) => break,
+                Err(error) => error,
+            };
+            let retry_in = (error.is_transient() && attempt < max_attempts)
+                .then(|| self.spawn_retry_policy.backoff_for(attempt));
+            self.store.write().record_spawn_attempt_failure(
+                &session_id,
+                attempt,
+                max_attempts,
+                &error.to_string(),
+                retry_in,
+            );
+            let Some(retry_in) = retry_in else {
+                if failures.is_empty() {
+                    return Err(error.into());
+                }
+                failures.push(error.to_string());
+                return Err(AliciaUiRuntimeError::SpawnRetriesExhausted {
+                    session_id,
+                    attempts: attempt,
+                    failures,
+                });
+            };
+            failures.push(error.to_string());
+            tokio::select! {
+                () = tokio::time::sleep(retry_in) => {}
+                () = cancellation.cancelled() => {
+                    return Err(AliciaUiRuntimeError::OperationCancelled { session_id });
+                }
+            }
+            attempt += 1;
+        }
+        if let Some(action_id) = approval_in_use {
+            self.store.write().record_approval_use(&action_id);
+        }
+        if let Some(isolation) = network_isolation {
+            self.store
+                .write()
+                .set_network_isolation(&session_id, isolation);
+        }
+        if matches!(request.mode, SessionMode::Pty) {
+            self.store.write().enable_terminal_emulation(&session_id);
+        }
+        self.bind_session_input(&session_id).await?;
+        self.pump_events();
+        Ok(())
+    }
+
+    /// Checks `ApplyPatch` against the current profile before a patch touches the workspace,
+    /// per file with the profile's path rules, the strictest file deciding: `Deny` always
+    /// blocks and `RequireApproval` needs the patch action's approval to be granted. Blocked
+    /// attempts are audited per file with that file's decision, like applied patches.
+    pub async fn authorize_patch_apply(
+        &mut self,
+        action_id: &str,
+    ) -> Result<ApprovalDecision, AliciaUiRuntimeError> {
+        self.revert_expired_full_access().await?;
+        let locked_down = self.store.read().is_agent_paused();
+        let named_profile = self.workspace_profile();
+        let files = self
+            .store
+            .read()
+            .diff_preview(action_id)
+            .map(|preview| preview.files.clone())
+            .unwrap_or_default();
+        // Each file answers to the profile and path rules of the root that owns it; files
+        // outside every root fall back to the main workspace's profile.
+        let workspace_roots = self.workspace_roots();
+        let mut root_profiles: HashMap<PathBuf, NamedProfile> = HashMap::new();
+        let mut file_decisions: Vec<(String, NamedProfile, PolicyDecision)> =
+            Vec::with_capacity(files.len());
+        for file in files {
+            let (root, path, file_profile) =
+                match ensure_target_in_workspace_roots(&workspace_roots, Path::new(&file)) {
+                    Ok(guard) => {
+                        let root = guard.canonical_workspace;
+                        let file_profile = match root_profiles.get(&root) {
+                            Some(file_profile) => file_profile.clone(),
+                            None => {
+                                let (file_profile, _) = self.resolve_root_profile(&root)?;
+                                root_profiles.insert(root.clone(), file_profile.clone());
+                                file_profile
+                            }
+                        };
+                        (root, guard.canonical_target, file_profile)
+                    }
+                    Err(_) => (
+                        self.workspace_root.clone(),
+                        PathBuf::from(&file),
+                        named_profile.clone(),
+                    ),
+                };
+            let decision = file_profile.decision_for_path(&root, &path, ActionKind::ApplyPatch);
+            file_decisions.push((file, file_profile, decision));
+        }
+        let policy_decision = if locked_down {
+            PolicyDecision::Deny
+        } else if file_decisions.is_empty() {
+            named_profile.decision_for(ActionKind::ApplyPatch)
+        } else {
+            file_decisions
+                .iter()
+                .map(|(_, _, decision)| *decision)
+                .fold(PolicyDecision::Allow, combine_policy_decisions)
+        };
+        let approval = self.store.read().approval(action_id).cloned();
+        let requested_approval_decision = match approval.as_ref().map(|approval| approval.status) {
+            Some(ApprovalStatus::Approved) => ApprovalDecision::Approved,
+            Some(ApprovalStatus::Denied) => ApprovalDecision::Denied,
+            Some(ApprovalStatus::Expired) => ApprovalDecision::Expired,
+            Some(ApprovalStatus::Pending) | None => {
+                let store = self.store.read();
+                let remembered = store.diff_preview(action_id).is_some_and(|preview| {
+                    !preview.files.is_empty()
+                        && preview.files.iter().all(|file| {
+                            store
+                                .remembered_approvals()
+                                .covers_target(store.workspace_root(), file)
+                        })
+                });
+                if remembered {
+                    ApprovalDecision::Approved
+                } else {
+                    ApprovalDecision::NotRequired
+                }
+            }
+        };
+        let approval_decision =
+            effective_approval_decision(policy_decision, requested_approval_decision);
+        let Some(reason) = blocked_reason(policy_decision, approval_decision) else {
+            return Ok(approval_decision);
+        };
+
+        let approver = approval
+            .as_ref()
+            .and_then(|approval| approval.approver.clone());
+        let event_sequence = approval.and_then(|approval| approval.resolved_sequence);
+        let session_id = self
+            .store
+            .read()
+            .active_session_id()
+            .unwrap_or(action_id)
+            .to_string();
+        let mut metadata = self.audit_metadata.clone();
+        if locked_down {
+            metadata.insert("reason".to_string(), "panic_switch".to_string());
+        }
+        for (file, file_profile, file_decision) in file_decisions {
+            let audit_context = SessionAuditContext {
+                action_kind: ActionKind::ApplyPatch,
+                target: file,
+                profile: file_profile.base,
+                profile_name: file_profile.custom_name().map(str::to_string),
+                policy_decision: if locked_down {
+                    PolicyDecision::Deny
+                } else {
+                    file_decision
+                },
+                approval_decision,
+                action_id: Some(action_id.to_string()),
+                event_sequence,
+                approver: approver.clone(),
+                metadata: metadata.clone(),
+            };
+            self.record_blocked_audit(&session_id, &audit_context)
+                .await?;
+        }
+        Err(AliciaUiRuntimeError::PatchBlocked {
+            action_id: action_id.to_string(),
+            reason,
+        })
+    }
+
+    /// Records a patch applied to the workspace once `authorize_patch_apply` allows it, then
+    /// persists the resulting `ApplyPatch` audit entries.
+    pub async fn record_patch_applied(
+        &mut self,
+        action_id: &str,
+        files: Vec<String>,
+    ) -> Result<(), AliciaUiRuntimeError> {
+        self.authorize_patch_apply(action_id).await?;
+        self.store
+            .write()
+            .push(IpcMessage::new(IpcEvent::PatchApplied(PatchApplied {
+                action_id: action_id.to_string(),
+                files,
+            })));
+        self.flush_store_audit_records().await?;
+        Ok(())
+    }
+
+    /// Writes the approved hunks of `action_id`'s patch to the workspace once
+    /// `authorize_patch_apply` allows it, copying each existing file under
+    /// `PATCH_BACKUPS_DIR` of its root first, then records `PatchApplied` for the files it
+    /// changed. Every hunk must be decided, nothing is written unless all approved hunks
+    /// apply, a write that fails puts back the files already rewritten, and a patch is
+    /// applied at most once. Returns the files changed.
+    pub async fn apply_approved_hunks(
+        &mut self,
+        action_id: &str,
+    ) -> Result<Vec<String>, AliciaUiRuntimeError> {
+        let (file_previews, applied) = self
+            .store
+            .read()
+            .diff_preview(action_id)
+            .map(|preview| (preview.file_previews.clone(), preview.applied))
+            .unwrap_or_default();
+        if applied {
+            return Err(PatchApplyError::AlreadyApplied(action_id.to_string()).into());
+        }
+        let undecided = file_previews
+            .iter()
+            .flat_map(|file| &file.hunks)
+            .filter(|hunk| hunk.decision == PatchHunkDecision::Pending)
+            .count();
+        if undecided > 0 {
+            return Err(PatchApplyError::UndecidedHunks {
+                action_id: action_id.to_string(),
+                count: undecided,
+            }
+            .into());
+        }
+        let approved: Vec<&PatchFilePreview> = file_previews
+            .iter()
+            .filter(|file| {
+                file.hunks
+                    .iter()
+                    .any(|hunk| hunk.decision == PatchHunkDecision::Approved)
+            })
+            .collect();
+        if approved.is_empty() {
+            return Err(PatchApplyError::NoApprovedHunks(action_id.to_string()).into());
+        }
+        self.authorize_patch_apply(action_id).await?;
+
+        // Every file is patched in memory first so a hunk that no longer applies leaves the
+        // workspace untouched.
+        let workspace_roots = self.workspace_roots();
+        let mut rewrites = Vec::with_capacity(approved.len());
+        for file in approved {
+            let guard =
+                ensure_target_in_workspace_roots(&workspace_roots, Path::new(&file.file_path))
+                    .map_err(|source| PatchApplyError::OutsideWorkspace {
+                        file_path: file.file_path.clone(),
+                        reason: source.to_string(),
+                    })?;
+            let path = guard.canonical_target;
+            let original = match std::fs::read_to_string(&path) {
+                Ok(text) => Some(text),
+                Err(source) if source.kind() == std::io::ErrorKind::NotFound => None,
+                Err(source) => {
+                    return Err(PatchApplyError::ReadFailed {
+                        path: path.to_string_lossy().to_string(),
+                        source,
+                    }
+                    .into());
+                }
+            };
+            let patched = apply_approved_hunks_to(file, original.as_deref().unwrap_or_default())?;
+            let relative = path.strip_prefix(&guard.canonical_workspace).map_or_else(
+                |_| file.file_path.clone(),
+                |relative| relative.to_string_lossy().to_string(),
+            );
+            let backup = patch_backup_path(&guard.canonical_workspace, action_id, &relative);
+            rewrites.push((file.file_path.clone(), path, backup, original, patched));
+        }
+
+        for (_, _, backup, original, _) in &rewrites {
+            let Some(original) = original else {
+                continue;
+            };
+            let backup_failed = |source| PatchApplyError::BackupFailed {
+                path: backup.to_string_lossy().to_string(),
+                source,
+            };
+            if let Some(parent) = backup.parent() {
+                std::fs::create_dir_all(parent).map_err(backup_failed)?;
+            }
+            std::fs::write(&backup, original).map_err(backup_failed)?;
+        }
+        for (index, (_, path, _, _, patched)) in rewrites.iter().enumerate() {
+            let written = match path.parent() {
+                Some(parent) => std::fs::create_dir_all(parent),
+                None => Ok(()),
+            }
+            .and_then(|()| std::fs::write(path, patched));
+            if let Err(source) = written {
+                // Best effort: the failed file may be truncated, so it is put back as well.
+                for (_, path, _, original, _) in &rewrites[..=index] {
+                    let _ = match original {
+                        Some(original) => std::fs::write(path, original),
+                        None => std::fs::remove_file(path),
+                    };
+                }
+                return Err(PatchApplyError::WriteFailed {
+                    path: path.to_string_lossy().to_string(),
+                    source,
+                }
+                .into());
+            }
+        }
+
+        let files: Vec<String> = rewrites
+            .into_iter()
+            .map(|(file_path, ..)| file_path)
+            .collect();
+        self.store
+            .write()
+            .push(IpcMessage::new(IpcEvent::PatchApplied(PatchApplied {
+                action_id: action_id.to_string(),
+                files: files.clone(),
+            })));
+        self.flush_store_audit_records().await?;
+        Ok(files)
+    }
+
+    /// Applies the patches queued with `UiEventStore::request_hunk_apply`. Failures are
+    /// reported in the chat. Returns the action ids applied.
+    pub async fn apply_requested_hunks(&mut self) -> Vec<String> {
+        let mut applied = Vec::new();
+        let requested = self.store.write().take_requested_hunk_applies();
+        for action_id in requested {
+            match self.apply_approved_hunks(&action_id).await {
+                Ok(_) => applied.push(action_id),
+                Err(error) => self
+                    .store
+                    .write()
+                    .push_chat_message(ChatRole::System, error.beginner_message()),
+            }
+        }
+        applied
+    }
+
+    /// Writes the audit records `exporter` keeps to `path`. Returns how many were written.
+    pub fn export_audit(
+        &self,
+        path: &Path,
+        exporter: &AuditExporter,
+    ) -> Result<usize, AliciaUiRuntimeError> {
+        let store = self.store.read();
+        Ok(exporter.write_to(path, store.audit_records())?)
+    }
+
+    /// Runs the exports queued with `UiEventStore::request_audit_export`, each to a new file
+    /// under [`AUDIT_EXPORTS_DIR`]. The outcome is reported in the chat. Returns the files
+    /// written.
+    pub fn export_requested_audits(&mut self) -> Vec<PathBuf> {
+        let mut written = Vec::new();
+        let requested = self.store.write().take_requested_audit_exports();
+        for exporter in requested {
+            let path = audit_export_path(&self.workspace_root, exporter.format, unix_now_s());
+            let message = match self.export_audit(&path, &exporter) {
+                Ok(count) => {
+                    let message = format!(
+                        "Auditoria exportada para {} ({count} registro(s)).",
+                        path.display()
+                    );
+                    written.push(path);
+                    message
+                }
+                Err(error) => error.beginner_message(),
+            };
+            self.store
+                .write()
+                .push_chat_message(ChatRole::System, message);
+        }
+        written
+    }
+
+    /// Action id of the approval a blocked `session_id` is waiting for.
+    pub fn parked_approval_id(&self, session_id: &str) -> Option<&str> {
+        self.parked_sessions
+            .iter()
+            .find(|(_, request)| request.session_id == session_id)
+            .map(|(action_id, _)| action_id.as_str())
+    }
+
+    /// Starts parked sessions whose approval was granted and drops those denied or expired.
+    /// Returns the ids of the sessions started.
+    pub async fn resume_parked_sessions(&mut self) -> Result<Vec<String>, AliciaUiRuntimeError> {
+        let resolved: Vec<(String, ApprovalStatus)> = self
+            .parked_sessions
+            .keys()
+            .filter_map(|action_id| {
+                let status = self
+                    .store
+                    .read()
+                    .approval(action_id)
+                    .map(|approval| approval.status);
+                match status {
+                    Some(ApprovalStatus::Pending) => None,
+                    Some(status) => Some((action_id.clone(), status)),
+                    None => Some((action_id.clone(), ApprovalStatus::Expired)),
+                }
+            })
+            .collect();
+
+        let mut started = Vec::new();
+        for (action_id, status) in resolved {
+            let Some(mut request) = self.parked_sessions.remove(&action_id) else {
+                continue;
+            };
+            if status == ApprovalStatus::Approved {
+                // The reviewer may have edited the command before approving it.
+                let approved_command = self
+                    .store
+                    .read()
+                    .approval(&action_id)
+                    .and_then(|approval| approval.command.clone());
+                if let Some(command) = approved_command
+                    && command != command_tokens(&request.program, &request.args)
+                    && let Some((program, args)) = command.split_first()
+                {
+                    request.program = program.clone();
+                    request.args = args.to_vec();
+                    request.audit_context.target.clear();
+                }
+                let session_id = request.session_id.clone();
+                self.start_session(request).await?;
+                started.push(session_id);
+            }
+        }
+        Ok(started)
+    }
+
+    /// Switches the store to `profile`, then persists the audit record of the change.
+    pub async fn set_permission_profile(
+        &mut self,
+        profile: PermissionProfile,
+    ) -> Result<(), AliciaUiRuntimeError> {
+        self.store.write().change_permission_profile(profile);
+        self.flush_store_audit_records().await?;
+        Ok(())
+    }
+
+    /// Applies the profile picked in the selector, if any. Returns the profile applied.
+    pub async fn apply_requested_profile_change(
+        &mut self,
+    ) -> Result<Option<PermissionProfile>, AliciaUiRuntimeError> {
+        let requested = self.store.write().take_requested_profile_change();
+        if let Some(profile) = requested {
+            self.set_permission_profile(profile).await?;
+        }
+        Ok(requested)
+    }
+
+    /// Grants `FullAccess` for `duration`, then persists the audit record of the escalation.
+    /// `pump_events` reverts it once due, provided the approval expiry task is running, and
+    /// every start or patch apply checks the deadline before deciding.
+    pub async fn grant_temporary_full_access(
+        &mut self,
+        duration: Duration,
+    ) -> Result<(), AliciaUiRuntimeError> {
+        self.store
+            .write()
+            .grant_temporary_full_access(duration, unix_now_s());
+        self.flush_store_audit_records().await?;
+        Ok(())
+    }
+
+    /// Reverts a temporary `FullAccess` grant that is due and persists the audit record of
+    /// the revert. Returns whether it reverted.
+    pub async fn revert_expired_full_access(&mut self) -> Result<bool, AliciaUiRuntimeError> {
+        let reverted = self
+            .store
+            .write()
+            .expire_temporary_full_access(unix_now_s());
+        if reverted {
+            self.flush_store_audit_records().await?;
+        }
+        Ok(reverted)
+    }
+
+    /// Engages the store's panic switch, broadcasts the denied approvals and persists the
+    /// audit record. Returns how many approvals were denied.
+    pub async fn engage_panic_switch(&mut self) -> Result<usize, AliciaUiRuntimeError> {
+        let count = self.store.write().engage_panic_switch().len();
+        self.publish_resolutions();
+        self.flush_store_audit_records().await?;
+        Ok(count)
+    }
+
+    /// Starts the sessions asked for with `/session new` in the store's session cwd, the
+    /// workspace root by default, or stages them when the store asks for their environment to be reviewed first. A session that needs
+    /// approval stays parked like any other; failures are reported in the chat. Returns the
+    /// ids of the sessions started.
+    pub async fn start_requested_sessions(&mut self) -> Vec<String> {
+        let mut started = Vec::new();
+        let requested = self.store.write().take_requested_sessions();
+        let review_session_env = self.store.read().review_session_env();
+        let cwd = self.store.read().session_cwd().map_or_else(
+            || self.workspace_root.clone(),
+            |cwd| self.workspace_root.join(cwd),
+        );
+        for command in requested {
+            // Interactive shells get OSC 133 markers so each command they run is tracked.
+            let interactive_shell = command.is_empty();
+            let command = if interactive_shell {
+                default_shell_command()
+            } else {
+                command
+            };
+            let Some((program, args)) = command.split_first() else {
+                continue;
+            };
+            let mut env: HashMap<String, String> = std::env::vars().collect();
+            if interactive_shell {
+                env.extend(shell_integration_env(program));
+            }
+            self.next_requested_session = self.next_requested_session.saturating_add(1);
+            let session_id = format!("session-{}", self.next_requested_session);
+            let request = SessionStartRequest::new(
+                session_id.clone(),
+                program.clone(),
+                args.to_vec(),
+                cwd.clone(),
+                env,
+            )
+            .with_audit_context(SessionAuditContext::for_execute_command(command.join(" ")));
+            if review_session_env {
+                self.store.write().stage_session(request);
+            } else if self.start_reviewed_session(request).await {
+                started.push(session_id);
+            }
+        }
+        started
+    }
+
+    /// Starts the staged sessions launched from the environment review, with their edits
+    /// applied. Returns the ids of the sessions started.
+    pub async fn start_staged_sessions(&mut self) -> Vec<String> {
+        let mut started = Vec::new();
+        let launched = self.store.write().take_launched_sessions();
+        for request in launched {
+            let session_id = request.session_id.clone();
+            if self.start_reviewed_session(request).await {
+                started.push(session_id);
+            }
+        }
+        started
+    }
+
+    async fn start_reviewed_session(&mut self, request: SessionStartRequest) -> bool {
+        let session_id = request.session_id.clone();
+        let cwd = request.cwd.clone();
+        match self.start_session(request).await {
+            Ok(()) => {
+                self.remember_recent_dir(&cwd);
+                true
+            }
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+                if self.parked_approval_id(&session_id).is_some() =>
+            {
+                false
+            }
+            Err(error) => {
+                self.store
+                    .write()
+                    .push_chat_message(ChatRole::System, error.beginner_message());
+                false
+            }
+        }
+    }
+
+    /// Hands submitted prompts to the agent and runs the tool calls of its replies until the
+    /// model answers without calling tools or waits on a command. Commands go through
+    /// `start_session`, so a command needing approval stays parked until
+    /// `resume_parked_sessions` runs it; call this after it on every tick. Returns how many
+    /// model replies were received.
+    pub async fn pump_agent(&mut self) -> Result<usize, AliciaUiRuntimeError> {
+        if self.store.read().is_agent_paused() {
+            return Ok(0);
+        }
+        let prompts = self.store.write().take_agent_prompts();
+        if self.agent.is_none() {
+            if !prompts.is_empty() {
+                self.store.write().push_chat_message(
+                    ChatRole::System,
+                    "Nenhum modelo configurado; o prompt nao foi enviado ao agente.",
+                );
+            }
+            return Ok(0);
+        }
+        for prompt in &prompts {
+            self.push_agent_prompt(prompt);
+        }
+        self.sync_selected_model();
+
+        let mut replies = 0;
+        loop {
+            self.collect_agent_command_results();
+            if !self.agent.as_ref().is_some_and(AgentLoop::ready_for_model)
+                || !self.agent_budget_allows_reply()
+            {
+                break;
+            }
+            let Some(agent) = self.agent.as_mut() else {
+                break;
+            };
+            let model = agent.model_name().to_string();
+            let message_id = agent.next_message_id();
+            let store = &self.store;
+            let mut streamed = false;
+            let reply = agent
+                .request_reply(|delta| {
+                    streamed = true;
+                    store
+                        .write()
+                        .push(IpcMessage::new(IpcEvent::AgentMessageDelta(
+                            AgentMessageDelta {
+                                message_id: message_id.clone(),
+                                delta: delta.to_string(),
+                            },
+                        )));
+                })
+                .await;
+            if streamed {
+                self.store
+                    .write()
+                    .push(IpcMessage::new(IpcEvent::AgentMessageComplete(
+                        AgentMessageComplete {
+                            message_id: message_id.clone(),
+                        },
+                    )));
+            }
+            let Some(reply) = reply? else {
+                self.store.write().push_chat_message(
+                    ChatRole::System,
+                    format!(
+                        "O agente atingiu o limite de {MAX_AGENT_STEPS_PER_PROMPT} passos neste prompt."
+                    ),
+                );
+                break;
+            };
+            replies += 1;
+            if let Some(usage) = reply.usage {
+                self.store.write().record_token_usage(&model, usage);
+            }
+            for call in reply.tool_calls {
+                self.run_agent_tool_call(&message_id, call).await;
+            }
+        }
+        Ok(replies)
+    }
+
+    /// Past the budget limit the loop pauses behind an approval: approving it allows another
+    /// limit's worth of spending, refusing it drops the rest of the prompt.
+    fn agent_budget_allows_reply(&mut self) -> bool {
+        let Some(limit_usd) = self.store.read().budget_limit_usd() else {
+            return true;
+        };
+        let spent_usd = self.store.read().total_cost_usd();
+        let Some(agent) = self.agent.as_mut() else {
+            return false;
+        };
+        if spent_usd < agent.budget_ceiling_usd(limit_usd) {
+            return true;
+        }
+        let Some(action_id) = agent.budget_approval_id().map(str::to_string) else {
+            let action_id = agent.next_id();
+            agent.set_budget_approval_id(Some(action_id.clone()));
+            self.store
+                .write()
+                .push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                    ApprovalRequested {
+                        action_id,
+                        summary: format!(
+                            "Continuar o agente acima do orcamento de {} (gasto: {})",
+                            format_cost_usd(limit_usd),
+                            format_cost_usd(spent_usd)
+                        ),
+                        expires_at_unix_s: unix_now_s()
+                            .saturating_add(PARKED_SESSION_APPROVAL_TTL_S),
+                    },
+                )));
+            self.store.write().push_chat_message(
+                ChatRole::System,
+                "Orcamento do agente excedido; aprove a continuacao para seguir.",
+            );
+            return false;
+        };
+        let status = self
+            .store
+            .read()
+            .approval(&action_id)
+            .map(|approval| approval.status);
+        match status {
+            Some(ApprovalStatus::Pending) => false,
+            Some(ApprovalStatus::Approved) => {
+                agent.set_budget_approval_id(None);
+                agent.raise_budget_ceiling(spent_usd + limit_usd);
+                true
+            }
+            Some(ApprovalStatus::Denied | ApprovalStatus::Expired) | None => {
+                agent.set_budget_approval_id(None);
+                agent.abandon_prompt();
+                self.store.write().push_chat_message(
+                    ChatRole::System,
+                    "Orcamento do agente excedido; o prompt foi interrompido.",
+                );
+                false
+            }
+        }
+    }
+
+    /// Runs `call` and reports it under the chat message `message_id`. A command left waiting
+    /// on its session finishes in `collect_agent_command_results`.
+    async fn run_agent_tool_call(&mut self, message_id: &str, call: AgentToolCall) {
+        let started_at = Instant::now();
+        let name = call.tool.name().to_string();
+        let arguments = call.tool.arguments_summary();
+        let mut action_id = None;
+        let result = match call.tool {
+            AgentTool::RunCommand { command, cwd } => {
+                self.start_agent_command(&call.id, command, cwd).await
+            }
+            AgentTool::ReadFile { path } => Some(self.read_agent_file(&path)),
+            AgentTool::ProposePatch {
+                file_path,
+                unified_diff,
+            } => {
+                let (result, patch_action_id) = self.propose_agent_patch(&file_path, &unified_diff);
+                action_id = patch_action_id;
+                Some(result)
+            }
+        };
+        let session_id = self.agent.as_ref().and_then(|agent| {
+            agent
+                .waiting_sessions()
+                .iter()
+                .find(|(call_id, _)| *call_id == call.id)
+                .map(|(_, session_id)| session_id.clone())
+        });
+        if let Some(session_id) = &session_id {
+            action_id = self.parked_approval_id(session_id).map(str::to_string);
+        }
+        self.store
+            .write()
+            .push(IpcMessage::new(IpcEvent::ToolCallStarted(
+                ToolCallStarted {
+                    call_id: call.id.clone(),
+                    message_id: message_id.to_string(),
+                    name,
+                    arguments,
+                    session_id,
+                    action_id,
+                },
+            )));
+        match result {
+            Some(result) => self.finish_agent_tool_call(&call.id, result, started_at),
+            None => {
+                self.agent_tool_call_starts.insert(call.id, started_at);
+            }
+        }
+    }
+
+    fn finish_agent_tool_call(&mut self, call_id: &str, result: String, started_at: Instant) {
+        self.store
+            .write()
+            .push(IpcMessage::new(IpcEvent::ToolCallFinished(
+                ToolCallFinished {
+                    call_id: call_id.to_string(),
+                    status: tool_result_status(&result),
+                    duration_ms: started_at.elapsed().as_millis() as u64,
+                },
+            )));
+        if let Some(agent) = self.agent.as_mut() {
+            agent.push_tool_result(call_id, result);
+        }
+    }
+
+    /// Starts a command on the agent's behalf. Returns the tool result right away when there
+    /// is no session to wait for.
+    async fn start_agent_command(
+        &mut self,
+        call_id: &str,
+        command: Vec<String>,
+        cwd: Option<String>,
+    ) -> Option<String> {
+        let Some((program, args)) = command.split_first() else {
+            return Some("erro: comando vazio".to_string());
+        };
+        let session_id = self.agent.as_mut().map(AgentLoop::next_id)?;
+        let cwd = cwd.map_or_else(
+            || self.workspace_root.clone(),
+            |cwd| self.workspace_root.join(cwd),
+        );
+        let request = SessionStartRequest::new(
+            session_id.clone(),
+            program.clone(),
+            args.to_vec(),
+            cwd,
+            std::env::vars().collect(),
+        )
+        .with_mode(SessionMode::Pipe)
+        .with_audit_context(SessionAuditContext::for_execute_command(command.join(" ")));
+        match self.start_session(request).await {
+            Ok(()) => {}
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+                if self.parked_approval_id(&session_id).is_some() => {}
+            Err(error) => return Some(format!("erro: {error}")),
+        }
+        if let Some(agent) = self.agent.as_mut() {
+            agent.wait_for_session(call_id.to_string(), session_id);
+        }
+        None
+    }
+
+    /// Feeds finished agent commands back to the model. A command no longer parked that never
+    /// started had its approval denied or expired.
+    fn collect_agent_command_results(&mut self) {
+        self.pump_events();
+        let Some(agent) = self.agent.as_ref() else {
+            return;
+        };
+        let mut results = Vec::new();
+        for (call_id, session_id) in agent.waiting_sessions() {
+            let finished = self
+                .store
+                .read()
+                .terminal_session(session_id)
+                .map(|session| match session.lifecycle {
+                    CommandLifecycle::Finished { exit_code, .. } => {
+                        Some(command_tool_result(exit_code, &session.visible_lines()))
+                    }
+                    CommandLifecycle::Running => None,
+                });
+            let result = match finished {
+                Some(Some(result)) => result,
+                Some(None) => continue,
+                None if self.parked_approval_id(session_id).is_some() => continue,
+                None => {
+                    let reason = self
+                        .store
+                        .read()
+                        .approval(&format!("{session_id}{PARKED_SESSION_ACTION_SUFFIX}"))
+                        .and_then(|approval| approval.denial_reason.clone());
+                    match reason {
+                        Some(reason) => format!(
+                            "bloqueado: a aprovacao do comando foi negada ({}); nao repita a \
+                             mesma acao",
+                            denial_reason_text(&reason)
+                        ),
+                        None => {
+                            "bloqueado: a aprovacao do comando foi negada ou expirou".to_string()
+                        }
+                    }
+                }
+            };
+            results.push((call_id.clone(), result));
+        }
+        for (call_id, result) in results {
+            if let Some(agent) = self.agent.as_mut() {
+                agent.stop_waiting(&call_id);
+            }
+            let started_at = self
+                .agent_tool_call_starts
+                .remove(&call_id)
+                .unwrap_or_else(Instant::now);
+            self.finish_agent_tool_call(&call_id, result, started_at);
+        }
+    }
+
+    fn read_agent_file(&self, path: &str) -> String {
+        self.read_workspace_file(path)
+            .unwrap_or_else(|(_, message)| message)
+    }
+
+    /// Contents of `path` for the agent, truncated to `MAX_AGENT_READ_BYTES`. The error carries
+    /// the audit status of the failed read and the text the agent gets instead.
+    fn read_workspace_file(&self, path: &str) -> Result<String, (ResultStatus, String)> {
+        if self.workspace_profile().decision_for_path(
+            &self.workspace_root,
+            Path::new(path),
+            ActionKind::ReadFile,
+        ) != PolicyDecision::Allow
+        {
+            return Err((
+                ResultStatus::Blocked,
+                "bloqueado: o perfil atual nao permite ler arquivos".to_string(),
+            ));
+        }
+        let guard = ensure_target_in_workspace(&self.workspace_root, Path::new(path))
+            .map_err(|error| (ResultStatus::Blocked, format!("bloqueado: {error}")))?;
+        match std::fs::read(&guard.canonical_target) {
+            Ok(bytes) if bytes.len() > MAX_AGENT_READ_BYTES => Ok(format!(
+                "{}\n[truncado em {MAX_AGENT_READ_BYTES} de {} bytes]",
+                String::from_utf8_lossy(&bytes[..MAX_AGENT_READ_BYTES]),
+                bytes.len()
+            )),
+            Ok(bytes) => Ok(String::from_utf8_lossy(&bytes).to_string()),
+            Err(error) => Err((ResultStatus::Failed, format!("erro: {error}"))),
+        }
+    }
+
+    /// Reads the attachments of `prompt` into the agent history ahead of it, auditing each read.
+    fn push_agent_prompt(&mut self, prompt: &ChatMessage) {
+        for attachment in &prompt.attachments {
+            let path = self.workspace_root.join(&attachment.path);
+            let path = path.to_string_lossy();
+            let content = match self.read_workspace_file(&path) {
+                Ok(content) => {
+                    self.store
+                        .write()
+                        .record_context_read(&attachment.path, ResultStatus::Succeeded);
+                    content
+                }
+                Err((status, message)) => {
+                    self.store
+                        .write()
+                        .record_context_read(&attachment.path, status);
+                    message
+                }
+            };
+            if let Some(agent) = self.agent.as_mut() {
+                agent.push_context_file(&attachment.path, &content);
+            }
+        }
+        if let Some(agent) = self.agent.as_mut() {
+            agent.push_prompt(prompt);
+        }
+    }
+
+    /// Stages the patch for review like any proposed patch; the model only learns that the
+    /// preview exists, applying it stays with the user.
+    /// Returns the tool result and the id of the staged action, if one was created.
+    fn propose_agent_patch(
+        &mut self,
+        file_path: &str,
+        unified_diff: &str,
+    ) -> (String, Option<String>) {
+        let policy_decision = self
+            .workspace_profile()
+            .decision_for(ActionKind::ApplyPatch);
+        if policy_decision == PolicyDecision::Deny {
+            return (
+                "bloqueado: o perfil atual nao permite aplicar patches".to_string(),
+                None,
+            );
+        }
+        if let Err(error) = parse_unified_diff_hunks(unified_diff) {
+            return (format!("erro: diff invalido: {error}"), None);
+        }
+        let Some(action_id) = self.agent.as_mut().map(AgentLoop::next_id) else {
+            return ("erro: nenhum agente configurado".to_string(), None);
+        };
+        self.store
+            .write()
+            .push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+                action_id: action_id.clone(),
+                action_kind: ActionKind::ApplyPatch,
+                target: file_path.to_string(),
+            })));
+        self.store
+            .write()
+            .push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+                PatchPreviewReady {
+                    action_id: action_id.clone(),
+                    files: vec![file_path.to_string()],
+                },
+            )));
+        let attached =
+            self.store
+                .write()
+                .attach_patch_file_diff(&action_id, file_path, unified_diff);
+        if let Err(error) = attached {
+            return (format!("erro: {error}"), Some(action_id));
+        }
+        if policy_decision == PolicyDecision::RequireApproval {
+            self.store
+                .write()
+                .push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                    ApprovalRequested {
+                        action_id: action_id.clone(),
+                        summary: format!("Aplicar patch em {file_path}"),
+                        expires_at_unix_s: unix_now_s()
+                            .saturating_add(PARKED_SESSION_APPROVAL_TTL_S),
+                    },
+                )));
+        }
+        (
+            format!("previa {action_id} criada para {file_path}; aguardando revisao do usuario"),
+            Some(action_id),
+        )
+    }
+
+    /// Stages `ActionProposed` and `ApprovalRequested` for a start request blocked for lack
+    /// of an approval, so approving it is enough to run the command.
+    fn park_for_approval(
+        &mut self,
+        request: SessionStartRequest,
+        command: Vec<String>,
+        session_root: PathBuf,
+    ) {
+        let action_id = format!("{}{PARKED_SESSION_ACTION_SUFFIX}", request.session_id);
+        self.parked_roots.insert(action_id.clone(), session_root);
+        let already_pending = self
+            .store
+            .read()
+            .approval(&action_id)
+            .is_some_and(|approval| approval.status == ApprovalStatus::Pending);
+        if !already_pending {
+            self.store
+                .write()
+                .push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+                    action_id: action_id.clone(),
+                    action_kind: ActionKind::ExecuteCommand,
+                    target: request.audit_context.target.clone(),
+                })));
+            self.store
+                .write()
+                .attach_approval_command(action_id.clone(), command.clone());
+            self.store
+                .write()
+                .push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                    ApprovalRequested {
+                        action_id: action_id.clone(),
+                        summary: format!("Executar {}", command.join(" ")),
+                        expires_at_unix_s: unix_now_s()
+                            .saturating_add(PARKED_SESSION_APPROVAL_TTL_S),
+                    },
+                )));
+        }
+        self.parked_sessions.insert(action_id, request);
+    }
+
+    pub async fn stop_session(&mut self, session_id: &str) -> Result<(), AliciaUiRuntimeError> {
+        self.stop_session_with_cancellation(session_id, &CancellationToken::new())
+            .await
+    }
+
+    /// Like `stop_session`, but stops waiting for the session to finish once `cancellation`
+    /// is cancelled. The session was already killed by then; only its cancellation audit
+    /// record is skipped.
+    #[instrument(name = "alicia.stop_session", skip(self, cancellation), err)]
+    pub async fn stop_session_with_cancellation(
+        &mut self,
+        session_id: &str,
+        cancellation: &CancellationToken,
+    ) -> Result<(), AliciaUiRuntimeError> {
+        self.session_manager.cancel(session_id).await?;
+        self.store.write().unbind_session_input(session_id);
+        let finished_event = self
+            .wait_for_session_finished_event(session_id, self.session_stop_timeout, cancellation)
+            .await?
+            .ok_or_else(|| AliciaUiRuntimeError::SessionStopTimeout {
+                session_id: session_id.to_string(),
+            })?;
+        self.record_cancellation_audit(session_id, &finished_event)
+            .await?;
+        self.pump_events();
+        Ok(())
+    }
+
+    /// Resizes the session's PTY (a ConPTY on Windows) and then its screen, so full-screen
+    /// programs redraw for the new window.
+    pub async fn resize_session(
+        &mut self,
+        session_id: &str,
+        rows: u16,
+        columns: u16,
+    ) -> Result<(), AliciaUiRuntimeError> {
+        self.session_manager
+            .resize(session_id, rows, columns)
+            .await?;
+        let _ = self
+            .store
+            .write()
+            .resize_terminal(session_id, rows, columns);
+        Ok(())
+    }
+
+    /// Applies the resizes queued by the terminal panel; failures go to the error center.
+    /// Returns how many sessions were resized.
+    pub async fn resize_requested_sessions(&mut self) -> usize {
+        let mut resized = 0;
+        let requested = self.store.write().take_requested_resizes();
+        for (session_id, rows, columns) in requested {
+            match self.resize_session(&session_id, rows, columns).await {
+                Ok(()) => resized += 1,
+                Err(error) => {
+                    self.store
+                        .write()
+                        .record_error(error.error_report(), unix_now_s());
+                }
+            }
+        }
+        resized
+    }
+
+    pub async fn bind_session_input(
+        &mut self,
+        session_id: &str,
+    ) -> Result<(), AliciaUiRuntimeError> {
+        let reattached = self.session_manager.reattach(session_id).await?;
+        self.store
+            .write()
+            .bind_session_input(session_id.to_string(), reattached.writer_tx);
+        Ok(())
+    }
+
+    pub fn send_input_to_active_session(
+        &self,
+        input: impl AsRef<[u8]>,
+    ) -> Result<(), UiEventStoreError> {
+        self.store.read().send_input_to_active_session(input)
+    }
+
+    pub fn send_line_to_active_session(&self, line: &str) -> Result<(), UiEventStoreError> {
+        let mut payload = line.as_bytes().to_vec();
+        payload.push(b'\n');
+        self.store.read().send_input_to_active_session(payload)
+    }
+
+    pub fn pump_events(&mut self) -> usize {
+        let started = std::time::Instant::now();
+        let mut processed = 0;
+
+        while let Some(events_rx) = &mut self.events_rx {
+            match events_rx.try_recv() {
+                Ok(message) => {
+                    self.store.write().push(message);
+                    processed += 1;
+                }
+                Err(tokio::sync::broadcast::error::TryRecvError::Lagged(skipped)) => {
+                    self.store
+                        .write()
+                        .record_subscriber_lag(RUNTIME_EVENTS_SUBSCRIBER, skipped);
+                }
+                Err(tokio::sync::broadcast::error::TryRecvError::Empty) => break,
+                Err(tokio::sync::broadcast::error::TryRecvError::Closed) => {
+                    self.event_loop_closed
+                        .store(true, std::sync::atomic::Ordering::Relaxed);
+                    break;
+                }
+            }
+        }
+
+        if processed > 0 {
+            self.store.write().record_event_latency(started.elapsed());
+            self.store.write().enforce_memory_budget();
+        }
+        self.check_session_cwds();
+        self.save_approval_memory();
+        self.save_requested_approval_rules();
+        self.refresh_health();
+        self.store.write().detect_input_prompts(Instant::now());
+        self.publish_resolutions();
+        processed
+    }
+
+    /// Checks whether events still reach the store, the audit log takes writes and the
+    /// project policy parses, along with the store's session and approval counts.
+    pub fn health(&self) -> RuntimeHealth {
+        let metrics = self.store.read().status_metrics();
+        let policy = match load_project_policy(&self.workspace_root) {
+            Ok(Some(_)) => PolicyFileStatus::Valid,
+            Ok(None) => PolicyFileStatus::Missing,
+            Err(error) => PolicyFileStatus::Invalid {
+                error: error.to_string(),
+            },
+        };
+        let audit_writable = self.audit_logger.as_ref().map(|audit_logger| {
+            !matches!(metrics.audit_health, AuditHealth::Failing { .. })
+                && std::fs::OpenOptions::new()
+                    .append(true)
+                    .open(audit_logger.path())
+                    .is_ok()
+        });
+        RuntimeHealth {
+            event_loop_alive: !self
+                .event_loop_closed
+                .load(std::sync::atomic::Ordering::Relaxed),
+            lagged_events: metrics.lagged_subscribers.values().sum(),
+            active_sessions: metrics.running_sessions,
+            pending_approvals: metrics.pending_approvals,
+            audit_writable,
+            policy,
+            checked_at_unix_s: unix_now_s(),
+        }
+    }
+
+    /// Publishes `health` to the store at most every `HEALTH_REFRESH_INTERVAL`.
+    fn refresh_health(&mut self) {
+        if self
+            .health_checked_at
+            .is_some_and(|checked_at| checked_at.elapsed() < HEALTH_REFRESH_INTERVAL)
+        {
+            return;
+        }
+        self.health_checked_at = Some(Instant::now());
+        let health = self.health();
+        self.store.write().set_runtime_health(health);
+    }
+
+    fn check_session_cwds(&mut self) {
+        let changes = self.store.write().take_cwd_changes();
+        if changes.is_empty() {
+            return;
+        }
+        let workspace_roots = self.workspace_roots();
+        for (session_id, cwd) in changes {
+            let inside_workspace =
+                ensure_target_in_workspace_roots(&workspace_roots, Path::new(&cwd)).is_ok();
+            self.store
+                .write()
+                .record_session_workspace_check(&session_id, inside_workspace);
+        }
+    }
+
+    /// Persists audit records produced by patch applies and rejected hunks, enriched with the
+    /// project audit fields of the current session.
+    pub async fn flush_store_audit_records(&mut self) -> Result<usize, AliciaUiRuntimeError> {
+        let records = self.store.write().take_unpersisted_audit_records();
+        let Some(audit_logger) = self.audit_logger.clone() else {
+            return Ok(0);
+        };
+
+        let persisted = records.len();
+        for mut record in records {
+            for (key, value) in &self.audit_metadata {
+                record
+                    .metadata
+                    .entry(key.clone())
+                    .or_insert_with(|| value.clone());
+            }
+            let session_id = record.session_id.clone();
+            self.append_audit_record(&audit_logger, &record, &session_id)
+                .await?;
+        }
+        Ok(persisted)
+    }
+
+    /// Appends `record` and reflects the outcome in the store's audit health.
+    async fn append_audit_record(
+        &mut self,
+        audit_logger: &AuditLogger,
+        record: &AuditRecord,
+        session_id: &str,
+    ) -> Result<(), AliciaUiRuntimeError> {
+        match audit_logger.append(record).await {
+            Ok(()) => {
+                self.store.write().record_audit_write(Ok(()));
+                Ok(())
+            }
+            Err(source) => {
+                self.store
+                    .write()
+                    .record_audit_write(Err(source.to_string()));
+                Err(AliciaUiRuntimeError::AuditWriteFailed {
+                    session_id: session_id.to_string(),
+                    source,
+                })
+            }
+        }
+    }
+
+    async fn record_blocked_audit(
+        &mut self,
+        session_id: &str,
+        audit_context: &SessionAuditContext,
+    ) -> Result<(), AliciaUiRuntimeError> {
+        let mut record = AuditRecord::new(
+            session_id,
+            audit_context.action_kind,
+            audit_context.target.as_str(),
+            audit_context.profile,
+            audit_context.policy_decision,
+            audit_context.approval_decision,
+            ResultStatus::Blocked,
+            0,
+        );
+        record.profile_name = audit_context.profile_name.clone();
+        record.action_id = audit_context.action_id.clone();
+        record.event_sequence = audit_context.event_sequence;
+        record.approver = audit_context.approver.clone();
+        record.metadata = audit_context.metadata.clone();
+        if let Some(audit_logger) = self.audit_logger.clone() {
+            self.append_audit_record(&audit_logger, &record, session_id)
+                .await?;
+        }
+        self.store.write().add_audit_record(record);
+        Ok(())
+    }
+
+    /// `Ok(None)` when `timeout` runs out first.
+    async fn wait_for_session_finished_event(
+        &mut self,
+        session_id: &str,
+        timeout: Duration,
+        cancellation: &CancellationToken,
+    ) -> Result<Option<CommandFinished>, AliciaUiRuntimeError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        tokio::select! {
+            biased;
+            () = cancellation.cancelled() => Err(AliciaUiRuntimeError::OperationCancelled {
+                session_id: session_id.to_string(),
+            }),
+            finished = self.wait_for_finish_until(session_id, deadline) => Ok(finished),
+        }
+    }
+
+    async fn wait_for_finish_until(
+        &mut self,
+        session_id: &str,
+        deadline: tokio::time::Instant,
+    ) -> Option<CommandFinished> {
+        let Some(events_rx) = self.events_rx.as_mut() else {
+            return self.wait_for_applied_finish(session_id, deadline).await;
+        };
+
+        loop {
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                return None;
+            }
+
+            let remaining = deadline.saturating_duration_since(now);
+            match tokio::time::timeout(remaining, events_rx.recv()).await {
+                Ok(Ok(message)) => {
+                    let mut finished = None;
+                    if let IpcEvent::CommandFinished(event) = &message.event
+                        && event.command_id == session_id
+                    {
+                        finished = Some(event.clone());
+                    }
+                    self.store.write().push(message);
+                    if finished.is_some() {
+                        return finished;
+                    }
+                }
+                Ok(Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped))) => {
+                    self.store
+                        .write()
+                        .record_subscriber_lag(RUNTIME_EVENTS_SUBSCRIBER, skipped);
+                }
+                Ok(Err(tokio::sync::broadcast::error::RecvError::Closed)) => return None,
+                Err(_) => return None,
+            }
+        }
+    }
+
+    /// With the event task applying events, waits for the store to see the session finish.
+    async fn wait_for_applied_finish(
+        &self,
+        session_id: &str,
+        deadline: tokio::time::Instant,
+    ) -> Option<CommandFinished> {
+        loop {
+            let finished = self
+                .store
+                .read()
+                .terminal_session(session_id)
+                .and_then(|session| match session.lifecycle {
+                    CommandLifecycle::Finished {
+                        exit_code,
+                        duration_ms,
+                    } => Some(CommandFinished {
+                        command_id: session_id.to_string(),
+                        exit_code,
+                        duration_ms,
+                    }),
+                    CommandLifecycle::Running => None,
+                });
+            if finished.is_some() {
+                return finished;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return None;
+            }
+            tokio::time::sleep(EVENT_TASK_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn record_cancellation_audit(
+        &mut self,
+        session_id: &str,
+        finished_event: &CommandFinished,
+    ) -> Result<(), AliciaUiRuntimeError> {
+        let Some(audit_logger) = self.audit_logger.clone() else {
+            return Ok(());
+        };
+
+        let (target, approval_link) = {
+            let store = self.store.read();
+            let session = store.terminal_session(session_id);
+            let target = session
+                .and_then(|session| {
+                    if session.command.is_empty() {
+                        None
+                    } else {
+                        Some(session.command.join(" "))
+                    }
+                })
+                .unwrap_or_else(|| session_id.to_string());
+            let approval_link = session
+                .and_then(|session| store.latest_resolved_approval_for_command(&session.command));
+            (target, approval_link)
+        };
+        let profile = self.session_named_profile(session_id);
+        let policy_decision = profile.decision_for(ActionKind::ExecuteCommand);
+        let approval_decision = match policy_decision {
+            PolicyDecision::RequireApproval => ApprovalDecision::Approved,
+            PolicyDecision::Allow | PolicyDecision::Deny => ApprovalDecision::NotRequired,
+        };
+        let result_status = if finished_event.exit_code == 0 {
+            ResultStatus::Succeeded
+        } else {
+            ResultStatus::Failed
+        };
+        let mut record = AuditRecord::new(
+            session_id,
+            ActionKind::ExecuteCommand,
+            target,
+            profile.base,
+            policy_decision,
+            approval_decision,
+            result_status,
+            finished_event.duration_ms,
+        );
+        record.profile_name = profile.custom_name().map(str::to_string);
+        if let Some(approval_link) = approval_link {
+            record.action_id = Some(approval_link.action_id);
+            record.event_sequence = approval_link.event_sequence;
+            record.approver = approval_link.approver;
+        }
+        record.metadata = self.audit_metadata.clone();
+        self.append_audit_record(&audit_logger, &record, session_id)
+            .await?;
+        self.store.write().add_audit_record(record);
+        Ok(())
+    }
+}
+
+/// Panel the keyboard acts on; `Tab` moves to the next visible one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum FocusedPanel {
+    #[default]
+    Approvals,
+    Chat,
+    Terminal,
+    Timeline,
+    Audit,
+}
+
+impl FocusedPanel {
+    const ORDER: [Self; 5] = [
+        Self::Approvals,
+        Self::Chat,
+        Self::Terminal,
+        Self::Timeline,
+        Self::Audit,
+    ];
+
+    fn is_visible(self, layout: &LayoutState) -> bool {
+        match self {
+            Self::Approvals => layout.approval_panel_visible,
+            Self::Audit => layout.audit_panel_visible,
+            Self::Chat | Self::Terminal | Self::Timeline => true,
+        }
+    }
+
+    fn next(self, layout: &LayoutState) -> Self {
+        let index = Self::ORDER
+            .iter()
+            .position(|panel| *panel == self)
+            .unwrap_or_default();
+        (1..=Self::ORDER.len())
+            .map(|step| Self::ORDER[(index + step) % Self::ORDER.len()])
+            .find(|panel| panel.is_visible(layout))
+            .unwrap_or(self)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Approvals => "Fila de Aprovações",
+            Self::Chat => "Chat",
+            Self::Terminal => "Terminal",
+            Self::Timeline => "Timeline",
+            Self::Audit => "Histórico de Aprovações",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct AliciaEguiView {
+    terminal_input_buffer: String,
+    chat_input_buffer: String,
+    status_message: Option<String>,
+    error_center_open: bool,
+    requested_error_actions: Vec<(u64, ErrorAction)>,
+    workspace_root: Option<PathBuf>,
+    full_file_views: HashMap<(String, String), FullFileContext>,
+    /// Hunks show old and new side by side instead of one column of `+`/`-` lines.
+    side_by_side_diff: bool,
+    /// Timeline filter toolbar: the kinds shown (all when empty), the session and the text
+    /// searched for.
+    timeline_kinds: Vec<TimelineKind>,
+    timeline_session: Option<String>,
+    timeline_query: String,
+    /// Format and filters of the "Exportar auditoria" button; `audit_export_hours` limits the
+    /// export to the last hours, 0 keeping all.
+    audit_export: AuditExporter,
+    audit_export_hours: u32,
+    symbol_contexts: HashMap<(String, String), Vec<HunkSymbolContext>>,
+    settings_open: bool,
+    command_stats_open: bool,
+    approval_metrics_open: bool,
+    debug_overlay_open: bool,
+    /// Sessions picked in the comparison window, left and right; `None` while it is closed.
+    compared_sessions: Option<(String, String)>,
+    comparison_only_differences: bool,
+    /// What the user typed so far to confirm an escalation to `FullAccess`.
+    full_access_confirmation: Option<String>,
+    /// Length offered for a temporary `FullAccess` grant in the confirmation window.
+    temporary_full_access_minutes: u32,
+    /// Open while the project policy is being edited.
+    policy_editor: Option<PolicyEditor>,
+    /// Environment reviews of the staged sessions, by session id.
+    env_editors: HashMap<String, SessionEnvEditor>,
+    /// Commands being edited before approval, as tokens, by action id.
+    command_edits: HashMap<String, Vec<String>>,
+    /// Denials being explained, category and detail, by action id.
+    denial_drafts: HashMap<String, (DenialCategory, String)>,
+    /// Text selected with the mouse in the terminal view, and the session it belongs to.
+    terminal_selection: Option<(String, TerminalSelection)>,
+    /// Query of the global output search; `None` while its window is closed.
+    output_search: Option<String>,
+    /// Line (0-based) of a session picked in the search results, and whether the terminal
+    /// already scrolled to it.
+    terminal_search_target: Option<(String, usize, bool)>,
+    /// Until when the window border flashes for a visual alert.
+    visual_alert_until: Option<Instant>,
+    editor_config: EditorConfig,
+    font_config: FontConfig,
+    font_config_draft: Option<FontConfig>,
+    applied_font_config: Option<FontConfig>,
+    layout: LayoutState,
+    layout_path: Option<PathBuf>,
+    persisted_layout: Option<LayoutState>,
+    /// Workspace files for `@` completion, listed on the first mention.
+    mention_candidates: Option<Vec<String>>,
+    /// Keyboard focus: the panel `Tab` last moved to and, in the approval queue, the index of
+    /// the approval `a` and `d` resolve.
+    focused_panel: FocusedPanel,
+    focused_approval: usize,
+}
+
+impl AliciaEguiView {
+    pub fn with_workspace_root(mut self, workspace_root: PathBuf) -> Self {
+        self.workspace_root = Some(workspace_root);
+        self
+    }
+
+    pub fn with_editor_config(mut self, editor_config: EditorConfig) -> Self {
+        self.editor_config = editor_config;
+        self
+    }
+
+    pub fn with_font_config(mut self, font_config: FontConfig) -> Self {
+        self.font_config = font_config;
+        self
+    }
+
+    pub fn font_config(&self) -> &FontConfig {
+        &self.font_config
+    }
+
+    /// Restores the layout saved at `layout_path` and keeps saving it there as panels change.
+    pub fn with_layout_path(mut self, layout_path: PathBuf) -> Self {
+        match LayoutState::load(&layout_path) {
+            Ok(layout) => {
+                self.persisted_layout = Some(layout.clone());
+                self.layout = layout;
+            }
+            Err(error) => {
+                self.status_message = Some(format!("Não consegui restaurar o layout: {error}"));
+            }
+        }
+        self.layout_path = Some(layout_path);
+        self
+    }
+
+    pub fn layout(&self) -> &LayoutState {
+        &self.layout
+    }
+
+    /// Actions picked in the error center (retry, open settings) for the host app to carry out.
+    pub fn take_error_actions(&mut self) -> Vec<(u64, ErrorAction)> {
+        std::mem::take(&mut self.requested_error_actions)
+    }
+
+    fn report_error(&mut self, store: &mut UiEventStore, report: ErrorReport) {
+        self.status_message = Some(report.message.clone());
+        store.record_error(report, unix_now_s());
+    }
+
+    /// `Tab` cycles the panels; in the approval queue the arrows move through the approvals
+    /// and `a`/`d` approve or deny the focused one. Keys typed into a text field are left to
+    /// it. Returns whether the focused approval changed, so the queue scrolls to it.
+    fn handle_focus_keys(
+        &mut self,
+        ctx: &egui::Context,
+        pending_approvals: &[ApprovalItem],
+        requested_resolutions: &mut Vec<(String, ApprovalResolution)>,
+    ) -> bool {
+        if !self.focused_panel.is_visible(&self.layout) {
+            self.focused_panel = self.focused_panel.next(&self.layout);
+        }
+        let last_approval = pending_approvals.len().saturating_sub(1);
+        self.focused_approval = self.focused_approval.min(last_approval);
+        let typing = ctx
+            .memory(|memory| memory.focused())
+            .is_some_and(|id| egui::text_edit::TextEditState::load(ctx, id).is_some());
+        if typing {
+            return false;
+        }
+
+        let consume = |key| ctx.input_mut(|input| input.consume_key(egui::Modifiers::NONE, key));
+        let mut handled = false;
+        if consume(egui::Key::Tab) {
+            self.focused_panel = self.focused_panel.next(&self.layout);
+            handled = true;
+        }
+        let previous_approval = self.focused_approval;
+        if self.focused_panel == FocusedPanel::Approvals && !pending_approvals.is_empty() {
+            if consume(egui::Key::ArrowUp) {
+                self.focused_approval = self.focused_approval.saturating_sub(1);
+                handled = true;
+            }
+            if consume(egui::Key::ArrowDown) {
+                self.focused_approval = (self.focused_approval + 1).min(last_approval);
+                handled = true;
+            }
+            let resolution = if consume(egui::Key::A) {
+                Some(ApprovalResolution::Approved)
+            } else if consume(egui::Key::D) {
+                Some(ApprovalResolution::Denied)
+            } else {
+                None
+            };
+            if let (Some(resolution), Some(approval)) =
+                (resolution, pending_approvals.get(self.focused_approval))
+            {
+                requested_resolutions.push((approval.action_id.clone(), resolution));
+                handled = true;
+            }
+        }
+        if handled {
+            // The same keys would otherwise also move egui's own widget focus.
+            ctx.memory_mut(|memory| memory.move_focus(egui::memory::FocusDirection::None));
+        }
+        self.focused_approval != previous_approval
+    }
+
+    /// Plays the audible alerts once per frame and flashes the window border for the visual
+    /// ones, asking for attention when the window is in the background.
+    fn show_alerts(&mut self, ctx: &egui::Context, alerts: Vec<Alert>) {
+        let now = Instant::now();
+        if let Some(alert) = alerts.iter().find(|alert| alert.channels.audible)
+            && let Err(error) = play_alert_sound()
+        {
+            self.status_message = Some(format!(
+                "Não consegui tocar o alerta de {}: {error}",
+                alert.source
+            ));
+        }
+        if let Some(alert) = alerts.iter().rev().find(|alert| alert.channels.visual) {
+            self.visual_alert_until = Some(now + VISUAL_ALERT_DURATION);
+            self.status_message = Some(format!(
+                "🔔 {}: {}",
+                alert_event_label(alert.event),
+                alert.source
+            ));
+            if !ctx.input(|input| input.focused) {
+                ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(
+                    egui::UserAttentionType::Informational,
+                ));
+            }
+        }
+        let Some(until) = self.visual_alert_until else {
+            return;
+        };
+        if now >= until {
+            self.visual_alert_until = None;
+            return;
+        }
+        let color = ctx.style().visuals.warn_fg_color;
+        ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("visual_alert"),
+        ))
+        .rect_stroke(
+            ctx.screen_rect().shrink(2.0),
+            0.0,
+            egui::Stroke::new(4.0, color),
+            egui::StrokeKind::Inside,
+        );
+        ctx.request_repaint_after(until - now);
+    }
+
+    pub fn render(&mut self, ctx: &egui::Context, store: &mut UiEventStore) -> Vec<IpcMessage> {
+        if self.applied_font_config.as_ref() != Some(&self.font_config) {
+            if let Err(error) = apply_font_config(ctx, &self.font_config) {
+                self.status_message = Some(format!("Não consegui carregar a fonte: {error}"));
+            }
+            self.applied_font_config = Some(self.font_config.clone());
+        }
+        if store.take_attention_request() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(
+                egui::UserAttentionType::Informational,
+            ));
+        }
+        self.show_alerts(ctx, store.take_alerts());
+
+        let pending_approvals: Vec<ApprovalItem> =
+            store.pending_approvals().into_iter().cloned().collect();
+        let unapplied_previews: Vec<PatchPreviewState> = store
+            .unapplied_diff_previews()
+            .into_iter()
+            .cloned()
+            .collect();
+        let timeline: Vec<TimelineEntry> = store
+            .timeline_filtered(
+                &self.timeline_kinds,
+                self.timeline_session.as_deref(),
+                &self.timeline_query,
+            )
+            .into_iter()
+            .cloned()
+            .collect();
+        let approval_history: Vec<ApprovalItem> =
+            store.approval_history().into_iter().cloned().collect();
+        let audit_records: Vec<AuditRecord> = store.audit_records().to_vec();
+        let session_ids = store.terminal_session_ids().to_vec();
+        let chat_messages: Vec<ChatMessage> = store.chat_messages().to_vec();
+        let error_entries: Vec<ErrorEntry> = store.error_center().entries().cloned().collect();
+        let status_metrics = store.status_metrics();
+        let unread_errors = status_metrics.unread_errors;
+        let mut dismissed_error_ids: Vec<u64> = Vec::new();
+        let mut toggled_full_file_views: Vec<(String, String)> = Vec::new();
+        let mut missing_symbol_contexts: Vec<(String, String)> = Vec::new();
+        let mut requested_editor_opens: Vec<EditorLocation> = Vec::new();
+        let mut copied_code_blocks: Vec<(u64, CodeBlock)> = Vec::new();
+        let mut requested_task_reports: Vec<u64> = Vec::new();
+        let mut focused_sessions: Vec<String> = Vec::new();
+        let mut requested_resolutions: Vec<(String, ApprovalResolution)> = Vec::new();
+        let mut requested_modified_approvals: Vec<(String, Vec<String>)> = Vec::new();
+        let mut requested_denials: Vec<(String, DenialReason)> = Vec::new();
+        let approval_memory_enabled = store.remembered_approvals().enabled;
+        let mut requested_remembered_approvals: Vec<String> = Vec::new();
+        let mut requested_always_allowed: Vec<String> = Vec::new();
+        let is_pending = |action_id: &String| {
+            pending_approvals
+                .iter()
+                .any(|approval| &approval.action_id == action_id)
+        };
+        self.command_edits
+            .retain(|action_id, _| is_pending(action_id));
+        self.denial_drafts
+            .retain(|action_id, _| is_pending(action_id));
+        let mut requested_copies: Vec<ClipboardExport> = Vec::new();
+        let mut requested_hunk_decisions: Vec<(String, String, String, PatchHunkDecision)> =
+            Vec::new();
+        let mut requested_bulk_hunk_decisions: Vec<(String, Option<String>, PatchHunkDecision)> =
+            Vec::new();
+        let mut requested_hunk_applies: Vec<String> = Vec::new();
+        let mut requested_audit_export: Option<AuditExporter> = None;
+        let mut emitted_messages = Vec::new();
+        let mut panic_requested = ctx.input_mut(|input| {
+            input.consume_key(
+                egui::Modifiers::CTRL | egui::Modifiers::ALT,
+                PANIC_SWITCH_KEY,
+            )
+        });
+        let approval_focus_moved =
+            self.handle_focus_keys(ctx, &pending_approvals, &mut requested_resolutions);
+
+        egui::TopBottomPanel::top("alicia_status_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let panic_button = egui::Button::new(
+                    egui::RichText::new("Pânico").strong().color(egui::Color32::WHITE),
+                )
+                .fill(egui::Color32::from_rgb(0xc6, 0x28, 0x28));
+                if ui
+                    .add(panic_button)
+                    .on_hover_text(
+                        "Muda para read_only, nega as aprovações pendentes de escrita e execução e pausa o agente (Ctrl+Alt+P).",
+                    )
+                    .clicked()
+                {
+                    panic_requested = true;
+                }
+                if store.is_agent_paused() {
+                    ui.colored_label(ui.visuals().warn_fg_color, "Agente pausado");
+                    if ui.button("Retomar agente").clicked() {
+                        store.resume_agent();
+                    }
+                }
+                ui.separator();
+                ui.label("Perfil ativo:");
+                let mut picked_profile = None;
+                egui::ComboBox::from_id_salt("alicia_permission_profile")
+                    .selected_text(store.profile().name.as_str())
+                    .show_ui(ui, |ui| {
+                        for option in PermissionProfile::ALL {
+                            let selected = store.profile() == &NamedProfile::builtin(option);
+                            if ui.selectable_label(selected, option.name()).clicked() {
+                                picked_profile = Some(option);
+                            }
+                        }
+                    });
+                match picked_profile {
+                    Some(PermissionProfile::FullAccess) => {
+                        self.full_access_confirmation = Some(String::new());
+                        if self.temporary_full_access_minutes == 0 {
+                            self.temporary_full_access_minutes =
+                                DEFAULT_TEMPORARY_FULL_ACCESS_MINUTES;
+                        }
+                    }
+                    Some(profile) => store.request_profile_change(profile),
+                    None => {}
+                }
+                if let Some(expires_at_unix_s) = store.temporary_full_access_expires_at() {
+                    let remaining = expires_at_unix_s.saturating_sub(unix_now_s()).max(0);
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "Acesso total temporário: {:02}:{:02}",
+                            remaining / 60,
+                            remaining % 60
+                        ),
+                    );
+                    ctx.request_repaint_after(Duration::from_secs(1));
+                }
+                if let Some(model) = store.selected_model() {
+                    ui.separator();
+                    ui.label(format!("Modelo: {model}"));
+                }
+                ui.separator();
+                health_indicator(ui, store.runtime_health());
+                ui.separator();
+                ui.label(format!(
+                    "Aprovações pendentes: {}",
+                    status_metrics.pending_approvals
+                ));
+                ui.separator();
+                ui.label(format!(
+                    "Sessões ativas: {}",
+                    status_metrics.running_sessions
+                ));
+                ui.separator();
+                ui.label(match status_metrics.last_event_latency {
+                    Some(latency) => format!(
+                        "Latência de eventos: {:.1} ms",
+                        latency.as_secs_f64() * 1_000.0
+                    ),
+                    None => String::from("Latência de eventos: -"),
+                });
+                if let Some(queue) = status_metrics.event_queue {
+                    ui.separator();
+                    let label = format!("Fila de eventos: {} / {}", queue.depth, queue.capacity);
+                    let response = if queue.depth >= queue.capacity {
+                        ui.colored_label(ui.visuals().warn_fg_color, label)
+                    } else {
+                        ui.label(label)
+                    };
+                    response.on_hover_text(format!(
+                        "Aplicados: {} · Pico: {} · Esperas com a fila cheia: {}",
+                        queue.applied, queue.max_depth, queue.full_waits
+                    ));
+                }
+                if status_metrics.dropped_events > 0 {
+                    ui.separator();
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("Eventos descartados: {}", status_metrics.dropped_events),
+                    );
+                }
+                if let Some(budget_bytes) = store.memory_budget_bytes() {
+                    ui.separator();
+                    let usage = store.memory_usage_estimate();
+                    let label = format!(
+                        "Memória: {} / {}",
+                        format_byte_size(usage.total()),
+                        format_byte_size(budget_bytes)
+                    );
+                    let evictions = store.memory_evictions();
+                    let response = if usage.total() > budget_bytes {
+                        ui.colored_label(ui.visuals().warn_fg_color, label)
+                    } else {
+                        ui.label(label)
+                    };
+                    response.on_hover_text(format!(
+                        "Eventos: {} · Terminal: {} · Diffs: {}\nDescartes: {} linhas, {} eventos ({})",
+                        format_byte_size(usage.events_bytes),
+                        format_byte_size(usage.scrollback_bytes),
+                        format_byte_size(usage.previews_bytes),
+                        evictions.scrollback_lines,
+                        evictions.events,
+                        format_byte_size(evictions.bytes as usize)
+                    ));
+                }
+                if !store.token_usage().is_empty() || store.budget_limit_usd().is_some() {
+                    ui.separator();
+                    let cost = format_cost_usd(store.total_cost_usd());
+                    match store.budget_limit_usd() {
+                        Some(limit) if store.total_cost_usd() >= limit => {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                format!(
+                                    "Custo: {cost} / {} (orçamento excedido)",
+                                    format_cost_usd(limit)
+                                ),
+                            );
+                        }
+                        Some(limit) => {
+                            ui.label(format!("Custo: {cost} / {}", format_cost_usd(limit)));
+                        }
+                        None => {
+                            ui.label(format!("Custo: {cost}"));
+                        }
+                    }
+                }
+                ui.separator();
+                match &status_metrics.audit_health {
+                    AuditHealth::NoWrites => {
+                        ui.label("Auditoria: sem registros");
+                    }
+                    AuditHealth::Healthy => {
+                        ui.label("Auditoria: ok");
+                    }
+                    AuditHealth::Failing {
+                        consecutive_failures,
+                        last_error,
+                    } => {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            format!("Auditoria: falhando ({consecutive_failures})"),
+                        )
+                        .on_hover_text(last_error);
+                    }
+                }
+                ui.separator();
+                let mut language = ui_language();
+                egui::ComboBox::from_id_salt("alicia_ui_language")
+                    .selected_text(language.display_name())
+                    .show_ui(ui, |ui| {
+                        for option in UiLanguage::ALL {
+                            ui.selectable_value(&mut language, option, option.display_name());
+                        }
+                    });
+                if language != ui_language() {
+                    set_ui_language(language);
+                }
+                ui.separator();
+                let badge = if unread_errors > 0 {
+                    format!("Erros ({unread_errors})")
+                } else {
+                    String::from("Erros")
+                };
+                if ui.button(badge).clicked() {
+                    self.error_center_open = !self.error_center_open;
+                }
+                if ui.button("Configurações").clicked() {
+                    self.settings_open = !self.settings_open;
+                }
+                if ui.button("Política").clicked() {
+                    if self.policy_editor.take().is_none() {
+                        let workspace_root = self
+                            .workspace_root
+                            .clone()
+                            .unwrap_or_else(|| PathBuf::from("."));
+                        match PolicyEditor::load(&workspace_root) {
+                            Ok(editor) => self.policy_editor = Some(editor),
+                            Err(error) => {
+                                self.status_message =
+                                    Some(format!("Não consegui abrir a política: {error}"));
+                            }
+                        }
+                    }
+                }
+                if ui.button("Estatísticas").clicked() {
+                    self.command_stats_open = !self.command_stats_open;
+                }
+                if ui.button("Métricas de aprovação").clicked() {
+                    self.approval_metrics_open = !self.approval_metrics_open;
+                }
+                if ui.button("Buscar").clicked() && self.output_search.take().is_none() {
+                    self.output_search = Some(String::new());
+                }
+                if ui.button("Comparar").clicked() {
+                    if self.compared_sessions.take().is_none() {
+                        match session_ids.as_slice() {
+                            [.., left, right] => {
+                                self.compared_sessions = Some((left.clone(), right.clone()));
+                            }
+                            _ => {
+                                self.status_message =
+                                    Some(String::from("Abra duas sessões para comparar."));
+                            }
+                        }
+                    }
+                }
+                if ui.button("Depuração").clicked() {
+                    self.debug_overlay_open = !self.debug_overlay_open;
+                }
+                let recent_dirs = store.recent_dirs().to_vec();
+                if !recent_dirs.is_empty() {
+                    let mut session_cwd = store.session_cwd().map(Path::to_path_buf);
+                    ui.label("Diretório:");
+                    egui::ComboBox::from_id_salt("alicia_session_cwd")
+                        .selected_text(
+                            session_cwd
+                                .as_deref()
+                                .map_or_else(|| "raiz do workspace".to_string(), |cwd| {
+                                    cwd.display().to_string()
+                                }),
+                        )
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut session_cwd, None, "raiz do workspace");
+                            for dir in recent_dirs {
+                                let label = dir.display().to_string();
+                                ui.selectable_value(&mut session_cwd, Some(dir), label);
+                            }
+                        });
+                    if session_cwd.as_deref() != store.session_cwd() {
+                        store.set_session_cwd(session_cwd);
+                    }
+                }
+                let mut review_session_env = store.review_session_env();
+                if ui
+                    .checkbox(&mut review_session_env, "Revisar ambiente antes de iniciar")
+                    .changed()
+                {
+                    store.set_review_session_env(review_session_env);
+                }
+                if let Some(status_message) = self.status_message.as_deref() {
+                    ui.separator();
+                    ui.label(status_message);
+                }
+            });
+        });
+
+        if let Some(mut typed) = self.full_access_confirmation.take() {
+            let mut open = true;
+            let mut confirmed = false;
+            let mut temporary_grant = None;
+            egui::Window::new("Confirmar acesso total")
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.label(
+                        "Com acesso total, comandos, escritas e rede rodam sem pedir aprovação.",
+                    );
+                    ui.label(format!("Digite {FULL_ACCESS_CONFIRMATION} para confirmar:"));
+                    ui.text_edit_singleline(&mut typed);
+                    ui.horizontal(|ui| {
+                        let matches = typed.trim() == FULL_ACCESS_CONFIRMATION;
+                        if ui
+                            .add_enabled(matches, egui::Button::new("Confirmar"))
+                            .clicked()
+                        {
+                            confirmed = true;
+                        }
+                        if ui.button("Cancelar").clicked() {
+                            open = false;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::DragValue::new(&mut self.temporary_full_access_minutes)
+                                .range(1..=240)
+                                .suffix(" min"),
+                        );
+                        let matches = typed.trim() == FULL_ACCESS_CONFIRMATION;
+                        if ui
+                            .add_enabled(
+                                matches,
+                                egui::Button::new(format!(
+                                    "Conceder por {} min",
+                                    self.temporary_full_access_minutes
+                                )),
+                            )
+                            .on_hover_text("Volta sozinho ao perfil atual quando o tempo acabar.")
+                            .clicked()
+                        {
+                            temporary_grant = Some(Duration::from_secs(
+                                u64::from(self.temporary_full_access_minutes) * 60,
+                            ));
+                        }
+                    });
+                });
+            if let Some(duration) = temporary_grant {
+                store.grant_temporary_full_access(duration, unix_now_s());
+            } else if confirmed {
+                store.request_profile_change(PermissionProfile::FullAccess);
+            } else if open {
+                self.full_access_confirmation = Some(typed);
+            }
+        }
+
+        let mut error_center_open = self.error_center_open;
+        egui::Window::new("Central de erros")
+            .open(&mut error_center_open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                if error_entries.is_empty() {
+                    ui.label("Nenhum erro registrado.");
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for entry in &error_entries {
+                        ui.group(|ui| {
+                            ui.label(format!(
+                                "[{}] unix={} {}",
+                                entry.code,
+                                entry.timestamp_unix_s,
+                                if entry.read { "" } else { "(novo)" }
+                            ));
+                            ui.label(&entry.message);
+                            ui.horizontal(|ui| {
+                                for action in &entry.suggested_actions {
+                                    let label = match action {
+                                        ErrorAction::Retry => "Tentar novamente",
+                                        ErrorAction::OpenSettings => "Abrir configurações",
+                                    };
+                                    if ui.button(label).clicked() {
+                                        if *action == ErrorAction::OpenSettings {
+                                            self.settings_open = true;
+                                        }
+                                        self.requested_error_actions.push((entry.id, *action));
+                                    }
+                                }
+                                if ui.button("Dispensar").clicked() {
+                                    dismissed_error_ids.push(entry.id);
+                                }
+                            });
+                        });
+                    }
+                });
+            });
+        self.error_center_open = error_center_open;
+        if self.error_center_open && unread_errors > 0 {
+            store.error_center_mut().mark_all_read();
+        }
+        for id in dismissed_error_ids {
+            store.error_center_mut().dismiss(id);
+        }
+
+        let mut settings_open = self.settings_open;
+        let mut applied_font_draft = false;
+        let mut notification_settings = store.notification_settings().clone();
+        let mut alert_settings = *store.alert_settings();
+        let mut store_config = store.config();
+        let remembered_approvals = store.remembered_approvals().clone();
+        let mut approval_memory_enabled = remembered_approvals.enabled;
+        let mut revoked_commands: Vec<Vec<String>> = Vec::new();
+        let mut revoked_targets: Vec<String> = Vec::new();
+        egui::Window::new("Configurações")
+            .open(&mut settings_open)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                let draft = self
+                    .font_config_draft
+                    .get_or_insert_with(|| self.font_config.clone());
+                ui.heading("Fonte monoespaçada");
+                ui.horizontal(|ui| {
+                    ui.label("Família:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut draft.family).hint_text("Hack (padrão)"),
+                    );
+                });
+                ui.add(egui::Slider::new(&mut draft.size, 8.0..=32.0).text("Tamanho"));
+                ui.checkbox(&mut draft.ligatures, "Ligaduras");
+                if ui.button("Aplicar").clicked() {
+                    applied_font_draft = true;
+                }
+                ui.separator();
+                ui.heading("Editor");
+                ui.horizontal(|ui| {
+                    for (label, preset) in [
+                        ("VS Code", EditorConfig::vscode()),
+                        ("JetBrains", EditorConfig::jetbrains()),
+                        ("Vim", EditorConfig::vim()),
+                    ] {
+                        if ui.small_button(label).clicked() {
+                            self.editor_config = preset;
+                        }
+                    }
+                });
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.editor_config.command)
+                        .font(egui::TextStyle::Monospace)
+                        .hint_text("code --goto {file}:{line}:{column}"),
+                );
+                ui.separator();
+                ui.heading("Painéis");
+                ui.checkbox(
+                    &mut self.layout.audit_panel_visible,
+                    "Mostrar histórico e auditoria",
+                );
+                ui.checkbox(
+                    &mut self.layout.approval_panel_visible,
+                    "Mostrar fila de aprovações",
+                );
+                ui.separator();
+                ui.heading("Notificações");
+                ui.checkbox(
+                    &mut notification_settings.approval_requested,
+                    "Novas aprovações",
+                );
+                ui.checkbox(
+                    &mut notification_settings.action_blocked,
+                    "Ações bloqueadas",
+                );
+                ui.checkbox(
+                    &mut notification_settings.long_command_finished,
+                    "Comandos longos finalizados",
+                );
+                let mut threshold_s = notification_settings.long_command_threshold.as_secs();
+                ui.add(egui::Slider::new(&mut threshold_s, 1..=600).text("Duração mínima (s)"));
+                notification_settings.long_command_threshold = Duration::from_secs(threshold_s);
+                ui.checkbox(
+                    &mut notification_settings.input_requested,
+                    "Sessões aguardando entrada",
+                );
+                ui.checkbox(
+                    &mut notification_settings.request_attention,
+                    "Pedir atenção da janela quando um comando longo terminar",
+                );
+                let mut idle_s = notification_settings.input_prompt_idle.as_secs();
+                ui.add(egui::Slider::new(&mut idle_s, 1..=120).text("Espera pelo prompt (s)"));
+                notification_settings.input_prompt_idle = Duration::from_secs(idle_s);
+                ui.separator();
+                ui.heading("Alertas");
+                egui::Grid::new("alert_settings").show(ui, |ui| {
+                    ui.label("Evento");
+                    ui.label("Som");
+                    ui.label("Visual");
+                    ui.end_row();
+                    for event in AlertEvent::ALL {
+                        let channels = alert_settings.channels_mut(event);
+                        ui.label(alert_event_label(event));
+                        ui.checkbox(&mut channels.audible, "");
+                        ui.checkbox(&mut channels.visual, "");
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+                ui.heading("Aprovações lembradas");
+                ui.checkbox(
+                    &mut approval_memory_enabled,
+                    "Lembrar aprovações entre execuções deste workspace",
+                );
+                if remembered_approvals.commands.is_empty()
+                    && remembered_approvals.targets.is_empty()
+                {
+                    ui.label("Nenhuma aprovação lembrada.");
+                }
+                for command in &remembered_approvals.commands {
+                    ui.horizontal(|ui| {
+                        if command.any_arguments {
+                            ui.monospace(format!("{} …", command.prefix.join(" ")));
+                        } else {
+                            ui.monospace(command.prefix.join(" "));
+                        }
+                        if ui.small_button("Revogar").clicked() {
+                            revoked_commands.push(command.prefix.clone());
+                        }
+                    });
+                }
+                for target in &remembered_approvals.targets {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("📁 {}", target.path));
+                        if ui.small_button("Revogar").clicked() {
+                            revoked_targets.push(target.path.clone());
+                        }
+                    });
+                }
+                ui.separator();
+                ui.heading("Limites");
+                ui.add(
+                    egui::Slider::new(&mut store_config.max_scrollback_lines, 100..=100_000)
+                        .logarithmic(true)
+                        .text("Linhas por terminal"),
+                );
+                let mut scrollback_bytes_enabled = store_config.max_scrollback_bytes.is_some();
+                ui.checkbox(&mut scrollback_bytes_enabled, "Limitar bytes por terminal");
+                if scrollback_bytes_enabled {
+                    let mut scrollback_mb = store_config
+                        .max_scrollback_bytes
+                        .map_or(DEFAULT_SCROLLBACK_MB, |bytes| {
+                            (bytes / (1024 * 1024)).max(1)
+                        });
+                    ui.add(
+                        egui::Slider::new(&mut scrollback_mb, 1..=1_024)
+                            .logarithmic(true)
+                            .text("Memória por terminal (MB)"),
+                    );
+                    store_config.max_scrollback_bytes = Some(scrollback_mb * 1024 * 1024);
+                } else {
+                    store_config.max_scrollback_bytes = None;
+                }
+                ui.add(
+                    egui::Slider::new(&mut store_config.max_timeline_entries, 100..=100_000)
+                        .logarithmic(true)
+                        .text("Entradas na timeline"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut store_config.output_preview_max_columns, 20..=500)
+                        .text("Prévia de saída (colunas)"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut store_config.max_approval_requests_per_minute, 1..=300)
+                        .text("Aprovações por minuto"),
+                );
+                let mut budget_enabled = store_config.memory_budget_bytes.is_some();
+                ui.checkbox(&mut budget_enabled, "Limitar memória");
+                if budget_enabled {
+                    let mut budget_mb = store_config
+                        .memory_budget_bytes
+                        .map_or(DEFAULT_MEMORY_BUDGET_MB, |bytes| bytes / (1024 * 1024));
+                    ui.add(egui::Slider::new(&mut budget_mb, 16..=4_096).text("Memória (MB)"));
+                    store_config.memory_budget_bytes = Some(budget_mb * 1024 * 1024);
+                } else {
+                    store_config.memory_budget_bytes = None;
+                }
+            });
+        if &alert_settings != store.alert_settings() {
+            store.set_alert_settings(alert_settings);
+        }
+        store.set_approval_memory_enabled(approval_memory_enabled);
+        for prefix in revoked_commands {
+            if store.revoke_remembered_command(&prefix) {
+                self.status_message =
+                    Some(format!("Aprovação de `{}` revogada.", prefix.join(" ")));
+            }
+        }
+        for path in revoked_targets {
+            if store.revoke_remembered_target(&path) {
+                self.status_message = Some(format!("Aprovação de {path} revogada."));
+            }
+        }
+        if &notification_settings != store.notification_settings() {
+            store.set_notification_settings(notification_settings);
+        }
+        if store_config != store.config() {
+            store.set_config(store_config);
+        }
+        self.settings_open = settings_open;
+        if applied_font_draft && let Some(draft) = self.font_config_draft.take() {
+            self.font_config = draft;
+        }
+        if !self.settings_open {
+            self.font_config_draft = None;
+        }
+
+        if let Some(editor) = self.policy_editor.as_mut() {
+            let mut policy_editor_open = true;
+            let saved = egui::Window::new("Política do projeto")
+                .open(&mut policy_editor_open)
+                .default_width(420.0)
+                .vscroll(true)
+                .show(ctx, |ui| editor.show(ui))
+                .and_then(|response| response.inner)
+                .flatten();
+            if let Some(path) = saved {
+                store.record_policy_saved(&path, editor.grants_full_access());
+            }
+            if !policy_editor_open {
+                self.policy_editor = None;
+            }
+        }
+
+        let command_stats = store.command_stats();
+        egui::Window::new("Estatísticas de comandos")
+            .open(&mut self.command_stats_open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                if command_stats.is_empty() {
+                    ui.label("Nenhum comando finalizado ainda.");
+                    return;
+                }
+                egui::Grid::new("alicia_command_stats")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Comando");
+                        ui.strong("Execuções");
+                        ui.strong("Duração média");
+                        ui.strong("Falhas");
+                        ui.strong("Tendência");
+                        ui.end_row();
+                        for stats in &command_stats {
+                            ui.monospace(&stats.command);
+                            ui.label(stats.runs.to_string());
+                            ui.label(format!("{:.1} s", stats.average_duration().as_secs_f64()));
+                            ui.label(format!("{:.0}%", stats.failure_rate() * 100.0));
+                            match stats.slowdown() {
+                                Some(slowdown) if slowdown >= 2.0 => {
+                                    ui.colored_label(
+                                        ui.visuals().warn_fg_color,
+                                        format!("{slowdown:.1}x mais lento"),
+                                    );
+                                }
+                                Some(slowdown) => {
+                                    ui.label(format!("{slowdown:.1}x"));
+                                }
+                                None => {
+                                    ui.label("-");
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        let approval_metrics = store.approval_metrics();
+        egui::Window::new("Métricas de aprovação")
+            .open(&mut self.approval_metrics_open)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                let seconds = |duration: Option<Duration>| {
+                    duration.map_or_else(
+                        || String::from("-"),
+                        |duration| format!("{:.1} s", duration.as_secs_f64()),
+                    )
+                };
+                ui.label(format!(
+                    "Pendentes: {} · Mais antiga esperando: {}",
+                    approval_metrics.pending,
+                    seconds(approval_metrics.oldest_pending_wait)
+                ));
+                ui.label(format!(
+                    "Resolvidas: {} · Espera total: {} · Vazão: {}",
+                    approval_metrics.resolved(),
+                    seconds(Some(approval_metrics.total_wait())),
+                    approval_metrics
+                        .resolved_per_hour
+                        .map_or_else(|| String::from("-"), |rate| format!("{rate:.1}/h"))
+                ));
+                if approval_metrics.by_kind.is_empty() {
+                    ui.label("Nenhuma aprovação resolvida ainda.");
+                    return;
+                }
+                egui::Grid::new("alicia_approval_metrics")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in [
+                            "Ação",
+                            "Resolvidas",
+                            "Aprovadas",
+                            "Negadas",
+                            "Expiradas",
+                            "p50",
+                            "p90",
+                            "p99",
+                        ] {
+                            ui.strong(header);
+                        }
+                        ui.end_row();
+                        for stats in &approval_metrics.by_kind {
+                            ui.label(stats.action_kind.map_or("sem ação", action_kind_name));
+                            ui.label(stats.resolved().to_string());
+                            ui.label(stats.approved.to_string());
+                            ui.label(stats.denied.to_string());
+                            ui.label(stats.expired.to_string());
+                            for percentile in [50.0, 90.0, 99.0] {
+                                ui.label(seconds(stats.percentile(percentile)));
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if let Some((mut left, mut right)) = self.compared_sessions.take() {
+            let mut open = true;
+            egui::Window::new("Comparar sessões")
+                .open(&mut open)
+                .default_width(560.0)
+                .vscroll(true)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        for (salt, picked) in [
+                            ("alicia_compare_left", &mut left),
+                            ("alicia_compare_right", &mut right),
+                        ] {
+                            egui::ComboBox::from_id_salt(salt)
+                                .selected_text(picked.as_str())
+                                .show_ui(ui, |ui| {
+                                    for session_id in &session_ids {
+                                        ui.selectable_value(
+                                            picked,
+                                            session_id.clone(),
+                                            session_id.as_str(),
+                                        );
+                                    }
+                                });
+                        }
+                        ui.checkbox(&mut self.comparison_only_differences, "Só diferenças");
+                    });
+                    let Some(comparison) = compare_sessions(store, &left, &right) else {
+                        ui.label("Sessão não encontrada.");
+                        return;
+                    };
+                    egui::Grid::new("alicia_session_comparison")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("Campo");
+                            for session_id in
+                                [&comparison.left_session_id, &comparison.right_session_id]
+                            {
+                                ui.label(session_text(store, session_id, session_id).strong());
+                            }
+                            ui.end_row();
+                            for row in &comparison.rows {
+                                if self.comparison_only_differences && !row.differs() {
+                                    continue;
+                                }
+                                ui.label(comparison_field_label(&row.field));
+                                for value in [&row.left, &row.right] {
+                                    let text = value.as_deref().unwrap_or("-");
+                                    if row.differs() {
+                                        ui.colored_label(ui.visuals().warn_fg_color, text);
+                                    } else {
+                                        ui.monospace(text);
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            if open {
+                self.compared_sessions = Some((left, right));
+            }
+        }
+
+        if let Some(mut query) = self.output_search.take() {
+            let mut open = true;
+            let mut picked = None;
+            egui::Window::new("Buscar na saída das sessões")
+                .open(&mut open)
+                .default_width(560.0)
+                .vscroll(true)
+                .show(ctx, |ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut query)
+                            .hint_text("error E0502")
+                            .desired_width(f32::INFINITY),
+                    );
+                    if query.trim().is_empty() {
+                        return;
+                    }
+                    let hits = store.search_all_output(query.trim());
+                    if hits.is_empty() {
+                        ui.label("Nenhuma linha encontrada.");
+                        return;
+                    }
+                    if hits.len() >= MAX_OUTPUT_SEARCH_HITS {
+                        ui.label(format!(
+                            "Mostrando as primeiras {MAX_OUTPUT_SEARCH_HITS} linhas; refine a busca."
+                        ));
+                    }
+                    for hit in hits {
+                        let label = format!("{}:{}  {}", hit.session_id, hit.line_no, hit.line);
+                        if ui
+                            .selectable_label(false, egui::RichText::new(label).monospace())
+                            .clicked()
+                        {
+                            picked = Some(hit);
+                        }
+                    }
+                });
+            if let Some(hit) = picked {
+                match store.set_active_session(&hit.session_id) {
+                    Ok(()) => {
+                        self.terminal_search_target =
+                            Some((hit.session_id, hit.line_no.saturating_sub(1), false));
+                    }
+                    Err(error) => self.report_error(store, error.error_report()),
+                }
+            }
+            if open {
+                self.output_search = Some(query);
+            }
+        }
+
+        let staged_sessions: Vec<SessionStartRequest> = store.staged_sessions().to_vec();
+        self.env_editors.retain(|session_id, _| {
+            staged_sessions
+                .iter()
+                .any(|request| &request.session_id == session_id)
+        });
+        for request in &staged_sessions {
+            let editor = self
+                .env_editors
+                .entry(request.session_id.clone())
+                .or_insert_with(|| SessionEnvEditor::new(request));
+            let mut launch = false;
+            let mut discard = false;
+            egui::Window::new(format!("Ambiente da sessão {}", request.session_id))
+                .id(egui::Id::new(("alicia_session_env", &request.session_id)))
+                .default_width(520.0)
+                .vscroll(true)
+                .show(ctx, |ui| {
+                    ui.monospace(request.audit_context.target.as_str());
+                    ui.separator();
+                    editor.show(ui);
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        launch = ui.button("Iniciar").clicked();
+                        discard = ui.button("Descartar").clicked();
+                    });
+                });
+            if launch {
+                store.launch_staged_session(&request.session_id, editor.edits());
+            } else if discard {
+                store.discard_staged_session(&request.session_id);
+            }
+        }
+
+        egui::Window::new("Depuração")
+            .open(&mut self.debug_overlay_open)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("alicia_debug_overlay")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Canal de eventos");
+                        ui.label(format!("{} eventos", status_metrics.event_channel_capacity));
+                        ui.end_row();
+                        ui.label("Eventos descartados");
+                        ui.label(status_metrics.dropped_events.to_string());
+                        ui.end_row();
+                        for (subscriber, lagged) in &status_metrics.lagged_subscribers {
+                            ui.label(format!("Atraso de {subscriber}"));
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                format!("{lagged} eventos perdidos"),
+                            );
+                            ui.end_row();
+                        }
+                        if let Some(queue) = status_metrics.event_queue {
+                            ui.label("Fila de aplicação");
+                            ui.label(format!(
+                                "{} / {} (pico {})",
+                                queue.depth, queue.capacity, queue.max_depth
+                            ));
+                            ui.end_row();
+                            ui.label("Esperas com a fila cheia");
+                            ui.label(queue.full_waits.to_string());
+                            ui.end_row();
+                        }
+                    });
+                if status_metrics.lagged_subscribers.is_empty() {
+                    ui.label("Nenhum assinante perdeu eventos.");
+                } else {
+                    ui.label("Aumente a capacidade do canal se os atrasos se repetirem.");
+                }
+            });
+
+        let mut selected_model: Option<String> = None;
+        let audit_panel = egui::SidePanel::left("alicia_audit_history")
+            .resizable(true)
+            .default_width(self.layout.audit_panel_width)
+            .show_animated(ctx, self.layout.audit_panel_visible, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    if !store.available_models().is_empty() {
+                        ui.heading("Modelo");
+                        let mut selected = store.selected_model().unwrap_or_default().to_string();
+                        egui::ComboBox::from_id_salt("alicia_agent_model")
+                            .selected_text(selected.clone())
+                            .show_ui(ui, |ui| {
+                                for model in store.available_models() {
+                                    ui.selectable_value(
+                                        &mut selected,
+                                        model.id.clone(),
+                                        format!("{} ({})", model.id, model.provider),
+                                    );
+                                }
+                            });
+                        if store.selected_model() != Some(selected.as_str()) {
+                            selected_model = Some(selected);
+                        }
+                        ui.separator();
+                    }
+                    panel_heading(ui, FocusedPanel::Audit, self.focused_panel);
+                    ui.separator();
+                    if approval_history.is_empty() {
+                        ui.label("Nenhuma aprovação resolvida.");
+                    }
+                    for approval in &approval_history {
+                        ui.group(|ui| {
+                            ui.label(format!("Ação: {}", approval.action_id));
+                            ui.label(format!(
+                                "Decisão: {}",
+                                approval_status_name(approval.status)
+                            ));
+                            if let Some(sequence) = approval.resolved_sequence {
+                                ui.label(format!("Evento: #{sequence}"));
+                            }
+                            if let Some(approver) = &approval.approver {
+                                ui.label(format!(
+                                    "Aprovador: {} ({})",
+                                    approver.name,
+                                    approver_source_name(approver.source)
+                                ));
+                            }
+                            if let Some(reason) = &approval.denial_reason {
+                                let category = denial_category_label(reason.category);
+                                ui.label(match &reason.detail {
+                                    Some(detail) => format!("Motivo: {category} — {detail}"),
+                                    None => format!("Motivo: {category}"),
+                                });
+                            }
+                            let linked_records = audit_records
+                                .iter()
+                                .filter(|record| {
+                                    record.action_id.as_deref() == Some(approval.action_id.as_str())
+                                })
+                                .count();
+                            ui.label(format!("Registros de auditoria: {linked_records}"));
+                        });
+                    }
+
+                    ui.heading("Auditoria");
+                    ui.separator();
+                    ui.collapsing("Exportar auditoria", |ui| {
+                        let export = &mut self.audit_export;
+                        egui::ComboBox::from_id_salt("alicia_audit_export_format")
+                            .selected_text(export.format.extension().to_uppercase())
+                            .show_ui(ui, |ui| {
+                                for format in AuditExportFormat::ALL {
+                                    ui.selectable_value(
+                                        &mut export.format,
+                                        format,
+                                        format.extension().to_uppercase(),
+                                    );
+                                }
+                            });
+                        egui::ComboBox::from_id_salt("alicia_audit_export_session")
+                            .selected_text(
+                                export
+                                    .filter
+                                    .session_id
+                                    .clone()
+                                    .unwrap_or_else(|| "todas as sessões".to_string()),
+                            )
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(
+                                    &mut export.filter.session_id,
+                                    None,
+                                    "todas as sessões",
+                                );
+                                for session_id in &session_ids {
+                                    ui.selectable_value(
+                                        &mut export.filter.session_id,
+                                        Some(session_id.clone()),
+                                        session_id.as_str(),
+                                    );
+                                }
+                            });
+                        ui.horizontal_wrapped(|ui| {
+                            for action_kind in [
+                                ActionKind::ReadFile,
+                                ActionKind::WriteFile,
+                                ActionKind::ExecuteCommand,
+                                ActionKind::ApplyPatch,
+                                ActionKind::NetworkAccess,
+                            ] {
+                                let kinds = &mut export.filter.action_kinds;
+                                let mut selected = kinds.contains(&action_kind);
+                                if ui
+                                    .checkbox(&mut selected, action_kind_name(action_kind))
+                                    .changed()
+                                {
+                                    if selected {
+                                        kinds.push(action_kind);
+                                    } else {
+                                        kinds.retain(|kind| *kind != action_kind);
+                                    }
+                                }
+                            }
+                        });
+                        ui.horizontal_wrapped(|ui| {
+                            for result_status in [
+                                ResultStatus::Succeeded,
+                                ResultStatus::Failed,
+                                ResultStatus::Blocked,
+                            ] {
+                                let statuses = &mut export.filter.result_statuses;
+                                let mut selected = statuses.contains(&result_status);
+                                if ui
+                                    .checkbox(&mut selected, result_status_name(result_status))
+                                    .changed()
+                                {
+                                    if selected {
+                                        statuses.push(result_status);
+                                    } else {
+                                        statuses.retain(|status| *status != result_status);
+                                    }
+                                }
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Últimas horas (0 = tudo):");
+                            ui.add(egui::DragValue::new(&mut self.audit_export_hours));
+                        });
+                        if ui.button("Exportar auditoria").clicked() {
+                            let since_unix_s = (self.audit_export_hours > 0)
+                                .then(|| unix_now_s() - i64::from(self.audit_export_hours) * 3600);
+                            let mut exporter = self.audit_export.clone();
+                            exporter.filter.since_unix_s = since_unix_s;
+                            requested_audit_export = Some(exporter);
+                        }
+                    });
+                    if audit_records.is_empty() {
+                        ui.label("Nenhum registro de auditoria.");
+                    }
+                    for record in &audit_records {
+                        ui.group(|ui| {
+                            ui.label(format!("Sessão: {}", record.session_id));
+                            ui.label(format!("Tipo: {}", action_kind_name(record.action_kind)));
+                            ui.label(format!("Onde: {}", record.target));
+                            ui.label(format!(
+                                "Resultado: {}",
+                                result_status_name(record.result_status)
+                            ));
+                            match (record.action_id.as_deref(), record.event_sequence) {
+                                (Some(action_id), Some(sequence)) => {
+                                    ui.label(format!(
+                                        "Ação vinculada: {action_id} (evento #{sequence})"
+                                    ));
+                                }
+                                (Some(action_id), None) => {
+                                    ui.label(format!("Ação vinculada: {action_id}"));
+                                }
+                                (None, _) => {
+                                    ui.label("Ação vinculada: nenhuma");
+                                }
+                            }
+                        });
+                    }
+                });
+            });
+
+        if let Some(audit_panel) = audit_panel {
+            self.layout.audit_panel_width = audit_panel.response.rect.width();
+        }
+        if let Some(model) = selected_model
+            && let Err(error) = store.select_model(&model)
+        {
+            self.report_error(store, error.error_report());
+        }
+
+        let approval_panel_visible = self.layout.approval_panel_visible;
+        let approval_panel = egui::SidePanel::right("alicia_approval_queue")
+            .resizable(true)
+            .default_width(self.layout.approval_panel_width)
+            .show_animated(ctx, approval_panel_visible, |ui| {
+                panel_heading(ui, FocusedPanel::Approvals, self.focused_panel);
+                ui.separator();
+
+                if pending_approvals.is_empty() {
+                    ui.label("Sem aprovações pendentes.");
+                } else {
+                    let now_unix_s = unix_now_s();
+                    ctx.request_repaint_after(Duration::from_secs(1));
+                    if self.focused_panel == FocusedPanel::Approvals {
+                        ui.small("↑/↓ escolhe, A aprova, D rejeita, Tab troca de painel.");
+                    }
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for (index, approval) in pending_approvals.iter().enumerate() {
+                            let focused = self.focused_panel == FocusedPanel::Approvals
+                                && index == self.focused_approval;
+                            let mut frame = egui::Frame::group(ui.style());
+                            if focused {
+                                frame = frame.stroke(ui.visuals().selection.stroke);
+                            }
+                            let group = frame.show(ui, |ui| {
+                                ui.label(format!("Ação: {}", approval.action_id));
+                                ui.label(format!("O que: {}", approval.summary));
+                                if approval.occurrences > 1 {
+                                    ui.colored_label(
+                                        ui.visuals().warn_fg_color,
+                                        format!(
+                                            "Pedido {} vezes; a decisão vale para todos.",
+                                            approval.occurrences
+                                        ),
+                                    );
+                                }
+
+                                if let Some(action_kind) = approval.action_kind {
+                                    ui.label(format!("Tipo: {}", action_kind_name(action_kind)));
+                                }
+
+                                if let Some(target) = approval.target.as_deref() {
+                                    ui.label(format!("Onde: {target}"));
+                                }
+
+                                if let Some(command) = approval.command.as_ref() {
+                                    ui.label(format!("Comando: {}", command.join(" ")));
+                                    for action_kind in inferred_command_action_kinds(command) {
+                                        ui.colored_label(
+                                            ui.visuals().warn_fg_color,
+                                            command_action_kind_warning(action_kind),
+                                        );
+                                    }
+                                    match self.command_edits.get_mut(&approval.action_id) {
+                                        Some(tokens) => {
+                                            let mut removed = None;
+                                            ui.horizontal_wrapped(|ui| {
+                                                for (index, token) in tokens.iter_mut().enumerate()
+                                                {
+                                                    ui.add(
+                                                        egui::TextEdit::singleline(token)
+                                                            .desired_width(80.0)
+                                                            .code_editor(),
+                                                    );
+                                                    if ui.small_button("✕").clicked() {
+                                                        removed = Some(index);
+                                                    }
+                                                }
+                                                if ui.small_button("+").clicked() {
+                                                    tokens.push(String::new());
+                                                }
+                                            });
+                                            if let Some(index) = removed {
+                                                tokens.remove(index);
+                                            }
+                                            ui.horizontal(|ui| {
+                                                if ui.button("Aprovar com alteração").clicked() {
+                                                    requested_modified_approvals.push((
+                                                        approval.action_id.clone(),
+                                                        tokens.clone(),
+                                                    ));
+                                                }
+                                                if ui.button("Cancelar edição").clicked() {
+                                                    self.command_edits.remove(&approval.action_id);
+                                                }
+                                            });
+                                        }
+                                        None => {
+                                            if ui.small_button("Editar comando").clicked() {
+                                                self.command_edits.insert(
+                                                    approval.action_id.clone(),
+                                                    command.clone(),
+                                                );
+                                            }
+                                        }
+                                    }
+                                }
+
+                                if approval.impact_files.is_empty() {
+                                    ui.label("Impacto: sem diff informado");
+                                } else {
+                                    ui.label(format!(
+                                        "Impacto: {} arquivo(s)",
+                                        approval.impact_files.len()
+                                    ));
+                                    for file in &approval.impact_files {
+                                        ui.horizontal(|ui| {
+                                            ui.label(format!("- {file}"));
+                                            if ui.small_button("Abrir").clicked() {
+                                                requested_editor_opens
+                                                    .push(EditorLocation::file(file.as_str()));
+                                            }
+                                        });
+                                    }
+                                }
+
+                                let remaining =
+                                    approval.expires_at_unix_s.saturating_sub(now_unix_s).max(0);
+                                let expiry = format!(
+                                    "Expira em {} (status: {})",
+                                    format_countdown(remaining),
+                                    approval_status_name(approval.status)
+                                );
+                                if remaining < APPROVAL_COUNTDOWN_WARNING_S {
+                                    ui.colored_label(ui.visuals().warn_fg_color, expiry);
+                                } else {
+                                    ui.label(expiry);
+                                }
+
+                                ui.horizontal(|ui| {
+                                    if ui.button("Aprovar").clicked() {
+                                        requested_resolutions.push((
+                                            approval.action_id.clone(),
+                                            ApprovalResolution::Approved,
+                                        ));
+                                    }
+                                    if approval_memory_enabled
+                                        && (approval.command.is_some()
+                                            || approval.target.is_some())
+                                        && ui
+                                            .button("Aprovar e lembrar")
+                                            .on_hover_text(
+                                                "Aprova também nas próximas execuções deste workspace.",
+                                            )
+                                            .clicked()
+                                    {
+                                        requested_remembered_approvals
+                                            .push(approval.action_id.clone());
+                                    }
+                                    if approval.command.is_some()
+                                        && ui
+                                            .button("Sempre permitir")
+                                            .on_hover_text(
+                                                "Aprova e adiciona uma regra à política do projeto para não perguntar de novo por este comando.",
+                                            )
+                                            .clicked()
+                                    {
+                                        requested_always_allowed
+                                            .push(approval.action_id.clone());
+                                    }
+                                    if ui.button("Rejeitar").clicked() {
+                                        requested_resolutions.push((
+                                            approval.action_id.clone(),
+                                            ApprovalResolution::Denied,
+                                        ));
+                                    }
+                                    if !self.denial_drafts.contains_key(&approval.action_id)
+                                        && ui.small_button("Rejeitar com motivo").clicked()
+                                    {
+                                        self.denial_drafts.insert(
+                                            approval.action_id.clone(),
+                                            (DenialCategory::TooRisky, String::new()),
+                                        );
+                                    }
+                                });
+                                if let Some((category, detail)) =
+                                    self.denial_drafts.get_mut(&approval.action_id)
+                                {
+                                    let mut cancelled = false;
+                                    ui.horizontal(|ui| {
+                                        egui::ComboBox::from_id_salt((
+                                            "alicia_denial_category",
+                                            &approval.action_id,
+                                        ))
+                                        .selected_text(denial_category_label(*category))
+                                        .show_ui(
+                                            ui,
+                                            |ui| {
+                                                for option in [
+                                                    DenialCategory::TooRisky,
+                                                    DenialCategory::WrongApproach,
+                                                    DenialCategory::NeedsTests,
+                                                    DenialCategory::Other,
+                                                ] {
+                                                    ui.selectable_value(
+                                                        category,
+                                                        option,
+                                                        denial_category_label(option),
+                                                    );
+                                                }
+                                            },
+                                        );
+                                        ui.add(
+                                            egui::TextEdit::singleline(detail)
+                                                .hint_text("Detalhe (opcional)"),
+                                        );
+                                    });
+                                    ui.horizontal(|ui| {
+                                        if ui.button("Confirmar rejeição").clicked() {
+                                            requested_denials.push((
+                                                approval.action_id.clone(),
+                                                DenialReason::new(*category)
+                                                    .with_detail(detail.clone()),
+                                            ));
+                                        }
+                                        cancelled = ui.button("Cancelar").clicked();
+                                    });
+                                    if cancelled {
+                                        self.denial_drafts.remove(&approval.action_id);
+                                    }
+                                }
+                                ui.horizontal(|ui| {
+                                    if ui.small_button("Copiar resumo").clicked() {
+                                        requested_copies.push(ClipboardExport::ApprovalSummary(
+                                            approval.action_id.clone(),
+                                        ));
+                                    }
+                                    if !approval.impact_files.is_empty()
+                                        && ui.small_button("Copiar lista de arquivos").clicked()
+                                    {
+                                        requested_copies.push(ClipboardExport::FileList(
+                                            approval.action_id.clone(),
+                                        ));
+                                    }
+                                    if unapplied_previews.iter().any(|preview| {
+                                        preview.action_id == approval.action_id
+                                            && !preview.file_previews.is_empty()
+                                    }) && ui.small_button("Copiar como patch").clicked()
+                                    {
+                                        requested_copies.push(ClipboardExport::Patch(
+                                            approval.action_id.clone(),
+                                        ));
+                                    }
+                                });
+                            });
+                            if focused && approval_focus_moved {
+                                group.response.scroll_to_me(None);
+                            }
+                            ui.separator();
+                        }
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.heading("Diff Preview");
+                    ui.checkbox(&mut self.side_by_side_diff, "Lado a lado");
+                });
+                ui.separator();
+                if unapplied_previews.is_empty() {
+                    ui.label("Nenhum diff pendente de aplicação.");
+                } else {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for preview in &unapplied_previews {
+                            ui.group(|ui| {
+                                ui.label(format!("Ação: {}", preview.action_id));
+                                ui.label(format!("Arquivos: {}", preview.files.len()));
+                                ui.horizontal(|ui| {
+                                    if !preview.file_previews.is_empty()
+                                        && ui.small_button("Copiar como patch").clicked()
+                                    {
+                                        requested_copies.push(ClipboardExport::Patch(
+                                            preview.action_id.clone(),
+                                        ));
+                                    }
+                                    if ui.small_button("Copiar lista de arquivos").clicked() {
+                                        requested_copies.push(ClipboardExport::FileList(
+                                            preview.action_id.clone(),
+                                        ));
+                                    }
+                                });
+                                if !preview.file_previews.is_empty() {
+                                    ui.horizontal(|ui| {
+                                        for (label, decision) in [
+                                            (
+                                                "Aprovar todos os blocos",
+                                                PatchHunkDecision::Approved,
+                                            ),
+                                            (
+                                                "Rejeitar todos os blocos",
+                                                PatchHunkDecision::Rejected,
+                                            ),
+                                        ] {
+                                            if ui.button(label).clicked() {
+                                                requested_bulk_hunk_decisions.push((
+                                                    preview.action_id.clone(),
+                                                    None,
+                                                    decision,
+                                                ));
+                                            }
+                                        }
+                                        let hunks = preview
+                                            .file_previews
+                                            .iter()
+                                            .flat_map(|file| &file.hunks);
+                                        let decided = hunks.clone().all(|hunk| {
+                                            hunk.decision != PatchHunkDecision::Pending
+                                        });
+                                        let any_approved = hunks.clone().any(|hunk| {
+                                            hunk.decision == PatchHunkDecision::Approved
+                                        });
+                                        if ui
+                                            .add_enabled(
+                                                decided && any_approved,
+                                                egui::Button::new("Aplicar blocos aprovados"),
+                                            )
+                                            .on_disabled_hover_text(
+                                                "Decida todos os blocos e aprove pelo menos um.",
+                                            )
+                                            .clicked()
+                                        {
+                                            requested_hunk_applies
+                                                .push(preview.action_id.clone());
+                                        }
+                                    });
+                                }
+                                if preview.file_previews.is_empty() {
+                                    for file in &preview.files {
+                                        ui.label(format!("- {file}"));
+                                    }
+                                } else {
+                                    for file_preview in &preview.file_previews {
+                                        ui.separator();
+                                        ui.label(format!("Arquivo: {}", file_preview.file_path));
+
+                                        if file_preview.hunks.is_empty() {
+                                            ui.label(
+                                                "Sem blocos (hunks) detalhados para este arquivo.",
+                                            );
+                                            continue;
+                                        }
+                                        ui.horizontal(|ui| {
+                                            for (label, decision) in [
+                                                (
+                                                    "Aprovar blocos do arquivo",
+                                                    PatchHunkDecision::Approved,
+                                                ),
+                                                (
+                                                    "Rejeitar blocos do arquivo",
+                                                    PatchHunkDecision::Rejected,
+                                                ),
+                                            ] {
+                                                if ui.button(label).clicked() {
+                                                    requested_bulk_hunk_decisions.push((
+                                                        preview.action_id.clone(),
+                                                        Some(file_preview.file_path.clone()),
+                                                        decision,
+                                                    ));
+                                                }
+                                            }
+                                        });
+
+                                        let view_key = (
+                                            preview.action_id.clone(),
+                                            file_preview.file_path.clone(),
+                                        );
+                                        let full_file = self.full_file_views.get(&view_key);
+                                        let toggle_label = if full_file.is_some() {
+                                            "Ocultar arquivo completo"
+                                        } else {
+                                            "Ver arquivo completo"
+                                        };
+                                        if ui.button(toggle_label).clicked() {
+                                            toggled_full_file_views.push(view_key.clone());
+                                        }
+                                        let symbol_contexts = self.symbol_contexts.get(&view_key);
+                                        if symbol_contexts.is_none() {
+                                            missing_symbol_contexts.push(view_key.clone());
+                                        }
+                                        if let Some(full_file) = full_file {
+                                            render_full_file_context(
+                                                ui,
+                                                &preview.action_id,
+                                                full_file,
+                                                &file_preview.hunks,
+                                                &mut requested_hunk_decisions,
+                                            );
+                                            continue;
+                                        }
+
+                                        for hunk in &file_preview.hunks {
+                                            ui.group(|ui| {
+                                                ui.label(format!("Bloco: {}", hunk.hunk_id));
+                                                ui.label(
+                                                    egui::RichText::new(hunk.header.as_str())
+                                                        .monospace(),
+                                                );
+                                                if let Some(context) =
+                                                    symbol_contexts.and_then(|contexts| {
+                                                        contexts.iter().find(|context| {
+                                                            context.hunk_id == hunk.hunk_id
+                                                        })
+                                                    })
+                                                {
+                                                    for symbol in &context.symbols {
+                                                        ui.label(
+                                                            egui::RichText::new(
+                                                                modified_symbol_summary(symbol),
+                                                            )
+                                                            .monospace(),
+                                                        );
+                                                    }
+                                                }
+                                                render_hunk_lines(
+                                                    ui,
+                                                    (
+                                                        preview.action_id.as_str(),
+                                                        file_preview.file_path.as_str(),
+                                                        hunk.hunk_id.as_str(),
+                                                    ),
+                                                    hunk,
+                                                    self.side_by_side_diff,
+                                                );
+                                                ui.label(format!(
+                                                    "Impacto: +{} / -{}",
+                                                    hunk.added_lines, hunk.removed_lines
+                                                ));
+                                                ui.label(format!(
+                                                    "Decisão: {}",
+                                                    patch_hunk_decision_name(hunk.decision)
+                                                ));
+
+                                                ui.horizontal(|ui| {
+                                                    if ui.button("Aprovar bloco").clicked() {
+                                                        requested_hunk_decisions.push((
+                                                            preview.action_id.clone(),
+                                                            file_preview.file_path.clone(),
+                                                            hunk.hunk_id.clone(),
+                                                            PatchHunkDecision::Approved,
+                                                        ));
+                                                    }
+                                                    if ui.button("Rejeitar bloco").clicked() {
+                                                        requested_hunk_decisions.push((
+                                                            preview.action_id.clone(),
+                                                            file_preview.file_path.clone(),
+                                                            hunk.hunk_id.clone(),
+                                                            PatchHunkDecision::Rejected,
+                                                        ));
+                                                    }
+                                                    if ui.button("Abrir no editor").clicked() {
+                                                        requested_editor_opens.push(
+                                                            EditorLocation::at_line(
+                                                                file_preview.file_path.as_str(),
+                                                                hunk.old_start.max(1),
+                                                            ),
+                                                        );
+                                                    }
+                                                });
+                                            });
+                                        }
+                                    }
+                                }
+                            });
+                            ui.separator();
+                        }
+                    });
+                }
+            });
+
+        if let Some(approval_panel) = approval_panel {
+            self.layout.approval_panel_width = approval_panel.response.rect.width();
+        }
+
+        for view_key in missing_symbol_contexts {
+            let hunks = store
+                .diff_preview(&view_key.0)
+                .and_then(|preview| {
+                    preview
+                        .file_previews
+                        .iter()
+                        .find(|file| file.file_path == view_key.1)
+                })
+                .map_or_else(Vec::new, |file| file.hunks.clone());
+            let workspace_root = self
+                .workspace_root
+                .clone()
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_else(|| PathBuf::from("."));
+            // Failures are cached as empty so the workspace is not rescanned every frame.
+            let contexts = load_hunk_symbol_contexts(&workspace_root, &view_key.1, &hunks)
+                .unwrap_or_else(|error| {
+                    self.status_message = Some(format!("Não consegui analisar símbolos: {error}"));
+                    Vec::new()
+                });
+            self.symbol_contexts.insert(view_key, contexts);
+        }
+
+        for (action_id, file_path) in toggled_full_file_views {
+            let view_key = (action_id, file_path);
+            if self.full_file_views.remove(&view_key).is_some() {
+                continue;
+            }
+            let hunks = store
+                .diff_preview(&view_key.0)
+                .and_then(|preview| {
+                    preview
+                        .file_previews
+                        .iter()
+                        .find(|file| file.file_path == view_key.1)
+                })
+                .map_or_else(Vec::new, |file| file.hunks.clone());
+            let workspace_root = self
+                .workspace_root
+                .clone()
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_else(|| PathBuf::from("."));
+            match FullFileContext::load(&workspace_root, &view_key.1, &hunks) {
+                Ok(full_file) => {
+                    self.full_file_views.insert(view_key, full_file);
+                }
+                Err(error) => {
+                    self.status_message =
+                        Some(format!("Não consegui abrir o arquivo completo: {error}"));
+                }
+            }
+        }
+
+        let timeline_panel = egui::TopBottomPanel::bottom("alicia_timeline")
+            .resizable(true)
+            .default_height(self.layout.timeline_height)
+            .show(ctx, |ui| {
+                panel_heading(ui, FocusedPanel::Timeline, self.focused_panel);
+                ui.horizontal_wrapped(|ui| {
+                    for kind in TimelineKind::ALL {
+                        let mut shown = self.timeline_kinds.contains(&kind);
+                        if ui.checkbox(&mut shown, timeline_kind_label(kind)).changed() {
+                            if shown {
+                                self.timeline_kinds.push(kind);
+                            } else {
+                                self.timeline_kinds.retain(|selected| *selected != kind);
+                            }
+                        }
+                    }
+                    ui.separator();
+                    egui::ComboBox::from_id_salt("alicia_timeline_session")
+                        .selected_text(
+                            self.timeline_session
+                                .clone()
+                                .unwrap_or_else(|| "todas as sessões".to_string()),
+                        )
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.timeline_session,
+                                None,
+                                "todas as sessões",
+                            );
+                            for session_id in &session_ids {
+                                ui.selectable_value(
+                                    &mut self.timeline_session,
+                                    Some(session_id.clone()),
+                                    session_id.as_str(),
+                                );
+                            }
+                        });
+                    ui.label("Buscar:");
+                    ui.text_edit_singleline(&mut self.timeline_query);
+                });
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for entry in &timeline {
+                        let mut text =
+                            egui::RichText::new(format!("#{} {}", entry.sequence, entry.summary))
+                                .monospace();
+                        if let Some(color) = store
+                            .timeline_entry_session(entry)
+                            .and_then(|session_id| store.session_color(session_id))
+                        {
+                            text = text.color(color);
+                        }
+                        ui.label(text);
+                    }
+                });
+            });
+
+        self.layout.timeline_height = timeline_panel.response.rect.height();
+
+        let dropped_paths: Vec<PathBuf> = ctx.input(|input| {
+            input
+                .raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect()
+        });
+        if !dropped_paths.is_empty() {
+            let workspace_root = self
+                .workspace_root
+                .clone()
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_else(|| PathBuf::from("."));
+            for path in dropped_paths {
+                match store.attach_context_file(&workspace_root, &path) {
+                    Ok(attachment) => {
+                        self.status_message = Some(format!("Anexado: {}", attachment.path));
+                    }
+                    Err(error) => {
+                        self.report_error(store, error.error_report());
+                    }
+                }
+            }
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            panel_heading(ui, FocusedPanel::Chat, self.focused_panel);
+            if let Some(turn) = store.token_usage().last_turn() {
+                let session_total = store.token_usage().session_total();
+                ui.group(|ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.strong(format!("Agente: {}", turn.model));
+                        ui.separator();
+                        ui.label(format!(
+                            "Último turno: {} tokens",
+                            format_token_count(turn.usage.total())
+                        ));
+                        ui.separator();
+                        ui.label(format!(
+                            "Sessão: {} tokens ({} prompt · {} resposta)",
+                            format_token_count(session_total.total()),
+                            format_token_count(session_total.prompt_tokens),
+                            format_token_count(session_total.completion_tokens)
+                        ));
+                        ui.separator();
+                        ui.label(format!(
+                            "Hoje: {} tokens",
+                            format_token_count(store.token_usage().day_total(unix_now_s()).total())
+                        ));
+                    });
+                });
+            }
+            if !chat_messages.is_empty() {
+                egui::ScrollArea::vertical()
+                    .id_salt("alicia_chat")
+                    .max_height(280.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for message in &chat_messages {
+                            ui.group(|ui| {
+                                ui.strong(match message.role {
+                                    ChatRole::User => "Você",
+                                    ChatRole::Agent => "Agente",
+                                    ChatRole::System => "Sistema",
+                                });
+                                for attachment in &message.attachments {
+                                    ui.small(format!("Anexo: {}", attachment.path));
+                                }
+                                if message.role == ChatRole::User
+                                    && ui
+                                        .small_button("Relatório da tarefa")
+                                        .on_hover_text(
+                                            "Gera um HTML com o pedido, os comandos, os diffs e a auditoria desta tarefa; imprima-o para ter o PDF.",
+                                        )
+                                        .clicked()
+                                {
+                                    requested_task_reports.push(message.id);
+                                }
+                                if let Some(block) = render_markdown(ui, message.blocks()) {
+                                    copied_code_blocks.push((message.id, block.clone()));
+                                }
+                                for tool_call in &message.tool_calls {
+                                    ui.horizontal_wrapped(|ui| {
+                                        match tool_call.status {
+                                            None => {
+                                                ui.spinner();
+                                            }
+                                            Some(ToolCallStatus::Succeeded) => {
+                                                ui.colored_label(egui::Color32::GREEN, "✔");
+                                            }
+                                            Some(ToolCallStatus::Failed) => {
+                                                ui.colored_label(egui::Color32::RED, "✖");
+                                            }
+                                            Some(ToolCallStatus::Blocked) => {
+                                                ui.colored_label(egui::Color32::YELLOW, "⛔");
+                                            }
+                                        }
+                                        ui.strong(&tool_call.name);
+                                        ui.monospace(&tool_call.arguments);
+                                        if let Some(duration_ms) = tool_call.duration_ms {
+                                            ui.small(format!("{duration_ms} ms"));
+                                        }
+                                        if let Some(session_id) = &tool_call.session_id
+                                            && ui
+                                                .small_button(format!("Sessão {session_id}"))
+                                                .clicked()
+                                        {
+                                            focused_sessions.push(session_id.clone());
+                                        }
+                                        if let Some(action_id) = &tool_call.action_id {
+                                            let status = store
+                                                .approval(action_id)
+                                                .map_or("sem aprovação", |approval| {
+                                                    approval_status_name(approval.status)
+                                                });
+                                            ui.small(format!("Ação {action_id}: {status}"));
+                                        }
+                                    });
+                                }
+                                if message.streaming {
+                                    ui.horizontal(|ui| {
+                                        ui.spinner();
+                                        ui.small("Agente está digitando");
+                                    });
+                                }
+                            });
+                        }
+                    });
+            }
+
+            if ui.input(|input| !input.raw.hovered_files.is_empty()) {
+                ui.colored_label(
+                    ui.visuals().hyperlink_color,
+                    "Solte os arquivos para anexar como contexto.",
+                );
+            }
+            let mut removed_attachments = Vec::new();
+            if !store.draft_attachments().is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    for attachment in store.draft_attachments() {
+                        ui.group(|ui| {
+                            ui.label(&attachment.path);
+                            if ui
+                                .small_button("x")
+                                .on_hover_text("Remover anexo")
+                                .clicked()
+                            {
+                                removed_attachments.push(attachment.path.clone());
+                            }
+                        });
+                    }
+                });
+            }
+            for path in removed_attachments {
+                store.remove_context_attachment(&path);
+            }
+            let mut should_send = false;
+            ui.horizontal(|ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.chat_input_buffer).hint_text(
+                        "Pergunte ao agente, digite / para comandos, @ para arquivos ou arraste arquivos aqui",
+                    ),
+                );
+                should_send = ui.button("Enviar prompt").clicked();
+                if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                    should_send = true;
+                }
+            });
+            if should_send && !self.chat_input_buffer.trim().is_empty() {
+                let prompt = std::mem::take(&mut self.chat_input_buffer);
+                if parse_slash_command(&prompt).is_none() {
+                    let workspace_root = self
+                        .workspace_root
+                        .clone()
+                        .or_else(|| std::env::current_dir().ok())
+                        .unwrap_or_else(|| PathBuf::from("."));
+                    for error in store.attach_file_mentions(&workspace_root, &prompt) {
+                        self.report_error(store, error.error_report());
+                    }
+                }
+                store.submit_chat_prompt(prompt);
+            }
+            if let Some(query) = active_file_mention(&self.chat_input_buffer) {
+                let files = self.mention_candidates.get_or_insert_with(|| {
+                    let workspace_root = self
+                        .workspace_root
+                        .clone()
+                        .or_else(|| std::env::current_dir().ok())
+                        .unwrap_or_else(|| PathBuf::from("."));
+                    workspace_files(&workspace_root)
+                });
+                let matches = fuzzy_file_matches(files, query, MAX_MENTION_SUGGESTIONS);
+                let query_len = query.len();
+                if !matches.is_empty() {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        for file in &matches {
+                            if ui.selectable_label(false, file).clicked() {
+                                let kept = self.chat_input_buffer.len() - query_len - 1;
+                                self.chat_input_buffer.truncate(kept);
+                                self.chat_input_buffer.push_str(&format!("@{file} "));
+                            }
+                        }
+                    });
+                }
+            }
+            let completions = store.slash_commands().completions(&self.chat_input_buffer);
+            if !completions.is_empty() {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for spec in &completions {
+                        if ui
+                            .selectable_label(false, &spec.usage)
+                            .on_hover_text(&spec.description)
+                            .clicked()
+                        {
+                            self.chat_input_buffer = format!("/{} ", spec.name);
+                        }
+                    }
+                });
+            }
+            ui.separator();
+
+            panel_heading(ui, FocusedPanel::Terminal, self.focused_panel);
+
+            if session_ids.is_empty() {
+                ui.label("Nenhuma sessão ativa.");
+            } else {
+                let previous_active = store.active_session_id().map(str::to_string);
+                let mut selected_session = previous_active
+                    .clone()
+                    .or_else(|| session_ids.first().cloned())
+                    .unwrap_or_default();
+
+                egui::ComboBox::from_label("Sessão")
+                    .selected_text(session_text(store, &selected_session, &selected_session))
+                    .show_ui(ui, |ui| {
+                        for session_id in &session_ids {
+                            let waiting = store
+                                .terminal_session(session_id)
+                                .is_some_and(TerminalSessionState::waiting_for_input);
+                            let label = if waiting {
+                                format!("{session_id} ⌨ aguardando entrada")
+                            } else {
+                                session_id.clone()
+                            };
+                            let label = session_text(store, session_id, &label);
+                            ui.selectable_value(&mut selected_session, session_id.clone(), label);
+                        }
+                    });
+
+                if previous_active.as_deref() != Some(selected_session.as_str())
+                    && let Err(error) = store.set_active_session(&selected_session)
+                {
+                    self.report_error(store, error.error_report());
+                }
+                let mut wrap_mode = store
+                    .terminal_session(&selected_session)
+                    .map(TerminalSessionState::wrap_mode)
+                    .unwrap_or_default();
+                let mut clear_requested = false;
+                ui.horizontal(|ui| {
+                    for mode in [TerminalWrapMode::Wrap, TerminalWrapMode::NoWrap] {
+                        ui.selectable_value(&mut wrap_mode, mode, mode.label());
+                    }
+                    clear_requested = ui.small_button("Limpar terminal").clicked();
+                    ui.checkbox(
+                        &mut self.layout.terminal_copy_on_select,
+                        "Copiar ao selecionar",
+                    );
+                    ui.separator();
+                    let threshold = store.session_long_command_threshold(&selected_session);
+                    let mut own_threshold = threshold.is_some();
+                    let mut threshold_s = threshold
+                        .unwrap_or(store.notification_settings().long_command_threshold)
+                        .as_secs();
+                    ui.checkbox(&mut own_threshold, "Avisar após");
+                    ui.add_enabled(
+                        own_threshold,
+                        egui::DragValue::new(&mut threshold_s)
+                            .range(1..=86_400)
+                            .suffix(" s"),
+                    );
+                    let threshold = own_threshold.then(|| Duration::from_secs(threshold_s));
+                    if threshold != store.session_long_command_threshold(&selected_session) {
+                        store.set_session_long_command_threshold(&selected_session, threshold);
+                    }
+                });
+                if store
+                    .terminal_session(&selected_session)
+                    .is_some_and(|session| session.wrap_mode() != wrap_mode)
+                    && let Err(error) = store.set_terminal_wrap_mode(&selected_session, wrap_mode)
+                {
+                    self.report_error(store, error.error_report());
+                }
+                if clear_requested
+                    && let Err(error) = store.clear_terminal(&selected_session)
+                {
+                    self.report_error(store, error.error_report());
+                }
+
+                if let Some(session) = store.terminal_session(&selected_session) {
+                    let label = format!("Diretório: {}", session.cwd);
+                    if session.outside_workspace() {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            format!("{label} (fora do workspace)"),
+                        );
+                    } else {
+                        ui.label(label);
+                    }
+                    match session.network_isolation() {
+                        Some(NetworkIsolation::Namespace) => {
+                            ui.label("Rede: bloqueada (namespace isolado)");
+                        }
+                        Some(NetworkIsolation::ProxyBlackhole) => {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                "Rede: bloqueada só por proxy; programas que ignoram proxy ainda acessam a rede.",
+                            );
+                        }
+                        None => {}
+                    }
+                }
+
+                if store
+                    .terminal_session(&selected_session)
+                    .is_some_and(TerminalSessionState::waiting_for_input)
+                {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "⌨ A sessão parece aguardar uma resposta; digite abaixo.",
+                    );
+                }
+                if let Some(suggestion) = store
+                    .terminal_session(&selected_session)
+                    .and_then(TerminalSessionState::failure_suggestion)
+                {
+                    egui::Frame::group(ui.style()).show(ui, |ui| {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            egui::RichText::new(&suggestion.title).strong(),
+                        );
+                        ui.label(&suggestion.suggestion);
+                    });
+                }
+
+                let terminal_text = store.active_terminal_text().unwrap_or_default();
+                let emulator = store
+                    .terminal_session(&selected_session)
+                    .and_then(TerminalSessionState::emulator);
+                let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+                let plain_job = |lines: &[String]| {
+                    egui::text::LayoutJob::simple(
+                        lines.join("\n"),
+                        font_id.clone(),
+                        ui.visuals().text_color(),
+                        f32::INFINITY,
+                    )
+                };
+                let target_line = self
+                    .terminal_search_target
+                    .as_ref()
+                    .filter(|(session_id, _, _)| session_id == &selected_session)
+                    .map(|(_, line, _)| *line);
+                let mut fitted_size = None;
+                let (job, lines, focus_row) = match (emulator, wrap_mode) {
+                    (Some(emulator), _) => {
+                        let (char_width, row_height) = ui.fonts(|fonts| {
+                            (fonts.glyph_width(&font_id, 'M'), fonts.row_height(&font_id))
+                        });
+                        fitted_size = Some((
+                            (ui.available_height() / row_height.max(1.0)).max(1.0) as u16,
+                            (ui.available_width() / char_width.max(1.0)).max(1.0) as u16,
+                        ));
+                        let job = terminal_screen_job(
+                            &emulator.styled_rows(),
+                            font_id.clone(),
+                            ui.visuals().text_color(),
+                            ui.visuals().strong_text_color(),
+                        );
+                        (job, emulator.screen_lines(), None)
+                    }
+                    (None, TerminalWrapMode::Wrap) => {
+                        let char_width = ui.fonts(|fonts| fonts.glyph_width(&font_id, 'M'));
+                        let columns = (ui.available_width() / char_width.max(1.0)) as usize;
+                        let lines: Vec<String> =
+                            terminal_text.split('\n').map(str::to_string).collect();
+                        let wrapped = soft_wrap_lines(&lines, columns);
+                        let focus_row = target_line.map(|line| {
+                            soft_wrap_lines(&lines[..line.min(lines.len())], columns).len()
+                        });
+                        (plain_job(&wrapped), wrapped, focus_row)
+                    }
+                    (None, TerminalWrapMode::NoWrap) => {
+                        let lines: Vec<String> =
+                            terminal_text.split('\n').map(str::to_string).collect();
+                        (plain_job(&lines), lines, target_line)
+                    }
+                };
+                let scroll_to_focus = self
+                    .terminal_search_target
+                    .as_mut()
+                    .filter(|(session_id, _, _)| session_id == &selected_session)
+                    .is_some_and(|(_, _, scrolled)| !std::mem::replace(scrolled, true));
+                let mut selection = self
+                    .terminal_selection
+                    .take()
+                    .filter(|(session_id, _)| session_id == &selected_session)
+                    .map(|(_, selection)| selection);
+                let copy_on_select = self.layout.terminal_copy_on_select;
+                let copied = egui::ScrollArea::horizontal()
+                    .id_salt("alicia_terminal_screen")
+                    .show(ui, |ui| {
+                        egui::Frame::canvas(ui.style())
+                            .show(ui, |ui| {
+                                show_selectable_terminal(
+                                    ui,
+                                    job,
+                                    &lines,
+                                    focus_row.map(|row| (row, scroll_to_focus)),
+                                    &mut selection,
+                                    copy_on_select,
+                                )
+                            })
+                            .inner
+                    })
+                    .inner;
+                self.terminal_selection =
+                    selection.map(|selection| (selected_session.clone(), selection));
+                if let Some(copied) = copied {
+                    // Rows split by soft wrapping are copied as the line they came from.
+                    ui.ctx()
+                        .copy_text(copied.replace(&format!("{SOFT_WRAP_INDICATOR}\n"), ""));
+                    self.status_message = Some(String::from("Seleção copiada."));
+                }
+
+                if let Some((rows, columns)) = fitted_size {
+                    store.request_terminal_resize(&selected_session, rows, columns);
+                }
+
+                let path_links = find_path_links(&terminal_text);
+                if !path_links.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("Abrir:");
+                        let skipped = path_links.len().saturating_sub(MAX_TERMINAL_PATH_LINKS);
+                        for link in path_links.into_iter().skip(skipped) {
+                            if ui.small_button(link.label()).clicked() {
+                                requested_editor_opens.push(link);
+                            }
+                        }
+                    });
+                }
+
+                let mut requested_image_open = None;
+                if let Some(session) = store
+                    .active_session_id()
+                    .and_then(|session_id| store.terminal_session(session_id))
+                {
+                    for image in session.inline_images() {
+                        ui.horizontal(|ui| {
+                            ui.label(image.placeholder());
+                            if ui
+                                .add_enabled(
+                                    image.data.is_some(),
+                                    egui::Button::new("Abrir imagem"),
+                                )
+                                .clicked()
+                            {
+                                requested_image_open = Some(image.open_in_viewer());
+                            }
+                        });
+                    }
+                }
+                match requested_image_open {
+                    Some(Ok(path)) => {
+                        self.status_message = Some(format!("Imagem aberta: {}", path.display()));
+                    }
+                    Some(Err(error)) => {
+                        self.status_message = Some(format!("Não consegui abrir a imagem: {error}"));
+                    }
+                    None => {}
+                }
+
+                ui.horizontal(|ui| {
+                    let response = ui.text_edit_singleline(&mut self.terminal_input_buffer);
+                    let mut should_send = ui.button("Enviar").clicked();
+                    if response.lost_focus()
+                        && ui.input(|input| input.key_pressed(egui::Key::Enter))
+                    {
+                        should_send = true;
+                    }
+
+                    if should_send && !self.terminal_input_buffer.is_empty() {
+                        let mut payload = self.terminal_input_buffer.clone().into_bytes();
+                        payload.push(b'\n');
+
+                        match store.send_input_to_active_session(payload) {
+                            Ok(()) => {
+                                self.terminal_input_buffer.clear();
+                                self.status_message =
+                                    Some(String::from("Input enviado para a sessão."));
+                            }
+                            Err(error) => {
+                                self.report_error(store, error.error_report());
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        for (message_id, block) in copied_code_blocks {
+            store.record_code_block_copy(message_id, &block);
+            self.status_message = Some(String::from("Código copiado."));
+        }
+
+        for message_id in requested_task_reports {
+            let Some(report) = task_report_html(store, message_id, unix_now_s()) else {
+                continue;
+            };
+            let path = self
+                .workspace_root
+                .clone()
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(TASK_REPORTS_DIR)
+                .join(format!("tarefa-{message_id}.html"));
+            let written = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|()| std::fs::write(&path, report));
+            self.status_message = Some(match written {
+                Ok(()) => format!("Relatório salvo em {}.", path.display()),
+                Err(error) => format!("Não consegui salvar o relatório: {error}"),
+            });
+        }
+
+        for session_id in focused_sessions {
+            if let Err(error) = store.set_active_session(&session_id) {
+                self.report_error(store, error.error_report());
+            }
+        }
+
+        if !requested_editor_opens.is_empty() {
+            let workspace_root = self
+                .workspace_root
+                .clone()
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_else(|| PathBuf::from("."));
+            for location in requested_editor_opens {
+                self.status_message = Some(
+                    match open_in_editor(&workspace_root, &self.editor_config, &location) {
+                        Ok(_) => format!("Aberto no editor: {}", location.label()),
+                        Err(error) => format!("Não consegui abrir no editor: {error}"),
+                    },
+                );
+            }
+        }
+
+        if panic_requested {
+            let denied = store.engage_panic_switch();
+            self.status_message = Some(format!(
+                "Botão de pânico acionado: {} aprovações negadas.",
+                denied.len()
+            ));
+            emitted_messages.extend(denied);
+        }
+
+        for (action_id, command) in requested_modified_approvals {
+            match store.approve_with_command(&action_id, command) {
+                Ok(message) => {
+                    emitted_messages.push(message);
+                    self.command_edits.remove(&action_id);
+                    self.status_message = Some(format!(
+                        "Aprovação {action_id} aprovada com o comando editado."
+                    ));
+                }
+                Err(error) => {
+                    self.report_error(store, error.error_report());
+                }
+            }
+        }
+
+        for (action_id, reason) in requested_denials {
+            match store.deny_with_reason(&action_id, reason) {
+                Ok(message) => {
+                    emitted_messages.push(message);
+                    self.denial_drafts.remove(&action_id);
+                    self.status_message = Some(format!("Aprovação {action_id} rejeitada."));
+                }
+                Err(error) => {
+                    self.report_error(store, error.error_report());
+                }
+            }
+        }
+
+        for action_id in requested_always_allowed {
+            match store.always_allow(&action_id) {
+                Ok(message) => {
+                    emitted_messages.push(message);
+                    self.status_message = Some(format!(
+                        "Aprovação {action_id} aprovada; o comando passa a ser permitido neste workspace."
+                    ));
+                }
+                Err(error) => {
+                    self.report_error(store, error.error_report());
+                }
+            }
+        }
+
+        for action_id in requested_remembered_approvals {
+            match store.approve_and_remember(&action_id) {
+                Ok(message) => {
+                    emitted_messages.push(message);
+                    self.status_message =
+                        Some(format!("Aprovação {action_id} aprovada e lembrada."));
+                }
+                Err(error) => {
+                    self.report_error(store, error.error_report());
+                }
+            }
+        }
+
+        for (action_id, resolution) in requested_resolutions {
+            match store.resolve_pending_approval(&action_id, resolution) {
+                Ok(message) => {
+                    emitted_messages.push(message);
+                    self.status_message = Some(format!(
+                        "Aprovação {} marcada como {}.",
+                        action_id,
+                        approval_resolution_name(resolution)
+                    ));
+                }
+                Err(error) => {
+                    self.report_error(store, error.error_report());
+                }
+            }
+        }
+
+        for export in requested_copies {
+            let Some(text) = export.text(store) else {
+                self.status_message = Some(String::from("Nada para copiar."));
+                continue;
+            };
+            self.status_message = Some(match copy_to_clipboard(&text) {
+                Ok(()) => String::from("Copiado para a área de transferência."),
+                Err(error) => format!("Não consegui copiar: {error}"),
+            });
+        }
+
+        for action_id in requested_hunk_applies {
+            store.request_hunk_apply(&action_id);
+            self.status_message = Some(format!("Aplicando blocos aprovados de {action_id}..."));
+        }
+
+        if let Some(exporter) = requested_audit_export {
+            self.status_message = Some(format!(
+                "Exportando auditoria ({})...",
+                exporter.format.extension().to_uppercase()
+            ));
+            store.request_audit_export(exporter);
+        }
+
+        for (action_id, file_path, decision) in requested_bulk_hunk_decisions {
+            match store.set_bulk_hunk_decision(&action_id, file_path.as_deref(), decision) {
+                Ok(changed) => {
+                    self.status_message = Some(format!(
+                        "{changed} bloco(s) de {} ({}) atualizados para {}.",
+                        action_id,
+                        file_path.as_deref().unwrap_or("todos os arquivos"),
+                        patch_hunk_decision_name(decision)
+                    ));
+                }
+                Err(error) => {
+                    self.report_error(store, error.error_report());
+                }
+            }
+        }
+
+        for (action_id, file_path, hunk_id, decision) in requested_hunk_decisions {
+            match store.set_patch_hunk_decision(&action_id, &file_path, &hunk_id, decision) {
+                Ok(()) => {
+                    self.status_message = Some(format!(
+                        "Bloco {} ({}) atualizado para {}.",
+                        hunk_id,
+                        file_path,
+                        patch_hunk_decision_name(decision)
+                    ));
+                }
+                Err(error) => {
+                    self.report_error(store, error.error_report());
+                }
+            }
+        }
+
+        // Save once a resize drag ends rather than on every frame of it.
+        if let Some(layout_path) = &self.layout_path
+            && self.persisted_layout.as_ref() != Some(&self.layout)
+            && !ctx.input(|input| input.pointer.any_down())
+        {
+            if let Err(error) = self.layout.save(layout_path) {
+                self.status_message = Some(format!("Não consegui salvar o layout: {error}"));
+            }
+            self.persisted_layout = Some(self.layout.clone());
+        }
+
+        if store.has_running_sessions() || store.is_agent_typing() {
+            ctx.request_repaint_after(Duration::from_millis(33));
+        }
+
+        emitted_messages
+    }
+}
+
+fn render_full_file_context(
+    ui: &mut egui::Ui,
+    action_id: &str,
+    full_file: &FullFileContext,
+    hunks: &[PatchHunkPreview],
+    requested_hunk_decisions: &mut Vec<(String, String, String, PatchHunkDecision)>,
+) {
+    egui::ScrollArea::vertical()
+        .id_salt(("alicia_full_file", action_id, full_file.file_path.as_str()))
+        .max_height(360.0)
+        .show(ui, |ui| {
+            for (index, line) in full_file.lines.iter().enumerate() {
+                if let Some(start) = full_file
+                    .hunk_starts
+                    .iter()
+                    .find(|start| start.line_index == index)
+                {
+                    let decision = hunks
+                        .iter()
+                        .find(|hunk| hunk.hunk_id == start.hunk_id)
+                        .map_or(start.decision, |hunk| hunk.decision);
+                    ui.horizontal(|ui| {
+                        ui.label(format!(
+                            "{} {} ({})",
+                            start.hunk_id,
+                            start.header,
+                            patch_hunk_decision_name(decision)
+                        ));
+                        for (label, decision) in [
+                            ("Aprovar bloco", PatchHunkDecision::Approved),
+                            ("Rejeitar bloco", PatchHunkDecision::Rejected),
+                        ] {
+                            if ui.small_button(label).clicked() {
+                                requested_hunk_decisions.push((
+                                    action_id.to_string(),
+                                    full_file.file_path.clone(),
+                                    start.hunk_id.clone(),
+                                    decision,
+                                ));
+                            }
+                        }
+                    });
+                }
+
+                let (marker, color) = match line.kind {
+                    FileContextLineKind::Unchanged => (' ', None),
+                    FileContextLineKind::HunkContext => (' ', Some(egui::Color32::LIGHT_BLUE)),
+                    FileContextLineKind::Removed => ('-', Some(egui::Color32::LIGHT_RED)),
+                    FileContextLineKind::Added => ('+', Some(egui::Color32::LIGHT_GREEN)),
+                };
+                let number = line
+                    .new_line
+                    .or(line.old_line)
+                    .map_or_else(String::new, |number| number.to_string());
+                let mut text =
+                    egui::RichText::new(format!("{number:>5} {marker} {}", line.text)).monospace();
+                if let Some(color) = color {
+                    text = text.color(color);
+                }
+                ui.label(text);
+            }
+        });
+}
+
+fn render_hunk_lines(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    hunk: &PatchHunkPreview,
+    side_by_side: bool,
+) {
+    let cell = |line: Option<&DiffLine>, number: fn(&DiffLine) -> Option<usize>| {
+        let Some(line) = line else {
+            return egui::RichText::new("").monospace();
+        };
+        let (marker, color) = match line.kind {
+            DiffLineKind::Context => (' ', None),
+            DiffLineKind::Removed => ('-', Some(egui::Color32::LIGHT_RED)),
+            DiffLineKind::Added => ('+', Some(egui::Color32::LIGHT_GREEN)),
+        };
+        let number = number(line).map_or_else(String::new, |number| number.to_string());
+        let text = egui::RichText::new(format!("{number:>5} {marker} {}", line.text)).monospace();
+        match color {
+            Some(color) => text.color(color),
+            None => text,
+        }
+    };
+    egui::ScrollArea::horizontal()
+        .id_salt(("alicia_hunk_lines", &id_salt))
+        .show(ui, |ui| {
+            if side_by_side {
+                egui::Grid::new(("alicia_hunk_side_by_side", &id_salt))
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for row in hunk.side_by_side() {
+                            ui.label(cell(row.left.as_ref(), |line| line.old_line));
+                            ui.label(cell(row.right.as_ref(), |line| line.new_line));
+                            ui.end_row();
+                        }
+                    });
+            } else {
+                for line in hunk.diff_lines() {
+                    ui.label(cell(Some(&line), |line| line.new_line.or(line.old_line)));
+                }
+            }
+        });
+}
+
+fn format_countdown(seconds: i64) -> String {
+    if seconds >= 3_600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3_600,
+            seconds % 3_600 / 60,
+            seconds % 60
+        )
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+fn unix_now_s() -> i64 {
+    let Ok(duration_since_epoch) = SystemTime::now().duration_since(UNIX_EPOCH) else {
+        return 0;
+    };
+    i64::try_from(duration_since_epoch.as_secs()).unwrap_or(i64::MAX)
+}
+
+fn command_target(program: &str, args: &[String], audit_target: &str) -> String {
+    if audit_target.is_empty() {
+        command_tokens(program, args).join(" ")
+    } else {
+        audit_target.to_string()
+    }
+}
+
+fn command_tokens(program: &str, args: &[String]) -> Vec<String> {
+    let mut command = Vec::with_capacity(args.len() + 1);
+    command.push(program.to_string());
+    command.extend(args.iter().cloned());
+    command
+}
+
+fn selected_approval_decision(
+    requested_decision: ApprovalDecision,
+    store_decision: Option<ApprovalDecision>,
+) -> ApprovalDecision {
+    if let Some(store_decision) = store_decision {
+        store_decision
+    } else {
+        requested_decision
+    }
+}
+
+/// Kinds `classify_command` infers besides `ExecuteCommand` itself.
+fn inferred_command_action_kinds(command: &[String]) -> Vec<ActionKind> {
+    classify_command(command)
+        .into_iter()
+        .filter(|action_kind| *action_kind != ActionKind::ExecuteCommand)
+        .collect()
+}
+
+fn command_action_kind_warning(action_kind: ActionKind) -> &'static str {
+    match action_kind {
+        ActionKind::NetworkAccess => "Este comando também acessa a rede",
+        ActionKind::WriteFile | ActionKind::ApplyPatch => "Este comando também grava arquivos",
+        ActionKind::ReadFile => "Este comando também lê arquivos",
+        ActionKind::ExecuteCommand => "Este comando também executa outros comandos",
+    }
+}
+
+fn combine_policy_decisions(left: PolicyDecision, right: PolicyDecision) -> PolicyDecision {
+    match (left, right) {
+        (PolicyDecision::Deny, _) | (_, PolicyDecision::Deny) => PolicyDecision::Deny,
+        (PolicyDecision::RequireApproval, _) | (_, PolicyDecision::RequireApproval) => {
+            PolicyDecision::RequireApproval
+        }
+        (PolicyDecision::Allow, PolicyDecision::Allow) => PolicyDecision::Allow,
+    }
+}
+
+fn effective_approval_decision(
+    policy_decision: PolicyDecision,
+    requested_approval_decision: ApprovalDecision,
+) -> ApprovalDecision {
+    match policy_decision {
+        PolicyDecision::Allow | PolicyDecision::Deny => ApprovalDecision::NotRequired,
+        PolicyDecision::RequireApproval => requested_approval_decision,
+    }
+}
+
+fn blocked_reason(
+    policy_decision: PolicyDecision,
+    approval_decision: ApprovalDecision,
+) -> Option<String> {
+    match policy_decision {
+        PolicyDecision::Allow => None,
+        PolicyDecision::Deny => Some(String::from("policy decision is deny")),
+        PolicyDecision::RequireApproval => match approval_decision {
+            ApprovalDecision::Approved => None,
+            ApprovalDecision::NotRequired => Some(String::from(
+                "approval required but no explicit decision was provided",
+            )),
+            ApprovalDecision::Denied => {
+                Some(String::from("approval required and was explicitly denied"))
+            }
+            ApprovalDecision::Expired => {
+                Some(String::from("approval required but the decision expired"))
+            }
+        },
+    }
+}
+
+fn action_kind_name(action_kind: ActionKind) -> &'static str {
+    match action_kind {
+        ActionKind::ReadFile => "read_file",
+        ActionKind::WriteFile => "write_file",
+        ActionKind::ExecuteCommand => "execute_command",
+        ActionKind::ApplyPatch => "apply_patch",
+        ActionKind::NetworkAccess => "network_access",
+    }
+}
+
+/// The panel's heading, in the selection color while it has the keyboard focus.
+fn panel_heading(ui: &mut egui::Ui, panel: FocusedPanel, focused_panel: FocusedPanel) {
+    let text = egui::RichText::new(panel.label()).heading();
+    if panel == focused_panel {
+        ui.label(text.color(ui.visuals().selection.stroke.color));
+    } else {
+        ui.label(text);
+    }
+}
+
+fn timeline_kind_label(kind: TimelineKind) -> &'static str {
+    match kind {
+        TimelineKind::Command => "Comandos",
+        TimelineKind::Approval => "Aprovações",
+        TimelineKind::Patch => "Patches",
+        TimelineKind::Audit => "Auditoria",
+        TimelineKind::Agent => "Agente",
+        TimelineKind::System => "Sistema",
+    }
+}
+
+fn timeline_kind_and_session(event: &IpcEvent) -> (TimelineKind, Option<&str>) {
+    match event {
+        IpcEvent::ActionProposed(_)
+        | IpcEvent::ApprovalRequested(_)
+        | IpcEvent::ApprovalResolved(_) => (TimelineKind::Approval, None),
+        IpcEvent::CommandStarted(event) => (TimelineKind::Command, Some(&event.command_id)),
+        IpcEvent::CommandOutputChunk(event) => (TimelineKind::Command, Some(&event.command_id)),
+        IpcEvent::CommandFinished(event) => (TimelineKind::Command, Some(&event.command_id)),
+        IpcEvent::NetworkAccessBlocked(event) => (TimelineKind::Command, Some(&event.command_id)),
+        IpcEvent::PatchPreviewReady(_) | IpcEvent::PatchApplied(_) => (TimelineKind::Patch, None),
+        IpcEvent::AgentMessageDelta(_)
+        | IpcEvent::AgentMessageComplete(_)
+        | IpcEvent::ToolCallStarted(_)
+        | IpcEvent::ToolCallFinished(_) => (TimelineKind::Agent, None),
+    }
+}
+
+fn tool_call_status_name(status: ToolCallStatus) -> &'static str {
+    match status {
+        ToolCallStatus::Succeeded => "succeeded",
+        ToolCallStatus::Failed => "failed",
+        ToolCallStatus::Blocked => "blocked",
+    }
+}
+
+fn chat_role_name(role: ChatRole) -> &'static str {
+    match role {
+        ChatRole::User => "user",
+        ChatRole::Agent => "agent",
+        ChatRole::System => "system",
+    }
+}
+
+fn denial_category_name(category: DenialCategory) -> &'static str {
+    match category {
+        DenialCategory::TooRisky => "too_risky",
+        DenialCategory::WrongApproach => "wrong_approach",
+        DenialCategory::NeedsTests => "needs_tests",
+        DenialCategory::Other => "other",
+    }
+}
+
+fn denial_category_label(category: DenialCategory) -> &'static str {
+    match category {
+        DenialCategory::TooRisky => "Arriscado demais",
+        DenialCategory::WrongApproach => "Abordagem errada",
+        DenialCategory::NeedsTests => "Faltam testes",
+        DenialCategory::Other => "Outro motivo",
+    }
+}
+
+/// `reason=<category>`, followed by the reviewer's detail when there is one.
+fn denial_reason_text(reason: &DenialReason) -> String {
+    match &reason.detail {
+        Some(detail) => format!("reason={} {detail}", denial_category_name(reason.category)),
+        None => format!("reason={}", denial_category_name(reason.category)),
+    }
+}
+
+fn approval_status_for(resolution: ApprovalResolution) -> ApprovalStatus {
+    match resolution {
+        ApprovalResolution::Approved => ApprovalStatus::Approved,
+        ApprovalResolution::Denied => ApprovalStatus::Denied,
+        ApprovalResolution::Expired => ApprovalStatus::Expired,
+    }
+}
+
+fn approval_resolution_name(resolution: ApprovalResolution) -> &'static str {
+    match resolution {
+        ApprovalResolution::Approved => "approved",
+        ApprovalResolution::Denied => "denied",
+        ApprovalResolution::Expired => "expired",
+    }
+}
+
+fn approval_status_name(status: ApprovalStatus) -> &'static str {
+    match status {
+        ApprovalStatus::Pending => "pending",
+        ApprovalStatus::Approved => "approved",
+        ApprovalStatus::Denied => "denied",
+        ApprovalStatus::Expired => "expired",
+    }
+}
+
+/// `label` after a dot in the accent of `session_id`.
+fn session_text(store: &UiEventStore, session_id: &str, label: &str) -> egui::RichText {
+    match store.session_color(session_id) {
+        Some(color) => egui::RichText::new(format!("● {label}")).color(color),
+        None => egui::RichText::new(label),
+    }
+}
+
+/// Dot and label summing up the runtime health, with the failed checks on hover.
+fn health_indicator(ui: &mut egui::Ui, health: Option<&RuntimeHealth>) {
+    let Some(health) = health else {
+        ui.colored_label(ui.visuals().weak_text_color(), "● Sem runtime");
+        return;
+    };
+    let (color, label) = match health.level() {
+        HealthLevel::Healthy => (egui::Color32::from_rgb(76, 175, 80), "● Conectado"),
+        HealthLevel::Degraded => (ui.visuals().warn_fg_color, "● Degradado"),
+        HealthLevel::Down => (ui.visuals().error_fg_color, "● Desconectado"),
+    };
+    let audit = match health.audit_writable {
+        Some(true) => "gravando",
+        Some(false) => "sem escrita",
+        None => "desligada",
+    };
+    let policy = match &health.policy {
+        PolicyFileStatus::Missing => "ausente",
+        PolicyFileStatus::Valid => "válida",
+        PolicyFileStatus::Invalid { .. } => "inválida",
+    };
+    let mut details = format!(
+        "Sessões ativas: {} · Aprovações pendentes: {}\nAuditoria: {audit} · Política: {policy}",
+        health.active_sessions, health.pending_approvals
+    );
+    for problem in health.problems() {
+        details.push_str(&format!("\n⚠ {problem}"));
+    }
+    ui.colored_label(color, label).on_hover_text(details);
+}
+
+fn alert_event_label(event: AlertEvent) -> &'static str {
+    match event {
+        AlertEvent::Bell => "Sino do terminal",
+        AlertEvent::ApprovalRequested => "Nova aprovação",
+        AlertEvent::CommandFailed => "Comando falhou",
+    }
+}
+
+fn command_output_stream_name(stream: CommandOutputStream) -> &'static str {
+    match stream {
+        CommandOutputStream::Stdout => "stdout",
+        CommandOutputStream::Stderr => "stderr",
+    }
+}
+
+/// The user's shell, for sessions opened without a command.
+fn default_shell_command() -> Vec<String> {
+    let shell = if cfg!(windows) {
+        std::env::var("COMSPEC").unwrap_or_else(|_| String::from("cmd.exe"))
+    } else {
+        std::env::var("SHELL").unwrap_or_else(|_| String::from("/bin/sh"))
+    };
+    vec![shell]
+}
+
+/// An emulated screen as one label, keeping every row even when blank. Bold text without a
+/// color of its own uses `strong_color`.
+/// Paints `job` as the terminal screen and lets the mouse select text on it; `lines` are the
+/// rows of `job` as plain text and `focus` a row to highlight, scrolled to when its flag is
+/// set. Returns the selected text when a drag ends with `copy_on_select` or when the copy
+/// shortcut is pressed outside text fields.
+fn show_selectable_terminal(
+    ui: &mut egui::Ui,
+    job: egui::text::LayoutJob,
+    lines: &[String],
+    focus: Option<(usize, bool)>,
+    selection: &mut Option<TerminalSelection>,
+    copy_on_select: bool,
+) -> Option<String> {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let (char_width, row_height) = ui.fonts(|fonts| {
+        (
+            fonts.glyph_width(&font_id, 'M').max(1.0),
+            fonts.row_height(&font_id).max(1.0),
+        )
+    });
+    let galley = ui.fonts(|fonts| fonts.layout_job(job));
+    let size = egui::vec2(
+        galley.size().x.max(ui.available_width()),
+        galley.size().y.max(row_height),
+    );
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+    let cell_at = |pos: egui::Pos2| {
+        TerminalCell::new(
+            ((pos.y - rect.top()) / row_height).max(0.0) as usize,
+            ((pos.x - rect.left()) / char_width).round().max(0.0) as usize,
+        )
+    };
+
+    let pointer = response.interact_pointer_pos();
+    if response.drag_started() {
+        *selection = pointer.map(|pos| TerminalSelection::new(cell_at(pos)));
+    } else if response.dragged() {
+        if let (Some(selection), Some(pos)) = (selection.as_mut(), pointer) {
+            selection.extend_to(cell_at(pos));
+        }
+    } else if response.clicked() {
+        *selection = None;
+    }
+
+    let copy_pressed = ui.input(|input| {
+        input
+            .events
+            .iter()
+            .any(|event| matches!(event, egui::Event::Copy))
+    }) && ui.memory(|memory| memory.focused().is_none());
+    let copied = selection
+        .filter(|selection| !selection.is_empty())
+        .filter(|_| copy_pressed || (copy_on_select && response.drag_stopped()))
+        .map(|selection| selection.text(lines));
+
+    if let Some((row, scroll)) = focus {
+        let top = rect.top() + row as f32 * row_height;
+        let row_rect = egui::Rect::from_min_max(
+            egui::pos2(rect.left(), top),
+            egui::pos2(rect.right(), top + row_height),
+        );
+        ui.painter().rect_filled(
+            row_rect,
+            0.0,
+            ui.visuals().warn_fg_color.gamma_multiply(0.25),
+        );
+        if scroll {
+            ui.scroll_to_rect(row_rect, Some(egui::Align::Center));
+        }
+    }
+    if let Some(selection) = selection.as_ref() {
+        let fill = ui.visuals().selection.bg_fill;
+        for (row, line) in lines.iter().enumerate() {
+            let Some(columns) = selection.columns_in_row(row, display_width(line)) else {
+                continue;
+            };
+            let top = rect.top() + row as f32 * row_height;
+            let highlight = egui::Rect::from_min_max(
+                egui::pos2(rect.left() + columns.start as f32 * char_width, top),
+                egui::pos2(
+                    rect.left() + columns.end as f32 * char_width,
+                    top + row_height,
+                ),
+            );
+            ui.painter().rect_filled(highlight, 0.0, fill);
+        }
+    }
+    ui.painter()
+        .galley(rect.min, galley, ui.visuals().text_color());
+    copied
+}
+
+fn terminal_screen_job(
+    rows: &[Vec<TerminalSpan>],
+    font_id: egui::FontId,
+    default_color: egui::Color32,
+    strong_color: egui::Color32,
+) -> egui::text::LayoutJob {
+    let rgb = |[red, green, blue]: [u8; 3]| egui::Color32::from_rgb(red, green, blue);
+    let mut job = egui::text::LayoutJob::default();
+    for (index, row) in rows.iter().enumerate() {
+        if index > 0 {
+            job.append(
+                "\n",
+                0.0,
+                egui::TextFormat::simple(font_id.clone(), default_color),
+            );
+        }
+        for span in row {
+            let color = match span.foreground {
+                Some(foreground) => rgb(foreground),
+                None if span.bold => strong_color,
+                None => default_color,
+            };
+            let mut format = egui::TextFormat::simple(font_id.clone(), color);
+            if let Some(background) = span.background {
+                format.background = rgb(background);
+            }
+            job.append(&span.text, 0.0, format);
+        }
+    }
+    job
+}
+
+/// Size of `message` for the memory budget, as it would go over IPC.
+fn estimated_message_bytes(message: &IpcMessage) -> usize {
+    serde_json::to_vec(message).map_or(0, |bytes| bytes.len())
+}
+
+fn permission_profile_name(profile: PermissionProfile) -> &'static str {
+    match profile {
+        PermissionProfile::ReadOnly => "read_only",
+        PermissionProfile::ReadWriteWithApproval => "read_write_with_approval",
+        PermissionProfile::FullAccess => "full_access",
+    }
+}
+
+fn comparison_field_label(field: &ComparisonField) -> String {
+    match field {
+        ComparisonField::Command => String::from("Comando"),
+        ComparisonField::Cwd => String::from("Diretório"),
+        ComparisonField::ExitCode => String::from("Exit code"),
+        ComparisonField::DurationMs => String::from("Duração (ms)"),
+        ComparisonField::OutputLines => String::from("Linhas de saída"),
+        ComparisonField::Action {
+            action_kind,
+            target,
+        } => format!("{} {target}", action_kind_name(*action_kind)),
+    }
+}
+
+fn policy_decision_name(policy_decision: PolicyDecision) -> &'static str {
+    match policy_decision {
+        PolicyDecision::Allow => "allow",
+        PolicyDecision::RequireApproval => "require_approval",
+        PolicyDecision::Deny => "deny",
+    }
+}
+
+fn approval_decision_name(approval_decision: ApprovalDecision) -> &'static str {
+    match approval_decision {
+        ApprovalDecision::NotRequired => "not_required",
+        ApprovalDecision::Approved => "approved",
+        ApprovalDecision::Denied => "denied",
+        ApprovalDecision::Expired => "expired",
+    }
+}
+
+fn result_status_name(result_status: ResultStatus) -> &'static str {
+    match result_status {
+        ResultStatus::Succeeded => "succeeded",
+        ResultStatus::Failed => "failed",
+        ResultStatus::Blocked => "blocked",
+    }
+}
+
+fn approver_source_name(source: ApproverSource) -> &'static str {
+    match source {
+        ApproverSource::OsUser => "os_user",
+        ApproverSource::Configured => "configured",
+        ApproverSource::RemotePrincipal => "remote_principal",
+    }
+}
+
+fn patch_hunk_decision_name(decision: PatchHunkDecision) -> &'static str {
+    match decision {
+        PatchHunkDecision::Pending => "pending",
+        PatchHunkDecision::Approved => "approved",
+        PatchHunkDecision::Rejected => "rejected",
+    }
+}
+
+fn modified_symbol_summary(symbol: &ModifiedSymbol) -> String {
+    let kind = match symbol.kind {
+        SymbolKind::Function => "fn",
+        SymbolKind::Type => "tipo",
+    };
+    if symbol.call_site_total == 0 {
+        return format!("Modifica {kind} {} (sem referências)", symbol.name);
+    }
+    let mut call_sites: Vec<String> = symbol
+        .call_sites
+        .iter()
+        .map(|call_site| format!("{}:{}", call_site.file_path, call_site.line))
+        .collect();
+    if symbol.call_site_total > call_sites.len() {
+        call_sites.push(format!(
+            "+{} outras",
+            symbol.call_site_total - call_sites.len()
+        ));
+    }
+    format!(
+        "Modifica {kind} {} ({} referências: {})",
+        symbol.name,
+        symbol.call_site_total,
+        call_sites.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use codex_alicia_core::ActionKind;
+    use codex_alicia_core::ApprovalDecision;
+    use codex_alicia_core::ApprovalResolution;
+    use codex_alicia_core::ApprovalValidity;
+    use codex_alicia_core::ApproverIdentity;
+    use codex_alicia_core::AuditRecord;
+    use codex_alicia_core::DEFAULT_SESSION_EVENTS_CAPACITY;
+    use codex_alicia_core::DenialCategory;
+    use codex_alicia_core::DenialReason;
+    use codex_alicia_core::EnvEdits;
+    use codex_alicia_core::IpcEvent;
+    use codex_alicia_core::IpcMessage;
+    use codex_alicia_core::NamedProfile;
+    use codex_alicia_core::NetworkIsolation;
+    use codex_alicia_core::PathRule;
+    use codex_alicia_core::PermissionProfile;
+    use codex_alicia_core::PolicyDecision;
+    use codex_alicia_core::ProfileDefinition;
+    use codex_alicia_core::ResultStatus;
+    use codex_alicia_core::SessionManager;
+    use codex_alicia_core::SessionManagerError;
+    use codex_alicia_core::SessionMode;
+    use codex_alicia_core::SessionStartRequest;
+    use codex_alicia_core::ShellCommand;
+    use codex_alicia_core::ShellCommandError;
+    use codex_alicia_core::fake_session::FakeSessionManager;
+    use codex_alicia_core::fake_session::FakeSessionScript;
+    use codex_alicia_core::ipc::ActionProposed;
+    use codex_alicia_core::ipc::AgentMessageComplete;
+    use codex_alicia_core::ipc::AgentMessageDelta;
+    use codex_alicia_core::ipc::ApprovalRequested;
+    use codex_alicia_core::ipc::ApprovalResolved;
+    use codex_alicia_core::ipc::CommandFinished;
+    use codex_alicia_core::ipc::CommandOutputChunk;
+    use codex_alicia_core::ipc::CommandStarted;
+    use codex_alicia_core::ipc::NetworkAccessBlocked;
+    use codex_alicia_core::ipc::PatchApplied;
+    use codex_alicia_core::ipc::PatchPreviewReady;
+    use codex_alicia_core::ipc::ToolCallStatus;
+    use pretty_assertions::assert_eq;
+    use tokio::sync::mpsc::error::TryRecvError;
+    use tokio_util::sync::CancellationToken;
+
+    use super::AgentLoop;
+    use super::AgentReply;
+    use super::AgentTool;
+    use super::AgentToolCall;
+    use super::AlertChannels;
+    use super::AlertEvent;
+    use super::AlertSettings;
+    use super::AliciaUiRuntime;
+    use super::AliciaUiRuntimeError;
+    use super::ApprovalPrompt;
+    use super::ApprovalStatus;
+    use super::AuditExportFilter;
+    use super::AuditExportFormat;
+    use super::AuditExporter;
+    use super::AuditHealth;
+    use super::ChatRole;
+    use super::CodeBlock;
+    use super::CommandApprovalLink;
+    use super::CommandLifecycle;
+    use super::ContextAttachment;
+    use super::DiffParseError;
+    use super::ErrorAction;
+    use super::ErrorEntry;
+    use super::FailureKind;
+    use super::HealthLevel;
+    use super::MarkdownBlock;
+    use super::ModelPricing;
+    use super::ModelRegistry;
+    use super::NotificationKind;
+    use super::NotificationSettings;
+    use super::PatchApplyError;
+    use super::PatchHunkDecision;
+    use super::PolicyFileStatus;
+    use super::PullRequestDraft;
+    use super::PullRequestError;
+    use super::PullRequestProvider;
+    use super::ScriptedAgentModel;
+    use super::ScrollbackLimits;
+    use super::SlashCommandHandler;
+    use super::SlashCommandSpec;
+    use super::SpawnRetryPolicy;
+    use super::StatusMetrics;
+    use super::StoreConfig;
+    use super::TerminalEmulator;
+    use super::TerminalSessionState;
+    use super::TimelineEntry;
+    use super::TimelineKind;
+    use super::TokenUsage;
+    use super::UiEventStore;
+    use super::UiEventStoreError;
+    use super::UiLanguage;
+    use super::WorkspaceRoot;
+
+    fn start_event(session_id: &str) -> IpcMessage {
+        IpcMessage::new(IpcEvent::CommandStarted(CommandStarted {
+            command_id: session_id.to_string(),
+            command: vec!["sh".to_string(), "-c".to_string(), "echo hi".to_string()],
+            cwd: ".".to_string(),
+        }))
+    }
+
+    fn shell_echo_input_command() -> (String, Vec<String>) {
+        if cfg!(windows) {
+            let cmd = std::env::var("COMSPEC").unwrap_or_else(|_| String::from("cmd.exe"));
+            let script = String::from("set /p ALICIA_INPUT=& echo !ALICIA_INPUT!");
+            (cmd, vec![String::from("/V:ON"), String::from("/C"), script])
+        } else {
+            (
+                String::from("/bin/sh"),
+                vec![
+                    String::from("-c"),
+                    String::from("read ALICIA_INPUT; echo $ALICIA_INPUT"),
+                ],
+            )
+        }
+    }
+
+    fn shell_echo_command(marker: &str) -> Result<(String, Vec<String>), ShellCommandError> {
+        ShellCommand::new("echo").arg(marker).build()
+    }
+
+    fn inherited_env() -> HashMap<String, String> {
+        std::env::vars().collect()
+    }
+
+    fn sample_unified_diff() -> &'static str {
+        "@@ -1,2 +1,3 @@\n-line_1\n+line_1_new\n line_2\n+line_3\n@@ -10,1 +11,2 @@\n-old_tail\n+new_tail_a\n+new_tail_b\n"
+    }
+
+    #[test]
+    fn stores_events_and_counts_pending_approvals() {
+        let mut store = UiEventStore::default();
+
+        store.push(start_event("cmd-1"));
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-1".to_string(),
+                summary: "requires approval".to_string(),
+                expires_at_unix_s: 1_735_689_600,
+            },
+        )));
+
+        assert_eq!(store.events().len(), 2);
+        assert_eq!(store.pending_approval_count(), 1);
+    }
+
+    #[test]
+    fn terminal_scrollback_keeps_recent_lines() {
+        let mut store = UiEventStore::new(StoreConfig::default().with_max_scrollback_lines(3));
+        store.push(start_event("cmd-scroll"));
+        store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+            CommandOutputChunk {
+                command_id: "cmd-scroll".to_string(),
+                stream: codex_alicia_core::CommandOutputStream::Stdout,
+                chunk: "a\nb\nc\nd\n".to_string(),
+            },
+        )));
+
+        let terminal = store.active_terminal_text();
+        let Some(terminal) = terminal else {
+            panic!("expected active terminal text");
+        };
+
+        assert_eq!(terminal, "b\nc\nd");
+    }
+
+    #[test]
+    fn terminal_scrollback_respects_byte_and_session_limits() {
+        let mut store =
+            UiEventStore::new(StoreConfig::default().with_max_scrollback_bytes(Some(10)));
+        let output = |session_id: &str, chunk: &str| {
+            IpcMessage::new(IpcEvent::CommandOutputChunk(CommandOutputChunk {
+                command_id: session_id.to_string(),
+                stream: codex_alicia_core::CommandOutputStream::Stdout,
+                chunk: chunk.to_string(),
+            }))
+        };
+        let text = |store: &UiEventStore, session_id: &str| {
+            store
+                .terminal_session(session_id)
+                .map(TerminalSessionState::visible_text)
+        };
+
+        store.push(start_event("cmd-bytes"));
+        store.push(output("cmd-bytes", "aaaa\nbbbb\ncccc\n"));
+        assert_eq!(text(&store, "cmd-bytes").as_deref(), Some("bbbb\ncccc"));
+        store.push(output("cmd-bytes", &"x".repeat(50)));
+        assert_eq!(text(&store, "cmd-bytes"), Some("x".repeat(10)));
+        assert_eq!(store.memory_usage_estimate().scrollback_bytes, 10);
+
+        store.push(start_event("cmd-lines"));
+        store.set_session_scrollback_limits("cmd-lines", Some(ScrollbackLimits::new(2)));
+        store.push(output("cmd-lines", "1\n2\n3\n"));
+        assert_eq!(text(&store, "cmd-lines").as_deref(), Some("2\n3"));
+        store.push(output("cmd-lines", &format!("{}\n", "y".repeat(50))));
+        assert_eq!(
+            text(&store, "cmd-lines"),
+            Some(format!("3\n{}", "y".repeat(50)))
+        );
+
+        store.set_session_scrollback_limits("cmd-lines", None);
+        assert_eq!(text(&store, "cmd-lines"), Some(String::new()));
+    }
+
+    #[test]
+    fn memory_budget_evicts_finished_sessions_first() -> Result<(), Box<dyn std::error::Error>> {
+        let mut store = UiEventStore::default();
+        for session_id in ["sess-old", "sess-live"] {
+            store.push(start_event(session_id));
+            store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+                CommandOutputChunk {
+                    command_id: session_id.to_string(),
+                    stream: codex_alicia_core::CommandOutputStream::Stdout,
+                    chunk: "linha de saida bem comprida\n".repeat(200),
+                },
+            )));
+        }
+        store.push(IpcMessage::new(IpcEvent::CommandFinished(
+            CommandFinished {
+                command_id: "sess-old".to_string(),
+                exit_code: 0,
+                duration_ms: 10,
+            },
+        )));
+        let before = store.memory_usage_estimate();
+        assert!(before.scrollback_bytes > 10_000);
+
+        store.set_memory_budget_bytes(Some(before.total() - 1_000));
+
+        let evictions = store.memory_evictions();
+        assert_eq!(evictions.passes, 1);
+        assert_eq!(evictions.scrollback_lines, 200);
+        assert_eq!(evictions.events, 0);
+        assert!(store.memory_usage_estimate().total() <= before.total() - 1_000);
+        store.set_active_session("sess-old")?;
+        assert_eq!(
+            store.active_terminal_text().as_deref(),
+            Some(EVICTED_SCROLLBACK_MARKER)
+        );
+        store.set_active_session("sess-live")?;
+        assert!(
+            store
+                .active_terminal_text()
+                .is_some_and(|text| text.lines().count() == 200)
+        );
+
+        store.set_memory_budget_bytes(Some(1));
+        let evictions = store.memory_evictions();
+        assert_eq!(evictions.passes, 2);
+        assert_eq!(evictions.events, 2);
+        assert_eq!(store.events().len(), 3);
+        assert!(
+            store
+                .timeline()
+                .iter()
+                .any(|entry| entry.summary.starts_with("memory_evicted "))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn clear_resets_the_terminal_without_ending_the_session()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let mut store = UiEventStore::default();
+        store.push(start_event("cmd-clear"));
+        let output = |chunk: &str| {
+            IpcMessage::new(IpcEvent::CommandOutputChunk(CommandOutputChunk {
+                command_id: "cmd-clear".to_string(),
+                stream: codex_alicia_core::CommandOutputStream::Stdout,
+                chunk: chunk.to_string(),
+            }))
+        };
+        store.set_active_session("cmd-clear")?;
+
+        store.push(output("antes\n\x1b[H\x1b[2J\x1b[3Jdepois\n"));
+        assert_eq!(store.active_terminal_text().as_deref(), Some("depois"));
+
+        store.push(output("mais\n"));
+        store.clear_terminal("cmd-clear")?;
+        assert_eq!(store.active_terminal_text().as_deref(), Some(""));
+        assert_eq!(
+            store
+                .terminal_session("cmd-clear")
+                .map(|session| session.lifecycle),
+            Some(CommandLifecycle::Running)
+        );
+        assert!(matches!(
+            store.clear_terminal("missing"),
+            Err(UiEventStoreError::SessionNotFound(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn searches_the_output_of_every_session() {
+        let mut store = UiEventStore::default();
+        for (session_id, chunk) in [
+            (
+                "cmd-api",
+                "Compiling api\nerror[E0502]: cannot borrow `x`\n",
+            ),
+            ("cmd-web", "ok\n"),
+            ("cmd-cli", "warning: unused\nError E0502 again"),
+        ] {
+            store.push(start_event(session_id));
+            store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+                CommandOutputChunk {
+                    command_id: session_id.to_string(),
+                    stream: codex_alicia_core::CommandOutputStream::Stdout,
+                    chunk: chunk.to_string(),
+                },
+            )));
+        }
+
+        let hits = store.search_all_output("e0502");
+        assert_eq!(
+            hits.iter()
+                .map(|hit| (hit.session_id.as_str(), hit.line_no, hit.line.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("cmd-api", 2, "error[E0502]: cannot borrow `x`"),
+                ("cmd-cli", 2, "Error E0502 again"),
+            ]
+        );
+        assert!(store.search_all_output("").is_empty());
+    }
+
+    #[test]
+    fn emulated_sessions_keep_a_screen_next_to_the_scrollback()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let mut store = UiEventStore::default();
+        store.enable_terminal_emulation("cmd-pty");
+        store.push(start_event("cmd-pty"));
+        store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+            CommandOutputChunk {
+                command_id: "cmd-pty".to_string(),
+                stream: codex_alicia_core::CommandOutputStream::Stdout,
+                chunk: "prompt$ \x1b[?1049h\x1b[Hless".to_string(),
+            },
+        )));
+
+        assert_eq!(
+            store.terminal_session_ids().to_vec(),
+            vec!["cmd-pty".to_string()]
+        );
+        let Some(emulator) = store
+            .terminal_session("cmd-pty")
+            .and_then(TerminalSessionState::emulator)
+        else {
+            panic!("expected an emulated session");
+        };
+        assert!(emulator.alternate_screen());
+        assert_eq!(emulator.screen_lines()[0], "less");
+
+        store.clear_terminal("cmd-pty")?;
+        let cleared = store
+            .terminal_session("cmd-pty")
+            .and_then(TerminalSessionState::emulator)
+            .map(TerminalEmulator::screen_lines);
+        assert_eq!(
+            cleared.and_then(|lines| lines.first().cloned()),
+            Some(String::new())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn osc7_updates_the_session_cwd_and_flags_workspace_escapes() {
+        let mut store = UiEventStore::default();
+        store.push(start_event("cmd-cd"));
+        store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+            CommandOutputChunk {
+                command_id: "cmd-cd".to_string(),
+                stream: codex_alicia_core::CommandOutputStream::Stdout,
+                chunk: "$ cd /etc\n\x1b]7;file://host/etc\x07$ ".to_string(),
+            },
+        )));
+
+        assert_eq!(
+            store
+                .terminal_session("cmd-cd")
+                .map(|session| session.cwd.as_str()),
+            Some("/etc")
+        );
+        assert_eq!(
+            store.take_cwd_changes(),
+            vec![("cmd-cd".to_string(), "/etc".to_string())]
+        );
+
+        store.record_session_workspace_check("cmd-cd", false);
+        store.record_session_workspace_check("cmd-cd", false);
+        assert!(
+            store
+                .terminal_session("cmd-cd")
+                .is_some_and(TerminalSessionState::outside_workspace)
+        );
+        let escapes: Vec<&AuditRecord> = store
+            .audit_records()
+            .iter()
+            .filter(|record| record.target == "cd /etc")
+            .collect();
+        assert_eq!(escapes.len(), 1);
+        assert_eq!(escapes[0].policy_decision, PolicyDecision::Deny);
+        assert_eq!(store.error_center().unread_count(), 1);
+    }
+
+    #[test]
+    fn osc133_markers_split_a_shell_session_into_commands() {
+        let mut store = UiEventStore::default();
+        store.push(start_event("sess-shell"));
+        for chunk in [
+            "\x1b]133;A\x07$ \x1b]133;B\x07cargo build\r\n\x1b]133;C\x07",
+            "Finished\r\n\x1b]133;D;0\x07\x1b]133;A\x07$ \x1b]133;B\x07false\r\n",
+            "\x1b]133;C\x07\x1b]133;D;1\x07\x1b]133;A\x07$ ",
+        ] {
+            store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+                CommandOutputChunk {
+                    command_id: "sess-shell".to_string(),
+                    stream: codex_alicia_core::CommandOutputStream::Stdout,
+                    chunk: chunk.to_string(),
+                },
+            )));
+        }
+
+        let Some(session) = store.terminal_session("sess-shell") else {
+            panic!("session was started");
+        };
+        let runs: Vec<(&str, Option<i32>)> = session
+            .shell_commands()
+            .iter()
+            .map(|run| (run.command.as_str(), run.exit_code))
+            .collect();
+        assert_eq!(runs, vec![("cargo build", Some(0)), ("false", Some(1))]);
+        assert!(!session.visible_text().contains("133;"));
+        assert_eq!(
+            store
+                .timeline()
+                .iter()
+                .filter(|entry| entry
+                    .summary
+                    .starts_with("shell_command_finished sess-shell"))
+                .count(),
+            2
+        );
+        let stats: Vec<&str> = store
+            .command_stats()
+            .iter()
+            .map(|stats| stats.command.as_str())
+            .collect();
+        assert!(stats.contains(&"false"));
+    }
+
+    #[test]
+    fn failed_sessions_get_a_suggested_next_step() {
+        let mut store = UiEventStore::default();
+        store.push(start_event("cmd-missing"));
+        store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+            CommandOutputChunk {
+                command_id: "cmd-missing".to_string(),
+                stream: codex_alicia_core::CommandOutputStream::Stderr,
+                chunk: "sh: 1: rg: not found\n".to_string(),
+            },
+        )));
+        store.push(IpcMessage::new(IpcEvent::CommandFinished(
+            CommandFinished {
+                command_id: "cmd-missing".to_string(),
+                exit_code: 127,
+                duration_ms: 5,
+            },
+        )));
+
+        assert_eq!(
+            store
+                .terminal_session("cmd-missing")
+                .and_then(TerminalSessionState::failure_suggestion)
+                .map(|suggestion| suggestion.kind),
+            Some(FailureKind::MissingBinary)
+        );
+        assert!(store.timeline().iter().any(|entry| {
+            entry
+                .summary
+                .starts_with("failure_suggestion cmd-missing missing_binary")
+        }));
+
+        store.push(start_event("cmd-missing"));
+        assert_eq!(
+            store
+                .terminal_session("cmd-missing")
+                .and_then(TerminalSessionState::failure_suggestion),
+            None
+        );
+    }
+
+    #[test]
+    fn command_stats_aggregate_finished_sessions() {
+        let mut store = UiEventStore::default();
+        for (session_id, exit_code, duration_ms) in [
+            ("run-1", 0, 1_000),
+            ("run-2", 1, 3_000),
+            ("run-3", 0, 3_000),
+        ] {
+            store.push(start_event(session_id));
+            store.push(IpcMessage::new(IpcEvent::CommandFinished(
+                CommandFinished {
+                    command_id: session_id.to_string(),
+                    exit_code,
+                    duration_ms,
+                },
+            )));
+        }
+
+        let stats = store.command_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].command, "sh -c echo hi");
+        assert_eq!(stats[0].runs, 3);
+        assert_eq!(stats[0].failures, 1);
+        assert_eq!(stats[0].slowdown(), Some(3.0));
+    }
+
+    #[test]
+    fn idle_prompts_flag_the_session_and_notify_once() {
+        let mut store = UiEventStore::default();
+        store.push(start_event("cmd-prompt"));
+        let output = |chunk: &str| {
+            IpcMessage::new(IpcEvent::CommandOutputChunk(CommandOutputChunk {
+                command_id: "cmd-prompt".to_string(),
+                stream: codex_alicia_core::CommandOutputStream::Stdout,
+                chunk: chunk.to_string(),
+            }))
+        };
+        store.push(output("Removendo build/\nContinuar? (y/n) "));
+        let idle = store.notification_settings().input_prompt_idle;
+
+        assert!(store.detect_input_prompts(Instant::now()).is_empty());
+        let later = Instant::now() + idle;
+        assert_eq!(
+            store.detect_input_prompts(later),
+            vec!["cmd-prompt".to_string()]
+        );
+        assert!(store.detect_input_prompts(later).is_empty());
+        assert!(
+            store
+                .terminal_session("cmd-prompt")
+                .is_some_and(TerminalSessionState::waiting_for_input)
+        );
+        let notifications = store.take_notifications();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].kind, NotificationKind::InputRequested);
+        assert_eq!(notifications[0].body, "cmd-prompt: Continuar? (y/n)");
+
+        store.push(output("y\n"));
+        assert!(
+            !store
+                .terminal_session("cmd-prompt")
+                .is_some_and(TerminalSessionState::waiting_for_input)
+        );
+    }
+
+    #[test]
+    fn store_config_is_applied_live() -> Result<(), Box<dyn std::error::Error>> {
+        let mut store = UiEventStore::default();
+        store.push(start_event("cmd-config"));
+        store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+            CommandOutputChunk {
+                command_id: "cmd-config".to_string(),
+                stream: codex_alicia_core::CommandOutputStream::Stdout,
+                chunk: "a\nb\nc\nd\n".to_string(),
+            },
+        )));
+        assert_eq!(store.timeline().len(), 2);
+
+        store.set_config(
+            StoreConfig::default()
+                .with_max_scrollback_lines(2)
+                .with_max_timeline_entries(2)
+                .with_output_preview_max_columns(3),
+        );
+        store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+            CommandOutputChunk {
+                command_id: "cmd-config".to_string(),
+                stream: codex_alicia_core::CommandOutputStream::Stdout,
+                chunk: "efgh\n".to_string(),
+            },
+        )));
+
+        store.set_active_session("cmd-config")?;
+        assert_eq!(store.active_terminal_text().as_deref(), Some("d\nefgh"));
+        let summaries: Vec<&str> = store
+            .timeline()
+            .iter()
+            .map(|entry| entry.summary.as_str())
+            .collect();
+        assert_eq!(
+            summaries,
+            vec![
+                "command_output_chunk cmd-config stdout a\\nb\\nc\\nd\\n",
+                "command_output_chunk cmd-config stdout efg...",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn replaying_the_journal_restores_sessions_approvals_and_previews()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let journal_path = super::event_journal_path(dir.path());
+        let mut store = UiEventStore::default();
+        store.set_event_journal(super::EventJournal::open(&journal_path)?);
+        store.push(start_event("sess-1"));
+        store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+            CommandOutputChunk {
+                command_id: "sess-1".to_string(),
+                stream: codex_alicia_core::CommandOutputStream::Stdout,
+                chunk: "compilando\n".to_string(),
+            },
+        )));
+        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+            PatchPreviewReady {
+                action_id: "act-1".to_string(),
+                files: vec!["src/main.rs".to_string()],
+            },
+        )));
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-1".to_string(),
+                summary: "Aplicar patch".to_string(),
+                expires_at_unix_s: i64::MAX,
+            },
+        )));
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-2".to_string(),
+                summary: "Rodar cargo test".to_string(),
+                expires_at_unix_s: i64::MAX,
+            },
+        )));
+        store.approve("act-2")?;
+        drop(store);
+
+        let mut restored = UiEventStore::replay_from(&journal_path)?;
+        let Some(session) = restored.terminal_session("sess-1") else {
+            panic!("replay should restore the session");
+        };
+        assert_eq!(session.visible_lines(), vec!["compilando".to_string()]);
+        assert_eq!(
+            session.lifecycle,
+            CommandLifecycle::Finished {
+                exit_code: -1,
+                duration_ms: 0,
+            }
+        );
+        for action_id in ["act-1", "act-2"] {
+            assert_eq!(
+                restored.approval(action_id).map(|approval| approval.status),
+                Some(ApprovalStatus::Expired)
+            );
+        }
+        assert_eq!(restored.pending_approval_count(), 0);
+        assert!(restored.diff_preview("act-1").is_some());
+        assert!(restored.take_alerts().is_empty());
+        assert!(
+            restored
+                .timeline()
+                .iter()
+                .any(|entry| entry.summary == "session_lost_on_restart sess-1")
+        );
+
+        assert!(restored.approve("act-1").is_err());
+        restored.push(start_event("sess-2"));
+        assert_eq!(restored.event_journal_path(), Some(journal_path.as_path()));
+        assert_eq!(super::read_event_journal(&journal_path)?.len(), 7);
+        Ok(())
+    }
+
+    #[test]
+    fn status_metrics_reflect_store_counters() {
+        let mut store = UiEventStore::default();
+        store.push(start_event("sess-metrics"));
+        store.record_event_latency(Duration::from_millis(3));
+        store.record_dropped_events(5);
+        store.record_audit_write(Err("disk full".to_string()));
+        store.record_audit_write(Err("disk still full".to_string()));
+
+        assert_eq!(
+            store.status_metrics(),
+            StatusMetrics {
+                pending_approvals: 0,
+                running_sessions: 1,
+                last_event_latency: Some(Duration::from_millis(3)),
+                dropped_events: 5,
+                event_channel_capacity: DEFAULT_SESSION_EVENTS_CAPACITY,
+                lagged_subscribers: BTreeMap::new(),
+                event_queue: None,
+                audit_health: AuditHealth::Failing {
+                    consecutive_failures: 2,
+                    last_error: "disk still full".to_string(),
+                },
+                unread_errors: 0,
+            }
+        );
+
+        store.record_audit_write(Ok(()));
+        assert_eq!(store.status_metrics().audit_health, AuditHealth::Healthy);
+        assert_eq!(
+            store.timeline().last().map(|entry| entry.summary.as_str()),
+            Some("events_dropped count=5 total=5")
+        );
+    }
+
+    #[test]
+    fn queues_notifications_for_enabled_event_kinds_only() {
+        let mut store = UiEventStore::default();
+        store.set_notification_settings(NotificationSettings {
+            long_command_threshold: Duration::from_secs(10),
+            ..NotificationSettings::default()
+        });
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-notify".to_string(),
+                summary: "rm -rf build".to_string(),
+                expires_at_unix_s: i64::MAX,
+            },
+        )));
+        store.push(start_event("sess-quick"));
+        store.push(IpcMessage::new(IpcEvent::CommandFinished(
+            CommandFinished {
+                command_id: "sess-quick".to_string(),
+                exit_code: 0,
+                duration_ms: 200,
+            },
+        )));
+        store.push(start_event("sess-long"));
+        store.push(IpcMessage::new(IpcEvent::CommandFinished(
+            CommandFinished {
+                command_id: "sess-long".to_string(),
+                exit_code: 1,
+                duration_ms: 42_000,
+            },
+        )));
+
+        let notifications: Vec<(NotificationKind, String)> = store
+            .take_notifications()
+            .into_iter()
+            .map(|notification| (notification.kind, notification.body))
+            .collect();
+        assert_eq!(
+            notifications,
+            vec![
+                (
+                    NotificationKind::ApprovalRequested,
+                    "rm -rf build".to_string()
+                ),
+                (
+                    NotificationKind::LongCommandFinished,
+                    "sh -c echo hi (exit_code=1, 42s)".to_string()
+                ),
+            ]
+        );
+
+        store.set_notification_settings(NotificationSettings {
+            action_blocked: false,
+            ..NotificationSettings::default()
+        });
+        store.add_audit_record(codex_alicia_core::AuditRecord::new(
+            "sess-blocked",
+            ActionKind::ExecuteCommand,
+            "curl example.com",
+            PermissionProfile::ReadOnly,
+            PolicyDecision::Deny,
+            ApprovalDecision::NotRequired,
+            ResultStatus::Blocked,
+            0,
+        ));
+        assert!(store.take_notifications().is_empty());
+    }
+
+    #[test]
+    fn session_thresholds_override_the_global_one_and_flag_the_window() {
+        let mut store = UiEventStore::default();
+        store.set_notification_settings(NotificationSettings {
+            long_command_threshold: Duration::from_secs(60),
+            request_attention: true,
+            ..NotificationSettings::default()
+        });
+        store.set_session_long_command_threshold("sess-build", Some(Duration::from_secs(5)));
+        for (session_id, duration_ms) in [("sess-build", 8_000), ("sess-test", 8_000)] {
+            store.push(start_event(session_id));
+            store.push(IpcMessage::new(IpcEvent::CommandFinished(
+                CommandFinished {
+                    command_id: session_id.to_string(),
+                    exit_code: 0,
+                    duration_ms,
+                },
+            )));
+        }
+
+        let bodies: Vec<String> = store
+            .take_notifications()
+            .into_iter()
+            .map(|notification| notification.body)
+            .collect();
+        assert_eq!(bodies, vec!["sh -c echo hi (exit_code=0, 8s)".to_string()]);
+        assert!(store.take_attention_request());
+        assert!(!store.take_attention_request());
+
+        store.set_session_long_command_threshold("sess-build", None);
+        assert_eq!(store.session_long_command_threshold("sess-build"), None);
+    }
+
+    #[test]
+    fn bells_approvals_and_failures_raise_the_enabled_alerts() {
+        let mut store = UiEventStore::default();
+        store.set_alert_settings(AlertSettings {
+            command_failed: AlertChannels {
+                audible: true,
+                visual: false,
+            },
+            ..AlertSettings::default()
+        });
+        store.push(start_event("sess-bell"));
+        for chunk in ["pronto\u{7}\u{7}\n", "ok\n"] {
+            store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+                CommandOutputChunk {
+                    command_id: "sess-bell".to_string(),
+                    stream: codex_alicia_core::CommandOutputStream::Stdout,
+                    chunk: chunk.to_string(),
+                },
+            )));
+        }
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-1".to_string(),
+                summary: "Aplicar patch".to_string(),
+                expires_at_unix_s: 60,
+            },
+        )));
+        for exit_code in [0, 2] {
+            store.push(IpcMessage::new(IpcEvent::CommandFinished(
+                CommandFinished {
+                    command_id: "sess-bell".to_string(),
+                    exit_code,
+                    duration_ms: 10,
+                },
+            )));
+        }
+
+        let alerts: Vec<(AlertEvent, String)> = store
+            .take_alerts()
+            .into_iter()
+            .map(|alert| (alert.event, alert.source))
+            .collect();
+        assert_eq!(
+            alerts,
+            vec![
+                (AlertEvent::Bell, "sess-bell".to_string()),
+                (AlertEvent::CommandFailed, "sess-bell".to_string()),
+            ]
+        );
+        assert!(store.take_alerts().is_empty());
+        assert_eq!(store.active_terminal_text(), Some("pronto\nok".to_string()));
+    }
+
+    #[test]
+    fn sessions_keep_distinct_colors_tagging_their_timeline_entries() {
+        let mut store = UiEventStore::default();
+        store.push(start_event("sess-api"));
+        store.push(start_event("sess-web"));
+        store.push(start_event("sess-api"));
+
+        let Some(api) = store.session_color("sess-api") else {
+            panic!("sess-api should have a color");
+        };
+        assert_ne!(store.session_color("sess-web"), Some(api));
+        assert_eq!(store.session_color("sess-db"), None);
+
+        let sessions: Vec<Option<&str>> = store
+            .timeline()
+            .iter()
+            .map(|entry| store.timeline_entry_session(entry))
+            .collect();
+        assert_eq!(
+            sessions,
+            vec![Some("sess-api"), Some("sess-web"), Some("sess-api")]
+        );
+    }
+
+    #[test]
+    fn timeline_filters_by_kind_session_and_text() {
+        let mut store = UiEventStore::default();
+        store.push(start_event("sess-api"));
+        store.push(start_event("sess-web"));
+        store.push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+            action_id: "act-1".to_string(),
+            action_kind: ActionKind::ExecuteCommand,
+            target: "Cargo.toml".to_string(),
+        })));
+
+        let sequences = |entries: Vec<&TimelineEntry>| -> Vec<u64> {
+            entries.iter().map(|entry| entry.sequence).collect()
+        };
+        assert_eq!(
+            sequences(store.timeline_filtered(&[], None, "")),
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            sequences(store.timeline_filtered(&[TimelineKind::Command], None, "")),
+            vec![0, 1]
+        );
+        assert_eq!(
+            sequences(store.timeline_filtered(&[], Some("sess-web"), "")),
+            vec![1]
+        );
+        assert_eq!(
+            sequences(store.timeline_filtered(
+                &[TimelineKind::Approval, TimelineKind::Patch],
+                None,
+                "cargo.TOML"
+            )),
+            vec![2]
+        );
+        assert_eq!(store.timeline()[1].session_id.as_deref(), Some("sess-web"));
+        assert_eq!(store.timeline()[2].kind, TimelineKind::Approval);
+    }
+
+    #[test]
+    fn approving_an_edited_command_replaces_the_proposed_one() -> Result<(), UiEventStoreError> {
+        let mut store = UiEventStore::default();
+        let proposed = vec!["rm".to_string(), "-rf".to_string(), "target".to_string()];
+        let edited = vec!["cargo".to_string(), "clean".to_string()];
+        store.push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+            action_id: "act-edit".to_string(),
+            action_kind: ActionKind::ExecuteCommand,
+            target: "rm -rf target".to_string(),
+        })));
+        store.attach_approval_command("act-edit", proposed.clone());
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-edit".to_string(),
+                summary: "Limpar build".to_string(),
+                expires_at_unix_s: 4_102_444_800,
+            },
+        )));
+
+        assert_eq!(
+            store.approve_with_command("act-edit", vec![String::new()]),
+            Err(UiEventStoreError::ApprovalCommandNotEditable {
+                action_id: "act-edit".to_string(),
+                reason: "the edited command is empty".to_string(),
+            })
+        );
+        let mut tokens = edited.clone();
+        tokens.push(String::new());
+        store.approve_with_command("act-edit", tokens)?;
+
+        let approval = store.approval("act-edit").cloned();
+        assert_eq!(
+            approval
+                .as_ref()
+                .map(|approval| (approval.status, approval.command.clone())),
+            Some((ApprovalStatus::Approved, Some(edited.clone())))
+        );
+        assert_eq!(
+            approval.and_then(|approval| approval.original_command),
+            Some(proposed.clone())
+        );
+        assert_eq!(
+            store.resolved_approval_decision_for_command(&edited),
+            Some(ApprovalDecision::Approved)
+        );
+        assert_eq!(
+            store.resolved_approval_decision_for_command(&proposed),
+            None
+        );
+        assert_eq!(
+            store.approve_with_command("act-edit", edited),
+            Err(UiEventStoreError::ApprovalNotPending(
+                "act-edit".to_string()
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn denial_reasons_reach_the_approval_and_the_ipc_message() -> Result<(), UiEventStoreError> {
+        let mut store = UiEventStore::default();
+        store.set_approver_identity(None);
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-deny".to_string(),
+                summary: "Apagar migrations".to_string(),
+                expires_at_unix_s: 4_102_444_800,
+            },
+        )));
+        let reason =
+            DenialReason::new(DenialCategory::WrongApproach).with_detail("crie uma migration nova");
+
+        let message = store.deny_with_reason("act-deny", reason.clone())?;
+
+        assert_eq!(
+            message.event,
+            IpcEvent::ApprovalResolved(ApprovalResolved {
+                action_id: "act-deny".to_string(),
+                resolution: ApprovalResolution::Denied,
+                approver: None,
+                reason: Some(reason.clone()),
+            })
+        );
+        assert_eq!(
+            store
+                .approval("act-deny")
+                .and_then(|approval| approval.denial_reason.clone()),
+            Some(reason)
+        );
+        assert_eq!(
+            store.timeline().last().map(|entry| entry.summary.as_str()),
+            Some("approval_resolved act-deny denied reason=wrong_approach crie uma migration nova")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_approval_requests_collapse_and_bursts_are_rate_limited() {
+        let mut store =
+            UiEventStore::new(StoreConfig::default().with_max_approval_requests_per_minute(2));
+        let command = vec!["rm".to_string(), "-rf".to_string(), "build".to_string()];
+        let request = |store: &mut UiEventStore, action_id: &str, target: &str| {
+            store.push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+                action_id: action_id.to_string(),
+                action_kind: ActionKind::ExecuteCommand,
+                target: target.to_string(),
+            })));
+            store.attach_approval_command(action_id, command.clone());
+            store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                ApprovalRequested {
+                    action_id: action_id.to_string(),
+                    summary: "Limpar build".to_string(),
+                    expires_at_unix_s: 4_102_444_800,
+                },
+            )));
+        };
+        request(&mut store, "act-1", "rm -rf build");
+        request(&mut store, "act-2", "rm -rf build");
+        request(&mut store, "act-3", "rm -rf build");
+        request(&mut store, "act-4", "rm -rf dist");
+        request(&mut store, "act-5", "rm -rf out");
+
+        let queue: Vec<(&str, u32)> = store
+            .pending_approvals()
+            .into_iter()
+            .map(|approval| (approval.action_id.as_str(), approval.occurrences))
+            .collect();
+        assert_eq!(queue, vec![("act-1", 3), ("act-4", 1)]);
+        assert_eq!(
+            store.approval("act-5").map(|approval| approval.status),
+            Some(ApprovalStatus::Expired)
+        );
+        assert!(
+            store
+                .timeline()
+                .iter()
+                .any(|entry| entry.summary == "approval_rate_limited act-5 limit=2/min")
+        );
+
+        assert!(store.approve("act-1").is_ok());
+        let statuses: Vec<Option<ApprovalStatus>> = ["act-2", "act-3"]
+            .iter()
+            .map(|action_id| store.approval(action_id).map(|approval| approval.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                Some(ApprovalStatus::Approved),
+                Some(ApprovalStatus::Approved)
+            ]
+        );
+        assert_eq!(store.pending_approval_count(), 1);
+    }
+
+    #[test]
+    fn carriage_return_overwrites_progress_line_in_place() {
+        let mut store = UiEventStore::default();
+        store.push(start_event("cmd-progress"));
+        for chunk in [
+            "Compiling\r\n",
+            "[=>   ] 20%\r",
+            "[===> ] 80%",
+            "\r[=====] 100%\rDone\n",
+        ] {
+            store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+                CommandOutputChunk {
+                    command_id: "cmd-progress".to_string(),
+                    stream: codex_alicia_core::CommandOutputStream::Stdout,
+                    chunk: chunk.to_string(),
+                },
+            )));
+        }
+
+        assert_eq!(
+            store.active_terminal_text(),
+            Some("Compiling\nDone==] 100%".to_string())
+        );
+    }
+
+    #[test]
+    fn terminal_graphics_are_replaced_by_placeholders() {
+        let mut store = UiEventStore::default();
+        store.push(start_event("cmd-plot"));
+        for chunk in ["plot:\n\u{1b}]1337;File=inline=1:iVBO", "Rw==\u{7}\nok\n"] {
+            store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+                CommandOutputChunk {
+                    command_id: "cmd-plot".to_string(),
+                    stream: codex_alicia_core::CommandOutputStream::Stdout,
+                    chunk: chunk.to_string(),
+                },
+            )));
+        }
+
+        assert_eq!(
+            store.active_terminal_text(),
+            Some("plot:\n[imagem 0 iterm2 8 bytes]\nok".to_string())
+        );
+        let images: Vec<(u64, Option<Vec<u8>>)> = store
+            .terminal_session("cmd-plot")
+            .map(|session| {
+                session
+                    .inline_images()
+                    .map(|image| (image.id, image.data.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        assert_eq!(images, vec![(0, Some(vec![0x89, b'P', b'N', b'G']))]);
+    }
+
+    #[test]
+    fn routes_input_to_the_selected_session() {
+        let mut store = UiEventStore::default();
+        store.push(start_event("sess-1"));
+        store.push(start_event("sess-2"));
+
+        let set_result = store.set_active_session("sess-2");
+        assert_eq!(set_result, Ok(()));
+
+        let (tx_1, mut rx_1) = tokio::sync::mpsc::channel(4);
+        let (tx_2, mut rx_2) = tokio::sync::mpsc::channel(4);
+        store.bind_session_input("sess-1", tx_1);
+        store.bind_session_input("sess-2", tx_2);
+
+        let send_result = store.send_input_to_active_session("echo Alicia");
+        assert_eq!(send_result, Ok(()));
+
+        assert_eq!(rx_1.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(rx_2.try_recv(), Ok(b"echo Alicia".to_vec()));
+    }
+
+    #[test]
+    fn approval_metrics_group_resolution_latency_by_action_kind()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let mut store = UiEventStore::default();
+        for (action_id, action_kind) in [
+            ("act-exec", codex_alicia_core::ActionKind::ExecuteCommand),
+            ("act-write", codex_alicia_core::ActionKind::WriteFile),
+        ] {
+            store.push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+                action_id: action_id.to_string(),
+                action_kind,
+                target: "src/main.rs".to_string(),
+            })));
+            store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                ApprovalRequested {
+                    action_id: action_id.to_string(),
+                    summary: action_id.to_string(),
+                    expires_at_unix_s: i64::MAX,
+                },
+            )));
+        }
+        store.approve("act-exec")?;
+
+        let metrics = store.approval_metrics();
+        assert_eq!(metrics.pending, 1);
+        assert!(metrics.oldest_pending_wait.is_some());
+        assert_eq!(metrics.resolved(), 1);
+        assert_eq!(metrics.by_kind.len(), 1);
+        let stats = &metrics.by_kind[0];
+        assert_eq!(
+            stats.action_kind,
+            Some(codex_alicia_core::ActionKind::ExecuteCommand)
+        );
+        assert_eq!((stats.approved, stats.denied), (1, 0));
+        assert!(stats.percentile(50.0).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn approval_prompt_contains_context_and_decision_updates_state() {
+        let mut store = UiEventStore::default();
+        store.push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+            action_id: "act-ctx".to_string(),
+            action_kind: codex_alicia_core::ActionKind::WriteFile,
+            target: "src/main.rs".to_string(),
+        })));
+        store.attach_approval_command(
+            "act-ctx",
+            vec!["cargo".to_string(), "test".to_string(), "-p".to_string()],
+        );
+        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+            PatchPreviewReady {
+                action_id: "act-ctx".to_string(),
+                files: vec!["src/main.rs".to_string(), "src/lib.rs".to_string()],
+            },
+        )));
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-ctx".to_string(),
+                summary: "Editar arquivos críticos".to_string(),
+                expires_at_unix_s: 1_735_689_600,
+            },
+        )));
+
+        let prompt = store.approval_prompt("act-ctx");
+        let Some(prompt) = prompt else {
+            panic!("expected approval prompt");
+        };
+
+        let expected = ApprovalPrompt {
+            action_id: "act-ctx".to_string(),
+            status: ApprovalStatus::Pending,
+            what: "Editar arquivos críticos".to_string(),
+            where_target: Some("src/main.rs".to_string()),
+            action_kind: Some(codex_alicia_core::ActionKind::WriteFile),
+            command: Some("cargo test -p".to_string()),
+            command_action_kinds: Vec::new(),
+            impact: Some("2 arquivo(s): src/main.rs, src/lib.rs".to_string()),
+            expires_at_unix_s: 1_735_689_600,
+        };
+        assert_eq!(prompt, expected);
+
+        let decision = store.approve("act-ctx");
+        let Ok(decision) = decision else {
+            panic!("approval should resolve");
+        };
+
+        assert!(matches!(
+            decision.event,
+            IpcEvent::ApprovalResolved(ref event)
+            if event.action_id == "act-ctx"
+                && event.resolution == codex_alicia_core::ApprovalResolution::Approved
+        ));
+
+        assert_eq!(store.pending_approval_count(), 0);
+        assert_eq!(
+            store.approval("act-ctx").map(|item| item.status),
+            Some(ApprovalStatus::Approved)
+        );
+    }
+
+    #[test]
+    fn approval_prompt_lists_action_kinds_inferred_from_command() {
+        let mut store = UiEventStore::default();
+        store.attach_approval_command(
+            "act-net",
+            vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "curl -fsSL https://example.com/install.sh > install.sh".to_string(),
+            ],
+        );
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-net".to_string(),
+                summary: "Baixar instalador".to_string(),
+                expires_at_unix_s: i64::MAX,
+            },
+        )));
+
+        let command_action_kinds = store
+            .approval_prompt("act-net")
+            .map(|prompt| prompt.command_action_kinds);
+
+        assert_eq!(
+            command_action_kinds,
+            Some(vec![ActionKind::WriteFile, ActionKind::NetworkAccess])
+        );
+    }
+
+    #[test]
+    fn resolved_approval_decision_for_command_reads_approval_state() {
+        let mut store = UiEventStore::default();
+        let command = vec!["cargo".to_string(), "test".to_string()];
+
+        store.push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+            action_id: "act-command".to_string(),
+            action_kind: ActionKind::ExecuteCommand,
+            target: "cargo test".to_string(),
+        })));
+        store.attach_approval_command("act-command", command.clone());
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-command".to_string(),
+                summary: "Executar comando".to_string(),
+                expires_at_unix_s: 1_735_689_600,
+            },
+        )));
+
+        assert_eq!(store.resolved_approval_decision_for_command(&command), None);
+
+        let approve_result = store.approve("act-command");
+        assert!(approve_result.is_ok());
+        assert_eq!(
+            store.resolved_approval_decision_for_command(&command),
+            Some(ApprovalDecision::Approved)
+        );
+    }
+
+    #[test]
+    fn remembered_approvals_outlive_the_store_until_revoked()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let command = vec!["cargo".to_string(), "test".to_string()];
+        let request = |store: &mut UiEventStore, action_id: &str| {
+            store.push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+                action_id: action_id.to_string(),
+                action_kind: ActionKind::ExecuteCommand,
+                target: "cargo test".to_string(),
+            })));
+            store.attach_approval_command(action_id, command.clone());
+            store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                ApprovalRequested {
+                    action_id: action_id.to_string(),
+                    summary: "Executar comando".to_string(),
+                    expires_at_unix_s: 1_735_689_600,
+                },
+            )));
+        };
+
+        let mut store = UiEventStore::default();
+        request(&mut store, "act-1");
+        store.approve_and_remember("act-1")?;
+        assert!(store.remembered_approvals().commands.is_empty());
+        assert_eq!(store.take_remembered_approvals_change(), None);
+
+        store.set_approval_memory_enabled(true);
+        request(&mut store, "act-2");
+        store.approve_and_remember("act-2")?;
+        let Some(remembered) = store.take_remembered_approvals_change() else {
+            panic!("remembering an approval should be saved");
+        };
+        assert_eq!(store.take_remembered_approvals_change(), None);
+
+        let mut restarted = UiEventStore::default();
+        restarted.set_remembered_approvals(remembered);
+        assert_eq!(
+            restarted.resolved_approval_decision_for_command(&command),
+            Some(ApprovalDecision::Approved)
+        );
+        let mut with_flags = command.clone();
+        with_flags.push("--all".to_string());
+        assert_eq!(
+            restarted.resolved_approval_decision_for_command(&with_flags),
+            None
+        );
+        assert_eq!(
+            restarted.resolved_approval_decision_for_command(&["cargo".to_string()]),
+            None
+        );
+
+        assert!(restarted.revoke_remembered_command(&command));
+        assert!(!restarted.revoke_remembered_command(&command));
+        assert_eq!(
+            restarted.resolved_approval_decision_for_command(&command),
+            None
+        );
+        assert!(restarted.take_remembered_approvals_change().is_some());
+        assert!(
+            restarted
+                .timeline()
+                .iter()
+                .any(|entry| entry.summary == "approval_memory_revoked command=cargo test")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn command_approvals_lapse_after_ttl_and_single_use() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let mut store = UiEventStore::default();
+        let command = vec!["cargo".to_string(), "publish".to_string()];
+        store.push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+            action_id: "act-ttl".to_string(),
+            action_kind: ActionKind::ExecuteCommand,
+            target: "cargo publish".to_string(),
+        })));
+        store.attach_approval_command("act-ttl", command.clone());
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-ttl".to_string(),
+                summary: "Publicar crate".to_string(),
+                expires_at_unix_s: i64::MAX,
+            },
+        )));
+        store.approve("act-ttl")?;
+        let granted_at = store
+            .approval("act-ttl")
+            .and_then(|approval| approval.resolved_at_unix_s)
+            .ok_or("approval should record when it was granted")?;
+
+        store.record_approval_use("act-ttl");
+        assert!(
+            store
+                .resolved_approval_for_command_at(&command, granted_at + 86_400)
+                .is_some()
+        );
+
+        store.set_approval_validity(BTreeMap::from([(
+            ActionKind::ExecuteCommand,
+            ApprovalValidity {
+                ttl_s: Some(600),
+                single_use: false,
+            },
+        )]));
+        assert!(
+            store
+                .resolved_approval_for_command_at(&command, granted_at + 599)
+                .is_some()
+        );
+        assert_eq!(
+            store.resolved_approval_for_command_at(&command, granted_at + 600),
+            None
+        );
+
+        store.set_approval_validity(BTreeMap::from([(
+            ActionKind::ExecuteCommand,
+            ApprovalValidity {
+                ttl_s: None,
+                single_use: true,
+            },
+        )]));
+        assert_eq!(
+            store.resolved_approval_for_command_at(&command, granted_at),
+            None
+        );
+        assert_eq!(
+            store
+                .latest_resolved_approval_for_command(&command)
+                .map(|link| link.action_id),
+            Some("act-ttl".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn audit_records_cross_link_with_resolved_approvals() {
+        let mut store = UiEventStore::default();
+        let command = vec!["cargo".to_string(), "build".to_string()];
+
+        store.push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+            action_id: "act-link".to_string(),
+            action_kind: ActionKind::ExecuteCommand,
+            target: "cargo build".to_string(),
+        })));
+        store.attach_approval_command("act-link", command.clone());
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-link".to_string(),
+                summary: "Compilar projeto".to_string(),
+                expires_at_unix_s: 4_102_444_800,
+            },
+        )));
+        store.set_approver_identity(Some(ApproverIdentity::configured("reviewer")));
+        assert!(store.approve("act-link").is_ok());
+
+        assert_eq!(
+            store.resolved_approval_for_command(&command),
+            Some(CommandApprovalLink {
+                action_id: "act-link".to_string(),
+                decision: ApprovalDecision::Approved,
+                event_sequence: Some(2),
+                approver: Some(ApproverIdentity::configured("reviewer")),
+            })
+        );
+        let history: Vec<&str> = store
+            .approval_history()
+            .into_iter()
+            .map(|approval| approval.action_id.as_str())
+            .collect();
+        assert_eq!(history, vec!["act-link"]);
+
+        store.add_audit_record(
+            codex_alicia_core::AuditRecord::new(
+                "sess-link",
+                ActionKind::ExecuteCommand,
+                "cargo build",
+                PermissionProfile::ReadWriteWithApproval,
+                PolicyDecision::RequireApproval,
+                ApprovalDecision::Approved,
+                ResultStatus::Succeeded,
+                10,
+            )
+            .with_action_id("act-link")
+            .with_event_sequence(2),
+        );
+        store.add_audit_record(codex_alicia_core::AuditRecord::new(
+            "sess-other",
+            ActionKind::ReadFile,
+            "README.md",
+            PermissionProfile::ReadWriteWithApproval,
+            PolicyDecision::Allow,
+            ApprovalDecision::NotRequired,
+            ResultStatus::Succeeded,
+            1,
+        ));
+
+        let linked: Vec<&str> = store
+            .audit_records_for_action("act-link")
+            .into_iter()
+            .map(|record| record.session_id.as_str())
+            .collect();
+        assert_eq!(linked, vec!["sess-link"]);
+        assert!(
+            store
+                .timeline()
+                .iter()
+                .any(|entry| entry.summary.contains("action_id=act-link event=#2")),
+            "expected audit timeline entry to reference originating action"
+        );
+    }
+
+    #[test]
+    fn timeline_preserves_order_and_diff_preview_is_available_before_apply() {
+        let mut store = UiEventStore::default();
+        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+            PatchPreviewReady {
+                action_id: "act-diff".to_string(),
+                files: vec!["src/a.rs".to_string()],
+            },
+        )));
+
+        let preview_before = store.diff_preview("act-diff");
+        let Some(preview_before) = preview_before else {
+            panic!("expected preview before apply");
+        };
+        assert_eq!(preview_before.applied, false);
+
+        store.push(IpcMessage::new(IpcEvent::PatchApplied(PatchApplied {
+            action_id: "act-diff".to_string(),
+            files: vec!["src/a.rs".to_string()],
+        })));
+
+        let preview_after = store.diff_preview("act-diff");
+        let Some(preview_after) = preview_after else {
+            panic!("expected preview after apply");
+        };
+        assert_eq!(preview_after.applied, true);
+
+        let timeline = store.timeline();
+        assert_eq!(timeline.len(), 3);
+        assert_eq!(timeline[0].sequence, 0);
+        assert_eq!(timeline[1].sequence, 1);
+        assert!(timeline[0].summary.contains("patch_preview_ready"));
+        assert!(timeline[1].summary.contains("patch_applied"));
+        assert!(
+            timeline[2]
+                .summary
+                .contains("audit session=act-diff action=apply_patch target=src/a.rs")
+        );
+    }
+
+    #[test]
+    fn patch_hunk_rejections_and_applies_emit_audit_records() {
+        let mut store = UiEventStore::default();
+        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+            PatchPreviewReady {
+                action_id: "act-audit-patch".to_string(),
+                files: vec!["src/main.rs".to_string()],
+            },
+        )));
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-audit-patch".to_string(),
+                summary: "Editar main".to_string(),
+                expires_at_unix_s: i64::MAX,
+            },
+        )));
+        let load_result =
+            store.attach_patch_file_diff("act-audit-patch", "src/main.rs", sample_unified_diff());
+        assert_eq!(load_result, Ok(2));
+        assert_eq!(
+            store.approve_patch_hunk("act-audit-patch", "src/main.rs", "hunk-1"),
+            Ok(())
+        );
+        assert_eq!(
+            store.reject_patch_hunk("act-audit-patch", "src/main.rs", "hunk-2"),
+            Ok(())
+        );
+        // Rejecting the same hunk again is not another audited decision.
+        assert_eq!(
+            store.reject_patch_hunk("act-audit-patch", "src/main.rs", "hunk-2"),
+            Ok(())
+        );
+        assert!(
+            store
+                .resolve_pending_approval("act-audit-patch", ApprovalResolution::Approved)
+                .is_ok()
+        );
+        store.push(IpcMessage::new(IpcEvent::PatchApplied(PatchApplied {
+            action_id: "act-audit-patch".to_string(),
+            files: vec!["src/main.rs".to_string()],
+        })));
+
+        let records = store.take_unpersisted_audit_records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(store.take_unpersisted_audit_records(), Vec::new());
+
+        let rejected = &records[0];
+        assert_eq!(rejected.action_kind, ActionKind::ApplyPatch);
+        assert_eq!(rejected.target, "src/main.rs");
+        assert_eq!(rejected.approval_decision, ApprovalDecision::Denied);
+        assert_eq!(rejected.result_status, ResultStatus::Blocked);
+        assert_eq!(rejected.action_id.as_deref(), Some("act-audit-patch"));
+        assert_eq!(
+            rejected.metadata,
+            BTreeMap::from([("hunk_id".to_string(), "hunk-2".to_string())])
+        );
+
+        let applied = &records[1];
+        assert_eq!(applied.action_kind, ActionKind::ApplyPatch);
+        assert_eq!(applied.target, "src/main.rs");
+        assert_eq!(applied.approval_decision, ApprovalDecision::Approved);
+        assert_eq!(applied.result_status, ResultStatus::Succeeded);
+        assert_eq!(
+            applied.metadata,
+            BTreeMap::from([
+                ("hunks_applied".to_string(), "hunk-1".to_string()),
+                ("hunks_rejected".to_string(), "hunk-2".to_string()),
+                ("partial_apply".to_string(), "true".to_string()),
+            ])
+        );
+        assert_eq!(store.audit_records_for_action("act-audit-patch").len(), 2);
+    }
+
+    #[test]
+    fn network_access_blocked_events_are_audited_as_blocked() {
+        let mut store = UiEventStore::default();
+        store.push(IpcMessage::new(IpcEvent::NetworkAccessBlocked(
+            NetworkAccessBlocked {
+                command_id: "sess-offline".to_string(),
+                isolation: NetworkIsolation::ProxyBlackhole,
+                detail: "curl: (6) Could not resolve host: example.com".to_string(),
+            },
+        )));
+
+        let records = store.take_unpersisted_audit_records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].session_id, "sess-offline");
+        assert_eq!(records[0].action_kind, ActionKind::NetworkAccess);
+        assert_eq!(records[0].policy_decision, PolicyDecision::Deny);
+        assert_eq!(records[0].result_status, ResultStatus::Blocked);
+        assert!(store.timeline().iter().any(|entry| {
+            entry
+                .summary
+                .starts_with("network_access_blocked sess-offline")
+        }));
+    }
+
+    #[test]
+    fn loads_patch_hunks_and_tracks_impact_per_hunk() {
+        let mut store = UiEventStore::default();
+        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+            PatchPreviewReady {
+                action_id: "act-hunks".to_string(),
+                files: vec!["src/main.rs".to_string()],
+            },
+        )));
+
+        let load_result =
+            store.attach_patch_file_diff("act-hunks", "src/main.rs", sample_unified_diff());
+        assert_eq!(load_result, Ok(2));
+
+        let unresolved_count = store.unresolved_patch_hunk_count("act-hunks");
+        assert_eq!(unresolved_count, Some(2));
+
+        let preview = store.diff_preview("act-hunks");
+        let Some(preview) = preview else {
+            panic!("expected patch preview");
+        };
+        assert_eq!(preview.file_previews.len(), 1);
+        let file_preview = &preview.file_previews[0];
+        assert_eq!(file_preview.file_path, "src/main.rs");
+        assert_eq!(file_preview.hunks.len(), 2);
+        assert_eq!(file_preview.hunks[0].added_lines, 2);
+        assert_eq!(file_preview.hunks[0].removed_lines, 1);
+        assert_eq!(file_preview.hunks[1].added_lines, 2);
+        assert_eq!(file_preview.hunks[1].removed_lines, 1);
+
+        assert!(
+            store
+                .timeline()
+                .iter()
+                .any(|entry| entry.summary.contains("patch_hunks_loaded act-hunks")),
+            "expected timeline to register loaded hunks"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_diffs_without_touching_the_preview() {
+        let mut store = UiEventStore::default();
+        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+            PatchPreviewReady {
+                action_id: "act-garbage".to_string(),
+                files: vec!["src/main.rs".to_string()],
+            },
+        )));
+
+        let result = store.attach_patch_file_diff(
+            "act-garbage",
+            "src/main.rs",
+            "@@ -1,3 +1,3 @@\n-old\n+new\n",
+        );
+
+        let Err(error) = result else {
+            panic!("expected malformed diff error");
+        };
+        assert_eq!(
+            error,
+            UiEventStoreError::MalformedPatchDiff {
+                action_id: "act-garbage".to_string(),
+                file_path: "src/main.rs".to_string(),
+                source: DiffParseError::InconsistentCounts {
+                    line: 1,
+                    header: "@@ -1,3 +1,3 @@".to_string(),
+                    expected_old: 3,
+                    expected_new: 3,
+                    actual_old: 1,
+                    actual_new: 1,
+                },
+            }
+        );
+        assert_eq!(error.error_report().code, "patch_diff_malformed");
+        assert_eq!(
+            store
+                .diff_preview("act-garbage")
+                .map(|preview| preview.file_previews.len()),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn allows_approving_and_rejecting_hunks_individually() {
+        let mut store = UiEventStore::default();
+        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+            PatchPreviewReady {
+                action_id: "act-granular".to_string(),
+                files: vec!["src/main.rs".to_string()],
+            },
+        )));
+        let load_result =
+            store.attach_patch_file_diff("act-granular", "src/main.rs", sample_unified_diff());
+        assert_eq!(load_result, Ok(2));
+
+        let approve_result = store.approve_patch_hunk("act-granular", "src/main.rs", "hunk-1");
+        assert_eq!(approve_result, Ok(()));
+        let reject_result = store.reject_patch_hunk("act-granular", "src/main.rs", "hunk-2");
+        assert_eq!(reject_result, Ok(()));
+
+        assert_eq!(store.unresolved_patch_hunk_count("act-granular"), Some(0));
+
+        let preview = store.diff_preview("act-granular");
+        let Some(preview) = preview else {
+            panic!("expected patch preview");
+        };
+        let file_preview = &preview.file_previews[0];
+        assert_eq!(file_preview.hunks[0].decision, PatchHunkDecision::Approved);
+        assert_eq!(file_preview.hunks[1].decision, PatchHunkDecision::Rejected);
+
+        assert!(
+            store.timeline().iter().any(|entry| entry.summary.contains(
+                "patch_hunk_decision act-granular file=src/main.rs hunk=hunk-1 decision=approved"
+            )),
+            "expected approved hunk decision in timeline"
+        );
+        assert!(
+            store.timeline().iter().any(|entry| entry.summary.contains(
+                "patch_hunk_decision act-granular file=src/main.rs hunk=hunk-2 decision=rejected"
+            )),
+            "expected rejected hunk decision in timeline"
+        );
+    }
+
+    #[test]
+    fn bulk_hunk_decisions_cover_a_file_or_the_whole_action() {
+        let mut store = UiEventStore::default();
+        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+            PatchPreviewReady {
+                action_id: "act-bulk".to_string(),
+                files: vec!["src/main.rs".to_string(), "src/lib.rs".to_string()],
+            },
+        )));
+        for file in ["src/main.rs", "src/lib.rs"] {
+            assert_eq!(
+                store.attach_patch_file_diff("act-bulk", file, sample_unified_diff()),
+                Ok(2)
+            );
+        }
+        let timeline_len = store.timeline().len();
+
+        assert_eq!(
+            store.reject_all_hunks_in_file("act-bulk", "src/lib.rs"),
+            Ok(2)
+        );
+        assert_eq!(store.unresolved_patch_hunk_count("act-bulk"), Some(2));
+        assert_eq!(store.approve_all_hunks("act-bulk"), Ok(4));
+        assert_eq!(store.unresolved_patch_hunk_count("act-bulk"), Some(0));
+        assert_eq!(
+            store.approve_all_hunks_in_file("act-bulk", "README.md"),
+            Err(UiEventStoreError::PatchFileNotFound {
+                action_id: "act-bulk".to_string(),
+                file_path: "README.md".to_string(),
+            })
+        );
+        assert_eq!(
+            store.reject_all_hunks("act-missing"),
+            Err(UiEventStoreError::PatchPreviewNotFound(
+                "act-missing".to_string()
+            ))
+        );
+
+        let summaries: Vec<&str> = store.timeline()[timeline_len..]
+            .iter()
+            .map(|entry| entry.summary.as_str())
+            .filter(|summary| summary.starts_with("patch_hunk"))
+            .collect();
+        assert_eq!(
+            summaries,
+            vec![
+                "patch_hunk_bulk_decision act-bulk file=src/lib.rs hunks=2 decision=rejected",
+                "patch_hunk_bulk_decision act-bulk file=* hunks=4 decision=approved",
+            ]
+        );
+        let rejection = store
+            .audit_records_for_action("act-bulk")
+            .into_iter()
+            .find(|record| record.approval_decision == ApprovalDecision::Denied);
+        assert_eq!(
+            rejection.and_then(|record| record.metadata.get("hunk_ids").cloned()),
+            Some("hunk-1,hunk-2".to_string())
+        );
+    }
+
+    #[test]
+    fn expire_pending_approvals_marks_final_state() {
+        let mut store = UiEventStore::default();
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-expire".to_string(),
+                summary: "aprovação com timeout".to_string(),
+                expires_at_unix_s: 100,
+            },
+        )));
+
+        let expired_messages = store.expire_pending_approvals(101);
+        assert_eq!(expired_messages.len(), 1);
+        assert!(matches!(
+            expired_messages.first().map(|message| &message.event),
+            Some(IpcEvent::ApprovalResolved(event))
+            if event.action_id == "act-expire"
+                && event.resolution == codex_alicia_core::ApprovalResolution::Expired
+                && event.approver.is_none()
+        ));
+
+        assert_eq!(store.pending_approval_count(), 0);
+        assert_eq!(
+            store.approval("act-expire").map(|item| item.status),
+            Some(ApprovalStatus::Expired)
+        );
+    }
+
+    #[test]
+    fn approval_prompt_counts_down_to_its_expiry() {
+        let mut store = UiEventStore::default();
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-countdown".to_string(),
+                summary: "aprovação com timeout".to_string(),
+                expires_at_unix_s: 10_000,
+            },
+        )));
+        let Some(prompt) = store.approval_prompt("act-countdown") else {
+            panic!("expected approval prompt");
+        };
+
+        assert_eq!(prompt.seconds_remaining(9_875), 125);
+        assert_eq!(prompt.countdown(9_875), "02:05");
+        assert_eq!(prompt.countdown(10_000 - 3_725), "1:02:05");
+        assert_eq!(prompt.seconds_remaining(10_001), 0);
+        assert_eq!(prompt.countdown(10_001), "00:00");
+    }
+
+    #[test]
+    fn command_finished_state_is_tracked() {
+        let mut store = UiEventStore::default();
+        store.push(start_event("cmd-finish"));
+        store.push(IpcMessage::new(IpcEvent::CommandFinished(
+            codex_alicia_core::ipc::CommandFinished {
+                command_id: "cmd-finish".to_string(),
+                exit_code: 0,
+                duration_ms: 42,
+            },
+        )));
+
+        let session = store.terminal_session("cmd-finish");
+        let Some(session) = session else {
+            panic!("expected terminal session state");
+        };
+
+        assert_eq!(
+            session.lifecycle,
+            CommandLifecycle::Finished {
+                exit_code: 0,
+                duration_ms: 42
+            }
+        );
+    }
+
+    #[test]
+    fn store_errors_include_clear_next_step_message() {
+        let errors = vec![
+            UiEventStoreError::SessionNotFound("sess-missing".to_string()),
+            UiEventStoreError::SessionInputNotBound("sess-not-bound".to_string()),
+            UiEventStoreError::SessionInputSendFailed {
+                session_id: "sess-send".to_string(),
+                reason: "channel closed".to_string(),
+            },
+            UiEventStoreError::ApprovalNotPending("act-ready".to_string()),
+        ];
+
+        for error in errors {
+            let message = error.beginner_message();
+            assert!(
+                message.contains("Proximo passo:"),
+                "expected beginner guidance in message: {message}"
+            );
+            assert!(
+                !message.contains('`'),
+                "message should avoid technical formatting: {message}"
+            );
+        }
+    }
+
+    #[test]
+    fn store_collects_reported_errors_in_error_center() {
+        let mut store = UiEventStore::default();
+        let error = UiEventStoreError::SessionInputNotBound("sess-late".to_string());
+
+        let id = store.record_error(error.error_report(), 1_700_000_000);
+
+        assert_eq!(
+            store.error_center().entry(id),
+            Some(&ErrorEntry {
+                id,
+                timestamp_unix_s: 1_700_000_000,
+                code: "session_input_not_bound",
+                message: error.beginner_message_in(UiLanguage::Portuguese),
+                suggested_actions: vec![ErrorAction::Retry],
+                read: false,
+            })
+        );
+        assert_eq!(store.error_center().unread_count(), 1);
+        assert_eq!(
+            store.timeline().last().map(|entry| entry.summary.as_str()),
+            Some("error session_input_not_bound")
+        );
+    }
+
+    #[test]
+    fn chat_messages_route_fenced_blocks_to_code_renderer() {
+        let mut store = UiEventStore::default();
+
+        let id = store.push_chat_message(ChatRole::Agent, "Rode:\n\n```sh\ncargo test\n```");
+
+        let message = &store.chat_messages()[0];
+        assert_eq!(message.id, id);
+        assert_eq!(
+            message.blocks().last(),
+            Some(&MarkdownBlock::CodeBlock(CodeBlock {
+                language: Some("sh".to_string()),
+                code: "cargo test".to_string(),
+            }))
+        );
+        assert_eq!(
+            store.timeline().last().map(|entry| entry.summary.as_str()),
+            Some("chat_message 0 agent")
+        );
+    }
+
+    #[test]
+    fn agent_message_deltas_stream_into_one_chat_message() {
+        let mut store = UiEventStore::default();
+        let delta = |delta: &str| {
+            IpcMessage::new(IpcEvent::AgentMessageDelta(AgentMessageDelta {
+                message_id: "msg-1".to_string(),
+                delta: delta.to_string(),
+            }))
+        };
+
+        store.push(delta("Rode:\n\n```sh\ncargo "));
+        assert!(store.is_agent_typing());
+        store.push(delta("test\n```"));
+        store.push(IpcMessage::new(IpcEvent::AgentMessageComplete(
+            AgentMessageComplete {
+                message_id: "msg-1".to_string(),
+            },
+        )));
+        store.push(delta(" atrasado"));
+
+        assert!(!store.is_agent_typing());
+        assert_eq!(store.chat_messages().len(), 1);
+        let message = &store.chat_messages()[0];
+        assert_eq!(message.role, ChatRole::Agent);
+        assert_eq!(message.content, "Rode:\n\n```sh\ncargo test\n```");
+        assert_eq!(
+            message.blocks().last(),
+            Some(&MarkdownBlock::CodeBlock(CodeBlock {
+                language: Some("sh".to_string()),
+                code: "cargo test".to_string(),
+            }))
+        );
+    }
+
+    #[test]
+    fn dropped_files_attach_inside_workspace_and_move_to_prompt() -> std::io::Result<()> {
+        let workspace = tempfile::TempDir::new()?;
+        let outside = tempfile::TempDir::new()?;
+        std::fs::create_dir_all(workspace.path().join("src"))?;
+        std::fs::write(workspace.path().join("src").join("main.rs"), "fn main() {}")?;
+        std::fs::write(outside.path().join("secret.txt"), "token")?;
+        let mut store = UiEventStore::default();
+
+        let attached = store.attach_context_file(
+            workspace.path(),
+            &workspace.path().join("src").join("main.rs"),
+        );
+        let expected = ContextAttachment {
+            path: std::path::Path::new("src")
+                .join("main.rs")
+                .to_string_lossy()
+                .to_string(),
+            size_bytes: 12,
+        };
+        assert_eq!(attached, Ok(expected.clone()));
+        assert!(matches!(
+            store.attach_context_file(workspace.path(), &outside.path().join("secret.txt")),
+            Err(UiEventStoreError::AttachmentOutsideWorkspace { .. })
+        ));
+        assert!(matches!(
+            store.attach_context_file(workspace.path(), &workspace.path().join("src")),
+            Err(UiEventStoreError::AttachmentNotAFile(_))
+        ));
+        assert_eq!(store.draft_attachments(), std::slice::from_ref(&expected));
+
+        store.submit_chat_prompt("Explique este arquivo");
+
+        assert!(store.draft_attachments().is_empty());
+        assert_eq!(
+            store
+                .chat_messages()
+                .last()
+                .map(|message| message.attachments.clone()),
+            Some(vec![expected])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn copied_code_blocks_are_recorded_in_timeline() {
+        let mut store = UiEventStore::default();
+        let block = CodeBlock {
+            language: None,
+            code: "cargo test\ngit status".to_string(),
+        };
+
+        store.record_code_block_copy(4, &block);
+
+        assert_eq!(
+            store.timeline().last().map(|entry| entry.summary.as_str()),
+            Some("code_block_copied message=4 lang=sh chars=21 cargo test\\ngit status")
+        );
+    }
+
+    #[test]
+    fn beginner_messages_switch_language_without_changing_display() {
+        let error = UiEventStoreError::ApprovalNotPending("act-ready".to_string());
+
+        assert_eq!(
+            error.beginner_message_in(UiLanguage::English),
+            "This approval has already been resolved. Next step: Refresh the screen and move on to the next pending approval."
+        );
+        assert_eq!(
+            error.beginner_message_in(UiLanguage::Portuguese),
+            "Essa aprovacao ja foi resolvida. Proximo passo: Atualize a tela e siga para a proxima aprovacao pendente."
+        );
+        assert_eq!(error.to_string(), "approval `act-ready` is not pending");
+    }
+
+    #[test]
+    fn runtime_errors_include_clear_next_step_message() {
+        let errors = vec![
+            AliciaUiRuntimeError::SessionManager(SessionManagerError::SessionNotFound(
+                "sess-runtime".to_string(),
+            )),
+            AliciaUiRuntimeError::ResolveProfileFailed {
+                workspace: "workspace".to_string(),
+                source: codex_alicia_core::ProjectPolicyConfigError::ReadFailed {
+                    path: ".codex/alicia-policy.toml".to_string(),
+                    source: std::io::Error::other("missing file"),
+                },
+            },
+            AliciaUiRuntimeError::WorkspaceGuardBlocked {
+                session_id: "sess-workspace".to_string(),
+                cwd: "../outside".to_string(),
+                source: codex_alicia_core::PolicyBridgeError::TargetOutsideWorkspace {
+                    workspace: "/repo".to_string(),
+                    target: "/outside".to_string(),
+                },
+            },
+            AliciaUiRuntimeError::CommandBlocked {
+                session_id: "sess-blocked".to_string(),
+                reason: "approval required".to_string(),
+            },
+            AliciaUiRuntimeError::SessionStopTimeout {
+                session_id: "sess-timeout".to_string(),
+            },
+            AliciaUiRuntimeError::OperationCancelled {
+                session_id: "sess-cancelled".to_string(),
+            },
+            AliciaUiRuntimeError::AuditWriteFailed {
+                session_id: "sess-audit".to_string(),
+                source: std::io::Error::other("disk full"),
+            },
+        ];
+
+        for error in errors {
+            let message = error.beginner_message();
+            assert!(
+                message.contains("Proximo passo:"),
+                "expected beginner guidance in message: {message}"
+            );
+            assert!(
+                !message.contains('`'),
+                "message should avoid technical formatting: {message}"
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn runtime_blocks_command_without_explicit_approval_in_read_write_profile() {
+        let session_manager = SessionManager::new();
+        let mut runtime = AliciaUiRuntime::new(session_manager, 128);
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
+
+        let Ok((program, args)) = shell_echo_command("blocked-by-approval") else {
+            panic!("echo command should build");
+        };
+        let session_id = "sess-blocked-approval";
+        let request = SessionStartRequest::new(
+            session_id,
+            program,
+            args,
+            PathBuf::from("."),
+            inherited_env(),
+        )
+        .with_mode(SessionMode::Pipe);
+
+        let result = runtime.start_session(request).await;
+        assert!(matches!(
+            result,
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+        ));
+        assert!(!runtime.session_manager().is_active(session_id).await);
+
+        let blocked_record = runtime
+            .store()
+            .audit_records()
+            .iter()
+            .find(|record| record.session_id == session_id)
+            .cloned();
+        let Some(blocked_record) = blocked_record else {
+            panic!("expected blocked audit record");
+        };
+        assert_eq!(
+            blocked_record.policy_decision,
+            PolicyDecision::RequireApproval
+        );
+        assert_eq!(
+            blocked_record.approval_decision,
+            ApprovalDecision::NotRequired
+        );
+        assert_eq!(blocked_record.result_status, ResultStatus::Blocked);
+    }
+
+    #[test]
+    fn spawn_retry_backoff_doubles_until_cap() {
+        let policy = SpawnRetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(350),
+        };
+
+        let backoffs: Vec<Duration> = (1..=4).map(|attempt| policy.backoff_for(attempt)).collect();
+        assert_eq!(
+            backoffs,
+            vec![
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+                Duration::from_millis(350),
+                Duration::from_millis(350),
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn runtime_does_not_retry_permanent_spawn_failures() {
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128);
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::FullAccess);
+
+        let request = SessionStartRequest::new(
+            "sess-missing-binary",
+            "alicia-missing-binary-for-test",
+            Vec::new(),
+            PathBuf::from("."),
+            inherited_env(),
+        )
+        .with_mode(SessionMode::Pipe);
+        let result = runtime.start_session(request).await;
+
+        assert!(matches!(
+            result,
+            Err(AliciaUiRuntimeError::SessionManager(
+                SessionManagerError::SpawnFailed { .. }
+            ))
+        ));
+        let attempts: Vec<&str> = runtime
+            .store()
+            .timeline()
+            .iter()
+            .filter(|entry| entry.summary.starts_with("session_spawn_attempt_failed"))
+            .map(|entry| entry.summary.as_str())
+            .collect();
+        assert_eq!(attempts.len(), 1);
+        assert!(attempts[0].contains("sess-missing-binary attempt=1/3 giving_up"));
+    }
+
+    #[tokio::test]
+    async fn runtime_gates_pull_requests_behind_network_access() {
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128);
+        let provider = PullRequestProvider::GitHub {
+            api_base: "https://api.github.com".to_string(),
+            owner: "acme".to_string(),
+            repo: "app".to_string(),
+        };
+        let draft = PullRequestDraft {
+            source_action_id: "act-1".to_string(),
+            branch: "alicia/act-1".to_string(),
+            base: "main".to_string(),
+            title: "AlicIA: Corrige parser".to_string(),
+            body: String::new(),
+        };
+
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::ReadOnly);
+        let denied = runtime
+            .create_pull_request(&draft, &provider, "token")
+            .await;
+        assert!(matches!(denied, Err(PullRequestError::NetworkDenied(_))));
+        assert_eq!(
+            runtime.store().audit_records()[0].result_status,
+            ResultStatus::Blocked
+        );
+
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
+        let action_id = runtime.request_pull_request(&draft, &provider);
+        assert_eq!(action_id, "act-1-pr");
+        let approval = runtime.store().approval(&action_id).cloned();
+        assert_eq!(
+            approval.as_ref().map(|approval| approval.status),
+            Some(ApprovalStatus::Pending)
+        );
+        assert_eq!(
+            approval.and_then(|approval| approval.action_kind),
+            Some(ActionKind::NetworkAccess)
+        );
+        let pending = runtime
+            .create_pull_request(&draft, &provider, "token")
+            .await;
+        assert!(matches!(pending, Err(PullRequestError::NotApproved(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn ipc_clients_cannot_resolve_their_own_approvals()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("alicia.sock");
+        let runtime = AliciaUiRuntime::new(SessionManager::new(), 128);
+        let serving =
+            runtime.spawn_ipc_server(codex_alicia_core::ipc::server::IpcServer::bind(&path)?);
+        let mut client = codex_alicia_core::ipc::client::IpcClient::connect(&path).await?;
+
+        client
+            .send(&IpcMessage::new(IpcEvent::ApprovalRequested(
+                ApprovalRequested {
+                    action_id: "act-1".to_string(),
+                    summary: "rm -rf build".to_string(),
+                    expires_at_unix_s: i64::MAX,
+                },
+            )))
+            .await?;
+        client
+            .send(&IpcMessage::new(IpcEvent::ApprovalResolved(
+                ApprovalResolved {
+                    action_id: "act-1".to_string(),
+                    resolution: ApprovalResolution::Approved,
+                    reason: None,
+                    approver: Some(ApproverIdentity::configured("ana")),
+                },
+            )))
+            .await?;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !runtime
+                .store()
+                .timeline()
+                .iter()
+                .any(|entry| entry.summary.starts_with("ipc_rejected"))
+            {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await?;
+
+        let status = runtime
+            .store()
+            .approval("act-1")
+            .map(|approval| approval.status);
+        assert_eq!(status, Some(ApprovalStatus::Pending));
+        serving.abort();
+        Ok(())
+    }
+
+    #[test]
+    fn ipc_clients_cannot_touch_resolved_actions_or_runtime_sessions() {
+        let mut store = UiEventStore::default();
+        let chunk = |session_id: &str| {
+            IpcMessage::new(IpcEvent::CommandOutputChunk(CommandOutputChunk {
+                command_id: session_id.to_string(),
+                stream: codex_alicia_core::CommandOutputStream::Stdout,
+                chunk: "ok\n".to_string(),
+            }))
+        };
+        assert!(
+            store.push_from_ipc(IpcMessage::new(IpcEvent::ApprovalRequested(
+                ApprovalRequested {
+                    action_id: "act-1".to_string(),
+                    summary: "Editar main".to_string(),
+                    expires_at_unix_s: i64::MAX,
+                },
+            )))
+        );
+        assert!(store.approve("act-1").is_ok());
+
+        assert!(
+            !store.push_from_ipc(IpcMessage::new(IpcEvent::PatchPreviewReady(
+                PatchPreviewReady {
+                    action_id: "act-1".to_string(),
+                    files: vec!["src/lib.rs".to_string()],
+                },
+            )))
+        );
+        assert!(
+            !store.push_from_ipc(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+                action_id: "act-1".to_string(),
+                action_kind: ActionKind::ReadFile,
+                target: "README.md".to_string(),
+            })))
+        );
+        assert!(store.diff_preview("act-1").is_none());
+
+        store.push(start_event("sess-runtime"));
+        assert!(!store.push_from_ipc(start_event("sess-runtime")));
+        assert!(!store.push_from_ipc(chunk("sess-runtime")));
+        assert!(store.push_from_ipc(start_event("sess-agent")));
+        assert!(store.push_from_ipc(chunk("sess-agent")));
+        assert!(
+            store
+                .timeline()
+                .iter()
+                .any(|entry| entry.summary == "ipc_rejected sess-runtime")
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn ipc_clients_receive_resolutions_made_in_the_ui()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("alicia.sock");
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128);
+        let serving =
+            runtime.spawn_ipc_server(codex_alicia_core::ipc::server::IpcServer::bind(&path)?);
+        let mut client = codex_alicia_core::ipc::client::IpcClient::connect(&path).await?;
+
+        client
+            .send(&IpcMessage::new(IpcEvent::ApprovalRequested(
+                ApprovalRequested {
+                    action_id: "act-1".to_string(),
+                    summary: "cargo publish".to_string(),
+                    expires_at_unix_s: i64::MAX,
+                },
+            )))
+            .await?;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while runtime.store().approval("act-1").is_none() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await?;
+
+        runtime.store_mut().deny("act-1")?;
+        runtime.pump_events();
+
+        let received = tokio::time::timeout(Duration::from_secs(5), client.recv()).await??;
+        assert!(matches!(
+            received.map(|message| message.event),
+            Some(IpcEvent::ApprovalResolved(event))
+                if event.action_id == "act-1"
+                    && event.resolution == ApprovalResolution::Denied
+        ));
+        serving.abort();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn expiry_task_expires_approvals_and_requests_a_repaint() {
+        let repaints = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&repaints);
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128).with_repaint_callback(
+            Arc::new(move || {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }),
+        );
+        let mut approval_events = runtime.approval_event_receiver();
+        runtime
+            .store_mut()
+            .push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                ApprovalRequested {
+                    action_id: "act-old".to_string(),
+                    summary: "aprovacao vencida".to_string(),
+                    expires_at_unix_s: 1,
+                },
+            )));
+
+        let task = runtime.spawn_approval_expiry_task(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        task.abort();
+
+        assert_eq!(
+            runtime.store().approval("act-old").map(|item| item.status),
+            Some(ApprovalStatus::Expired)
+        );
+        assert!(matches!(
+            approval_events.try_recv().map(|message| message.event),
+            Ok(IpcEvent::ApprovalResolved(event))
+                if event.action_id == "act-old"
+                    && event.resolution == ApprovalResolution::Expired
+        ));
+        assert!(repaints.load(std::sync::atomic::Ordering::SeqCst) > 0);
+    }
+
+    #[tokio::test]
+    async fn runtime_gates_patch_apply_on_profile_and_approval()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128);
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
+        runtime
+            .store_mut()
+            .push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+                PatchPreviewReady {
+                    action_id: "act-patch".to_string(),
+                    files: vec!["src/main.rs".to_string()],
+                },
+            )));
+        runtime
+            .store_mut()
+            .push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                ApprovalRequested {
+                    action_id: "act-patch".to_string(),
+                    summary: "Editar main".to_string(),
+                    expires_at_unix_s: i64::MAX,
+                },
+            )));
+
+        let pending = runtime
+            .record_patch_applied("act-patch", vec!["src/main.rs".to_string()])
+            .await;
+        assert!(matches!(
+            pending,
+            Err(AliciaUiRuntimeError::PatchBlocked { .. })
+        ));
+        assert_eq!(
+            runtime
+                .store()
+                .diff_preview("act-patch")
+                .map(|preview| preview.applied),
+            Some(false)
+        );
+        let blocked: Vec<(ActionKind, &str, ResultStatus)> = runtime
+            .store()
+            .audit_records()
+            .iter()
+            .map(|record| {
+                (
+                    record.action_kind,
+                    record.target.as_str(),
+                    record.result_status,
+                )
+            })
+            .collect();
+        assert_eq!(
+            blocked,
+            vec![(ActionKind::ApplyPatch, "src/main.rs", ResultStatus::Blocked)]
+        );
+
+        runtime.store_mut().approve("act-patch")?;
+        runtime
+            .record_patch_applied("act-patch", vec!["src/main.rs".to_string()])
+            .await?;
+        let applied = runtime.store().audit_records().last().cloned();
+        assert_eq!(
+            applied.map(|record| (
+                record.action_kind,
+                record.approval_decision,
+                record.result_status
+            )),
+            Some((
+                ActionKind::ApplyPatch,
+                ApprovalDecision::Approved,
+                ResultStatus::Succeeded
+            ))
+        );
+
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::ReadOnly);
+        let denied = runtime.authorize_patch_apply("act-patch").await;
+        assert!(matches!(
+            denied,
+            Err(AliciaUiRuntimeError::PatchBlocked { .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn patch_apply_checks_path_rules_for_every_file() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let workspace = tempfile::tempdir()?;
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        let definition = ProfileDefinition {
+            paths: vec![PathRule {
+                path: PathBuf::from("./src/generated"),
+                decision: PolicyDecision::Deny,
+            }],
+            ..Default::default()
+        };
+        runtime
+            .store_mut()
+            .set_profile(NamedProfile::from_definition(
+                "codegen",
+                &definition,
+                PermissionProfile::FullAccess,
+            ));
+        runtime
+            .store_mut()
+            .push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+                PatchPreviewReady {
+                    action_id: "act-patch".to_string(),
+                    files: vec![
+                        "src/main.rs".to_string(),
+                        workspace
+                            .path()
+                            .join("src/generated/api.rs")
+                            .to_string_lossy()
+                            .to_string(),
+                    ],
+                },
+            )));
+
+        assert!(matches!(
+            runtime.authorize_patch_apply("act-patch").await,
+            Err(AliciaUiRuntimeError::PatchBlocked { .. })
+        ));
+        let decisions: Vec<PolicyDecision> = runtime
+            .store()
+            .audit_records()
+            .iter()
+            .map(|record| record.policy_decision)
+            .collect();
+        assert_eq!(decisions, vec![PolicyDecision::Allow, PolicyDecision::Deny]);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn runtime_parks_blocked_command_until_its_approval_is_granted()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128);
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
+
+        let (program, args) = shell_echo_command("parked")?;
+        let session_id = "sess-parked";
+        let request = SessionStartRequest::new(
+            session_id,
+            program,
+            args,
+            PathBuf::from("."),
+            inherited_env(),
+        )
+        .with_mode(SessionMode::Pipe);
+
+        let result = runtime.start_session(request.clone()).await;
+        assert!(matches!(
+            result,
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+        ));
+        assert_eq!(
+            runtime.parked_approval_id(session_id),
+            Some("sess-parked-start")
+        );
+        let approval = runtime.store().approval("sess-parked-start").cloned();
+        assert_eq!(
+            approval.as_ref().map(|approval| approval.status),
+            Some(ApprovalStatus::Pending)
+        );
+        assert!(approval.is_some_and(|approval| approval.command.is_some()));
+
+        // Retrying while the approval is pending must not queue a second request.
+        let _ = runtime.start_session(request).await;
+        assert_eq!(runtime.store().pending_approval_count(), 1);
+        assert!(runtime.resume_parked_sessions().await?.is_empty());
+
+        runtime.store_mut().approve("sess-parked-start")?;
+        let started = runtime.resume_parked_sessions().await?;
+
+        assert_eq!(started, vec![session_id.to_string()]);
+        assert_eq!(runtime.parked_approval_id(session_id), None);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn always_allow_adds_a_policy_rule_later_runs_approve_with()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::tempdir()?;
+        let (program, args) = shell_echo_command("regra")?;
+        let request = |session_id: &str| {
+            SessionStartRequest::new(
+                session_id,
+                program.clone(),
+                args.clone(),
+                workspace.path().to_path_buf(),
+                inherited_env(),
+            )
+            .with_mode(SessionMode::Pipe)
+        };
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
+        assert!(matches!(
+            runtime.start_session(request("sess-1")).await,
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+        ));
+        runtime.store_mut().always_allow("sess-1-start")?;
+        runtime.save_requested_approval_rules();
+        let Some(policy) = codex_alicia_core::load_project_policy(workspace.path())? else {
+            panic!("always allow should create the project policy");
+        };
+        let command = super::command_tokens(&program, &args);
+        assert!(policy.approval_rules.matching_rule(&command).is_some());
+
+        let mut restarted = AliciaUiRuntime::new(SessionManager::new(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        restarted
+            .store_mut()
+            .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
+        restarted.start_session(request("sess-2")).await?;
+        assert_eq!(restarted.store().pending_approval_count(), 0);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn always_allow_saves_to_the_session_root_with_a_fixed_default_profile()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let work = tempfile::TempDir::new()?;
+        let service_a = work.path().join("service-a");
+        let service_b = work.path().join("service-b");
+        std::fs::create_dir_all(&service_a)?;
+        std::fs::create_dir_all(&service_b)?;
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128)
+            .with_workspace_root(service_a.clone())
+            .with_additional_workspace_root(
+                WorkspaceRoot::new(service_b.clone())
+                    .with_fallback_profile(PermissionProfile::ReadWriteWithApproval),
+            );
+        let (program, args) = shell_echo_command("service-b")?;
+        assert!(matches!(
+            runtime
+                .start_session(
+                    SessionStartRequest::new(
+                        "sess-b",
+                        program.clone(),
+                        args.clone(),
+                        service_b.clone(),
+                        inherited_env(),
+                    )
+                    .with_mode(SessionMode::Pipe),
+                )
+                .await,
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+        ));
+
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::FullAccess);
+        runtime.store_mut().always_allow("sess-b-start")?;
+        runtime.save_requested_approval_rules();
+
+        assert_eq!(codex_alicia_core::load_project_policy(&service_a)?, None);
+        let Some(policy) = codex_alicia_core::load_project_policy(&service_b)? else {
+            panic!("always allow should create the session root's policy");
+        };
+        assert_eq!(
+            policy.permission_profile,
+            PermissionProfile::ReadWriteWithApproval
+        );
+        let command = super::command_tokens(&program, &args);
+        assert!(policy.approval_rules.matching_rule(&command).is_some());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn apply_approved_hunks_writes_only_approved_hunks_with_a_backup()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::tempdir()?;
+        std::fs::create_dir_all(workspace.path().join("src"))?;
+        let original = "line_1\nline_2\nmid\nmid\nmid\nmid\nmid\nmid\nmid\nold_tail\n";
+        std::fs::write(workspace.path().join("src/main.rs"), original)?;
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::FullAccess);
+        runtime
+            .store_mut()
+            .push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+                PatchPreviewReady {
+                    action_id: "act-hunks".to_string(),
+                    files: vec!["src/main.rs".to_string()],
+                },
+            )));
+        runtime.store_mut().attach_patch_file_diff(
+            "act-hunks",
+            "src/main.rs",
+            sample_unified_diff(),
+        )?;
+
+        runtime
+            .store_mut()
+            .approve_patch_hunk("act-hunks", "src/main.rs", "hunk-1")?;
+        assert!(matches!(
+            runtime.apply_approved_hunks("act-hunks").await,
+            Err(AliciaUiRuntimeError::PatchApplyFailed(
+                PatchApplyError::UndecidedHunks { count: 1, .. }
+            ))
+        ));
+        runtime
+            .store_mut()
+            .reject_patch_hunk("act-hunks", "src/main.rs", "hunk-2")?;
+        runtime.store_mut().request_hunk_apply("act-hunks");
+        assert_eq!(runtime.apply_requested_hunks().await, vec!["act-hunks"]);
+
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("src/main.rs"))?,
+            "line_1_new\nline_2\nline_3\nmid\nmid\nmid\nmid\nmid\nmid\nmid\nold_tail\n"
+        );
+        let backup = super::patch_backup_path(workspace.path(), "act-hunks", "src/main.rs");
+        assert_eq!(std::fs::read_to_string(&backup)?, original);
+        assert!(
+            runtime
+                .store()
+                .diff_preview("act-hunks")
+                .is_some_and(|preview| preview.applied)
+        );
+
+        assert!(matches!(
+            runtime.apply_approved_hunks("act-hunks").await,
+            Err(AliciaUiRuntimeError::PatchApplyFailed(
+                PatchApplyError::AlreadyApplied(_)
+            ))
+        ));
+        assert_eq!(std::fs::read_to_string(backup)?, original);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn apply_approved_hunks_puts_back_rewritten_files_when_a_write_fails()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::tempdir()?;
+        std::fs::create_dir_all(workspace.path().join("src"))?;
+        std::fs::write(workspace.path().join("src/main.rs"), "old\n")?;
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::FullAccess);
+        runtime
+            .store_mut()
+            .push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+                PatchPreviewReady {
+                    action_id: "act-partial".to_string(),
+                    files: Vec::new(),
+                },
+            )));
+        // `gen` is created as a file, so the directory `gen/out.rs` needs can't be made.
+        for (file_path, diff) in [
+            ("src/main.rs", "@@ -1,1 +1,1 @@\n-old\n+new\n"),
+            ("gen", "@@ -0,0 +1,1 @@\n+generated\n"),
+            ("gen/out.rs", "@@ -0,0 +1,1 @@\n+out\n"),
+        ] {
+            runtime
+                .store_mut()
+                .attach_patch_file_diff("act-partial", file_path, diff)?;
+            runtime
+                .store_mut()
+                .approve_patch_hunk("act-partial", file_path, "hunk-1")?;
+        }
+
+        assert!(matches!(
+            runtime.apply_approved_hunks("act-partial").await,
+            Err(AliciaUiRuntimeError::PatchApplyFailed(
+                PatchApplyError::WriteFailed { .. }
+            ))
+        ));
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("src/main.rs"))?,
+            "old\n"
+        );
+        assert!(!workspace.path().join("gen").exists());
+        assert!(
+            runtime
+                .store()
+                .diff_preview("act-partial")
+                .is_some_and(|preview| !preview.applied)
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn requested_audit_exports_are_written_under_the_workspace()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::tempdir()?;
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        for (session_id, result_status) in [
+            ("sess-1", ResultStatus::Succeeded),
+            ("sess-1", ResultStatus::Blocked),
+            ("sess-2", ResultStatus::Succeeded),
+        ] {
+            runtime.store_mut().add_audit_record(AuditRecord::new(
+                session_id,
+                ActionKind::ExecuteCommand,
+                "cargo test",
+                PermissionProfile::ReadWriteWithApproval,
+                PolicyDecision::Allow,
+                ApprovalDecision::NotRequired,
+                result_status,
+                5,
+            ));
+        }
+        runtime.store_mut().request_audit_export(
+            AuditExporter::new(AuditExportFormat::Jsonl).with_filter(
+                AuditExportFilter::default()
+                    .with_session_id("sess-1")
+                    .with_result_statuses(vec![ResultStatus::Succeeded]),
+            ),
+        );
+
+        let written = runtime.export_requested_audits();
+        let [path] = written.as_slice() else {
+            panic!("one export should be written, got {written:?}");
+        };
+        assert!(path.starts_with(workspace.path().join(super::AUDIT_EXPORTS_DIR)));
+        let exported: Vec<AuditRecord> = std::fs::read_to_string(path)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].result_status, ResultStatus::Succeeded);
+        assert!(runtime.store().chat_messages().iter().any(|message| {
+            message.role == ChatRole::System && message.content.contains("Auditoria exportada")
+        }));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn runtime_blocks_command_with_denied_approval_in_read_write_profile() {
+        let session_manager = SessionManager::new();
+        let mut runtime = AliciaUiRuntime::new(session_manager, 128);
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
+
+        let marker = "denied-by-policy";
+        let Ok((program, args)) = shell_echo_command(marker) else {
+            panic!("echo command should build");
+        };
+        let mut command = vec![program.clone()];
+        command.extend(args.clone());
+        runtime
+            .store_mut()
+            .push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+                action_id: "act-denied-cmd".to_string(),
+                action_kind: ActionKind::ExecuteCommand,
+                target: command.join(" "),
+            })));
+        runtime
+            .store_mut()
+            .attach_approval_command("act-denied-cmd", command);
+        runtime
+            .store_mut()
+            .push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                ApprovalRequested {
+                    action_id: "act-denied-cmd".to_string(),
+                    summary: "executar comando negado".to_string(),
+                    expires_at_unix_s: 4_102_444_800,
+                },
+            )));
+        let deny_result = runtime.store_mut().resolve_pending_approval_as(
+            "act-denied-cmd",
+            codex_alicia_core::ApprovalResolution::Denied,
+            Some(ApproverIdentity::remote_principal("ops@example.com")),
+        );
+        assert!(deny_result.is_ok(), "expected denial to resolve");
+
+        let session_id = "sess-denied-approval";
+        let request = SessionStartRequest::new(
+            session_id,
+            program,
+            args,
+            PathBuf::from("."),
+            inherited_env(),
+        )
+        .with_mode(SessionMode::Pipe);
+        let result = runtime.start_session(request).await;
+        assert!(matches!(
+            result,
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+        ));
+
+        let blocked_record = runtime
+            .store()
+            .audit_records()
+            .iter()
+            .find(|record| record.session_id == session_id)
+            .cloned();
+        let Some(blocked_record) = blocked_record else {
+            panic!("expected blocked audit record");
+        };
+        assert_eq!(
+            blocked_record.policy_decision,
+            PolicyDecision::RequireApproval
+        );
+        assert_eq!(blocked_record.approval_decision, ApprovalDecision::Denied);
+        assert_eq!(blocked_record.result_status, ResultStatus::Blocked);
+        assert_eq!(blocked_record.action_id.as_deref(), Some("act-denied-cmd"));
+        assert_eq!(blocked_record.event_sequence, Some(2));
+        assert_eq!(
+            blocked_record.approver,
+            Some(ApproverIdentity::remote_principal("ops@example.com"))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn runtime_allows_command_with_resolved_approval_in_read_write_profile() {
+        let session_manager = SessionManager::new();
+        let mut runtime = AliciaUiRuntime::new(session_manager, 128);
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
+
+        let marker = "approved-by-policy";
+        let Ok((program, args)) = shell_echo_command(marker) else {
+            panic!("echo command should build");
+        };
+        let mut command = vec![program.clone()];
+        command.extend(args.clone());
+        runtime
+            .store_mut()
+            .push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+                action_id: "act-approved-cmd".to_string(),
+                action_kind: ActionKind::ExecuteCommand,
+                target: command.join(" "),
+            })));
+        runtime
+            .store_mut()
+            .attach_approval_command("act-approved-cmd", command);
+        runtime
+            .store_mut()
+            .push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                ApprovalRequested {
+                    action_id: "act-approved-cmd".to_string(),
+                    summary: "executar comando aprovado".to_string(),
+                    expires_at_unix_s: 4_102_444_800,
+                },
+            )));
+        let approve_result = runtime.store_mut().approve("act-approved-cmd");
+        assert!(approve_result.is_ok(), "expected approval to resolve");
+
+        let request = SessionStartRequest::new(
+            "sess-approved-approval",
+            program,
+            args,
+            PathBuf::from("."),
+            inherited_env(),
+        )
+        .with_mode(SessionMode::Pipe);
+
+        if let Err(error) = runtime.start_session(request).await {
+            panic!("expected approved execution to start: {error}");
+        }
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        let mut saw_marker = false;
+        let mut finished_ok = false;
+        while tokio::time::Instant::now() < deadline {
+            runtime.pump_events();
+            if let Some(text) = runtime.store().active_terminal_text()
+                && text.contains(marker)
+            {
+                saw_marker = true;
+            }
+            if let Some(session) = runtime.store().terminal_session("sess-approved-approval")
+                && matches!(
+                    session.lifecycle,
+                    CommandLifecycle::Finished {
+                        exit_code: 0,
+                        duration_ms: _
+                    }
+                )
+            {
+                finished_ok = true;
+            }
+            if saw_marker && finished_ok {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(25)).await;
+        }
 
-        assert_eq!(terminal, "b\nc\nd");
+        assert!(saw_marker, "expected approved command output marker");
+        assert!(
+            finished_ok,
+            "expected approved command to finish successfully"
+        );
     }
 
-    #[test]
-    fn routes_input_to_the_selected_session() {
-        let mut store = UiEventStore::default();
-        store.push(start_event("sess-1"));
-        store.push(start_event("sess-2"));
-
-        let set_result = store.set_active_session("sess-2");
-        assert_eq!(set_result, Ok(()));
-
-        let (tx_1, mut rx_1) = tokio::sync::mpsc::channel(4);
-        let (tx_2, mut rx_2) = tokio::sync::mpsc::channel(4);
-        store.bind_session_input("sess-1", tx_1);
-        store.bind_session_input("sess-2", tx_2);
-
-        let send_result = store.send_input_to_active_session("echo Alicia");
-        assert_eq!(send_result, Ok(()));
-
-        assert_eq!(rx_1.try_recv(), Err(TryRecvError::Empty));
-        assert_eq!(rx_2.try_recv(), Ok(b"echo Alicia".to_vec()));
-    }
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn runtime_blocked_audit_carries_project_audit_fields()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::TempDir::new()?;
+        let policy_path = codex_alicia_core::project_policy_file_path(workspace.path());
+        if let Some(parent) = policy_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(
+            &policy_path,
+            "permission_profile = \"read_only\"\n\n[audit_fields]\ncost_center = { value = \"eng-42\" }\n",
+        )?;
+
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        let (program, args) = shell_echo_command("blocked-read-only")?;
+        let result = runtime
+            .start_session(
+                SessionStartRequest::new(
+                    "sess-audit-fields",
+                    program,
+                    args,
+                    workspace.path().to_path_buf(),
+                    inherited_env(),
+                )
+                .with_mode(SessionMode::Pipe),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+        ));
 
-    #[test]
-    fn approval_prompt_contains_context_and_decision_updates_state() {
-        let mut store = UiEventStore::default();
-        store.push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
-            action_id: "act-ctx".to_string(),
-            action_kind: codex_alicia_core::ActionKind::WriteFile,
-            target: "src/main.rs".to_string(),
-        })));
-        store.attach_approval_command(
-            "act-ctx",
-            vec!["cargo".to_string(), "test".to_string(), "-p".to_string()],
+        let record = runtime
+            .store()
+            .audit_records()
+            .iter()
+            .find(|record| record.session_id == "sess-audit-fields")
+            .cloned()
+            .ok_or("expected blocked audit record")?;
+        assert_eq!(
+            record.metadata,
+            BTreeMap::from([("cost_center".to_string(), "eng-42".to_string())])
         );
-        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
-            PatchPreviewReady {
-                action_id: "act-ctx".to_string(),
-                files: vec!["src/main.rs".to_string(), "src/lib.rs".to_string()],
-            },
-        )));
-        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
-            ApprovalRequested {
-                action_id: "act-ctx".to_string(),
-                summary: "Editar arquivos críticos".to_string(),
-                expires_at_unix_s: 1_735_689_600,
-            },
-        )));
-
-        let prompt = store.approval_prompt("act-ctx");
-        let Some(prompt) = prompt else {
-            panic!("expected approval prompt");
-        };
+        Ok(())
+    }
 
-        let expected = ApprovalPrompt {
-            action_id: "act-ctx".to_string(),
-            status: ApprovalStatus::Pending,
-            what: "Editar arquivos críticos".to_string(),
-            where_target: Some("src/main.rs".to_string()),
-            action_kind: Some(codex_alicia_core::ActionKind::WriteFile),
-            command: Some("cargo test -p".to_string()),
-            impact: Some("2 arquivo(s): src/main.rs, src/lib.rs".to_string()),
-            expires_at_unix_s: 1_735_689_600,
-        };
-        assert_eq!(prompt, expected);
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn runtime_governs_each_workspace_root_with_its_own_profile()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let work = tempfile::TempDir::new()?;
+        let service_a = work.path().join("service-a");
+        let service_b = work.path().join("service-b");
+        std::fs::create_dir_all(&service_a)?;
+        let policy_path = codex_alicia_core::project_policy_file_path(&service_b);
+        if let Some(parent) = policy_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&policy_path, "permission_profile = \"read_only\"\n")?;
 
-        let decision = store.approve("act-ctx");
-        let Ok(decision) = decision else {
-            panic!("approval should resolve");
-        };
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128)
+            .with_workspace_root(service_a.clone())
+            .with_additional_workspace_root(
+                WorkspaceRoot::new(service_b.clone())
+                    .with_fallback_profile(PermissionProfile::FullAccess),
+            );
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::FullAccess);
 
+        let (program, args) = shell_echo_command("service-b")?;
+        let blocked = runtime
+            .start_session(
+                SessionStartRequest::new(
+                    "sess-service-b",
+                    program,
+                    args,
+                    service_b.clone(),
+                    inherited_env(),
+                )
+                .with_mode(SessionMode::Pipe),
+            )
+            .await;
         assert!(matches!(
-            decision.event,
-            IpcEvent::ApprovalResolved(ref event)
-            if event.action_id == "act-ctx"
-                && event.resolution == codex_alicia_core::ApprovalResolution::Approved
+            blocked,
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
         ));
-
-        assert_eq!(store.pending_approval_count(), 0);
         assert_eq!(
-            store.approval("act-ctx").map(|item| item.status),
-            Some(ApprovalStatus::Approved)
+            runtime.session_profile("sess-service-b"),
+            PermissionProfile::ReadOnly
         );
+
+        let (program, args) = shell_echo_command("outside")?;
+        let outside = runtime
+            .start_session(
+                SessionStartRequest::new(
+                    "sess-outside",
+                    program,
+                    args,
+                    work.path().to_path_buf(),
+                    inherited_env(),
+                )
+                .with_mode(SessionMode::Pipe),
+            )
+            .await;
+        assert!(matches!(
+            outside,
+            Err(AliciaUiRuntimeError::WorkspaceGuardBlocked { .. })
+        ));
+        Ok(())
     }
 
-    #[test]
-    fn resolved_approval_decision_for_command_reads_approval_state() {
-        let mut store = UiEventStore::default();
-        let command = vec!["cargo".to_string(), "test".to_string()];
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn sessions_in_another_root_leave_the_store_profile_and_patch_rules_alone()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let work = tempfile::TempDir::new()?;
+        let service_a = work.path().join("service-a");
+        let service_b = work.path().join("service-b");
+        std::fs::create_dir_all(&service_a)?;
+        let policy_path = codex_alicia_core::project_policy_file_path(&service_b);
+        if let Some(parent) = policy_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&policy_path, "permission_profile = \"read_only\"\n")?;
 
-        store.push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
-            action_id: "act-command".to_string(),
-            action_kind: ActionKind::ExecuteCommand,
-            target: "cargo test".to_string(),
-        })));
-        store.attach_approval_command("act-command", command.clone());
-        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
-            ApprovalRequested {
-                action_id: "act-command".to_string(),
-                summary: "Executar comando".to_string(),
-                expires_at_unix_s: 1_735_689_600,
-            },
-        )));
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128)
+            .with_workspace_root(service_a.clone())
+            .with_additional_workspace_root(WorkspaceRoot::new(service_b.clone()));
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::FullAccess);
 
-        assert_eq!(store.resolved_approval_decision_for_command(&command), None);
+        let (program, args) = shell_echo_command("service-b")?;
+        let blocked = runtime
+            .start_session(
+                SessionStartRequest::new(
+                    "sess-service-b",
+                    program,
+                    args,
+                    service_b.clone(),
+                    inherited_env(),
+                )
+                .with_mode(SessionMode::Pipe),
+            )
+            .await;
+        assert!(matches!(
+            blocked,
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+        ));
+        assert_eq!(
+            runtime.store().permission_profile(),
+            PermissionProfile::FullAccess
+        );
 
-        let approve_result = store.approve("act-command");
-        assert!(approve_result.is_ok());
+        for (action_id, file) in [
+            ("act-a", "src/main.rs".to_string()),
+            (
+                "act-b",
+                service_b.join("src/lib.rs").to_string_lossy().to_string(),
+            ),
+        ] {
+            runtime
+                .store_mut()
+                .push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+                    PatchPreviewReady {
+                        action_id: action_id.to_string(),
+                        files: vec![file],
+                    },
+                )));
+        }
         assert_eq!(
-            store.resolved_approval_decision_for_command(&command),
-            Some(ApprovalDecision::Approved)
+            runtime.authorize_patch_apply("act-a").await?,
+            ApprovalDecision::NotRequired
         );
+        assert!(matches!(
+            runtime.authorize_patch_apply("act-b").await,
+            Err(AliciaUiRuntimeError::PatchBlocked { .. })
+        ));
+        Ok(())
     }
 
-    #[test]
-    fn timeline_preserves_order_and_diff_preview_is_available_before_apply() {
-        let mut store = UiEventStore::default();
-        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
-            PatchPreviewReady {
-                action_id: "act-diff".to_string(),
-                files: vec!["src/a.rs".to_string()],
-            },
-        )));
-
-        let preview_before = store.diff_preview("act-diff");
-        let Some(preview_before) = preview_before else {
-            panic!("expected preview before apply");
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn runtime_decides_with_the_named_profile_of_the_policy_file()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::TempDir::new()?;
+        let policy_path = codex_alicia_core::project_policy_file_path(workspace.path());
+        if let Some(parent) = policy_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(
+            &policy_path,
+            "permission_profile = \"full_access\"\nprofile = \"ci\"\n\n[profiles.ci]\ncommands = [{ prefix = \"git push\", decision = \"deny\" }]\n",
+        )?;
+        let fake = FakeSessionManager::new();
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        let request = |session_id: &str, program: &str, args: &[&str]| {
+            SessionStartRequest::new(
+                session_id,
+                program,
+                args.iter().map(|arg| arg.to_string()).collect(),
+                workspace.path().to_path_buf(),
+                HashMap::new(),
+            )
+            .with_mode(SessionMode::Pipe)
         };
-        assert_eq!(preview_before.applied, false);
-
-        store.push(IpcMessage::new(IpcEvent::PatchApplied(PatchApplied {
-            action_id: "act-diff".to_string(),
-            files: vec!["src/a.rs".to_string()],
-        })));
 
-        let preview_after = store.diff_preview("act-diff");
-        let Some(preview_after) = preview_after else {
-            panic!("expected preview after apply");
-        };
-        assert_eq!(preview_after.applied, true);
+        let blocked = runtime
+            .start_session(request("sess-push", "git", &["push", "origin", "main"]))
+            .await;
+        assert!(matches!(
+            blocked,
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+        ));
+        runtime
+            .start_session(request("sess-build", "cargo", &["build"]))
+            .await?;
 
-        let timeline = store.timeline();
-        assert_eq!(timeline.len(), 2);
-        assert_eq!(timeline[0].sequence, 0);
-        assert_eq!(timeline[1].sequence, 1);
-        assert!(timeline[0].summary.contains("patch_preview_ready"));
-        assert!(timeline[1].summary.contains("patch_applied"));
+        assert_eq!(runtime.session_named_profile("sess-build").name, "ci");
+        assert_eq!(
+            runtime.session_profile("sess-build"),
+            PermissionProfile::FullAccess
+        );
+        let audit_records = runtime.store().audit_records().to_vec();
+        assert_eq!(audit_records[0].profile_name.as_deref(), Some("ci"));
+        assert_eq!(audit_records[0].profile, PermissionProfile::FullAccess);
+        Ok(())
     }
 
-    #[test]
-    fn loads_patch_hunks_and_tracks_impact_per_hunk() {
-        let mut store = UiEventStore::default();
-        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
-            PatchPreviewReady {
-                action_id: "act-hunks".to_string(),
-                files: vec!["src/main.rs".to_string()],
-            },
-        )));
-
-        let load_result =
-            store.attach_patch_file_diff("act-hunks", "src/main.rs", sample_unified_diff());
-        assert_eq!(load_result, Ok(2));
-
-        let unresolved_count = store.unresolved_patch_hunk_count("act-hunks");
-        assert_eq!(unresolved_count, Some(2));
-
-        let preview = store.diff_preview("act-hunks");
-        let Some(preview) = preview else {
-            panic!("expected patch preview");
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn starts_check_the_temporary_full_access_deadline()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::TempDir::new()?;
+        let policy_path = codex_alicia_core::project_policy_file_path(workspace.path());
+        if let Some(parent) = policy_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(
+            &policy_path,
+            "permission_profile = \"read_write_with_approval\"\n",
+        )?;
+        let fake = FakeSessionManager::new();
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        let request = |session_id: &str| {
+            SessionStartRequest::new(
+                session_id,
+                "cargo",
+                vec!["build".to_string()],
+                workspace.path().to_path_buf(),
+                HashMap::new(),
+            )
+            .with_mode(SessionMode::Pipe)
         };
-        assert_eq!(preview.file_previews.len(), 1);
-        let file_preview = &preview.file_previews[0];
-        assert_eq!(file_preview.file_path, "src/main.rs");
-        assert_eq!(file_preview.hunks.len(), 2);
-        assert_eq!(file_preview.hunks[0].added_lines, 2);
-        assert_eq!(file_preview.hunks[0].removed_lines, 1);
-        assert_eq!(file_preview.hunks[1].added_lines, 2);
-        assert_eq!(file_preview.hunks[1].removed_lines, 1);
 
-        assert!(
-            store
-                .timeline()
-                .iter()
-                .any(|entry| entry.summary.contains("patch_hunks_loaded act-hunks")),
-            "expected timeline to register loaded hunks"
+        runtime
+            .grant_temporary_full_access(Duration::from_secs(600))
+            .await?;
+        runtime.start_session(request("sess-granted")).await?;
+        assert_eq!(
+            runtime.session_profile("sess-granted"),
+            PermissionProfile::FullAccess
         );
-    }
-
-    #[test]
-    fn allows_approving_and_rejecting_hunks_individually() {
-        let mut store = UiEventStore::default();
-        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
-            PatchPreviewReady {
-                action_id: "act-granular".to_string(),
-                files: vec!["src/main.rs".to_string()],
-            },
-        )));
-        let load_result =
-            store.attach_patch_file_diff("act-granular", "src/main.rs", sample_unified_diff());
-        assert_eq!(load_result, Ok(2));
-
-        let approve_result = store.approve_patch_hunk("act-granular", "src/main.rs", "hunk-1");
-        assert_eq!(approve_result, Ok(()));
-        let reject_result = store.reject_patch_hunk("act-granular", "src/main.rs", "hunk-2");
-        assert_eq!(reject_result, Ok(()));
 
-        assert_eq!(store.unresolved_patch_hunk_count("act-granular"), Some(0));
+        runtime.grant_temporary_full_access(Duration::ZERO).await?;
+        assert!(matches!(
+            runtime.start_session(request("sess-expired")).await,
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+        ));
+        assert_eq!(runtime.store().temporary_full_access_expires_at(), None);
+        assert!(!runtime.store().profile_pinned());
+        assert_eq!(
+            runtime.session_profile("sess-expired"),
+            PermissionProfile::ReadWriteWithApproval
+        );
+        Ok(())
+    }
 
-        let preview = store.diff_preview("act-granular");
-        let Some(preview) = preview else {
-            panic!("expected patch preview");
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn selector_profile_keeps_precedence_over_the_policy_file()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::TempDir::new()?;
+        let policy_path = codex_alicia_core::project_policy_file_path(workspace.path());
+        if let Some(parent) = policy_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&policy_path, "permission_profile = \"full_access\"\n")?;
+        let fake = FakeSessionManager::new();
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        let request = |session_id: &str| {
+            SessionStartRequest::new(
+                session_id,
+                "cargo",
+                vec!["build".to_string()],
+                workspace.path().to_path_buf(),
+                HashMap::new(),
+            )
+            .with_mode(SessionMode::Pipe)
         };
-        let file_preview = &preview.file_previews[0];
-        assert_eq!(file_preview.hunks[0].decision, PatchHunkDecision::Approved);
-        assert_eq!(file_preview.hunks[1].decision, PatchHunkDecision::Rejected);
 
-        assert!(
-            store.timeline().iter().any(|entry| entry.summary.contains(
-                "patch_hunk_decision act-granular file=src/main.rs hunk=hunk-1 decision=approved"
-            )),
-            "expected approved hunk decision in timeline"
+        runtime.start_session(request("sess-policy")).await?;
+        assert_eq!(
+            runtime.session_profile("sess-policy"),
+            PermissionProfile::FullAccess
         );
-        assert!(
-            store.timeline().iter().any(|entry| entry.summary.contains(
-                "patch_hunk_decision act-granular file=src/main.rs hunk=hunk-2 decision=rejected"
-            )),
-            "expected rejected hunk decision in timeline"
+
+        runtime
+            .set_permission_profile(PermissionProfile::ReadWriteWithApproval)
+            .await?;
+        assert!(matches!(
+            runtime.start_session(request("sess-selector")).await,
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+        ));
+        assert_eq!(
+            runtime.store().permission_profile(),
+            PermissionProfile::ReadWriteWithApproval
+        );
+        assert_eq!(
+            runtime.session_profile("sess-selector"),
+            PermissionProfile::ReadWriteWithApproval
         );
+        Ok(())
     }
 
-    #[test]
-    fn expire_pending_approvals_marks_final_state() {
-        let mut store = UiEventStore::default();
-        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
-            ApprovalRequested {
-                action_id: "act-expire".to_string(),
-                summary: "aprovação com timeout".to_string(),
-                expires_at_unix_s: 100,
-            },
-        )));
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn panic_switch_blocks_starts_even_under_a_full_access_policy()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::TempDir::new()?;
+        let policy_path = codex_alicia_core::project_policy_file_path(workspace.path());
+        if let Some(parent) = policy_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&policy_path, "permission_profile = \"full_access\"\n")?;
+        let fake = FakeSessionManager::new();
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        let request = |session_id: &str| {
+            SessionStartRequest::new(
+                session_id,
+                "cargo",
+                vec!["build".to_string()],
+                workspace.path().to_path_buf(),
+                HashMap::new(),
+            )
+            .with_mode(SessionMode::Pipe)
+        };
 
-        let expired_messages = store.expire_pending_approvals(101);
-        assert_eq!(expired_messages.len(), 1);
+        runtime.engage_panic_switch().await?;
+        runtime
+            .store_mut()
+            .change_permission_profile(PermissionProfile::FullAccess);
         assert!(matches!(
-            expired_messages.first().map(|message| &message.event),
-            Some(IpcEvent::ApprovalResolved(event))
-            if event.action_id == "act-expire"
-                && event.resolution == codex_alicia_core::ApprovalResolution::Expired
+            runtime.start_session(request("sess-locked")).await,
+            Err(AliciaUiRuntimeError::CommandBlocked { .. })
         ));
+        assert!(runtime.store().pending_approvals().is_empty());
+        assert!(runtime.store().audit_records().iter().any(|record| {
+            record.session_id == "sess-locked"
+                && record.result_status == ResultStatus::Blocked
+                && record.metadata.get("reason").map(String::as_str) == Some("panic_switch")
+        }));
 
-        assert_eq!(store.pending_approval_count(), 0);
+        runtime.store_mut().resume_agent();
+        runtime.start_session(request("sess-resumed")).await?;
         assert_eq!(
-            store.approval("act-expire").map(|item| item.status),
-            Some(ApprovalStatus::Expired)
+            runtime.session_profile("sess-resumed"),
+            PermissionProfile::FullAccess
         );
+        Ok(())
     }
 
-    #[test]
-    fn command_finished_state_is_tracked() {
-        let mut store = UiEventStore::default();
-        store.push(start_event("cmd-finish"));
-        store.push(IpcMessage::new(IpcEvent::CommandFinished(
-            codex_alicia_core::ipc::CommandFinished {
-                command_id: "cmd-finish".to_string(),
-                exit_code: 0,
-                duration_ms: 42,
-            },
-        )));
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn offline_profiles_fail_closed_without_a_network_namespace()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::TempDir::new()?;
+        let policy_path = codex_alicia_core::project_policy_file_path(workspace.path());
+        if let Some(parent) = policy_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(
+            &policy_path,
+            "permission_profile = \"full_access\"\nprofile = \"offline\"\n\n[profiles.offline]\ndecisions = { network_access = \"deny\" }\n",
+        )?;
+        let fake = FakeSessionManager::new();
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        let request = SessionStartRequest::new(
+            "sess-offline",
+            "cargo",
+            vec!["build".to_string()],
+            workspace.path().to_path_buf(),
+            HashMap::new(),
+        )
+        .with_mode(SessionMode::Pipe);
 
-        let session = store.terminal_session("cmd-finish");
-        let Some(session) = session else {
-            panic!("expected terminal session state");
-        };
+        let started = runtime.start_session(request).await;
+        match codex_alicia_core::available_network_isolation() {
+            NetworkIsolation::Namespace => {
+                assert!(started.is_ok());
+                assert_eq!(
+                    runtime
+                        .store()
+                        .terminal_session("sess-offline")
+                        .and_then(TerminalSessionState::network_isolation),
+                    Some(NetworkIsolation::Namespace)
+                );
+            }
+            NetworkIsolation::ProxyBlackhole => {
+                assert!(matches!(
+                    started,
+                    Err(AliciaUiRuntimeError::CommandBlocked { .. })
+                ));
+                assert!(runtime.store().audit_records().iter().any(|record| {
+                    record.session_id == "sess-offline"
+                        && record.policy_decision == PolicyDecision::RequireApproval
+                        && record.metadata.get("network_isolation").map(String::as_str)
+                            == Some("proxy_blackhole")
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn runtime_audits_profile_changes_requested_by_the_selector()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let mut runtime = AliciaUiRuntime::new(SessionManager::new(), 128);
+        runtime
+            .store_mut()
+            .set_approver_identity(Some(ApproverIdentity::configured("ana")));
+        runtime
+            .store_mut()
+            .request_profile_change(PermissionProfile::FullAccess);
 
         assert_eq!(
-            session.lifecycle,
-            CommandLifecycle::Finished {
-                exit_code: 0,
-                duration_ms: 42
-            }
+            runtime.apply_requested_profile_change().await?,
+            Some(PermissionProfile::FullAccess)
+        );
+        assert_eq!(runtime.apply_requested_profile_change().await?, None);
+        runtime
+            .set_permission_profile(PermissionProfile::FullAccess)
+            .await?;
+        runtime
+            .set_permission_profile(PermissionProfile::ReadOnly)
+            .await?;
+
+        let store = runtime.store();
+        assert_eq!(store.permission_profile(), PermissionProfile::ReadOnly);
+        let changes: Vec<&str> = store
+            .timeline()
+            .iter()
+            .filter(|entry| entry.summary.starts_with("profile_changed"))
+            .map(|entry| entry.summary.as_str())
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                "profile_changed from=read_write_with_approval to=full_access",
+                "profile_changed from=full_access to=read_only",
+            ]
+        );
+        let records = store.audit_records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].target, "permission_profile");
+        assert_eq!(records[0].approval_decision, ApprovalDecision::Approved);
+        assert_eq!(
+            records[0].approver,
+            Some(ApproverIdentity::configured("ana"))
+        );
+        assert_eq!(
+            records[0]
+                .metadata
+                .get("previous_profile")
+                .map(String::as_str),
+            Some("read_write_with_approval")
         );
+        assert_eq!(records[1].approval_decision, ApprovalDecision::NotRequired);
+        assert_eq!(records[1].approver, None);
+        Ok(())
     }
 
     #[test]
-    fn store_errors_include_clear_next_step_message() {
-        let errors = vec![
-            UiEventStoreError::SessionNotFound("sess-missing".to_string()),
-            UiEventStoreError::SessionInputNotBound("sess-not-bound".to_string()),
-            UiEventStoreError::SessionInputSendFailed {
-                session_id: "sess-send".to_string(),
-                reason: "channel closed".to_string(),
-            },
-            UiEventStoreError::ApprovalNotPending("act-ready".to_string()),
-        ];
+    fn temporary_full_access_reverts_to_the_previous_profile() {
+        let mut store = UiEventStore::default();
+        store.set_approver_identity(Some(ApproverIdentity::configured("ana")));
+        store.set_permission_profile(PermissionProfile::ReadOnly);
+
+        store.grant_temporary_full_access(Duration::from_secs(600), 1_000);
+        assert_eq!(store.permission_profile(), PermissionProfile::FullAccess);
+        assert_eq!(store.temporary_full_access_expires_at(), Some(1_600));
+        assert!(!store.expire_temporary_full_access(1_599));
+        assert!(store.expire_temporary_full_access(1_600));
+        assert_eq!(store.permission_profile(), PermissionProfile::ReadOnly);
+        assert_eq!(store.temporary_full_access_expires_at(), None);
+        assert!(!store.expire_temporary_full_access(2_000));
+
+        let records = store.audit_records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].profile, PermissionProfile::FullAccess);
+        assert_eq!(records[0].approval_decision, ApprovalDecision::Approved);
+        assert_eq!(
+            records[0]
+                .metadata
+                .get("expires_at_unix_s")
+                .map(String::as_str),
+            Some("1600")
+        );
+        assert_eq!(records[1].profile, PermissionProfile::ReadOnly);
+        assert_eq!(
+            records[1].metadata.get("reason").map(String::as_str),
+            Some("temporary_full_access_expired")
+        );
 
-        for error in errors {
-            let message = error.beginner_message();
-            assert!(
-                message.contains("Proximo passo:"),
-                "expected beginner guidance in message: {message}"
-            );
-            assert!(
-                !message.contains('`'),
-                "message should avoid technical formatting: {message}"
-            );
-        }
+        store.grant_temporary_full_access(Duration::from_secs(60), 3_000);
+        store.change_permission_profile(PermissionProfile::ReadWriteWithApproval);
+        assert!(!store.expire_temporary_full_access(i64::MAX));
+        assert_eq!(
+            store.permission_profile(),
+            PermissionProfile::ReadWriteWithApproval
+        );
     }
 
-    #[test]
-    fn runtime_errors_include_clear_next_step_message() {
-        let errors = vec![
-            AliciaUiRuntimeError::SessionManager(SessionManagerError::SessionNotFound(
-                "sess-runtime".to_string(),
-            )),
-            AliciaUiRuntimeError::ResolveProfileFailed {
-                workspace: "workspace".to_string(),
-                source: codex_alicia_core::ProjectPolicyConfigError::ReadFailed {
-                    path: ".codex/alicia-policy.toml".to_string(),
-                    source: std::io::Error::other("missing file"),
-                },
-            },
-            AliciaUiRuntimeError::WorkspaceGuardBlocked {
-                session_id: "sess-workspace".to_string(),
-                cwd: "../outside".to_string(),
-                source: codex_alicia_core::PolicyBridgeError::TargetOutsideWorkspace {
-                    workspace: "/repo".to_string(),
-                    target: "/outside".to_string(),
-                },
-            },
-            AliciaUiRuntimeError::CommandBlocked {
-                session_id: "sess-blocked".to_string(),
-                reason: "approval required".to_string(),
-            },
-            AliciaUiRuntimeError::SessionStopTimeout {
-                session_id: "sess-timeout".to_string(),
-            },
-            AliciaUiRuntimeError::AuditWriteFailed {
-                session_id: "sess-audit".to_string(),
-                source: std::io::Error::other("disk full"),
-            },
-        ];
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn panic_switch_drops_to_read_only_and_pauses_the_agent()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let model = Arc::new(ScriptedAgentModel::new([AgentReply {
+            text: "Retomando.".to_string(),
+            usage: None,
+            tool_calls: Vec::new(),
+        }]));
+        let mut runtime =
+            AliciaUiRuntime::new(SessionManager::new(), 128).with_agent_model(model.clone());
+        {
+            let mut store = runtime.store_mut();
+            store.set_permission_profile(PermissionProfile::FullAccess);
+            store.set_approver_identity(Some(ApproverIdentity::configured("ana")));
+            for (action_id, action_kind) in [
+                ("act-write", ActionKind::WriteFile),
+                ("act-exec", ActionKind::ExecuteCommand),
+                ("act-read", ActionKind::ReadFile),
+            ] {
+                store.push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
+                    action_id: action_id.to_string(),
+                    action_kind,
+                    target: "src/main.rs".to_string(),
+                })));
+                store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                    ApprovalRequested {
+                        action_id: action_id.to_string(),
+                        summary: action_id.to_string(),
+                        expires_at_unix_s: i64::MAX,
+                    },
+                )));
+            }
+            store.submit_chat_prompt("continue");
+        }
 
-        for error in errors {
-            let message = error.beginner_message();
+        assert_eq!(runtime.engage_panic_switch().await?, 2);
+        assert_eq!(runtime.pump_agent().await?, 0);
+        assert!(model.requests().is_empty());
+
+        {
+            let store = runtime.store();
+            assert_eq!(store.permission_profile(), PermissionProfile::ReadOnly);
+            assert!(store.is_agent_paused());
+            let pending: Vec<&str> = store
+                .pending_approvals()
+                .into_iter()
+                .map(|approval| approval.action_id.as_str())
+                .collect();
+            assert_eq!(pending, vec!["act-read"]);
             assert!(
-                message.contains("Proximo passo:"),
-                "expected beginner guidance in message: {message}"
+                store.timeline().iter().any(|entry| entry.summary
+                    == "panic_switch_engaged from=full_access denied_approvals=2")
             );
-            assert!(
-                !message.contains('`'),
-                "message should avoid technical formatting: {message}"
+            let record = store
+                .audit_records()
+                .iter()
+                .find(|record| {
+                    record.metadata.get("reason").map(String::as_str) == Some("panic_switch")
+                })
+                .ok_or("panic switch was not audited")?;
+            assert_eq!(record.profile, PermissionProfile::ReadOnly);
+            assert_eq!(record.approver, Some(ApproverIdentity::configured("ana")));
+            assert_eq!(
+                record.metadata.get("denied_approvals").map(String::as_str),
+                Some("act-write,act-exec")
             );
         }
+
+        runtime.store_mut().resume_agent();
+        assert_eq!(runtime.pump_agent().await?, 1);
+        Ok(())
     }
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
-    async fn runtime_blocks_command_without_explicit_approval_in_read_write_profile() {
-        let session_manager = SessionManager::new();
-        let mut runtime = AliciaUiRuntime::new(session_manager, 128);
+    async fn event_task_applies_bursts_without_pump_events()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let fake = FakeSessionManager::new();
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 10_000);
         runtime
             .store_mut()
-            .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
-
-        let (program, args) = shell_echo_command("blocked-by-approval");
-        let session_id = "sess-blocked-approval";
-        let request = SessionStartRequest::new(
-            session_id,
-            program,
-            args,
-            PathBuf::from("."),
-            inherited_env(),
-        )
-        .with_mode(SessionMode::Pipe);
+            .set_permission_profile(PermissionProfile::FullAccess);
+        let task = runtime.spawn_event_task(8);
+        runtime
+            .start_session(
+                SessionStartRequest::new(
+                    "sess-burst",
+                    "cargo",
+                    vec!["build".to_string()],
+                    PathBuf::from("."),
+                    HashMap::new(),
+                )
+                .with_mode(SessionMode::Pipe),
+            )
+            .await?;
+        for index in 0..500 {
+            assert!(fake.emit_output("sess-burst", &format!("linha {index}\n")));
+        }
+        assert!(fake.finish("sess-burst", 0));
+
+        let store = runtime.shared_store();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while !store
+            .read()
+            .terminal_session("sess-burst")
+            .is_some_and(|session| matches!(session.lifecycle, CommandLifecycle::Finished { .. }))
+        {
+            if tokio::time::Instant::now() >= deadline {
+                return Err("event task did not apply the session events".into());
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        task.abort();
+
+        let metrics = store
+            .read()
+            .status_metrics()
+            .event_queue
+            .ok_or("expected event queue metrics")?;
+        assert_eq!(metrics.capacity, 8);
+        assert!(metrics.applied > 0);
+        assert!(metrics.max_depth > 0);
+        assert_eq!(runtime.pump_events(), 0);
+        Ok(())
+    }
 
-        let result = runtime.start_session(request).await;
-        assert!(matches!(
-            result,
-            Err(AliciaUiRuntimeError::CommandBlocked { .. })
-        ));
-        assert!(!runtime.session_manager().is_active(session_id).await);
+    #[tokio::test]
+    async fn terminal_resizes_reach_the_pty_and_the_screen()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let fake = FakeSessionManager::new();
+        fake.set_default_script(FakeSessionScript::default());
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 10_000);
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::FullAccess);
+        runtime
+            .start_session(
+                SessionStartRequest::new(
+                    "sess-vim",
+                    "vim",
+                    Vec::new(),
+                    PathBuf::from("."),
+                    HashMap::new(),
+                )
+                .with_mode(SessionMode::Pty),
+            )
+            .await?;
 
-        let blocked_record = runtime
+        runtime
+            .store_mut()
+            .request_terminal_resize("sess-vim", 24, 100);
+        runtime
+            .store_mut()
+            .request_terminal_resize("sess-vim", 40, 120);
+        assert_eq!(runtime.resize_requested_sessions().await, 1);
+        assert_eq!(fake.pty_size("sess-vim"), Some((40, 120)));
+        let size = runtime
             .store()
-            .audit_records()
-            .iter()
-            .find(|record| record.session_id == session_id);
-        let Some(blocked_record) = blocked_record else {
-            panic!("expected blocked audit record");
+            .terminal_session("sess-vim")
+            .and_then(TerminalSessionState::emulator)
+            .map(TerminalEmulator::size);
+        assert_eq!(size, Some((40, 120)));
+
+        runtime
+            .store_mut()
+            .request_terminal_resize("sess-vim", 40, 120);
+        assert_eq!(runtime.resize_requested_sessions().await, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn staged_sessions_launch_with_the_reviewed_environment() {
+        let fake = FakeSessionManager::new();
+        fake.set_default_script(FakeSessionScript::default());
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 128);
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::FullAccess);
+        runtime.store_mut().set_review_session_env(true);
+        runtime
+            .store_mut()
+            .request_session(vec!["cargo".to_string(), "test".to_string()]);
+        runtime
+            .store_mut()
+            .request_session(vec!["make".to_string()]);
+
+        assert!(runtime.start_requested_sessions().await.is_empty());
+        assert!(fake.started_requests().is_empty());
+        assert_eq!(runtime.store().staged_sessions().len(), 2);
+
+        let mut edits = EnvEdits::default();
+        edits.set("RUST_LOG", "debug");
+        edits.remove("PATH");
+        assert!(
+            runtime
+                .store_mut()
+                .launch_staged_session("session-1", &edits)
+        );
+        assert!(runtime.store_mut().discard_staged_session("session-2"));
+        assert!(
+            !runtime
+                .store_mut()
+                .launch_staged_session("session-2", &edits)
+        );
+        assert_eq!(runtime.start_staged_sessions().await, vec!["session-1"]);
+
+        let started = fake.started_requests();
+        let [request] = started.as_slice() else {
+            panic!("only the launched session should start: {started:?}");
         };
         assert_eq!(
-            blocked_record.policy_decision,
-            PolicyDecision::RequireApproval
+            request.env.get("RUST_LOG").map(String::as_str),
+            Some("debug")
         );
+        assert!(!request.env.contains_key("PATH"));
         assert_eq!(
-            blocked_record.approval_decision,
-            ApprovalDecision::NotRequired
+            request
+                .audit_context
+                .metadata
+                .get("env_removed")
+                .map(String::as_str),
+            Some("PATH")
         );
-        assert_eq!(blocked_record.result_status, ResultStatus::Blocked);
+        assert!(runtime.store().staged_sessions().is_empty());
     }
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
-    async fn runtime_blocks_command_with_denied_approval_in_read_write_profile() {
-        let session_manager = SessionManager::new();
-        let mut runtime = AliciaUiRuntime::new(session_manager, 128);
+    #[tokio::test]
+    async fn sessions_remember_their_cwd_per_workspace() -> Result<(), Box<dyn std::error::Error>> {
+        let fake = FakeSessionManager::new();
+        fake.set_default_script(FakeSessionScript::default());
+        let workspace = tempfile::tempdir()?;
+        let config = tempfile::tempdir()?;
+        let api = workspace.path().join("api");
+        std::fs::create_dir_all(&api)?;
+        let recent_dirs_path = config.path().join(RECENT_DIRS_FILE_NAME);
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 128)
+            .with_workspace_root(workspace.path().to_path_buf())
+            .with_recent_dirs_path(recent_dirs_path.clone());
         runtime
             .store_mut()
-            .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
+            .set_permission_profile(PermissionProfile::FullAccess);
+        assert!(runtime.store().recent_dirs().is_empty());
 
-        let marker = "denied-by-policy";
-        let (program, args) = shell_echo_command(marker);
-        let mut command = vec![program.clone()];
-        command.extend(args.clone());
         runtime
             .store_mut()
-            .push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
-                action_id: "act-denied-cmd".to_string(),
-                action_kind: ActionKind::ExecuteCommand,
-                target: command.join(" "),
-            })));
+            .set_session_cwd(Some(PathBuf::from("api")));
         runtime
             .store_mut()
-            .attach_approval_command("act-denied-cmd", command);
+            .request_session(vec!["cargo".to_string(), "test".to_string()]);
+        assert_eq!(runtime.start_requested_sessions().await, vec!["session-1"]);
+        let canonical_api = std::fs::canonicalize(&api)?;
+        assert_eq!(runtime.store().recent_dirs(), [canonical_api.clone()]);
+
+        runtime
+            .store_mut()
+            .set_session_cwd(Some(PathBuf::from("../fora")));
+        runtime.store_mut().request_session(vec!["ls".to_string()]);
+        assert!(runtime.start_requested_sessions().await.is_empty());
+        assert_eq!(runtime.store().recent_dirs(), [canonical_api.clone()]);
+
+        let restored = AliciaUiRuntime::new(FakeSessionManager::new().session_manager(), 128)
+            .with_recent_dirs_path(recent_dirs_path)
+            .with_workspace_root(workspace.path().to_path_buf());
+        assert_eq!(restored.store().recent_dirs(), [canonical_api]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn health_reports_an_unparseable_policy_file() -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::tempdir()?;
+        let fake = FakeSessionManager::new();
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 128)
+            .with_workspace_root(workspace.path().to_path_buf());
+        assert_eq!(runtime.store().runtime_health(), None);
+
+        runtime.pump_events();
+        let Some(health) = runtime.store().runtime_health().cloned() else {
+            panic!("pump_events should publish the runtime health");
+        };
+        assert_eq!(health.level(), HealthLevel::Healthy);
+        assert_eq!(health.policy, PolicyFileStatus::Missing);
+        assert_eq!(health.audit_writable, None);
+
+        let policy_path = codex_alicia_core::project_policy_file_path(workspace.path());
+        if let Some(parent) = policy_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&policy_path, "profile = [")?;
+        let health = runtime.health();
+        assert!(matches!(health.policy, PolicyFileStatus::Invalid { .. }));
+        assert_eq!(health.level(), HealthLevel::Degraded);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn shutdown_kills_sessions_expires_approvals_and_saves_a_snapshot()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let fake = FakeSessionManager::new();
+        let workspace = tempfile::tempdir()?;
+        let snapshot = workspace.path().join("estado").join("eventos.jsonl");
+        let mut runtime =
+            AliciaUiRuntime::new(fake.session_manager(), 128).with_snapshot_path(snapshot.clone());
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::FullAccess);
+        runtime
+            .start_session(
+                SessionStartRequest::new(
+                    "sess-shutdown",
+                    "cargo",
+                    vec!["watch".to_string()],
+                    PathBuf::from("."),
+                    HashMap::new(),
+                )
+                .with_mode(SessionMode::Pipe),
+            )
+            .await?;
         runtime
             .store_mut()
             .push(IpcMessage::new(IpcEvent::ApprovalRequested(
                 ApprovalRequested {
-                    action_id: "act-denied-cmd".to_string(),
-                    summary: "executar comando negado".to_string(),
-                    expires_at_unix_s: 4_102_444_800,
+                    action_id: "act-open".to_string(),
+                    summary: "aprovacao em aberto".to_string(),
+                    expires_at_unix_s: i64::MAX,
                 },
             )));
-        let deny_result = runtime.store_mut().deny("act-denied-cmd");
-        assert!(deny_result.is_ok(), "expected denial to resolve");
 
-        let session_id = "sess-denied-approval";
-        let request = SessionStartRequest::new(
-            session_id,
-            program,
-            args,
-            PathBuf::from("."),
-            inherited_env(),
-        )
-        .with_mode(SessionMode::Pipe);
-        let result = runtime.start_session(request).await;
+        let report = runtime.shutdown(Duration::from_millis(400)).await;
+
+        assert!(report.is_clean(), "{report:?}");
+        assert_eq!(report.killed_sessions, vec!["sess-shutdown".to_string()]);
+        assert_eq!(report.expired_approvals, 1);
+        assert_eq!(
+            runtime.store().approval("act-open").map(|item| item.status),
+            Some(ApprovalStatus::Expired)
+        );
+        assert_eq!(report.snapshot_path.as_deref(), Some(snapshot.as_path()));
+        let saved = std::fs::read_to_string(&snapshot)?;
+        assert_eq!(saved.lines().count(), runtime.store().events().len());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn cancelled_tokens_abort_session_operations() -> Result<(), Box<dyn std::error::Error>> {
+        let fake = FakeSessionManager::new();
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 128);
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::FullAccess);
+        let request = |session_id: &str| {
+            SessionStartRequest::new(
+                session_id,
+                "cargo",
+                vec!["watch".to_string()],
+                PathBuf::from("."),
+                HashMap::new(),
+            )
+            .with_mode(SessionMode::Pipe)
+        };
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+
+        let start = runtime
+            .start_session_with_cancellation(request("sess-closed-tab"), &cancelled)
+            .await;
         assert!(matches!(
-            result,
-            Err(AliciaUiRuntimeError::CommandBlocked { .. })
+            start,
+            Err(AliciaUiRuntimeError::OperationCancelled { ref session_id })
+                if session_id == "sess-closed-tab"
         ));
+        assert!(fake.started_requests().is_empty());
 
-        let blocked_record = runtime
+        runtime.start_session(request("sess-watch")).await?;
+        let stop = runtime
+            .stop_session_with_cancellation("sess-watch", &cancelled)
+            .await;
+        assert!(matches!(
+            stop,
+            Err(AliciaUiRuntimeError::OperationCancelled { .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn runtime_drives_fake_sessions_without_spawning()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let fake = FakeSessionManager::new();
+        fake.script(
+            "sess-fake",
+            FakeSessionScript::default().with_output("pronto\n"),
+        );
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 128);
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::FullAccess);
+
+        runtime
+            .start_session(
+                SessionStartRequest::new(
+                    "sess-fake",
+                    "cargo",
+                    vec!["test".to_string()],
+                    PathBuf::from("."),
+                    HashMap::new(),
+                )
+                .with_mode(SessionMode::Pipe),
+            )
+            .await?;
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+        while runtime.store().active_session_id() != Some("sess-fake") {
+            runtime.pump_events();
+            if tokio::time::Instant::now() >= deadline {
+                return Err("fake session did not become active".into());
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        runtime.send_line_to_active_session("q")?;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(fake.finish("sess-fake", 0));
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+        while tokio::time::Instant::now() < deadline {
+            runtime.pump_events();
+            if runtime
+                .store()
+                .terminal_session("sess-fake")
+                .is_some_and(|session| {
+                    matches!(session.lifecycle, CommandLifecycle::Finished { .. })
+                })
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let session = runtime
             .store()
-            .audit_records()
-            .iter()
-            .find(|record| record.session_id == session_id);
-        let Some(blocked_record) = blocked_record else {
-            panic!("expected blocked audit record");
+            .terminal_session("sess-fake")
+            .cloned()
+            .ok_or("expected fake session in store")?;
+        assert!(session.visible_text().contains("pronto"));
+        assert!(matches!(
+            session.lifecycle,
+            CommandLifecycle::Finished { exit_code: 0, .. }
+        ));
+        assert_eq!(fake.input("sess-fake"), b"q\n".to_vec());
+        assert_eq!(fake.started_requests().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn agent_turns_prompts_into_tool_calls_and_chat_replies()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let fake = FakeSessionManager::new();
+        fake.script(
+            "agent-0",
+            FakeSessionScript::exiting(0).with_output("test result: ok\n"),
+        );
+        let tool_call = |id: &str, tool| AgentToolCall {
+            id: id.to_string(),
+            tool,
         };
+        let model = Arc::new(ScriptedAgentModel::new([
+            AgentReply {
+                text: "Vou rodar os testes.".to_string(),
+                usage: Some(TokenUsage::new(1_000, 200)),
+                tool_calls: vec![tool_call(
+                    "call-1",
+                    AgentTool::RunCommand {
+                        command: vec!["cargo".to_string(), "test".to_string()],
+                        cwd: None,
+                    },
+                )],
+            },
+            AgentReply {
+                text: String::new(),
+                usage: None,
+                tool_calls: vec![tool_call(
+                    "call-2",
+                    AgentTool::ProposePatch {
+                        file_path: "src/main.rs".to_string(),
+                        unified_diff: "@@ -1,1 +1,1 @@\n-a\n+b\n".to_string(),
+                    },
+                )],
+            },
+            AgentReply {
+                text: "Testes passaram; revise o patch.".to_string(),
+                usage: Some(TokenUsage::new(1_300, 50)),
+                tool_calls: Vec::new(),
+            },
+        ]));
+        let mut runtime =
+            AliciaUiRuntime::new(fake.session_manager(), 128).with_agent_model(model.clone());
+        runtime
+            .store_mut()
+            .set_permission_profile(PermissionProfile::FullAccess);
+        runtime.store_mut().submit_chat_prompt("rode os testes");
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+        let mut replies = runtime.pump_agent().await?;
+        while runtime.agent().is_some_and(|agent| !agent.is_idle()) {
+            if tokio::time::Instant::now() >= deadline {
+                return Err("agent did not finish its tool calls".into());
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            replies += runtime.pump_agent().await?;
+        }
+
+        assert_eq!(replies, 3);
+        let agent_messages: Vec<&str> = runtime
+            .store()
+            .chat_messages()
+            .iter()
+            .filter(|message| message.role == ChatRole::Agent)
+            .map(|message| message.content.as_str())
+            .collect();
         assert_eq!(
-            blocked_record.policy_decision,
-            PolicyDecision::RequireApproval
+            agent_messages,
+            vec!["Vou rodar os testes.", "Testes passaram; revise o patch."]
         );
-        assert_eq!(blocked_record.approval_decision, ApprovalDecision::Denied);
-        assert_eq!(blocked_record.result_status, ResultStatus::Blocked);
+        assert!(!runtime.store().is_agent_typing());
+        let token_usage = runtime.store().token_usage().clone();
+        assert_eq!(token_usage.session_total(), TokenUsage::new(2_300, 250));
+        assert_eq!(token_usage.turns().count(), 2);
+        assert_eq!(token_usage.model_total("scripted").total(), 2_550);
+        let requests = model.requests();
+        assert_eq!(requests.len(), 3);
+        let command_result = &requests[1].messages[2];
+        assert_eq!(command_result.tool_call_id.as_deref(), Some("call-1"));
+        assert!(command_result.content.starts_with("exit_code=0\n"));
+        assert!(command_result.content.contains("test result: ok"));
+        assert!(runtime.store().diff_preview("agent-1").is_some());
+        assert_eq!(fake.started_requests()[0].program, "cargo");
+        let tool_calls: Vec<(&str, Option<&str>, Option<&str>, Option<ToolCallStatus>)> = runtime
+            .store()
+            .chat_messages()
+            .iter()
+            .flat_map(|message| &message.tool_calls)
+            .map(|tool_call| {
+                (
+                    tool_call.name.as_str(),
+                    tool_call.session_id.as_deref(),
+                    tool_call.action_id.as_deref(),
+                    tool_call.status,
+                )
+            })
+            .collect();
+        assert_eq!(
+            tool_calls,
+            vec![
+                (
+                    "run_command",
+                    Some("agent-0"),
+                    None,
+                    Some(ToolCallStatus::Succeeded)
+                ),
+                (
+                    "propose_patch",
+                    None,
+                    Some("agent-1"),
+                    Some(ToolCallStatus::Succeeded)
+                ),
+            ]
+        );
+        Ok(())
     }
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
-    async fn runtime_allows_command_with_resolved_approval_in_read_write_profile() {
-        let session_manager = SessionManager::new();
-        let mut runtime = AliciaUiRuntime::new(session_manager, 128);
-        runtime
+    #[tokio::test]
+    async fn file_mentions_reach_the_agent_as_audited_reads()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::tempdir()?;
+        std::fs::create_dir_all(workspace.path().join("src"))?;
+        std::fs::write(workspace.path().join("src/lib.rs"), "pub fn soma() {}\n")?;
+        let model = Arc::new(ScriptedAgentModel::new([AgentReply {
+            text: "A funcao soma nao faz nada.".to_string(),
+            ..AgentReply::default()
+        }]));
+        let mut runtime = AliciaUiRuntime::new(FakeSessionManager::new().session_manager(), 128)
+            .with_workspace_root(workspace.path().to_path_buf())
+            .with_agent_model(model.clone());
+
+        let prompt = "explique @src/lib.rs e @../fora.txt";
+        let errors = runtime
             .store_mut()
-            .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
+            .attach_file_mentions(workspace.path(), prompt);
+        assert_eq!(errors.len(), 1);
+        runtime.store_mut().submit_chat_prompt(prompt);
+        assert_eq!(runtime.pump_agent().await?, 1);
+
+        let requests = model.requests();
+        let messages = &requests.first().ok_or("expected a model request")?.messages;
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].content.contains("pub fn soma() {}"));
+        assert!(messages[1].content.ends_with("Anexos: src/lib.rs"));
+        let reads: Vec<(&str, ResultStatus)> = runtime
+            .store()
+            .audit_records()
+            .iter()
+            .filter(|record| record.action_kind == ActionKind::ReadFile)
+            .map(|record| (record.target.as_str(), record.result_status))
+            .collect();
+        assert_eq!(reads, vec![("src/lib.rs", ResultStatus::Succeeded)]);
+        Ok(())
+    }
 
-        let marker = "approved-by-policy";
-        let (program, args) = shell_echo_command(marker);
-        let mut command = vec![program.clone()];
-        command.extend(args.clone());
+    #[tokio::test]
+    async fn agent_pauses_behind_an_approval_once_over_budget()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let model = Arc::new(ScriptedAgentModel::new([
+            AgentReply {
+                text: "Vou ler o manifesto.".to_string(),
+                tool_calls: vec![AgentToolCall {
+                    id: "call-1".to_string(),
+                    tool: AgentTool::ReadFile {
+                        path: "Cargo.toml".to_string(),
+                    },
+                }],
+                usage: Some(TokenUsage::new(1_000_000, 0)),
+            },
+            AgentReply {
+                text: "Pronto.".to_string(),
+                tool_calls: Vec::new(),
+                usage: Some(TokenUsage::new(1_000, 0)),
+            },
+        ]));
+        let mut runtime = AliciaUiRuntime::new(FakeSessionManager::new().session_manager(), 128)
+            .with_agent_model(model.clone());
         runtime
             .store_mut()
-            .push(IpcMessage::new(IpcEvent::ActionProposed(ActionProposed {
-                action_id: "act-approved-cmd".to_string(),
-                action_kind: ActionKind::ExecuteCommand,
-                target: command.join(" "),
-            })));
+            .set_model_pricing("scripted", ModelPricing::new(2.0, 8.0));
+        runtime.store_mut().set_budget_limit_usd(Some(1.5));
+        runtime.store_mut().submit_chat_prompt("leia o manifesto");
+
+        assert_eq!(runtime.pump_agent().await?, 1);
+        assert!((runtime.store().total_cost_usd() - 2.0).abs() < 1e-9);
+        let action_id = runtime
+            .store()
+            .pending_approvals()
+            .first()
+            .map(|approval| approval.action_id.clone())
+            .ok_or("expected a budget approval")?;
+        assert_eq!(runtime.pump_agent().await?, 0);
+        assert_eq!(runtime.store().pending_approvals().len(), 1);
+        assert_eq!(model.requests().len(), 1);
+
         runtime
             .store_mut()
-            .attach_approval_command("act-approved-cmd", command);
+            .push(IpcMessage::new(IpcEvent::ApprovalResolved(
+                ApprovalResolved {
+                    action_id,
+                    resolution: ApprovalResolution::Approved,
+                    approver: None,
+                    reason: None,
+                },
+            )));
+        assert_eq!(runtime.pump_agent().await?, 1);
+        assert!(runtime.agent().is_some_and(AgentLoop::is_idle));
+        let tool_result = &model.requests()[1].messages[2];
+        assert!(tool_result.content.contains("codex-alicia-ui"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn selected_model_answers_the_next_prompt() -> Result<(), Box<dyn std::error::Error>> {
+        let reply = |text: &str| AgentReply {
+            text: text.to_string(),
+            tool_calls: Vec::new(),
+            usage: Some(TokenUsage::new(10, 5)),
+        };
+        let fast = Arc::new(ScriptedAgentModel::new([reply("rapido")]).with_name("fast"));
+        let smart = Arc::new(ScriptedAgentModel::new([reply("esperto")]).with_name("smart"));
+        let registry = ModelRegistry::new()
+            .with_model("local", fast.clone())
+            .with_priced_model("remote", smart.clone(), Some(ModelPricing::new(1.0, 1.0)));
+        let mut runtime = AliciaUiRuntime::new(FakeSessionManager::new().session_manager(), 128)
+            .with_model_registry(registry);
+        assert_eq!(runtime.store().selected_model(), Some("fast"));
+        assert_eq!(runtime.store().available_models().len(), 2);
+
+        runtime.store_mut().submit_chat_prompt("/model gigante");
+        assert_eq!(runtime.store().selected_model(), Some("fast"));
+        runtime.store_mut().submit_chat_prompt("/model smart");
+        assert_eq!(runtime.store().selected_model(), Some("smart"));
+        runtime.store_mut().submit_chat_prompt("ola");
+
+        assert_eq!(runtime.pump_agent().await?, 1);
+        assert!(fast.requests().is_empty());
+        assert_eq!(smart.requests().len(), 1);
+        assert_eq!(smart.requests()[0].messages.len(), 1);
+        assert_eq!(runtime.agent().map(AgentLoop::model_name), Some("smart"));
+        assert_eq!(
+            runtime
+                .store()
+                .token_usage()
+                .last_turn()
+                .map(|turn| turn.model.as_str()),
+            Some("smart")
+        );
+        assert!(runtime.store().total_cost_usd() > 0.0);
+        assert!(
+            runtime
+                .store_mut()
+                .select_model("gigante")
+                .is_err_and(|error| error == UiEventStoreError::UnknownModel("gigante".to_string()))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn prompts_without_a_model_get_a_system_reply() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let mut runtime = AliciaUiRuntime::new(FakeSessionManager::new().session_manager(), 128);
+        runtime.store_mut().submit_chat_prompt("ola");
+
+        assert_eq!(runtime.pump_agent().await?, 0);
+        let last = runtime
+            .store()
+            .chat_messages()
+            .last()
+            .cloned()
+            .ok_or("expected a chat message")?;
+        assert_eq!(last.role, ChatRole::System);
+        assert!(runtime.store_mut().take_agent_prompts().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn slash_commands_run_instead_of_reaching_the_agent()
+    -> Result<(), Box<dyn std::error::Error>> {
+        struct EchoCommand;
+
+        impl SlashCommandHandler for EchoCommand {
+            fn spec(&self) -> SlashCommandSpec {
+                SlashCommandSpec::new("echo", "/echo <texto>", "Repete o texto")
+            }
+
+            fn run(&self, _store: &mut UiEventStore, args: &[String]) -> String {
+                args.join(" ")
+            }
+        }
+
+        let fake = FakeSessionManager::new();
+        let mut runtime = AliciaUiRuntime::new(fake.session_manager(), 128);
+        let last_reply = |store: &UiEventStore| {
+            store
+                .chat_messages()
+                .last()
+                .map(|message| (message.role, message.content.clone()))
+        };
+
         runtime
             .store_mut()
             .push(IpcMessage::new(IpcEvent::ApprovalRequested(
                 ApprovalRequested {
-                    action_id: "act-approved-cmd".to_string(),
-                    summary: "executar comando aprovado".to_string(),
+                    action_id: "act-slash".to_string(),
+                    summary: "executar comando".to_string(),
                     expires_at_unix_s: 4_102_444_800,
                 },
             )));
-        let approve_result = runtime.store_mut().approve("act-approved-cmd");
-        assert!(approve_result.is_ok(), "expected approval to resolve");
+        runtime.store_mut().submit_chat_prompt("/approve act-slash");
+        assert_eq!(
+            runtime
+                .store()
+                .approval("act-slash")
+                .map(|approval| approval.status),
+            Some(ApprovalStatus::Approved)
+        );
 
-        let request = SessionStartRequest::new(
-            "sess-approved-approval",
-            program,
-            args,
-            PathBuf::from("."),
-            inherited_env(),
-        )
-        .with_mode(SessionMode::Pipe);
+        runtime
+            .store_mut()
+            .submit_chat_prompt("/profile full_access");
+        assert_eq!(
+            runtime.store().permission_profile(),
+            PermissionProfile::FullAccess
+        );
 
-        if let Err(error) = runtime.start_session(request).await {
-            panic!("expected approved execution to start: {error}");
-        }
+        runtime.store_mut().submit_chat_prompt("/nada");
+        assert!(
+            last_reply(&runtime.store())
+                .is_some_and(|(_, content)| content.starts_with("Comando desconhecido: /nada."))
+        );
 
-        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
-        let mut saw_marker = false;
-        let mut finished_ok = false;
-        while tokio::time::Instant::now() < deadline {
-            runtime.pump_events();
-            if let Some(text) = runtime.store().active_terminal_text()
-                && text.contains(marker)
-            {
-                saw_marker = true;
-            }
-            if let Some(session) = runtime.store().terminal_session("sess-approved-approval")
-                && matches!(
-                    session.lifecycle,
-                    CommandLifecycle::Finished {
-                        exit_code: 0,
-                        duration_ms: _
-                    }
-                )
-            {
-                finished_ok = true;
-            }
-            if saw_marker && finished_ok {
-                break;
-            }
-            tokio::time::sleep(Duration::from_millis(25)).await;
-        }
+        runtime
+            .store_mut()
+            .register_slash_command(Arc::new(EchoCommand));
+        runtime.store_mut().submit_chat_prompt("/echo ola mundo");
+        assert_eq!(
+            last_reply(&runtime.store()),
+            Some((ChatRole::System, "ola mundo".to_string()))
+        );
+        assert!(runtime.store_mut().take_agent_prompts().is_empty());
 
-        assert!(saw_marker, "expected approved command output marker");
-        assert!(
-            finished_ok,
-            "expected approved command to finish successfully"
+        runtime
+            .store_mut()
+            .submit_chat_prompt("/session new cargo test");
+        assert_eq!(runtime.start_requested_sessions().await, vec!["session-1"]);
+        assert_eq!(
+            fake.started_requests()
+                .iter()
+                .map(|request| request.program.as_str())
+                .collect::<Vec<_>>(),
+            vec!["cargo"]
         );
+
+        runtime.store_mut().submit_chat_prompt("/clear");
+        assert_eq!(runtime.store().chat_messages().len(), 1);
+        Ok(())
     }
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -4,14 +4,37 @@ use std::time::Duration;
 
 use clap::Parser;
 use clap::ValueEnum;
+use codex_alicia_core::AUDIT_KEY_ENV_VAR;
+use codex_alicia_core::AuditEncryption;
 use codex_alicia_core::AuditLogger;
+use codex_alicia_core::DEFAULT_SESSION_EVENTS_CAPACITY;
 use codex_alicia_core::PermissionProfile;
 use codex_alicia_core::SessionAuditContext;
 use codex_alicia_core::SessionManager;
 use codex_alicia_core::SessionMode;
 use codex_alicia_core::SessionStartRequest;
+use codex_alicia_core::ipc::server::IpcServer;
+use codex_alicia_ui::APPROVAL_EXPIRY_INTERVAL;
 use codex_alicia_ui::AliciaUiRuntime;
 use codex_alicia_ui::CommandLifecycle;
+use codex_alicia_ui::RemoteAccess;
+use codex_alicia_ui::UiLanguage;
+use codex_alicia_ui::UiNotice;
+use codex_alicia_ui::listen_json_rpc;
+use codex_alicia_ui::send_desktop_notification;
+use codex_alicia_ui::serve_rest_api;
+use codex_alicia_ui::session_report_html;
+use codex_alicia_ui::set_ui_language;
+use codex_keyring_store::DefaultKeyringStore;
+use codex_otel::config::OtelExporter;
+use codex_otel::config::OtelSettings;
+use codex_otel::otel_provider::OtelProvider;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+const AUDIT_KEYRING_ACCOUNT: &str = "default";
+const API_TOKEN_ENV_VAR: &str = "ALICIA_API_TOKEN";
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 enum CliSessionMode {
@@ -52,18 +75,116 @@ struct AliciaAppCli {
     #[arg(long)]
     audit_path: Option<PathBuf>,
 
+    /// Criptografa a auditoria com a chave age lida desta variavel de ambiente.
+    #[arg(long, value_name = "VAR", num_args = 0..=1, default_missing_value = AUDIT_KEY_ENV_VAR)]
+    audit_key_env: Option<String>,
+
+    /// Criptografa a auditoria com a chave guardada no chaveiro do sistema.
+    #[arg(long, conflicts_with = "audit_key_env")]
+    audit_keyring: bool,
+
+    /// Descriptografa um JSONL de auditoria criptografado, imprimindo os registros.
+    #[arg(long, value_name = "PATH")]
+    decrypt_audit: Option<PathBuf>,
+
+    /// Idioma das mensagens de erro (pt, en). Padrao: ALICIA_LANG ou pt.
+    #[arg(long, value_name = "LANG")]
+    lang: Option<String>,
+
+    /// Exporta spans de sessao, aprovacao e patch para este endpoint OTLP (gRPC).
+    #[arg(long, value_name = "URL")]
+    otlp_endpoint: Option<String>,
+
+    /// Atende JSON-RPC 2.0 (uma requisicao por linha) neste endereco TCP, ex. 127.0.0.1:7410.
+    #[arg(long, value_name = "ADDR")]
+    rpc_listen: Option<String>,
+
+    /// Expoe a API REST (sessoes, aprovacoes, auditoria) neste endereco, ex. 127.0.0.1:7411.
+    #[arg(long, value_name = "ADDR")]
+    rest_listen: Option<String>,
+
+    /// Variavel de ambiente com o token que clientes JSON-RPC e REST precisam apresentar.
+    #[arg(long, value_name = "VAR", default_value = API_TOKEN_ENV_VAR)]
+    api_token_env: String,
+
+    /// Nome registrado como aprovador das decisoes feitas via JSON-RPC ou REST.
+    #[arg(long, value_name = "NAME", default_value = "remote")]
+    api_principal: String,
+
+    /// Permite --rpc-listen e --rest-listen em enderecos fora do loopback.
+    #[arg(long)]
+    allow_remote_listen: bool,
+
+    /// Recebe eventos IPC de um agente externo neste socket Unix (pipe nomeado no Windows,
+    /// ex. \\.\pipe\alicia) e devolve a ele as resolucoes de aprovacao.
+    #[arg(long, value_name = "PATH")]
+    ipc_socket: Option<PathBuf>,
+
+    /// Ao terminar, grava um relatorio HTML autocontido da sessao (conversa, terminal, diffs e
+    /// auditoria) neste caminho.
+    #[arg(long, value_name = "PATH")]
+    report_html: Option<PathBuf>,
+
+    /// Eventos que um assinante pode acumular antes de perder os mais antigos.
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_SESSION_EVENTS_CAPACITY)]
+    event_channel_capacity: usize,
+
     /// Cancela a sessao automaticamente apos X ms.
     #[arg(long)]
     cancel_after_ms: Option<u64>,
 
     /// Comando a executar, preferencialmente apos `--`.
-    #[arg(required = true, trailing_var_arg = true)]
+    #[arg(required_unless_present = "decrypt_audit", trailing_var_arg = true)]
     command: Vec<String>,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = AliciaAppCli::parse();
+    let language_code = cli
+        .lang
+        .clone()
+        .or_else(|| std::env::var("ALICIA_LANG").ok());
+    if let Some(language) = language_code.as_deref().and_then(UiLanguage::from_code) {
+        set_ui_language(language);
+    }
+    let audit_encryption = resolve_audit_encryption(&cli)?;
+    // Kept alive until the end of `main` so pending spans are flushed on drop.
+    let _otel = init_tracing(cli.otlp_endpoint.as_deref());
+
+    if let Some(path) = &cli.decrypt_audit {
+        let Some(encryption) = audit_encryption else {
+            eprintln!(
+                "Informe a chave da auditoria com --audit-key-env ou --audit-keyring. Proximo passo: repita o comando com uma dessas opcoes."
+            );
+            std::process::exit(2);
+        };
+        match encryption.decrypt_and_verify(path) {
+            Ok(records) => {
+                for record in records {
+                    println!("{}", serde_json::to_string(&record)?);
+                }
+                return Ok(());
+            }
+            Err(error) => {
+                eprintln!("Falha ao ler a auditoria: {error}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let remote_access = match remote_access(&cli) {
+        Some(access) => access,
+        None if cli.rpc_listen.is_some() || cli.rest_listen.is_some() => {
+            eprintln!(
+                "Defina o token da API em {}. Proximo passo: exporte a variavel e repita o comando.",
+                cli.api_token_env
+            );
+            std::process::exit(2);
+        }
+        None => RemoteAccess::new("", cli.api_principal.clone()),
+    };
+
     let cwd = match cli.cwd {
         Some(path) => path,
         None => std::env::current_dir()?,
@@ -82,12 +203,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     let session_manager = if let Some(path) = &cli.audit_path {
-        let logger = AuditLogger::open(path).await?;
+        let mut logger = AuditLogger::open(path).await?;
+        if let Some(encryption) = audit_encryption {
+            logger = logger.with_encryption(encryption);
+        }
         SessionManager::with_audit_logger(logger)
     } else {
         SessionManager::new()
-    };
+    }
+    .with_event_capacity(cli.event_channel_capacity);
     let mut runtime = AliciaUiRuntime::new(session_manager, 2_000);
+    let _approval_expiry = runtime.spawn_approval_expiry_task(APPROVAL_EXPIRY_INTERVAL);
     runtime
         .store_mut()
         .set_permission_profile(PermissionProfile::FullAccess);
@@ -103,15 +229,50 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     if let Err(error) = runtime.start_session(request).await {
         eprintln!("{}", error.beginner_message());
-        return Ok(());
+        let Some(action_id) = runtime.parked_approval_id(&cli.session_id) else {
+            return Ok(());
+        };
+        println!("Aguardando aprovacao {action_id} para iniciar a sessao.");
+    } else {
+        println!("Sessao iniciada: {}", cli.session_id);
     }
-
-    println!("Sessao iniciada: {}", cli.session_id);
     println!("Diretorio: {}", cwd.display());
     if let Some(audit_path) = &cli.audit_path {
         println!("Auditoria: {}", audit_path.display());
     }
 
+    let (rpc_tx, mut rpc_rx) = tokio::sync::mpsc::channel(64);
+    if let Some(addr) = cli.rpc_listen.clone() {
+        println!("JSON-RPC: {addr}");
+        let rpc_tx = rpc_tx.clone();
+        let access = remote_access.clone();
+        tokio::spawn(async move {
+            if let Err(error) = listen_json_rpc(&addr, access, rpc_tx).await {
+                eprintln!("Falha no servidor JSON-RPC em {addr}: {error}");
+            }
+        });
+    }
+    if let Some(addr) = cli.rest_listen.clone() {
+        println!("API REST: http://{addr}");
+        let access = remote_access.clone();
+        tokio::spawn(async move {
+            if let Err(error) = serve_rest_api(&addr, access, rpc_tx).await {
+                eprintln!("Falha na API REST em {addr}: {error}");
+            }
+        });
+    }
+    let _ipc_server = match cli.ipc_socket.as_ref().map(IpcServer::bind) {
+        Some(Ok(server)) => {
+            println!("IPC: {}", server.path().display());
+            Some(runtime.spawn_ipc_server(server))
+        }
+        Some(Err(error)) => {
+            eprintln!("Nao consegui abrir o socket IPC: {error}");
+            None
+        }
+        None => None,
+    };
+
     let mut printed_lines = 0_usize;
     let cancel_deadline = cli
         .cancel_after_ms
@@ -121,6 +282,40 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     loop {
         runtime.pump_events();
+        while let Ok(call) = rpc_rx.try_recv() {
+            let resolutions = call.answer(&mut runtime.store_mut());
+            for message in resolutions {
+                runtime.publish_ipc_message(message);
+            }
+        }
+        match runtime.resume_parked_sessions().await {
+            Ok(started) => {
+                for session_id in started {
+                    println!(
+                        "{}",
+                        UiNotice::SessionStartedAfterApproval.message(&session_id)
+                    );
+                }
+            }
+            Err(error) => {
+                eprintln!("{}", error.beginner_message());
+                break;
+            }
+        }
+        let session_missing = runtime.store().terminal_session(&cli.session_id).is_none();
+        if runtime.parked_approval_id(&cli.session_id).is_none()
+            && session_missing
+            && !runtime.session_manager().is_active(&cli.session_id).await
+        {
+            println!("Aprovacao negada ou expirada; sessao nao iniciada.");
+            break;
+        }
+        let notifications = runtime.store_mut().take_notifications();
+        for notification in notifications {
+            if let Err(error) = send_desktop_notification(&notification) {
+                eprintln!("Nao consegui exibir a notificacao: {error}");
+            }
+        }
 
         if let Some(session) = runtime.store().terminal_session(&cli.session_id) {
             let lines = session.visible_lines();
@@ -157,6 +352,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         tokio::time::sleep(Duration::from_millis(25)).await;
     }
 
+    let shutdown = runtime.shutdown(SHUTDOWN_TIMEOUT).await;
+    for session_id in &shutdown.unfinished_sessions {
+        eprintln!("Sessao {session_id} continuou rodando apos o encerramento.");
+    }
+    for error in &shutdown.errors {
+        eprintln!("Falha ao encerrar: {error}");
+    }
+
+    if let Some(path) = &cli.report_html {
+        let generated_at_unix_s = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        let report = session_report_html(&runtime.store(), generated_at_unix_s);
+        match std::fs::write(path, report) {
+            Ok(()) => println!("Relatorio: {}", path.display()),
+            Err(error) => eprintln!(
+                "Nao consegui gravar o relatorio em {}: {error}",
+                path.display()
+            ),
+        }
+    }
+
     if final_exit_code == 0 {
         Ok(())
     } else {
@@ -169,6 +386,74 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
+fn resolve_audit_encryption(
+    cli: &AliciaAppCli,
+) -> Result<Option<AuditEncryption>, Box<dyn std::error::Error>> {
+    if let Some(variable) = &cli.audit_key_env {
+        return Ok(Some(AuditEncryption::from_env(variable)?));
+    }
+    if cli.audit_keyring && cli.decrypt_audit.is_some() {
+        // Reading a log back must not leave a new, useless key in the keychain.
+        return Ok(Some(AuditEncryption::load_from_keyring(
+            &DefaultKeyringStore,
+            AUDIT_KEYRING_ACCOUNT,
+        )?));
+    }
+    if cli.audit_keyring {
+        return Ok(Some(AuditEncryption::from_keyring(
+            &DefaultKeyringStore,
+            AUDIT_KEYRING_ACCOUNT,
+        )?));
+    }
+    Ok(None)
+}
+
+/// Token clients of `--rpc-listen`/`--rest-listen` must present; `None` when it is unset.
+fn remote_access(cli: &AliciaAppCli) -> Option<RemoteAccess> {
+    let token = std::env::var(&cli.api_token_env)
+        .ok()
+        .filter(|token| !token.trim().is_empty())?;
+    let access = RemoteAccess::new(token, cli.api_principal.clone());
+    Some(if cli.allow_remote_listen {
+        access.allowing_non_loopback()
+    } else {
+        access
+    })
+}
+
+fn init_tracing(otlp_endpoint: Option<&str>) -> Option<OtelProvider> {
+    let endpoint = otlp_endpoint?;
+    let codex_home = std::env::var_os("CODEX_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".codex")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    let settings = OtelSettings {
+        environment: "local".to_string(),
+        service_name: "codex-alicia-ui".to_string(),
+        service_version: env!("CARGO_PKG_VERSION").to_string(),
+        codex_home,
+        exporter: OtelExporter::None,
+        trace_exporter: OtelExporter::OtlpGrpc {
+            endpoint: endpoint.to_string(),
+            headers: HashMap::new(),
+            tls: None,
+        },
+        metrics_exporter: OtelExporter::None,
+        runtime_metrics: false,
+    };
+    let provider = match OtelProvider::from(&settings) {
+        Ok(provider) => provider?,
+        Err(error) => {
+            eprintln!("Nao consegui iniciar o exportador OTLP: {error}");
+            return None;
+        }
+    };
+    let _ = tracing_subscriber::registry()
+        .with(provider.tracing_layer())
+        .try_init();
+    Some(provider)
+}
+
 fn inherited_env() -> HashMap<String, String> {
     std::env::vars().collect()
 }
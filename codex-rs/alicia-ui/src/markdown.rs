@@ -0,0 +1,353 @@
+use pulldown_cmark::CodeBlockKind;
+use pulldown_cmark::Event;
+use pulldown_cmark::Parser;
+use pulldown_cmark::Tag;
+use pulldown_cmark::TagEnd;
+
+use crate::code_block::CodeBlock;
+use crate::code_block::render_code_block;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct InlineSpan {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkdownBlock {
+    Heading {
+        level: u8,
+        spans: Vec<InlineSpan>,
+    },
+    Paragraph(Vec<InlineSpan>),
+    /// `marker` is `•` or `N.` for the first block of an item and empty for continuations.
+    ListItem {
+        depth: usize,
+        marker: String,
+        spans: Vec<InlineSpan>,
+    },
+    Quote(Vec<InlineSpan>),
+    CodeBlock(CodeBlock),
+    Rule,
+}
+
+#[derive(Default)]
+struct MarkdownParseState {
+    blocks: Vec<MarkdownBlock>,
+    spans: Vec<InlineSpan>,
+    bold_depth: usize,
+    italic_depth: usize,
+    link: Option<String>,
+    heading_level: Option<u8>,
+    quote_depth: usize,
+    /// Next ordinal for ordered lists, `None` for bullet lists.
+    list_stack: Vec<Option<u64>>,
+    item_markers: Vec<String>,
+    code_block: Option<CodeBlock>,
+}
+
+impl MarkdownParseState {
+    fn push_text(&mut self, text: &str, code: bool) {
+        self.spans.push(InlineSpan {
+            text: text.to_string(),
+            bold: self.bold_depth > 0,
+            italic: self.italic_depth > 0,
+            code,
+            link: self.link.clone(),
+        });
+    }
+
+    fn flush_spans(&mut self) {
+        if self.spans.is_empty() {
+            return;
+        }
+        let spans = std::mem::take(&mut self.spans);
+        let block = if let Some(level) = self.heading_level {
+            MarkdownBlock::Heading { level, spans }
+        } else if let Some(marker) = self.item_markers.last_mut() {
+            MarkdownBlock::ListItem {
+                depth: self.list_stack.len(),
+                marker: std::mem::take(marker),
+                spans,
+            }
+        } else if self.quote_depth > 0 {
+            MarkdownBlock::Quote(spans)
+        } else {
+            MarkdownBlock::Paragraph(spans)
+        };
+        self.blocks.push(block);
+    }
+}
+
+/// Parses CommonMark into flat render blocks. Fenced code keeps its language tag so it can be
+/// routed to the highlighted code block renderer.
+pub fn parse_markdown(markdown: &str) -> Vec<MarkdownBlock> {
+    let mut state = MarkdownParseState::default();
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Heading { level, .. } => {
+                    state.flush_spans();
+                    state.heading_level = Some(level as u8);
+                }
+                Tag::BlockQuote(_) => {
+                    state.flush_spans();
+                    state.quote_depth += 1;
+                }
+                Tag::List(start) => {
+                    state.flush_spans();
+                    state.list_stack.push(start);
+                }
+                Tag::Item => {
+                    state.flush_spans();
+                    let marker = match state.list_stack.last_mut() {
+                        Some(Some(ordinal)) => {
+                            let marker = format!("{ordinal}.");
+                            *ordinal = ordinal.saturating_add(1);
+                            marker
+                        }
+                        Some(None) | None => String::from("•"),
+                    };
+                    state.item_markers.push(marker);
+                }
+                Tag::CodeBlock(kind) => {
+                    state.flush_spans();
+                    let language = match kind {
+                        CodeBlockKind::Fenced(info) => info
+                            .split_whitespace()
+                            .next()
+                            .map(str::to_string)
+                            .filter(|language| !language.is_empty()),
+                        CodeBlockKind::Indented => None,
+                    };
+                    state.code_block = Some(CodeBlock {
+                        language,
+                        code: String::new(),
+                    });
+                }
+                Tag::Emphasis => state.italic_depth += 1,
+                Tag::Strong => state.bold_depth += 1,
+                Tag::Link { dest_url, .. } => state.link = Some(dest_url.to_string()),
+                _ => {}
+            },
+            Event::End(tag) => match tag {
+                TagEnd::Heading(_) => {
+                    state.flush_spans();
+                    state.heading_level = None;
+                }
+                TagEnd::Paragraph => state.flush_spans(),
+                TagEnd::BlockQuote(_) => {
+                    state.flush_spans();
+                    state.quote_depth = state.quote_depth.saturating_sub(1);
+                }
+                TagEnd::List(_) => {
+                    state.flush_spans();
+                    state.list_stack.pop();
+                }
+                TagEnd::Item => {
+                    state.flush_spans();
+                    state.item_markers.pop();
+                }
+                TagEnd::CodeBlock => {
+                    if let Some(mut code_block) = state.code_block.take() {
+                        let trimmed_len = code_block.code.trim_end_matches('\n').len();
+                        code_block.code.truncate(trimmed_len);
+                        state.blocks.push(MarkdownBlock::CodeBlock(code_block));
+                    }
+                }
+                TagEnd::Emphasis => state.italic_depth = state.italic_depth.saturating_sub(1),
+                TagEnd::Strong => state.bold_depth = state.bold_depth.saturating_sub(1),
+                TagEnd::Link => state.link = None,
+                _ => {}
+            },
+            Event::Text(text) => match state.code_block.as_mut() {
+                Some(code_block) => code_block.code.push_str(&text),
+                None => state.push_text(&text, false),
+            },
+            Event::Code(code) => state.push_text(&code, true),
+            Event::SoftBreak => state.push_text(" ", false),
+            Event::HardBreak => state.push_text("\n", false),
+            Event::Rule => {
+                state.flush_spans();
+                state.blocks.push(MarkdownBlock::Rule);
+            }
+            _ => {}
+        }
+    }
+    state.flush_spans();
+    state.blocks
+}
+
+/// Renders `blocks`; returns the code block whose copy button was clicked, if any.
+pub fn render_markdown<'a>(
+    ui: &mut egui::Ui,
+    blocks: &'a [MarkdownBlock],
+) -> Option<&'a CodeBlock> {
+    let mut copied = None;
+    for block in blocks {
+        match block {
+            MarkdownBlock::Heading { level, spans } => {
+                let size = match level {
+                    1 => 22.0,
+                    2 => 19.0,
+                    _ => 16.0,
+                };
+                ui.horizontal_wrapped(|ui| {
+                    for span in spans {
+                        render_span(ui, span, Some(size));
+                    }
+                });
+            }
+            MarkdownBlock::Paragraph(spans) => {
+                ui.horizontal_wrapped(|ui| {
+                    for span in spans {
+                        render_span(ui, span, None);
+                    }
+                });
+            }
+            MarkdownBlock::ListItem {
+                depth,
+                marker,
+                spans,
+            } => {
+                ui.horizontal_wrapped(|ui| {
+                    ui.add_space(depth.saturating_sub(1) as f32 * 16.0);
+                    ui.label(if marker.is_empty() {
+                        " "
+                    } else {
+                        marker.as_str()
+                    });
+                    for span in spans {
+                        render_span(ui, span, None);
+                    }
+                });
+            }
+            MarkdownBlock::Quote(spans) => {
+                ui.horizontal_wrapped(|ui| {
+                    ui.weak("▎");
+                    for span in spans {
+                        render_span(ui, span, None);
+                    }
+                });
+            }
+            MarkdownBlock::CodeBlock(code_block) => {
+                if render_code_block(ui, code_block) {
+                    copied = Some(code_block);
+                }
+            }
+            MarkdownBlock::Rule => {
+                ui.separator();
+            }
+        }
+    }
+    copied
+}
+
+fn render_span(ui: &mut egui::Ui, span: &InlineSpan, heading_size: Option<f32>) {
+    let mut text = egui::RichText::new(&span.text);
+    if let Some(size) = heading_size {
+        text = text.size(size).strong();
+    }
+    if span.bold {
+        text = text.strong();
+    }
+    if span.italic {
+        text = text.italics();
+    }
+    if span.code {
+        text = text.code();
+    }
+    match &span.link {
+        Some(url) => {
+            ui.hyperlink_to(text, url);
+        }
+        None => {
+            ui.label(text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::InlineSpan;
+    use super::MarkdownBlock;
+    use super::parse_markdown;
+    use crate::code_block::CodeBlock;
+
+    fn plain(text: &str) -> InlineSpan {
+        InlineSpan {
+            text: text.to_string(),
+            ..InlineSpan::default()
+        }
+    }
+
+    #[test]
+    fn parses_headings_lists_inline_code_and_fenced_blocks() {
+        let blocks = parse_markdown(
+            "# Plano\n\n1. Rode `cargo test`\n2. Veja **falhas**\n\n```rust\nfn main() {}\n```\n",
+        );
+
+        assert_eq!(
+            blocks,
+            vec![
+                MarkdownBlock::Heading {
+                    level: 1,
+                    spans: vec![plain("Plano")],
+                },
+                MarkdownBlock::ListItem {
+                    depth: 1,
+                    marker: "1.".to_string(),
+                    spans: vec![
+                        plain("Rode "),
+                        InlineSpan {
+                            code: true,
+                            ..plain("cargo test")
+                        },
+                    ],
+                },
+                MarkdownBlock::ListItem {
+                    depth: 1,
+                    marker: "2.".to_string(),
+                    spans: vec![
+                        plain("Veja "),
+                        InlineSpan {
+                            bold: true,
+                            ..plain("falhas")
+                        },
+                    ],
+                },
+                MarkdownBlock::CodeBlock(CodeBlock {
+                    language: Some("rust".to_string()),
+                    code: "fn main() {}".to_string(),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_nested_bullets_and_quotes() {
+        let blocks = parse_markdown("- a\n  - b\n\n> dica");
+
+        assert_eq!(
+            blocks,
+            vec![
+                MarkdownBlock::ListItem {
+                    depth: 1,
+                    marker: "•".to_string(),
+                    spans: vec![plain("a")],
+                },
+                MarkdownBlock::ListItem {
+                    depth: 2,
+                    marker: "•".to_string(),
+                    spans: vec![plain("b")],
+                },
+                MarkdownBlock::Quote(vec![plain("dica")]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,53 @@
+/// Approximate bytes held by a `UiEventStore`, split by what the budget can act on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Serialized size of the retained IPC events.
+    pub events_bytes: usize,
+    /// Text of every session's scrollback.
+    pub scrollback_bytes: usize,
+    /// Lines of the diff previews.
+    pub previews_bytes: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(self) -> usize {
+        self.events_bytes
+            .saturating_add(self.scrollback_bytes)
+            .saturating_add(self.previews_bytes)
+    }
+}
+
+/// What the memory budget evicted so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryEvictions {
+    /// Times the store went over budget and evicted something.
+    pub passes: u64,
+    pub scrollback_lines: u64,
+    pub events: u64,
+    pub bytes: u64,
+}
+
+/// Size for the UI: `512 B`, `1.5 KB`, `12.0 MB`.
+pub fn format_byte_size(bytes: usize) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+    match bytes as f64 {
+        size if size < KB => format!("{bytes} B"),
+        size if size < MB => format!("{:.1} KB", size / KB),
+        size => format!("{:.1} MB", size / MB),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::format_byte_size;
+
+    #[test]
+    fn formats_sizes_in_binary_units() {
+        assert_eq!(format_byte_size(512), "512 B");
+        assert_eq!(format_byte_size(1_536), "1.5 KB");
+        assert_eq!(format_byte_size(12 * 1024 * 1024), "12.0 MB");
+    }
+}
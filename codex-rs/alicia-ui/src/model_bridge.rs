@@ -0,0 +1,165 @@
+//! Integration point for LLM backends that stream their answers. A [`ModelBridge`] only
+//! turns a conversation into a stream of [`ChatDelta`]s; wrapped in a [`BridgedAgentModel`]
+//! it answers chat prompts like any [`AgentModel`], its text streaming into the chat and its
+//! tool calls becoming the proposals and approvals the runtime already handles.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use tokio::sync::mpsc;
+
+use crate::AgentError;
+use crate::AgentModel;
+use crate::AgentReply;
+use crate::AgentRequest;
+use crate::AgentToolCall;
+use crate::TokenUsage;
+
+/// One piece of a streamed answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatDelta {
+    Text(String),
+    /// A complete tool call; backends that stream call arguments assemble them first.
+    ToolCall(AgentToolCall),
+    /// Usage of the whole answer, when the provider reports it.
+    Usage(TokenUsage),
+}
+
+pub type ChatDeltaStream = BoxStream<'static, Result<ChatDelta, AgentError>>;
+
+pub trait ModelBridge: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Streams the answer to `request`, whose last messages are the new prompt or the tool
+    /// results the model is waiting for. An error item ends the answer.
+    fn send_prompt(&self, request: &AgentRequest) -> ChatDeltaStream;
+}
+
+/// Adapts a [`ModelBridge`] to [`AgentModel`], for
+/// [`AliciaUiRuntime::with_model_bridge`](crate::AliciaUiRuntime::with_model_bridge).
+pub struct BridgedAgentModel {
+    bridge: Arc<dyn ModelBridge>,
+}
+
+impl BridgedAgentModel {
+    pub fn new(bridge: Arc<dyn ModelBridge>) -> Self {
+        Self { bridge }
+    }
+}
+
+impl std::fmt::Debug for BridgedAgentModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BridgedAgentModel")
+            .field("bridge", &self.bridge.name())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl AgentModel for BridgedAgentModel {
+    fn name(&self) -> &str {
+        self.bridge.name()
+    }
+
+    async fn complete(&self, request: &AgentRequest) -> Result<AgentReply, AgentError> {
+        let (deltas, _) = mpsc::unbounded_channel();
+        self.stream(request, deltas).await
+    }
+
+    async fn stream(
+        &self,
+        request: &AgentRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<AgentReply, AgentError> {
+        let mut stream = self.bridge.send_prompt(request);
+        let mut reply = AgentReply::default();
+        while let Some(delta) = stream.next().await {
+            match delta? {
+                ChatDelta::Text(text) => {
+                    reply.text.push_str(&text);
+                    let _ = deltas.send(text);
+                }
+                ChatDelta::ToolCall(tool_call) => reply.tool_calls.push(tool_call),
+                ChatDelta::Usage(usage) => reply.usage = Some(usage),
+            }
+        }
+        Ok(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::StreamExt;
+    use pretty_assertions::assert_eq;
+    use tokio::sync::mpsc;
+
+    use super::BridgedAgentModel;
+    use super::ChatDelta;
+    use super::ChatDeltaStream;
+    use super::ModelBridge;
+    use crate::AgentError;
+    use crate::AgentModel;
+    use crate::AgentRequest;
+    use crate::AgentTool;
+    use crate::AgentToolCall;
+    use crate::TokenUsage;
+
+    struct CannedBridge(Vec<Result<ChatDelta, AgentError>>);
+
+    impl ModelBridge for CannedBridge {
+        fn name(&self) -> &str {
+            "canned"
+        }
+
+        fn send_prompt(&self, _request: &AgentRequest) -> ChatDeltaStream {
+            futures::stream::iter(self.0.clone()).boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn forwards_text_deltas_and_collects_tool_calls() -> Result<(), AgentError> {
+        let tool_call = AgentToolCall {
+            id: "call-1".to_string(),
+            tool: AgentTool::ReadFile {
+                path: "Cargo.toml".to_string(),
+            },
+        };
+        let model = BridgedAgentModel::new(Arc::new(CannedBridge(vec![
+            Ok(ChatDelta::Text("Vou ".to_string())),
+            Ok(ChatDelta::Text("ler.".to_string())),
+            Ok(ChatDelta::ToolCall(tool_call.clone())),
+            Ok(ChatDelta::Usage(TokenUsage::new(10, 4))),
+        ])));
+        let request = AgentRequest {
+            messages: Vec::new(),
+        };
+        let (deltas_tx, mut deltas_rx) = mpsc::unbounded_channel();
+
+        let reply = model.stream(&request, deltas_tx).await?;
+        assert_eq!(reply.text, "Vou ler.");
+        assert_eq!(reply.tool_calls, vec![tool_call]);
+        assert_eq!(reply.usage, Some(TokenUsage::new(10, 4)));
+        let mut deltas = Vec::new();
+        while let Ok(delta) = deltas_rx.try_recv() {
+            deltas.push(delta);
+        }
+        assert_eq!(deltas, vec!["Vou ", "ler."]);
+
+        let failing = BridgedAgentModel::new(Arc::new(CannedBridge(vec![
+            Ok(ChatDelta::Text("meio".to_string())),
+            Err(AgentError::ModelFailed {
+                model: "canned".to_string(),
+                reason: "conexao caiu".to_string(),
+            }),
+        ])));
+        assert!(matches!(
+            failing.complete(&request).await,
+            Err(AgentError::ModelFailed { .. })
+        ));
+        Ok(())
+    }
+}
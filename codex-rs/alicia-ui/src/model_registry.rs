@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use crate::AgentModel;
+use crate::ModelPricing;
+
+/// What the UI shows of a registered model. `id` is the model's `AgentModel::name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelDescriptor {
+    pub id: String,
+    pub provider: String,
+}
+
+struct RegisteredModel {
+    descriptor: ModelDescriptor,
+    model: Arc<dyn AgentModel>,
+    pricing: Option<ModelPricing>,
+}
+
+/// Models the agent can switch between at runtime, in registration order. The first one is
+/// the default selection.
+#[derive(Default)]
+pub struct ModelRegistry {
+    models: Vec<RegisteredModel>,
+}
+
+impl ModelRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `model` under `provider`; a later model with the same id replaces it.
+    pub fn with_model(self, provider: impl Into<String>, model: Arc<dyn AgentModel>) -> Self {
+        self.with_priced_model(provider, model, None)
+    }
+
+    pub fn with_priced_model(
+        mut self,
+        provider: impl Into<String>,
+        model: Arc<dyn AgentModel>,
+        pricing: Option<ModelPricing>,
+    ) -> Self {
+        let descriptor = ModelDescriptor {
+            id: model.name().to_string(),
+            provider: provider.into(),
+        };
+        self.models
+            .retain(|registered| registered.descriptor.id != descriptor.id);
+        self.models.push(RegisteredModel {
+            descriptor,
+            model,
+            pricing,
+        });
+        self
+    }
+
+    pub fn descriptors(&self) -> Vec<ModelDescriptor> {
+        self.models
+            .iter()
+            .map(|registered| registered.descriptor.clone())
+            .collect()
+    }
+
+    pub fn model(&self, id: &str) -> Option<Arc<dyn AgentModel>> {
+        self.models
+            .iter()
+            .find(|registered| registered.descriptor.id == id)
+            .map(|registered| Arc::clone(&registered.model))
+    }
+
+    pub fn pricing(&self, id: &str) -> Option<ModelPricing> {
+        self.models
+            .iter()
+            .find(|registered| registered.descriptor.id == id)
+            .and_then(|registered| registered.pricing)
+    }
+
+    pub fn default_model(&self) -> Option<Arc<dyn AgentModel>> {
+        self.models
+            .first()
+            .map(|registered| Arc::clone(&registered.model))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+}
+
+impl std::fmt::Debug for ModelRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.models.iter().map(|registered| &registered.descriptor))
+            .finish()
+    }
+}
@@ -0,0 +1,116 @@
+use std::process::Command;
+use std::time::Duration;
+
+pub const DEFAULT_LONG_COMMAND_THRESHOLD: Duration = Duration::from_secs(30);
+pub const DEFAULT_INPUT_PROMPT_IDLE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    ApprovalRequested,
+    LongCommandFinished,
+    ActionBlocked,
+    InputRequested,
+}
+
+/// Which events raise a desktop notification. Commands count as long-running once they take
+/// at least `long_command_threshold`, unless their session sets its own with
+/// `UiEventStore::set_session_long_command_threshold`, and as waiting for input once their
+/// output stops on a prompt for `input_prompt_idle`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationSettings {
+    pub approval_requested: bool,
+    pub long_command_finished: bool,
+    pub action_blocked: bool,
+    pub long_command_threshold: Duration,
+    pub input_requested: bool,
+    pub input_prompt_idle: Duration,
+    /// Also ask the window manager to flag the window when a long command finishes.
+    pub request_attention: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            approval_requested: true,
+            long_command_finished: true,
+            action_blocked: true,
+            long_command_threshold: DEFAULT_LONG_COMMAND_THRESHOLD,
+            input_requested: true,
+            input_prompt_idle: DEFAULT_INPUT_PROMPT_IDLE,
+            request_attention: false,
+        }
+    }
+}
+
+impl NotificationSettings {
+    pub fn is_enabled(&self, kind: NotificationKind) -> bool {
+        match kind {
+            NotificationKind::ApprovalRequested => self.approval_requested,
+            NotificationKind::LongCommandFinished => self.long_command_finished,
+            NotificationKind::ActionBlocked => self.action_blocked,
+            NotificationKind::InputRequested => self.input_requested,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopNotification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+}
+
+/// Shows `notification` through the OS notification center: `osascript` on macOS, a
+/// PowerShell toast on Windows and `notify-send` elsewhere. Returns once the helper is
+/// spawned; it is reaped on a background thread so the UI never waits on it.
+pub fn send_desktop_notification(notification: &DesktopNotification) -> std::io::Result<()> {
+    let mut child = notification_command(notification).spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn notification_command(notification: &DesktopNotification) -> Command {
+    let script = format!(
+        "display notification {} with title {}",
+        applescript_string(&notification.body),
+        applescript_string(&notification.title)
+    );
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(script);
+    command
+}
+
+#[cfg(target_os = "macos")]
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(windows)]
+fn notification_command(notification: &DesktopNotification) -> Command {
+    // Title and body travel as environment variables so no quoting reaches the script.
+    const TOAST_SCRIPT: &str = "\
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null;\
+$template = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02);\
+$texts = $template.GetElementsByTagName('text');\
+$texts.Item(0).AppendChild($template.CreateTextNode($env:ALICIA_NOTIFY_TITLE)) > $null;\
+$texts.Item(1).AppendChild($template.CreateTextNode($env:ALICIA_NOTIFY_BODY)) > $null;\
+$toast = [Windows.UI.Notifications.ToastNotification]::new($template);\
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('AlicIA').Show($toast)";
+    let mut command = Command::new("powershell");
+    command
+        .args(["-NoProfile", "-NonInteractive", "-Command", TOAST_SCRIPT])
+        .env("ALICIA_NOTIFY_TITLE", &notification.title)
+        .env("ALICIA_NOTIFY_BODY", &notification.body);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn notification_command(notification: &DesktopNotification) -> Command {
+    let mut command = Command::new("notify-send");
+    command
+        .args(["--app-name", "AlicIA", "--"])
+        .arg(&notification.title)
+        .arg(&notification.body);
+    command
+}
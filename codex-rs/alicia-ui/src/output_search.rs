@@ -0,0 +1,75 @@
+/// Hits returned by `UiEventStore::search_all_output`, enough for a panel without letting a
+/// one-letter query copy every scrollback.
+pub const MAX_OUTPUT_SEARCH_HITS: usize = 500;
+
+/// A line of a session's output matching a global search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputSearchHit {
+    pub session_id: String,
+    /// 1-based position in the session's visible buffer.
+    pub line_no: usize,
+    pub line: String,
+}
+
+/// Appends to `hits` the lines of `lines` containing `query`, ignoring case, until
+/// [`MAX_OUTPUT_SEARCH_HITS`] is reached. Returns false once it is.
+pub(crate) fn search_lines(
+    session_id: &str,
+    lines: &[String],
+    query: &str,
+    hits: &mut Vec<OutputSearchHit>,
+) -> bool {
+    let query = query.to_lowercase();
+    for (index, line) in lines.iter().enumerate() {
+        if hits.len() >= MAX_OUTPUT_SEARCH_HITS {
+            return false;
+        }
+        if line.to_lowercase().contains(&query) {
+            hits.push(OutputSearchHit {
+                session_id: session_id.to_string(),
+                line_no: index + 1,
+                line: line.clone(),
+            });
+        }
+    }
+    hits.len() < MAX_OUTPUT_SEARCH_HITS
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::MAX_OUTPUT_SEARCH_HITS;
+    use super::OutputSearchHit;
+    use super::search_lines;
+
+    #[test]
+    fn matches_ignoring_case_and_stops_at_the_cap() {
+        let lines = vec![
+            "Compiling alicia".to_string(),
+            "error[E0502]: cannot borrow".to_string(),
+            "ERROR: build failed".to_string(),
+        ];
+        let mut hits = Vec::new();
+        assert!(search_lines("sess-1", &lines, "error", &mut hits));
+        assert_eq!(
+            hits.iter()
+                .map(|hit| (hit.session_id.as_str(), hit.line_no))
+                .collect::<Vec<_>>(),
+            vec![("sess-1", 2), ("sess-1", 3)]
+        );
+
+        let many = vec!["x".to_string(); MAX_OUTPUT_SEARCH_HITS + 1];
+        let mut hits = Vec::new();
+        assert!(!search_lines("sess-2", &many, "x", &mut hits));
+        assert_eq!(hits.len(), MAX_OUTPUT_SEARCH_HITS);
+        assert_eq!(
+            hits.last(),
+            Some(&OutputSearchHit {
+                session_id: "sess-2".to_string(),
+                line_no: MAX_OUTPUT_SEARCH_HITS,
+                line: "x".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::PatchFilePreview;
+use crate::PatchHunkDecision;
+use crate::PatchHunkPreview;
+
+/// Where `AliciaUiRuntime::apply_approved_hunks` copies each file before rewriting it, under
+/// the root of the workspace holding the file, one directory per action.
+pub const PATCH_BACKUPS_DIR: &str = ".codex/patch-backups";
+
+#[derive(Debug, Error)]
+pub enum PatchApplyError {
+    #[error("patch for action `{0}` has no approved hunks")]
+    NoApprovedHunks(String),
+    #[error("patch for action `{0}` was already applied")]
+    AlreadyApplied(String),
+    #[error("patch for action `{action_id}` still has {count} undecided hunk(s)")]
+    UndecidedHunks { action_id: String, count: usize },
+    #[error("patch file `{file_path}` is outside the workspace: {reason}")]
+    OutsideWorkspace { file_path: String, reason: String },
+    #[error("hunk `{hunk_id}` of `{file_path}` does not match line {line} of the file")]
+    HunkMismatch {
+        file_path: String,
+        hunk_id: String,
+        line: usize,
+    },
+    #[error("hunk `{hunk_id}` of `{file_path}` has a line without a diff prefix: `{text}`")]
+    MalformedHunkLine {
+        file_path: String,
+        hunk_id: String,
+        text: String,
+    },
+    #[error("hunk `{hunk_id}` of `{file_path}` overlaps another hunk or starts past the end")]
+    HunkOutOfRange { file_path: String, hunk_id: String },
+    #[error("failed to read `{path}`: {source}")]
+    ReadFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to back up `{path}`: {source}")]
+    BackupFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to write `{path}`: {source}")]
+    WriteFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// `file`'s approved hunks as a unified diff of their own, with the new-side line numbers
+/// shifted to account for the hunks left out. `None` when no hunk is approved.
+pub fn filtered_unified_diff(file: &PatchFilePreview) -> Option<String> {
+    let mut diff = format!("--- a/{0}\n+++ b/{0}\n", file.file_path);
+    let mut offset: i64 = 0;
+    let mut any = false;
+    for hunk in approved_hunks(file) {
+        any = true;
+        let section = hunk
+            .header
+            .splitn(4, "@@")
+            .nth(2)
+            .filter(|section| !section.trim().is_empty())
+            .unwrap_or_default();
+        let new_start = (hunk.old_start as i64 + offset).max(0);
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@{section}\n",
+            hunk.old_start, hunk.old_count, new_start, hunk.new_count
+        ));
+        for line in &hunk.lines {
+            diff.push_str(line);
+            diff.push('\n');
+        }
+        offset += hunk.new_count as i64 - hunk.old_count as i64;
+    }
+    any.then_some(diff)
+}
+
+/// Applies `file`'s approved hunks to `original`, the file's current text (empty for a file
+/// the patch creates). Hunks must match at the lines their headers name; there is no fuzz.
+pub fn apply_approved_hunks_to(
+    file: &PatchFilePreview,
+    original: &str,
+) -> Result<String, PatchApplyError> {
+    let eol = if original.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let old_lines: Vec<&str> = original.split_inclusive('\n').collect();
+    let content = |line: &str| {
+        line.strip_suffix('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .unwrap_or(line)
+            .to_string()
+    };
+
+    let mut hunks = approved_hunks(file);
+    hunks.sort_by_key(|hunk| hunk.old_start);
+    let mut output = String::with_capacity(original.len());
+    let mut cursor = 0_usize;
+    for hunk in hunks {
+        let out_of_range = || PatchApplyError::HunkOutOfRange {
+            file_path: file.file_path.clone(),
+            hunk_id: hunk.hunk_id.clone(),
+        };
+        // An empty old side names the line the hunk goes after.
+        let start = if hunk.old_count == 0 {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        if start < cursor || start > old_lines.len() {
+            return Err(out_of_range());
+        }
+        output.extend(old_lines[cursor..start].iter().copied());
+        cursor = start;
+
+        let mut previous = None;
+        for line in &hunk.lines {
+            let (kind, text) = match line.chars().next() {
+                Some(kind) => (kind, &line[kind.len_utf8()..]),
+                None => (' ', ""),
+            };
+            match kind {
+                '\\' => {
+                    if previous == Some('+') && output.ends_with('\n') {
+                        output.truncate(output.len() - 1);
+                        if output.ends_with('\r') {
+                            output.truncate(output.len() - 1);
+                        }
+                    }
+                    continue;
+                }
+                '+' => {
+                    output.push_str(text);
+                    output.push_str(eol);
+                }
+                '-' | ' ' => {
+                    let Some(old_line) = old_lines.get(cursor) else {
+                        return Err(out_of_range());
+                    };
+                    if content(old_line) != text {
+                        return Err(PatchApplyError::HunkMismatch {
+                            file_path: file.file_path.clone(),
+                            hunk_id: hunk.hunk_id.clone(),
+                            line: cursor + 1,
+                        });
+                    }
+                    if kind == ' ' {
+                        output.push_str(old_line);
+                    }
+                    cursor += 1;
+                }
+                _ => {
+                    return Err(PatchApplyError::MalformedHunkLine {
+                        file_path: file.file_path.clone(),
+                        hunk_id: hunk.hunk_id.clone(),
+                        text: line.clone(),
+                    });
+                }
+            }
+            previous = Some(kind);
+        }
+    }
+    output.extend(old_lines[cursor..].iter().copied());
+    Ok(output)
+}
+
+/// `<workspace>/.codex/patch-backups/<action_id>/<file_path>`, with every character of
+/// `action_id` other than ASCII letters, digits, `-` and `_` replaced by `_` so the agent's
+/// ids can't climb out of the backups directory.
+pub fn patch_backup_path(workspace_root: &Path, action_id: &str, file_path: &str) -> PathBuf {
+    let action_dir: String = action_id
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    workspace_root
+        .join(PATCH_BACKUPS_DIR)
+        .join(action_dir)
+        .join(file_path.trim_start_matches('/'))
+}
+
+fn approved_hunks(file: &PatchFilePreview) -> Vec<&PatchHunkPreview> {
+    file.hunks
+        .iter()
+        .filter(|hunk| hunk.decision == PatchHunkDecision::Approved)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use std::path::Path;
+
+    use super::PatchApplyError;
+    use super::apply_approved_hunks_to;
+    use super::filtered_unified_diff;
+    use super::patch_backup_path;
+    use crate::PatchFilePreview;
+    use crate::PatchHunkDecision;
+    use crate::parse_unified_diff_hunks;
+
+    fn file_preview(diff: &str, decisions: &[PatchHunkDecision]) -> PatchFilePreview {
+        let Ok(mut hunks) = parse_unified_diff_hunks(diff) else {
+            panic!("sample diff should parse");
+        };
+        for (hunk, decision) in hunks.iter_mut().zip(decisions) {
+            hunk.decision = *decision;
+        }
+        PatchFilePreview {
+            file_path: "src/lib.rs".to_string(),
+            hunks,
+        }
+    }
+
+    const ORIGINAL: &str = "a\nb\nc\nd\ne\nf\n";
+    const DIFF: &str = "@@ -1,2 +1,3 @@ fn one\n a\n+a2\n b\n@@ -5,2 +6,1 @@\n e\n-f\n";
+
+    #[test]
+    fn applies_only_the_approved_hunks() -> Result<(), PatchApplyError> {
+        let both = file_preview(
+            DIFF,
+            &[PatchHunkDecision::Approved, PatchHunkDecision::Approved],
+        );
+        assert_eq!(
+            apply_approved_hunks_to(&both, ORIGINAL)?,
+            "a\na2\nb\nc\nd\ne\n"
+        );
+
+        let second_only = file_preview(
+            DIFF,
+            &[PatchHunkDecision::Rejected, PatchHunkDecision::Approved],
+        );
+        assert_eq!(
+            apply_approved_hunks_to(&second_only, ORIGINAL)?,
+            "a\nb\nc\nd\ne\n"
+        );
+        assert_eq!(
+            filtered_unified_diff(&second_only).as_deref(),
+            Some("--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -5,2 +5,1 @@\n e\n-f\n")
+        );
+
+        assert!(matches!(
+            apply_approved_hunks_to(&both, "a\nx\nc\nd\ne\nf\n"),
+            Err(PatchApplyError::HunkMismatch { line: 2, .. })
+        ));
+
+        let mut malformed = both;
+        if let Some(hunk) = malformed.hunks.first_mut() {
+            hunk.lines.push("?b".to_string());
+        }
+        assert!(matches!(
+            apply_approved_hunks_to(&malformed, ORIGINAL),
+            Err(PatchApplyError::MalformedHunkLine { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_line_endings_and_missing_final_newlines() -> Result<(), PatchApplyError> {
+        let crlf = file_preview(
+            "@@ -1,2 +1,2 @@\n a\n-b\n+B\n",
+            &[PatchHunkDecision::Approved],
+        );
+        assert_eq!(apply_approved_hunks_to(&crlf, "a\r\nb\r\n")?, "a\r\nB\r\n");
+
+        let no_newline = file_preview(
+            "@@ -1,1 +1,1 @@\n-a\n+b\n\\ No newline at end of file\n",
+            &[PatchHunkDecision::Approved],
+        );
+        assert_eq!(apply_approved_hunks_to(&no_newline, "a\n")?, "b");
+
+        let new_file = file_preview("@@ -0,0 +1,2 @@\n+x\n+y\n", &[PatchHunkDecision::Approved]);
+        assert_eq!(apply_approved_hunks_to(&new_file, "")?, "x\ny\n");
+        Ok(())
+    }
+
+    #[test]
+    fn backup_paths_keep_action_ids_inside_the_backups_directory() {
+        let root = Path::new("/work/app");
+        assert_eq!(
+            patch_backup_path(root, "act-1", "src/main.rs"),
+            root.join(".codex/patch-backups/act-1/src/main.rs")
+        );
+        assert_eq!(
+            patch_backup_path(root, "../../etc", "passwd"),
+            root.join(".codex/patch-backups/______etc/passwd")
+        );
+        assert_eq!(
+            patch_backup_path(root, "/tmp/x", "a.rs"),
+            root.join(".codex/patch-backups/_tmp_x/a.rs")
+        );
+    }
+}
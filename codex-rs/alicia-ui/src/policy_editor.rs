@@ -0,0 +1,458 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_alicia_core::ActionKind;
+use codex_alicia_core::ApprovalRule;
+use codex_alicia_core::CommandRule;
+use codex_alicia_core::NamedProfile;
+use codex_alicia_core::PathRule;
+use codex_alicia_core::PermissionProfile;
+use codex_alicia_core::PolicyDecision;
+use codex_alicia_core::ProfileDefinition;
+use codex_alicia_core::ProjectPolicyConfig;
+use codex_alicia_core::ProjectPolicyConfigError;
+use codex_alicia_core::load_project_policy;
+use codex_alicia_core::save_project_policy;
+
+use crate::FULL_ACCESS_CONFIRMATION;
+use crate::action_kind_name;
+use crate::policy_decision_name;
+
+const ACTION_KINDS: [ActionKind; 5] = [
+    ActionKind::ReadFile,
+    ActionKind::WriteFile,
+    ActionKind::ExecuteCommand,
+    ActionKind::ApplyPatch,
+    ActionKind::NetworkAccess,
+];
+const DECISIONS: [PolicyDecision; 3] = [
+    PolicyDecision::Allow,
+    PolicyDecision::RequireApproval,
+    PolicyDecision::Deny,
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum PolicyEditorError {
+    #[error("type `{FULL_ACCESS_CONFIRMATION}` to save a policy that grants full access")]
+    FullAccessNotConfirmed,
+    #[error(transparent)]
+    Policy(#[from] ProjectPolicyConfigError),
+}
+
+/// Draft of a workspace's `.codex/alicia-policy.toml`, edited with structured controls and
+/// only written back once it validates.
+#[derive(Debug, Clone)]
+pub struct PolicyEditor {
+    workspace_root: PathBuf,
+    draft: ProjectPolicyConfig,
+    new_profile_name: String,
+    /// What the user typed to confirm saving a draft that grants full access.
+    confirmation: String,
+    message: Option<String>,
+}
+
+impl PolicyEditor {
+    /// Starts from the workspace's policy file, or from an empty policy when it has none.
+    pub fn load(workspace_root: &Path) -> Result<Self, ProjectPolicyConfigError> {
+        let draft = load_project_policy(workspace_root)?
+            .unwrap_or_else(|| ProjectPolicyConfig::new(PermissionProfile::ReadWriteWithApproval));
+        Ok(Self {
+            workspace_root: workspace_root.to_path_buf(),
+            draft,
+            new_profile_name: String::new(),
+            confirmation: String::new(),
+            message: None,
+        })
+    }
+
+    pub fn draft(&self) -> &ProjectPolicyConfig {
+        &self.draft
+    }
+
+    pub fn draft_mut(&mut self) -> &mut ProjectPolicyConfig {
+        &mut self.draft
+    }
+
+    /// Adds an empty profile named `name`. Returns false when the name is blank or taken.
+    pub fn add_profile(&mut self, name: &str) -> bool {
+        let name = name.trim();
+        if name.is_empty() || self.draft.profiles.contains_key(name) {
+            return false;
+        }
+        self.draft
+            .profiles
+            .insert(name.to_string(), ProfileDefinition::default());
+        true
+    }
+
+    /// Removes the profile and stops selecting it.
+    pub fn remove_profile(&mut self, name: &str) {
+        self.draft.profiles.remove(name);
+        if self.draft.profile.as_deref() == Some(name) {
+            self.draft.profile = None;
+        }
+    }
+
+    /// Whether the draft's base profile is `FullAccess`, or one of its profiles is built on
+    /// `FullAccess` or allows every action.
+    pub fn grants_full_access(&self) -> bool {
+        self.draft.permission_profile == PermissionProfile::FullAccess
+            || self.draft.profiles.iter().any(|(name, definition)| {
+                let profile =
+                    NamedProfile::from_definition(name, definition, self.draft.permission_profile);
+                profile.base == PermissionProfile::FullAccess
+                    || ACTION_KINDS
+                        .iter()
+                        .all(|action| profile.decision_for(*action) == PolicyDecision::Allow)
+            })
+    }
+
+    /// Sets what the user typed to confirm a draft that grants full access.
+    pub fn set_confirmation(&mut self, typed: impl Into<String>) {
+        self.confirmation = typed.into();
+    }
+
+    fn full_access_confirmed(&self) -> bool {
+        !self.grants_full_access() || self.confirmation.trim() == FULL_ACCESS_CONFIRMATION
+    }
+
+    /// Writes the draft back, refusing one that grants full access until
+    /// [`FULL_ACCESS_CONFIRMATION`] has been typed.
+    pub fn save(&self) -> Result<PathBuf, PolicyEditorError> {
+        if !self.full_access_confirmed() {
+            return Err(PolicyEditorError::FullAccessNotConfirmed);
+        }
+        Ok(save_project_policy(&self.workspace_root, &self.draft)?)
+    }
+
+    /// Draws the editor. Returns the path written when "Salvar" saved the draft this frame.
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) -> Option<PathBuf> {
+        let mut saved = None;
+        ui.heading("Perfil");
+        ui.horizontal(|ui| {
+            ui.label("Perfil base:");
+            egui::ComboBox::from_id_salt("alicia_policy_base_profile")
+                .selected_text(self.draft.permission_profile.name())
+                .show_ui(ui, |ui| {
+                    for option in PermissionProfile::ALL {
+                        ui.selectable_value(
+                            &mut self.draft.permission_profile,
+                            option,
+                            option.name(),
+                        );
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Perfil ativo:");
+            let names: Vec<String> = self.draft.profiles.keys().cloned().collect();
+            egui::ComboBox::from_id_salt("alicia_policy_active_profile")
+                .selected_text(self.draft.profile.as_deref().unwrap_or("(perfil base)"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.draft.profile, None, "(perfil base)");
+                    for name in names {
+                        let label = name.clone();
+                        ui.selectable_value(&mut self.draft.profile, Some(name), label);
+                    }
+                });
+        });
+
+        ui.separator();
+        ui.heading("Perfis nomeados");
+        let mut removed_profile = None;
+        for (name, definition) in &mut self.draft.profiles {
+            egui::CollapsingHeader::new(name.as_str())
+                .id_salt(("alicia_policy_profile", name.as_str()))
+                .show(ui, |ui| {
+                    show_profile_definition(ui, name, definition);
+                    if ui.button("Remover perfil").clicked() {
+                        removed_profile = Some(name.clone());
+                    }
+                });
+        }
+        if let Some(name) = removed_profile {
+            self.remove_profile(&name);
+        }
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_profile_name).hint_text("nome do perfil"),
+            );
+            if ui.button("Adicionar perfil").clicked() && !self.new_profile_name.trim().is_empty() {
+                let name = std::mem::take(&mut self.new_profile_name);
+                if !self.add_profile(&name) {
+                    self.message = Some(format!("Já existe um perfil chamado \"{name}\"."));
+                    self.new_profile_name = name;
+                }
+            }
+        });
+
+        ui.separator();
+        ui.heading("Aprovações automáticas");
+        ui.label("Comandos aprovados sem perguntar; * vale qualquer trecho, ? um caractere.");
+        let mut removed_rule = None;
+        for (index, rule) in self.draft.approval_rules.rules.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                if rule.command.is_empty() {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut rule.pattern)
+                            .font(egui::TextStyle::Monospace)
+                            .hint_text("cargo test*"),
+                    );
+                } else {
+                    ui.monospace(&rule.pattern)
+                        .on_hover_text("Regra de \"sempre permitir\": vale só este comando exato.");
+                }
+                ui.checkbox(&mut rule.read_only, "só leitura");
+                if ui.small_button("Remover").clicked() {
+                    removed_rule = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed_rule {
+            self.draft.approval_rules.rules.remove(index);
+        }
+        if ui.button("Adicionar regra").clicked() {
+            self.draft.approval_rules.rules.push(ApprovalRule {
+                pattern: String::new(),
+                read_only: false,
+                command: Vec::new(),
+            });
+        }
+
+        ui.separator();
+        if self.grants_full_access() {
+            ui.colored_label(
+                egui::Color32::from_rgb(220, 80, 60),
+                "Esta política concede acesso total: comandos e edições rodam sem aprovação.",
+            );
+            ui.horizontal(|ui| {
+                ui.label(format!("Digite {FULL_ACCESS_CONFIRMATION} para confirmar:"));
+                ui.text_edit_singleline(&mut self.confirmation);
+            });
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(self.full_access_confirmed(), egui::Button::new("Salvar"))
+                .clicked()
+            {
+                self.message = Some(match self.save() {
+                    Ok(path) => {
+                        let message = format!("Política salva em {}.", path.display());
+                        self.confirmation.clear();
+                        saved = Some(path);
+                        message
+                    }
+                    Err(error) => format!("Não salvei a política: {error}"),
+                });
+            }
+            if ui.button("Recarregar").clicked() {
+                match load_project_policy(&self.workspace_root) {
+                    Ok(config) => {
+                        self.draft = config.unwrap_or_else(|| {
+                            ProjectPolicyConfig::new(PermissionProfile::ReadWriteWithApproval)
+                        });
+                        self.confirmation.clear();
+                        self.message = None;
+                    }
+                    Err(error) => {
+                        self.message = Some(format!("Não consegui recarregar: {error}"));
+                    }
+                }
+            }
+        });
+        if let Some(message) = self.message.as_deref() {
+            ui.label(message);
+        }
+        saved
+    }
+}
+
+fn show_profile_definition(ui: &mut egui::Ui, name: &str, definition: &mut ProfileDefinition) {
+    ui.horizontal(|ui| {
+        ui.label("Base:");
+        egui::ComboBox::from_id_salt(("alicia_policy_profile_base", name))
+            .selected_text(
+                definition
+                    .base
+                    .map_or("(perfil base do arquivo)", PermissionProfile::name),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut definition.base, None, "(perfil base do arquivo)");
+                for option in PermissionProfile::ALL {
+                    ui.selectable_value(&mut definition.base, Some(option), option.name());
+                }
+            });
+    });
+
+    ui.label("Decisões por ação:");
+    egui::Grid::new(("alicia_policy_profile_decisions", name)).show(ui, |ui| {
+        for action in ACTION_KINDS {
+            ui.label(action_kind_name(action));
+            let mut decision = definition.decisions.get(&action).copied();
+            egui::ComboBox::from_id_salt((
+                "alicia_policy_decision",
+                name,
+                action_kind_name(action),
+            ))
+            .selected_text(decision.map_or("herdar", policy_decision_name))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut decision, None, "herdar");
+                for option in DECISIONS {
+                    ui.selectable_value(&mut decision, Some(option), policy_decision_name(option));
+                }
+            });
+            match decision {
+                Some(decision) => {
+                    definition.decisions.insert(action, decision);
+                }
+                None => {
+                    definition.decisions.remove(&action);
+                }
+            }
+            ui.end_row();
+        }
+    });
+
+    ui.label("Regras de comando (prefixo):");
+    let mut removed_command = None;
+    for (index, rule) in definition.commands.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut rule.prefix)
+                    .font(egui::TextStyle::Monospace)
+                    .hint_text("git push"),
+            );
+            decision_combo(
+                ui,
+                ("alicia_policy_command", name, index),
+                &mut rule.decision,
+            );
+            if ui.small_button("Remover").clicked() {
+                removed_command = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed_command {
+        definition.commands.remove(index);
+    }
+    if ui.button("Adicionar comando").clicked() {
+        definition.commands.push(CommandRule {
+            prefix: String::new(),
+            decision: PolicyDecision::RequireApproval,
+        });
+    }
+
+    ui.label("Caminhos protegidos:");
+    let mut removed_path = None;
+    for (index, rule) in definition.paths.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            let mut path = rule.path.to_string_lossy().to_string();
+            if ui
+                .add(
+                    egui::TextEdit::singleline(&mut path)
+                        .font(egui::TextStyle::Monospace)
+                        .hint_text("vendor"),
+                )
+                .changed()
+            {
+                rule.path = PathBuf::from(path);
+            }
+            decision_combo(ui, ("alicia_policy_path", name, index), &mut rule.decision);
+            if ui.small_button("Remover").clicked() {
+                removed_path = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed_path {
+        definition.paths.remove(index);
+    }
+    if ui.button("Adicionar caminho").clicked() {
+        definition.paths.push(PathRule {
+            path: PathBuf::new(),
+            decision: PolicyDecision::Deny,
+        });
+    }
+}
+
+fn decision_combo(ui: &mut egui::Ui, id_salt: impl std::hash::Hash, decision: &mut PolicyDecision) {
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(policy_decision_name(*decision))
+        .show_ui(ui, |ui| {
+            for option in DECISIONS {
+                ui.selectable_value(decision, option, policy_decision_name(option));
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::ApprovalDecision;
+    use codex_alicia_core::PermissionProfile;
+    use codex_alicia_core::PolicyDecision;
+    use codex_alicia_core::load_project_policy;
+    use pretty_assertions::assert_eq;
+
+    use super::PolicyEditor;
+    use super::PolicyEditorError;
+    use crate::FULL_ACCESS_CONFIRMATION;
+    use crate::UiEventStore;
+
+    #[test]
+    fn edits_a_missing_policy_and_writes_it_back() -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::tempdir()?;
+        let mut editor = PolicyEditor::load(workspace.path())?;
+        assert!(editor.add_profile("ci"));
+        assert!(!editor.add_profile("ci"));
+        editor.draft_mut().permission_profile = PermissionProfile::ReadOnly;
+        editor.draft_mut().profile = Some("ci".to_string());
+        editor.save()?;
+
+        let saved = load_project_policy(workspace.path())?;
+        assert_eq!(saved.as_ref(), Some(editor.draft()));
+
+        editor.remove_profile("ci");
+        assert_eq!(editor.draft().profile, None);
+        Ok(())
+    }
+
+    #[test]
+    fn full_access_policies_need_the_typed_confirmation() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let workspace = tempfile::tempdir()?;
+        let mut editor = PolicyEditor::load(workspace.path())?;
+        assert!(!editor.grants_full_access());
+
+        assert!(editor.add_profile("yolo"));
+        let Some(definition) = editor.draft_mut().profiles.get_mut("yolo") else {
+            panic!("profile was just added");
+        };
+        for action in super::ACTION_KINDS {
+            definition.decisions.insert(action, PolicyDecision::Allow);
+        }
+        assert!(editor.grants_full_access());
+        assert!(matches!(
+            editor.save(),
+            Err(PolicyEditorError::FullAccessNotConfirmed)
+        ));
+        assert_eq!(load_project_policy(workspace.path())?, None);
+
+        editor.set_confirmation(FULL_ACCESS_CONFIRMATION);
+        let path = editor.save()?;
+
+        let mut store = UiEventStore::default();
+        store.record_policy_saved(&path, editor.grants_full_access());
+        let Some(record) = store.audit_records().last() else {
+            panic!("saving the policy should be audited");
+        };
+        assert_eq!(record.target, path.display().to_string());
+        assert_eq!(record.approval_decision, ApprovalDecision::Approved);
+        assert_eq!(
+            record
+                .metadata
+                .get("grants_full_access")
+                .map(String::as_str),
+            Some("true")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,383 @@
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+use serde_json::json;
+use thiserror::Error;
+
+use crate::UiEventStore;
+
+/// Suffix of the `NetworkAccess` action that gates opening a PR for a patch action.
+pub const PULL_REQUEST_ACTION_SUFFIX: &str = "-pr";
+const MAX_TIMELINE_LINES_IN_BODY: usize = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PullRequestProvider {
+    GitHub {
+        api_base: String,
+        owner: String,
+        repo: String,
+    },
+    GitLab {
+        api_base: String,
+        project: String,
+    },
+}
+
+/// Self-hosted GitHub Enterprise and GitLab hosts remotes may point at, besides `github.com`
+/// and `gitlab.com`. Host names are matched exactly, ignoring case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderHosts {
+    pub github: Vec<String>,
+    pub gitlab: Vec<String>,
+}
+
+impl ProviderHosts {
+    fn is_github(&self, host: &str) -> bool {
+        host.eq_ignore_ascii_case("github.com")
+            || self
+                .github
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(host))
+    }
+
+    fn is_gitlab(&self, host: &str) -> bool {
+        host.eq_ignore_ascii_case("gitlab.com")
+            || self
+                .gitlab
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(host))
+    }
+}
+
+impl PullRequestProvider {
+    /// Recognizes `git@host:owner/repo.git`, `https://host/owner/repo` and `ssh://` remotes
+    /// on `github.com` or `gitlab.com`.
+    pub fn from_remote_url(url: &str) -> Option<Self> {
+        Self::from_remote_url_with_hosts(url, &ProviderHosts::default())
+    }
+
+    /// Like [`PullRequestProvider::from_remote_url`], also accepting the self-hosted `hosts`.
+    /// A host that merely contains "github" or "gitlab" is not trusted with the API token.
+    pub fn from_remote_url_with_hosts(url: &str, hosts: &ProviderHosts) -> Option<Self> {
+        let url = url.trim();
+        let without_scheme = url
+            .split_once("://")
+            .map_or(url, |(_, rest)| rest)
+            .trim_start_matches("git@");
+        let (host, path) = without_scheme
+            .split_once(':')
+            .filter(|(host, _)| !host.contains('/'))
+            .or_else(|| without_scheme.split_once('/'))?;
+        let host = host.rsplit('@').next().unwrap_or(host);
+        let path = path.trim_matches('/').trim_end_matches(".git");
+        if path.is_empty() {
+            return None;
+        }
+        if hosts.is_github(host) {
+            let (owner, repo) = path.split_once('/')?;
+            let api_base = if host.eq_ignore_ascii_case("github.com") {
+                "https://api.github.com".to_string()
+            } else {
+                format!("https://{host}/api/v3")
+            };
+            Some(Self::GitHub {
+                api_base,
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+            })
+        } else if hosts.is_gitlab(host) {
+            Some(Self::GitLab {
+                api_base: format!("https://{host}/api/v4"),
+                project: path.to_string(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Environment variable holding the API token.
+    pub fn token_env_var(&self) -> &'static str {
+        match self {
+            Self::GitHub { .. } => "GITHUB_TOKEN",
+            Self::GitLab { .. } => "GITLAB_TOKEN",
+        }
+    }
+
+    pub fn api_base(&self) -> &str {
+        match self {
+            Self::GitHub { api_base, .. } | Self::GitLab { api_base, .. } => api_base,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequestDraft {
+    pub source_action_id: String,
+    pub branch: String,
+    pub base: String,
+    pub title: String,
+    pub body: String,
+}
+
+impl PullRequestDraft {
+    pub fn network_action_id(&self) -> String {
+        format!("{}{PULL_REQUEST_ACTION_SUFFIX}", self.source_action_id)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PullRequestError {
+    #[error("no applied patch found for action `{0}`")]
+    PatchNotApplied(String),
+    #[error("network access for `{0}` is denied by the current profile")]
+    NetworkDenied(String),
+    #[error("network access for `{0}` has not been approved")]
+    NotApproved(String),
+    #[error("remote `{0}` is not a GitHub or GitLab repository")]
+    UnsupportedRemote(String),
+    #[error("`git {command}` failed: {stderr}")]
+    GitFailed { command: String, stderr: String },
+    #[error("failed to run git: {0}")]
+    GitUnavailable(#[source] std::io::Error),
+    #[error("provider request failed: {0}")]
+    RequestFailed(#[from] reqwest::Error),
+    #[error("provider answered {status}: {body}")]
+    ProviderRejected { status: u16, body: String },
+}
+
+/// Title and body for the PR of an applied patch, built from its approval summary, files and
+/// the timeline entries that mention it.
+pub fn draft_pull_request(
+    store: &UiEventStore,
+    action_id: &str,
+    base: &str,
+) -> Result<PullRequestDraft, PullRequestError> {
+    let preview = store
+        .diff_preview(action_id)
+        .filter(|preview| preview.applied)
+        .ok_or_else(|| PullRequestError::PatchNotApplied(action_id.to_string()))?;
+    let summary = store.approval(action_id).map_or_else(
+        || format!("patch {action_id}"),
+        |approval| approval.summary.clone(),
+    );
+
+    let mut body = format!("## Resumo\n\n{summary}\n\n## Arquivos\n\n");
+    for file in &preview.files {
+        body.push_str(&format!("- `{file}`\n"));
+    }
+    body.push_str("\n## Linha do tempo\n\n");
+    let entries: Vec<_> = store
+        .timeline()
+        .iter()
+        .filter(|entry| entry.summary.contains(action_id))
+        .collect();
+    let skipped = entries.len().saturating_sub(MAX_TIMELINE_LINES_IN_BODY);
+    for entry in entries.into_iter().skip(skipped) {
+        body.push_str(&format!("- #{} {}\n", entry.sequence, entry.summary));
+    }
+    body.push_str("\nGerado pelo AlicIA a partir das mudancas aplicadas pelo agente.\n");
+
+    let branch_suffix: String = action_id
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    Ok(PullRequestDraft {
+        source_action_id: action_id.to_string(),
+        branch: format!("alicia/{branch_suffix}"),
+        base: base.to_string(),
+        title: format!("AlicIA: {summary}"),
+        body,
+    })
+}
+
+pub fn git_remote_url(workspace_root: &Path, remote: &str) -> Result<String, PullRequestError> {
+    run_git(workspace_root, &["remote", "get-url", remote])
+}
+
+/// Pushes the current `HEAD` to `branch` on `remote`.
+pub fn push_branch(
+    workspace_root: &Path,
+    remote: &str,
+    branch: &str,
+) -> Result<(), PullRequestError> {
+    let refspec = format!("HEAD:refs/heads/{branch}");
+    run_git(workspace_root, &["push", remote, &refspec]).map(|_| ())
+}
+
+fn run_git(workspace_root: &Path, args: &[&str]) -> Result<String, PullRequestError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(workspace_root)
+        .output()
+        .map_err(PullRequestError::GitUnavailable)?;
+    if !output.status.success() {
+        return Err(PullRequestError::GitFailed {
+            command: args.join(" "),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Opens the PR/MR through the provider API and returns its web URL.
+pub async fn open_pull_request(
+    provider: &PullRequestProvider,
+    token: &str,
+    draft: &PullRequestDraft,
+) -> Result<String, PullRequestError> {
+    let client = reqwest::Client::new();
+    let (request, url_field) = match provider {
+        PullRequestProvider::GitHub {
+            api_base,
+            owner,
+            repo,
+        } => (
+            client
+                .post(format!("{api_base}/repos/{owner}/{repo}/pulls"))
+                .bearer_auth(token)
+                .header("Accept", "application/vnd.github+json")
+                .json(&json!({
+                    "title": draft.title,
+                    "head": draft.branch,
+                    "base": draft.base,
+                    "body": draft.body,
+                })),
+            "html_url",
+        ),
+        PullRequestProvider::GitLab { api_base, project } => (
+            client
+                .post(format!(
+                    "{api_base}/projects/{}/merge_requests",
+                    project.replace('/', "%2F")
+                ))
+                .header("PRIVATE-TOKEN", token)
+                .json(&json!({
+                    "title": draft.title,
+                    "source_branch": draft.branch,
+                    "target_branch": draft.base,
+                    "description": draft.body,
+                })),
+            "web_url",
+        ),
+    };
+    let response = request
+        .header("User-Agent", "codex-alicia-ui")
+        .send()
+        .await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(PullRequestError::ProviderRejected {
+            status: status.as_u16(),
+            body,
+        });
+    }
+    let url = serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|value| value[url_field].as_str().map(str::to_string))
+        .unwrap_or_default();
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::IpcEvent;
+    use codex_alicia_core::IpcMessage;
+    use codex_alicia_core::ipc::ApprovalRequested;
+    use codex_alicia_core::ipc::PatchApplied;
+    use pretty_assertions::assert_eq;
+
+    use super::ProviderHosts;
+    use super::PullRequestProvider;
+    use super::draft_pull_request;
+    use crate::UiEventStore;
+
+    #[test]
+    fn parses_remotes_and_drafts_pr_from_applied_patch() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(
+            PullRequestProvider::from_remote_url("git@github.com:acme/app.git"),
+            Some(PullRequestProvider::GitHub {
+                api_base: "https://api.github.com".to_string(),
+                owner: "acme".to_string(),
+                repo: "app".to_string(),
+            })
+        );
+        assert_eq!(
+            PullRequestProvider::from_remote_url("https://gitlab.com/group/sub/app"),
+            Some(PullRequestProvider::GitLab {
+                api_base: "https://gitlab.com/api/v4".to_string(),
+                project: "group/sub/app".to_string(),
+            })
+        );
+        assert_eq!(
+            PullRequestProvider::from_remote_url("https://gitlab.example.com/group/sub/app"),
+            None
+        );
+        assert_eq!(
+            PullRequestProvider::from_remote_url("git@github.com.evil.io:acme/app.git"),
+            None
+        );
+        let hosts = ProviderHosts {
+            github: vec!["git.corp.example".to_string()],
+            gitlab: vec!["gitlab.example.com".to_string()],
+        };
+        assert_eq!(
+            PullRequestProvider::from_remote_url_with_hosts(
+                "https://gitlab.example.com/group/sub/app",
+                &hosts
+            ),
+            Some(PullRequestProvider::GitLab {
+                api_base: "https://gitlab.example.com/api/v4".to_string(),
+                project: "group/sub/app".to_string(),
+            })
+        );
+        assert_eq!(
+            PullRequestProvider::from_remote_url_with_hosts(
+                "git@git.corp.example:acme/app.git",
+                &hosts
+            ),
+            Some(PullRequestProvider::GitHub {
+                api_base: "https://git.corp.example/api/v3".to_string(),
+                owner: "acme".to_string(),
+                repo: "app".to_string(),
+            })
+        );
+        assert_eq!(
+            PullRequestProvider::from_remote_url("https://example.com/acme/app.git"),
+            None
+        );
+
+        let mut store = UiEventStore::default();
+        assert!(draft_pull_request(&store, "act/1", "main").is_err());
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act/1".to_string(),
+                summary: "Corrige parser".to_string(),
+                expires_at_unix_s: i64::MAX,
+            },
+        )));
+        store.push(IpcMessage::new(IpcEvent::PatchApplied(PatchApplied {
+            action_id: "act/1".to_string(),
+            files: vec!["src/parser.rs".to_string()],
+        })));
+
+        let draft = draft_pull_request(&store, "act/1", "main")?;
+        assert_eq!(draft.branch, "alicia/act-1");
+        assert_eq!(draft.title, "AlicIA: Corrige parser");
+        assert_eq!(draft.network_action_id(), "act/1-pr");
+        assert!(draft.body.contains("- `src/parser.rs`"));
+        assert!(
+            draft
+                .body
+                .contains("approval_requested act/1 Corrige parser")
+        );
+        Ok(())
+    }
+}
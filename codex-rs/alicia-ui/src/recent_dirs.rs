@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_alicia_core::ensure_target_in_workspace;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+pub const RECENT_DIRS_FILE_NAME: &str = "alicia-recent-dirs.json";
+/// Directories remembered per workspace; the least recently used is dropped first.
+pub const MAX_RECENT_DIRS: usize = 10;
+
+/// Working directories sessions were started in, most recent first, keyed by workspace root.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RecentDirs {
+    pub workspaces: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Error)]
+pub enum RecentDirsError {
+    #[error("failed to read recent directories `{path}`: {source}")]
+    ReadFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("recent directories `{path}` are invalid: {source}")]
+    InvalidFile {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("failed to write recent directories `{path}`: {source}")]
+    WriteFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// `$CODEX_HOME/alicia-recent-dirs.json`, falling back to `~/.codex`.
+pub fn default_recent_dirs_path() -> Option<PathBuf> {
+    let codex_home = std::env::var_os("CODEX_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".codex")))?;
+    Some(codex_home.join(RECENT_DIRS_FILE_NAME))
+}
+
+impl RecentDirs {
+    /// Missing files yield no recent directories.
+    pub fn load(path: &Path) -> Result<Self, RecentDirsError> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(source) if source.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(source) => {
+                return Err(RecentDirsError::ReadFailed {
+                    path: path.to_string_lossy().to_string(),
+                    source,
+                });
+            }
+        };
+        serde_json::from_str(&text).map_err(|source| RecentDirsError::InvalidFile {
+            path: path.to_string_lossy().to_string(),
+            source,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), RecentDirsError> {
+        let write_failed = |source| RecentDirsError::WriteFailed {
+            path: path.to_string_lossy().to_string(),
+            source,
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(write_failed)?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(std::io::Error::other);
+        std::fs::write(path, text.map_err(write_failed)?).map_err(write_failed)
+    }
+
+    /// Moves `cwd` to the front of the workspace's list. Returns false when it already was
+    /// the most recent one.
+    pub fn record(&mut self, workspace_root: &Path, cwd: &Path) -> bool {
+        let cwd = cwd.to_string_lossy().to_string();
+        let dirs = self
+            .workspaces
+            .entry(workspace_root.to_string_lossy().to_string())
+            .or_default();
+        if dirs.first() == Some(&cwd) {
+            return false;
+        }
+        dirs.retain(|dir| dir != &cwd);
+        dirs.insert(0, cwd);
+        dirs.truncate(MAX_RECENT_DIRS);
+        true
+    }
+
+    /// The workspace's directories, most recent first, leaving out those that were deleted
+    /// or no longer resolve inside `workspace_root`, e.g. through a symlink that moved.
+    pub fn for_workspace(&self, workspace_root: &Path) -> Vec<PathBuf> {
+        let Some(dirs) = self
+            .workspaces
+            .get(workspace_root.to_string_lossy().as_ref())
+        else {
+            return Vec::new();
+        };
+        dirs.iter()
+            .map(PathBuf::from)
+            .filter(|dir| {
+                ensure_target_in_workspace(workspace_root, dir)
+                    .is_ok_and(|guard| guard.canonical_target.is_dir())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::MAX_RECENT_DIRS;
+    use super::RecentDirs;
+
+    #[test]
+    fn keeps_the_most_recent_dirs_inside_the_workspace() -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = TempDir::new()?;
+        let outside = TempDir::new()?;
+        let root = workspace.path();
+        std::fs::create_dir_all(root.join("api"))?;
+        std::fs::create_dir_all(root.join("web"))?;
+        let path = root.join("config").join("recent.json");
+        assert_eq!(RecentDirs::load(&path)?, RecentDirs::default());
+
+        let mut recent = RecentDirs::default();
+        assert!(recent.record(root, &root.join("api")));
+        assert!(recent.record(root, &root.join("web")));
+        assert!(recent.record(root, &root.join("api")));
+        assert!(!recent.record(root, &root.join("api")));
+        assert!(recent.record(root, &root.join("removida")));
+        assert!(recent.record(root, outside.path()));
+        recent.save(&path)?;
+
+        let restored = RecentDirs::load(&path)?;
+        assert_eq!(restored, recent);
+        assert_eq!(
+            restored.for_workspace(root),
+            vec![root.join("api"), root.join("web")]
+        );
+        assert!(restored.for_workspace(outside.path()).is_empty());
+
+        for index in 0..MAX_RECENT_DIRS + 2 {
+            recent.record(root, &root.join(format!("dir-{index}")));
+        }
+        assert_eq!(
+            recent
+                .workspaces
+                .get(root.to_string_lossy().as_ref())
+                .map(Vec::len),
+            Some(MAX_RECENT_DIRS)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,352 @@
+use axum::Json;
+use axum::Router;
+use axum::extract::FromRequestParts;
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::routing::get;
+use axum::routing::post;
+use codex_alicia_core::ApproverIdentity;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use crate::rpc::INVALID_PARAMS;
+use crate::rpc::INVALID_REQUEST;
+use crate::rpc::RemoteAccess;
+use crate::rpc::RpcCall;
+use crate::rpc::RpcError;
+use crate::rpc::STORE_ERROR;
+
+type RestResponse = (StatusCode, Json<Value>);
+
+#[derive(Clone)]
+struct RestState {
+    calls: mpsc::Sender<RpcCall>,
+    access: RemoteAccess,
+}
+
+/// Remote principal of a request carrying `Authorization: Bearer <token>` with the API token.
+struct Authorized(ApproverIdentity);
+
+impl FromRequestParts<RestState> for Authorized {
+    type Rejection = RestResponse;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &RestState,
+    ) -> Result<Self, Self::Rejection> {
+        parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| state.access.authorize(token.trim()))
+            .map(Authorized)
+            .ok_or_else(|| {
+                (
+                    StatusCode::UNAUTHORIZED,
+                    Json(json!({ "error": { "message": "missing or invalid bearer token" } })),
+                )
+            })
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct AuditQuery {
+    action_id: Option<String>,
+}
+
+/// Builds the REST routes. Each handler forwards a `UiEventStore` call to `calls`, the same
+/// queue the JSON-RPC transports use, so the store owner answers both between frames:
+///
+/// - `GET /sessions`, `GET /sessions/{id}`
+/// - `GET /approvals`, `GET /approvals/{id}`, `POST /approvals/{id}/resolve`
+///   with `{"resolution": "approved" | "denied"}`
+/// - `GET /diffs/{action_id}`
+/// - `GET /audit`, optionally filtered with `?actionId=`
+/// - `GET /metrics`, the status bar counters plus event channel lag per subscriber
+/// - `GET /health`, the last `AliciaUiRuntime::health` check, `null` before the first one
+///
+/// Every route needs `Authorization: Bearer <token>` with the token of `access`; approvals
+/// resolved through the API are recorded as resolved by its remote principal.
+pub fn rest_router(calls: mpsc::Sender<RpcCall>, access: RemoteAccess) -> Router {
+    Router::new()
+        .route("/sessions", get(list_sessions))
+        .route("/sessions/{session_id}", get(get_session))
+        .route("/approvals", get(list_approvals))
+        .route("/approvals/{action_id}", get(get_approval))
+        .route("/approvals/{action_id}/resolve", post(resolve_approval))
+        .route("/diffs/{action_id}", get(get_diff_preview))
+        .route("/audit", get(list_audit_records))
+        .route("/metrics", get(get_metrics))
+        .route("/health", get(get_health))
+        .with_state(RestState { calls, access })
+}
+
+/// Serves `rest_router` on `addr` until the listener fails. Non-loopback addresses are refused
+/// unless `access` allows them.
+pub async fn serve_rest_api(
+    addr: &str,
+    access: RemoteAccess,
+    calls: mpsc::Sender<RpcCall>,
+) -> std::io::Result<()> {
+    let listener = access.bind(addr).await?;
+    axum::serve(listener, rest_router(calls, access)).await
+}
+
+async fn list_sessions(
+    State(state): State<RestState>,
+    Authorized(principal): Authorized,
+) -> RestResponse {
+    call_store(&state.calls, principal, "listSessions", Value::Null).await
+}
+
+async fn get_session(
+    State(state): State<RestState>,
+    Authorized(principal): Authorized,
+    Path(session_id): Path<String>,
+) -> RestResponse {
+    call_store(
+        &state.calls,
+        principal,
+        "getSession",
+        json!({ "sessionId": session_id }),
+    )
+    .await
+}
+
+async fn list_approvals(
+    State(state): State<RestState>,
+    Authorized(principal): Authorized,
+) -> RestResponse {
+    call_store(&state.calls, principal, "listApprovals", Value::Null).await
+}
+
+async fn get_approval(
+    State(state): State<RestState>,
+    Authorized(principal): Authorized,
+    Path(action_id): Path<String>,
+) -> RestResponse {
+    call_store(
+        &state.calls,
+        principal,
+        "getApproval",
+        json!({ "actionId": action_id }),
+    )
+    .await
+}
+
+async fn resolve_approval(
+    State(state): State<RestState>,
+    Authorized(principal): Authorized,
+    Path(action_id): Path<String>,
+    Json(body): Json<Value>,
+) -> RestResponse {
+    let params = json!({ "actionId": action_id, "resolution": body["resolution"] });
+    call_store(&state.calls, principal, "resolveApproval", params).await
+}
+
+async fn get_diff_preview(
+    State(state): State<RestState>,
+    Authorized(principal): Authorized,
+    Path(action_id): Path<String>,
+) -> RestResponse {
+    call_store(
+        &state.calls,
+        principal,
+        "getDiffPreview",
+        json!({ "actionId": action_id }),
+    )
+    .await
+}
+
+async fn list_audit_records(
+    State(state): State<RestState>,
+    Authorized(principal): Authorized,
+    Query(query): Query<AuditQuery>,
+) -> RestResponse {
+    call_store(
+        &state.calls,
+        principal,
+        "listAuditRecords",
+        json!({ "actionId": query.action_id }),
+    )
+    .await
+}
+
+async fn get_metrics(
+    State(state): State<RestState>,
+    Authorized(principal): Authorized,
+) -> RestResponse {
+    call_store(&state.calls, principal, "getMetrics", Value::Null).await
+}
+
+async fn get_health(
+    State(state): State<RestState>,
+    Authorized(principal): Authorized,
+) -> RestResponse {
+    call_store(&state.calls, principal, "getHealth", Value::Null).await
+}
+
+async fn call_store(
+    calls: &mpsc::Sender<RpcCall>,
+    principal: ApproverIdentity,
+    method: &str,
+    params: Value,
+) -> RestResponse {
+    let (call, outcome) = RpcCall::new(method, params);
+    let outcome = match calls.send(call.with_approver(principal)).await {
+        Ok(()) => outcome.await.ok(),
+        Err(_) => None,
+    };
+    match outcome {
+        Some(Ok(result)) => (StatusCode::OK, Json(result)),
+        Some(Err(error)) => (error_status(&error), Json(json!({ "error": error }))),
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": { "message": "runtime is not answering" } })),
+        ),
+    }
+}
+
+fn error_status(error: &RpcError) -> StatusCode {
+    let store_code = error
+        .data
+        .as_ref()
+        .and_then(|data| data["code"].as_str())
+        .unwrap_or_default();
+    match error.code {
+        INVALID_PARAMS | INVALID_REQUEST => StatusCode::BAD_REQUEST,
+        STORE_ERROR if store_code.ends_with("_not_found") => StatusCode::NOT_FOUND,
+        STORE_ERROR => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::IpcEvent;
+    use codex_alicia_core::IpcMessage;
+    use codex_alicia_core::ipc::ApprovalRequested;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use tokio::sync::mpsc;
+
+    use axum::extract::FromRequestParts;
+    use axum::http::Request;
+    use axum::http::StatusCode;
+    use codex_alicia_core::ApproverIdentity;
+
+    use super::Authorized;
+    use super::RestState;
+    use super::call_store;
+    use crate::RemoteAccess;
+    use crate::StoreConfig;
+    use crate::UiEventStore;
+
+    fn principal() -> ApproverIdentity {
+        ApproverIdentity::remote_principal("ci-bot")
+    }
+
+    #[tokio::test]
+    async fn requires_the_api_bearer_token() -> Result<(), Box<dyn std::error::Error>> {
+        let (calls, _pending) = mpsc::channel(8);
+        let state = RestState {
+            calls,
+            access: RemoteAccess::new("s3cret", "ci-bot"),
+        };
+        for (header, authorized) in [
+            (None, false),
+            (Some("Bearer guess"), false),
+            (Some("Basic s3cret"), false),
+            (Some("Bearer s3cret"), true),
+        ] {
+            let mut request = Request::builder().uri("/approvals");
+            if let Some(header) = header {
+                request = request.header("authorization", header);
+            }
+            let (mut parts, ()) = request.body(())?.into_parts();
+            match Authorized::from_request_parts(&mut parts, &state).await {
+                Ok(Authorized(approver)) => {
+                    assert!(authorized, "{header:?} was accepted");
+                    assert_eq!(approver, principal());
+                }
+                Err((status, _)) => {
+                    assert!(!authorized, "{header:?} was refused");
+                    assert_eq!(status, StatusCode::UNAUTHORIZED);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn maps_store_calls_to_http_statuses() {
+        let (calls, mut pending) = mpsc::channel(8);
+        let store_owner = tokio::spawn(async move {
+            let mut store =
+                UiEventStore::new(StoreConfig::default().with_max_scrollback_lines(100));
+            store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                ApprovalRequested {
+                    action_id: "act-1".to_string(),
+                    summary: "run cargo test".to_string(),
+                    expires_at_unix_s: i64::MAX,
+                },
+            )));
+            store.record_subscriber_lag("runtime", 3);
+            while let Some(call) = pending.recv().await {
+                call.answer(&mut store);
+            }
+        });
+
+        let (status, body) = call_store(
+            &calls,
+            principal(),
+            "resolveApproval",
+            json!({ "actionId": "act-1", "resolution": "denied" }),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body.0["resolution"], json!("denied"));
+
+        let (status, _) = call_store(
+            &calls,
+            principal(),
+            "resolveApproval",
+            json!({ "actionId": "act-1", "resolution": "approved" }),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::CONFLICT);
+
+        let (status, _) = call_store(
+            &calls,
+            principal(),
+            "getDiffPreview",
+            json!({ "actionId": "act-9" }),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+
+        let (status, _) = call_store(
+            &calls,
+            principal(),
+            "resolveApproval",
+            json!({ "actionId": "act-1" }),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        let (status, body) = call_store(&calls, principal(), "getMetrics", json!(null)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body.0["laggedSubscribers"]["runtime"], json!(3));
+
+        drop(calls);
+        let _ = store_owner.await;
+    }
+}
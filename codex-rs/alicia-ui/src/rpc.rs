@@ -0,0 +1,708 @@
+use std::net::SocketAddr;
+
+use codex_alicia_core::ApprovalResolution;
+use codex_alicia_core::ApproverIdentity;
+use codex_alicia_core::IpcMessage;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_json::json;
+use tokio::io::AsyncBufRead;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+
+use crate::CommandLifecycle;
+use crate::PatchHunkDecision;
+use crate::TerminalSessionState;
+use crate::UiEventStore;
+use crate::UiEventStoreError;
+
+const JSON_RPC_VERSION: &str = "2.0";
+const PARSE_ERROR: i64 = -32700;
+pub(crate) const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+pub(crate) const INVALID_PARAMS: i64 = -32602;
+/// Store rejected the call (unknown approval, hunk, ...); `data.code` carries the error code
+/// also shown in the error center.
+pub(crate) const STORE_ERROR: i64 = -32000;
+/// A TCP client called a method before `authenticate` succeeded.
+pub(crate) const UNAUTHORIZED: i64 = -32001;
+const AUTHENTICATE_METHOD: &str = "authenticate";
+
+/// Credential that clients of the network transports (`listen_json_rpc`, `serve_rest_api`)
+/// must present, and the principal their approval verdicts are recorded under. Listeners
+/// only bind to loopback addresses unless [`RemoteAccess::allowing_non_loopback`] is set.
+#[derive(Clone)]
+pub struct RemoteAccess {
+    token: String,
+    principal: String,
+    allow_non_loopback: bool,
+}
+
+impl RemoteAccess {
+    pub fn new(token: impl Into<String>, principal: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            principal: principal.into(),
+            allow_non_loopback: false,
+        }
+    }
+
+    /// Lets listeners bind to addresses reachable from other hosts.
+    pub fn allowing_non_loopback(mut self) -> Self {
+        self.allow_non_loopback = true;
+        self
+    }
+
+    /// The remote principal for a client presenting `token`, or `None` when it doesn't match.
+    /// An empty configured token never matches.
+    pub fn authorize(&self, token: &str) -> Option<ApproverIdentity> {
+        let expected = self.token.as_bytes();
+        let presented = token.as_bytes();
+        // Compared without short-circuiting so the mismatch position doesn't leak through
+        // timing.
+        let matches = !expected.is_empty()
+            && expected.len() == presented.len()
+            && expected
+                .iter()
+                .zip(presented)
+                .fold(0_u8, |diff, (left, right)| diff | (left ^ right))
+                == 0;
+        matches.then(|| ApproverIdentity::remote_principal(self.principal.clone()))
+    }
+
+    pub(crate) async fn bind(&self, addr: &str) -> std::io::Result<tokio::net::TcpListener> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let local_addr: SocketAddr = listener.local_addr()?;
+        if !self.allow_non_loopback && !local_addr.ip().is_loopback() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("refusing to listen on non-loopback address {local_addr}"),
+            ));
+        }
+        Ok(listener)
+    }
+}
+
+impl std::fmt::Debug for RemoteAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteAccess")
+            .field("token", &"<redacted>")
+            .field("principal", &self.principal)
+            .field("allow_non_loopback", &self.allow_non_loopback)
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    jsonrpc: Option<String>,
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct RpcError {
+    pub(crate) code: i64,
+    pub(crate) message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) data: Option<Value>,
+}
+
+impl RpcError {
+    pub(crate) fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<UiEventStoreError> for RpcError {
+    fn from(error: UiEventStoreError) -> Self {
+        Self {
+            code: STORE_ERROR,
+            message: error.to_string(),
+            data: Some(json!({ "code": error.error_report().code })),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionParams {
+    session_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActionParams {
+    action_id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct AuditParams {
+    action_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResolveApprovalParams {
+    action_id: String,
+    resolution: ApprovalResolution,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthenticateParams {
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HunkDecisionParams {
+    action_id: String,
+    file_path: String,
+    hunk_id: String,
+    decision: PatchHunkDecision,
+}
+
+/// Handles one JSON-RPC 2.0 request against `store`. Returns the serialized response, or
+/// `None` for notifications (requests without `id`).
+///
+/// Methods mirror `UiEventStore`: `listSessions`, `getSession`, `listApprovals`,
+/// `getApproval`, `resolveApproval`, `listDiffPreviews`, `getDiffPreview`,
+/// `setPatchHunkDecision`, `listAuditRecords`, `getMetrics` and `getHealth`.
+pub fn handle_rpc_request(store: &mut UiEventStore, request: &str) -> Option<String> {
+    let (id, outcome) = match parse_request(request) {
+        Ok(request) => {
+            let outcome = dispatch(store, &request.method, request.params, None);
+            (request.id?, outcome)
+        }
+        Err(rejected) => rejected,
+    };
+    response_line(id, outcome)
+}
+
+/// Envelope checks; rejected requests come back with the id to answer them with.
+fn parse_request(request: &str) -> Result<RpcRequest, (Value, Result<Value, RpcError>)> {
+    match serde_json::from_str::<RpcRequest>(request) {
+        Ok(request) if request.jsonrpc.as_deref() != Some(JSON_RPC_VERSION) => Err((
+            request.id.unwrap_or(Value::Null),
+            Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"")),
+        )),
+        Ok(request) => Ok(request),
+        Err(error) => {
+            let code = if serde_json::from_str::<Value>(request).is_ok() {
+                INVALID_REQUEST
+            } else {
+                PARSE_ERROR
+            };
+            Err((Value::Null, Err(RpcError::new(code, error.to_string()))))
+        }
+    }
+}
+
+fn response_line(id: Value, outcome: Result<Value, RpcError>) -> Option<String> {
+    let (result, error) = match outcome {
+        Ok(result) => (Some(result), None),
+        Err(error) => (None, Some(error)),
+    };
+    serde_json::to_string(&RpcResponse {
+        jsonrpc: JSON_RPC_VERSION,
+        id,
+        result,
+        error,
+    })
+    .ok()
+}
+
+/// `approver` is the remote principal behind the call; local calls leave it `None` and
+/// resolve as the store's own approver.
+pub(crate) fn dispatch(
+    store: &mut UiEventStore,
+    method: &str,
+    params: Value,
+    approver: Option<ApproverIdentity>,
+) -> Result<Value, RpcError> {
+    match method {
+        "listSessions" => {
+            let store: &UiEventStore = store;
+            Ok(Value::Array(
+                store
+                    .terminal_session_ids()
+                    .iter()
+                    .filter_map(|session_id| store.terminal_session(session_id))
+                    .map(|session| session_json(store, session, false))
+                    .collect(),
+            ))
+        }
+        "getSession" => {
+            let params: SessionParams = parse_params(params)?;
+            let session = store
+                .terminal_session(&params.session_id)
+                .ok_or_else(|| UiEventStoreError::SessionNotFound(params.session_id.clone()))?;
+            Ok(session_json(store, session, true))
+        }
+        "listApprovals" => to_json(store.pending_approvals()),
+        "getApproval" => {
+            let params: ActionParams = parse_params(params)?;
+            let approval = store
+                .approval(&params.action_id)
+                .ok_or_else(|| UiEventStoreError::ApprovalNotPending(params.action_id.clone()))?;
+            to_json(approval)
+        }
+        "resolveApproval" => {
+            let params: ResolveApprovalParams = parse_params(params)?;
+            let message = match approver {
+                Some(approver) => store.resolve_pending_approval_as(
+                    &params.action_id,
+                    params.resolution,
+                    Some(approver),
+                )?,
+                None => store.resolve_pending_approval(&params.action_id, params.resolution)?,
+            };
+            to_json(message)
+        }
+        "listDiffPreviews" => to_json(store.unapplied_diff_previews()),
+        "getDiffPreview" => {
+            let params: ActionParams = parse_params(params)?;
+            let preview = store
+                .diff_preview(&params.action_id)
+                .ok_or_else(|| UiEventStoreError::PatchPreviewNotFound(params.action_id.clone()))?;
+            to_json(preview)
+        }
+        "setPatchHunkDecision" => {
+            let params: HunkDecisionParams = parse_params(params)?;
+            match approver {
+                Some(approver) => store.set_patch_hunk_decision_as(
+                    &params.action_id,
+                    &params.file_path,
+                    &params.hunk_id,
+                    params.decision,
+                    Some(approver),
+                )?,
+                None => store.set_patch_hunk_decision(
+                    &params.action_id,
+                    &params.file_path,
+                    &params.hunk_id,
+                    params.decision,
+                )?,
+            }
+            let unresolved = store.unresolved_patch_hunk_count(&params.action_id);
+            Ok(json!({ "unresolvedHunks": unresolved }))
+        }
+        "getMetrics" => {
+            let metrics = store.status_metrics();
+            Ok(json!({
+                "pendingApprovals": metrics.pending_approvals,
+                "runningSessions": metrics.running_sessions,
+                "lastEventLatencyMs": metrics
+                    .last_event_latency
+                    .map(|latency| latency.as_secs_f64() * 1_000.0),
+                "droppedEvents": metrics.dropped_events,
+                "eventChannelCapacity": metrics.event_channel_capacity,
+                "laggedSubscribers": metrics.lagged_subscribers,
+                "eventQueue": metrics.event_queue.map(|queue| json!({
+                    "capacity": queue.capacity,
+                    "depth": queue.depth,
+                    "maxDepth": queue.max_depth,
+                    "fullWaits": queue.full_waits,
+                    "applied": queue.applied,
+                })),
+                "unreadErrors": metrics.unread_errors,
+            }))
+        }
+        "getHealth" => to_json(store.runtime_health()),
+        "listAuditRecords" => {
+            let params: AuditParams = if params.is_null() {
+                AuditParams::default()
+            } else {
+                parse_params(params)?
+            };
+            match params.action_id {
+                Some(action_id) => to_json(store.audit_records_for_action(&action_id)),
+                None => to_json(store.audit_records()),
+            }
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("method `{method}` not found"),
+        )),
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|error| RpcError::new(INVALID_PARAMS, error.to_string()))
+}
+
+fn to_json(value: impl Serialize) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|error| RpcError::new(STORE_ERROR, error.to_string()))
+}
+
+fn session_json(store: &UiEventStore, session: &TerminalSessionState, with_output: bool) -> Value {
+    let (running, exit_code, duration_ms) = match session.lifecycle {
+        CommandLifecycle::Running => (true, None, None),
+        CommandLifecycle::Finished {
+            exit_code,
+            duration_ms,
+        } => (false, Some(exit_code), Some(duration_ms)),
+    };
+    let mut value = json!({
+        "sessionId": session.session_id,
+        "command": session.command,
+        "cwd": session.cwd,
+        "running": running,
+        "exitCode": exit_code,
+        "durationMs": duration_ms,
+        "active": store.active_session_id() == Some(session.session_id.as_str()),
+    });
+    if with_output {
+        value["lines"] = json!(session.visible_lines());
+    }
+    value
+}
+
+/// Method call read by a transport, answered by whoever owns the store.
+#[derive(Debug)]
+pub struct RpcCall {
+    method: String,
+    params: Value,
+    approver: Option<ApproverIdentity>,
+    reply: oneshot::Sender<Result<Value, RpcError>>,
+}
+
+impl RpcCall {
+    pub(crate) fn new(
+        method: impl Into<String>,
+        params: Value,
+    ) -> (Self, oneshot::Receiver<Result<Value, RpcError>>) {
+        let (reply, outcome) = oneshot::channel();
+        let call = Self {
+            method: method.into(),
+            params,
+            approver: None,
+            reply,
+        };
+        (call, outcome)
+    }
+
+    /// Marks the call as made by an authenticated remote `approver`.
+    pub(crate) fn with_approver(mut self, approver: ApproverIdentity) -> Self {
+        self.approver = Some(approver);
+        self
+    }
+
+    /// Answers the call against `store`. Returns the `ApprovalResolved` messages the store
+    /// has not broadcast yet, the call's own included, for the caller to publish.
+    pub fn answer(self, store: &mut UiEventStore) -> Vec<IpcMessage> {
+        let outcome = dispatch(store, &self.method, self.params, self.approver);
+        let _ = self.reply.send(outcome);
+        store.take_unpublished_resolutions()
+    }
+}
+
+/// Serves newline-delimited JSON-RPC over `reader`/`writer` of a trusted local channel such as
+/// stdio, forwarding each call to `calls` so the store owner answers it between frames.
+/// Socket clients go through [`listen_json_rpc`], which authenticates them first.
+pub async fn serve_json_rpc<R, W>(
+    reader: R,
+    writer: W,
+    calls: mpsc::Sender<RpcCall>,
+) -> std::io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    serve_connection(reader, writer, calls, None).await
+}
+
+/// With `access`, every call before a successful `authenticate` is refused and later calls
+/// carry the authenticated principal.
+async fn serve_connection<R, W>(
+    reader: R,
+    mut writer: W,
+    calls: mpsc::Sender<RpcCall>,
+    access: Option<&RemoteAccess>,
+) -> std::io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut principal: Option<ApproverIdentity> = None;
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (id, outcome) = match parse_request(&line) {
+            Ok(request) if access.is_some() && request.method == AUTHENTICATE_METHOD => {
+                let outcome =
+                    parse_params::<AuthenticateParams>(request.params).and_then(|params| {
+                        principal = access.and_then(|access| access.authorize(&params.token));
+                        principal
+                            .as_ref()
+                            .map(|principal| json!({ "principal": principal.name }))
+                            .ok_or_else(|| RpcError::new(UNAUTHORIZED, "invalid token"))
+                    });
+                let Some(id) = request.id else {
+                    continue;
+                };
+                (id, outcome)
+            }
+            Ok(request) if access.is_some() && principal.is_none() => (
+                request.id.unwrap_or(Value::Null),
+                Err(RpcError::new(
+                    UNAUTHORIZED,
+                    "call `authenticate` with the API token first",
+                )),
+            ),
+            Ok(request) => {
+                let (mut call, outcome) = RpcCall::new(request.method, request.params);
+                if let Some(principal) = principal.clone() {
+                    call = call.with_approver(principal);
+                }
+                if calls.send(call).await.is_err() {
+                    break;
+                }
+                let Ok(outcome) = outcome.await else {
+                    break;
+                };
+                let Some(id) = request.id else {
+                    continue;
+                };
+                (id, outcome)
+            }
+            Err(rejected) => rejected,
+        };
+        if let Some(response) = response_line(id, outcome) {
+            writer.write_all(response.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+            writer.flush().await?;
+        }
+    }
+    Ok(())
+}
+
+/// Accepts JSON-RPC clients on `addr` until the listener fails, one task per connection.
+/// Each client must first call `authenticate` with `{"token": ...}` matching `access`.
+pub async fn listen_json_rpc(
+    addr: &str,
+    access: RemoteAccess,
+    calls: mpsc::Sender<RpcCall>,
+) -> std::io::Result<()> {
+    let listener = access.bind(addr).await?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let calls = calls.clone();
+        let access = access.clone();
+        tokio::spawn(async move {
+            let (reader, writer) = stream.into_split();
+            let reader = tokio::io::BufReader::new(reader);
+            let _ = serve_connection(reader, writer, calls, Some(&access)).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::IpcEvent;
+    use codex_alicia_core::IpcMessage;
+    use codex_alicia_core::ipc::ApprovalRequested;
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+    use serde_json::json;
+
+    use codex_alicia_core::ApproverIdentity;
+    use tokio::io::AsyncBufReadExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::sync::mpsc;
+
+    use super::RemoteAccess;
+    use super::handle_rpc_request;
+    use super::serve_connection;
+    use crate::StoreConfig;
+    use crate::UiEventStore;
+
+    fn call(store: &mut UiEventStore, request: Value) -> Value {
+        let response = handle_rpc_request(store, &request.to_string()).unwrap_or_default();
+        serde_json::from_str(&response).unwrap_or(Value::Null)
+    }
+
+    #[test]
+    fn resolves_approvals_and_reports_errors_over_json_rpc() {
+        let mut store = UiEventStore::new(StoreConfig::default().with_max_scrollback_lines(100));
+        store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+            ApprovalRequested {
+                action_id: "act-1".to_string(),
+                summary: "run cargo test".to_string(),
+                expires_at_unix_s: i64::MAX,
+            },
+        )));
+
+        let listed = call(
+            &mut store,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "listApprovals"}),
+        );
+        assert_eq!(listed["result"][0]["actionId"], json!("act-1"));
+        assert_eq!(listed["result"][0]["status"], json!("pending"));
+
+        let resolved = call(
+            &mut store,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "resolveApproval",
+                "params": {"actionId": "act-1", "resolution": "approved"},
+            }),
+        );
+        assert_eq!(resolved["id"], json!(2));
+        assert_eq!(
+            store.approval("act-1").map(|approval| approval.status),
+            Some(crate::ApprovalStatus::Approved)
+        );
+
+        let again = call(
+            &mut store,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "resolveApproval",
+                "params": {"actionId": "act-1", "resolution": "denied"},
+            }),
+        );
+        assert_eq!(again["error"]["code"], json!(-32000));
+
+        let unknown = call(
+            &mut store,
+            json!({"jsonrpc": "2.0", "id": 4, "method": "dropTables"}),
+        );
+        assert_eq!(unknown["error"]["code"], json!(-32601));
+
+        assert_eq!(
+            handle_rpc_request(
+                &mut store,
+                r#"{"jsonrpc": "2.0", "method": "listSessions"}"#
+            ),
+            None
+        );
+        let malformed = handle_rpc_request(&mut store, "{not json").unwrap_or_default();
+        assert_eq!(
+            serde_json::from_str::<Value>(&malformed).unwrap_or(Value::Null)["error"]["code"],
+            json!(-32700)
+        );
+    }
+
+    async fn exchange<W, R>(
+        writer: &mut W,
+        responses: &mut tokio::io::Lines<R>,
+        request: Value,
+    ) -> Result<Value, Box<dyn std::error::Error>>
+    where
+        W: tokio::io::AsyncWrite + Unpin,
+        R: tokio::io::AsyncBufRead + Unpin,
+    {
+        writer.write_all(format!("{request}\n").as_bytes()).await?;
+        let line = responses.next_line().await?.ok_or("connection closed")?;
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    #[tokio::test]
+    async fn socket_clients_authenticate_and_resolve_as_remote_principals()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let (calls, mut pending) = mpsc::channel(8);
+        let store_owner = tokio::spawn(async move {
+            let mut store =
+                UiEventStore::new(StoreConfig::default().with_max_scrollback_lines(100));
+            store.push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                ApprovalRequested {
+                    action_id: "act-1".to_string(),
+                    summary: "run cargo test".to_string(),
+                    expires_at_unix_s: i64::MAX,
+                },
+            )));
+            let mut published = Vec::new();
+            while let Some(call) = pending.recv().await {
+                published.extend(call.answer(&mut store));
+            }
+            (store, published)
+        });
+        let (client, server) = tokio::io::duplex(4096);
+        let (server_reader, server_writer) = tokio::io::split(server);
+        let connection = tokio::spawn(async move {
+            let access = RemoteAccess::new("s3cret", "ci-bot");
+            let reader = tokio::io::BufReader::new(server_reader);
+            serve_connection(reader, server_writer, calls, Some(&access)).await
+        });
+        let (client_reader, mut client_writer) = tokio::io::split(client);
+        let mut responses = tokio::io::BufReader::new(client_reader).lines();
+        let resolve = json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "resolveApproval",
+            "params": {"actionId": "act-1", "resolution": "approved"},
+        });
+
+        let refused = exchange(&mut client_writer, &mut responses, resolve.clone()).await?;
+        assert_eq!(refused["error"]["code"], json!(-32001));
+        let wrong = exchange(
+            &mut client_writer,
+            &mut responses,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "authenticate",
+                "params": {"token": "guess"},
+            }),
+        )
+        .await?;
+        assert_eq!(wrong["error"]["code"], json!(-32001));
+        let accepted = exchange(
+            &mut client_writer,
+            &mut responses,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "authenticate",
+                "params": {"token": "s3cret"},
+            }),
+        )
+        .await?;
+        assert_eq!(accepted["result"]["principal"], json!("ci-bot"));
+        let resolved = exchange(&mut client_writer, &mut responses, resolve).await?;
+        assert_eq!(resolved["id"], json!(2));
+
+        drop(client_writer);
+        connection.await??;
+        let (store, published) = store_owner.await?;
+        assert!(matches!(
+            published.as_slice(),
+            [IpcMessage { event: IpcEvent::ApprovalResolved(event), .. }]
+                if event.action_id == "act-1"
+        ));
+        assert_eq!(
+            store
+                .approval("act-1")
+                .and_then(|approval| approval.approver.clone()),
+            Some(ApproverIdentity::remote_principal("ci-bot"))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use egui::Color32;
+
+/// Accent colors handed out to sessions, readable on both the dark and the light theme.
+pub const SESSION_PALETTE: [Color32; 8] = [
+    Color32::from_rgb(97, 175, 239),
+    Color32::from_rgb(229, 152, 102),
+    Color32::from_rgb(152, 195, 121),
+    Color32::from_rgb(198, 120, 221),
+    Color32::from_rgb(224, 108, 117),
+    Color32::from_rgb(86, 182, 194),
+    Color32::from_rgb(209, 154, 102),
+    Color32::from_rgb(171, 178, 191),
+];
+
+/// The accent of `session_id`: the palette slot its id hashes to, or the next one no session
+/// in `taken` uses, so the same id keeps its color across runs while parallel sessions get
+/// different ones until the palette runs out.
+pub(crate) fn pick_session_color<'a>(
+    session_id: &str,
+    taken: impl IntoIterator<Item = &'a Color32>,
+) -> Color32 {
+    let taken: Vec<Color32> = taken.into_iter().copied().collect();
+    let start = fnv1a(session_id) as usize % SESSION_PALETTE.len();
+    (0..SESSION_PALETTE.len())
+        .map(|offset| SESSION_PALETTE[(start + offset) % SESSION_PALETTE.len()])
+        .find(|color| !taken.contains(color))
+        .unwrap_or(SESSION_PALETTE[start])
+}
+
+/// Stable across platforms and releases, unlike `DefaultHasher`.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::SESSION_PALETTE;
+    use super::pick_session_color;
+
+    #[test]
+    fn same_id_same_color_and_parallel_sessions_differ() {
+        let first = pick_session_color("sess-1", []);
+        assert_eq!(pick_session_color("sess-1", []), first);
+
+        let mut taken = Vec::new();
+        for index in 0..SESSION_PALETTE.len() {
+            let color = pick_session_color(&format!("sess-{index}"), &taken);
+            assert!(!taken.contains(&color));
+            taken.push(color);
+        }
+        assert!(SESSION_PALETTE.contains(&pick_session_color("sess-extra", &taken)));
+    }
+}
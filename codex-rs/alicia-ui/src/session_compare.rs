@@ -0,0 +1,245 @@
+use codex_alicia_core::ActionKind;
+
+use crate::CommandLifecycle;
+use crate::UiEventStore;
+use crate::approval_decision_name;
+use crate::policy_decision_name;
+use crate::result_status_name;
+
+/// What a `ComparisonRow` compares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComparisonField {
+    Command,
+    Cwd,
+    ExitCode,
+    DurationMs,
+    OutputLines,
+    /// Outcome of the audited action on `target`, as `policy/approval/result`.
+    Action {
+        action_kind: ActionKind,
+        target: String,
+    },
+}
+
+/// One field of both sessions; `None` where the session has no value, e.g. an exit code
+/// while still running or an action only the other session took.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComparisonRow {
+    pub field: ComparisonField,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl ComparisonRow {
+    pub fn differs(&self) -> bool {
+        self.left != self.right
+    }
+}
+
+/// Two sessions side by side, e.g. a failed run and its retry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionComparison {
+    pub left_session_id: String,
+    pub right_session_id: String,
+    pub rows: Vec<ComparisonRow>,
+}
+
+impl SessionComparison {
+    pub fn differences(&self) -> impl Iterator<Item = &ComparisonRow> {
+        self.rows.iter().filter(|row| row.differs())
+    }
+}
+
+/// Compares the command, outcome and audited actions of two terminal sessions. Actions are
+/// matched by kind and target; when one repeats, its last outcome counts. `None` when the
+/// store does not know either session.
+pub fn compare_sessions(
+    store: &UiEventStore,
+    left_session_id: &str,
+    right_session_id: &str,
+) -> Option<SessionComparison> {
+    let left = store.terminal_session(left_session_id)?;
+    let right = store.terminal_session(right_session_id)?;
+    let finished = |lifecycle: CommandLifecycle| match lifecycle {
+        CommandLifecycle::Running => (None, None),
+        CommandLifecycle::Finished {
+            exit_code,
+            duration_ms,
+        } => (Some(exit_code.to_string()), Some(duration_ms.to_string())),
+    };
+    let (left_exit_code, left_duration) = finished(left.lifecycle);
+    let (right_exit_code, right_duration) = finished(right.lifecycle);
+
+    let mut rows = vec![
+        ComparisonRow {
+            field: ComparisonField::Command,
+            left: Some(left.command.join(" ")),
+            right: Some(right.command.join(" ")),
+        },
+        ComparisonRow {
+            field: ComparisonField::Cwd,
+            left: Some(left.cwd.clone()),
+            right: Some(right.cwd.clone()),
+        },
+        ComparisonRow {
+            field: ComparisonField::ExitCode,
+            left: left_exit_code,
+            right: right_exit_code,
+        },
+        ComparisonRow {
+            field: ComparisonField::DurationMs,
+            left: left_duration,
+            right: right_duration,
+        },
+        ComparisonRow {
+            field: ComparisonField::OutputLines,
+            left: Some(left.visible_lines().len().to_string()),
+            right: Some(right.visible_lines().len().to_string()),
+        },
+    ];
+
+    let left_actions = action_outcomes(store, left_session_id);
+    let right_actions = action_outcomes(store, right_session_id);
+    let mut keys: Vec<&(ActionKind, String)> = Vec::new();
+    for (key, _) in left_actions.iter().chain(&right_actions) {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    let outcome_of = |actions: &[((ActionKind, String), String)], key: &(ActionKind, String)| {
+        actions
+            .iter()
+            .rev()
+            .find(|(candidate, _)| candidate == key)
+            .map(|(_, outcome)| outcome.clone())
+    };
+    for key in keys {
+        rows.push(ComparisonRow {
+            field: ComparisonField::Action {
+                action_kind: key.0,
+                target: key.1.clone(),
+            },
+            left: outcome_of(&left_actions, key),
+            right: outcome_of(&right_actions, key),
+        });
+    }
+
+    Some(SessionComparison {
+        left_session_id: left_session_id.to_string(),
+        right_session_id: right_session_id.to_string(),
+        rows,
+    })
+}
+
+fn action_outcomes(store: &UiEventStore, session_id: &str) -> Vec<((ActionKind, String), String)> {
+    store
+        .audit_records()
+        .iter()
+        .filter(|record| record.session_id == session_id)
+        .map(|record| {
+            (
+                (record.action_kind, record.target.clone()),
+                format!(
+                    "{}/{}/{}",
+                    policy_decision_name(record.policy_decision),
+                    approval_decision_name(record.approval_decision),
+                    result_status_name(record.result_status)
+                ),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::ActionKind;
+    use codex_alicia_core::ApprovalDecision;
+    use codex_alicia_core::AuditRecord;
+    use codex_alicia_core::IpcEvent;
+    use codex_alicia_core::IpcMessage;
+    use codex_alicia_core::PermissionProfile;
+    use codex_alicia_core::PolicyDecision;
+    use codex_alicia_core::ResultStatus;
+    use codex_alicia_core::ipc::CommandFinished;
+    use codex_alicia_core::ipc::CommandStarted;
+    use pretty_assertions::assert_eq;
+
+    use super::ComparisonField;
+    use super::ComparisonRow;
+    use super::compare_sessions;
+    use crate::UiEventStore;
+
+    #[test]
+    fn compares_outcomes_and_audited_actions_of_two_runs() {
+        let mut store = UiEventStore::default();
+        for (session_id, exit_code, approval) in [
+            ("sess-1", 101, ApprovalDecision::Denied),
+            ("sess-2", 0, ApprovalDecision::Approved),
+        ] {
+            store.push(IpcMessage::new(IpcEvent::CommandStarted(CommandStarted {
+                command_id: session_id.to_string(),
+                command: vec!["cargo".to_string(), "test".to_string()],
+                cwd: "/ws".to_string(),
+            })));
+            store.push(IpcMessage::new(IpcEvent::CommandFinished(
+                CommandFinished {
+                    command_id: session_id.to_string(),
+                    exit_code,
+                    duration_ms: 1_000,
+                },
+            )));
+            store.add_audit_record(AuditRecord::new(
+                session_id,
+                ActionKind::NetworkAccess,
+                "crates.io",
+                PermissionProfile::ReadWriteWithApproval,
+                PolicyDecision::RequireApproval,
+                approval,
+                ResultStatus::Succeeded,
+                5,
+            ));
+        }
+        store.add_audit_record(AuditRecord::new(
+            "sess-2",
+            ActionKind::WriteFile,
+            "Cargo.lock",
+            PermissionProfile::ReadWriteWithApproval,
+            PolicyDecision::Allow,
+            ApprovalDecision::NotRequired,
+            ResultStatus::Succeeded,
+            1,
+        ));
+
+        let Some(comparison) = compare_sessions(&store, "sess-1", "sess-2") else {
+            panic!("both sessions are known");
+        };
+        let differences: Vec<&ComparisonRow> = comparison.differences().collect();
+        assert_eq!(
+            differences,
+            vec![
+                &ComparisonRow {
+                    field: ComparisonField::ExitCode,
+                    left: Some("101".to_string()),
+                    right: Some("0".to_string()),
+                },
+                &ComparisonRow {
+                    field: ComparisonField::Action {
+                        action_kind: ActionKind::NetworkAccess,
+                        target: "crates.io".to_string(),
+                    },
+                    left: Some("require_approval/denied/succeeded".to_string()),
+                    right: Some("require_approval/approved/succeeded".to_string()),
+                },
+                &ComparisonRow {
+                    field: ComparisonField::Action {
+                        action_kind: ActionKind::WriteFile,
+                        target: "Cargo.lock".to_string(),
+                    },
+                    left: None,
+                    right: Some("allow/not_required/succeeded".to_string()),
+                },
+            ]
+        );
+        assert_eq!(compare_sessions(&store, "sess-1", "sess-9"), None);
+    }
+}
@@ -0,0 +1,444 @@
+use std::collections::BTreeSet;
+
+use codex_alicia_core::AuditRecord;
+
+use crate::ChatMessage;
+use crate::ChatRole;
+use crate::CommandLifecycle;
+use crate::PatchHunkDecision;
+use crate::PatchPreviewState;
+use crate::UiEventStore;
+use crate::action_kind_name;
+use crate::approval_decision_name;
+use crate::policy_decision_name;
+use crate::result_status_name;
+
+/// Only the tail of each terminal is embedded; full output stays in the session itself.
+const MAX_TERMINAL_EXCERPT_LINES: usize = 200;
+
+const REPORT_STYLE: &str = "\
+body{font-family:system-ui,sans-serif;margin:2rem auto;max-width:72rem;color:#1f2328;line-height:1.45}\
+h1,h2{border-bottom:1px solid #d0d7de;padding-bottom:.3rem}\
+table{border-collapse:collapse;width:100%;font-size:.9rem}\
+th,td{border:1px solid #d0d7de;padding:.3rem .5rem;text-align:left;vertical-align:top}\
+th{background:#f6f8fa}\
+pre{background:#f6f8fa;padding:.6rem;overflow-x:auto;white-space:pre-wrap;word-break:break-word}\
+.message{border-left:4px solid #d0d7de;padding:.2rem .8rem;margin:.8rem 0}\
+.message.user{border-color:#0969da}.message.agent{border-color:#8250df}\
+.role{font-weight:600;font-size:.85rem;text-transform:uppercase}\
+.diff{font-family:ui-monospace,monospace;padding:0;background:#fff;border:1px solid #d0d7de}\
+.diff span{display:block;padding:0 .6rem}\
+.diff .add{background:#dafbe1}.diff .del{background:#ffebe9}.diff .meta{background:#ddf4ff}\
+.badge{display:inline-block;border-radius:1rem;padding:0 .5rem;font-size:.8rem;background:#eaeef2}\
+.badge.approved,.badge.succeeded{background:#dafbe1}\
+.badge.rejected,.badge.blocked,.badge.failed{background:#ffebe9}\
+@media print{body{margin:0;max-width:none;font-size:10pt}h2,h3,h4{break-after:avoid}tr,.message{break-inside:avoid}\
+pre{overflow-x:visible}.diff span{print-color-adjust:exact;-webkit-print-color-adjust:exact}}";
+
+/// Self-contained HTML page with the conversation, terminal excerpts, diffs with their hunk
+/// decisions and the audit table, for attaching to a code review or incident ticket. Styles
+/// are inlined and nothing is loaded from the network.
+pub fn session_report_html(store: &UiEventStore, generated_at_unix_s: i64) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"pt-BR\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Relatorio de sessao AlicIA</title>\n");
+    html.push_str(&format!("<style>{REPORT_STYLE}</style>\n</head>\n<body>\n"));
+    html.push_str("<h1>Relatorio de sessao AlicIA</h1>\n");
+    html.push_str(&format!(
+        "<p>Gerado em (unix): {generated_at_unix_s}<br>Perfil: {}</p>\n",
+        store.profile().name
+    ));
+
+    push_conversation(&mut html, store.chat_messages());
+    push_terminals(&mut html, store);
+    push_diffs(&mut html, &store.diff_previews());
+    push_audit_table(&mut html, store.audit_records());
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Report of one task: the user prompt `prompt_message_id`, the agent replies up to the next
+/// prompt, the commands they ran with exit codes, their diffs with hunk decisions and the
+/// audit records of both, for reviewers who do not run AlicIA. Printing it from a browser
+/// gives the PDF. `None` when the message is not a user prompt.
+pub fn task_report_html(
+    store: &UiEventStore,
+    prompt_message_id: u64,
+    generated_at_unix_s: i64,
+) -> Option<String> {
+    let messages = store.chat_messages();
+    let start = messages
+        .iter()
+        .position(|message| message.id == prompt_message_id && message.role == ChatRole::User)?;
+    let end = messages[start + 1..]
+        .iter()
+        .position(|message| message.role == ChatRole::User)
+        .map_or(messages.len(), |offset| start + 1 + offset);
+    let task = &messages[start..end];
+    let tool_calls = task.iter().flat_map(|message| &message.tool_calls);
+    let session_ids: BTreeSet<&str> = tool_calls
+        .clone()
+        .filter_map(|tool_call| tool_call.session_id.as_deref())
+        .collect();
+    let action_ids: BTreeSet<&str> = tool_calls
+        .filter_map(|tool_call| tool_call.action_id.as_deref())
+        .collect();
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"pt-BR\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!(
+        "<title>Relatorio da tarefa {prompt_message_id}</title>\n"
+    ));
+    html.push_str(&format!("<style>{REPORT_STYLE}</style>\n</head>\n<body>\n"));
+    html.push_str(&format!(
+        "<h1>Relatorio da tarefa {prompt_message_id}</h1>\n"
+    ));
+    html.push_str(&format!(
+        "<p>Gerado em (unix): {generated_at_unix_s}<br>Perfil: {}</p>\n",
+        store.profile().name
+    ));
+
+    push_conversation(&mut html, task);
+    push_commands(&mut html, store, &session_ids);
+    let previews: Vec<&PatchPreviewState> = store
+        .diff_previews()
+        .into_iter()
+        .filter(|preview| action_ids.contains(preview.action_id.as_str()))
+        .collect();
+    push_diffs(&mut html, &previews);
+    let records: Vec<AuditRecord> = store
+        .audit_records()
+        .iter()
+        .filter(|record| {
+            session_ids.contains(record.session_id.as_str())
+                || record
+                    .action_id
+                    .as_deref()
+                    .is_some_and(|action_id| action_ids.contains(action_id))
+        })
+        .cloned()
+        .collect();
+    push_audit_table(&mut html, &records);
+
+    html.push_str("</body>\n</html>\n");
+    Some(html)
+}
+
+fn push_conversation(html: &mut String, messages: &[ChatMessage]) {
+    html.push_str("<h2>Conversa</h2>\n");
+    if messages.is_empty() {
+        html.push_str("<p>Nenhuma mensagem.</p>\n");
+        return;
+    }
+    for message in messages {
+        let (class, label) = match message.role {
+            ChatRole::User => ("user", "Usuario"),
+            ChatRole::Agent => ("agent", "Agente"),
+            ChatRole::System => ("system", "Sistema"),
+        };
+        html.push_str(&format!(
+            "<div class=\"message {class}\"><div class=\"role\">{label}</div>\n<pre>{}</pre>\n",
+            escape_html(&message.content)
+        ));
+        if !message.attachments.is_empty() {
+            let paths: Vec<String> = message
+                .attachments
+                .iter()
+                .map(|attachment| format!("<code>{}</code>", escape_html(&attachment.path)))
+                .collect();
+            html.push_str(&format!("<p>Contexto: {}</p>\n", paths.join(", ")));
+        }
+        html.push_str("</div>\n");
+    }
+}
+
+fn push_terminals(html: &mut String, store: &UiEventStore) {
+    html.push_str("<h2>Terminal</h2>\n");
+    if store.terminal_session_ids().is_empty() {
+        html.push_str("<p>Nenhuma sessao.</p>\n");
+        return;
+    }
+    for session in store
+        .terminal_session_ids()
+        .iter()
+        .filter_map(|session_id| store.terminal_session(session_id))
+    {
+        let status = match session.lifecycle {
+            CommandLifecycle::Running => "em execucao".to_string(),
+            CommandLifecycle::Finished {
+                exit_code,
+                duration_ms,
+            } => format!("exit_code={exit_code}, {duration_ms} ms"),
+        };
+        html.push_str(&format!(
+            "<h3>{}</h3>\n<p><code>{}</code> em <code>{}</code> ({status})</p>\n",
+            escape_html(&session.session_id),
+            escape_html(&session.command.join(" ")),
+            escape_html(&session.cwd)
+        ));
+        let lines = session.visible_lines();
+        let skipped = lines.len().saturating_sub(MAX_TERMINAL_EXCERPT_LINES);
+        if skipped > 0 {
+            html.push_str(&format!("<p>{skipped} linhas anteriores omitidas.</p>\n"));
+        }
+        html.push_str(&format!(
+            "<pre>{}</pre>\n",
+            escape_html(&lines[skipped..].join("\n"))
+        ));
+    }
+}
+
+fn push_commands(html: &mut String, store: &UiEventStore, session_ids: &BTreeSet<&str>) {
+    html.push_str("<h2>Comandos</h2>\n");
+    if session_ids.is_empty() {
+        html.push_str("<p>Nenhum comando.</p>\n");
+        return;
+    }
+    html.push_str("<table>\n<tr><th>Sessao</th><th>Comando</th><th>Diretorio</th><th>Exit code</th><th>Duracao</th></tr>\n");
+    for session_id in session_ids {
+        let Some(session) = store.terminal_session(session_id) else {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td colspan=\"4\">nao iniciado</td></tr>\n",
+                escape_html(session_id)
+            ));
+            continue;
+        };
+        let (exit_code, duration) = match session.lifecycle {
+            CommandLifecycle::Running => ("em execucao".to_string(), String::new()),
+            CommandLifecycle::Finished {
+                exit_code,
+                duration_ms,
+            } => (exit_code.to_string(), format!("{duration_ms} ms")),
+        };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td><code>{}</code></td><td><code>{}</code></td><td>{exit_code}</td><td>{duration}</td></tr>\n",
+            escape_html(session_id),
+            escape_html(&session.command.join(" ")),
+            escape_html(&session.cwd)
+        ));
+    }
+    html.push_str("</table>\n");
+}
+
+fn push_diffs(html: &mut String, previews: &[&PatchPreviewState]) {
+    html.push_str("<h2>Diffs</h2>\n");
+    if previews.is_empty() {
+        html.push_str("<p>Nenhum diff.</p>\n");
+        return;
+    }
+    for preview in previews {
+        let state = if preview.applied {
+            "aplicado"
+        } else {
+            "nao aplicado"
+        };
+        html.push_str(&format!(
+            "<h3>{} <span class=\"badge\">{state}</span></h3>\n",
+            escape_html(&preview.action_id)
+        ));
+        for file in &preview.file_previews {
+            html.push_str(&format!("<h4>{}</h4>\n", escape_html(&file.file_path)));
+            for hunk in &file.hunks {
+                let (class, label) = match hunk.decision {
+                    PatchHunkDecision::Pending => ("pending", "pendente"),
+                    PatchHunkDecision::Approved => ("approved", "aprovado"),
+                    PatchHunkDecision::Rejected => ("rejected", "rejeitado"),
+                };
+                html.push_str(&format!(
+                    "<p><span class=\"badge {class}\">{label}</span> +{} -{}</p>\n<pre class=\"diff\"><span class=\"meta\">{}</span>",
+                    hunk.added_lines,
+                    hunk.removed_lines,
+                    escape_html(&hunk.header)
+                ));
+                for line in &hunk.lines {
+                    let class = match line.chars().next() {
+                        Some('+') => "add",
+                        Some('-') => "del",
+                        _ => "ctx",
+                    };
+                    html.push_str(&format!(
+                        "<span class=\"{class}\">{}</span>",
+                        escape_html(line)
+                    ));
+                }
+                html.push_str("</pre>\n");
+            }
+        }
+    }
+}
+
+fn push_audit_table(html: &mut String, records: &[AuditRecord]) {
+    html.push_str("<h2>Auditoria</h2>\n");
+    if records.is_empty() {
+        html.push_str("<p>Nenhum registro.</p>\n");
+        return;
+    }
+    html.push_str("<table>\n<tr><th>Horario (unix)</th><th>Sessao</th><th>Acao</th><th>Alvo</th><th>Politica</th><th>Aprovacao</th><th>Resultado</th><th>Aprovador</th></tr>\n");
+    for record in records {
+        let result = result_status_name(record.result_status);
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td><td><span class=\"badge {result}\">{result}</span></td><td>{}</td></tr>\n",
+            record.timestamp,
+            escape_html(&record.session_id),
+            action_kind_name(record.action_kind),
+            escape_html(&record.target),
+            policy_decision_name(record.policy_decision),
+            approval_decision_name(record.approval_decision),
+            record
+                .approver
+                .as_ref()
+                .map(|approver| escape_html(&approver.name))
+                .unwrap_or_default()
+        ));
+    }
+    html.push_str("</table>\n");
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::ActionKind;
+    use codex_alicia_core::ApprovalDecision;
+    use codex_alicia_core::AuditRecord;
+    use codex_alicia_core::IpcEvent;
+    use codex_alicia_core::IpcMessage;
+    use codex_alicia_core::PermissionProfile;
+    use codex_alicia_core::PolicyDecision;
+    use codex_alicia_core::ResultStatus;
+    use codex_alicia_core::ipc::CommandFinished;
+    use codex_alicia_core::ipc::CommandOutputChunk;
+    use codex_alicia_core::ipc::CommandOutputStream;
+    use codex_alicia_core::ipc::CommandStarted;
+    use codex_alicia_core::ipc::PatchPreviewReady;
+    use codex_alicia_core::ipc::ToolCallStarted;
+
+    use super::session_report_html;
+    use super::task_report_html;
+    use crate::ChatRole;
+    use crate::UiEventStore;
+
+    #[test]
+    fn report_embeds_conversation_terminal_diffs_and_audit()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let mut store = UiEventStore::default();
+        store.push_chat_message(ChatRole::User, "corrija <main>");
+        store.push(IpcMessage::new(IpcEvent::CommandStarted(CommandStarted {
+            command_id: "sess-1".to_string(),
+            command: vec!["cargo".to_string(), "test".to_string()],
+            cwd: "/ws".to_string(),
+        })));
+        store.push(IpcMessage::new(IpcEvent::CommandOutputChunk(
+            CommandOutputChunk {
+                command_id: "sess-1".to_string(),
+                stream: CommandOutputStream::Stdout,
+                chunk: "test result: ok\n".to_string(),
+            },
+        )));
+        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+            PatchPreviewReady {
+                action_id: "act-1".to_string(),
+                files: vec!["src/main.rs".to_string()],
+            },
+        )));
+        store.attach_patch_file_diff(
+            "act-1",
+            "src/main.rs",
+            "@@ -1,1 +1,1 @@\n-old & busted\n+new\n",
+        )?;
+        store.approve_patch_hunk("act-1", "src/main.rs", "hunk-1")?;
+        store.add_audit_record(AuditRecord::new(
+            "sess-1",
+            ActionKind::ExecuteCommand,
+            "cargo test",
+            PermissionProfile::ReadWriteWithApproval,
+            PolicyDecision::RequireApproval,
+            ApprovalDecision::Approved,
+            ResultStatus::Succeeded,
+            12,
+        ));
+
+        let html = session_report_html(&store, 1_700_000_000);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("http"));
+        assert!(html.contains("<pre>corrija &lt;main&gt;</pre>"));
+        assert!(html.contains("<code>cargo test</code> em <code>/ws</code>"));
+        assert!(html.contains("test result: ok"));
+        assert!(html.contains("<span class=\"badge approved\">aprovado</span> +1 -1"));
+        assert!(html.contains("<span class=\"del\">-old &amp; busted</span>"));
+        assert!(html.contains("<td>execute_command</td><td><code>cargo test</code></td>"));
+        Ok(())
+    }
+    #[test]
+    fn task_report_covers_only_the_commands_and_audit_of_its_prompt() {
+        let mut store = UiEventStore::default();
+        let run_task = |store: &mut UiEventStore, prompt: &str, session_id: &str| {
+            let prompt_id = store.push_chat_message(ChatRole::User, prompt);
+            store.push(IpcMessage::new(IpcEvent::ToolCallStarted(
+                ToolCallStarted {
+                    call_id: format!("call-{session_id}"),
+                    message_id: format!("msg-{session_id}"),
+                    name: "run_command".to_string(),
+                    arguments: "cargo test".to_string(),
+                    session_id: Some(session_id.to_string()),
+                    action_id: None,
+                },
+            )));
+            store.push(IpcMessage::new(IpcEvent::CommandStarted(CommandStarted {
+                command_id: session_id.to_string(),
+                command: vec!["cargo".to_string(), "test".to_string()],
+                cwd: "/ws".to_string(),
+            })));
+            store.push(IpcMessage::new(IpcEvent::CommandFinished(
+                CommandFinished {
+                    command_id: session_id.to_string(),
+                    exit_code: 101,
+                    duration_ms: 40,
+                },
+            )));
+            store.add_audit_record(AuditRecord::new(
+                session_id,
+                ActionKind::ExecuteCommand,
+                format!("cargo test {session_id}"),
+                PermissionProfile::ReadWriteWithApproval,
+                PolicyDecision::Allow,
+                ApprovalDecision::NotRequired,
+                ResultStatus::Failed,
+                40,
+            ));
+            prompt_id
+        };
+        let first = run_task(&mut store, "rode os testes", "sess-1");
+        let second = run_task(&mut store, "corrija a falha", "sess-2");
+
+        let Some(html) = task_report_html(&store, first, 1_700_000_000) else {
+            panic!("a user prompt has a task report");
+        };
+        assert!(html.contains("<h1>Relatorio da tarefa 0</h1>"));
+        assert!(html.contains("<pre>rode os testes</pre>"));
+        assert!(!html.contains("corrija a falha"));
+        assert!(html.contains("<tr><td>sess-1</td><td><code>cargo test</code></td><td><code>/ws</code></td><td>101</td><td>40 ms</td></tr>"));
+        assert!(html.contains("<code>cargo test sess-1</code>"));
+        assert!(!html.contains("sess-2"));
+        assert!(html.contains("@media print"));
+
+        assert!(task_report_html(&store, second, 0).is_some_and(|html| html.contains("sess-2")));
+        assert_eq!(task_report_html(&store, first + 1, 0), None);
+    }
+}
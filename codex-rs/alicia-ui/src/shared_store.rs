@@ -0,0 +1,69 @@
+use std::sync::Arc;
+use std::sync::PoisonError;
+use std::sync::RwLock;
+use std::sync::RwLockReadGuard;
+use std::sync::RwLockWriteGuard;
+
+use crate::UiEventStore;
+
+/// Cloneable handle to one `UiEventStore`, so the egui view, a TUI and the JSON-RPC server
+/// can observe the store the runtime feeds. Guards block the other frontends while alive:
+/// copy what you need out of them instead of holding them across an `.await`.
+#[derive(Debug, Clone, Default)]
+pub struct SharedUiEventStore {
+    inner: Arc<RwLock<UiEventStore>>,
+}
+
+impl SharedUiEventStore {
+    pub fn new(store: UiEventStore) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(store)),
+        }
+    }
+
+    /// A frontend that panicked while writing leaves the store as it was; the others keep
+    /// using it rather than failing too.
+    pub fn read(&self) -> RwLockReadGuard<'_, UiEventStore> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, UiEventStore> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::IpcEvent;
+    use codex_alicia_core::IpcMessage;
+    use codex_alicia_core::ipc::ApprovalRequested;
+    use pretty_assertions::assert_eq;
+
+    use super::SharedUiEventStore;
+
+    #[test]
+    fn clones_observe_the_same_store() {
+        let store = SharedUiEventStore::default();
+        let frontends: Vec<_> = (0..4)
+            .map(|index| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    store
+                        .write()
+                        .push(IpcMessage::new(IpcEvent::ApprovalRequested(
+                            ApprovalRequested {
+                                action_id: format!("act-{index}"),
+                                summary: "aprovacao".to_string(),
+                                expires_at_unix_s: i64::MAX,
+                            },
+                        )));
+                })
+            })
+            .collect();
+        for frontend in frontends {
+            assert!(frontend.join().is_ok());
+        }
+
+        assert_eq!(store.read().pending_approval_count(), 4);
+    }
+}
@@ -0,0 +1,322 @@
+use std::time::Instant;
+
+/// `OSC 7 ; file://host/path ST`, sent by shells with integration enabled after every `cd`.
+const OSC7_PREFIX: &str = "\u{1b}]7;";
+/// `OSC 133 ; <mark> ST` semantic prompt markers: `A` prompt start, `B` command line start,
+/// `C` command output start and `D ; <exit code>` command finished.
+const OSC133_PREFIX: &str = "\u{1b}]133;";
+const BEL: char = '\u{7}';
+const ESC: char = '\u{1b}';
+const BACKSPACE: char = '\u{8}';
+const STRING_TERMINATOR: &str = "\u{1b}\\";
+/// Echo kept while a command line is typed; longer lines are cut.
+const MAX_COMMAND_LINE_BYTES: usize = 4_096;
+
+/// Makes bash mark its prompts with OSC 133: `PROMPT_COMMAND` reports the last exit code
+/// and the prompt start, appends the command line marker to `PS1` (after `.bashrc` set it)
+/// and `PS0` marks where the output starts.
+const BASH_PROMPT_COMMAND: &str = r#"printf '\033]133;D;%s\007\033]133;A\007' "$?"; [[ $PS1 == *'133;B'* ]] || PS1="$PS1"'\[\e]133;B\a\]'"#;
+const BASH_PS0: &str = r"\e]133;C\a";
+
+/// A command run inside a long-lived shell session, delimited by OSC 133 markers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellCommandRun {
+    /// Command line as echoed after the prompt; empty when the shell sent no `B` marker.
+    pub command: String,
+    /// `None` when the next prompt came without a `D` marker reporting it.
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum MarkerPhase {
+    #[default]
+    Idle,
+    Typing,
+    Running,
+}
+
+/// Follows the OSC 133 markers of one session across output chunks.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CommandMarkerTracker {
+    phase: MarkerPhase,
+    command_line: String,
+    started_at: Option<Instant>,
+}
+
+impl CommandMarkerTracker {
+    /// Output with its OSC 133 markers removed, plus the commands they finished. A marker
+    /// split across chunks is left in the output.
+    pub(crate) fn feed(&mut self, text: &str, now: Instant) -> (String, Vec<ShellCommandRun>) {
+        let mut output = String::with_capacity(text.len());
+        let mut finished = Vec::new();
+        let mut rest = text;
+        while let Some(start) = rest.find(OSC133_PREFIX) {
+            let body = &rest[start + OSC133_PREFIX.len()..];
+            let Some((end, terminator_len)) = osc_end(body) else {
+                break;
+            };
+            self.observe(&rest[..start]);
+            output.push_str(&rest[..start]);
+            let mut params = body[..end].split(';');
+            match params.next() {
+                Some("A") => {
+                    // A new prompt without `D`: the shell never reported the exit code.
+                    if self.phase == MarkerPhase::Running {
+                        finished.push(self.finish(None, now));
+                    }
+                    self.phase = MarkerPhase::Idle;
+                }
+                Some("B") => {
+                    self.command_line.clear();
+                    self.phase = MarkerPhase::Typing;
+                }
+                Some("C") => {
+                    if self.phase != MarkerPhase::Typing {
+                        self.command_line.clear();
+                    }
+                    self.started_at = Some(now);
+                    self.phase = MarkerPhase::Running;
+                }
+                Some("D") if self.phase == MarkerPhase::Running => {
+                    let exit_code = params.next().and_then(|code| code.trim().parse().ok());
+                    finished.push(self.finish(exit_code, now));
+                    self.phase = MarkerPhase::Idle;
+                }
+                _ => {}
+            }
+            rest = &body[end + terminator_len..];
+        }
+        self.observe(rest);
+        output.push_str(rest);
+        (output, finished)
+    }
+
+    fn observe(&mut self, text: &str) {
+        if self.phase == MarkerPhase::Typing
+            && self.command_line.len() + text.len() <= MAX_COMMAND_LINE_BYTES
+        {
+            self.command_line.push_str(text);
+        }
+    }
+
+    fn finish(&mut self, exit_code: Option<i32>, now: Instant) -> ShellCommandRun {
+        let duration = self
+            .started_at
+            .take()
+            .map(|started_at| now.saturating_duration_since(started_at))
+            .unwrap_or_default();
+        ShellCommandRun {
+            command: echoed_command_line(&std::mem::take(&mut self.command_line)),
+            exit_code,
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+        }
+    }
+}
+
+/// Environment that turns on OSC 133 markers for `shell`, when Alicia knows how to for it.
+pub(crate) fn shell_integration_env(shell: &str) -> Vec<(String, String)> {
+    let name = std::path::Path::new(shell)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    if name != "bash" {
+        return Vec::new();
+    }
+    vec![
+        (
+            "PROMPT_COMMAND".to_string(),
+            BASH_PROMPT_COMMAND.to_string(),
+        ),
+        ("PS0".to_string(), BASH_PS0.to_string()),
+    ]
+}
+
+/// Output with its OSC 7 sequences removed, plus the directory reported by the last one.
+/// A sequence split across chunks is left in the output.
+pub(crate) fn strip_osc7_cwd(text: &str) -> (String, Option<String>) {
+    let mut output = String::with_capacity(text.len());
+    let mut cwd = None;
+    let mut rest = text;
+    while let Some(start) = rest.find(OSC7_PREFIX) {
+        let body = &rest[start + OSC7_PREFIX.len()..];
+        let Some((end, terminator_len)) = osc_end(body) else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        if let Some(path) = file_url_path(&body[..end]) {
+            cwd = Some(path);
+        }
+        rest = &body[end + terminator_len..];
+    }
+    output.push_str(rest);
+    (output, cwd)
+}
+
+/// Whether `line`, the last thing a program printed, reads like a question waiting for an
+/// answer: it ends with `:`, `?` or a yes/no choice such as `(y/n)`.
+pub(crate) fn looks_like_input_prompt(line: &str) -> bool {
+    let line = line.trim_end().to_lowercase();
+    line.ends_with(':')
+        || line.ends_with('?')
+        || ["(y/n)", "[y/n]", "(yes/no)", "[yes/no]"]
+            .iter()
+            .any(|choice| line.ends_with(choice))
+}
+
+/// The typed command as the terminal echoed it: backspaces applied, escape sequences and
+/// other control characters dropped.
+fn echoed_command_line(echo: &str) -> String {
+    let mut line = String::with_capacity(echo.len());
+    let mut chars = echo.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            BACKSPACE => {
+                line.pop();
+            }
+            ESC => {
+                // CSI sequences end at their first letter; others are two characters long.
+                if chars.next() == Some('[') {
+                    for ch in chars.by_ref() {
+                        if ch.is_ascii_alphabetic() || ch == '~' {
+                            break;
+                        }
+                    }
+                }
+            }
+            ch if ch.is_control() => {}
+            ch => line.push(ch),
+        }
+    }
+    line.trim().to_string()
+}
+
+fn osc_end(body: &str) -> Option<(usize, usize)> {
+    let bel = body.find(BEL).map(|index| (index, BEL.len_utf8()));
+    let st = body
+        .find(STRING_TERMINATOR)
+        .map(|index| (index, STRING_TERMINATOR.len()));
+    match (bel, st) {
+        (Some(bel), Some(st)) => Some(if bel.0 < st.0 { bel } else { st }),
+        (bel, st) => bel.or(st),
+    }
+}
+
+/// Path of a `file://host/path` URL, percent-decoded. Windows drive paths lose the slash in
+/// front of the drive letter.
+fn file_url_path(url: &str) -> Option<String> {
+    let without_scheme = url.strip_prefix("file://")?;
+    let path = &without_scheme[without_scheme.find('/')?..];
+    let decoded = percent_decode(path);
+    let bytes = decoded.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'/' && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        return Some(decoded[1..].to_string());
+    }
+    Some(decoded)
+}
+
+fn percent_decode(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut input = text.bytes();
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let hex = [input.next(), input.next()];
+            if let [Some(high), Some(low)] = hex
+                && let Ok(hex) = std::str::from_utf8(&[high, low])
+                && let Ok(decoded) = u8::from_str_radix(hex, 16)
+            {
+                bytes.push(decoded);
+                continue;
+            }
+            bytes.push(byte);
+            bytes.extend(hex.into_iter().flatten());
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use super::CommandMarkerTracker;
+    use super::ShellCommandRun;
+    use super::looks_like_input_prompt;
+    use super::shell_integration_env;
+    use super::strip_osc7_cwd;
+
+    #[test]
+    fn reports_the_last_directory_and_strips_the_sequences() {
+        let (output, cwd) = strip_osc7_cwd(
+            "\u{1b}]7;file://host/tmp\u{7}$ cd meu%20dir\n\u{1b}]7;file://host/tmp/meu%20dir\u{1b}\\$ ",
+        );
+        assert_eq!(output, "$ cd meu%20dir\n$ ");
+        assert_eq!(cwd.as_deref(), Some("/tmp/meu dir"));
+
+        let (_, cwd) = strip_osc7_cwd("\u{1b}]7;file://pc/C:/Users/ana\u{7}");
+        assert_eq!(cwd.as_deref(), Some("C:/Users/ana"));
+
+        let (output, cwd) = strip_osc7_cwd("sem integracao\u{1b}]7;file://host/inc");
+        assert_eq!(output, "sem integracao\u{1b}]7;file://host/inc");
+        assert_eq!(cwd, None);
+    }
+
+    #[test]
+    fn recognizes_prompts_waiting_for_an_answer() {
+        assert!(looks_like_input_prompt("Password: "));
+        assert!(looks_like_input_prompt("Overwrite existing file?"));
+        assert!(looks_like_input_prompt("Continue [Y/n]"));
+        assert!(!looks_like_input_prompt("Compiling app v0.1.0"));
+        assert!(!looks_like_input_prompt("user@host:~$ "));
+    }
+
+    #[test]
+    fn segments_commands_between_osc133_markers() {
+        let mut tracker = CommandMarkerTracker::default();
+        let start = Instant::now();
+        let (output, finished) = tracker.feed("\u{1b}]133;A\u{7}$ \u{1b}]133;B\u{7}car", start);
+        assert_eq!(output, "$ car");
+        assert_eq!(finished, Vec::new());
+
+        let (_, finished) = tracker.feed("x\u{8}go test\r\n\u{1b}]133;C\u{7}", start);
+        assert_eq!(finished, Vec::new());
+        let (output, finished) = tracker.feed(
+            "ok\r\n\u{1b}]133;D;101\u{1b}\\\u{1b}]133;A\u{7}$ ",
+            start + Duration::from_millis(1_500),
+        );
+        assert_eq!(output, "ok\r\n$ ");
+        assert_eq!(
+            finished,
+            vec![ShellCommandRun {
+                command: "cargo test".to_string(),
+                exit_code: Some(101),
+                duration_ms: 1_500,
+            }]
+        );
+
+        let (_, finished) = tracker.feed("\u{1b}]133;C\u{7}\u{1b}]133;A\u{7}", start);
+        assert_eq!(
+            finished,
+            vec![ShellCommandRun {
+                command: String::new(),
+                exit_code: None,
+                duration_ms: 0,
+            }]
+        );
+        let (_, finished) = tracker.feed("\u{1b}]133;D;0\u{7}", start);
+        assert_eq!(finished, Vec::new());
+    }
+
+    #[test]
+    fn injects_markers_only_into_shells_it_knows() {
+        let env = shell_integration_env("/usr/bin/bash");
+        let names: Vec<&str> = env.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["PROMPT_COMMAND", "PS0"]);
+        assert_eq!(shell_integration_env("/bin/zsh"), Vec::new());
+    }
+}
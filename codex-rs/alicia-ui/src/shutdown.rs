@@ -0,0 +1,46 @@
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_alicia_core::IpcMessage;
+
+/// Ctrl-C, sent to running sessions before they are killed.
+pub(crate) const INTERRUPT_INPUT: [u8; 1] = [0x03];
+
+/// What `AliciaUiRuntime::shutdown` did, for the host app to log or show before exiting.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Sessions that ended after the interrupt.
+    pub interrupted_sessions: Vec<String>,
+    /// Sessions that ignored the interrupt and were killed.
+    pub killed_sessions: Vec<String>,
+    /// Sessions still running when the timeout ran out.
+    pub unfinished_sessions: Vec<String>,
+    pub expired_approvals: usize,
+    pub flushed_audit_records: usize,
+    pub snapshot_path: Option<PathBuf>,
+    /// Steps that failed; shutdown carries on past them.
+    pub errors: Vec<String>,
+}
+
+impl ShutdownReport {
+    /// Every session ended and every step succeeded.
+    pub fn is_clean(&self) -> bool {
+        self.unfinished_sessions.is_empty() && self.errors.is_empty()
+    }
+}
+
+/// Writes `events` as JSONL, one `IpcMessage` per line, replacing what `path` held.
+pub(crate) fn write_event_snapshot(path: &Path, events: &[IpcMessage]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for event in events {
+        serde_json::to_writer(&mut file, event)?;
+        file.write_all(b"\n")?;
+    }
+    file.flush()
+}
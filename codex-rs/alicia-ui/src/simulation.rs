@@ -0,0 +1,592 @@
+//! Scripted and seeded event streams for exercising `UiEventStore` without spawning
+//! processes. Time is virtual: approvals expire when the script advances the clock, never
+//! from the wall clock.
+
+use std::collections::BTreeSet;
+
+use codex_alicia_core::ActionKind;
+use codex_alicia_core::CommandOutputStream;
+use codex_alicia_core::IpcEvent;
+use codex_alicia_core::IpcMessage;
+use codex_alicia_core::ipc::ActionProposed;
+use codex_alicia_core::ipc::ApprovalRequested;
+use codex_alicia_core::ipc::CommandFinished;
+use codex_alicia_core::ipc::CommandOutputChunk;
+use codex_alicia_core::ipc::CommandStarted;
+use codex_alicia_core::ipc::PatchApplied;
+use codex_alicia_core::ipc::PatchPreviewReady;
+use thiserror::Error;
+
+use crate::ApprovalStatus;
+use crate::UiEventStore;
+
+/// 2025-01-01T00:00:00Z.
+pub const DEFAULT_SIMULATION_START_UNIX_S: i64 = 1_735_689_600;
+
+const ACTION_KINDS: [ActionKind; 5] = [
+    ActionKind::ReadFile,
+    ActionKind::WriteFile,
+    ActionKind::ExecuteCommand,
+    ActionKind::ApplyPatch,
+    ActionKind::NetworkAccess,
+];
+const SIMULATED_COMMANDS: [&str; 4] = ["cargo test", "npm run build", "git status", "ls -la"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimulationStep {
+    Event(IpcMessage),
+    /// Moves the virtual clock forward.
+    AdvanceTime {
+        seconds: i64,
+    },
+    /// Resolves a pending approval through the store, as a user click would. Approvals that
+    /// are no longer pending are skipped.
+    Approve(String),
+    Deny(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationConfig {
+    pub seed: u64,
+    pub steps: usize,
+    pub start_unix_s: i64,
+    pub approval_ttl_s: i64,
+    pub max_running_sessions: usize,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            steps: 200,
+            start_unix_s: DEFAULT_SIMULATION_START_UNIX_S,
+            approval_ttl_s: 300,
+            max_running_sessions: 3,
+        }
+    }
+}
+
+impl SimulationConfig {
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_steps(mut self, steps: usize) -> Self {
+        self.steps = steps;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulationScript {
+    pub start_unix_s: i64,
+    pub steps: Vec<SimulationStep>,
+}
+
+impl SimulationScript {
+    pub fn new(start_unix_s: i64) -> Self {
+        Self {
+            start_unix_s,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn event(mut self, event: IpcEvent) -> Self {
+        self.steps
+            .push(SimulationStep::Event(IpcMessage::new(event)));
+        self
+    }
+
+    pub fn advance_time(mut self, seconds: i64) -> Self {
+        self.steps.push(SimulationStep::AdvanceTime { seconds });
+        self
+    }
+
+    pub fn approve(mut self, action_id: impl Into<String>) -> Self {
+        self.steps.push(SimulationStep::Approve(action_id.into()));
+        self
+    }
+
+    pub fn deny(mut self, action_id: impl Into<String>) -> Self {
+        self.steps.push(SimulationStep::Deny(action_id.into()));
+        self
+    }
+
+    /// Realistic interleaving of sessions, approvals, patches and clock jumps. The same
+    /// config always produces the same script.
+    pub fn generate(config: &SimulationConfig) -> Self {
+        ScriptGenerator::new(config).generate()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invariant violated after step {step}: {message}")]
+pub struct InvariantViolation {
+    pub step: usize,
+    pub message: String,
+}
+
+/// Drives a store through scripts on a virtual clock, checking invariants after every step.
+/// Like the UI loop, each step ends with an expiration sweep at the current virtual time.
+#[derive(Debug)]
+pub struct Simulation {
+    store: UiEventStore,
+    now_unix_s: i64,
+    steps_run: usize,
+    applied_previews: BTreeSet<String>,
+}
+
+impl Simulation {
+    pub fn new(store: UiEventStore, start_unix_s: i64) -> Self {
+        Self {
+            store,
+            now_unix_s: start_unix_s,
+            steps_run: 0,
+            applied_previews: BTreeSet::new(),
+        }
+    }
+
+    /// Runs `script` against a default store.
+    pub fn run_script(script: &SimulationScript) -> Result<Self, InvariantViolation> {
+        let mut simulation = Self::new(UiEventStore::default(), script.start_unix_s);
+        simulation.run(script)?;
+        Ok(simulation)
+    }
+
+    pub fn run(&mut self, script: &SimulationScript) -> Result<(), InvariantViolation> {
+        for step in &script.steps {
+            self.apply(step);
+            self.steps_run += 1;
+            self.check_invariants()
+                .map_err(|message| InvariantViolation {
+                    step: self.steps_run,
+                    message,
+                })?;
+        }
+        Ok(())
+    }
+
+    pub fn apply(&mut self, step: &SimulationStep) {
+        match step {
+            SimulationStep::Event(message) => self.store.push(message.clone()),
+            SimulationStep::AdvanceTime { seconds } => {
+                self.now_unix_s = self.now_unix_s.saturating_add(*seconds);
+            }
+            SimulationStep::Approve(action_id) => {
+                let _ = self.store.approve(action_id);
+            }
+            SimulationStep::Deny(action_id) => {
+                let _ = self.store.deny(action_id);
+            }
+        }
+        self.store.expire_pending_approvals(self.now_unix_s);
+    }
+
+    /// Invariants every store must keep, whatever the event order. Returns the first broken
+    /// one.
+    pub fn check_invariants(&mut self) -> Result<(), String> {
+        check_store_invariants(&self.store, self.now_unix_s)?;
+        for preview in self.store.diff_previews() {
+            if preview.applied {
+                self.applied_previews.insert(preview.action_id.clone());
+            }
+        }
+        if let Some(action_id) = self.applied_previews.iter().find(|action_id| {
+            self.store
+                .diff_preview(action_id)
+                .is_none_or(|preview| !preview.applied)
+        }) {
+            return Err(format!(
+                "applied patch `{action_id}` reappeared as unapplied"
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn store(&self) -> &UiEventStore {
+        &self.store
+    }
+
+    pub fn into_store(self) -> UiEventStore {
+        self.store
+    }
+
+    pub fn now_unix_s(&self) -> i64 {
+        self.now_unix_s
+    }
+
+    pub fn steps_run(&self) -> usize {
+        self.steps_run
+    }
+}
+
+/// Stateless invariants of a store observed at `now_unix_s`: the pending queue only holds
+/// pending, unexpired approvals, the timeline is ordered and every listed terminal session
+/// exists.
+pub fn check_store_invariants(store: &UiEventStore, now_unix_s: i64) -> Result<(), String> {
+    let pending = store.pending_approvals();
+    if pending.len() != store.pending_approval_count() {
+        return Err(format!(
+            "pending count {} does not match queue length {}",
+            store.pending_approval_count(),
+            pending.len()
+        ));
+    }
+    if let Some(approval) = pending
+        .iter()
+        .find(|approval| approval.status != ApprovalStatus::Pending)
+    {
+        return Err(format!(
+            "approval `{}` is queued as pending but is {:?}",
+            approval.action_id, approval.status
+        ));
+    }
+    if let Some(approval) = pending
+        .iter()
+        .find(|approval| approval.expires_at_unix_s < now_unix_s)
+    {
+        return Err(format!(
+            "approval `{}` expired at {} but is still pending at {now_unix_s}",
+            approval.action_id, approval.expires_at_unix_s
+        ));
+    }
+    if let Some(pair) = store
+        .timeline()
+        .windows(2)
+        .find(|pair| pair[0].sequence >= pair[1].sequence)
+    {
+        return Err(format!(
+            "timeline sequence {} is followed by {}",
+            pair[0].sequence, pair[1].sequence
+        ));
+    }
+    if let Some(session_id) = store
+        .terminal_session_ids()
+        .iter()
+        .find(|session_id| store.terminal_session(session_id).is_none())
+    {
+        return Err(format!(
+            "terminal session `{session_id}` is listed but missing"
+        ));
+    }
+    Ok(())
+}
+
+/// Arbitrary scripts of up to `max_steps` steps for proptest: events from
+/// `codex_alicia_core::strategies`, approval clicks on the same action ids and clock jumps,
+/// starting at the strategies' epoch.
+#[cfg(any(test, feature = "proptest"))]
+pub fn simulation_script(
+    max_steps: usize,
+) -> impl proptest::strategy::Strategy<Value = SimulationScript> {
+    use codex_alicia_core::strategies;
+    use proptest::prelude::*;
+
+    let step = prop_oneof![
+        4 => strategies::ipc_event()
+            .prop_map(|event| SimulationStep::Event(IpcMessage::new(event))),
+        1 => (1..=strategies::MAX_STRATEGY_APPROVAL_TTL_S / 4)
+            .prop_map(|seconds| SimulationStep::AdvanceTime { seconds }),
+        1 => strategies::action_id().prop_map(SimulationStep::Approve),
+        1 => strategies::action_id().prop_map(SimulationStep::Deny),
+    ];
+    proptest::collection::vec(step, 1..=max_steps.max(1)).prop_map(|steps| SimulationScript {
+        start_unix_s: strategies::STRATEGY_START_UNIX_S,
+        steps,
+    })
+}
+
+/// splitmix64; small, seedable and stable across platforms and releases.
+#[derive(Debug, Clone)]
+struct SimulationRng(u64);
+
+impl SimulationRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut value = self.0;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        value ^ (value >> 31)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        if bound == 0 {
+            return 0;
+        }
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        items.get(self.below(items.len()))
+    }
+}
+
+struct ScriptGenerator {
+    config: SimulationConfig,
+    rng: SimulationRng,
+    script: SimulationScript,
+    next_id: u64,
+    running_sessions: Vec<String>,
+    requested_approvals: Vec<String>,
+    ready_patches: Vec<String>,
+}
+
+impl ScriptGenerator {
+    fn new(config: &SimulationConfig) -> Self {
+        Self {
+            config: *config,
+            rng: SimulationRng(config.seed),
+            script: SimulationScript::new(config.start_unix_s),
+            next_id: 0,
+            running_sessions: Vec::new(),
+            requested_approvals: Vec::new(),
+            ready_patches: Vec::new(),
+        }
+    }
+
+    fn generate(mut self) -> SimulationScript {
+        while self.script.steps.len() < self.config.steps {
+            match self.rng.below(10) {
+                0 | 1 => self.start_session(),
+                2 | 3 => self.output_chunk(),
+                4 => self.finish_session(),
+                5 => self.request_approval(),
+                6 => self.resolve_approval(),
+                7 => self.preview_patch(),
+                8 => self.apply_patch(),
+                _ => {
+                    let seconds = 1 + self.rng.below(self.config.approval_ttl_s.max(1) as usize);
+                    self.script.steps.push(SimulationStep::AdvanceTime {
+                        seconds: seconds as i64,
+                    });
+                }
+            }
+        }
+        self.script.steps.truncate(self.config.steps);
+        self.script
+    }
+
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{prefix}-{}", self.next_id)
+    }
+
+    fn push(&mut self, event: IpcEvent) {
+        self.script
+            .steps
+            .push(SimulationStep::Event(IpcMessage::new(event)));
+    }
+
+    /// Virtual time reached by the steps generated so far.
+    fn now_unix_s(&self) -> i64 {
+        self.script
+            .steps
+            .iter()
+            .fold(self.config.start_unix_s, |now, step| match step {
+                SimulationStep::AdvanceTime { seconds } => now.saturating_add(*seconds),
+                _ => now,
+            })
+    }
+
+    fn start_session(&mut self) {
+        if self.running_sessions.len() >= self.config.max_running_sessions {
+            return self.finish_session();
+        }
+        let session_id = self.next_id("sim-session");
+        let command = self
+            .rng
+            .pick(&SIMULATED_COMMANDS)
+            .copied()
+            .unwrap_or("true");
+        self.push(IpcEvent::CommandStarted(CommandStarted {
+            command_id: session_id.clone(),
+            command: command.split_whitespace().map(str::to_string).collect(),
+            cwd: "/sim/workspace".to_string(),
+        }));
+        self.running_sessions.push(session_id);
+    }
+
+    fn output_chunk(&mut self) {
+        let Some(session_id) = self.rng.pick(&self.running_sessions).cloned() else {
+            return self.start_session();
+        };
+        let stream = if self.rng.below(4) == 0 {
+            CommandOutputStream::Stderr
+        } else {
+            CommandOutputStream::Stdout
+        };
+        let line = self.rng.next_u64();
+        self.push(IpcEvent::CommandOutputChunk(CommandOutputChunk {
+            command_id: session_id,
+            stream,
+            chunk: format!("linha {line:x}\n"),
+        }));
+    }
+
+    fn finish_session(&mut self) {
+        if self.running_sessions.is_empty() {
+            return;
+        }
+        let index = self.rng.below(self.running_sessions.len());
+        let session_id = self.running_sessions.swap_remove(index);
+        let exit_code = if self.rng.below(5) == 0 { 1 } else { 0 };
+        let duration_ms = self.rng.next_u64() % 60_000;
+        self.push(IpcEvent::CommandFinished(CommandFinished {
+            command_id: session_id,
+            exit_code,
+            duration_ms,
+        }));
+    }
+
+    fn request_approval(&mut self) {
+        let action_id = self.next_id("sim-action");
+        let action_kind = self
+            .rng
+            .pick(&ACTION_KINDS)
+            .copied()
+            .unwrap_or(ActionKind::ExecuteCommand);
+        self.push(IpcEvent::ActionProposed(ActionProposed {
+            action_id: action_id.clone(),
+            action_kind,
+            target: format!("alvo {action_id}"),
+        }));
+        let expires_at_unix_s = self.now_unix_s().saturating_add(self.config.approval_ttl_s);
+        self.push(IpcEvent::ApprovalRequested(ApprovalRequested {
+            action_id: action_id.clone(),
+            summary: format!("Acao simulada {action_id}"),
+            expires_at_unix_s,
+        }));
+        self.requested_approvals.push(action_id);
+    }
+
+    fn resolve_approval(&mut self) {
+        if self.requested_approvals.is_empty() {
+            return self.request_approval();
+        }
+        let index = self.rng.below(self.requested_approvals.len());
+        let action_id = self.requested_approvals.swap_remove(index);
+        let step = if self.rng.below(3) == 0 {
+            SimulationStep::Deny(action_id)
+        } else {
+            SimulationStep::Approve(action_id)
+        };
+        self.script.steps.push(step);
+    }
+
+    fn preview_patch(&mut self) {
+        let action_id = self.next_id("sim-patch");
+        let files = (0..=self.rng.below(3))
+            .map(|index| format!("src/sim_{index}.rs"))
+            .collect();
+        self.push(IpcEvent::PatchPreviewReady(PatchPreviewReady {
+            action_id: action_id.clone(),
+            files,
+        }));
+        self.ready_patches.push(action_id);
+    }
+
+    fn apply_patch(&mut self) {
+        if self.ready_patches.is_empty() {
+            return self.preview_patch();
+        }
+        let index = self.rng.below(self.ready_patches.len());
+        let action_id = self.ready_patches.swap_remove(index);
+        self.push(IpcEvent::PatchApplied(PatchApplied {
+            action_id,
+            files: vec!["src/sim_0.rs".to_string()],
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::IpcEvent;
+    use codex_alicia_core::IpcMessage;
+    use codex_alicia_core::ipc::ApprovalRequested;
+    use codex_alicia_core::strategies;
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+
+    use super::Simulation;
+    use super::SimulationConfig;
+    use super::SimulationScript;
+    use super::check_store_invariants;
+    use super::simulation_script;
+    use crate::ApprovalStatus;
+    use crate::CommandLifecycle;
+    use crate::UiEventStore;
+
+    #[test]
+    fn seeded_scripts_are_deterministic_and_keep_invariants()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let config = SimulationConfig::default().with_seed(42).with_steps(500);
+        let script = SimulationScript::generate(&config);
+        assert_eq!(script, SimulationScript::generate(&config));
+        assert_eq!(script.steps.len(), 500);
+        assert_ne!(script, SimulationScript::generate(&config.with_seed(7)));
+
+        for seed in 0..20 {
+            let script = SimulationScript::generate(&config.with_seed(seed));
+            let simulation = Simulation::run_script(&script)?;
+            assert_eq!(simulation.steps_run(), 500);
+        }
+        let simulation = Simulation::run_script(&script)?;
+        let store = simulation.store();
+        assert!(
+            store
+                .terminal_session_ids()
+                .iter()
+                .filter_map(|session_id| store.terminal_session(session_id))
+                .any(|session| matches!(session.lifecycle, CommandLifecycle::Finished { .. }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn virtual_time_expires_scripted_approvals() -> Result<(), Box<dyn std::error::Error>> {
+        let start = 1_000;
+        let script = SimulationScript::new(start)
+            .event(IpcEvent::ApprovalRequested(ApprovalRequested {
+                action_id: "act-1".to_string(),
+                summary: "Executar".to_string(),
+                expires_at_unix_s: start + 60,
+            }))
+            .advance_time(61)
+            .approve("act-1");
+
+        let simulation = Simulation::run_script(&script)?;
+
+        assert_eq!(simulation.now_unix_s(), 1_061);
+        assert_eq!(
+            simulation
+                .store()
+                .approval("act-1")
+                .map(|approval| approval.status),
+            Some(ApprovalStatus::Expired)
+        );
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_scripts_keep_store_invariants(script in simulation_script(64)) {
+            Simulation::run_script(&script)?;
+        }
+
+        #[test]
+        fn approval_sequences_keep_pending_count_equal_to_queue_length(
+            events in strategies::approval_sequence(),
+        ) {
+            let mut store = UiEventStore::default();
+            for event in events {
+                store.push(IpcMessage::new(event));
+                check_store_invariants(&store, strategies::STRATEGY_START_UNIX_S)
+                    .map_err(TestCaseError::fail)?;
+            }
+        }
+    }
+}
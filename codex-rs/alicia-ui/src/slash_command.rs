@@ -0,0 +1,330 @@
+use std::sync::Arc;
+
+use codex_alicia_core::PermissionProfile;
+
+use crate::UiEventStore;
+use crate::permission_profile_name;
+
+/// What the autocomplete popup shows of a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlashCommandSpec {
+    /// Name typed after the slash, e.g. `approve`.
+    pub name: String,
+    pub usage: String,
+    pub description: String,
+}
+
+impl SlashCommandSpec {
+    pub fn new(
+        name: impl Into<String>,
+        usage: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            usage: usage.into(),
+            description: description.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlashCommandInvocation {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+/// A command typed in the chat input. Plugins implement this and register it with
+/// [`UiEventStore::register_slash_command`].
+pub trait SlashCommandHandler: Send + Sync {
+    fn spec(&self) -> SlashCommandSpec;
+
+    /// Runs the command; the returned text is posted as a system reply in the chat.
+    fn run(&self, store: &mut UiEventStore, args: &[String]) -> String;
+}
+
+/// Parses `/name arg...`. Input whose first word is not a plain command name, such as
+/// `/etc/hosts`, is a prompt rather than a command.
+pub fn parse_slash_command(input: &str) -> Option<SlashCommandInvocation> {
+    let mut words = input.trim().strip_prefix('/')?.split_whitespace();
+    let name = words.next()?;
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return None;
+    }
+    Some(SlashCommandInvocation {
+        name: name.to_ascii_lowercase(),
+        args: words.map(str::to_string).collect(),
+    })
+}
+
+/// Commands available in the chat input, in registration order.
+#[derive(Clone)]
+pub struct SlashCommandRegistry {
+    handlers: Vec<Arc<dyn SlashCommandHandler>>,
+}
+
+impl Default for SlashCommandRegistry {
+    fn default() -> Self {
+        Self {
+            handlers: vec![
+                Arc::new(SessionCommand),
+                Arc::new(ApproveCommand),
+                Arc::new(DiffCommand),
+                Arc::new(ProfileCommand),
+                Arc::new(ModelCommand),
+                Arc::new(ClearCommand),
+            ],
+        }
+    }
+}
+
+impl SlashCommandRegistry {
+    /// Adds `handler`; it replaces a command with the same name.
+    pub fn register(&mut self, handler: Arc<dyn SlashCommandHandler>) {
+        let name = handler.spec().name;
+        self.handlers
+            .retain(|existing| existing.spec().name != name);
+        self.handlers.push(handler);
+    }
+
+    pub fn specs(&self) -> Vec<SlashCommandSpec> {
+        self.handlers.iter().map(|handler| handler.spec()).collect()
+    }
+
+    pub fn handler(&self, name: &str) -> Option<Arc<dyn SlashCommandHandler>> {
+        self.handlers
+            .iter()
+            .find(|handler| handler.spec().name == name)
+            .map(Arc::clone)
+    }
+
+    /// Commands matching what is typed so far: every command whose name starts with the
+    /// partial name, or the command itself once its arguments are being typed.
+    pub fn completions(&self, input: &str) -> Vec<SlashCommandSpec> {
+        let Some(typed) = input.trim_start().strip_prefix('/') else {
+            return Vec::new();
+        };
+        let typed = typed.to_ascii_lowercase();
+        self.specs()
+            .into_iter()
+            .filter(|spec| match typed.split_once(char::is_whitespace) {
+                Some((name, _)) => spec.name == name,
+                None => spec.name.starts_with(&typed),
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for SlashCommandRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.handlers.iter().map(|handler| handler.spec().name))
+            .finish()
+    }
+}
+
+fn usage_reply(spec: &SlashCommandSpec) -> String {
+    format!("Uso: {}", spec.usage)
+}
+
+struct SessionCommand;
+
+impl SlashCommandHandler for SessionCommand {
+    fn spec(&self) -> SlashCommandSpec {
+        SlashCommandSpec::new(
+            "session",
+            "/session new [comando...]",
+            "Abre uma nova sessao de terminal (padrao: o shell do usuario)",
+        )
+    }
+
+    fn run(&self, store: &mut UiEventStore, args: &[String]) -> String {
+        match args.split_first() {
+            Some((subcommand, command)) if subcommand == "new" => {
+                store.request_session(command.to_vec());
+                if command.is_empty() {
+                    "Abrindo uma nova sessao com o shell padrao.".to_string()
+                } else {
+                    format!("Abrindo uma nova sessao: {}", command.join(" "))
+                }
+            }
+            _ => usage_reply(&self.spec()),
+        }
+    }
+}
+
+struct ApproveCommand;
+
+impl SlashCommandHandler for ApproveCommand {
+    fn spec(&self) -> SlashCommandSpec {
+        SlashCommandSpec::new("approve", "/approve <acao>", "Aprova uma acao pendente")
+    }
+
+    fn run(&self, store: &mut UiEventStore, args: &[String]) -> String {
+        let [action_id] = args else {
+            return usage_reply(&self.spec());
+        };
+        match store.approve(action_id) {
+            Ok(_) => format!("Acao {action_id} aprovada."),
+            Err(error) => error.beginner_message(),
+        }
+    }
+}
+
+struct DiffCommand;
+
+impl SlashCommandHandler for DiffCommand {
+    fn spec(&self) -> SlashCommandSpec {
+        SlashCommandSpec::new(
+            "diff",
+            "/diff <acao>",
+            "Resume o patch proposto por uma acao",
+        )
+    }
+
+    fn run(&self, store: &mut UiEventStore, args: &[String]) -> String {
+        let [action_id] = args else {
+            return usage_reply(&self.spec());
+        };
+        let Some(preview) = store.diff_preview(action_id) else {
+            return format!("Nenhum patch para a acao {action_id}.");
+        };
+        let mut reply = format!("Patch de {action_id}:");
+        for file in &preview.file_previews {
+            let added: usize = file.hunks.iter().map(|hunk| hunk.added_lines).sum();
+            let removed: usize = file.hunks.iter().map(|hunk| hunk.removed_lines).sum();
+            reply.push_str(&format!(
+                "\n- `{}`: {} hunk(s), +{added} -{removed}",
+                file.file_path,
+                file.hunks.len()
+            ));
+        }
+        if preview.applied {
+            reply.push_str("\nJa aplicado.");
+        }
+        reply
+    }
+}
+
+struct ProfileCommand;
+
+const PROFILES: [PermissionProfile; 3] = [
+    PermissionProfile::ReadOnly,
+    PermissionProfile::ReadWriteWithApproval,
+    PermissionProfile::FullAccess,
+];
+
+impl SlashCommandHandler for ProfileCommand {
+    fn spec(&self) -> SlashCommandSpec {
+        SlashCommandSpec::new(
+            "profile",
+            "/profile [read_only|read_write_with_approval|full_access]",
+            "Mostra ou troca o perfil de permissao",
+        )
+    }
+
+    fn run(&self, store: &mut UiEventStore, args: &[String]) -> String {
+        match args {
+            [] => format!("Perfil atual: {}.", store.profile().name),
+            [name] => match PROFILES
+                .into_iter()
+                .find(|profile| permission_profile_name(*profile) == name)
+            {
+                Some(profile) => {
+                    store.change_permission_profile(profile);
+                    format!("Perfil alterado para {name}.")
+                }
+                None => format!("Perfil desconhecido: {name}. {}", usage_reply(&self.spec())),
+            },
+            _ => usage_reply(&self.spec()),
+        }
+    }
+}
+
+struct ModelCommand;
+
+impl SlashCommandHandler for ModelCommand {
+    fn spec(&self) -> SlashCommandSpec {
+        SlashCommandSpec::new("model", "/model [id]", "Mostra ou troca o modelo do agente")
+    }
+
+    fn run(&self, store: &mut UiEventStore, args: &[String]) -> String {
+        match args {
+            [] => {
+                let available: Vec<&str> = store
+                    .available_models()
+                    .iter()
+                    .map(|model| model.id.as_str())
+                    .collect();
+                format!(
+                    "Modelo atual: {}. Disponiveis: {}.",
+                    store.selected_model().unwrap_or("nenhum"),
+                    available.join(", ")
+                )
+            }
+            [id] => match store.select_model(id) {
+                Ok(()) => format!("Modelo do agente: {id}."),
+                Err(error) => error.beginner_message(),
+            },
+            _ => usage_reply(&self.spec()),
+        }
+    }
+}
+
+struct ClearCommand;
+
+impl SlashCommandHandler for ClearCommand {
+    fn spec(&self) -> SlashCommandSpec {
+        SlashCommandSpec::new("clear", "/clear", "Limpa a conversa")
+    }
+
+    fn run(&self, store: &mut UiEventStore, _args: &[String]) -> String {
+        store.clear_chat();
+        "Conversa limpa.".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::SlashCommandInvocation;
+    use super::SlashCommandRegistry;
+    use super::parse_slash_command;
+
+    #[test]
+    fn parses_commands_but_not_paths() {
+        assert_eq!(
+            parse_slash_command("  /Approve act-1 "),
+            Some(SlashCommandInvocation {
+                name: "approve".to_string(),
+                args: vec!["act-1".to_string()],
+            })
+        );
+        assert_eq!(parse_slash_command("/etc/hosts esta vazio"), None);
+        assert_eq!(parse_slash_command("ola /clear"), None);
+        assert_eq!(parse_slash_command("/"), None);
+    }
+
+    #[test]
+    fn completes_partial_names_and_keeps_the_typed_command() {
+        let registry = SlashCommandRegistry::default();
+        let names = |input: &str| -> Vec<String> {
+            registry
+                .completions(input)
+                .into_iter()
+                .map(|spec| spec.name)
+                .collect()
+        };
+        assert_eq!(
+            names("/"),
+            vec!["session", "approve", "diff", "profile", "model", "clear"]
+        );
+        assert_eq!(names("/pr"), vec!["profile"]);
+        assert_eq!(names("/diff act"), vec!["diff"]);
+        assert!(names("ola").is_empty());
+    }
+}
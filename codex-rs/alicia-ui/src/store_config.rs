@@ -0,0 +1,131 @@
+use crate::terminal_wrap::truncate_to_width;
+
+const DEFAULT_SCROLLBACK_LINES: usize = 2_000;
+const DEFAULT_OUTPUT_PREVIEW_MAX_COLUMNS: usize = 80;
+const DEFAULT_MAX_TIMELINE_ENTRIES: usize = 10_000;
+const DEFAULT_MAX_APPROVAL_REQUESTS_PER_MINUTE: usize = 30;
+
+/// Limits of a `UiEventStore`. Every field can be changed while the store is running; see
+/// `UiEventStore::set_config`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoreConfig {
+    /// Lines kept per terminal session.
+    pub max_scrollback_lines: usize,
+    /// Bytes of text kept per terminal session, on top of the line cap; `None` caps lines
+    /// only.
+    pub max_scrollback_bytes: Option<usize>,
+    /// Terminal columns of command output or code quoted in a timeline entry.
+    pub output_preview_max_columns: usize,
+    /// Timeline entries kept; the oldest are dropped first.
+    pub max_timeline_entries: usize,
+    /// `None` keeps everything; see `UiEventStore::enforce_memory_budget`.
+    pub memory_budget_bytes: Option<usize>,
+    /// Distinct approval requests queued per minute; the ones past it expire right away.
+    /// Duplicates of a pending request don't count.
+    pub max_approval_requests_per_minute: usize,
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        Self {
+            max_scrollback_lines: DEFAULT_SCROLLBACK_LINES,
+            max_scrollback_bytes: None,
+            output_preview_max_columns: DEFAULT_OUTPUT_PREVIEW_MAX_COLUMNS,
+            max_timeline_entries: DEFAULT_MAX_TIMELINE_ENTRIES,
+            memory_budget_bytes: None,
+            max_approval_requests_per_minute: DEFAULT_MAX_APPROVAL_REQUESTS_PER_MINUTE,
+        }
+    }
+}
+
+impl StoreConfig {
+    pub fn with_max_scrollback_lines(mut self, max_scrollback_lines: usize) -> Self {
+        self.max_scrollback_lines = max_scrollback_lines.max(1);
+        self
+    }
+
+    pub fn with_max_scrollback_bytes(mut self, max_scrollback_bytes: Option<usize>) -> Self {
+        self.max_scrollback_bytes = max_scrollback_bytes.map(|bytes| bytes.max(1));
+        self
+    }
+
+    pub fn with_output_preview_max_columns(mut self, output_preview_max_columns: usize) -> Self {
+        self.output_preview_max_columns = output_preview_max_columns;
+        self
+    }
+
+    pub fn with_max_timeline_entries(mut self, max_timeline_entries: usize) -> Self {
+        self.max_timeline_entries = max_timeline_entries.max(1);
+        self
+    }
+
+    pub fn with_memory_budget_bytes(mut self, memory_budget_bytes: Option<usize>) -> Self {
+        self.memory_budget_bytes = memory_budget_bytes;
+        self
+    }
+
+    pub fn with_max_approval_requests_per_minute(
+        mut self,
+        max_approval_requests_per_minute: usize,
+    ) -> Self {
+        self.max_approval_requests_per_minute = max_approval_requests_per_minute.max(1);
+        self
+    }
+
+    pub fn scrollback_limits(&self) -> ScrollbackLimits {
+        ScrollbackLimits::new(self.max_scrollback_lines).with_max_bytes(self.max_scrollback_bytes)
+    }
+
+    /// `text` cut to `output_preview_max_columns`, with `...` when something was cut.
+    pub(crate) fn preview(&self, text: &str) -> String {
+        let (preview, truncated) = truncate_to_width(text, self.output_preview_max_columns);
+        if truncated {
+            format!("{preview}...")
+        } else {
+            preview.to_string()
+        }
+    }
+}
+
+/// Caps of one terminal session's scrollback. The oldest lines are dropped until both hold;
+/// a single line longer than `max_bytes` keeps only its start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScrollbackLimits {
+    pub max_lines: usize,
+    pub max_bytes: Option<usize>,
+}
+
+impl ScrollbackLimits {
+    pub fn new(max_lines: usize) -> Self {
+        Self {
+            max_lines: max_lines.max(1),
+            max_bytes: None,
+        }
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: Option<usize>) -> Self {
+        self.max_bytes = max_bytes.map(|bytes| bytes.max(1));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::StoreConfig;
+
+    #[test]
+    fn previews_are_cut_at_the_configured_length() {
+        let config = StoreConfig::default().with_output_preview_max_columns(4);
+        assert_eq!(config.preview("abcd"), "abcd");
+        assert_eq!(config.preview("abcdef"), "abcd...");
+        assert_eq!(config.preview("日本語"), "日本...");
+        assert_eq!(
+            StoreConfig::default()
+                .with_max_scrollback_lines(0)
+                .max_scrollback_lines,
+            1
+        );
+    }
+}
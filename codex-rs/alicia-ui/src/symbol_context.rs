@@ -0,0 +1,337 @@
+use std::path::Path;
+
+use codex_alicia_core::ensure_target_in_workspace;
+
+use crate::FileContextError;
+use crate::PatchHunkPreview;
+
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "rs", "py", "js", "jsx", "ts", "tsx", "go", "java", "kt", "c", "cc", "cpp", "h", "hpp", "cs",
+    "rb", "swift", "sh",
+];
+pub(crate) const SKIPPED_DIRS: &[&str] = &["target", "node_modules", "dist", "build", "vendor"];
+const MAX_SCANNED_FILES: usize = 5_000;
+const MAX_SCANNED_FILE_BYTES: u64 = 1024 * 1024;
+pub const MAX_LISTED_CALL_SITES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Type,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolDefinition {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallSite {
+    pub file_path: String,
+    pub line: usize,
+}
+
+/// Symbol touched by a hunk with its references elsewhere in the workspace. Only the first
+/// `MAX_LISTED_CALL_SITES` are listed; `call_site_total` counts all of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModifiedSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub call_sites: Vec<CallSite>,
+    pub call_site_total: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkSymbolContext {
+    pub hunk_id: String,
+    pub symbols: Vec<ModifiedSymbol>,
+}
+
+/// Line-based definition scanner for common languages. It is deliberately coarse: it only
+/// needs to name what a hunk touches, not to parse the file.
+pub fn find_definitions(source: &str) -> Vec<SymbolDefinition> {
+    source
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let (name, kind) = definition_on_line(line)?;
+            Some(SymbolDefinition {
+                name,
+                kind,
+                line: index + 1,
+            })
+        })
+        .collect()
+}
+
+fn definition_on_line(line: &str) -> Option<(String, SymbolKind)> {
+    let mut rest = line.trim_start();
+    for modifier in [
+        "pub(crate) ",
+        "pub(super) ",
+        "pub ",
+        "export default ",
+        "export ",
+        "async ",
+        "unsafe ",
+        "const ",
+        "static ",
+    ] {
+        rest = rest.strip_prefix(modifier).unwrap_or(rest);
+    }
+    let (rest, kind) = [
+        ("fn ", SymbolKind::Function),
+        ("def ", SymbolKind::Function),
+        ("function ", SymbolKind::Function),
+        ("func ", SymbolKind::Function),
+        ("struct ", SymbolKind::Type),
+        ("enum ", SymbolKind::Type),
+        ("trait ", SymbolKind::Type),
+        ("class ", SymbolKind::Type),
+        ("interface ", SymbolKind::Type),
+        ("type ", SymbolKind::Type),
+    ]
+    .into_iter()
+    .find_map(|(keyword, kind)| rest.strip_prefix(keyword).map(|rest| (rest, kind)))?;
+    let name: String = rest
+        .trim_start()
+        .chars()
+        .take_while(|ch| ch.is_alphanumeric() || *ch == '_')
+        .collect();
+    (!name.is_empty()).then_some((name, kind))
+}
+
+/// Symbols a hunk modifies: the definition enclosing its first old line, definitions inside
+/// its old range and definitions it adds.
+pub fn modified_symbols(source: &str, hunk: &PatchHunkPreview) -> Vec<SymbolDefinition> {
+    let definitions = find_definitions(source);
+    let first_line = hunk.old_start.max(1);
+    let last_line = hunk.old_start + hunk.old_count.saturating_sub(1);
+
+    let mut symbols: Vec<SymbolDefinition> = Vec::new();
+    if let Some(enclosing) = definitions
+        .iter()
+        .rev()
+        .find(|definition| definition.line <= first_line)
+    {
+        symbols.push(enclosing.clone());
+    }
+    symbols.extend(
+        definitions
+            .iter()
+            .filter(|definition| definition.line > first_line && definition.line <= last_line)
+            .cloned(),
+    );
+    let mut new_line = hunk.new_start;
+    for line in &hunk.lines {
+        if let Some(added) = line.strip_prefix('+')
+            && let Some((name, kind)) = definition_on_line(added)
+        {
+            symbols.push(SymbolDefinition {
+                name,
+                kind,
+                line: new_line,
+            });
+        }
+        if !line.starts_with('-') {
+            new_line += 1;
+        }
+    }
+
+    let mut seen = Vec::new();
+    symbols.retain(|symbol| {
+        if seen.contains(&symbol.name) {
+            false
+        } else {
+            seen.push(symbol.name.clone());
+            true
+        }
+    });
+    symbols
+}
+
+/// Finds lines in workspace source files that reference `name`, skipping its definitions.
+pub fn find_call_sites(
+    workspace_root: &Path,
+    name: &str,
+    kind: SymbolKind,
+) -> (Vec<CallSite>, usize) {
+    let mut files = Vec::new();
+    collect_source_files(workspace_root, &mut files);
+
+    let mut call_sites = Vec::new();
+    let mut total = 0;
+    for path in files {
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let file_path = path
+            .strip_prefix(workspace_root)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        for (index, line) in text.lines().enumerate() {
+            if !references_symbol(line, name, kind)
+                || definition_on_line(line).is_some_and(|(defined, _)| defined == name)
+            {
+                continue;
+            }
+            total += 1;
+            if call_sites.len() < MAX_LISTED_CALL_SITES {
+                call_sites.push(CallSite {
+                    file_path: file_path.clone(),
+                    line: index + 1,
+                });
+            }
+        }
+    }
+    (call_sites, total)
+}
+
+/// Functions count as referenced when called (`name(`, `name::<`) or passed by path
+/// (`::name`); types on any whole-word use.
+fn references_symbol(line: &str, name: &str, kind: SymbolKind) -> bool {
+    line.match_indices(name).any(|(index, _)| {
+        let before = line[..index].chars().next_back();
+        let after = &line[index + name.len()..];
+        let starts_word = !before.is_some_and(|ch| ch.is_alphanumeric() || ch == '_');
+        let ends_word = !after
+            .chars()
+            .next()
+            .is_some_and(|ch| ch.is_alphanumeric() || ch == '_');
+        if !(starts_word && ends_word) {
+            return false;
+        }
+        match kind {
+            SymbolKind::Function => {
+                after.starts_with('(') || after.starts_with("::<") || line[..index].ends_with("::")
+            }
+            SymbolKind::Type => true,
+        }
+    })
+}
+
+fn collect_source_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if files.len() >= MAX_SCANNED_FILES {
+            return;
+        }
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            if !file_name.starts_with('.') && !SKIPPED_DIRS.contains(&file_name.as_str()) {
+                collect_source_files(&path, files);
+            }
+        } else if path.extension().is_some_and(|extension| {
+            SOURCE_EXTENSIONS
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        }) && entry
+            .metadata()
+            .is_ok_and(|metadata| metadata.len() <= MAX_SCANNED_FILE_BYTES)
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Loads the pre-patch `file_path` and resolves, per hunk, the symbols it modifies and
+/// where they are referenced in the workspace.
+pub fn load_hunk_symbol_contexts(
+    workspace_root: &Path,
+    file_path: &str,
+    hunks: &[PatchHunkPreview],
+) -> Result<Vec<HunkSymbolContext>, FileContextError> {
+    let guard = ensure_target_in_workspace(workspace_root, &workspace_root.join(file_path))
+        .map_err(|source| FileContextError::OutsideWorkspace {
+            file_path: file_path.to_string(),
+            source,
+        })?;
+    // New files have no pre-patch content; their symbols come from the added lines alone.
+    let source = match std::fs::read_to_string(&guard.canonical_target) {
+        Ok(source) => source,
+        Err(source) if source.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(source) => {
+            return Err(FileContextError::ReadFailed {
+                file_path: file_path.to_string(),
+                source,
+            });
+        }
+    };
+
+    Ok(hunks
+        .iter()
+        .map(|hunk| HunkSymbolContext {
+            hunk_id: hunk.hunk_id.clone(),
+            symbols: modified_symbols(&source, hunk)
+                .into_iter()
+                .map(|symbol| {
+                    let (call_sites, call_site_total) =
+                        find_call_sites(&guard.canonical_workspace, &symbol.name, symbol.kind);
+                    ModifiedSymbol {
+                        name: symbol.name,
+                        kind: symbol.kind,
+                        call_sites,
+                        call_site_total,
+                    }
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::CallSite;
+    use super::HunkSymbolContext;
+    use super::ModifiedSymbol;
+    use super::SymbolKind;
+    use super::load_hunk_symbol_contexts;
+    use crate::parse_unified_diff_hunks;
+
+    #[test]
+    fn resolves_modified_function_and_its_callers() -> Result<(), Box<dyn std::error::Error>> {
+        let workspace = tempfile::TempDir::new()?;
+        std::fs::create_dir_all(workspace.path().join("src"))?;
+        std::fs::write(
+            workspace.path().join("src").join("lib.rs"),
+            "pub fn parse_config(raw: &str) -> Config {\n    Config::default()\n}\n\npub struct Config;\n",
+        )?;
+        std::fs::write(
+            workspace.path().join("src").join("main.rs"),
+            "fn main() {\n    let config = parse_config(\"x\");\n    let parse_config_name = 1;\n}\n",
+        )?;
+        let hunks = parse_unified_diff_hunks(
+            "@@ -2,1 +2,2 @@\n-    Config::default()\n+    let config = Config::default();\n+    config\n",
+        )?;
+
+        let contexts = load_hunk_symbol_contexts(workspace.path(), "src/lib.rs", &hunks)?;
+
+        assert_eq!(
+            contexts,
+            vec![HunkSymbolContext {
+                hunk_id: "hunk-1".to_string(),
+                symbols: vec![ModifiedSymbol {
+                    name: "parse_config".to_string(),
+                    kind: SymbolKind::Function,
+                    call_sites: vec![CallSite {
+                        file_path: std::path::Path::new("src")
+                            .join("main.rs")
+                            .to_string_lossy()
+                            .to_string(),
+                        line: 2,
+                    }],
+                    call_site_total: 1,
+                }],
+            }]
+        );
+        Ok(())
+    }
+}
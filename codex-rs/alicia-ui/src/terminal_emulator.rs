@@ -0,0 +1,242 @@
+/// Size `codex_utils_pty` opens every PTY with; the emulator must match the PTY, through
+/// `set_size` once the session is resized, so cursor addressing lands where the program
+/// expects.
+pub const PTY_ROWS: u16 = 24;
+pub const PTY_COLUMNS: u16 = 80;
+
+/// The 16 ANSI colors, as xterm draws them.
+const ANSI_PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
+/// A run of screen cells sharing the same style. `None` colors are the theme defaults.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalSpan {
+    pub text: String,
+    pub foreground: Option<[u8; 3]>,
+    pub background: Option<[u8; 3]>,
+    pub bold: bool,
+}
+
+/// VT100/ANSI screen for PTY sessions, so full-screen programs (`less`, `vim`) and colors
+/// render the way a terminal shows them.
+pub struct TerminalEmulator {
+    parser: vt100::Parser,
+    scrollback_lines: usize,
+}
+
+impl TerminalEmulator {
+    pub fn new(scrollback_lines: usize) -> Self {
+        Self {
+            parser: vt100::Parser::new(PTY_ROWS, PTY_COLUMNS, scrollback_lines),
+            scrollback_lines,
+        }
+    }
+
+    pub fn process(&mut self, output: &str) {
+        self.parser.process(output.as_bytes());
+    }
+
+    /// `(rows, columns)`.
+    pub fn size(&self) -> (u16, u16) {
+        self.parser.screen().size()
+    }
+
+    pub fn set_size(&mut self, rows: u16, columns: u16) {
+        self.parser.screen_mut().set_size(rows, columns);
+    }
+
+    /// Whether a full-screen program switched to the alternate screen.
+    pub fn alternate_screen(&self) -> bool {
+        self.parser.screen().alternate_screen()
+    }
+
+    /// `(row, column)`, zero based.
+    pub fn cursor_position(&self) -> (u16, u16) {
+        self.parser.screen().cursor_position()
+    }
+
+    /// Screen text, one entry per row, without trailing blanks.
+    pub fn screen_lines(&self) -> Vec<String> {
+        let (_, columns) = self.size();
+        self.parser
+            .screen()
+            .rows(0, columns)
+            .map(|row| row.trim_end().to_string())
+            .collect()
+    }
+
+    /// Screen rows split into styled runs; wide characters occupy their first cell only.
+    pub fn styled_rows(&self) -> Vec<Vec<TerminalSpan>> {
+        let screen = self.parser.screen();
+        let (rows, columns) = screen.size();
+        (0..rows)
+            .map(|row| {
+                let mut spans: Vec<TerminalSpan> = Vec::new();
+                for column in 0..columns {
+                    let Some(cell) = screen.cell(row, column) else {
+                        continue;
+                    };
+                    if cell.is_wide_continuation() {
+                        continue;
+                    }
+                    let mut foreground = color_rgb(cell.fgcolor());
+                    let mut background = color_rgb(cell.bgcolor());
+                    if cell.inverse() {
+                        std::mem::swap(&mut foreground, &mut background);
+                    }
+                    let text = if cell.has_contents() {
+                        cell.contents()
+                    } else {
+                        " "
+                    };
+                    match spans.last_mut() {
+                        Some(span)
+                            if span.foreground == foreground
+                                && span.background == background
+                                && span.bold == cell.bold() =>
+                        {
+                            span.text.push_str(text);
+                        }
+                        _ => spans.push(TerminalSpan {
+                            text: text.to_string(),
+                            foreground,
+                            background,
+                            bold: cell.bold(),
+                        }),
+                    }
+                }
+                // Trailing blanks without a background are padding, not content.
+                while let Some(span) = spans.last_mut()
+                    && span.background.is_none()
+                {
+                    span.text.truncate(span.text.trim_end().len());
+                    if !span.text.is_empty() {
+                        break;
+                    }
+                    spans.pop();
+                }
+                spans
+            })
+            .collect()
+    }
+}
+
+/// Clones carry the screen, cursor and modes but not the scrollback.
+impl Clone for TerminalEmulator {
+    fn clone(&self) -> Self {
+        let mut clone = Self::new(self.scrollback_lines);
+        let (rows, columns) = self.size();
+        clone.set_size(rows, columns);
+        clone
+            .parser
+            .process(&self.parser.screen().state_formatted());
+        clone
+    }
+}
+
+impl PartialEq for TerminalEmulator {
+    fn eq(&self, other: &Self) -> bool {
+        self.parser.screen().state_formatted() == other.parser.screen().state_formatted()
+    }
+}
+
+impl Eq for TerminalEmulator {}
+
+impl std::fmt::Debug for TerminalEmulator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TerminalEmulator")
+            .field("cursor_position", &self.cursor_position())
+            .field("alternate_screen", &self.alternate_screen())
+            .finish()
+    }
+}
+
+fn color_rgb(color: vt100::Color) -> Option<[u8; 3]> {
+    match color {
+        vt100::Color::Default => None,
+        vt100::Color::Rgb(red, green, blue) => Some([red, green, blue]),
+        vt100::Color::Idx(index) => Some(indexed_color_rgb(index)),
+    }
+}
+
+/// xterm's 256-color palette: the ANSI colors, a 6x6x6 cube and a gray ramp.
+fn indexed_color_rgb(index: u8) -> [u8; 3] {
+    match index {
+        0..=15 => ANSI_PALETTE[usize::from(index)],
+        16..=231 => {
+            let cube = index - 16;
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            [level(cube / 36), level(cube / 6 % 6), level(cube % 6)]
+        }
+        232..=255 => {
+            let gray = 8 + (index - 232) * 10;
+            [gray, gray, gray]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::TerminalEmulator;
+    use super::TerminalSpan;
+    use super::indexed_color_rgb;
+
+    #[test]
+    fn cursor_movement_and_alternate_screen_are_emulated() {
+        let mut emulator = TerminalEmulator::new(100);
+        emulator.process("linha 1\r\nlinha 2\x1b[1;7HX");
+        assert_eq!(emulator.screen_lines()[..2], ["linha X", "linha 2"]);
+        assert_eq!(emulator.cursor_position(), (0, 7));
+
+        emulator.process("\x1b[?1049h\x1b[Hvim");
+        assert!(emulator.alternate_screen());
+        assert_eq!(emulator.screen_lines()[0], "vim");
+
+        emulator.process("\x1b[?1049l");
+        assert!(!emulator.alternate_screen());
+        assert_eq!(emulator.screen_lines()[0], "linha X");
+    }
+
+    #[test]
+    fn colors_split_rows_into_spans() {
+        let mut emulator = TerminalEmulator::new(0);
+        emulator.process("ok \x1b[1;31merro\x1b[0m");
+        assert_eq!(
+            emulator.styled_rows()[0],
+            vec![
+                TerminalSpan {
+                    text: "ok ".to_string(),
+                    foreground: None,
+                    background: None,
+                    bold: false,
+                },
+                TerminalSpan {
+                    text: "erro".to_string(),
+                    foreground: Some([205, 0, 0]),
+                    background: None,
+                    bold: true,
+                },
+            ]
+        );
+        assert_eq!(indexed_color_rgb(196), [255, 0, 0]);
+        assert_eq!(indexed_color_rgb(244), [128, 128, 128]);
+    }
+}
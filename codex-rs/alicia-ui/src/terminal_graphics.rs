@@ -0,0 +1,345 @@
+use std::path::PathBuf;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+
+const ESC: char = '\u{1b}';
+const BEL: char = '\u{7}';
+const ITERM2_FILE_PREFIX: &str = "\u{1b}]1337;File=";
+const KITTY_PREFIX: &str = "\u{1b}_G";
+const STRING_TERMINATOR: &str = "\u{1b}\\";
+/// Unterminated graphics sequences larger than this are dropped instead of buffered forever.
+const MAX_PENDING_GRAPHICS_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    ITerm2,
+    Kitty,
+    Sixel,
+}
+
+impl GraphicsProtocol {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ITerm2 => "iterm2",
+            Self::Kitty => "kitty",
+            Self::Sixel => "sixel",
+        }
+    }
+}
+
+/// Image pulled out of terminal output. `data` holds the decoded file bytes when the
+/// protocol carried an encoded image (PNG, JPEG, ...); sixel and raw pixel payloads only
+/// keep the placeholder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineImage {
+    pub id: u64,
+    pub protocol: GraphicsProtocol,
+    pub name: Option<String>,
+    pub data: Option<Vec<u8>>,
+    pub encoded_len: usize,
+}
+
+impl InlineImage {
+    pub fn placeholder(&self) -> String {
+        match &self.name {
+            Some(name) => format!(
+                "[imagem {} {} {name} {} bytes]",
+                self.id,
+                self.protocol.name(),
+                self.encoded_len
+            ),
+            None => format!(
+                "[imagem {} {} {} bytes]",
+                self.id,
+                self.protocol.name(),
+                self.encoded_len
+            ),
+        }
+    }
+
+    /// Writes the decoded image to the temp dir and opens it with the system viewer.
+    pub fn open_in_viewer(&self) -> std::io::Result<PathBuf> {
+        let Some(data) = &self.data else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!(
+                    "{} image {} has no decodable payload",
+                    self.protocol.name(),
+                    self.id
+                ),
+            ));
+        };
+        let extension = self
+            .name
+            .as_deref()
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, extension)| extension)
+            .filter(|extension| extension.chars().all(char::is_alphanumeric))
+            .unwrap_or("png");
+        let path = std::env::temp_dir().join(format!("alicia-image-{}.{extension}", self.id));
+        std::fs::write(&path, data)?;
+        webbrowser::open(&path.to_string_lossy())?;
+        Ok(path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct FilteredOutput {
+    pub(crate) text: String,
+    pub(crate) images: Vec<InlineImage>,
+}
+
+/// Strips iTerm2, kitty and sixel graphics sequences out of terminal output, replacing each
+/// image with a one-line placeholder. Sequences split across chunks are buffered.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct GraphicsFilter {
+    pending: String,
+    kitty_control: Option<String>,
+    kitty_payload: String,
+    next_image_id: u64,
+}
+
+enum SequenceScan {
+    /// Not a graphics sequence; emit the escape byte as regular text.
+    NotGraphics,
+    /// Possibly a graphics sequence that has not been fully received yet.
+    Incomplete,
+    Complete {
+        len: usize,
+        image: Option<InlineImage>,
+    },
+}
+
+impl GraphicsFilter {
+    pub(crate) fn filter(&mut self, chunk: &str) -> FilteredOutput {
+        let mut input = std::mem::take(&mut self.pending);
+        input.push_str(chunk);
+
+        let mut output = FilteredOutput::default();
+        let mut rest = input.as_str();
+        while let Some(escape_index) = rest.find(ESC) {
+            output.text.push_str(&rest[..escape_index]);
+            rest = &rest[escape_index..];
+            match self.scan_sequence(rest) {
+                SequenceScan::NotGraphics => {
+                    output.text.push(ESC);
+                    rest = &rest[ESC.len_utf8()..];
+                }
+                SequenceScan::Incomplete => {
+                    if rest.len() > MAX_PENDING_GRAPHICS_BYTES {
+                        output
+                            .text
+                            .push_str("[imagem descartada: sequencia muito grande]");
+                    } else {
+                        self.pending = rest.to_string();
+                    }
+                    return output;
+                }
+                SequenceScan::Complete { len, image } => {
+                    if let Some(image) = image {
+                        output.text.push_str(&image.placeholder());
+                        output.images.push(image);
+                    }
+                    rest = &rest[len..];
+                }
+            }
+        }
+        output.text.push_str(rest);
+        output
+    }
+
+    fn scan_sequence(&mut self, sequence: &str) -> SequenceScan {
+        if let Some(body_start) = matched_prefix_len(sequence, ITERM2_FILE_PREFIX) {
+            let Some(body_start) = body_start else {
+                return SequenceScan::Incomplete;
+            };
+            let body = &sequence[body_start..];
+            let terminator = match (body.find(BEL), body.find(STRING_TERMINATOR)) {
+                (Some(bel), Some(st)) if st < bel => Some((st, STRING_TERMINATOR.len())),
+                (Some(bel), _) => Some((bel, BEL.len_utf8())),
+                (None, Some(st)) => Some((st, STRING_TERMINATOR.len())),
+                (None, None) => None,
+            };
+            let Some((body_len, terminator_len)) = terminator else {
+                return SequenceScan::Incomplete;
+            };
+            let image = self.parse_iterm2(&body[..body_len]);
+            return SequenceScan::Complete {
+                len: body_start + body_len + terminator_len,
+                image: Some(image),
+            };
+        }
+
+        if let Some(body_start) = matched_prefix_len(sequence, KITTY_PREFIX) {
+            let Some(body_start) = body_start else {
+                return SequenceScan::Incomplete;
+            };
+            let body = &sequence[body_start..];
+            let Some(body_len) = body.find(STRING_TERMINATOR) else {
+                return SequenceScan::Incomplete;
+            };
+            let image = self.parse_kitty(&body[..body_len]);
+            return SequenceScan::Complete {
+                len: body_start + body_len + STRING_TERMINATOR.len(),
+                image,
+            };
+        }
+
+        // Sixel is a DCS sequence: ESC P <numeric params> q <data> ESC \
+        if let Some(dcs) = sequence.strip_prefix("\u{1b}P") {
+            let params_len = dcs
+                .find(|ch: char| !(ch.is_ascii_digit() || ch == ';'))
+                .unwrap_or(dcs.len());
+            if params_len == dcs.len() {
+                return SequenceScan::Incomplete;
+            }
+            if !dcs[params_len..].starts_with('q') {
+                return SequenceScan::NotGraphics;
+            }
+            let Some(body_len) = dcs.find(STRING_TERMINATOR) else {
+                return SequenceScan::Incomplete;
+            };
+            let image = InlineImage {
+                id: self.allocate_image_id(),
+                protocol: GraphicsProtocol::Sixel,
+                name: None,
+                data: None,
+                encoded_len: body_len,
+            };
+            return SequenceScan::Complete {
+                len: 2 + body_len + STRING_TERMINATOR.len(),
+                image: Some(image),
+            };
+        }
+        SequenceScan::NotGraphics
+    }
+
+    fn parse_iterm2(&mut self, body: &str) -> InlineImage {
+        let (arguments, payload) = body.split_once(':').unwrap_or((body, ""));
+        let name = arguments
+            .split(';')
+            .find_map(|argument| argument.strip_prefix("name="))
+            .and_then(|encoded| BASE64_STANDARD.decode(encoded).ok())
+            .and_then(|decoded| String::from_utf8(decoded).ok());
+        InlineImage {
+            id: self.allocate_image_id(),
+            protocol: GraphicsProtocol::ITerm2,
+            name,
+            data: BASE64_STANDARD.decode(payload).ok(),
+            encoded_len: payload.len(),
+        }
+    }
+
+    /// Kitty images may arrive in several `m=1` chunks; only the final chunk yields an image,
+    /// using the control data of the first chunk. Queries and deletes produce no placeholder.
+    fn parse_kitty(&mut self, body: &str) -> Option<InlineImage> {
+        let (control, payload) = body.split_once(';').unwrap_or((body, ""));
+        let first_control = self
+            .kitty_control
+            .get_or_insert_with(|| control.to_string())
+            .clone();
+        self.kitty_payload.push_str(payload);
+        if kitty_control_value(control, "m") == Some("1") {
+            return None;
+        }
+        self.kitty_control = None;
+        let payload = std::mem::take(&mut self.kitty_payload);
+        if matches!(kitty_control_value(&first_control, "a"), Some("q" | "d")) {
+            return None;
+        }
+        let data = if kitty_control_value(&first_control, "f") == Some("100") {
+            BASE64_STANDARD.decode(&payload).ok()
+        } else {
+            None
+        };
+        Some(InlineImage {
+            id: self.allocate_image_id(),
+            protocol: GraphicsProtocol::Kitty,
+            name: None,
+            data,
+            encoded_len: payload.len(),
+        })
+    }
+
+    fn allocate_image_id(&mut self) -> u64 {
+        let id = self.next_image_id;
+        self.next_image_id = self.next_image_id.saturating_add(1);
+        id
+    }
+}
+
+/// Kitty control data is a comma-separated `key=value` list such as `a=T,f=100,m=1`.
+fn kitty_control_value<'a>(control: &'a str, key: &str) -> Option<&'a str> {
+    control
+        .split(',')
+        .find_map(|pair| pair.strip_prefix(key)?.strip_prefix('='))
+}
+
+/// `Some(Some(len))` when `sequence` starts with `prefix`, `Some(None)` when `sequence` is a
+/// truncated prefix that may still match once more output arrives, `None` otherwise.
+fn matched_prefix_len(sequence: &str, prefix: &str) -> Option<Option<usize>> {
+    if sequence.starts_with(prefix) {
+        Some(Some(prefix.len()))
+    } else if sequence.len() < prefix.len() && prefix.starts_with(sequence) {
+        Some(None)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine as _;
+    use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+    use pretty_assertions::assert_eq;
+
+    use super::GraphicsFilter;
+    use super::GraphicsProtocol;
+    use super::InlineImage;
+
+    #[test]
+    fn replaces_iterm2_image_split_across_chunks_with_placeholder() {
+        let mut filter = GraphicsFilter::default();
+        let name = BASE64_STANDARD.encode("plot.png");
+        let payload = BASE64_STANDARD.encode([0x89, b'P', b'N', b'G']);
+        let sequence = format!("\u{1b}]1337;File=name={name};inline=1:{payload}\u{7}");
+        let (head, tail) = sequence.split_at(10);
+
+        let first = filter.filter(&format!("antes {head}"));
+        let second = filter.filter(&format!("{tail}\ndepois"));
+
+        assert_eq!(first.text, "antes ");
+        assert_eq!(second.text, "[imagem 0 iterm2 plot.png 8 bytes]\ndepois");
+        assert_eq!(
+            second.images,
+            vec![InlineImage {
+                id: 0,
+                protocol: GraphicsProtocol::ITerm2,
+                name: Some("plot.png".to_string()),
+                data: Some(vec![0x89, b'P', b'N', b'G']),
+                encoded_len: 8,
+            }]
+        );
+    }
+
+    #[test]
+    fn replaces_chunked_kitty_and_sixel_images_and_keeps_other_escapes() {
+        let mut filter = GraphicsFilter::default();
+
+        let output = filter.filter(
+            "\u{1b}[31mred\u{1b}[0m \u{1b}_Ga=T,f=100,m=1;iVBO\u{1b}\\\u{1b}_Gm=0;Rw==\u{1b}\\ \u{1b}Pq#0;2;0;0;0~~\u{1b}\\",
+        );
+
+        assert_eq!(
+            output.text,
+            "\u{1b}[31mred\u{1b}[0m [imagem 0 kitty 8 bytes] [imagem 1 sixel 13 bytes]"
+        );
+        let protocols: Vec<GraphicsProtocol> =
+            output.images.iter().map(|image| image.protocol).collect();
+        assert_eq!(
+            protocols,
+            vec![GraphicsProtocol::Kitty, GraphicsProtocol::Sixel]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use std::ops::Range;
+
+use unicode_width::UnicodeWidthChar;
+
+/// Boundary between two cells of the terminal grid: `column` 0 is before the first cell of
+/// `row`. Columns count display cells, so a CJK character spans two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TerminalCell {
+    pub row: usize,
+    pub column: usize,
+}
+
+impl TerminalCell {
+    pub fn new(row: usize, column: usize) -> Self {
+        Self { row, column }
+    }
+}
+
+/// Text picked with the mouse in the terminal view, from where the drag started (`anchor`)
+/// to where the pointer is (`head`), flowing across rows like a terminal selection does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalSelection {
+    anchor: TerminalCell,
+    head: TerminalCell,
+}
+
+impl TerminalSelection {
+    pub fn new(anchor: TerminalCell) -> Self {
+        Self {
+            anchor,
+            head: anchor,
+        }
+    }
+
+    pub fn extend_to(&mut self, head: TerminalCell) {
+        self.head = head;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.head
+    }
+
+    /// Start and end, in reading order.
+    pub fn bounds(&self) -> (TerminalCell, TerminalCell) {
+        if self.anchor <= self.head {
+            (self.anchor, self.head)
+        } else {
+            (self.head, self.anchor)
+        }
+    }
+
+    /// Columns selected in `row`, up to `row_width` for the rows the selection runs past.
+    pub fn columns_in_row(&self, row: usize, row_width: usize) -> Option<Range<usize>> {
+        let (start, end) = self.bounds();
+        if self.is_empty() || row < start.row || row > end.row {
+            return None;
+        }
+        let from = if row == start.row { start.column } else { 0 };
+        let to = if row == end.row {
+            end.column
+        } else {
+            row_width.max(from)
+        };
+        (from < to).then_some(from..to)
+    }
+
+    /// The selected text of `lines`, rows joined by `\n` and trailing blanks dropped.
+    pub fn text(&self, lines: &[String]) -> String {
+        let (start, end) = self.bounds();
+        if self.is_empty() {
+            return String::new();
+        }
+        (start.row..=end.row)
+            .map(|row| {
+                let line = lines.get(row).map(String::as_str).unwrap_or_default();
+                let columns = self.columns_in_row(row, usize::MAX).unwrap_or(0..0);
+                slice_columns(line, columns).trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// The characters of `line` starting inside `columns`.
+fn slice_columns(line: &str, columns: Range<usize>) -> &str {
+    let mut column = 0;
+    let mut from = line.len();
+    let mut to = line.len();
+    for (index, ch) in line.char_indices() {
+        if column >= columns.end {
+            to = index;
+            break;
+        }
+        if column >= columns.start && from == line.len() {
+            from = index;
+        }
+        column += ch.width().unwrap_or(0);
+    }
+    &line[from.min(to)..to]
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::TerminalCell;
+    use super::TerminalSelection;
+
+    #[test]
+    fn selects_across_rows_by_display_column() {
+        let lines = vec![
+            "$ cargo test".to_string(),
+            "日本語 ok   ".to_string(),
+            "done".to_string(),
+        ];
+        let mut selection = TerminalSelection::new(TerminalCell::new(1, 2));
+        assert!(selection.is_empty());
+        assert_eq!(selection.text(&lines), "");
+
+        selection.extend_to(TerminalCell::new(1, 6));
+        assert_eq!(selection.text(&lines), "本語");
+
+        selection.extend_to(TerminalCell::new(0, 2));
+        assert_eq!(selection.text(&lines), "cargo test\n日");
+        assert_eq!(selection.columns_in_row(0, 12), Some(2..12));
+        assert_eq!(selection.columns_in_row(2, 4), None);
+
+        let mut selection = TerminalSelection::new(TerminalCell::new(1, 7));
+        selection.extend_to(TerminalCell::new(2, 40));
+        assert_eq!(selection.text(&lines), "ok\ndone");
+    }
+}
@@ -0,0 +1,103 @@
+use unicode_width::UnicodeWidthChar;
+use unicode_width::UnicodeWidthStr;
+
+/// Ends a terminal row that continues on the next one.
+pub const SOFT_WRAP_INDICATOR: char = '↩';
+
+/// How a terminal session shows lines wider than the panel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TerminalWrapMode {
+    /// Long lines continue on the next row, marked with [`SOFT_WRAP_INDICATOR`].
+    #[default]
+    Wrap,
+    /// Lines keep their width and the panel scrolls horizontally.
+    NoWrap,
+}
+
+impl TerminalWrapMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Wrap => "Quebrar linhas",
+            Self::NoWrap => "Rolagem horizontal",
+        }
+    }
+}
+
+/// Terminal columns taken by `text`: CJK and emoji take two, combining marks none.
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// The longest prefix of `text` fitting in `max_columns`, keeping combining marks with
+/// their base character, and whether anything was cut.
+pub(crate) fn truncate_to_width(text: &str, max_columns: usize) -> (&str, bool) {
+    let mut columns = 0;
+    for (index, ch) in text.char_indices() {
+        columns += ch.width().unwrap_or(0);
+        if columns > max_columns {
+            return (&text[..index], true);
+        }
+    }
+    (text, false)
+}
+
+/// `lines` cut into rows of at most `columns` terminal columns. Every row but the last of a
+/// line ends with [`SOFT_WRAP_INDICATOR`], which takes one of those columns. A row always
+/// holds at least one character, so a double-width one still fits a one-column panel.
+pub fn soft_wrap_lines(lines: &[String], columns: usize) -> Vec<String> {
+    let columns = columns.max(1);
+    let row_columns = columns.saturating_sub(1).max(1);
+    let mut rows = Vec::with_capacity(lines.len());
+    for line in lines {
+        if display_width(line) <= columns {
+            rows.push(line.clone());
+            continue;
+        }
+        let mut row = String::new();
+        let mut row_width = 0;
+        for ch in line.chars() {
+            let width = ch.width().unwrap_or(0);
+            if row_width + width > row_columns && !row.is_empty() {
+                row.push(SOFT_WRAP_INDICATOR);
+                rows.push(std::mem::take(&mut row));
+                row_width = 0;
+            }
+            row.push(ch);
+            row_width += width;
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::display_width;
+    use super::soft_wrap_lines;
+    use super::truncate_to_width;
+
+    #[test]
+    fn wraps_only_lines_wider_than_the_panel() {
+        let lines = vec!["abcd".to_string(), "abcdefgh".to_string(), String::new()];
+        assert_eq!(
+            soft_wrap_lines(&lines, 4),
+            vec!["abcd", "abc↩", "def↩", "gh", ""]
+        );
+    }
+
+    #[test]
+    fn measures_wide_and_combining_characters_in_columns() {
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width("🦀"), 2);
+
+        assert_eq!(
+            soft_wrap_lines(&["日本語です".to_string()], 5),
+            vec!["日本↩", "語で↩", "す"]
+        );
+        assert_eq!(truncate_to_width("日本語", 5), ("日本", true));
+        assert_eq!(truncate_to_width("cafe\u{301}", 4), ("cafe\u{301}", false));
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+/// Turns kept for the per-turn breakdown; totals keep counting past it.
+pub const MAX_TRACKED_TURNS: usize = 512;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn new(prompt_tokens: u64, completion_tokens: u64) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+        }
+    }
+
+    pub fn total(self) -> u64 {
+        self.prompt_tokens.saturating_add(self.completion_tokens)
+    }
+
+    fn add(&mut self, usage: Self) {
+        self.prompt_tokens = self.prompt_tokens.saturating_add(usage.prompt_tokens);
+        self.completion_tokens = self
+            .completion_tokens
+            .saturating_add(usage.completion_tokens);
+    }
+}
+
+/// Price of a model in US dollars per million tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModelPricing {
+    pub prompt_usd_per_million: f64,
+    pub completion_usd_per_million: f64,
+}
+
+impl ModelPricing {
+    pub fn new(prompt_usd_per_million: f64, completion_usd_per_million: f64) -> Self {
+        Self {
+            prompt_usd_per_million,
+            completion_usd_per_million,
+        }
+    }
+
+    pub fn cost_usd(self, usage: TokenUsage) -> f64 {
+        (usage.prompt_tokens as f64 * self.prompt_usd_per_million
+            + usage.completion_tokens as f64 * self.completion_usd_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Usage of one model call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnTokenUsage {
+    pub model: String,
+    pub usage: TokenUsage,
+    pub recorded_at_unix_s: i64,
+}
+
+/// Token usage of this UI session, per turn, per model and per UTC day.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenUsageLedger {
+    turns: VecDeque<TurnTokenUsage>,
+    session_total: TokenUsage,
+    per_model: BTreeMap<String, TokenUsage>,
+    per_day: BTreeMap<i64, TokenUsage>,
+}
+
+impl TokenUsageLedger {
+    pub fn record(&mut self, model: &str, usage: TokenUsage, now_unix_s: i64) {
+        self.session_total.add(usage);
+        self.per_model
+            .entry(model.to_string())
+            .or_default()
+            .add(usage);
+        self.per_day
+            .entry(now_unix_s.div_euclid(SECONDS_PER_DAY))
+            .or_default()
+            .add(usage);
+        if self.turns.len() == MAX_TRACKED_TURNS {
+            self.turns.pop_front();
+        }
+        self.turns.push_back(TurnTokenUsage {
+            model: model.to_string(),
+            usage,
+            recorded_at_unix_s: now_unix_s,
+        });
+    }
+
+    /// Most recent turns, oldest first.
+    pub fn turns(&self) -> impl Iterator<Item = &TurnTokenUsage> {
+        self.turns.iter()
+    }
+
+    pub fn last_turn(&self) -> Option<&TurnTokenUsage> {
+        self.turns.back()
+    }
+
+    pub fn session_total(&self) -> TokenUsage {
+        self.session_total
+    }
+
+    pub fn model_total(&self, model: &str) -> TokenUsage {
+        self.per_model.get(model).copied().unwrap_or_default()
+    }
+
+    /// Usage of the UTC day containing `unix_s`.
+    pub fn day_total(&self, unix_s: i64) -> TokenUsage {
+        self.per_day
+            .get(&unix_s.div_euclid(SECONDS_PER_DAY))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+}
+
+/// Compact count for the UI: `950`, `1.2k`, `3.4M`.
+pub fn format_token_count(tokens: u64) -> String {
+    match tokens {
+        0..=999 => tokens.to_string(),
+        1_000..=999_999 => format!("{:.1}k", tokens as f64 / 1_000.0),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// Cost for the UI; cents are too coarse for a few hundred tokens, so small amounts keep
+/// four decimals.
+pub fn format_cost_usd(cost_usd: f64) -> String {
+    if cost_usd > 0.0 && cost_usd < 0.01 {
+        format!("US$ {cost_usd:.4}")
+    } else {
+        format!("US$ {cost_usd:.2}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::MAX_TRACKED_TURNS;
+    use super::ModelPricing;
+    use super::TokenUsage;
+    use super::TokenUsageLedger;
+    use super::format_cost_usd;
+    use super::format_token_count;
+
+    #[test]
+    fn ledger_aggregates_per_session_model_and_day() {
+        let day = 1_735_689_600;
+        let mut ledger = TokenUsageLedger::default();
+        ledger.record("gpt", TokenUsage::new(800, 400), day + 10);
+        ledger.record("claude", TokenUsage::new(100, 50), day + 20);
+        ledger.record("gpt", TokenUsage::new(10, 5), day + 86_400);
+
+        assert_eq!(ledger.session_total(), TokenUsage::new(910, 455));
+        assert_eq!(ledger.model_total("gpt"), TokenUsage::new(810, 405));
+        assert_eq!(ledger.day_total(day + 3_600), TokenUsage::new(900, 450));
+        assert_eq!(ledger.day_total(day + 90_000).total(), 15);
+        assert_eq!(
+            ledger.last_turn().map(|turn| turn.model.as_str()),
+            Some("gpt")
+        );
+
+        for _ in 0..MAX_TRACKED_TURNS {
+            ledger.record("gpt", TokenUsage::new(1, 0), day);
+        }
+        assert_eq!(ledger.turns().count(), MAX_TRACKED_TURNS);
+        assert_eq!(
+            ledger.session_total().prompt_tokens,
+            910 + MAX_TRACKED_TURNS as u64
+        );
+    }
+
+    #[test]
+    fn formats_compact_counts() {
+        assert_eq!(format_token_count(950), "950");
+        assert_eq!(format_token_count(1_200), "1.2k");
+        assert_eq!(format_token_count(3_400_000), "3.4M");
+    }
+
+    #[test]
+    fn prices_prompt_and_completion_tokens_separately() {
+        let pricing = ModelPricing::new(3.0, 15.0);
+        let cost = pricing.cost_usd(TokenUsage::new(200, 100));
+        assert_eq!(format_cost_usd(cost), "US$ 0.0021");
+        assert_eq!(format_cost_usd(12.5), "US$ 12.50");
+        assert_eq!(format_cost_usd(0.0), "US$ 0.00");
+    }
+}
@@ -0,0 +1,398 @@
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::PatchHunkDecision;
+use crate::PatchHunkPreview;
+
+/// Why a unified diff was rejected. Lines are 1-based positions in the diff text.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DiffParseError {
+    #[error("diff has no hunks")]
+    NoHunks,
+    #[error("line {line}: malformed hunk header `{header}`")]
+    MalformedHeader { line: usize, header: String },
+    #[error(
+        "line {line}: hunk `{header}` declares {expected_old} old/{expected_new} new lines but has {actual_old}/{actual_new}"
+    )]
+    InconsistentCounts {
+        line: usize,
+        header: String,
+        expected_old: usize,
+        expected_new: usize,
+        actual_old: usize,
+        actual_new: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// One line of a hunk with its number on each side it exists on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub text: String,
+    /// Followed by `\ No newline at end of file`.
+    pub no_newline_at_end: bool,
+}
+
+/// A row of the two-column view: old side on the left, new side on the right. Context lines
+/// fill both; a removed line shares its row with the added line that replaced it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SideBySideRow {
+    pub left: Option<DiffLine>,
+    pub right: Option<DiffLine>,
+}
+
+impl PatchHunkPreview {
+    /// The hunk's lines, typed and numbered from its header.
+    pub fn diff_lines(&self) -> Vec<DiffLine> {
+        let mut old_line = self.old_start;
+        let mut new_line = self.new_start;
+        let mut lines: Vec<DiffLine> = Vec::with_capacity(self.lines.len());
+        for line in &self.lines {
+            let (kind, text) = match line.chars().next() {
+                Some('\\') => {
+                    if let Some(previous) = lines.last_mut() {
+                        previous.no_newline_at_end = true;
+                    }
+                    continue;
+                }
+                Some('+') => (DiffLineKind::Added, &line[1..]),
+                Some('-') => (DiffLineKind::Removed, &line[1..]),
+                Some(' ') => (DiffLineKind::Context, &line[1..]),
+                _ => (DiffLineKind::Context, line.as_str()),
+            };
+            let (old, new) = match kind {
+                DiffLineKind::Context => (Some(old_line), Some(new_line)),
+                DiffLineKind::Added => (None, Some(new_line)),
+                DiffLineKind::Removed => (Some(old_line), None),
+            };
+            old_line += usize::from(old.is_some());
+            new_line += usize::from(new.is_some());
+            lines.push(DiffLine {
+                kind,
+                old_line: old,
+                new_line: new,
+                text: text.to_string(),
+                no_newline_at_end: false,
+            });
+        }
+        lines
+    }
+
+    /// `diff_lines` paired into rows for a two-column diff.
+    pub fn side_by_side(&self) -> Vec<SideBySideRow> {
+        let mut rows = Vec::new();
+        let mut removed: Vec<DiffLine> = Vec::new();
+        let mut added: Vec<DiffLine> = Vec::new();
+        let flush = |rows: &mut Vec<SideBySideRow>,
+                     removed: &mut Vec<DiffLine>,
+                     added: &mut Vec<DiffLine>| {
+            let count = removed.len().max(added.len());
+            let mut removed = removed.drain(..);
+            let mut added = added.drain(..);
+            for _ in 0..count {
+                rows.push(SideBySideRow {
+                    left: removed.next(),
+                    right: added.next(),
+                });
+            }
+        };
+        for line in self.diff_lines() {
+            match line.kind {
+                DiffLineKind::Removed => {
+                    if !added.is_empty() {
+                        flush(&mut rows, &mut removed, &mut added);
+                    }
+                    removed.push(line);
+                }
+                DiffLineKind::Added => added.push(line),
+                DiffLineKind::Context => {
+                    flush(&mut rows, &mut removed, &mut added);
+                    rows.push(SideBySideRow {
+                        left: Some(line.clone()),
+                        right: Some(line),
+                    });
+                }
+            }
+        }
+        flush(&mut rows, &mut removed, &mut added);
+        rows
+    }
+}
+
+/// Hunk being read, with the old/new lines its header still owes.
+struct OpenHunk {
+    line: usize,
+    hunk: PatchHunkPreview,
+    old_remaining: usize,
+    new_remaining: usize,
+}
+
+impl OpenHunk {
+    fn is_complete(&self) -> bool {
+        self.old_remaining == 0 && self.new_remaining == 0
+    }
+
+    fn inconsistent(&self, actual_old: usize, actual_new: usize) -> DiffParseError {
+        DiffParseError::InconsistentCounts {
+            line: self.line,
+            header: self.hunk.header.clone(),
+            expected_old: self.hunk.old_count,
+            expected_new: self.hunk.new_count,
+            actual_old,
+            actual_new,
+        }
+    }
+
+    fn short_by(&self) -> DiffParseError {
+        self.inconsistent(
+            self.hunk.old_count - self.old_remaining,
+            self.hunk.new_count - self.new_remaining,
+        )
+    }
+}
+
+/// Parses the hunks of a unified diff. File headers (`diff --git`, `---`, `+++`, `index`)
+/// between hunks are skipped; every hunk must hold exactly the lines its header declares,
+/// so a truncated or padded diff is an error instead of a misleading preview.
+pub fn parse_unified_diff_hunks(
+    unified_diff: &str,
+) -> Result<Vec<PatchHunkPreview>, DiffParseError> {
+    let mut hunks = Vec::new();
+    let mut open: Option<OpenHunk> = None;
+
+    for (index, line) in unified_diff.lines().enumerate() {
+        let line_number = index + 1;
+        if line.starts_with("@@") {
+            if let Some(previous) = open.take() {
+                if !previous.is_complete() {
+                    return Err(previous.short_by());
+                }
+                hunks.push(previous.hunk);
+            }
+            let (old_start, old_count, new_start, new_count) =
+                parse_hunk_header(line).ok_or_else(|| DiffParseError::MalformedHeader {
+                    line: line_number,
+                    header: line.to_string(),
+                })?;
+            open = Some(OpenHunk {
+                line: line_number,
+                hunk: PatchHunkPreview {
+                    hunk_id: format!("hunk-{}", hunks.len() + 1),
+                    header: line.to_string(),
+                    old_start,
+                    old_count,
+                    new_start,
+                    new_count,
+                    added_lines: 0,
+                    removed_lines: 0,
+                    lines: Vec::new(),
+                    decision: PatchHunkDecision::Pending,
+                },
+                old_remaining: old_count,
+                new_remaining: new_count,
+            });
+            continue;
+        }
+
+        let Some(current) = open.as_mut() else {
+            continue;
+        };
+        // "\ No newline at end of file" annotates the previous line.
+        if line.starts_with('\\') {
+            current.hunk.lines.push(line.to_string());
+            continue;
+        }
+        if current.is_complete() {
+            let is_file_header = line.starts_with("--- ") || line.starts_with("+++ ");
+            if !is_file_header && matches!(line.chars().next(), Some('+' | '-' | ' ')) {
+                let (extra_old, extra_new) = match line.chars().next() {
+                    Some('-') => (1, 0),
+                    Some('+') => (0, 1),
+                    _ => (1, 1),
+                };
+                return Err(current.inconsistent(
+                    current.hunk.old_count + extra_old,
+                    current.hunk.new_count + extra_new,
+                ));
+            }
+            continue;
+        }
+        match line.chars().next() {
+            Some('+') if current.new_remaining > 0 => {
+                current.new_remaining -= 1;
+                current.hunk.added_lines += 1;
+            }
+            Some('-') if current.old_remaining > 0 => {
+                current.old_remaining -= 1;
+                current.hunk.removed_lines += 1;
+            }
+            // Blank context lines lose their leading space in some editors and mail clients.
+            Some(' ') | None if current.old_remaining > 0 && current.new_remaining > 0 => {
+                current.old_remaining -= 1;
+                current.new_remaining -= 1;
+            }
+            _ => return Err(current.short_by()),
+        }
+        current.hunk.lines.push(line.to_string());
+    }
+
+    if let Some(last) = open.take() {
+        if !last.is_complete() {
+            return Err(last.short_by());
+        }
+        hunks.push(last.hunk);
+    }
+    if hunks.is_empty() {
+        return Err(DiffParseError::NoHunks);
+    }
+    Ok(hunks)
+}
+
+/// `@@ -old_start[,old_count] +new_start[,new_count] @@[ section]`.
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let mut parts = line.split_whitespace();
+    if parts.next() != Some("@@") {
+        return None;
+    }
+    let (old_start, old_count) = parse_hunk_range(parts.next()?, '-')?;
+    let (new_start, new_count) = parse_hunk_range(parts.next()?, '+')?;
+    if parts.next() != Some("@@") {
+        return None;
+    }
+    Some((old_start, old_count, new_start, new_count))
+}
+
+fn parse_hunk_range(raw: &str, prefix: char) -> Option<(usize, usize)> {
+    let raw = raw.strip_prefix(prefix)?;
+    let mut parts = raw.split(',');
+    let start = parts.next()?.parse::<usize>().ok()?;
+    let count = parts
+        .next()
+        .map_or(Some(1_usize), |value| value.parse::<usize>().ok())?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((start, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::strategies;
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+
+    use super::DiffLine;
+    use super::DiffLineKind;
+    use super::DiffParseError;
+    use super::parse_unified_diff_hunks;
+
+    #[test]
+    fn rejects_malformed_headers_and_inconsistent_counts() -> Result<(), DiffParseError> {
+        let hunks = parse_unified_diff_hunks(
+            "diff --git a/src/main.rs b/src/main.rs\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,2 +1,2 @@ fn main\n-old\n+new\n same\n\\ No newline at end of file\n",
+        )?;
+        assert_eq!(hunks.len(), 1);
+        assert_eq!((hunks[0].added_lines, hunks[0].removed_lines), (1, 1));
+
+        assert_eq!(
+            parse_unified_diff_hunks("@@ -1,x +1,1 @@\n-a\n+b\n"),
+            Err(DiffParseError::MalformedHeader {
+                line: 1,
+                header: "@@ -1,x +1,1 @@".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_unified_diff_hunks("@@ -1,2 +1,1 @@\n-a\n+b\n"),
+            Err(DiffParseError::InconsistentCounts {
+                line: 1,
+                header: "@@ -1,2 +1,1 @@".to_string(),
+                expected_old: 2,
+                expected_new: 1,
+                actual_old: 1,
+                actual_new: 1,
+            })
+        );
+        assert!(matches!(
+            parse_unified_diff_hunks("@@ -1 +1 @@\n-a\n+b\n+c\n"),
+            Err(DiffParseError::InconsistentCounts { actual_new: 2, .. })
+        ));
+        assert_eq!(
+            parse_unified_diff_hunks("sure, here is the fix!"),
+            Err(DiffParseError::NoHunks)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn hunk_lines_are_typed_numbered_and_paired() -> Result<(), DiffParseError> {
+        let hunks = parse_unified_diff_hunks(
+            "@@ -3,3 +3,4 @@\n keep\n-old\n+new\n+extra\n tail\n\\ No newline at end of file\n",
+        )?;
+        let line = |kind, old_line, new_line, text: &str| DiffLine {
+            kind,
+            old_line,
+            new_line,
+            text: text.to_string(),
+            no_newline_at_end: false,
+        };
+        let mut tail = line(DiffLineKind::Context, Some(5), Some(6), "tail");
+        tail.no_newline_at_end = true;
+        assert_eq!(
+            hunks[0].diff_lines(),
+            vec![
+                line(DiffLineKind::Context, Some(3), Some(3), "keep"),
+                line(DiffLineKind::Removed, Some(4), None, "old"),
+                line(DiffLineKind::Added, None, Some(4), "new"),
+                line(DiffLineKind::Added, None, Some(5), "extra"),
+                tail,
+            ]
+        );
+
+        let rows: Vec<(Option<String>, Option<String>)> = hunks[0]
+            .side_by_side()
+            .into_iter()
+            .map(|row| (row.left.map(|l| l.text), row.right.map(|r| r.text)))
+            .collect();
+        let some = |text: &str| Some(text.to_string());
+        assert_eq!(
+            rows,
+            vec![
+                (some("keep"), some("keep")),
+                (some("old"), some("new")),
+                (None, some("extra")),
+                (some("tail"), some("tail")),
+            ]
+        );
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_text_never_panics(diff in any::<String>()) {
+            let _ = parse_unified_diff_hunks(&diff);
+        }
+
+        #[test]
+        fn well_formed_diffs_parse_with_matching_counts(diff in strategies::unified_diff()) {
+            let hunks = parse_unified_diff_hunks(&diff)?;
+            prop_assert_eq!(hunks.len(), diff.matches("@@ -").count());
+            for hunk in hunks {
+                let context = hunk.lines.len() - hunk.added_lines - hunk.removed_lines;
+                prop_assert_eq!(hunk.old_count, context + hunk.removed_lines);
+                prop_assert_eq!(hunk.new_count, context + hunk.added_lines);
+            }
+        }
+    }
+}
@@ -0,0 +1,394 @@
+//! Headless harness for `AliciaEguiView`: renders frames against a store, clicks widgets by
+//! their visible label and records the `IpcMessage`s the view emits, so approval-flow
+//! regressions show up in plain `cargo test` runs.
+
+use std::path::Path;
+
+use codex_alicia_core::IpcMessage;
+use egui::epaint::ClippedShape;
+use egui::epaint::Shape;
+use thiserror::Error;
+
+use crate::AliciaEguiView;
+use crate::UiEventStore;
+
+/// Set to `1` to rewrite snapshots instead of comparing against them.
+pub const SNAPSHOT_UPDATE_ENV_VAR: &str = "ALICIA_UPDATE_SNAPSHOTS";
+
+/// Tall enough that the approval queue and diff preview fit without scrolling.
+const HARNESS_SCREEN_SIZE: egui::Vec2 = egui::vec2(1920.0, 2160.0);
+const HARNESS_FRAME_S: f64 = 1.0 / 60.0;
+/// Lets panels and groups settle their sizes before anything is clicked.
+const WARMUP_FRAMES: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum ViewHarnessError {
+    #[error("no text `{0}` is on screen")]
+    TextNotFound(String),
+    #[error("failed to serialize emitted messages: {0}")]
+    Serialize(#[from] serde_json::Error),
+    #[error("failed to access snapshot `{path}`: {source}")]
+    SnapshotIo {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(
+        "emitted messages differ from snapshot `{path}` (set {SNAPSHOT_UPDATE_ENV_VAR}=1 to accept)\n--- expected\n{expected}+++ actual\n{actual}"
+    )]
+    SnapshotMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// Drives an [`AliciaEguiView`] without a window. Each frame runs the real `render` on a
+/// fixed-size screen; widgets are found through the text they paint.
+pub struct ViewHarness {
+    ctx: egui::Context,
+    view: AliciaEguiView,
+    store: UiEventStore,
+    emitted_messages: Vec<IpcMessage>,
+    painted_texts: Vec<(String, egui::Rect)>,
+    time_s: f64,
+}
+
+impl ViewHarness {
+    pub fn new(store: UiEventStore) -> Self {
+        Self::with_view(AliciaEguiView::default(), store)
+    }
+
+    pub fn with_view(view: AliciaEguiView, store: UiEventStore) -> Self {
+        let mut harness = Self {
+            ctx: egui::Context::default(),
+            view,
+            store,
+            emitted_messages: Vec::new(),
+            painted_texts: Vec::new(),
+            time_s: 0.0,
+        };
+        for _ in 0..WARMUP_FRAMES {
+            harness.run();
+        }
+        harness
+    }
+
+    /// Renders one frame without input, e.g. after changing the store.
+    pub fn run(&mut self) {
+        self.run_frame(Vec::new());
+    }
+
+    pub fn store(&self) -> &UiEventStore {
+        &self.store
+    }
+
+    pub fn store_mut(&mut self) -> &mut UiEventStore {
+        &mut self.store
+    }
+
+    pub fn view(&self) -> &AliciaEguiView {
+        &self.view
+    }
+
+    pub fn view_mut(&mut self) -> &mut AliciaEguiView {
+        &mut self.view
+    }
+
+    /// Whether any text painted in the last frame contains `text`.
+    pub fn has_text(&self, text: &str) -> bool {
+        self.painted_texts
+            .iter()
+            .any(|(painted, _)| painted.contains(text))
+    }
+
+    /// Clicks the first widget painted with exactly `label`.
+    pub fn click(&mut self, label: &str) -> Result<(), ViewHarnessError> {
+        let position = self.find_label(0, label)?;
+        self.click_at(position);
+        Ok(())
+    }
+
+    /// Clicks the first widget labelled `label` painted after a text containing `anchor`, such
+    /// as the "Aprovar" button of the approval showing "Ação: act-2".
+    pub fn click_after(&mut self, anchor: &str, label: &str) -> Result<(), ViewHarnessError> {
+        let anchor_index = self
+            .painted_texts
+            .iter()
+            .position(|(painted, _)| painted.contains(anchor))
+            .ok_or_else(|| ViewHarnessError::TextNotFound(anchor.to_string()))?;
+        let position = self.find_label(anchor_index + 1, label)?;
+        self.click_at(position);
+        Ok(())
+    }
+
+    /// Presses and releases `key` with no modifiers, on separate frames.
+    pub fn press_key(&mut self, key: egui::Key) {
+        let event = |pressed| egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed,
+            repeat: false,
+            modifiers: egui::Modifiers::NONE,
+        };
+        self.run_frame(vec![event(true)]);
+        self.run_frame(vec![event(false)]);
+        self.run();
+    }
+
+    /// Every message the view emitted since the harness started, in order.
+    pub fn emitted_messages(&self) -> &[IpcMessage] {
+        &self.emitted_messages
+    }
+
+    pub fn take_emitted_messages(&mut self) -> Vec<IpcMessage> {
+        std::mem::take(&mut self.emitted_messages)
+    }
+
+    /// Emitted messages as JSON lines, the format stored in snapshot files.
+    pub fn messages_snapshot(&self) -> Result<String, ViewHarnessError> {
+        let mut snapshot = String::new();
+        for message in &self.emitted_messages {
+            snapshot.push_str(&serde_json::to_string(message)?);
+            snapshot.push('\n');
+        }
+        Ok(snapshot)
+    }
+
+    /// Compares the emitted messages with the snapshot at `path`. A missing snapshot is
+    /// written, as is any snapshot while [`SNAPSHOT_UPDATE_ENV_VAR`] is `1`.
+    pub fn assert_messages_snapshot(&self, path: &Path) -> Result<(), ViewHarnessError> {
+        let actual = self.messages_snapshot()?;
+        let snapshot_io = |source| ViewHarnessError::SnapshotIo {
+            path: path.display().to_string(),
+            source,
+        };
+        let update = std::env::var(SNAPSHOT_UPDATE_ENV_VAR).is_ok_and(|value| value == "1");
+        if update || !path.exists() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(snapshot_io)?;
+            }
+            return std::fs::write(path, actual).map_err(snapshot_io);
+        }
+        let expected = std::fs::read_to_string(path).map_err(snapshot_io)?;
+        if expected != actual {
+            return Err(ViewHarnessError::SnapshotMismatch {
+                path: path.display().to_string(),
+                expected,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    fn find_label(&self, from: usize, label: &str) -> Result<egui::Pos2, ViewHarnessError> {
+        self.painted_texts
+            .iter()
+            .skip(from)
+            .find(|(painted, _)| painted == label)
+            .map(|(_, rect)| rect.center())
+            .ok_or_else(|| ViewHarnessError::TextNotFound(label.to_string()))
+    }
+
+    /// Hover, press and release on separate frames, the way a real pointer arrives.
+    fn click_at(&mut self, position: egui::Pos2) {
+        let button = |pressed| egui::Event::PointerButton {
+            pos: position,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        };
+        self.run_frame(vec![egui::Event::PointerMoved(position)]);
+        self.run_frame(vec![button(true)]);
+        self.run_frame(vec![button(false)]);
+        self.run();
+    }
+
+    fn run_frame(&mut self, events: Vec<egui::Event>) {
+        self.time_s += HARNESS_FRAME_S;
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                HARNESS_SCREEN_SIZE,
+            )),
+            time: Some(self.time_s),
+            events,
+            ..Default::default()
+        };
+        let view = &mut self.view;
+        let store = &mut self.store;
+        let emitted_messages = &mut self.emitted_messages;
+        let output = self.ctx.run(input, |ctx| {
+            emitted_messages.extend(view.render(ctx, store));
+        });
+        self.painted_texts.clear();
+        for clipped in &output.shapes {
+            collect_painted_texts(clipped, &clipped.shape, &mut self.painted_texts);
+        }
+    }
+}
+
+/// Texts that are at least partly visible, with the visible part of their bounds.
+fn collect_painted_texts(
+    clipped: &ClippedShape,
+    shape: &Shape,
+    painted_texts: &mut Vec<(String, egui::Rect)>,
+) {
+    match shape {
+        Shape::Vec(shapes) => {
+            for shape in shapes {
+                collect_painted_texts(clipped, shape, painted_texts);
+            }
+        }
+        Shape::Text(text) => {
+            let visible = text.visual_bounding_rect().intersect(clipped.clip_rect);
+            if visible.is_positive() {
+                painted_texts.push((text.galley.text().to_string(), visible));
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codex_alicia_core::ApprovalResolution;
+    use codex_alicia_core::ApproverIdentity;
+    use codex_alicia_core::IpcEvent;
+    use codex_alicia_core::IpcMessage;
+    use codex_alicia_core::ipc::ApprovalRequested;
+    use codex_alicia_core::ipc::ApprovalResolved;
+    use codex_alicia_core::ipc::PatchPreviewReady;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::ViewHarness;
+    use super::ViewHarnessError;
+    use crate::ApprovalStatus;
+    use crate::PatchHunkDecision;
+    use crate::UiEventStore;
+
+    fn approval_requested(action_id: &str) -> IpcMessage {
+        IpcMessage::new(IpcEvent::ApprovalRequested(ApprovalRequested {
+            action_id: action_id.to_string(),
+            summary: format!("Executar {action_id}"),
+            expires_at_unix_s: i64::MAX,
+        }))
+    }
+
+    #[test]
+    fn clicking_approval_buttons_resolves_and_emits_messages()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let mut store = UiEventStore::default();
+        store.set_approver_identity(Some(ApproverIdentity::configured("reviewer")));
+        store.push(approval_requested("act-1"));
+        store.push(approval_requested("act-2"));
+        let mut harness = ViewHarness::new(store);
+        assert!(harness.has_text("Ação: act-2"));
+
+        harness.click_after("Ação: act-2", "Rejeitar")?;
+        harness.click("Aprovar")?;
+
+        let resolved = |action_id: &str, resolution| {
+            IpcMessage::new(IpcEvent::ApprovalResolved(ApprovalResolved {
+                action_id: action_id.to_string(),
+                resolution,
+                approver: Some(ApproverIdentity::configured("reviewer")),
+                reason: None,
+            }))
+        };
+        assert_eq!(
+            harness.emitted_messages(),
+            [
+                resolved("act-2", ApprovalResolution::Denied),
+                resolved("act-1", ApprovalResolution::Approved),
+            ]
+        );
+        let status = |action_id| {
+            harness
+                .store()
+                .approval(action_id)
+                .map(|approval| approval.status)
+        };
+        assert_eq!(status("act-1"), Some(ApprovalStatus::Approved));
+        assert_eq!(status("act-2"), Some(ApprovalStatus::Denied));
+        assert!(harness.has_text("Sem aprovações pendentes."));
+
+        let temp = TempDir::new()?;
+        let snapshot = temp.path().join("snapshots").join("approval_flow.jsonl");
+        harness.assert_messages_snapshot(&snapshot)?;
+        harness.assert_messages_snapshot(&snapshot)?;
+        harness.take_emitted_messages();
+        assert!(matches!(
+            harness.assert_messages_snapshot(&snapshot),
+            Err(ViewHarnessError::SnapshotMismatch { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn approval_hotkeys_resolve_the_focused_approval() {
+        let mut store = UiEventStore::default();
+        store.push(approval_requested("act-1"));
+        store.push(approval_requested("act-2"));
+        let mut harness = ViewHarness::new(store);
+
+        harness.press_key(egui::Key::ArrowDown);
+        harness.press_key(egui::Key::D);
+        harness.press_key(egui::Key::Tab);
+        harness.press_key(egui::Key::A);
+        assert_eq!(harness.emitted_messages().len(), 1);
+        for _ in 0..4 {
+            harness.press_key(egui::Key::Tab);
+        }
+        harness.press_key(egui::Key::A);
+
+        let resolutions: Vec<(String, ApprovalResolution)> = harness
+            .emitted_messages()
+            .iter()
+            .filter_map(|message| match &message.event {
+                IpcEvent::ApprovalResolved(resolved) => {
+                    Some((resolved.action_id.clone(), resolved.resolution))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            resolutions,
+            vec![
+                ("act-2".to_string(), ApprovalResolution::Denied),
+                ("act-1".to_string(), ApprovalResolution::Approved),
+            ]
+        );
+        assert!(harness.has_text("Sem aprovações pendentes."));
+    }
+
+    #[test]
+    fn clicking_hunk_buttons_records_decisions() -> Result<(), Box<dyn std::error::Error>> {
+        let mut store = UiEventStore::default();
+        store.push(IpcMessage::new(IpcEvent::PatchPreviewReady(
+            PatchPreviewReady {
+                action_id: "act-patch".to_string(),
+                files: vec!["src/main.rs".to_string()],
+            },
+        )));
+        store.attach_patch_file_diff("act-patch", "src/main.rs", "@@ -1,1 +1,1 @@\n-a\n+b\n")?;
+        let mut harness = ViewHarness::new(store);
+
+        harness.click("Rejeitar bloco")?;
+
+        let decision = harness
+            .store()
+            .diff_preview("act-patch")
+            .and_then(|preview| preview.file_previews.first())
+            .and_then(|file| file.hunks.first())
+            .map(|hunk| hunk.decision);
+        assert_eq!(decision, Some(PatchHunkDecision::Rejected));
+        assert!(harness.emitted_messages().is_empty());
+        assert!(matches!(
+            harness.click("Botão inexistente"),
+            Err(ViewHarnessError::TextNotFound(_))
+        ));
+        Ok(())
+    }
+}
@@ -15,6 +15,8 @@ use codex_alicia_core::ResultStatus;
 use codex_alicia_core::SessionManager;
 use codex_alicia_core::SessionMode;
 use codex_alicia_core::SessionStartRequest;
+use codex_alicia_core::ShellCommand;
+use codex_alicia_core::ShellCommandError;
 use codex_alicia_core::ipc::ActionProposed;
 use codex_alicia_core::ipc::ApprovalRequested;
 use codex_alicia_ui::AliciaUiRuntime;
@@ -26,30 +28,19 @@ fn inherited_env() -> HashMap<String, String> {
     std::env::vars().collect()
 }
 
-fn shell_echo_command(marker: &str) -> (String, Vec<String>) {
-    if cfg!(windows) {
-        let cmd = std::env::var("COMSPEC").unwrap_or_else(|_| String::from("cmd.exe"));
-        let script = format!("echo {marker}");
-        (cmd, vec![String::from("/C"), script])
-    } else {
-        (
-            String::from("/bin/sh"),
-            vec![String::from("-c"), format!("echo {marker}")],
-        )
-    }
+fn shell_echo_command(marker: &str) -> Result<(String, Vec<String>), ShellCommandError> {
+    ShellCommand::new("echo").arg(marker).build()
 }
 
-fn shell_long_running_command_with_start_marker(marker: &str) -> (String, Vec<String>) {
-    if cfg!(windows) {
-        let cmd = std::env::var("COMSPEC").unwrap_or_else(|_| String::from("cmd.exe"));
-        let script = format!("echo {marker} & ping -n 20 127.0.0.1 > NUL");
-        (cmd, vec![String::from("/C"), script])
+fn shell_long_running_command_with_start_marker(
+    marker: &str,
+) -> Result<(String, Vec<String>), ShellCommandError> {
+    let script = if cfg!(windows) {
+        format!("echo {marker} & ping -n 20 127.0.0.1 > NUL")
     } else {
-        (
-            String::from("/bin/sh"),
-            vec![String::from("-c"), format!("echo {marker}; sleep 20")],
-        )
-    }
+        format!("echo {marker}; sleep 20")
+    };
+    ShellCommand::new(script).build()
 }
 
 fn parse_jsonl_lines(text: &str) -> Vec<Value> {
@@ -76,7 +67,7 @@ async fn e2e_happy_path_approval_execution_and_audit() -> Result<(), Box<dyn std
     );
 
     let marker = "alicia_e2e_happy_ok";
-    let (program, args) = shell_echo_command(marker);
+    let (program, args) = shell_echo_command(marker)?;
     let mut command = vec![program.clone()];
     command.extend(args.clone());
 
@@ -362,7 +353,7 @@ async fn e2e_safe_cancel_persists_final_audit_state() -> Result<(), Box<dyn std:
         .set_permission_profile(PermissionProfile::ReadWriteWithApproval);
 
     let marker = "alicia_cancel_start";
-    let (program, args) = shell_long_running_command_with_start_marker(marker);
+    let (program, args) = shell_long_running_command_with_start_marker(marker)?;
     let mut command = vec![program.clone()];
     command.extend(args.clone());
     runtime
@@ -420,6 +411,7 @@ async fn e2e_safe_cancel_persists_final_audit_state() -> Result<(), Box<dyn std:
         .audit_records()
         .iter()
         .find(|record| record.session_id == "sess-e2e-cancel")
+        .cloned()
         .ok_or_else(|| std::io::Error::other("expected in-memory audit record"))?;
     assert_eq!(record.result_status, ResultStatus::Failed);
 
@@ -5,6 +5,7 @@ use std::sync::Arc;
 use std::sync::Mutex as StdMutex;
 
 use portable_pty::MasterPty;
+use portable_pty::PtySize;
 use portable_pty::SlavePty;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
@@ -18,7 +19,7 @@ pub(crate) trait ChildTerminator: Send + Sync {
 
 pub struct PtyHandles {
     pub _slave: Option<Box<dyn SlavePty + Send>>,
-    pub _master: Box<dyn MasterPty + Send>,
+    pub master: Box<dyn MasterPty + Send>,
 }
 
 impl fmt::Debug for PtyHandles {
@@ -40,7 +41,7 @@ pub struct ProcessHandle {
     exit_code: Arc<StdMutex<Option<i32>>>,
     // PtyHandles must be preserved because the process will receive Control+C if the
     // slave is closed
-    _pty_handles: StdMutex<Option<PtyHandles>>,
+    pty_handles: StdMutex<Option<PtyHandles>>,
 }
 
 impl fmt::Debug for ProcessHandle {
@@ -75,7 +76,7 @@ impl ProcessHandle {
                 wait_handle: StdMutex::new(Some(wait_handle)),
                 exit_status,
                 exit_code,
-                _pty_handles: StdMutex::new(pty_handles),
+                pty_handles: StdMutex::new(pty_handles),
             },
             initial_output_rx,
         )
@@ -101,6 +102,24 @@ impl ProcessHandle {
         self.exit_code.lock().ok().and_then(|guard| *guard)
     }
 
+    /// Resizes the PTY (or the ConPTY on Windows) so the child sees the new window size.
+    /// Fails for processes spawned with pipes.
+    pub fn resize(&self, rows: u16, cols: u16) -> anyhow::Result<()> {
+        let guard = self
+            .pty_handles
+            .lock()
+            .map_err(|_| anyhow::anyhow!("PTY handles lock poisoned"))?;
+        let Some(handles) = guard.as_ref() else {
+            anyhow::bail!("process was not spawned with a PTY");
+        };
+        handles.master.resize(PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+    }
+
     /// Attempts to kill the child and abort helper tasks.
     pub fn terminate(&self) {
         if let Ok(mut killer_opt) = self.killer.lock() {
@@ -149,7 +149,7 @@ pub async fn spawn_process(
         } else {
             None
         },
-        _master: pair.master,
+        master: pair.master,
     };
 
     let (handle, output_rx) = ProcessHandle::new(
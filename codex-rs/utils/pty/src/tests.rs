@@ -282,6 +282,29 @@ async fn pipe_and_pty_share_interface() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pty_resize_reaches_the_child() -> anyhow::Result<()> {
+    let env_map: HashMap<String, String> = std::env::vars().collect();
+    let (program, args) = shell_command("sleep 0.2; stty size");
+    let pty = spawn_pty_process(&program, &args, Path::new("."), &env_map, &None).await?;
+    pty.session.resize(40, 120)?;
+
+    let (output, code) = collect_output_until_exit(pty.output_rx, pty.exit_rx, 3_000).await;
+    assert_eq!(code, 0);
+    assert!(
+        String::from_utf8_lossy(&output).contains("40 120"),
+        "resized output mismatch: {output:?}"
+    );
+
+    let (pipe_program, pipe_args) = shell_command("true");
+    let pipe =
+        spawn_pipe_process(&pipe_program, &pipe_args, Path::new("."), &env_map, &None).await?;
+    assert!(pipe.session.resize(40, 120).is_err());
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn pipe_drains_stderr_without_stdout_activity() -> anyhow::Result<()> {
     let Some(python) = find_python() else {
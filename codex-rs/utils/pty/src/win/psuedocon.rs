@@ -59,7 +59,6 @@ use winapi::um::winnt::OSVERSIONINFOW;
 pub type HPCON = HANDLE;
 
 pub const PSEUDOCONSOLE_RESIZE_QUIRK: DWORD = 0x2;
-#[allow(dead_code)]
 pub const PSEUDOCONSOLE_PASSTHROUGH_MODE: DWORD = 0x8;
 
 // https://learn.microsoft.com/en-gb/windows/console/createpseudoconsole
@@ -98,6 +97,13 @@ fn load_conpty() -> ConPtyFuncs {
 
 lazy_static! {
     static ref CONPTY: ConPtyFuncs = load_conpty();
+    // Only the sideloaded conpty.dll understands passthrough mode; it hands the child's VT
+    // sequences through untouched instead of re-rendering them.
+    static ref CONPTY_FLAGS: DWORD = if ConPtyFuncs::open(Path::new("conpty.dll")).is_ok() {
+        PSEUDOCONSOLE_RESIZE_QUIRK | PSEUDOCONSOLE_PASSTHROUGH_MODE
+    } else {
+        PSEUDOCONSOLE_RESIZE_QUIRK
+    };
 }
 
 pub fn conpty_supported() -> bool {
@@ -137,7 +143,7 @@ impl PsuedoCon {
                 size,
                 input.as_raw_handle() as _,
                 output.as_raw_handle() as _,
-                PSEUDOCONSOLE_RESIZE_QUIRK,
+                *CONPTY_FLAGS,
                 &mut con,
             )
         };